@@ -0,0 +1,173 @@
+//! Loading program binaries for deploy and upgrade
+//!
+//! `--program` accepts a local path, `-` for stdin, or an `https://` URL.
+//! Remote and piped artifacts are kept in memory so no extra plaintext copy
+//! lands on disk.
+
+use anyhow::{Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::utils::{detect_program_file, sha256_hex};
+
+/// Largest artifact accepted; the loader caps ProgramData at 10 MiB
+pub const MAX_PROGRAM_BYTES: usize = 10 * 1024 * 1024;
+
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// ELF machine types of Solana programs: legacy BPF and SBF
+const EM_BPF: u16 = 247;
+const EM_SBF: u16 = 263;
+
+/// A validated program binary and where it came from
+pub struct ProgramArtifact {
+    pub data: Vec<u8>,
+    /// Path, `stdin`, or the URL it was downloaded from
+    pub source: String,
+    pub sha256: String,
+    /// Artifact name (file stem) when the source has one
+    pub name: Option<String>,
+}
+
+impl ProgramArtifact {
+    pub fn print_summary(&self) {
+        println!("\nProgram artifact:");
+        println!("• Source: {}", self.source);
+        println!("• Size:   {} bytes", self.data.len());
+        println!("• SHA256: {}\n", self.sha256);
+    }
+}
+
+/// Read `--program` (or the detected build artifact) and validate it
+///
+/// A URL source requires `expected_sha256`; for other sources it is optional
+/// and checked when given.
+pub async fn load_program(spec: Option<&str>, expected_sha256: Option<&str>) -> Result<ProgramArtifact> {
+    let (data, source, name) = match spec {
+        Some("-") => (read_stdin()?, "stdin".to_string(), None),
+        Some(url) if url.starts_with("https://") || url.starts_with("http://") => {
+            if expected_sha256.is_none() {
+                anyhow::bail!(
+                    "Remote programs need a checksum.\n\
+                    Pass --sha256 <hash> with the expected SHA-256 of the .so."
+                );
+            }
+            let name = url
+                .split(['?', '#'])
+                .next()
+                .and_then(|path| path.rsplit('/').next())
+                .and_then(|file| Path::new(file).file_stem())
+                .map(|stem| stem.to_string_lossy().to_string())
+                .filter(|stem| !stem.is_empty());
+            (download(url).await?, url.to_string(), name)
+        }
+        Some(path) => read_file(&PathBuf::from(path))?,
+        None => {
+            let path = detect_program_file()?;
+            read_file(&path)?
+        }
+    };
+
+    validate_elf(&data).with_context(|| format!("{source} is not a Solana program"))?;
+
+    let sha256 = sha256_hex(&data);
+    if let Some(expected) = expected_sha256 {
+        if !sha256.eq_ignore_ascii_case(expected.trim()) {
+            anyhow::bail!(
+                "Checksum mismatch for {source}.\n\
+                Expected: {expected}\n\
+                Actual:   {sha256}"
+            );
+        }
+    }
+
+    Ok(ProgramArtifact { data, source, sha256, name })
+}
+
+fn read_file(path: &Path) -> Result<(Vec<u8>, String, Option<String>)> {
+    if !path.exists() {
+        anyhow::bail!("Program file not found: {}", path.display());
+    }
+    let size = std::fs::metadata(path)?.len() as usize;
+    if size > MAX_PROGRAM_BYTES {
+        anyhow::bail!("{} is {size} bytes, above the {MAX_PROGRAM_BYTES} byte limit", path.display());
+    }
+    let data = std::fs::read(path).context("Failed to read program file")?;
+    let name = path.file_stem().map(|s| s.to_string_lossy().to_string());
+    Ok((data, path.display().to_string(), name))
+}
+
+fn read_stdin() -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    std::io::stdin()
+        .take(MAX_PROGRAM_BYTES as u64 + 1)
+        .read_to_end(&mut data)
+        .context("Failed to read program from stdin")?;
+    if data.len() > MAX_PROGRAM_BYTES {
+        anyhow::bail!("Program on stdin exceeds the {MAX_PROGRAM_BYTES} byte limit");
+    }
+    if data.is_empty() {
+        anyhow::bail!("No program data on stdin");
+    }
+    Ok(data)
+}
+
+async fn download(url: &str) -> Result<Vec<u8>> {
+    println!("Downloading {url}...");
+
+    let mut response = reqwest::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .user_agent(concat!("shield-deploy/", env!("CARGO_PKG_VERSION")))
+        .build()?
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Failed to download {url}"))?
+        .error_for_status()
+        .with_context(|| format!("Failed to download {url}"))?;
+
+    if response.content_length().is_some_and(|len| len as usize > MAX_PROGRAM_BYTES) {
+        anyhow::bail!("{url} is larger than the {MAX_PROGRAM_BYTES} byte limit");
+    }
+
+    let progress = match response.content_length() {
+        Some(len) => ProgressBar::new(len).with_style(
+            ProgressStyle::with_template("  {bar:30} {bytes}/{total_bytes} ({bytes_per_sec})")
+                .unwrap_or_else(|_| ProgressStyle::default_bar()),
+        ),
+        None => ProgressBar::new_spinner().with_style(
+            ProgressStyle::with_template("  {spinner} {bytes} ({bytes_per_sec})")
+                .unwrap_or_else(|_| ProgressStyle::default_spinner()),
+        ),
+    };
+
+    // Content-Length can be absent or wrong, so count as we go
+    let mut data = Vec::new();
+    while let Some(chunk) = response.chunk().await.context("Download interrupted")? {
+        data.extend_from_slice(&chunk);
+        progress.set_position(data.len() as u64);
+        if data.len() > MAX_PROGRAM_BYTES {
+            progress.abandon();
+            anyhow::bail!("{url} is larger than the {MAX_PROGRAM_BYTES} byte limit");
+        }
+    }
+    progress.finish_and_clear();
+    println!("  ✓ Downloaded {} bytes", data.len());
+    Ok(data)
+}
+
+/// Check for a 64-bit little-endian ELF built for BPF/SBF
+pub fn validate_elf(data: &[u8]) -> Result<()> {
+    if data.len() < 20 || &data[..4] != b"\x7fELF" {
+        anyhow::bail!("missing ELF header");
+    }
+    if data[4] != 2 || data[5] != 1 {
+        anyhow::bail!("not a 64-bit little-endian ELF");
+    }
+    let machine = u16::from_le_bytes([data[18], data[19]]);
+    if machine != EM_BPF && machine != EM_SBF {
+        anyhow::bail!("ELF targets machine {machine}, not BPF/SBF");
+    }
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use anyhow::{Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use crate::config::DeployerKeypair;
+
+const MAGIC: &[u8; 8] = b"SHIELDBK";
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const LEN_LEN: usize = 4;
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN + NONCE_LEN + LEN_LEN;
+
+/// Portable copy of a project's deployer, state, and config
+#[derive(Serialize, Deserialize)]
+pub struct DeployerBundle {
+    pub created_at: i64,
+    pub deployer: DeployerKeypair,
+    pub state: Option<serde_json::Value>,
+    pub config: Option<String>,
+}
+
+/// Snapshot of a whole `.shield` directory, written by `backup`
+#[derive(Serialize, Deserialize)]
+pub struct ShieldArchive {
+    pub created_at: i64,
+    pub files: Vec<ArchivedFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchivedFile {
+    /// Path relative to `.shield`, always with `/` separators
+    pub path: String,
+    pub modified: i64,
+    /// Base64 file contents
+    pub contents: String,
+}
+
+impl ArchivedFile {
+    pub fn new(path: String, modified: i64, contents: &[u8]) -> Self {
+        Self { path, modified, contents: BASE64.encode(contents) }
+    }
+
+    pub fn contents(&self) -> Result<Vec<u8>> {
+        BASE64.decode(&self.contents)
+            .with_context(|| format!("Archive entry {} is corrupted", self.path))
+    }
+}
+
+/// Encrypt data with a key derived from the passphrase (Argon2id + ChaCha20-Poly1305)
+///
+/// Layout: magic | version | salt | nonce | ciphertext length (u32 LE) | ciphertext
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+
+    let cipher = cipher_for(passphrase, &salt)?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+    let mut out = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&(ciphertext.len() as u32).to_le_bytes());
+    out.extend_from_slice(&ciphertext);
+
+    Ok(out)
+}
+
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if data.len() < MAGIC.len() || &data[..MAGIC.len()] != MAGIC {
+        anyhow::bail!("Not a shield-deploy bundle (unrecognized file format)");
+    }
+
+    let version = data.get(MAGIC.len()).copied();
+    if version.is_some_and(|v| v != VERSION) {
+        anyhow::bail!("Unsupported bundle version {}. Upgrade shield-deploy to read it.", version.unwrap_or(0));
+    }
+
+    let salt_start = MAGIC.len() + 1;
+    let nonce_start = salt_start + SALT_LEN;
+    let len_start = nonce_start + NONCE_LEN;
+
+    let expected_len = data
+        .get(len_start..HEADER_LEN)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+
+    let ciphertext = match expected_len {
+        Some(len) if data.len() - HEADER_LEN >= len => &data[HEADER_LEN..HEADER_LEN + len],
+        _ => anyhow::bail!("Bundle is truncated - the file is incomplete or was cut off during copy"),
+    };
+
+    let salt = &data[salt_start..nonce_start];
+    let nonce = Nonce::from_slice(&data[nonce_start..len_start]);
+
+    let cipher = cipher_for(passphrase, salt)?;
+
+    cipher
+        .decrypt(nonce, ciphertext)
+        .map_err(|_| anyhow::anyhow!(
+            "Could not decrypt bundle - wrong passphrase.\n\
+            (If you are sure the passphrase is right, the file is corrupted.)"
+        ))
+}
+
+fn cipher_for(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("Key derivation failed: {e}"))?;
+
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+pub fn encode_bundle(bundle: &DeployerBundle, passphrase: &str) -> Result<Vec<u8>> {
+    let plaintext = serde_json::to_vec(bundle)?;
+    encrypt(&plaintext, passphrase)
+}
+
+pub fn decode_bundle(data: &[u8], passphrase: &str) -> Result<DeployerBundle> {
+    let plaintext = decrypt(data, passphrase)?;
+    serde_json::from_slice(&plaintext)
+        .context("Bundle decrypted but its contents are invalid")
+}
+
+pub fn encode_archive(archive: &ShieldArchive, passphrase: &str) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&serde_json::to_vec(archive)?)?;
+    encrypt(&encoder.finish()?, passphrase)
+}
+
+pub fn decode_archive(data: &[u8], passphrase: &str) -> Result<ShieldArchive> {
+    let compressed = decrypt(data, passphrase)?;
+
+    let mut plaintext = Vec::new();
+    ZlibDecoder::new(compressed.as_slice())
+        .read_to_end(&mut plaintext)
+        .context("Not a .shield backup (is this an export-deployer bundle?)")?;
+
+    serde_json::from_slice(&plaintext)
+        .context("Backup decrypted but its contents are invalid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSPHRASE: &str = "correct horse battery staple";
+
+    fn bundle() -> DeployerBundle {
+        DeployerBundle {
+            created_at: 1_700_000_000,
+            deployer: DeployerKeypair { keypair: (0..64).collect() },
+            state: Some(serde_json::json!({ "network": "devnet" })),
+            config: Some("network = \"devnet\"\n".to_string()),
+        }
+    }
+
+    fn error_of<T>(result: Result<T>) -> String {
+        match result {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => format!("{e:#}"),
+        }
+    }
+
+    #[test]
+    fn bundle_round_trips() {
+        let encoded = encode_bundle(&bundle(), PASSPHRASE).unwrap();
+        let decoded = decode_bundle(&encoded, PASSPHRASE).unwrap();
+
+        assert_eq!(decoded.created_at, 1_700_000_000);
+        assert_eq!(decoded.deployer.keypair, (0..64).collect::<Vec<u8>>());
+        assert_eq!(decoded.state, bundle().state);
+        assert_eq!(decoded.config, bundle().config);
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let encoded = encode_bundle(&bundle(), PASSPHRASE).unwrap();
+
+        assert!(error_of(decode_bundle(&encoded, "incorrect horse")).contains("wrong passphrase"));
+    }
+
+    #[test]
+    fn truncated_file_is_rejected() {
+        let encoded = encode_bundle(&bundle(), PASSPHRASE).unwrap();
+
+        for len in [encoded.len() - 1, HEADER_LEN, HEADER_LEN - 1, MAGIC.len() + 1] {
+            assert!(error_of(decode_bundle(&encoded[..len], PASSPHRASE)).contains("truncated"), "cut at {len}");
+        }
+    }
+
+    #[test]
+    fn wrong_magic_or_version_is_rejected() {
+        let encoded = encode_bundle(&bundle(), PASSPHRASE).unwrap();
+
+        let mut wrong_magic = encoded.clone();
+        wrong_magic[0] = b'X';
+        assert!(error_of(decode_bundle(&wrong_magic, PASSPHRASE)).contains("Not a shield-deploy bundle"));
+        assert!(error_of(decode_bundle(b"SHIELD", PASSPHRASE)).contains("Not a shield-deploy bundle"));
+
+        let mut wrong_version = encoded;
+        wrong_version[MAGIC.len()] = VERSION + 1;
+        assert!(error_of(decode_bundle(&wrong_version, PASSPHRASE)).contains("Unsupported bundle version 2"));
+    }
+
+    #[test]
+    fn archive_round_trips_and_is_told_apart_from_a_bundle() {
+        let archive = ShieldArchive {
+            created_at: 1_700_000_000,
+            files: vec![ArchivedFile::new("state.json".to_string(), 1_700_000_000, b"{}")],
+        };
+        let encoded = encode_archive(&archive, PASSPHRASE).unwrap();
+        let decoded = decode_archive(&encoded, PASSPHRASE).unwrap();
+        assert_eq!(decoded.files.len(), 1);
+        assert_eq!(decoded.files[0].path, "state.json");
+        assert_eq!(decoded.files[0].contents().unwrap(), b"{}");
+
+        let bundle = encode_bundle(&bundle(), PASSPHRASE).unwrap();
+        assert!(error_of(decode_archive(&bundle, PASSPHRASE)).contains("Not a .shield backup"));
+    }
+}
@@ -0,0 +1,305 @@
+use anyhow::{Context, Result};
+use clap::{Subcommand, ValueHint};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use crate::commands::verify::fetch_program_bytecode;
+use crate::commands::CliProgress;
+use crate::config::Config;
+use crate::project::ShieldProject;
+use crate::utils::*;
+
+pub(crate) const ANCHOR_TOML: &str = "Anchor.toml";
+const DEPLOY_DIR: &str = "target/deploy";
+
+#[derive(Subcommand)]
+pub enum AnchorAction {
+    /// Deploy or upgrade the workspace programs for the provider cluster
+    Deploy {
+        /// Programs to deploy, by their Anchor.toml name
+        programs: Vec<String>,
+        /// Deploy every program listed for the cluster
+        #[arg(long, conflicts_with = "programs")]
+        all: bool,
+        /// Run `anchor build` first
+        #[arg(long)]
+        build: bool,
+        /// Cluster name or RPC URL, overriding [provider] cluster
+        #[arg(long, value_hint = ValueHint::Url)]
+        url: Option<String>,
+    },
+}
+
+/// What Anchor.toml says about one program on the active cluster
+struct AnchorProgram {
+    name: String,
+    declared_id: Option<Pubkey>,
+    artifact: PathBuf,
+    keypair_path: PathBuf,
+}
+
+enum Plan {
+    Deploy { keypair: Option<Keypair> },
+    Upgrade { program_id: Pubkey },
+}
+
+pub async fn execute(action: AnchorAction) -> Result<()> {
+    match action {
+        AnchorAction::Deploy { programs, all, build, url } => deploy(programs, all, build, url).await,
+    }
+}
+
+async fn deploy(names: Vec<String>, all: bool, build: bool, url: Option<String>) -> Result<()> {
+    print_header("Anchor Deploy");
+
+    let anchor_toml = fs::read_to_string(ANCHOR_TOML)
+        .context("No Anchor.toml in the current directory")?;
+    let manifest: toml::Table = toml::from_str(&anchor_toml).context("Anchor.toml is not valid TOML")?;
+
+    let cluster = match &url {
+        Some(url) => url.clone(),
+        None => manifest
+            .get("provider")
+            .and_then(|p| p.get("cluster"))
+            .and_then(|c| c.as_str())
+            .unwrap_or("localnet")
+            .to_string(),
+    };
+    let (cluster_name, rpc_url) = resolve_cluster(&cluster);
+
+    let available = workspace_programs(&manifest, &cluster_name)?;
+    let selected: Vec<&AnchorProgram> = if all || (names.is_empty() && available.len() == 1) {
+        available.iter().collect()
+    } else if names.is_empty() {
+        anyhow::bail!(
+            "Anchor.toml lists {} programs for {cluster_name}: {}\n\
+            Name the ones to deploy or pass --all",
+            available.len(),
+            available.iter().map(|p| p.name.as_str()).collect::<Vec<_>>().join(", ")
+        );
+    } else {
+        names
+            .iter()
+            .map(|name| {
+                available.iter().find(|p| &p.name == name).ok_or_else(|| anyhow::anyhow!(
+                    "'{name}' is not listed under [programs.{cluster_name}] in Anchor.toml"
+                ))
+            })
+            .collect::<Result<_>>()?
+    };
+
+    if build {
+        println!("\n Running anchor build...");
+        let mut command = Command::new("anchor");
+        command.arg("build");
+        if !all && selected.len() == 1 {
+            command.args(["-p", &selected[0].name]);
+        }
+        let status = command.status().context("Failed to run `anchor build`. Is Anchor installed?")?;
+        if !status.success() {
+            anyhow::bail!("anchor build failed");
+        }
+    }
+
+    let config = Config::new()?;
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+    let deployer = config.load_deployer()?;
+    config.check_rotation_policy()?;
+    let state = config.load_state()?;
+
+    let rpc_client = RpcClient::new_with_commitment(rpc_url.clone(), commitment());
+
+    let mut plans = Vec::new();
+    println!("\nCluster: {cluster_name} ({rpc_url})\n");
+    for program in &selected {
+        if !program.artifact.exists() {
+            anyhow::bail!(
+                "No artifact for {} at {}.\n\
+                Run `anchor build` or pass --build",
+                program.name,
+                program.artifact.display()
+            );
+        }
+
+        let plan = plan_program(&rpc_client, &deployer, &state, program).await?;
+        match &plan {
+            Plan::Upgrade { program_id } => println!("• {:<20} upgrade {program_id}", program.name),
+            Plan::Deploy { keypair: Some(keypair) } => {
+                println!("• {:<20} deploy  {} (declared ID)", program.name, keypair.pubkey())
+            }
+            Plan::Deploy { keypair: None } => {
+                println!("• {:<20} deploy  new program ID, written back to Anchor.toml", program.name)
+            }
+        }
+        plans.push(plan);
+    }
+    println!();
+
+    if !prompt_confirmation("Proceed?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let project = ShieldProject::open(".")?.with_rpc_url(rpc_url);
+    let mut anchor_toml = anchor_toml;
+
+    for (program, plan) in selected.iter().zip(plans) {
+        println!("\n {}...", program.name);
+        let program_data = fs::read(&program.artifact)
+            .with_context(|| format!("Failed to read {}", program.artifact.display()))?;
+
+        let outcome = match plan {
+            Plan::Upgrade { program_id } => project.upgrade(&program_id, &program_data, &CliProgress).await?,
+            Plan::Deploy { keypair } => project.deploy(&program_data, keypair, &CliProgress).await?,
+        };
+        println!("  ✓ {} ({})", outcome.program_id, format_sol(outcome.cost_lamports));
+
+        if program.declared_id != Some(outcome.program_id) {
+            anchor_toml = set_program_id(&anchor_toml, &cluster_name, &program.name, &outcome.program_id);
+            fs::write(ANCHOR_TOML, &anchor_toml).context("Failed to update Anchor.toml")?;
+            println!("  ✓ Anchor.toml [programs.{cluster_name}] updated");
+            println!("  ⚠️  Update declare_id! in the program source to {}", outcome.program_id);
+        }
+    }
+
+    print_success(&format!("{} program(s) deployed to {cluster_name}", selected.len()));
+
+    Ok(())
+}
+
+/// Upgrade programs this deployer already controls, deploy everything else
+async fn plan_program(
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    state: &crate::config::ProjectState,
+    program: &AnchorProgram,
+) -> Result<Plan> {
+    let Some(declared_id) = program.declared_id else {
+        return Ok(Plan::Deploy { keypair: None });
+    };
+
+    // Isolated programs are controlled through their dedicated key
+    let isolated_authority = state
+        .program(&declared_id.to_string())
+        .filter(|p| p.isolated_authority)
+        .and_then(|p| p.authority.as_deref().and_then(|a| Pubkey::from_str(a).ok()));
+
+    match fetch_program_bytecode(rpc_client, &declared_id).await {
+        Ok(on_chain) if isolated_authority.is_some() && on_chain.upgrade_authority == isolated_authority => {
+            Ok(Plan::Upgrade { program_id: declared_id })
+        }
+        Ok(on_chain) if on_chain.upgrade_authority == Some(deployer.pubkey()) => {
+            if !state.deployed_programs.iter().any(|p| p.program_id == declared_id.to_string()) {
+                anyhow::bail!(
+                    "{} ({declared_id}) is controlled by the deployer but missing from state.\n\
+                    Run `shield-deploy recover --program-id {declared_id}` first.",
+                    program.name
+                );
+            }
+            Ok(Plan::Upgrade { program_id: declared_id })
+        }
+        Ok(_) => anyhow::bail!(
+            "{} ({declared_id}) is already deployed with a different upgrade authority",
+            program.name
+        ),
+        // Not on chain yet: deploy at the declared ID when Anchor's keypair matches it
+        Err(_) => {
+            let keypair = read_keypair_file(&program.keypair_path)
+                .ok()
+                .filter(|k| k.pubkey() == declared_id);
+            Ok(Plan::Deploy { keypair })
+        }
+    }
+}
+
+fn workspace_programs(manifest: &toml::Table, cluster: &str) -> Result<Vec<AnchorProgram>> {
+    let programs = manifest
+        .get("programs")
+        .and_then(|p| p.get(cluster))
+        .and_then(|p| p.as_table())
+        .ok_or_else(|| anyhow::anyhow!("Anchor.toml has no [programs.{cluster}] section"))?;
+
+    programs
+        .iter()
+        .map(|(name, id)| {
+            let declared_id = id
+                .as_str()
+                .map(Pubkey::from_str)
+                .transpose()
+                .with_context(|| format!("Invalid program ID for {name} in Anchor.toml"))?;
+            Ok(AnchorProgram {
+                name: name.clone(),
+                declared_id,
+                artifact: Path::new(DEPLOY_DIR).join(format!("{name}.so")),
+                keypair_path: Path::new(DEPLOY_DIR).join(format!("{name}-keypair.json")),
+            })
+        })
+        .collect()
+}
+
+/// Anchor cluster monikers to (name used in [programs.*], RPC URL)
+pub(crate) fn resolve_cluster(cluster: &str) -> (String, String) {
+    match cluster {
+        "localnet" | "localhost" => ("localnet".into(), "http://127.0.0.1:8899".into()),
+        "devnet" => ("devnet".into(), "https://api.devnet.solana.com".into()),
+        "testnet" => ("testnet".into(), "https://api.testnet.solana.com".into()),
+        "mainnet" | "mainnet-beta" => ("mainnet".into(), "https://api.mainnet-beta.solana.com".into()),
+        url => {
+            let name = if url.contains("devnet") {
+                "devnet"
+            } else if url.contains("testnet") {
+                "testnet"
+            } else if url.contains("mainnet") {
+                "mainnet"
+            } else {
+                "localnet"
+            };
+            (name.into(), url.into())
+        }
+    }
+}
+
+/// Rewrite one entry of [programs.<cluster>] in place, keeping comments and layout
+pub(crate) fn set_program_id(anchor_toml: &str, cluster: &str, name: &str, program_id: &Pubkey) -> String {
+    let header = format!("[programs.{cluster}]");
+    let entry = format!("{name} = \"{program_id}\"");
+
+    let mut lines: Vec<String> = anchor_toml.lines().map(String::from).collect();
+    let Some(start) = lines.iter().position(|l| l.trim() == header) else {
+        lines.push(String::new());
+        lines.push(header);
+        lines.push(entry);
+        return lines.join("\n") + "\n";
+    };
+
+    let end = lines[start + 1..]
+        .iter()
+        .position(|l| l.trim_start().starts_with('['))
+        .map(|i| start + 1 + i)
+        .unwrap_or(lines.len());
+
+    let existing = (start + 1..end).find(|&i| {
+        lines[i]
+            .split_once('=')
+            .map(|(key, _)| key.trim() == name)
+            .unwrap_or(false)
+    });
+
+    match existing {
+        Some(i) => lines[i] = entry,
+        None => lines.insert(start + 1, entry),
+    }
+
+    lines.join("\n") + "\n"
+}
@@ -0,0 +1,103 @@
+use anyhow::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    signer::Signer,
+};
+use std::time::Duration;
+use crate::config::{Config, ProjectConfig};
+use crate::privacy::{audit_deployer, Linkability, TransferClass, PRIVACY_DELAY_SECS};
+use crate::utils::*;
+
+pub async fn execute(json: bool) -> Result<()> {
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    let deployer = config.load_deployer()?;
+    let state = config.load_state()?;
+    let vault_keys: Vec<_> = config.load_vault_keys()?
+        .iter()
+        .map(|k| k.pubkey())
+        .collect();
+    let privacy_delay = ProjectConfig::load()?
+        .privacy_delay()
+        .unwrap_or(Duration::from_secs(PRIVACY_DELAY_SECS));
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    if !json {
+        print_header("Deployer Privacy Audit");
+        println!("\nScanning transaction history of {}...", deployer.pubkey());
+    }
+
+    let report = audit_deployer(
+        &rpc_client,
+        &deployer.pubkey(),
+        &state,
+        &vault_keys,
+        privacy_delay,
+    )
+    .await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!();
+        for entry in &report.entries {
+            let datetime = entry.block_time
+                .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+                .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            let icon = match entry.linkability {
+                Linkability::Private => "✓",
+                Linkability::Weak => "⚠️ ",
+                Linkability::Linked => "✗",
+            };
+            let class = match entry.class {
+                TransferClass::PrivacyPool => "privacy pool",
+                TransferClass::DirectTransfer => "direct transfer",
+                TransferClass::Airdrop => "airdrop",
+                TransferClass::ProgramFees => "program fees",
+                TransferClass::Other => "other",
+            };
+            let sign = if entry.lamports < 0 { "-" } else { "+" };
+
+            println!(
+                "{icon} {datetime}  {:<16} {sign}{}",
+                class,
+                format_sol(entry.lamports.unsigned_abs())
+            );
+            if let Some(counterparty) = &entry.counterparty {
+                println!("  ↳ Counterparty: {counterparty}");
+            }
+            if let Some(note) = &entry.note {
+                println!("  ↳ {note}");
+            }
+            if entry.linkability != Linkability::Private {
+                println!("  ↳ {}", explorer_tx_url(&entry.signature, &report.network));
+            }
+        }
+
+        println!("\nTransactions: {}", report.entries.len());
+        match report.verdict {
+            Linkability::Private => print_success("No links to other wallets found"),
+            Linkability::Weak => print_warning("Weak links found (timing or test-cluster funding)"),
+            Linkability::Linked => print_warning("Deployer is directly linked to another wallet"),
+        }
+    }
+
+    if report.verdict == Linkability::Linked {
+        anyhow::bail!("Audit found linked transfers");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use crate::bundle::{encode_archive, ArchivedFile, ShieldArchive};
+use crate::config::{write_secret_file, Config};
+use crate::utils::*;
+
+/// Entries under .shield that are never archived
+const EXCLUDED_ENTRIES: &[&str] = &["backups", ".lock"];
+
+/// `rotate` and `finalize` offer a backup when the newest one is older than this
+const RECENT_BACKUP: Duration = Duration::from_secs(24 * 60 * 60);
+
+pub async fn execute(out: Option<PathBuf>) -> Result<()> {
+    print_header("Backup .shield");
+
+    let config = Config::new()?;
+
+    if !config.shield_dir().exists() {
+        anyhow::bail!(
+            "No .shield directory found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    create_backup(&config, out)?;
+
+    Ok(())
+}
+
+/// Ask for a passphrase and write an encrypted archive of `.shield`
+pub fn create_backup(config: &Config, out: Option<PathBuf>) -> Result<PathBuf> {
+    let out = out.unwrap_or_else(|| {
+        let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+        config.backups_dir().join(format!("shield-{stamp}.bak"))
+    });
+
+    if out.exists() && !prompt_confirmation(&format!("{} exists. Overwrite?", out.display()))? {
+        anyhow::bail!("Backup cancelled");
+    }
+
+    let mut files = Vec::new();
+    collect_files(config.shield_dir(), config.shield_dir(), &mut files)?;
+
+    let archive = ShieldArchive {
+        created_at: chrono::Utc::now().timestamp(),
+        files,
+    };
+
+    println!("\nArchiving {} files from {}", archive.files.len(), config.shield_dir().display());
+    println!("The backup is encrypted with a passphrase.");
+    println!("Without it the backup cannot be restored.\n");
+
+    let passphrase = prompt_passphrase("Passphrase", true)?;
+    if passphrase.is_empty() {
+        anyhow::bail!("Passphrase cannot be empty");
+    }
+
+    let data = encode_archive(&archive, &passphrase)?;
+
+    if let Some(parent) = out.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    write_secret_file(&out, data).context("Failed to write backup")?;
+    config.record_backup()?;
+
+    print_success(&format!("Backup written to {}", out.display()));
+    println!("→ Restore with `shield-deploy restore {}`", out.display());
+
+    Ok(out)
+}
+
+/// Offer a backup before a risky operation unless a recent one exists
+pub fn offer_backup(config: &Config) -> Result<()> {
+    let recent = fs::read_dir(config.backups_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| e.metadata().ok()?.modified().ok())
+                .any(|modified| {
+                    SystemTime::now()
+                        .duration_since(modified)
+                        .map(|age| age < RECENT_BACKUP)
+                        .unwrap_or(true)
+                })
+        })
+        .unwrap_or(false);
+
+    if recent {
+        return Ok(());
+    }
+
+    print_warning("No backup of .shield from the last 24 hours");
+    // A backup needs a passphrase, which --yes can't supply
+    if assume_yes() {
+        println!("  ↳ Skipped under --yes; run `shield-deploy backup` to create one");
+        return Ok(());
+    }
+    if prompt_confirmation("Create one now?")? {
+        create_backup(config, None)?;
+        println!();
+    }
+
+    Ok(())
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<ArchivedFile>) -> Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        let relative = path.strip_prefix(root)?;
+        if dir == root
+            && relative.to_str().map(|name| EXCLUDED_ENTRIES.contains(&name)).unwrap_or(false)
+        {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+            continue;
+        }
+
+        let contents = fs::read(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let modified = fs::metadata(&path)?
+            .modified()
+            .ok()
+            .and_then(|m| m.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        let relative = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.push(ArchivedFile::new(relative, modified, &contents));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signer::Signer;
+use crate::config::Config;
+use crate::utils::*;
+
+/// Print the deployer balance with a single RPC call
+///
+/// Only `deployer.json` is read, so this works without `state.json`.
+pub async fn execute(lamports: bool, json: bool, min: Option<Amount>) -> Result<()> {
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    let deployer = config.load_deployer()?;
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    let balance = rpc_client.get_balance(&deployer.pubkey()).await
+        .context("Failed to get deployer balance")?;
+
+    if json {
+        let output = serde_json::json!({
+            "deployer": deployer.pubkey().to_string(),
+            "lamports": balance,
+            "sol": balance as f64 / LAMPORTS_PER_SOL as f64,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else if lamports {
+        println!("{balance}");
+    } else {
+        println!("{} {}", deployer.pubkey(), format_sol(balance));
+    }
+
+    if let Some(min) = min {
+        let min_lamports = min.resolve(0);
+        if balance < min_lamports {
+            anyhow::bail!(
+                "Deployer balance of {balance} lamports is below the required minimum of {min_lamports} lamports"
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use std::str::FromStr;
+use crate::commands::verify::fetch_buffer;
+use crate::config::{BufferStatus, Config, OperationKind, OperationRecord};
+use crate::hygiene::{close_buffer, scan_buffers};
+use crate::utils::*;
+
+pub async fn execute(close: Vec<String>, all: bool) -> Result<()> {
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    let deployer = config.load_deployer()?;
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    if !close.is_empty() {
+        return close_buffers(&config, &rpc_client, &deployer, &close).await;
+    }
+
+    print_header("Buffers");
+
+    let state = config.load_state()?;
+    let on_chain = scan_buffers(&rpc_client, &deployer.pubkey()).await?;
+    let lamports_of = |address: &str| {
+        on_chain
+            .iter()
+            .find(|(pubkey, _)| pubkey.to_string() == address)
+            .map(|(_, lamports)| *lamports)
+    };
+
+    let records: Vec<_> = state.buffers
+        .iter()
+        .filter(|b| all || !matches!(b.status, BufferStatus::Consumed | BufferStatus::Closed))
+        .collect();
+
+    let mut locked = 0;
+    println!();
+    if records.is_empty() {
+        println!("No open buffers recorded.");
+    }
+    for record in &records {
+        let balance = lamports_of(&record.address);
+        locked += balance.unwrap_or(0);
+        println!("{}", record.address);
+        println!(
+            "  ↳ {} · {:?} · {} bytes",
+            record.purpose.map_or("unknown".to_string(), |p| format!("{p:?}").to_lowercase()),
+            record.status,
+            record.size
+        );
+        if let Some(program_id) = &record.program_id {
+            println!("  ↳ Program: {program_id}");
+        }
+        match balance {
+            Some(lamports) => println!("  ↳ Balance: {}", format_sol(lamports)),
+            None if record.authority != deployer.pubkey().to_string() => {
+                println!("  ↳ Authority: {}", record.authority)
+            }
+            None => println!("  ↳ Not on-chain"),
+        }
+    }
+
+    // Deployer-owned buffers this project has no record of
+    let untracked: Vec<_> = on_chain
+        .iter()
+        .filter(|(address, _)| !state.buffers.iter().any(|b| b.address == address.to_string()))
+        .collect();
+    if !untracked.is_empty() {
+        println!("\nOn-chain but not recorded:");
+        for (address, lamports) in &untracked {
+            locked += lamports;
+            println!("  • {address} ({})", format_sol(*lamports));
+        }
+    }
+
+    if locked > 0 {
+        println!("\nRent held in buffers: {}", format_sol(locked));
+        println!("→ Reclaim it with `shield-deploy buffers --close <ADDRESS>`");
+        println!("→ Or finish a deploy from one with `shield-deploy deploy --buffer <ADDRESS>`");
+    }
+
+    Ok(())
+}
+
+/// Close buffers through the loader, refunding their rent to the deployer
+async fn close_buffers(
+    config: &Config,
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    addresses: &[String],
+) -> Result<()> {
+    print_header("Close Buffers");
+
+    let scheduled: Vec<String> = config
+        .load_scheduled_upgrades()?
+        .into_iter()
+        .map(|plan| plan.buffer)
+        .collect();
+
+    // Check every buffer before closing any of them
+    let mut targets = Vec::new();
+    for address in addresses {
+        let buffer = Pubkey::from_str(address)
+            .with_context(|| format!("Invalid buffer address: {address}"))?;
+        let on_chain = fetch_buffer(rpc_client, &buffer).await?;
+        if on_chain.authority != Some(deployer.pubkey()) {
+            anyhow::bail!(
+                "The deployer is not the authority of {buffer} (authority: {}).",
+                on_chain.authority.map_or("none".to_string(), |a| a.to_string())
+            );
+        }
+        if scheduled.contains(address) {
+            anyhow::bail!(
+                "{buffer} holds a scheduled upgrade.\n\
+                Cancel the upgrade before closing its buffer."
+            );
+        }
+        targets.push((buffer, on_chain.lamports));
+    }
+
+    println!();
+    for (buffer, lamports) in &targets {
+        println!("• {buffer} ({})", format_sol(*lamports));
+    }
+    println!();
+    if !prompt_confirmation(&format!("Close {} buffer(s)?", targets.len()))? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let mut state = config.load_state()?;
+    let mut reclaimed = 0;
+    let mut signatures = Vec::new();
+    for (buffer, lamports) in &targets {
+        match close_buffer(rpc_client, deployer, buffer).await {
+            Ok(signature) => {
+                println!("  ✓ Closed {buffer}");
+                reclaimed += lamports;
+                signatures.push(signature.to_string());
+                if let Some(record) = state.buffers.iter_mut().find(|b| b.address == buffer.to_string()) {
+                    record.status = BufferStatus::Closed;
+                }
+            }
+            Err(e) => print_warning(&format!("Could not close {buffer}: {e:#}")),
+        }
+    }
+
+    if signatures.is_empty() {
+        anyhow::bail!("No buffers were closed");
+    }
+
+    let mut record = OperationRecord::new(OperationKind::CloseBuffer, &get_network_name());
+    record.note = Some(format!("closed {} buffers, reclaimed {}", signatures.len(), format_sol(reclaimed)));
+    record.signatures = signatures;
+    state.operations.push(record);
+    if let Ok(balance) = rpc_client.get_balance(&deployer.pubkey()).await {
+        state.last_balance = balance;
+    }
+    config.save_state(&state)?;
+
+    print_success(&format!("Reclaimed {} to the deployer", format_sol(reclaimed)));
+    Ok(())
+}
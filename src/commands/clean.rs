@@ -0,0 +1,175 @@
+use anyhow::{Context, Result};
+use dialoguer::{theme::ColorfulTheme, Input};
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::config::{BufferStatus, Config};
+use crate::privacy::{CIRCUIT_DIR, CIRCUIT_FILES};
+use crate::utils::*;
+
+/// Entries under .shield that `--all` keeps unless `--include-keys` is given
+const PROTECTED_ENTRIES: &[&str] = &["deployer.json", "vault", "spill", "authorities", "programs", "backups", "state-backups", "plans", "retired"];
+
+/// Phrase the user must type before key material is deleted
+const KEY_CONFIRMATION: &str = "delete my keys";
+
+pub async fn execute(
+    circuits: bool,
+    logs: bool,
+    pending: bool,
+    artifacts: bool,
+    state_backups: bool,
+    all: bool,
+    include_keys: bool,
+    dry_run: bool,
+) -> Result<()> {
+    print_header("Clean");
+
+    let config = Config::new()?;
+
+    let logs = logs || all;
+    let pending = pending || all;
+    let artifacts = artifacts || all;
+
+    if !(circuits || logs || pending || artifacts || state_backups) {
+        anyhow::bail!(
+            "Nothing to clean.\n\
+            Pass --circuits, --logs, --pending, --artifacts, --state-backups or --all."
+        );
+    }
+
+    let mut paths: Vec<PathBuf> = Vec::new();
+    if circuits {
+        paths.extend(CIRCUIT_FILES.iter().map(|(file, _)| Path::new(CIRCUIT_DIR).join(file)));
+    }
+    if logs {
+        paths.push(config.logs_dir());
+    }
+    if artifacts {
+        paths.push(config.proposals_dir());
+    }
+    if state_backups {
+        paths.push(config.state_backups_dir());
+    }
+    if all && config.shield_dir().exists() {
+        for entry in fs::read_dir(config.shield_dir())? {
+            let path = entry?.path();
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            if include_keys || !PROTECTED_ENTRIES.contains(&name.as_str()) {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+    paths.dedup();
+    paths.retain(|p| p.exists());
+
+    // Unfinished buffer writes are records in state.json, not separate files
+    let pending_buffers: Vec<String> = if pending && config.state_path().exists() {
+        config.load_state()?
+            .buffers
+            .iter()
+            .filter(|b| b.status == BufferStatus::Writing)
+            .map(|b| b.address.clone())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    if paths.is_empty() && pending_buffers.is_empty() {
+        println!("\nNothing to remove.");
+        return Ok(());
+    }
+
+    let mut total_bytes = 0;
+    println!();
+    for path in &paths {
+        let size = disk_usage(path);
+        total_bytes += size;
+        println!("• {} ({})", path.display(), format_bytes(size));
+    }
+    for address in &pending_buffers {
+        println!("• pending buffer record {address}");
+    }
+
+    if dry_run {
+        println!("\nDry run: {} would be reclaimed.", format_bytes(total_bytes));
+        return Ok(());
+    }
+
+    if !pending_buffers.is_empty() {
+        println!("\nRemoving buffer records does not close the buffers on chain;");
+        println!("their rent stays locked until they are closed.");
+    }
+
+    let removes_keys = paths.iter().any(|p| {
+        p.file_name()
+            .map(|n| n != "state-backups" && PROTECTED_ENTRIES.contains(&n.to_string_lossy().as_ref()))
+            .unwrap_or(false)
+    });
+
+    if removes_keys {
+        print_warning("This deletes the private deployer, vault and program keys.");
+        println!("Any SOL they hold and any authority they have will be lost for good.\n");
+
+        ensure_interactive(KEY_CONFIRMATION)?;
+        let typed: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("Type '{KEY_CONFIRMATION}' to continue"))
+            .allow_empty(true)
+            .interact_text()
+            .context("Failed to read confirmation")?;
+
+        if typed.trim() != KEY_CONFIRMATION {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    } else {
+        println!();
+        if !prompt_confirmation("Remove these?")? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    for path in &paths {
+        if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+        .with_context(|| format!("Failed to remove {}", path.display()))?;
+    }
+
+    // state.json may itself have been removed by --all
+    if !pending_buffers.is_empty() && config.state_path().exists() {
+        let mut state = config.load_state()?;
+        state.buffers.retain(|b| b.status != BufferStatus::Writing);
+        config.save_state(&state)?;
+    }
+
+    print_success(&format!("Reclaimed {}", format_bytes(total_bytes)));
+
+    Ok(())
+}
+
+fn disk_usage(path: &Path) -> u64 {
+    if path.is_dir() {
+        fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| disk_usage(&e.path()))
+                    .sum()
+            })
+            .unwrap_or(0)
+    } else {
+        fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.1} KB", b as f64 / 1024.0),
+        b => format!("{b} B"),
+    }
+}
@@ -0,0 +1,9 @@
+use anyhow::Result;
+use clap_complete::{generate, Shell};
+use std::io;
+
+pub fn execute(shell: Shell, mut command: clap::Command) -> Result<()> {
+    let name = command.get_name().to_string();
+    generate(shell, &mut command, name, &mut io::stdout());
+    Ok(())
+}
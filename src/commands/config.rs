@@ -0,0 +1,182 @@
+use anyhow::Result;
+use clap::{builder::PossibleValuesParser, Subcommand};
+use crate::config::{config_key, ConfigScope, ProjectConfig, CONFIG_KEYS};
+use crate::utils::*;
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print the effective value of a key
+    Get {
+        key: String,
+        /// Read the global config only
+        #[arg(long)]
+        global: bool,
+    },
+    /// Validate and store a value
+    Set {
+        #[arg(value_parser = PossibleValuesParser::new(CONFIG_KEYS.iter().map(|k| k.name)))]
+        key: String,
+        value: String,
+        /// Write to the global config instead of .shield/config.toml
+        #[arg(long)]
+        global: bool,
+    },
+    /// Remove a key
+    Unset {
+        key: String,
+        /// Remove from the global config instead of .shield/config.toml
+        #[arg(long)]
+        global: bool,
+    },
+    /// Show every effective value with its source
+    List {
+        /// Print structured JSON output
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+pub async fn execute(action: ConfigAction) -> Result<()> {
+    match action {
+        ConfigAction::Get { key, global } => get(&key, global),
+        ConfigAction::Set { key, value, global } => set(&key, &value, scope(global)),
+        ConfigAction::Unset { key, global } => unset(&key, scope(global)),
+        ConfigAction::List { json } => list(json),
+    }
+}
+
+fn scope(global: bool) -> ConfigScope {
+    if global { ConfigScope::Global } else { ConfigScope::Project }
+}
+
+fn get(key: &str, global: bool) -> Result<()> {
+    let scopes: &[ConfigScope] = if global {
+        &[ConfigScope::Global]
+    } else {
+        &[ConfigScope::Project, ConfigScope::Global]
+    };
+
+    for &scope in scopes {
+        if let Some(value) = scope.load_table()?.get(key) {
+            println!("{}", display_value(value));
+            return Ok(());
+        }
+    }
+
+    if config_key(key).is_none() {
+        anyhow::bail!("Unknown config key '{key}'. Run `shield-deploy config list` to see known keys.");
+    }
+    anyhow::bail!("'{key}' is not set")
+}
+
+fn set(key: &str, raw: &str, scope: ConfigScope) -> Result<()> {
+    let known = config_key(key).ok_or_else(|| anyhow::anyhow!(
+        "Unknown config key '{key}'. Run `shield-deploy config list` to see known keys."
+    ))?;
+
+    let value = known.kind
+        .parse(raw)
+        .map_err(|e| anyhow::anyhow!("Invalid value for {key}: {e}"))?;
+
+    let mut table = scope.load_table()?;
+    table.insert(key.to_string(), value);
+    scope.save_table(&table)?;
+
+    // Make sure the merged config still loads before reporting success
+    ProjectConfig::load()?;
+
+    print_success(&format!("Set {key} in {} config", scope.label()));
+    Ok(())
+}
+
+fn unset(key: &str, scope: ConfigScope) -> Result<()> {
+    let mut table = scope.load_table()?;
+
+    if table.remove(key).is_none() {
+        println!("{key} is not set in the {} config.", scope.label());
+        return Ok(());
+    }
+
+    scope.save_table(&table)?;
+    print_success(&format!("Removed {key} from {} config", scope.label()));
+    Ok(())
+}
+
+fn list(json: bool) -> Result<()> {
+    let project = ConfigScope::Project.load_table()?;
+    let global = ConfigScope::Global.load_table()?;
+
+    let lookup = |key: &str| {
+        project.get(key).map(|v| (v, ConfigScope::Project))
+            .or_else(|| global.get(key).map(|v| (v, ConfigScope::Global)))
+    };
+
+    let mut unknown: Vec<&String> = project.keys()
+        .chain(global.keys())
+        .filter(|k| config_key(k).is_none())
+        .collect();
+    unknown.sort();
+    unknown.dedup();
+
+    if json {
+        let mut rows: Vec<serde_json::Value> = CONFIG_KEYS
+            .iter()
+            .map(|k| {
+                let effective = lookup(k.name);
+                serde_json::json!({
+                    "key": k.name,
+                    "value": effective.map(|(v, _)| display_value(v)),
+                    "source": effective.map(|(_, s)| s.label()).unwrap_or("default"),
+                    "description": k.description,
+                    "known": true,
+                })
+            })
+            .collect();
+        rows.extend(unknown.iter().map(|k| {
+            let effective = lookup(k);
+            serde_json::json!({
+                "key": k,
+                "value": effective.map(|(v, _)| display_value(v)),
+                "source": effective.map(|(_, s)| s.label()),
+                "known": false,
+            })
+        }));
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+
+    println!("{:<16} {:<44} SOURCE", "KEY", "VALUE");
+    for key in CONFIG_KEYS {
+        match lookup(key.name) {
+            Some((value, source)) => {
+                println!("{:<16} {:<44} {}", key.name, display_value(value), source.label());
+            }
+            None => println!("{:<16} {:<44} default", key.name, "-"),
+        }
+    }
+
+    if !unknown.is_empty() {
+        println!();
+        for key in unknown {
+            if let Some((value, source)) = lookup(key) {
+                print_warning(&format!(
+                    "Unknown key '{key}' = {} in {} config (ignored)",
+                    display_value(value),
+                    source.label()
+                ));
+            }
+        }
+    }
+
+    println!("\nProject: {}", ConfigScope::Project.path()?.display());
+    println!("Global:  {}", ConfigScope::Global.path()?.display());
+
+    Ok(())
+}
+
+fn display_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::{Context, Result, anyhow};
-use solana_client::rpc_client::RpcClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_loader_v3_interface::{
     state::UpgradeableLoaderState,
     instruction as bpf_loader_upgradeable,
@@ -7,27 +7,76 @@ use solana_loader_v3_interface::{
 use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
 use solana_sdk::{
     pubkey::Pubkey,
-    signature::{Keypair, Signer},
+    signature::{read_keypair_file, Keypair, Signature, Signer},
     transaction::Transaction,
     instruction::Instruction as SdkInstruction,
     instruction::AccountMeta,
-    commitment_config::CommitmentConfig
 };
-use solana_system_interface::instruction as system_instruction;
 use solana_pubkey::Pubkey as SolanaPubkeyV2;
-use solana_address::Address;
-use std::{fs};
-use std::path::PathBuf;
-use crate::config::{Config, DeployedProgram};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use crate::artifact::load_program;
+use crate::compute::{estimate_priority_fee, report_priority_fees, FeeSettings};
+use crate::config::{
+    BufferStatus, Config, DeployedProgram, OperationKind, OperationRecord, PendingDeploy,
+    ProjectConfig, ProjectState,
+};
+use crate::privacy::{audit_deployer, Linkability, PRIVACY_DELAY_SECS};
+use crate::network::confirm_mainnet;
+use crate::hygiene::{auto_close_buffers, close_buffer, set_buffer_status};
+use crate::cost::{default_max_data_len, enforce_spend_limit, estimate_deploy, measure_spend, print_spend, resolve_max_data_len};
+use crate::loader::{clear_pending_deploy, create_deploy_buffer, deploy_program, finish_deploy, programdata_address};
 use crate::utils::*;
-use crate::commands::upgrade::upgrade_program_bpf_upgradeable;
+use crate::commands::estimate::print_breakdown;
+use crate::commands::fund::top_up;
+use crate::commands::finalize::{confirm_program_id, print_finalize_warning, print_immutable_notes};
+use crate::commands::verify::{fetch_buffer, record_verified_hash, verify_deployed_bytecode};
+use crate::commands::CliProgress;
 
-const MIN_DEPLOY_BALANCE: u64 = 2_000_000_000; // 2 SOL minimum
 // const MAX_PERMITTED_DATA_INCREASE: usize = 10 * 1024; // 10KB per transaction
 
-pub async fn execute(program_path: Option<String>) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub async fn execute(
+    program_path: Option<String>,
+    program_sha256: Option<String>,
+    isolate_authority: bool,
+    override_spend_limit: bool,
+    fee_payer_path: Option<PathBuf>,
+    unsafe_ignore_git: bool,
+    recover: Option<String>,
+    no_auto_clean: bool,
+    fresh: bool,
+    program_keypair_path: Option<PathBuf>,
+    max_len: Option<usize>,
+    max_len_multiplier: Option<f64>,
+    dry_run: bool,
+    buffer: Option<String>,
+    finalize: bool,
+    force_finalize: bool,
+    auto_fund: bool,
+    vanity_prefix: Option<String>,
+    duplicate: bool,
+    sync_keys: bool,
+    with_idl: bool,
+    idl_path: Option<PathBuf>,
+) -> Result<()> {
     print_header("Deploy Program");
     
+    if finalize && assume_yes() && !force_finalize {
+        anyhow::bail!(
+            "Deploying an immutable program is irreversible, so --yes alone won't confirm it.\n\
+            Pass --force-finalize as well to deploy with --final without prompts."
+        );
+    }
+    if finalize && ProjectConfig::load()?.finalize_min_age().is_some() {
+        anyhow::bail!(
+            "finalize_min_age is set, so programs must soak before they become immutable.\n\
+            Deploy without --final and run `shield-deploy finalize` once the period has passed."
+        );
+    }
+    
     let config = Config::new()?;
     
     if !config.deployer_exists() {
@@ -37,93 +86,434 @@ pub async fn execute(program_path: Option<String>) -> Result<()> {
         );
     }
     
+    config.check_git_secrets(unsafe_ignore_git)?;
     let deployer = config.load_deployer()?;
+    config.check_rotation_policy()?;
     
-    // Detect or use provided program
-    let program_file = if let Some(path) = program_path {
-        PathBuf::from(path)
-    } else {
-        detect_program_file()
-            .ok_or_else(|| anyhow::anyhow!(
-                "No program file found.\n\
-                Build your program first or specify with --program"
-            ))?
+    // Detect or use provided program (path, `-` for stdin, or URL)
+    let artifact = load_program(program_path.as_deref(), program_sha256.as_deref()).await?;
+    artifact.print_summary();
+    let max_data_len = resolve_max_data_len(artifact.data.len(), max_len, max_len_multiplier)?;
+    
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url.clone(),
+        commitment(),
+    );
+    
+    // A buffer already holding this build (see `shield-deploy buffers`) skips the write
+    let existing_buffer = match buffer {
+        Some(address) => Some(check_existing_buffer(&rpc_client, &deployer, &address, &artifact.data).await?),
+        None => None,
     };
     
-    if !program_file.exists() {
-        anyhow::bail!("Program file not found: {}", program_file.display());
+    // The same build already deployed here is almost always a repeated command
+    if recover.is_none() {
+        check_duplicate(&config.load_state()?, &artifact.data, artifact.name.as_deref(), duplicate || dry_run)?;
+    }
+    
+    if dry_run {
+        return print_dry_run(&rpc_client, &deployer, &artifact.data, max_data_len, fee_payer_path.as_deref()).await;
     }
     
-    println!("\nBuild artifact detected:");
-    println!("• {}\n", program_file.display());
+    if let Some(program_id) = recover {
+        guard_recovery(&rpc_client, &deployer, artifact.data.len(), max_data_len, override_spend_limit).await?;
+        if let Some(path) = &fee_payer_path {
+            use_fee_payer(&rpc_client, path, 0).await?;
+        }
+        confirm_recovered_finalize(&program_id, finalize, force_finalize)?;
+        let program_name = artifact.name.clone();
+        let (program_id, finalized) =
+            recover_deploy(&config, &rpc_client, &deployer, &program_id, &artifact.data, artifact.name, finalize).await?;
+        return after_deploy(
+            &config,
+            &rpc_client,
+            &deployer,
+            &program_id,
+            &artifact.data,
+            program_name.as_deref(),
+            isolate_authority,
+            no_auto_clean,
+            with_idl,
+            idl_path,
+            sync_keys,
+            finalized,
+        )
+        .await;
+    }
+    
+    // A deploy killed mid-write left its buffer behind; pick it up or abandon it
+    match config.load_state()?.pending_deploy {
+        Some(pending) if fresh => abandon_pending_deploy(&config, &rpc_client, &deployer, &pending).await?,
+        Some(pending) if pending.artifact_hash == artifact.sha256 => {
+            print_warning(&format!(
+                "A deploy of {} was interrupted while writing buffer {}",
+                pending.program_id, pending.buffer
+            ));
+            if !prompt_confirmation("Resume it from the existing buffer?")? {
+                anyhow::bail!("Deploy stopped. Pass --fresh to close the old buffer and start over.");
+            }
+            guard_recovery(&rpc_client, &deployer, artifact.data.len(), max_data_len, override_spend_limit).await?;
+            if let Some(path) = &fee_payer_path {
+                use_fee_payer(&rpc_client, path, 0).await?;
+            }
+            confirm_recovered_finalize(&pending.program_id, finalize, force_finalize)?;
+            let program_name = artifact.name.clone();
+            let (program_id, finalized) = recover_deploy(
+                &config,
+                &rpc_client,
+                &deployer,
+                &pending.program_id,
+                &artifact.data,
+                artifact.name,
+                finalize,
+            )
+            .await
+            .context("Resuming failed. Pass --fresh to close the old buffer and start over")?;
+            return after_deploy(
+                &config,
+                &rpc_client,
+                &deployer,
+                &program_id,
+                &artifact.data,
+                program_name.as_deref(),
+                isolate_authority,
+                no_auto_clean,
+                with_idl,
+                idl_path,
+                sync_keys,
+                finalized,
+            )
+            .await;
+        }
+        Some(pending) => anyhow::bail!(
+            "An interrupted deploy of {} was writing a different build into buffer {}.\n\
+            Deploy that artifact again to resume it, or pass --fresh to close the buffer and start over.",
+            pending.program_id,
+            pending.buffer
+        ),
+        None if fresh => println!("No interrupted deploy to abandon.\n"),
+        None => {}
+    }
+    
+    // Same analysis as `shield-deploy audit`; warn only, the deploy itself links nothing new
+    let vault_keys: Vec<Pubkey> = config.load_vault_keys()?.iter().map(|k| k.pubkey()).collect();
+    let privacy_delay = ProjectConfig::load()?
+        .privacy_delay()
+        .unwrap_or(Duration::from_secs(PRIVACY_DELAY_SECS));
+    if let Ok(report) = audit_deployer(
+        &rpc_client,
+        &deployer.pubkey(),
+        &config.load_state()?,
+        &vault_keys,
+        privacy_delay,
+    )
+    .await {
+        if report.verdict == Linkability::Linked {
+            print_warning("The deployer is already linked to another wallet on-chain");
+            println!("→ Run `shield-deploy audit` for details\n");
+        }
+    }
+    
+    // Vanity keypairs ground ahead of time with `shield-deploy grind`; keys with
+    // an open buffer belong to an interrupted deploy and are left for --recover
+    let state = config.load_state()?;
+    let (interrupted, ground): (Vec<Keypair>, Vec<Keypair>) = config
+        .load_pending_program_keys()?
+        .into_iter()
+        .partition(|k| state.buffers.iter().any(|b| {
+            b.program_id == Some(k.pubkey().to_string())
+                && matches!(b.status, BufferStatus::Writing | BufferStatus::Ready)
+        }));
+    for keypair in &interrupted {
+        print_warning(&format!("A deploy of {} was interrupted", keypair.pubkey()));
+        println!("→ Finish it with `shield-deploy deploy --recover {}`\n", keypair.pubkey());
+    }
+    
+    // The program ID should match the declare_id! the program was built with
+    let (program_keypair, key_source) = match (program_keypair_path, vanity_prefix) {
+        (Some(path), _) => (read_program_keypair(&path)?, format!("provided keypair {}", path.display())),
+        (None, Some(prefix)) => {
+            println!("Grinding a program ID starting with {prefix}...");
+            let result = crate::commands::grind::grind(&prefix, false, 1).await?;
+            match result.keypairs.into_iter().next() {
+                Some(keypair) if !result.interrupted => (keypair, format!("vanity prefix {prefix}")),
+                _ => anyhow::bail!("Cancelled; nothing was deployed"),
+            }
+        }
+        (None, None) => match anchor_program_keypair(&artifact.source) {
+            Some(path) => (read_program_keypair(&path)?, format!("Anchor artifact {}", path.display())),
+            None => match ground.into_iter().next() {
+                Some(keypair) if prompt_confirmation(&format!(
+                    "Use pending program keypair {}?",
+                    keypair.pubkey()
+                ))? => (keypair, "pending keypair from `shield-deploy grind`".to_string()),
+                _ => (Keypair::new(), "freshly generated".to_string()),
+            },
+        },
+    };
+    let program_id = program_keypair.pubkey();
+    println!("Program ID: {program_id} ({key_source})\n");
+    
+    if with_retry(|| rpc_client.get_account_with_commitment(&program_id, rpc_client.commitment())).await?.value.is_some() {
+        anyhow::bail!(
+            "An account already exists at {program_id}.\n\
+            Use `shield-deploy upgrade` for a deployed program, or pass a different --program-keypair."
+        );
+    }
     
     println!("This deployment will:");
     println!("• Use the private deployer");
     println!("• Hide your funding wallet on-chain");
-    println!("• Set upgrade authority to the deployer\n");
+    if let Some((address, _)) = &existing_buffer {
+        println!("• Deploy from the existing buffer {address}");
+    }
+    if finalize {
+        println!("• Drop the upgrade authority in the deploy transaction (immutable)\n");
+    } else if isolate_authority {
+        println!("• Hand upgrade authority to a new key dedicated to this program\n");
+    } else {
+        println!("• Set upgrade authority to the deployer\n");
+    }
+    
+    let program_data = artifact.data;
+    
+    let mut estimate = estimate_deploy(
+        &rpc_client,
+        program_data.len(),
+        max_data_len,
+        estimate_priority_fee(&rpc_client, &[deployer.pubkey()]).await,
+    )
+    .await?;
+    // The buffer is already paid for and the loader drains it into ProgramData
+    let buffer_lamports = match &existing_buffer {
+        Some((_, lamports)) => {
+            estimate.chunk_count = 0;
+            estimate.write_fees = 0;
+            estimate.buffer_rent = 0;
+            *lamports
+        }
+        None => 0,
+    };
+    
+    println!("Cost:");
+    print_breakdown(&estimate);
+    if buffer_lamports > 0 {
+        println!("  ↳ {} of it is already held by the buffer", format_sol(buffer_lamports));
+    }
+    
+    // Capacity is fixed at deploy; show what the choice costs against the default
+    let default_len = default_max_data_len(program_data.len());
+    if max_data_len != default_len {
+        let default_rent = rpc_client
+            .get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_programdata(default_len)).await
+            .context("Failed to get ProgramData rent")?;
+        let (sign, diff) = if estimate.programdata_rent >= default_rent {
+            ('+', estimate.programdata_rent - default_rent)
+        } else {
+            ('-', default_rent - estimate.programdata_rent)
+        };
+        println!("  ↳ ProgramData rent {sign}{} against the default {default_len} bytes", format_sol(diff));
+    }
+    println!();
+    
+    if finalize {
+        print_finalize_warning(&program_id);
+    }
     
     if !prompt_confirmation("Proceed?")? {
         println!("Cancelled.");
         return Ok(());
     }
+    if finalize && !force_finalize {
+        confirm_program_id(&program_id)?;
+    }
     
     // Check deployer balance
-    let rpc_url = get_rpc_url()?;
-    let rpc_client = RpcClient::new_with_commitment(
-        rpc_url.clone(),
-        CommitmentConfig::confirmed(),
-    );
-    
-    let balance = rpc_client.get_balance(&deployer.pubkey())
+    let mut balance = balance_of(&rpc_client, &deployer.pubkey()).await
         .context("Failed to get deployer balance")?;
     
-    if balance < MIN_DEPLOY_BALANCE {
-        anyhow::bail!(
-            "Insufficient deployer balance.\n\
-            Current: {}\n\
-            Needed: ~5 SOL\n\
-            Run `shield-deploy fund` to add more SOL.",
-            format_sol(balance)
-        );
+    let network = confirm_mainnet("Deploying", Some(estimate.net_cost())).await?;
+    enforce_spend_limit(&network, estimate.required_balance(), override_spend_limit)?;
+    
+    // With a separate fee payer the deployer only needs the rent
+    let required = match &fee_payer_path {
+        Some(path) => {
+            let payer = use_fee_payer(&rpc_client, path, estimate.fee_budget()).await?;
+            println!("  ↳ Fee payer: {payer}");
+            estimate.required_balance() - estimate.fee_budget()
+        }
+        None => estimate.required_balance(),
     }
+    .saturating_sub(buffer_lamports);
     
-    println!("\n Deploying program...");
+    if balance < required {
+        let shortfall = required - balance;
+        let offer = || {
+            std::io::stdin().is_terminal()
+                && !assume_yes()
+                && prompt_confirmation(&format!(
+                    "The deployer is short by {}. Top it up through Privacy Cash now?",
+                    format_sol(shortfall)
+                ))
+                .unwrap_or(false)
+        };
+        if !auto_fund && !offer() {
+            anyhow::bail!(
+                "Insufficient deployer balance.\n\
+                Current: {}\n\
+                Needed: {} (short by {})\n\
+                Run `shield-deploy fund` to add more SOL, or pass --auto-fund.",
+                format_sol(balance),
+                format_sol(required),
+                format_sol(shortfall)
+            );
+        }
+        balance = top_up(&config, &rpc_client, &deployer, shortfall, required).await?;
+    }
     
-    let program_data = fs::read(&program_file)
-        .context("Failed to read program file")?;
+    println!("\n Deploying program...");
     
     println!("  ↳ Program size: {} bytes", program_data.len());
-    
-    // Generate program keypair
-    let program_keypair = Keypair::new();
-    let program_id = program_keypair.pubkey();
-    
     println!("  ↳ Program ID: {program_id}");
     
+    // Keep the key on disk until the deploy lands so --recover can finish it
+    config.save_pending_program_key(&program_keypair)?;
+    
     // Deploy program using BPF Loader Upgradeable
-    deploy_program_bpf_upgradeable(
-        &rpc_client,
-        &deployer,
-        &program_keypair,
-        &program_data,
-    )
-    .await
+    let signature = match &existing_buffer {
+        Some((buffer_pubkey, _)) => deploy_from_buffer(
+            &config,
+            &rpc_client,
+            &deployer,
+            &program_keypair,
+            buffer_pubkey,
+            program_data.len(),
+            max_data_len,
+            finalize,
+        )
+        .await,
+        None => deploy_program_bpf_upgradeable(
+            &config,
+            &rpc_client,
+            &deployer,
+            &program_keypair,
+            &program_data,
+            max_data_len,
+            finalize,
+        )
+        .await,
+    }
     .context("Failed to deploy program")?;
     
     print_success("Program deployed");
     
+    config.keep_program_key(&program_id)?;
+    
+    let balance_after = balance_of(&rpc_client, &deployer.pubkey()).await.ok();
+    
     println!("\nProgram ID:        {program_id}");
-    println!("Upgrade authority: private deployer");
+    if finalize {
+        println!("Upgrade authority: None (immutable)");
+    } else if !isolate_authority {
+        println!("Upgrade authority: private deployer");
+    }
     
     let mut state = config.load_state()?;
-    state.deployed_programs.push(DeployedProgram {
-        program_id: program_id.to_string(),
-        deployed_at: chrono::Utc::now().timestamp(),
-        last_upgraded: None,
-    });
-    state.last_balance = balance;
+    let program_name = artifact.name.clone();
+    let mut program = deployed_program(&program_id, &deployer, &program_data, artifact.name, max_data_len);
+    if finalize {
+        program.authority = None;
+        program.immutable = true;
+        let mut record = OperationRecord::new(OperationKind::Finalize, &get_network_name());
+        record.program_id = Some(program_id.to_string());
+        record.signatures.push(signature.to_string());
+        record.note = Some("deployed with --final".to_string());
+        state.operations.push(record);
+    }
+    state.deployed_programs.push(program);
+    let mut record = OperationRecord::new(OperationKind::Deploy, &get_network_name());
+    record.program_id = Some(program_id.to_string());
+    record.buffer = state.latest_buffer(&program_id.to_string());
+    record.buffer_signatures = take_buffer_signatures();
+    record.signatures.push(signature.to_string());
+    record.cost_lamports = balance_after.map(|after| balance.saturating_sub(after));
+    if let Some(after) = balance_after {
+        let spend = measure_spend(&rpc_client, &program_id, &record, balance, after).await;
+        print_spend(&spend);
+        record.spend = Some(spend);
+    }
+    state.operations.push(record);
+    state.last_balance = balance_after.unwrap_or(balance);
     config.save_state(&state)?;
     
+    after_deploy(
+        &config,
+        &rpc_client,
+        &deployer,
+        &program_id,
+        &program_data,
+        program_name.as_deref(),
+        isolate_authority,
+        no_auto_clean,
+        with_idl,
+        idl_path,
+        sync_keys,
+        finalize,
+    )
+    .await
+}
+
+/// Steps after a program lands, shared by fresh, resumed and recovered deploys
+///
+/// Checks the deployed bytecode, moves the authority to its own key, closes
+/// stale buffers, publishes the IDL and syncs the program ID into the
+/// workspace, as requested on the command line.
+#[allow(clippy::too_many_arguments)]
+async fn after_deploy(
+    config: &Config,
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    program_id: &Pubkey,
+    program_data: &[u8],
+    program_name: Option<&str>,
+    isolate_authority: bool,
+    no_auto_clean: bool,
+    with_idl: bool,
+    idl_path: Option<PathBuf>,
+    sync_keys: bool,
+    immutable: bool,
+) -> Result<()> {
+    // Move the authority first so a failed check never leaves it on the deployer
+    if isolate_authority {
+        isolate_program_authority(config, rpc_client, deployer, program_id).await?;
+    }
+    
+    let verified_hash = verify_deployed_bytecode(rpc_client, program_id, program_data).await?;
+    record_verified_hash(config, program_id, verified_hash)?;
+    
+    if !no_auto_clean {
+        auto_close_buffers(config, rpc_client, deployer, &CliProgress).await;
+    }
+    
+    if with_idl {
+        crate::commands::idl::publish_with_deploy(config, rpc_client, deployer, program_id, idl_path)
+            .await
+            .with_context(|| format!(
+                "{program_id} is deployed, but publishing its IDL failed.\n\
+                Retry with `shield-deploy idl init {program_id}`"
+            ))?;
+    }
+    
+    if sync_keys {
+        crate::commands::keys::sync_deployed(program_name, program_id)?;
+    }
+    
+    if immutable {
+        print_immutable_notes();
+        return Ok(());
+    }
+    
     println!("\nNext steps:");
     println!("→ Upgrade later with `shield-deploy upgrade`");
     println!("→ Transfer authority if desired");
@@ -131,58 +521,329 @@ pub async fn execute(program_path: Option<String>) -> Result<()> {
     Ok(())
 }
 
-/// Verify that a program can be deployed (doesn't exist or is upgradeable)
-fn verify_can_deploy(
+/// The --final warning and typed confirmation before resuming a deploy with it
+fn confirm_recovered_finalize(program_id: &str, finalize: bool, force_finalize: bool) -> Result<()> {
+    if !finalize {
+        return Ok(());
+    }
+    let program_id: Pubkey = program_id
+        .parse()
+        .with_context(|| format!("Invalid program ID: {program_id}"))?;
+    print_finalize_warning(&program_id);
+    if !prompt_confirmation("Finish the deploy as immutable?")? {
+        anyhow::bail!("Deploy stopped; nothing was sent");
+    }
+    if !force_finalize {
+        confirm_program_id(&program_id)?;
+    }
+    Ok(())
+}
+
+/// Mainnet opt-in and spend cap for finishing an interrupted deploy
+///
+/// Recovery branches off before the main estimate, so the rest of the deploy
+/// is priced here, at worst a full rewrite of the buffer.
+async fn guard_recovery(
     rpc_client: &RpcClient,
+    deployer: &Keypair,
+    program_len: usize,
+    max_data_len: usize,
+    override_spend_limit: bool,
+) -> Result<()> {
+    let priority_fee = estimate_priority_fee(rpc_client, &[deployer.pubkey()]).await;
+    let estimate = estimate_deploy(rpc_client, program_len, max_data_len, priority_fee).await?;
+    let network = confirm_mainnet("Finishing the interrupted deploy", Some(estimate.net_cost())).await?;
+    enforce_spend_limit(&network, estimate.required_balance(), override_spend_limit)
+}
+
+/// Check that `address` is a deployer-owned buffer holding exactly `program_data`
+///
+/// Returns the buffer and its lamports.
+async fn check_existing_buffer(
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    address: &str,
+    program_data: &[u8],
+) -> Result<(Pubkey, u64)> {
+    let buffer = Pubkey::from_str(address).context("Invalid buffer address")?;
+    let on_chain = fetch_buffer(rpc_client, &buffer).await?;
+    
+    if on_chain.authority != Some(deployer.pubkey()) {
+        anyhow::bail!(
+            "Buffer {buffer} is not owned by the deployer (authority: {}).",
+            on_chain.authority.map_or("none".to_string(), |a| a.to_string())
+        );
+    }
+    if on_chain.payload != strip_trailing_zeros(program_data) {
+        anyhow::bail!(
+            "Buffer {buffer} does not hold this build (payload {}, artifact {}).\n\
+            Run `shield-deploy inspect-buffer {buffer}` to see what it holds.",
+            sha256_hex(&on_chain.payload),
+            sha256_hex(strip_trailing_zeros(program_data))
+        );
+    }
+    
+    println!("Using buffer {buffer} ({} already paid)\n", format_sol(on_chain.lamports));
+    Ok((buffer, on_chain.lamports))
+}
+
+/// Deploy a new program from a buffer written by an earlier attempt
+async fn deploy_from_buffer(
+    config: &Config,
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    program_keypair: &Keypair,
+    buffer_pubkey: &Pubkey,
+    program_len: usize,
+    max_data_len: usize,
+    immutable: bool,
+) -> Result<Signature> {
+    let program_id = program_keypair.pubkey();
+    
+    // Tie the buffer to this program so `deploy --recover` can pick it up
+    let mut state = config.load_state()?;
+    if let Some(record) = state.buffers.iter_mut().find(|b| b.address == buffer_pubkey.to_string()) {
+        record.program_id = Some(program_id.to_string());
+        record.status = BufferStatus::Ready;
+        config.save_state(&state)?;
+    }
+    
+    println!("\n Deploying program from buffer {buffer_pubkey}...");
+    let signature = finish_deploy(
+        config,
+        rpc_client,
+        &FeeSettings::current(),
+        deployer,
+        &program_id,
+        Some(program_keypair),
+        buffer_pubkey,
+        program_len,
+        max_data_len,
+        immutable,
+        &CliProgress,
+    )
+    .await?;
+    
+    let lib_name = get_program_lib_name()?;
+    deploy_idl_if_available(&program_id, &lib_name).await?;
+    
+    Ok(signature)
+}
+
+/// `<name>-keypair.json` next to a local `<name>.so`, as `anchor build` leaves it
+fn anchor_program_keypair(artifact_source: &str) -> Option<PathBuf> {
+    let so = Path::new(artifact_source);
+    if so.extension().and_then(|e| e.to_str()) != Some("so") {
+        return None;
+    }
+    let stem = so.file_stem()?.to_string_lossy();
+    let keypair = so.with_file_name(format!("{stem}-keypair.json"));
+    keypair.exists().then_some(keypair)
+}
+
+fn read_program_keypair(path: &Path) -> Result<Keypair> {
+    read_keypair_file(path)
+        .map_err(|e| anyhow!("Failed to read program keypair {}: {e}", path.display()))
+}
+
+/// Move a freshly deployed program's upgrade authority to its own key
+///
+/// The key is kept under `.shield/authorities/`; `upgrade`, `finalize` and
+/// `rotate` sign with it while the deployer keeps paying fees.
+async fn isolate_program_authority(
+    config: &Config,
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
     program_id: &Pubkey,
-    upgrade_authority: &Keypair,
-) -> Result<bool> {
-    match rpc_client.get_account(program_id) {
-        Ok(account) => {
-            let loader_id_sdk = Pubkey::new_from_array(LOADER_ID.to_bytes());
-            // Program exists - verify it's upgradeable
-            if account.owner != loader_id_sdk {
-                return Err(anyhow!(
-                    "Program {program_id} exists but is not an upgradeable program"
-                ));
-            }
+) -> Result<()> {
+    println!("\n Isolating upgrade authority...");
+    
+    let authority = Keypair::new();
+    config.stage_program_authority(program_id, &authority)?;
+    
+    let signature = set_upgrade_authority_checked(rpc_client, &fee_payer(deployer), deployer, &authority, program_id).await
+        .context("Failed to hand upgrade authority to the dedicated key. The deployer still controls the program")?;
+    let path = config.commit_program_authority(program_id)?;
+    
+    let mut state = config.load_state()?;
+    if let Some(program) = state.program_mut(&program_id.to_string()) {
+        program.authority = Some(authority.pubkey().to_string());
+        program.isolated_authority = true;
+    }
+    let mut record = OperationRecord::new(OperationKind::TransferAuthority, &get_network_name());
+    record.program_id = Some(program_id.to_string());
+    record.signatures.push(signature.to_string());
+    record.note = Some(format!("isolated authority {}", authority.pubkey()));
+    state.operations.push(record);
+    config.save_state(&state)?;
+    
+    println!("  ✓ Upgrade authority: {}", authority.pubkey());
+    println!("  ↳ Key stored at {}", path.display());
+    
+    Ok(())
+}
 
-            // Check if this is a valid program
-            match bincode::deserialize::<UpgradeableLoaderState>(&account.data) {
-                Ok(UpgradeableLoaderState::Program {
-                    programdata_address,
-                }) => {
-                    // Get ProgramData to check authority
-                    let programdata_address_pk = solana_sdk::pubkey::Pubkey::new_from_array(programdata_address.to_bytes());
-                    let programdata = rpc_client.get_account(&programdata_address_pk)?;
-                    match bincode::deserialize::<UpgradeableLoaderState>(&programdata.data)? {
-                        UpgradeableLoaderState::ProgramData {
-                            upgrade_authority_address,
-                            ..
-                        } => {
-                            if upgrade_authority_address.is_none() {
-                                return Err(anyhow!(
-                                    "Program {program_id} is immutable and cannot be upgraded"
-                                ));
-                            }
-                            let expected_authority_v2 = SolanaPubkeyV2::new_from_array(upgrade_authority.pubkey().to_bytes());
-                            if upgrade_authority_address != Some(expected_authority_v2) {
-                                return Err(anyhow!(
-                                    "Authority mismatch. Expected {}, found {:?}",
-                                    upgrade_authority.pubkey(),
-                                    upgrade_authority_address
-                                ));
-                            }
-                            Ok(true) // Exists and can upgrade
-                        }
-                        _ => Err(anyhow!("Invalid ProgramData state")),
-                    }
-                }
-                _ => Err(anyhow!("Invalid program account")),
+/// Deploy every program in `target/deploy/` that isn't deployed on this network yet
+///
+/// Each artifact goes through [`execute`] with its `<name>-keypair.json`, so
+/// it is recorded under its name as soon as it lands. After a failure,
+/// running this again only deploys the programs that are still missing.
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_all(
+    isolate_authority: bool,
+    override_spend_limit: bool,
+    fee_payer_path: Option<PathBuf>,
+    unsafe_ignore_git: bool,
+    no_auto_clean: bool,
+    max_len_multiplier: Option<f64>,
+    dry_run: bool,
+    auto_fund: bool,
+    sync_keys: bool,
+    with_idl: bool,
+) -> Result<()> {
+    print_header("Deploy Workspace");
+    
+    let config = Config::new()?;
+    let files = list_program_files();
+    if files.is_empty() {
+        anyhow::bail!(
+            "No programs found in {}.\n\
+            Build the workspace first (e.g. `anchor build`).",
+            artifact_dirs().iter().map(|dir| dir.display().to_string()).collect::<Vec<_>>().join(", ")
+        );
+    }
+    
+    let rpc_client = RpcClient::new_with_commitment(get_rpc_url()?, commitment());
+    let network = get_network_name();
+    let state = config.load_state()?;
+    let priority_fee = estimate_priority_fee(&rpc_client, &[]).await;
+    
+    println!();
+    println!("{:<24} {:>10}  {:>14}  Status", "Program", "Size", "Est. cost");
+    let mut todo = Vec::new();
+    let mut total = 0;
+    for path in &files {
+        let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let size = std::fs::metadata(path)?.len() as usize;
+        let deployed = state.deployed_programs.iter().find(|p| {
+            p.name.as_deref() == Some(name.as_str()) && p.cluster.as_deref().is_none_or(|c| c == network)
+        });
+        let (cost, status) = match deployed {
+            Some(program) => ("-".to_string(), format!("deployed ({})", program.program_id)),
+            None => {
+                let max_data_len = resolve_max_data_len(size, None, max_len_multiplier)?;
+                let estimate = estimate_deploy(&rpc_client, size, max_data_len, priority_fee).await?;
+                total += estimate.net_cost();
+                todo.push(path.clone());
+                let keypair = if anchor_program_keypair(&path.display().to_string()).is_some() {
+                    "pending"
+                } else {
+                    "pending, no keypair (new program ID)"
+                };
+                (format_sol(estimate.net_cost()), keypair.to_string())
             }
+        };
+        println!("{name:<24} {size:>10}  {cost:>14}  {status}");
+    }
+    println!("\n{} of {} programs to deploy, about {} in total\n", todo.len(), files.len(), format_sol(total));
+    
+    if todo.is_empty() {
+        print_success("Every program in the workspace is already deployed");
+        return Ok(());
+    }
+    
+    let deployed_before = state.deployed_programs.len();
+    for (i, path) in todo.iter().enumerate() {
+        println!("\n[{}/{}] {}", i + 1, todo.len(), path.display());
+        let result = execute(
+            Some(path.display().to_string()),
+            None,
+            isolate_authority,
+            override_spend_limit,
+            fee_payer_path.clone(),
+            unsafe_ignore_git,
+            None,
+            no_auto_clean,
+            false,
+            None,
+            None,
+            max_len_multiplier,
+            dry_run,
+            None,
+            false,
+            false,
+            auto_fund,
+            None,
+            false,
+            sync_keys,
+            with_idl,
+            None,
+        )
+        .await;
+        if let Err(e) = result {
+            let remaining = todo.len() - i;
+            return Err(e).context(format!(
+                "Deploying {} failed; {remaining} program(s) not deployed.\n\
+                Programs deployed so far are recorded. Run `shield-deploy deploy --all` again to continue.",
+                path.display()
+            ));
         }
-        Err(_) => Ok(false), // Program doesn't exist - can deploy
     }
+    
+    if !dry_run {
+        let deployed = config.load_state()?.deployed_programs.len().saturating_sub(deployed_before);
+        print_success(&format!("Deployed {deployed} of {} program(s)", todo.len()));
+    }
+    Ok(())
+}
+
+/// Price a deploy with the same cost model as the real run and stop
+///
+/// Nothing is signed or sent; the only RPC calls are rent, fee and balance lookups.
+async fn print_dry_run(
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    program_data: &[u8],
+    max_data_len: usize,
+    fee_payer_path: Option<&Path>,
+) -> Result<()> {
+    let estimate = estimate_deploy(
+        rpc_client,
+        program_data.len(),
+        max_data_len,
+        estimate_priority_fee(rpc_client, &[deployer.pubkey()]).await,
+    )
+    .await?;
+    let balance = balance_of(rpc_client, &deployer.pubkey()).await
+        .context("Failed to get deployer balance")?;
+    
+    print_header("Deploy Dry Run");
+    println!();
+    println!("Program size:      {} bytes", program_data.len());
+    println!("Network:           {}", get_network_name());
+    println!();
+    print_breakdown(&estimate);
+    
+    // A fee payer covers the fees; the deployer still holds the rent
+    let required = match fee_payer_path {
+        Some(path) => {
+            println!("  ↳ Fees of {} come from {}", format_sol(estimate.fee_budget()), path.display());
+            estimate.required_balance() - estimate.fee_budget()
+        }
+        None => estimate.required_balance(),
+    };
+    
+    println!();
+    println!("Deployer balance:  {}", format_sol(balance));
+    if balance >= required {
+        print_success(&format!("Enough for this deploy, {} to spare", format_sol(balance - required)));
+    } else {
+        print_warning(&format!("Short by {}", format_sol(required - balance)));
+        println!("→ Run `shield-deploy fund` to add more SOL");
+    }
+    println!("\nDry run: nothing was sent.");
+    
+    Ok(())
 }
 
 /// Deploy a program using BPF Loader Upgradeable
@@ -192,170 +853,465 @@ fn verify_can_deploy(
 /// 2. Write program data to buffer (in chunks)
 /// 3. Deploy from buffer to program account
 /// 4. Set deployer as upgrade authority
-async fn deploy_program_bpf_upgradeable(
+pub async fn deploy_program_bpf_upgradeable(
+    config: &Config,
     rpc_client: &RpcClient,
     deployer: &Keypair,
     program_keypair: &Keypair,
     program_data: &[u8],
-) -> Result<()> {
+    max_data_len: usize,
+    immutable: bool,
+) -> Result<Signature> {
     let program_id = program_keypair.pubkey();
-    let deployer_pubkey = deployer.pubkey();
 
-    println!("\n🔍 Verifying deployment prerequisites...");
+    let signature = deploy_program(
+        config,
+        rpc_client,
+        &FeeSettings::current(),
+        deployer,
+        program_keypair,
+        program_data,
+        max_data_len,
+        immutable,
+        &CliProgress,
+    )
+    .await?;
+
+    // Get program name from the current directory or Cargo.toml
+    let lib_name = get_program_lib_name()?;
     
-    // Check if program already exists and validate upgrade authority
-    let program_exists = verify_can_deploy(rpc_client, &program_id, deployer)?;
+    // Deploy IDL if available
+    deploy_idl_if_available(&program_id, &lib_name).await?;
     
-    if program_exists {
-        println!("  ⚠️  Program already exists - this will be an upgrade");
-        return upgrade_program_bpf_upgradeable(
-            rpc_client,
-            deployer,
-            &program_id,
-            program_data,
-        )
-        .await;
+    Ok(signature)
+}
+
+/// Close the buffer of an interrupted deploy and forget it (`deploy --fresh`)
+///
+/// The program keypair stays pending, so the next deploy can offer to reuse
+/// the same program ID.
+async fn abandon_pending_deploy(
+    config: &Config,
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    pending: &PendingDeploy,
+) -> Result<()> {
+    println!("\n Abandoning the interrupted deploy of {}...", pending.program_id);
+    
+    let buffer = Pubkey::from_str(&pending.buffer)
+        .with_context(|| format!("Invalid buffer address in state: {}", pending.buffer))?;
+    let mut state = config.load_state()?;
+    let closed = match buffer_state(rpc_client, &buffer, &deployer.pubkey(), 0).await? {
+        BufferState::Missing => {
+            println!("  ↳ Buffer {buffer} is already closed");
+            true
+        }
+        BufferState::WrongAuthority => {
+            print_warning(&format!("Buffer {buffer} no longer belongs to the deployer; leaving it open"));
+            false
+        }
+        _ => {
+            let lamports = with_retry(|| rpc_client.get_balance(&buffer)).await.unwrap_or_default();
+            let signature = close_buffer(rpc_client, deployer, &buffer).await?;
+            println!("  ✓ Closed {buffer}, reclaimed {}", format_sol(lamports));
+            let mut record = OperationRecord::new(OperationKind::CloseBuffer, &get_network_name());
+            record.signatures.push(signature.to_string());
+            record.note = Some(format!("abandoned deploy of {}", pending.program_id));
+            state.operations.push(record);
+            true
+        }
+    };
+    if closed {
+        if let Some(record) = state.buffers.iter_mut().find(|b| b.address == pending.buffer) {
+            record.status = BufferStatus::Closed;
+        }
     }
+    state.pending_deploy = None;
+    config.save_state(&state)?;
+    println!();
     
-    println!("  ✓ Program does not exist - proceeding with fresh deployment");
+    Ok(())
+}
+
+/// What an interrupted deploy left behind at the program address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgramAccountState {
+    /// Nothing there yet
+    Missing,
+    /// Created and owned by the loader, but never initialized
+    Uninitialized,
+    /// Initialized program whose ProgramData holds code
+    Deployed,
+    /// Owned by another program or in an unexpected loader state
+    Foreign,
+}
+
+/// What is left of the buffer the interrupted deploy was writing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferState {
+    /// No recorded buffer, or it has been closed
+    Missing,
+    /// Owned by the deployer and large enough for the artifact
+    Usable,
+    /// Its authority is no longer the deployer
+    WrongAuthority,
+    /// Too small for the artifact being deployed
+    WrongSize,
+    /// Started for a different build of the program
+    DifferentArtifact,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecoveryPlan {
+    /// The deploy landed; only local state needs updating
+    AlreadyDeployed,
+    /// Finish the deploy, writing into the existing buffer when possible
+    Deploy { reuse_buffer: bool, create_program: bool },
+    /// Nothing can be resumed; the reason is shown to the user
+    Impossible(String),
+}
+
+/// Decide how to resume a deploy from what is on-chain and on disk
+pub fn plan_recovery(program: ProgramAccountState, buffer: BufferState, have_program_key: bool) -> RecoveryPlan {
+    let reuse_buffer = buffer == BufferState::Usable;
+    match program {
+        ProgramAccountState::Deployed => RecoveryPlan::AlreadyDeployed,
+        ProgramAccountState::Foreign => RecoveryPlan::Impossible(
+            "the program address holds an account this deployer cannot finish".to_string(),
+        ),
+        // DeployWithMaxDataLen only needs the program to sign when it is created
+        ProgramAccountState::Uninitialized => RecoveryPlan::Deploy { reuse_buffer, create_program: false },
+        ProgramAccountState::Missing if have_program_key => RecoveryPlan::Deploy { reuse_buffer, create_program: true },
+        ProgramAccountState::Missing => RecoveryPlan::Impossible(
+            "the program account was never created and its keypair is not in .shield/programs/".to_string(),
+        ),
+    }
+}
+
+async fn program_account_state(rpc_client: &RpcClient, program_id: &Pubkey) -> Result<ProgramAccountState> {
+    let Some(account) = with_retry(|| rpc_client.get_account_with_commitment(program_id, rpc_client.commitment())).await?
+        .value
+    else {
+        return Ok(ProgramAccountState::Missing);
+    };
+    if account.owner != Pubkey::new_from_array(LOADER_ID.to_bytes()) {
+        return Ok(ProgramAccountState::Foreign);
+    }
+    match bincode::deserialize::<UpgradeableLoaderState>(&account.data) {
+        Ok(UpgradeableLoaderState::Uninitialized) => Ok(ProgramAccountState::Uninitialized),
+        Ok(UpgradeableLoaderState::Program { .. }) => {
+            let programdata_address = programdata_address(program_id);
+            let programdata = with_retry(|| {
+                rpc_client.get_account_with_commitment(&programdata_address, rpc_client.commitment())
+            }).await?
+            .value;
+            Ok(match programdata {
+                Some(_) => ProgramAccountState::Deployed,
+                None => ProgramAccountState::Foreign,
+            })
+        }
+        _ => Ok(ProgramAccountState::Foreign),
+    }
+}
+
+async fn buffer_state(rpc_client: &RpcClient, buffer: &Pubkey, deployer: &Pubkey, program_len: usize) -> Result<BufferState> {
+    let Some(account) = with_retry(|| rpc_client.get_account_with_commitment(buffer, rpc_client.commitment())).await?
+        .value
+    else {
+        return Ok(BufferState::Missing);
+    };
+    match bincode::deserialize::<UpgradeableLoaderState>(&account.data) {
+        Ok(UpgradeableLoaderState::Buffer { authority_address }) => {
+            let deployer_v2 = SolanaPubkeyV2::new_from_array(deployer.to_bytes());
+            if authority_address != Some(deployer_v2) {
+                Ok(BufferState::WrongAuthority)
+            } else if account.data.len() < UpgradeableLoaderState::size_of_buffer(program_len) {
+                Ok(BufferState::WrongSize)
+            } else {
+                Ok(BufferState::Usable)
+            }
+        }
+        _ => Ok(BufferState::Missing),
+    }
+}
+
+/// Stop a deploy of bytecode already deployed on this network unless `allow`
+///
+/// A new build of a program that is already deployed only gets a pointer to
+/// `upgrade`, since deploying it under a fresh ID may be intended.
+fn check_duplicate(state: &ProjectState, program_data: &[u8], name: Option<&str>, allow: bool) -> Result<()> {
+    let network = get_network_name();
+    let hash = sha256_hex(strip_trailing_zeros(program_data));
+    let on_network: Vec<&DeployedProgram> = state
+        .deployed_programs
+        .iter()
+        .filter(|p| p.cluster.as_deref().is_none_or(|c| c == network))
+        .collect();
+
+    if let Some(existing) = on_network.iter().find(|p| p.bytecode_hash.as_deref() == Some(hash.as_str())) {
+        let upgrade_hint = if existing.immutable {
+            "It is immutable, so it can't be upgraded in place.".to_string()
+        } else {
+            format!("To ship a new build to it, use `shield-deploy upgrade {}`.", existing.program_id)
+        };
+        if allow {
+            print_warning(&format!(
+                "Same binary, already deployed on {network} as {}",
+                existing.program_id
+            ));
+            println!();
+            return Ok(());
+        }
+        anyhow::bail!(
+            "Same binary, already deployed on {network} as {}.\n\
+            Deploying it again creates a second copy under a new program ID and pays the rent twice.\n\
+            {upgrade_hint}\n\
+            Pass --duplicate to deploy another copy anyway.",
+            existing.program_id
+        );
+    }
+
+    let same_name = name.and_then(|name| Some((name, on_network.iter().find(|p| p.name.as_deref() == Some(name))?)));
+    if let Some((name, existing)) = same_name {
+        println!("New binary of {name}, which is deployed on {network} as {}.", existing.program_id);
+        if !existing.immutable {
+            println!("→ To replace that program instead, use `shield-deploy upgrade {}`\n", existing.program_id);
+        }
+    }
+    Ok(())
+}
+
+fn deployed_program(
+    program_id: &Pubkey,
+    deployer: &Keypair,
+    program_data: &[u8],
+    name: Option<String>,
+    max_data_len: usize,
+) -> DeployedProgram {
+    DeployedProgram {
+        program_id: program_id.to_string(),
+        deployed_at: chrono::Utc::now().timestamp(),
+        last_upgraded: None,
+        name,
+        cluster: Some(get_network_name()),
+        authority: Some(deployer.pubkey().to_string()),
+        size: Some(program_data.len()),
+        max_data_len: Some(max_data_len),
+        bytecode_hash: Some(sha256_hex(strip_trailing_zeros(program_data))),
+        ..Default::default()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn record_recovered_deploy(
+    config: &Config,
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    program_id: &Pubkey,
+    program_data: &[u8],
+    name: Option<String>,
+    max_data_len: usize,
+    signature: Option<Signature>,
+    immutable: bool,
+) -> Result<()> {
+    let mut state = config.load_state()?;
+    if !state.deployed_programs.iter().any(|p| p.program_id == program_id.to_string()) {
+        state.deployed_programs.push(deployed_program(program_id, deployer, program_data, name, max_data_len));
+    }
+    if immutable {
+        if let Some(program) = state.program_mut(&program_id.to_string()) {
+            program.authority = None;
+            program.immutable = true;
+        }
+        let mut record = OperationRecord::new(OperationKind::Finalize, &get_network_name());
+        record.program_id = Some(program_id.to_string());
+        record.signatures.extend(signature.map(|s| s.to_string()));
+        record.note = Some("recovered deploy with --final".to_string());
+        state.operations.push(record);
+    }
+    let mut record = OperationRecord::new(OperationKind::Deploy, &get_network_name());
+    record.program_id = Some(program_id.to_string());
+    record.buffer = state.latest_buffer(&program_id.to_string());
+    record.buffer_signatures = take_buffer_signatures();
+    record.signatures.extend(signature.map(|s| s.to_string()));
+    record.note = Some("recovered".to_string());
+    state.operations.push(record);
+    if let Ok(balance) = balance_of(rpc_client, &deployer.pubkey()).await {
+        state.last_balance = balance;
+    }
+    config.save_state(&state)
+}
+
+/// Finish a deploy that stopped after creating its buffer or program account
+///
+/// With `immutable` the upgrade authority is dropped in the deploy
+/// transaction, as a fresh `deploy --final` does. Returns the program ID and
+/// whether it was made immutable here.
+async fn recover_deploy(
+    config: &Config,
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    program_id_str: &str,
+    program_data: &[u8],
+    program_name: Option<String>,
+    immutable: bool,
+) -> Result<(Pubkey, bool)> {
+    let program_id: Pubkey = program_id_str
+        .parse()
+        .with_context(|| format!("Invalid program ID: {program_id_str}"))?;
     
-    println!("\n Creating program buffer...");
+    println!("\n🔍 Inspecting {program_id}...");
     
-    let buffer_keypair = Keypair::new();
-    let buffer_pubkey = buffer_keypair.pubkey();
+    let program_keypair = config
+        .load_pending_program_keys()?
+        .into_iter()
+        .find(|k| k.pubkey() == program_id);
+    let program = program_account_state(rpc_client, &program_id).await?;
     
-    // Calculate required size for buffer
-    let buffer_size = UpgradeableLoaderState::size_of_buffer(program_data.len());
-    let buffer_lamports = rpc_client
-        .get_minimum_balance_for_rent_exemption(buffer_size)
-        .context("Failed to get rent exemption for buffer")?;
+    // Most recent open buffer recorded for this program
+    let state = config.load_state()?;
+    let max_data_len = state.pending_deploy
+        .as_ref()
+        .filter(|p| p.program_id == program_id_str)
+        .and_then(|p| p.max_data_len)
+        .unwrap_or_else(|| default_max_data_len(program_data.len()));
+    let recorded_buffer = state.buffers
+        .iter()
+        .rev()
+        .find(|b| {
+            b.program_id.as_deref() == Some(program_id_str)
+                && matches!(b.status, BufferStatus::Writing | BufferStatus::Ready)
+        })
+        .map(|b| Ok::<_, anyhow::Error>((Pubkey::from_str(&b.address)?, b.artifact_hash.clone())))
+        .transpose()?;
+    let buffer = match &recorded_buffer {
+        Some((address, hash)) => match buffer_state(rpc_client, address, &deployer.pubkey(), program_data.len()).await? {
+            BufferState::Usable if *hash != sha256_hex(program_data) => BufferState::DifferentArtifact,
+            other => other,
+        },
+        None => BufferState::Missing,
+    };
+    let recorded_buffer = recorded_buffer.map(|(address, _)| address);
     
-    let deployer_addr = Address::from(deployer_pubkey.to_bytes());
-    let buffer_addr = Address::from(buffer_pubkey.to_bytes());
-    let loader_addr = Address::from(LOADER_ID.to_bytes());
-
-    // Create buffer account
-    let create_buffer_ix = system_instruction::create_account(
-        &deployer_addr,
-        &buffer_addr,
-        buffer_lamports,
-        buffer_size as u64,
-        &loader_addr,
-    );
+    println!("  ↳ Program account: {program:?}");
+    match &recorded_buffer {
+        Some(address) => println!("  ↳ Buffer {address}: {buffer:?}"),
+        None => println!("  ↳ Buffer: none recorded"),
+    }
+    println!("  ↳ Program keypair: {}", if program_keypair.is_some() { "found" } else { "missing" });
     
-    let sdk_instruction = SdkInstruction {
-        program_id: Pubkey::from(create_buffer_ix.program_id.to_bytes()),
-        accounts: create_buffer_ix
-            .accounts
-            .iter()
-            .map(|acc| AccountMeta {
-                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
-                is_signer: acc.is_signer,
-                is_writable: acc.is_writable,
-            })
-            .collect(),
-        data: create_buffer_ix.data,
+    let plan = plan_recovery(program, buffer, program_keypair.is_some());
+    let (reuse_buffer, create_program) = match plan {
+        RecoveryPlan::AlreadyDeployed => {
+            println!("\n  ✓ The deploy already landed");
+            record_recovered_deploy(
+                config,
+                rpc_client,
+                deployer,
+                &program_id,
+                program_data,
+                program_name,
+                max_data_len,
+                None,
+                false,
+            )
+            .await?;
+            config.keep_program_key(&program_id)?;
+            if let Some(address) = recorded_buffer {
+                set_buffer_status(config, &address, BufferStatus::Consumed)?;
+            }
+            clear_pending_deploy(config, &program_id)?;
+            print_success("Local state updated");
+            if immutable {
+                print_warning("The deploy landed before --final could be applied here");
+                println!("→ Run `shield-deploy finalize {program_id}` if it is still upgradeable");
+            }
+            return Ok((program_id, false));
+        }
+        RecoveryPlan::Impossible(reason) => {
+            print_warning(&format!("Cannot recover {program_id}: {reason}"));
+            if let Some(address) = recorded_buffer.filter(|_| buffer != BufferState::Missing) {
+                println!("\nThe buffer still holds rent. Reclaim it with:");
+                println!("  solana program close {address} --keypair .shield/deployer.json");
+            }
+            anyhow::bail!("Deploy of {program_id} cannot be recovered");
+        }
+        RecoveryPlan::Deploy { reuse_buffer, create_program } => (reuse_buffer, create_program),
     };
-
-    let recent_blockhash = rpc_client.get_latest_blockhash()?;
-    let mut transaction = Transaction::new_with_payer(
-        &[sdk_instruction],
-        Some(&deployer_pubkey),
-    );
-    transaction.sign(&[deployer, &buffer_keypair], recent_blockhash);
     
-    let signature = rpc_client
-        .send_and_confirm_transaction(&transaction)
-        .context("Failed to create buffer account")?;
+    // Only needed as a signer when the program account is created here
+    let program_signer = program_keypair.as_ref().filter(|_| create_program);
     
-    println!("  ✓ Buffer created: {signature}");
-    println!("  ↳ Buffer address: {buffer_pubkey}");
+    if let Some(address) = recorded_buffer.filter(|_| !reuse_buffer && buffer != BufferState::Missing) {
+        print_warning(&format!("Buffer {address} can't be reused ({buffer:?}); a new one will be written"));
+        println!("→ Reclaim its rent with `solana program close {address} --keypair .shield/deployer.json`");
+    }
     
-    println!("\n Writing program data to buffer...");
+    let fees = FeeSettings::current();
+    let buffer_pubkey = match recorded_buffer.filter(|_| reuse_buffer) {
+        Some(address) => {
+            println!("\n Resuming buffer write...");
+            write_missing_chunks(rpc_client, &fees, deployer, &address, program_data, &CliProgress).await?;
+            address
+        }
+        None => {
+            println!("\n Creating program buffer...");
+            let address = create_deploy_buffer(
+                config,
+                rpc_client,
+                &fees,
+                deployer,
+                &program_id,
+                program_data,
+                max_data_len,
+                &CliProgress,
+            )
+            .await?;
+            println!("\n Writing program data to buffer...");
+            write_program_data_chunked(rpc_client, &fees, deployer, &address, program_data, &CliProgress).await?;
+            address
+        }
+    };
+    set_buffer_status(config, &buffer_pubkey, BufferStatus::Ready)?;
     
-    write_program_data_chunked(
+    println!("\n Deploying program from buffer...");
+    let signature = finish_deploy(
+        config,
         rpc_client,
+        &fees,
         deployer,
+        &program_id,
+        program_signer,
         &buffer_pubkey,
-        program_data,
-        true,
+        program_data.len(),
+        max_data_len,
+        immutable,
+        &CliProgress,
     )
-        .await
-        .context("Failed to write program data")?;
+    .await?;
     
-    println!("\n Deploying program from buffer...");
-   
-    
-    // Calculate program account size
-    let program_data_len = program_data.len();
-    let max_data_len = program_data_len * 3;
-    let programdata_size = UpgradeableLoaderState::size_of_programdata(max_data_len);
-    let programdata_lamports = rpc_client
-        .get_minimum_balance_for_rent_exemption(programdata_size)
-        .context("Failed to get rent exemption for program data")?;
-    
-    // Derive ProgramData address
-    let loader_id_sdk = Pubkey::new_from_array(LOADER_ID.to_bytes());
-    let (programdata_address, _) = Pubkey::find_program_address(
-        &[program_id.as_ref()],
-        &loader_id_sdk,
-    );
-    
-    let deployer_v2 = SolanaPubkeyV2::new_from_array(deployer_pubkey.to_bytes());
-    let programdata_v2 = SolanaPubkeyV2::new_from_array(programdata_address.to_bytes());
-    let buffer_v2 = SolanaPubkeyV2::new_from_array(buffer_pubkey.to_bytes());
-    let program_v2 = SolanaPubkeyV2::new_from_array(program_id.to_bytes());
-
-
-    // Deploy with upgradeable loader
-    let deploy_instructions = bpf_loader_upgradeable::deploy_with_max_program_len(
-        &deployer_v2,
-        &programdata_v2,
-        &buffer_v2,
-        &program_v2,
-        programdata_lamports,
-        program_data_len * 2,
-    )?;
-    
-    // Convert to solana_sdk::Instruction
-    let sdk_instructions: Vec<SdkInstruction> = deploy_instructions
-        .into_iter()
-        .map(|ix| SdkInstruction {
-            program_id: Pubkey::from(ix.program_id.to_bytes()),
-            accounts: ix
-                .accounts
-                .iter()
-                .map(|acc| AccountMeta {
-                    pubkey: Pubkey::from(acc.pubkey.to_bytes()),
-                    is_signer: acc.is_signer,
-                    is_writable: acc.is_writable,
-                })
-                .collect(),
-            data: ix.data,
-        })
-        .collect();
-
-    let recent_blockhash = rpc_client.get_latest_blockhash()?;
-    let mut transaction = Transaction::new_with_payer(
-        &sdk_instructions,
-        Some(&deployer_pubkey),
-    );
-    transaction.sign(&[deployer, program_keypair], recent_blockhash);
-    
-    let signature = rpc_client
-        .send_and_confirm_transaction_with_spinner(&transaction)
-        .context("Failed to deploy program")?;
-    
-    println!("  Program deployed: {signature}");
-    println!("  ↳ ProgramData address: {programdata_address}");
-
-    // Get program name from the current directory or Cargo.toml
-    let lib_name = get_program_lib_name()?;
+    record_recovered_deploy(
+        config,
+        rpc_client,
+        deployer,
+        &program_id,
+        program_data,
+        program_name,
+        max_data_len,
+        Some(signature),
+        immutable,
+    )
+    .await?;
+    config.keep_program_key(&program_id)?;
     
-    // Deploy IDL if available
-    deploy_idl_if_available(&program_id, &lib_name).await?;
+    print_success("Program deployed");
+    println!("\nProgram ID: {program_id}");
+    if immutable {
+        println!("Upgrade authority: None (immutable)");
+    }
+    report_priority_fees();
     
-    Ok(())
+    Ok((program_id, immutable))
 }
 
 
@@ -405,15 +1361,14 @@ async fn write_program_data_to_buffer(
             data: write_ix.data,
         };
         
-        let recent_blockhash = rpc_client.get_latest_blockhash()?;
+        let recent_blockhash = with_retry(|| rpc_client.get_latest_blockhash()).await?;
         let mut transaction = Transaction::new_with_payer(
             &[sdk_instruction],
             Some(&deployer.pubkey()),
         );
         transaction.sign(&[deployer], recent_blockhash);
         
-        rpc_client
-            .send_and_confirm_transaction(&transaction)
+        send_and_confirm(rpc_client, &transaction).await
             .context(format!("Failed to write chunk {} of {}", chunk_index + 1, total_chunks))?;
         
         if (chunk_index + 1) % 10 == 0 || chunk_index + 1 == total_chunks {
@@ -424,4 +1379,72 @@ async fn write_program_data_to_buffer(
     println!("  ✓ All data written successfully");
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::{deploy_instructions, to_sdk_instructions};
+
+    #[test]
+    fn plan_recovery_covers_every_branch() {
+        use BufferState as B;
+        use ProgramAccountState as P;
+
+        let deploy = |reuse_buffer, create_program| RecoveryPlan::Deploy { reuse_buffer, create_program };
+        let cases = [
+            // Landed: nothing to send, whatever the buffer looks like
+            (P::Deployed, B::Missing, false, Some(RecoveryPlan::AlreadyDeployed)),
+            (P::Deployed, B::Usable, true, Some(RecoveryPlan::AlreadyDeployed)),
+            // Someone else's account at the address
+            (P::Foreign, B::Usable, true, None),
+            // Program account created, so its key isn't needed again
+            (P::Uninitialized, B::Usable, false, Some(deploy(true, false))),
+            (P::Uninitialized, B::WrongSize, true, Some(deploy(false, false))),
+            // Program account still to create, which takes its keypair
+            (P::Missing, B::Usable, true, Some(deploy(true, true))),
+            (P::Missing, B::DifferentArtifact, true, Some(deploy(false, true))),
+            (P::Missing, B::WrongAuthority, true, Some(deploy(false, true))),
+            (P::Missing, B::Usable, false, None),
+        ];
+
+        for (program, buffer, have_key, expected) in cases {
+            let plan = plan_recovery(program, buffer, have_key);
+            match expected {
+                Some(expected) => assert_eq!(plan, expected, "{program:?} / {buffer:?} / key {have_key}"),
+                None => assert!(
+                    matches!(plan, RecoveryPlan::Impossible(_)),
+                    "{program:?} / {buffer:?} / key {have_key} gave {plan:?}"
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn resuming_with_final_drops_the_upgrade_authority() {
+        let deployer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let buffer = Pubkey::new_unique();
+        let deployer_v2 = SolanaPubkeyV2::new_from_array(deployer.to_bytes());
+        let programdata_v2 = SolanaPubkeyV2::new_from_array(programdata_address(&program_id).to_bytes());
+        let drop_authority = to_sdk_instructions(vec![bpf_loader_upgradeable::set_upgrade_authority(
+            &programdata_v2,
+            &deployer_v2,
+            None,
+        )])
+        .remove(0);
+
+        // Interrupted before and after the program account was created
+        for program in [ProgramAccountState::Missing, ProgramAccountState::Uninitialized] {
+            let RecoveryPlan::Deploy { create_program, .. } = plan_recovery(program, BufferState::Usable, true) else {
+                panic!("{program:?} should be resumable");
+            };
+            let upgradeable = deploy_instructions(&deployer, &program_id, &buffer, 1, 4096, create_program, false).unwrap();
+            let immutable = deploy_instructions(&deployer, &program_id, &buffer, 1, 4096, create_program, true).unwrap();
+
+            assert_eq!(immutable.len(), upgradeable.len() + 1, "{program:?}");
+            assert_eq!(immutable[..upgradeable.len()], upgradeable[..], "{program:?}");
+            assert_eq!(immutable.last(), Some(&drop_authority), "{program:?}");
+        }
+    }
+}
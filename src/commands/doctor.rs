@@ -0,0 +1,513 @@
+use anyhow::Result;
+use serde::Serialize;
+use solana_cli_config::{Config as SolanaConfig, CONFIG_FILE};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use std::fs;
+use std::path::Path;
+use crate::commands::CliProgress;
+use crate::config::{Config, GitSecrets, ProjectConfig, ProjectState, RotationSeverity, DEPLOYER_KEY_ENV};
+use crate::hygiene::{close_stale_buffers, DEFAULT_BUFFER_MAX_AGE};
+use crate::pool::serves_compression;
+use crate::privacy::{CIRCUIT_DIR, CIRCUIT_FILES};
+use crate::proxy;
+use crate::utils::*;
+
+/// Oldest validator release the loader instructions we use are known to work with
+const MIN_SOLANA_VERSION: (u64, u64) = (1, 18);
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Serialize)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub message: String,
+    pub remediation: Option<String>,
+}
+
+impl CheckResult {
+    fn pass(name: &'static str, message: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Pass, message: message.into(), remediation: None }
+    }
+
+    fn warn(name: &'static str, message: impl Into<String>, remediation: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            message: message.into(),
+            remediation: Some(remediation.into()),
+        }
+    }
+
+    fn fail(name: &'static str, message: impl Into<String>, remediation: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            message: message.into(),
+            remediation: Some(remediation.into()),
+        }
+    }
+}
+
+pub async fn execute(json: bool, fix: bool) -> Result<()> {
+    let config = Config::new()?;
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url.clone(),
+        commitment(),
+    );
+
+    let results = vec![
+        check_solana_config(),
+        check_rpc(&rpc_client, &rpc_url).await,
+        check_proxy().await,
+        check_genesis(&rpc_client, &config).await,
+        check_circuits(Path::new(CIRCUIT_DIR)),
+        check_permissions(&config),
+        check_git(&config),
+        check_deployer(&config),
+        check_rotation(&config),
+        check_state_schema(&config),
+        check_indexer(&rpc_url).await,
+    ];
+
+    let failed = results.iter().any(|r| r.status == CheckStatus::Fail);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        print_header("Shield-Deploy Doctor");
+        println!();
+        for result in &results {
+            let icon = match result.status {
+                CheckStatus::Pass => "✓",
+                CheckStatus::Warn => "⚠️ ",
+                CheckStatus::Fail => "✗",
+            };
+            println!("{icon} {:<18} {}", result.name, result.message);
+            if let Some(remediation) = &result.remediation {
+                println!("  ↳ {remediation}");
+            }
+        }
+    }
+
+    if fix {
+        fix_stale_buffers(&config, &rpc_client).await?;
+    }
+
+    if failed {
+        anyhow::bail!("One or more checks failed");
+    }
+
+    Ok(())
+}
+
+/// `--fix`: close stale buffers even when automatic cleanup isn't configured
+async fn fix_stale_buffers(config: &Config, rpc_client: &RpcClient) -> Result<()> {
+    if !config.deployer_exists() {
+        return Ok(());
+    }
+    let max_age = ProjectConfig::load()?
+        .auto_close_buffers_after()
+        .unwrap_or(DEFAULT_BUFFER_MAX_AGE);
+    let deployer = config.load_deployer()?;
+    close_stale_buffers(config, rpc_client, &deployer, max_age, &CliProgress).await?;
+    Ok(())
+}
+
+pub fn check_solana_config() -> CheckResult {
+    const NAME: &str = "solana-config";
+
+    let Some(config_file) = CONFIG_FILE.as_ref() else {
+        return CheckResult::warn(
+            NAME,
+            "Unable to determine Solana config file path",
+            "Set HOME or run `solana config set --url devnet`",
+        );
+    };
+
+    match SolanaConfig::load(config_file) {
+        Ok(config) => CheckResult::pass(NAME, format!("{} ({})", config_file, config.json_rpc_url)),
+        Err(e) => CheckResult::warn(
+            NAME,
+            format!("Could not read {config_file}: {e}"),
+            "Run `solana config set --url devnet` (devnet is used by default)",
+        ),
+    }
+}
+
+pub async fn check_rpc(rpc_client: &RpcClient, rpc_url: &str) -> CheckResult {
+    let version = rpc_client.get_version().await.map(|version| version.solana_core);
+    rpc_check(rpc_url, version.as_deref().map_err(|e| e.to_string()))
+}
+
+/// Judge the `getVersion` answer (or error) of `rpc_url`
+fn rpc_check(rpc_url: &str, solana_core: Result<&str, String>) -> CheckResult {
+    const NAME: &str = "rpc";
+
+    match solana_core {
+        Ok(solana_core) => {
+            let mut parts = solana_core
+                .split('.')
+                .map(|p| p.parse::<u64>().unwrap_or(0));
+            let major = parts.next().unwrap_or(0);
+            let minor = parts.next().unwrap_or(0);
+
+            if (major, minor) < MIN_SOLANA_VERSION {
+                CheckResult::warn(
+                    NAME,
+                    format!("{rpc_url} runs solana-core {solana_core}"),
+                    format!(
+                        "Use an RPC running {}.{} or newer",
+                        MIN_SOLANA_VERSION.0, MIN_SOLANA_VERSION.1
+                    ),
+                )
+            } else {
+                CheckResult::pass(NAME, format!("{rpc_url} (solana-core {solana_core})"))
+            }
+        }
+        Err(e) => CheckResult::fail(
+            NAME,
+            format!("{rpc_url} unreachable: {e}"),
+            "Check your network connection or switch RPC with `solana config set --url`",
+        ),
+    }
+}
+
+pub async fn check_proxy() -> CheckResult {
+    const NAME: &str = "proxy";
+
+    let Some(proxy) = proxy::proxy_url() else {
+        return CheckResult::pass(NAME, "None (RPC traffic goes direct)");
+    };
+    let shown = proxy::redact(&proxy);
+
+    let proxied = match proxy::exit_ip(false).await {
+        Ok(ip) => ip,
+        Err(e) => {
+            return CheckResult::fail(
+                NAME,
+                format!("{shown} unusable: {e}"),
+                "Start the proxy (e.g. Tor) or unset it with `shield-deploy config unset proxy`",
+            )
+        }
+    };
+
+    match proxy::exit_ip(true).await {
+        Ok(direct) if direct == proxied => CheckResult::warn(
+            NAME,
+            format!("{shown} in effect, but the exit IP {proxied} matches the direct path"),
+            "The proxy does not hide your IP; use an anonymizing proxy such as Tor",
+        ),
+        Ok(direct) => CheckResult::pass(NAME, format!("{shown} (exit {proxied}, direct {direct})")),
+        // The direct path being blocked is normal behind a firewall that forces the proxy
+        Err(_) => CheckResult::pass(NAME, format!("{shown} (exit {proxied}, direct path unreachable)")),
+    }
+}
+
+/// The compression indexer `fund` and `pool-stats` read pool activity from
+pub async fn check_indexer(rpc_url: &str) -> CheckResult {
+    const NAME: &str = "indexer";
+
+    let endpoint = match ProjectConfig::load() {
+        Ok(config) => config.indexer_url.unwrap_or_else(|| rpc_url.to_string()),
+        Err(e) => return CheckResult::fail(NAME, format!("Could not read config: {e}"), "Fix .shield/config.toml"),
+    };
+
+    match serves_compression(&endpoint).await {
+        Ok(()) => CheckResult::pass(NAME, format!("{endpoint} serves compression methods")),
+        Err(e) => CheckResult::warn(
+            NAME,
+            format!("{endpoint} does not serve compression methods: {e:#}"),
+            "Pool activity checks are skipped; point `indexer_url` at a Photon indexer with `shield-deploy config set indexer_url <url>`",
+        ),
+    }
+}
+
+pub async fn check_genesis(rpc_client: &RpcClient, config: &Config) -> CheckResult {
+    let network = match config.load_state() {
+        Ok(state) if !state.network.is_empty() => state.network,
+        _ => return CheckResult::pass("network", "No recorded network to compare"),
+    };
+
+    let genesis_hash = rpc_client.get_genesis_hash().await.map(|hash| hash.to_string());
+    genesis_check(&network, genesis_hash.map_err(|e| e.to_string()))
+}
+
+/// Judge the RPC's genesis hash (or the error fetching it) against the recorded `network`
+fn genesis_check(recorded: &str, genesis_hash: Result<String, String>) -> CheckResult {
+    const NAME: &str = "network";
+
+    let genesis_hash = match genesis_hash {
+        Ok(hash) => hash,
+        Err(e) => return CheckResult::warn(
+            NAME,
+            format!("Could not fetch genesis hash: {e}"),
+            "Re-run once the RPC is reachable",
+        ),
+    };
+
+    match network_from_genesis_hash(&genesis_hash) {
+        Some(network) if network == recorded => {
+            CheckResult::pass(NAME, format!("RPC serves {network}, matching state"))
+        }
+        Some(network) => CheckResult::fail(
+            NAME,
+            format!("RPC serves {network} but state was initialized for {recorded}"),
+            format!("Point the Solana CLI at {recorded}, or pass --switch-network to move the project"),
+        ),
+        None if recorded == "localhost" => {
+            CheckResult::pass(NAME, "Local cluster, matching state")
+        }
+        None => CheckResult::warn(
+            NAME,
+            format!("Unrecognized genesis hash {genesis_hash} (state: {recorded})"),
+            "Confirm the RPC points at the cluster you intend to use",
+        ),
+    }
+}
+
+pub fn check_circuits(circuit_dir: &Path) -> CheckResult {
+    const NAME: &str = "circuits";
+
+    for (file, expected) in CIRCUIT_FILES {
+        let path = circuit_dir.join(file);
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(_) => return CheckResult::fail(
+                NAME,
+                format!("{} missing", path.display()),
+                format!("Copy the Privacy Cash circuit files into ./{CIRCUIT_DIR}/"),
+            ),
+        };
+
+        if sha256_hex(&data) != *expected {
+            return CheckResult::fail(
+                NAME,
+                format!("{} checksum mismatch", path.display()),
+                "Replace the file with the official Privacy Cash circuit",
+            );
+        }
+    }
+
+    CheckResult::pass(NAME, format!("{} files present and valid", CIRCUIT_FILES.len()))
+}
+
+#[cfg(unix)]
+pub fn check_permissions(config: &Config) -> CheckResult {
+    use std::os::unix::fs::PermissionsExt;
+
+    const NAME: &str = "permissions";
+
+    if Config::deployer_from_env() {
+        return CheckResult::pass(NAME, format!("Deployer comes from ${DEPLOYER_KEY_ENV}, no key file"));
+    }
+
+    let deployer_path = config.deployer_path();
+    let Ok(metadata) = fs::metadata(&deployer_path) else {
+        return CheckResult::pass(NAME, "No deployer key to check");
+    };
+
+    let mode = metadata.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+        return CheckResult::warn(
+            NAME,
+            format!("{} is accessible by other users ({mode:o})", deployer_path.display()),
+            format!("chmod 600 {}", deployer_path.display()),
+        );
+    }
+
+    CheckResult::pass(NAME, format!("deployer.json mode {mode:o}"))
+}
+
+#[cfg(not(unix))]
+pub fn check_permissions(_config: &Config) -> CheckResult {
+    CheckResult::pass("permissions", "Not checked on this platform")
+}
+
+pub fn check_deployer(config: &Config) -> CheckResult {
+    const NAME: &str = "deployer";
+
+    if !config.deployer_exists() {
+        return CheckResult::warn(NAME, "No deployer found", "Run `shield-deploy init`");
+    }
+
+    if Config::deployer_from_env() {
+        return match config.load_deployer() {
+            Ok(_) => CheckResult::pass(NAME, format!("${DEPLOYER_KEY_ENV} parses")),
+            Err(e) => CheckResult::fail(
+                NAME,
+                format!("{e}"),
+                format!("Set {DEPLOYER_KEY_ENV} to a base58 secret key or a JSON byte array"),
+            ),
+        };
+    }
+
+    match config.load_deployer() {
+        Ok(_) => CheckResult::pass(NAME, "deployer.json parses"),
+        Err(e) => CheckResult::fail(
+            NAME,
+            format!("deployer.json is unreadable: {e}"),
+            "Restore the deployer key from a backup",
+        ),
+    }
+}
+
+pub fn check_rotation(config: &Config) -> CheckResult {
+    const NAME: &str = "rotation";
+
+    if !config.deployer_exists() {
+        return CheckResult::pass(NAME, "No deployer to rotate");
+    }
+
+    match config.rotation_status() {
+        Ok(None) => CheckResult::pass(NAME, "No rotation policy configured"),
+        Ok(Some(status)) => match status.severity() {
+            RotationSeverity::Ok => CheckResult::pass(NAME, status.summary()),
+            RotationSeverity::DueSoon => CheckResult::warn(
+                NAME,
+                format!("Rotation due soon: {}", status.summary()),
+                "Plan a `shield-deploy rotate`",
+            ),
+            RotationSeverity::Overdue | RotationSeverity::LongOverdue => CheckResult::fail(
+                NAME,
+                format!("Rotation overdue: {}", status.summary()),
+                "Run `shield-deploy rotate`",
+            ),
+        },
+        Err(e) => CheckResult::warn(NAME, format!("Could not read rotation policy: {e}"), "Run `shield-deploy config list`"),
+    }
+}
+
+pub fn check_state_schema(config: &Config) -> CheckResult {
+    const NAME: &str = "state";
+
+    let Ok(json) = fs::read_to_string(config.state_path()) else {
+        return CheckResult::pass(NAME, "No state file yet");
+    };
+
+    let raw: serde_json::Value = match serde_json::from_str(&json) {
+        Ok(value) => value,
+        Err(e) => return CheckResult::fail(
+            NAME,
+            format!("state.json is not valid JSON: {e}"),
+            "Restore state.json from a backup",
+        ),
+    };
+
+    let state: ProjectState = match serde_json::from_value(raw.clone()) {
+        Ok(state) => state,
+        Err(e) => return CheckResult::fail(
+            NAME,
+            format!("state.json does not match the expected schema: {e}"),
+            "Restore state.json from a backup",
+        ),
+    };
+
+    // Fields added since the file was written are filled with defaults on load
+    let current = serde_json::to_value(&state).unwrap_or_default();
+    let missing: Vec<&String> = current
+        .as_object()
+        .map(|fields| fields.keys().filter(|k| raw.get(k.as_str()).is_none()).collect())
+        .unwrap_or_default();
+
+    if missing.is_empty() {
+        CheckResult::pass(NAME, "state.json schema is current")
+    } else {
+        CheckResult::warn(
+            NAME,
+            format!("state.json predates fields: {}", missing.iter().map(|k| k.as_str()).collect::<Vec<_>>().join(", ")),
+            "It will be upgraded automatically on the next write",
+        )
+    }
+}
+
+pub fn check_git(config: &Config) -> CheckResult {
+    const NAME: &str = "git";
+
+    match config.tracked_secrets() {
+        GitSecrets::NotARepo => CheckResult::pass(NAME, "Not a git repository"),
+        GitSecrets::GitUnavailable => CheckResult::warn(
+            NAME,
+            "git not found; could not check for committed keys",
+            "Install git or confirm .shield/ was never committed",
+        ),
+        GitSecrets::Clean => CheckResult::pass(NAME, "No key files tracked by git"),
+        GitSecrets::Tracked(files) => CheckResult::fail(
+            NAME,
+            format!("Key files tracked by git: {}", files.join(", ")),
+            "git rm -r --cached .shield, commit, then run `shield-deploy rotate`",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEVNET_GENESIS: &str = "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG";
+
+    fn scratch_config() -> (tempfile::TempDir, Config) {
+        let root = tempfile::tempdir().unwrap();
+        let config = Config::at(root.path().join(".shield"));
+        fs::create_dir(config.shield_dir()).unwrap();
+        (root, config)
+    }
+
+    #[test]
+    fn rpc_version_outcomes() {
+        let url = "http://rpc";
+        assert_eq!(rpc_check(url, Ok("2.1.14")).status, CheckStatus::Pass);
+        assert_eq!(rpc_check(url, Ok("1.18.0")).status, CheckStatus::Pass);
+        assert_eq!(rpc_check(url, Ok("1.17.31")).status, CheckStatus::Warn);
+        assert_eq!(rpc_check(url, Err("connection refused".to_string())).status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn genesis_outcomes() {
+        let genesis = |hash: &str| Ok(hash.to_string());
+        assert_eq!(genesis_check("devnet", genesis(DEVNET_GENESIS)).status, CheckStatus::Pass);
+        assert_eq!(genesis_check("localhost", genesis("LocalGenesis1111")).status, CheckStatus::Pass);
+        assert_eq!(genesis_check("mainnet-beta", genesis(DEVNET_GENESIS)).status, CheckStatus::Fail);
+        assert_eq!(genesis_check("devnet", genesis("LocalGenesis1111")).status, CheckStatus::Warn);
+        assert_eq!(genesis_check("devnet", Err("timeout".to_string())).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn circuit_outcomes() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(check_circuits(dir.path()).status, CheckStatus::Fail);
+
+        for (file, _) in CIRCUIT_FILES {
+            fs::write(dir.path().join(file), b"not a circuit").unwrap();
+        }
+        let result = check_circuits(dir.path());
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.message.contains("checksum mismatch"));
+    }
+
+    #[test]
+    fn state_schema_outcomes() {
+        let (_root, config) = scratch_config();
+        assert_eq!(check_state_schema(&config).status, CheckStatus::Pass);
+
+        fs::write(config.state_path(), serde_json::to_string(&ProjectState::default()).unwrap()).unwrap();
+        assert_eq!(check_state_schema(&config).status, CheckStatus::Pass);
+
+        // Written before operations, buffers and backups were recorded
+        fs::write(config.state_path(), r#"{"network":"devnet","deployed_programs":[],"last_balance":0}"#).unwrap();
+        assert_eq!(check_state_schema(&config).status, CheckStatus::Warn);
+
+        fs::write(config.state_path(), "{}").unwrap();
+        assert_eq!(check_state_schema(&config).status, CheckStatus::Fail);
+
+        fs::write(config.state_path(), "{ not json").unwrap();
+        assert_eq!(check_state_schema(&config).status, CheckStatus::Fail);
+    }
+}
@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signer::Signer,
+};
+use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use crate::compute::{estimate_priority_fee, set_priority_fee, PriorityFee};
+use crate::config::Config;
+use crate::cost::{default_max_data_len, estimate_deploy, estimate_upgrade, CostEstimate};
+use crate::utils::*;
+
+pub async fn execute(
+    program_path: Option<String>,
+    program_id: Option<String>,
+    max_len: Option<usize>,
+    priority_fee: Option<PriorityFee>,
+    json: bool,
+) -> Result<()> {
+    if let Some(fee) = priority_fee {
+        set_priority_fee(fee);
+    }
+
+    let program_file = if let Some(path) = program_path {
+        PathBuf::from(path)
+    } else {
+        detect_program_file()?
+    };
+
+    let program_len = fs::metadata(&program_file)
+        .with_context(|| format!("Program file not found: {}", program_file.display()))?
+        .len() as usize;
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    // Sample recent fees for the accounts the real transactions will write
+    let mut fee_accounts: Vec<Pubkey> = Config::new()
+        .ok()
+        .filter(|config| config.deployer_exists())
+        .and_then(|config| config.load_deployer().ok())
+        .map(|deployer| deployer.pubkey())
+        .into_iter()
+        .collect();
+
+    let (operation, estimate) = match &program_id {
+        Some(program_id) => {
+            let program_id = Pubkey::from_str(program_id)
+                .context("Invalid program ID")?;
+            let loader = Pubkey::new_from_array(LOADER_ID.to_bytes());
+            fee_accounts.push(Pubkey::find_program_address(&[program_id.as_ref()], &loader).0);
+            let priority_fee = estimate_priority_fee(&rpc_client, &fee_accounts).await;
+            ("upgrade", estimate_upgrade(&rpc_client, &program_id, program_len, priority_fee).await?)
+        }
+        None => {
+            let max_data_len = max_len.unwrap_or_else(|| default_max_data_len(program_len));
+            if max_data_len < program_len {
+                anyhow::bail!(
+                    "--max-len ({max_data_len}) is smaller than the program ({program_len} bytes)"
+                );
+            }
+            let priority_fee = estimate_priority_fee(&rpc_client, &fee_accounts).await;
+            ("deploy", estimate_deploy(&rpc_client, program_len, max_data_len, priority_fee).await?)
+        }
+    };
+
+    if json {
+        let mut value = serde_json::to_value(&estimate)?;
+        value["operation"] = operation.into();
+        value["required_balance"] = estimate.required_balance().into();
+        value["net_cost"] = estimate.net_cost().into();
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+
+    print_header(if operation == "deploy" { "Deploy Cost Estimate" } else { "Upgrade Cost Estimate" });
+
+    println!();
+    println!("Artifact:          {} ({program_len} bytes)", program_file.display());
+    if let Some(program_id) = &program_id {
+        println!("Program ID:        {program_id}");
+    }
+    println!();
+    print_breakdown(&estimate);
+
+    Ok(())
+}
+
+/// Per-component cost breakdown shared by `estimate` and pre-spend summaries
+pub fn print_breakdown(estimate: &CostEstimate) {
+    println!("Buffer rent:       {} (returned after the write)", format_sol(estimate.buffer_rent));
+    if estimate.program_rent > 0 {
+        println!("Program rent:      {}", format_sol(estimate.program_rent));
+    }
+    if estimate.programdata_rent > 0 {
+        println!(
+            "ProgramData rent:  {} (max len {} bytes)",
+            format_sol(estimate.programdata_rent),
+            estimate.max_data_len
+        );
+    }
+    if estimate.extend_rent > 0 {
+        println!("Extend rent:       {}", format_sol(estimate.extend_rent));
+    }
+    println!(
+        "Write fees:        {} ({} chunks)",
+        format_sol(estimate.write_fees),
+        estimate.chunk_count
+    );
+    println!("Transaction fees:  {}", format_sol(estimate.transaction_fees));
+    if estimate.priority_fees > 0 {
+        println!("Priority fees:     {}", format_sol(estimate.priority_fees));
+    }
+    if estimate.margin > 0 {
+        println!("Safety margin:     {} (resent writes, fee drift)", format_sol(estimate.margin));
+    }
+    println!();
+    println!("Required balance:  {}", format_sol(estimate.required_balance()));
+    println!("Net cost:          {}", format_sol(estimate.net_cost()));
+}
@@ -0,0 +1,70 @@
+use anyhow::{Context, Result};
+use solana_sdk::signer::Signer;
+use std::fs;
+use std::path::PathBuf;
+use crate::bundle::{encode_bundle, DeployerBundle};
+use crate::config::{write_secret_file, Config, DeployerKeypair};
+use crate::utils::*;
+
+pub async fn execute(out: PathBuf) -> Result<()> {
+    print_header("Export Deployer");
+
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    if out.exists() && !prompt_confirmation(&format!("{} exists. Overwrite?", out.display()))? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let deployer = config.load_deployer()?;
+
+    let state = if config.state_path().exists() {
+        let json = fs::read_to_string(config.state_path())
+            .context("Failed to read state")?;
+        Some(serde_json::from_str(&json)?)
+    } else {
+        None
+    };
+
+    let project_config = fs::read_to_string(config.config_path()).ok();
+
+    let bundle = DeployerBundle {
+        created_at: chrono::Utc::now().timestamp(),
+        deployer: DeployerKeypair {
+            keypair: deployer.to_bytes().to_vec(),
+        },
+        state,
+        config: project_config,
+    };
+
+    println!("\nThe bundle is encrypted with a passphrase.");
+    println!("Without it the bundle cannot be restored.\n");
+
+    let passphrase = prompt_passphrase("Passphrase", true)?;
+    if passphrase.is_empty() {
+        anyhow::bail!("Passphrase cannot be empty");
+    }
+
+    let data = encode_bundle(&bundle, &passphrase)?;
+
+    write_secret_file(&out, data).context("Failed to write bundle")?;
+    config.record_backup()?;
+
+    print_success("Deployer exported");
+
+    println!("\nBundle:    {}", out.display());
+    println!("Deployer:  {}", deployer.pubkey());
+
+    println!("\n⚠️  Important:");
+    println!("  • Store the bundle and passphrase separately");
+    println!("  • Anyone with both can upgrade your programs");
+
+    Ok(())
+}
@@ -1,26 +1,26 @@
 use anyhow::{Context, Result};
-use solana_client::rpc_client::RpcClient;
-use solana_loader_v3_interface::{
-    instruction as bpf_loader_upgradeable,
-    state::UpgradeableLoaderState,
-};
-use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
-use solana_sdk::{
-    pubkey::Pubkey,
-    signature::Signer,
-    transaction::Transaction,
-    instruction::Instruction as SdkInstruction,
-    instruction::AccountMeta,
-    commitment_config::CommitmentConfig
-};
-use solana_pubkey::Pubkey as SolanaPubkeyV2;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
-use crate::config::Config;
+use std::time::Duration;
+use crate::commands::backup::offer_backup;
+use crate::commands::CliProgress;
+use crate::compute::FeeSettings;
+use crate::config::{Config, OperationKind, OperationRecord, ProjectConfig};
+use crate::loader::{finalize_program, last_deployed_at};
+use crate::network::confirm_mainnet;
 use crate::utils::*;
 
-pub async fn execute(program_id_str: String) -> Result<()> {
+pub async fn execute(program_id_str: String, override_min_age: bool, force_finalize: bool) -> Result<()> {
     print_header("Finalize Program (Make Immutable)");
     
+    if assume_yes() && !force_finalize {
+        anyhow::bail!(
+            "Finalizing is irreversible, so --yes alone won't confirm it.\n\
+            Pass --force-finalize as well to finalize without prompts."
+        );
+    }
+    
     let config = Config::new()?;
     
     if !config.deployer_exists() {
@@ -37,9 +37,7 @@ pub async fn execute(program_id_str: String) -> Result<()> {
         .context("Invalid program ID")?;
     
     // Check if this is one of our deployed programs
-    let program_info = state.deployed_programs
-        .iter()
-        .find(|p| p.program_id == program_id_str);
+    let program_info = state.program(&program_id_str);
     
     if program_info.is_none() {
         print_warning("This program was not deployed by Shield-Deploy");
@@ -47,6 +45,73 @@ pub async fn execute(program_id_str: String) -> Result<()> {
         println!();
     }
     
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url.clone(),
+        authority_commitment(),
+    );
+    
+    let settings = ProjectConfig::load()?;
+    if settings.finalize_min_age().is_some() {
+        let local = program_info.map(|p| p.last_upgraded.unwrap_or(p.deployed_at));
+        check_min_age(&rpc_client, &settings, &program_id, local, override_min_age).await?;
+    }
+    
+    print_finalize_warning(&program_id);
+    
+    if !prompt_confirmation("I understand this is PERMANENT and IRREVERSIBLE")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    confirm_mainnet("Finalizing", None).await?;
+    
+    offer_backup(&config)?;
+    
+    if !force_finalize {
+        confirm_program_id(&program_id)?;
+    }
+    
+    println!("\n Finalizing program (making immutable)...");
+    println!("  ↳ Confirming at {} commitment", commitment_label(rpc_client.commitment()));
+    
+    let program_authority = config.load_program_authority(&program_id)?;
+    let signature = finalize_program(
+        &rpc_client,
+        &FeeSettings::current(),
+        &deployer,
+        program_authority.as_ref().unwrap_or(&deployer),
+        &program_id,
+        &CliProgress,
+    )
+    .await
+    .context("Failed to finalize program")?;
+    
+    let mut state = config.load_state()?;
+    if let Some(program) = state.program_mut(&program_id_str) {
+        program.authority = None;
+        program.immutable = true;
+    }
+    // An immutable program's dedicated authority is worthless now
+    if program_authority.is_some() {
+        config.remove_program_authority(&program_id)?;
+    }
+    let mut record = OperationRecord::new(OperationKind::Finalize, &get_network_name());
+    record.program_id = Some(program_id.to_string());
+    record.signatures.push(signature.to_string());
+    state.operations.push(record);
+    config.save_state(&state)?;
+    
+    print_success("Program is now IMMUTABLE");
+    
+    println!("\nProgram ID: {program_id}");
+    println!("Upgrade authority: None (immutable)");
+    print_immutable_notes();
+    
+    Ok(())
+}
+
+/// The warning shown before a program is made immutable, by `finalize` and `deploy --final`
+pub fn print_finalize_warning(program_id: &Pubkey) {
     println!("\n⚠️  ⚠️  ⚠️  CRITICAL WARNING ⚠️  ⚠️  ⚠️\n");
     println!("This will make the program PERMANENTLY IMMUTABLE.");
     println!();
@@ -65,46 +130,29 @@ pub async fn execute(program_id_str: String) -> Result<()> {
     println!("  ✓ You understand the consequences");
     println!("  ✓ There are NO bugs or security issues");
     println!();
-    
-    if !prompt_confirmation("I understand this is PERMANENT and IRREVERSIBLE")? {
-        println!("Cancelled.");
-        return Ok(());
-    }
-    
+}
+
+/// Make the user type the program ID before it is made immutable
+pub fn confirm_program_id(program_id: &Pubkey) -> Result<()> {
     println!();
     print_warning("FINAL CONFIRMATION");
     println!("Type the program ID to confirm finalization:");
     println!("{program_id}");
     println!();
     
+    ensure_interactive("Program ID")?;
     let confirmation: String = dialoguer::Input::new()
         .with_prompt("Program ID")
         .interact_text()?;
     
-    if confirmation.trim() != program_id_str {
+    if confirmation.trim() != program_id.to_string() {
         anyhow::bail!("Program ID mismatch. Finalization cancelled.");
     }
-    
-    let rpc_url = get_rpc_url()?;
-    let rpc_client = RpcClient::new_with_commitment(
-        rpc_url.clone(),
-        CommitmentConfig::confirmed(),
-    );
-    
-    println!("\n Finalizing program (making immutable)...");
-    
-    finalize_program(
-        &rpc_client,
-        &deployer,
-        &program_id,
-    )
-    .await
-    .context("Failed to finalize program")?;
-    
-    print_success("Program is now IMMUTABLE");
-    
-    println!("\nProgram ID: {program_id}");
-    println!("Upgrade authority: None (immutable)");
+    Ok(())
+}
+
+/// What an immutable program means from here on
+pub fn print_immutable_notes() {
     println!();
     println!("⚠️  Important:");
     println!("  • This program can NEVER be upgraded");
@@ -113,140 +161,51 @@ pub async fn execute(program_id_str: String) -> Result<()> {
     println!("  • Keep the source code as the only way to verify behavior");
     println!();
     println!("✓ The program is now trustless and verifiable");
-    
-    Ok(())
 }
 
-/// Make a program immutable by setting upgrade authority to None
-/// 
-/// This is IRREVERSIBLE. After this, NO ONE can upgrade the program.
-async fn finalize_program(
+/// Refuse to finalize until `min_age` has passed since the last deploy or upgrade
+///
+/// The on-chain ProgramData slot is authoritative; the local timestamp only
+/// adds to it, so a missing or stale `state.json` cannot shorten the soak.
+async fn check_min_age(
     rpc_client: &RpcClient,
-    current_authority: &solana_sdk::signature::Keypair,
+    settings: &ProjectConfig,
     program_id: &Pubkey,
+    local_timestamp: Option<i64>,
+    override_min_age: bool,
 ) -> Result<()> {
-    // Derive ProgramData address
-    let loader_id_sdk = Pubkey::new_from_array(LOADER_ID.to_bytes());
-    let (programdata_address, _) = Pubkey::find_program_address(
-        &[program_id.as_ref()],
-        &loader_id_sdk,
-    );
-    
-    println!("  ↳ ProgramData: {programdata_address}");
+    let on_chain_timestamp = last_deployed_at(rpc_client, program_id).await;
 
-    // Verify we currently control this program
-    verify_current_authority(rpc_client, &programdata_address, current_authority)
-        .await?;
-    
-    // Create set_upgrade_authority instruction with None
-    // This is THE KEY DIFFERENCE - None instead of Some(pubkey)
-    let programdata_v2 = SolanaPubkeyV2::new_from_array(programdata_address.to_bytes());
-    let current_authority_v2 = SolanaPubkeyV2::new_from_array(current_authority.pubkey().to_bytes());
-
-    let set_authority_ix = bpf_loader_upgradeable::set_upgrade_authority(
-        &programdata_v2,
-        &current_authority_v2,
-        None,
-    );
-
-    let sdk_instruction = SdkInstruction {
-        program_id: Pubkey::from(set_authority_ix.program_id.to_bytes()),
-        accounts: set_authority_ix
-            .accounts
-            .iter()
-            .map(|acc| AccountMeta {
-                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
-                is_signer: acc.is_signer,
-                is_writable: acc.is_writable,
-            })
-            .collect(),
-        data: set_authority_ix.data,
+    let Some(last_change) = on_chain_timestamp.max(local_timestamp) else {
+        anyhow::bail!(
+            "Cannot determine when {program_id} was last deployed or upgraded.\n\
+            finalize_min_age is set, so finalization is refused."
+        );
     };
-    
-    let recent_blockhash = rpc_client.get_latest_blockhash()?;
-    let mut transaction = Transaction::new_with_payer(
-        &[sdk_instruction],
-        Some(&current_authority.pubkey()),
-    );
-    transaction.sign(&[current_authority], recent_blockhash);
-    
-    let signature = rpc_client
-        .send_and_confirm_transaction(&transaction)
-        .context("Failed to finalize program")?;
-    
-    println!("  ✓ Transaction confirmed: {signature}");
-    
-    verify_immutable(rpc_client, &programdata_address).await?;
-    
-    Ok(())
-}
 
-/// Verify we control the program before finalizing
-async fn verify_current_authority(
-    rpc_client: &RpcClient,
-    programdata_address: &Pubkey,
-    expected_authority: &solana_sdk::signature::Keypair,
-) -> Result<()> {
-    let account = rpc_client
-        .get_account(programdata_address)
-        .context("ProgramData account not found")?;
-    
-    let programdata_state: UpgradeableLoaderState = bincode::deserialize(&account.data)
-        .context("Failed to deserialize ProgramData")?;
-    
-    match programdata_state {
-        UpgradeableLoaderState::ProgramData {
-            upgrade_authority_address,
-            slot: _,
-        } => {
-            if let Some(authority) = upgrade_authority_address {
-                let expected_pubkey = expected_authority.pubkey();
-                
-                if authority.to_bytes() == expected_pubkey.to_bytes() {
-                    println!("  ✓ Authority verified: you control this program");
-                    Ok(())
-                } else {
-                    anyhow::bail!(
-                        "Authority mismatch.\n\
-                        Expected: {expected_pubkey}\n\
-                        Found: {authority}\n\
-                        You do not control this program."
-                    )
-                }
-            } else {
-                anyhow::bail!("Program is already immutable")
-            }
-        }
-        _ => anyhow::bail!("Invalid ProgramData account state"),
+    let now = chrono::Utc::now().timestamp();
+    let Some(remaining) = settings.finalize_soak_remaining(last_change, now) else {
+        return Ok(());
+    };
+    let age = Duration::from_secs(now.saturating_sub(last_change).max(0) as u64);
+    let min_age = settings.finalize_min_age().unwrap_or_default();
+    if !override_min_age {
+        anyhow::bail!(
+            "Last changed {} ago; finalize_min_age is {}.\n\
+            Finalization is allowed in {}. Pass --override-min-age for emergencies.",
+            format_duration(age),
+            format_duration(min_age),
+            format_duration(remaining)
+        );
     }
-}
 
-/// Verify the program is now immutable
-async fn verify_immutable(
-    rpc_client: &RpcClient,
-    programdata_address: &Pubkey,
-) -> Result<()> {
-    let account = rpc_client
-        .get_account(programdata_address)
-        .context("ProgramData account not found")?;
-    
-    let programdata_state: UpgradeableLoaderState = bincode::deserialize(&account.data)
-        .context("Failed to deserialize ProgramData")?;
-    
-    match programdata_state {
-        UpgradeableLoaderState::ProgramData {
-            upgrade_authority_address,
-            slot: _,
-        } => {
-            if upgrade_authority_address.is_none() {
-                println!("  ✓ Verified: Program is now immutable");
-                Ok(())
-            } else {
-                anyhow::bail!(
-                    "Finalization failed: authority is still set to {upgrade_authority_address:?}"
-                )
-            }
-        }
-        _ => anyhow::bail!("Invalid ProgramData account state"),
+    print_warning(&format!(
+        "Overriding finalize_min_age: last changed {} ago, {} of the soak period remain",
+        format_duration(age),
+        format_duration(remaining)
+    ));
+    if !prompt_confirmation("Skip the soak period for this emergency?")? {
+        anyhow::bail!("Finalization cancelled.");
     }
-}
\ No newline at end of file
+    Ok(())
+}
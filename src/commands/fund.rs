@@ -1,11 +1,59 @@
 use anyhow::{Context, Result};
+use clap::Subcommand;
+use privacy_cash::SendPrivatelyResult;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signature};
 use solana_sdk::signer::Signer;
-use crate::config::Config;
-use crate::privacy::PrivacyLayer;
+use std::fs;
+use std::time::{Duration, Instant};
+use crate::cost::enforce_spend_limit;
+use crate::config::{
+    Config, FundPlan, FundingMethod, OperationKind, OperationRecord, PlanStatus, ProjectConfig,
+};
+use crate::network::confirm_mainnet;
+use crate::pool::{pool_stats, PoolHealth};
+use crate::privacy::{decompress_fee_estimate, hold_back_amount, vault_balances, PrivacyLayer, PRIVACY_DELAY_SECS};
 use crate::utils::*;
 
-pub async fn execute() -> Result<()> {
+/// Faucets cap single requests; localnet allows more than devnet
+const LOCALNET_AIRDROP_STEP: u64 = 2 * LAMPORTS_PER_SOL;
+const DEVNET_AIRDROP_STEP: u64 = LAMPORTS_PER_SOL;
+const AIRDROP_ATTEMPTS: u32 = 3;
+const AIRDROP_CONFIRM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often a detached worker records that it is still alive
+const WORKER_HEARTBEAT_SECS: i64 = 30;
+
+/// Smallest amount Privacy Cash accepts
+const PRIVACY_CASH_MINIMUM: u64 = 20_000_000;
+
+/// A vault left holding less than this after a partial release is drained instead
+const VAULT_DUST: u64 = 1_000_000;
+
+/// Privacy Cash fee (~0.006 SOL) taken out of what the deployer receives
+const PRIVACY_CASH_FEE_ESTIMATE: u64 = 6_000_000;
+
+/// How long `top_up` waits for the deployer balance to show the funding
+const TOP_UP_SETTLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Subcommand)]
+pub enum FundAction {
+    /// Abort a detached funding plan before it decompresses
+    Cancel {
+        /// Plan ID (its vault public key, shown by `status`)
+        plan: String,
+    },
+}
+
+pub async fn execute(
+    airdrop: bool,
+    perturb: Option<bool>,
+    override_spend_limit: bool,
+    detach: bool,
+    from_compressed: bool,
+    unsafe_ignore_git: bool,
+) -> Result<()> {
     print_header("Fund Private Deployer");
     
     let config = Config::new()?;
@@ -17,15 +65,51 @@ pub async fn execute() -> Result<()> {
         );
     }
     
+    config.check_git_secrets(unsafe_ignore_git)?;
     let deployer = config.load_deployer()?;
+    config.check_rotation_policy()?;
+    
+    if from_compressed {
+        return fund_from_compressed(&config, &deployer, perturb).await;
+    }
+    
+    // Development-only shortcut: localnet has no Privacy Cash deployment
+    let rpc_url = get_rpc_url()?;
+    let network = match network_from_genesis_hash(
+        &RpcClient::new_with_commitment(rpc_url.clone(), commitment()).get_genesis_hash().await?.to_string()
+    ) {
+        Some(network) => network.to_string(),
+        None if get_network_name() == "localhost" => "localhost".to_string(),
+        None => "unknown".to_string(),
+    };
+    
+    let use_airdrop = match network.as_str() {
+        "localhost" => airdrop || prompt_confirmation(
+            "Local validator detected. Airdrop instead (NOT private, development only)?"
+        )?,
+        "devnet" => airdrop,
+        _ if airdrop => anyhow::bail!(
+            "--airdrop is only available on a local validator or devnet (detected: {network})"
+        ),
+        _ => false,
+    };
+    
+    if use_airdrop && detach {
+        anyhow::bail!("--detach only applies to Privacy Cash funding");
+    }
+    
+    if use_airdrop {
+        return fund_by_airdrop(&config, &deployer, &rpc_url, &network).await;
+    }
     
     println!();
-    let amount_sol = prompt_amount("Amount to fund (SOL)")?;
+    let amount_lamports = prompt_amount("Amount to fund (SOL)", false)?.resolve(0);
     
-    if amount_sol < 0.02 {
+    if amount_lamports < PRIVACY_CASH_MINIMUM {
         print_warning(&format!(
             "Privacy Cash requires minimum 0.02 SOL\n\
-            You entered: {amount_sol} SOL"
+            You entered: {}",
+            format_sol(amount_lamports)
         ));
         println!("\n💡 Tip: Use at least 0.02 SOL for ZK-proof privacy");
         println!("   Or use 0.02 SOL as minimum for best privacy\n");
@@ -36,7 +120,7 @@ pub async fn execute() -> Result<()> {
         }
     }
     
-    let amount_lamports = (amount_sol.max(0.02) * LAMPORTS_PER_SOL as f64) as u64;
+    let amount_lamports = amount_lamports.max(PRIVACY_CASH_MINIMUM);
     
     let rounded_lamports = PrivacyLayer::round_amount(amount_lamports);
     let rounded_sol = rounded_lamports as f64 / LAMPORTS_PER_SOL as f64;
@@ -46,6 +130,44 @@ pub async fn execute() -> Result<()> {
         println!("   (Privacy Cash minimum: 0.02 SOL)");
     }
     
+    let network = confirm_mainnet("Funding", Some(rounded_lamports)).await?;
+    enforce_spend_limit(&network, rounded_lamports, override_spend_limit)?;
+    
+    // Matching in/out amounts are a correlation signal when pool volume is low
+    let perturb = perturb
+        .or(ProjectConfig::load()?.perturb_amounts)
+        .unwrap_or(network == "mainnet-beta");
+    
+    // Remainders held back by earlier runs can be released alongside this one
+    let rpc_client = RpcClient::new_with_commitment(rpc_url.clone(), commitment());
+    
+    // Same report as `pool-stats`; an unreachable indexer just skips the check
+    if network == "mainnet-beta" {
+        let endpoint = ProjectConfig::load()?.indexer_url.unwrap_or_else(|| rpc_url.clone());
+        if let Ok(report) = pool_stats(&rpc_client, &endpoint, &[("1h", 3_600)]).await {
+            if report.health != PoolHealth::Healthy {
+                print_warning("Privacy pool activity is low right now; this funding may stand out");
+                println!("→ Check `shield-deploy pool-stats --window 24h` or wait for more activity\n");
+            }
+        }
+    }
+    // Vaults waiting on a detached plan belong to that plan
+    let planned: Vec<String> = config.load_fund_plans()?
+        .into_iter()
+        .filter(|p| p.is_open())
+        .map(|p| p.vault)
+        .collect();
+    let shielded: Vec<(Keypair, u64)> = vault_balances(&rpc_client, config.load_vault_keys()?).await?
+        .into_iter()
+        .filter(|(vault, _)| !planned.contains(&vault.pubkey().to_string()))
+        .collect();
+    let shielded_total: u64 = shielded.iter().map(|(_, balance)| balance).sum();
+    let release_shielded = shielded_total > 0 && prompt_confirmation(&format!(
+        "{} is held shielded in {} vault(s). Release it to the deployer as well?",
+        format_sol(shielded_total),
+        shielded.len()
+    ))?;
+    
     println!();
     let wallet_choice = prompt_funding_wallet()?;
     
@@ -66,7 +188,21 @@ pub async fn execute() -> Result<()> {
     println!("  ✓ Privacy Cash pool breaks connection");
     println!();
     println!("Fees: ~0.006 SOL (Privacy Cash network fee)");
+    let decompress_legs = perturb as usize + if release_shielded { shielded.len() } else { 0 };
+    if decompress_legs > 0 {
+        let per_leg = decompress_fee_estimate(&rpc_client, &deployer.pubkey()).await;
+        println!(
+            "      + up to {} for {decompress_legs} decompress transaction(s), priority fee included",
+            format_sol(per_leg * decompress_legs as u64)
+        );
+    }
     println!();
+    if perturb {
+        println!("Amount perturbation: ON");
+        println!("  • A random 1-5% remainder stays shielded in a project vault");
+        println!("  • The deployer receives a different amount than you deposit");
+        println!();
+    }
     println!("Requirements:");
     println!("  • Circuit files in ./circuit/ directory");
     println!("  • Minimum 0.02 SOL");
@@ -88,23 +224,30 @@ pub async fn execute() -> Result<()> {
     println!("💸 Amount: {rounded_sol} SOL");
     
     // Initialize privacy layer
-    let rpc_url = get_rpc_url()?;
     let privacy = PrivacyLayer::new(&rpc_url);
     
+    if detach {
+        if release_shielded {
+            release_vaults(&config, &privacy, &deployer, shielded).await?;
+        }
+        return detach_funding(&config, &privacy, &funding_keypair, rounded_sol, perturb).await;
+    }
+    
     println!("\n🚀 Starting Privacy Cash transfer...");
     println!("   (This may take 10-30 seconds for ZK proof generation)");
     
     // Execute private transfer via Privacy Cash
-    let result = privacy.fund_burner_private(
-        &funding_keypair,
-        &deployer.pubkey(),
-        rounded_sol,
-    )
-    .await
-    .context("Privacy Cash transfer failed")?;
+    let PrivateFunding { result, received_signature, amount_received, held_back } =
+        fund_private(&config, &privacy, &funding_keypair, &deployer, rounded_sol, perturb).await?;
     
-    // Apply additional privacy delay
-    privacy.apply_privacy_delay().await;
+    if release_shielded {
+        release_vaults(&config, &privacy, &deployer, shielded).await?;
+    }
+    
+    // The perturbed path already waited before decompressing
+    if !perturb {
+        privacy.apply_privacy_delay().await;
+    }
     
     print_success("Privacy Cash funding complete!");
     
@@ -112,11 +255,18 @@ pub async fn execute() -> Result<()> {
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!("Deposit TX:   {}", result.deposit_signature);
     println!("Withdraw TX:  {} (amount hidden!)", result.withdraw_signature);
+    if perturb {
+        println!("Deployer TX:  {received_signature}");
+    }
     println!();
     println!("Amount deposited: {} SOL", 
         result.amount_deposited as f64 / LAMPORTS_PER_SOL as f64);
     println!("Amount received:  {} SOL (hidden on-chain)", 
-        result.amount_received as f64 / LAMPORTS_PER_SOL as f64);
+        amount_received as f64 / LAMPORTS_PER_SOL as f64);
+    if held_back > 0 {
+        println!("Held back:        {} SOL (shielded in a project vault)",
+            held_back as f64 / LAMPORTS_PER_SOL as f64);
+    }
     println!("Privacy Cash fee: {} SOL", 
         result.total_fees as f64 / LAMPORTS_PER_SOL as f64);
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
@@ -130,9 +280,734 @@ pub async fn execute() -> Result<()> {
     println!("  • The funding wallet is no longer needed");
     println!("  • Burner appears as random Privacy Cash user");
     println!("  • Use privacy-focused RPC for extra privacy");
+    if held_back > 0 {
+        println!("  • The deployer's incoming amount differs from your deposit,");
+        println!("    so the two can't be matched by value in a quiet pool");
+        println!("  • The remainder shows in `status` and can be released by a later fund");
+    }
+    
+    println!("\nNext step:");
+    println!("→ Deploy using `shield-deploy deploy`");
+    
+    Ok(())
+}
+
+/// What a Privacy Cash funding delivered to the deployer
+pub struct PrivateFunding {
+    pub result: SendPrivatelyResult,
+    /// Signature of the transfer that reached the deployer
+    pub received_signature: String,
+    pub amount_received: u64,
+    /// Remainder left shielded in a project vault by perturbation
+    pub held_back: u64,
+}
+
+/// Send `amount_sol` from `funding_keypair` to the deployer through Privacy Cash
+///
+/// With `perturb` the funds land in a vault first and the privacy delay is
+/// applied before part of them is released; without it the caller applies
+/// the delay. The receipt is recorded in state either way.
+pub async fn fund_private(
+    config: &Config,
+    privacy: &PrivacyLayer,
+    funding_keypair: &Keypair,
+    deployer: &Keypair,
+    amount_sol: f64,
+    perturb: bool,
+) -> Result<PrivateFunding> {
+    let funding = if perturb {
+        // Land in a vault first so the deployer-side amount can differ
+        let vault = Keypair::new();
+        let vault_path = config.save_vault_key(&vault)?;
+        
+        let result = privacy.compress_sol(funding_keypair, &vault.pubkey(), amount_sol)
+            .await
+            .context("Privacy Cash transfer failed")?;
+        
+        privacy.apply_privacy_delay().await;
+        
+        let held_back = hold_back_amount(result.amount_received);
+        let (signature, sent) = privacy
+            .decompress_sol(&vault, &deployer.pubkey(), Some(result.amount_received - held_back))
+            .await
+            .context(format!(
+                "Failed to decompress funds. They remain shielded in {}",
+                vault_path.display()
+            ))?;
+        
+        PrivateFunding { result, received_signature: signature.to_string(), amount_received: sent, held_back }
+    } else {
+        let result = privacy.fund_burner_private(
+            funding_keypair,
+            &deployer.pubkey(),
+            amount_sol,
+        )
+        .await
+        .context("Privacy Cash transfer failed")?;
+        
+        PrivateFunding {
+            received_signature: result.withdraw_signature.to_string(),
+            amount_received: result.amount_received,
+            result,
+            held_back: 0,
+        }
+    };
+    
+    // Only the burner-side receipt is recorded; the deposit would reveal the funding wallet
+    let mut state = config.load_state()?;
+    let mut record = OperationRecord::new(OperationKind::Fund, &get_network_name());
+    record.amount_lamports = Some(funding.amount_received);
+    record.signatures.push(funding.received_signature.clone());
+    record.funding_method = Some(FundingMethod::PrivacyCash);
+    if funding.held_back > 0 {
+        record.note = Some(format!("held back {} shielded", format_sol(funding.held_back)));
+    }
+    state.operations.push(record);
+    config.save_state(&state)?;
+    
+    Ok(funding)
+}
+
+/// Cover a `shortfall` in the deployer balance through Privacy Cash (`deploy --auto-fund`)
+///
+/// Adds headroom for the Privacy Cash fee and any held-back remainder, then
+/// waits for the deployer balance to reach `required` so the caller can
+/// carry on in the same run. Returns the new balance.
+pub async fn top_up(
+    config: &Config,
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    shortfall: u64,
+    required: u64,
+) -> Result<u64> {
+    let network = get_network_name();
+    let perturb = ProjectConfig::load()?.perturb_amounts.unwrap_or(network == "mainnet-beta");
+    let headroom = if perturb { shortfall / 20 } else { 0 };
+    let amount_lamports = PrivacyLayer::round_amount(
+        (shortfall + headroom + PRIVACY_CASH_FEE_ESTIMATE).max(PRIVACY_CASH_MINIMUM)
+    );
+    
+    println!("
+ Topping up the deployer through Privacy Cash...");
+    println!("  ↳ Short by {}, funding {}", format_sol(shortfall), format_sol(amount_lamports));
+    let network = confirm_mainnet("Funding", Some(amount_lamports)).await?;
+    enforce_spend_limit(&network, amount_lamports, false)?;
+    
+    let funding_keypair = load_funding_keypair(prompt_funding_wallet()?)
+        .context("Failed to load funding wallet")?;
+    println!("  ↳ Funding wallet: {}", funding_keypair.pubkey());
+    
+    let privacy = PrivacyLayer::new(&rpc_client.url());
+    let amount_sol = amount_lamports as f64 / LAMPORTS_PER_SOL as f64;
+    let funding = fund_private(config, &privacy, &funding_keypair, deployer, amount_sol, perturb).await?;
+    if !perturb {
+        privacy.apply_privacy_delay().await;
+    }
+    println!("  ✓ Deployer received {}", format_sol(funding.amount_received));
+    
+    // The RPC may lag behind the withdraw it just confirmed
+    let started = Instant::now();
+    loop {
+        let balance = balance_of(rpc_client, &deployer.pubkey()).await
+            .context("Failed to get deployer balance")?;
+        if balance >= required {
+            println!("  ✓ Deployer balance is now {}\n", format_sol(balance));
+            return Ok(balance);
+        }
+        if started.elapsed() > TOP_UP_SETTLE_TIMEOUT {
+            anyhow::bail!(
+                "The deployer balance is {} after funding, still short of {}.\n\
+                Run `shield-deploy fund` to add the rest, then deploy again.",
+                format_sol(balance),
+                format_sol(required)
+            );
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+/// Fund the deployer from the cluster faucet in faucet-sized steps
+async fn fund_by_airdrop(config: &Config, deployer: &Keypair, rpc_url: &str, network: &str) -> Result<()> {
+    println!();
+    print_warning("Airdrop funding is NOT private and exists only for development");
+    println!("  The faucet transfer is public. Never use this path for a real deployment.\n");
+    
+    let amount_lamports = prompt_amount("Amount to airdrop (SOL)", false)?.resolve(0);
+    let step = if network == "localhost" { LOCALNET_AIRDROP_STEP } else { DEVNET_AIRDROP_STEP };
+    
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url.to_string(),
+        commitment(),
+    );
+    
+    let mut record = OperationRecord::new(OperationKind::Fund, &get_network_name());
+    record.funding_method = Some(FundingMethod::Airdrop);
+    record.note = Some(format!("{network} faucet airdrop (not private)"));
+    
+    let mut received = 0;
+    while received < amount_lamports {
+        let request = step.min(amount_lamports - received);
+        let signature = airdrop_with_retry(&rpc_client, deployer, request).await?;
+        received += request;
+        record.signatures.push(signature.to_string());
+        println!("  ✓ {} ({} of {})", format_sol(request), format_sol(received), format_sol(amount_lamports));
+    }
+    
+    record.amount_lamports = Some(received);
+    let mut state = config.load_state()?;
+    state.operations.push(record);
+    state.last_balance = rpc_client.get_balance(&deployer.pubkey()).await.unwrap_or(state.last_balance);
+    config.save_state(&state)?;
+    
+    print_success(&format!("Airdropped {} to the deployer", format_sol(received)));
+    println!("\nNext step:");
+    println!("→ Deploy using `shield-deploy deploy`");
+    
+    Ok(())
+}
+
+/// Faucets are flaky: retry a request a few times and poll until it confirms
+async fn airdrop_with_retry(rpc_client: &RpcClient, deployer: &Keypair, lamports: u64) -> Result<Signature> {
+    let mut last_error = None;
+    
+    for attempt in 1..=AIRDROP_ATTEMPTS {
+        match rpc_client.request_airdrop(&deployer.pubkey(), lamports).await {
+            Ok(signature) => {
+                let started = Instant::now();
+                while started.elapsed() < AIRDROP_CONFIRM_TIMEOUT {
+                    if rpc_client.confirm_transaction(&signature).await.unwrap_or(false) {
+                        return Ok(signature);
+                    }
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                }
+                last_error = Some(anyhow::anyhow!("airdrop {signature} did not confirm"));
+            }
+            Err(e) => last_error = Some(e.into()),
+        }
+        
+        if attempt < AIRDROP_ATTEMPTS {
+            println!("  ⚠️  Airdrop attempt {attempt} failed, retrying...");
+            tokio::time::sleep(Duration::from_secs(2 * attempt as u64)).await;
+        }
+    }
+    
+    Err(last_error
+        .unwrap_or_else(|| anyhow::anyhow!("airdrop failed"))
+        .context("Faucet airdrop failed. The faucet may be rate limiting; try a smaller amount later"))
+}
+
+/// Shield the funds now and leave the delayed decompression to a background worker
+async fn detach_funding(
+    config: &Config,
+    privacy: &PrivacyLayer,
+    funding_keypair: &Keypair,
+    amount_sol: f64,
+    perturb: bool,
+) -> Result<()> {
+    // Persist the vault key first so a crash can never strand funds
+    let vault = Keypair::new();
+    config.save_vault_key(&vault)?;
+    
+    let result = privacy.compress_sol(funding_keypair, &vault.pubkey(), amount_sol)
+        .await
+        .context("Privacy Cash transfer failed")?;
+    
+    let delay = ProjectConfig::load()?
+        .privacy_delay()
+        .unwrap_or(Duration::from_secs(PRIVACY_DELAY_SECS));
+    let now = chrono::Utc::now().timestamp();
+    let mut plan = FundPlan {
+        vault: vault.pubkey().to_string(),
+        amount_lamports: result.amount_received,
+        perturb,
+        created_at: now,
+        execute_after: now + delay.as_secs() as i64,
+        status: PlanStatus::Scheduled,
+        worker_pid: None,
+        heartbeat: None,
+        signature: None,
+        error: None,
+    };
+    config.save_fund_plan(&plan)?;
+    
+    let due = chrono::DateTime::from_timestamp(plan.execute_after, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    
+    print_success(&format!("{} shielded in vault {}", format_sol(result.amount_received), plan.vault));
+    println!("\nDeposit TX:   {}", result.deposit_signature);
+    println!("Decompresses: after {due} ({} privacy delay)", format_duration(delay));
+    
+    match spawn_worker(config, &plan.vault) {
+        Ok(pid) => {
+            plan.worker_pid = Some(pid);
+            config.save_fund_plan(&plan)?;
+            println!("Worker:       pid {pid}, log in {}", config.logs_dir().display());
+            println!("\n→ Track it with `shield-deploy status`");
+            println!("→ If the worker stops, run `shield-deploy fund --resume` after {due}");
+        }
+        Err(e) => {
+            print_warning(&format!("Could not start a background worker: {e}"));
+            println!("  The funds are safe in the vault.");
+            println!("\n→ Run `shield-deploy fund --resume` after {due}");
+        }
+    }
+    println!("→ Abort with `shield-deploy fund cancel {}`", plan.vault);
+    
+    Ok(())
+}
+
+/// Start `shield-deploy fund --run-plan` in its own process group so it outlives the terminal
+#[cfg(unix)]
+fn spawn_worker(config: &Config, vault: &str) -> Result<u32> {
+    use std::os::unix::process::CommandExt;
+    
+    fs::create_dir_all(config.logs_dir()).context("Failed to create logs directory")?;
+    let log = fs::File::create(config.logs_dir().join(format!("fund-{vault}.log")))
+        .context("Failed to create worker log")?;
+    
+    let child = std::process::Command::new(std::env::current_exe()?)
+        .args(["fund", "--run-plan", vault])
+        .stdin(std::process::Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .process_group(0)
+        .spawn()
+        .context("Failed to spawn worker")?;
+    
+    Ok(child.id())
+}
+
+#[cfg(not(unix))]
+fn spawn_worker(_config: &Config, _vault: &str) -> Result<u32> {
+    anyhow::bail!("background workers are only supported on Unix")
+}
+
+/// Worker body: heartbeat until the plan is due, then decompress
+pub async fn run_plan(vault: &str) -> Result<()> {
+    let config = Config::new()?;
+    let pid = std::process::id();
+    
+    loop {
+        // Checked and saved under the plan lock, so a cancel in between isn't overwritten
+        let now = chrono::Utc::now().timestamp();
+        let beat = config.update_fund_plan(vault, |plan| {
+            if plan.status != PlanStatus::Scheduled {
+                return Ok(Err(plan.status));
+            }
+            plan.worker_pid = Some(pid);
+            plan.heartbeat = Some(now);
+            Ok(Ok(plan.execute_after - now))
+        }).await?;
+        let remaining = match beat {
+            Ok(remaining) => remaining,
+            Err(status) => {
+                println!("Plan {vault} is {status:?}; nothing to do");
+                return Ok(());
+            }
+        };
+        
+        if remaining <= 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_secs(remaining.min(WORKER_HEARTBEAT_SECS) as u64)).await;
+    }
     
+    execute_plan(&config, vault).await
+}
+
+/// Run every due plan that no live worker is handling
+pub async fn resume() -> Result<()> {
+    print_header("Resume Funding Plans");
+    
+    let config = Config::new()?;
+    let plans: Vec<FundPlan> = config.load_fund_plans()?
+        .into_iter()
+        .filter(|p| p.is_open())
+        .collect();
+    
+    if plans.is_empty() {
+        println!("\nNo funding plans to resume.");
+        return Ok(());
+    }
+    
+    let now = chrono::Utc::now().timestamp();
+    let mut failed = 0;
+    for plan in plans {
+        println!("\n• {} ({})", plan.vault, format_sol(plan.amount_lamports));
+        if plan.worker_alive() {
+            println!("  ↳ Worker {} is handling it", plan.worker_pid.unwrap_or_default());
+            continue;
+        }
+        if now < plan.execute_after {
+            println!(
+                "  ↳ Not due for {}; run `shield-deploy fund --resume` again then",
+                format_duration(Duration::from_secs((plan.execute_after - now) as u64))
+            );
+            continue;
+        }
+        if let Err(e) = execute_plan(&config, &plan.vault).await {
+            println!("  ✗ {e:#}");
+            failed += 1;
+        }
+    }
+    
+    if failed > 0 {
+        anyhow::bail!("{failed} funding plan(s) failed. The funds remain shielded in their vaults.");
+    }
+    Ok(())
+}
+
+/// Abort a plan that has not started decompressing; its funds stay shielded
+pub async fn cancel(vault: &str) -> Result<()> {
+    print_header("Cancel Funding Plan");
+    
+    let config = Config::new()?;
+    let plan = config.load_fund_plan(vault)?;
+    
+    match plan.status {
+        PlanStatus::Scheduled | PlanStatus::Failed => {}
+        PlanStatus::Running => anyhow::bail!("Plan {vault} is already decompressing and cannot be cancelled"),
+        PlanStatus::Completed | PlanStatus::Cancelled => {
+            println!("\nPlan {vault} is already {:?}.", plan.status);
+            return Ok(());
+        }
+    }
+    
+    println!("\nVault:   {vault}");
+    println!("Amount:  {}", format_sol(plan.amount_lamports));
+    println!("\nThe funds stay shielded in the vault; a later `shield-deploy fund` can release them.\n");
+    
+    if !prompt_confirmation("Cancel this plan?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    
+    // A live worker notices on its next heartbeat and exits
+    mark_cancelled(&config, vault).await?;
+    
+    print_success(&format!("Plan {vault} cancelled"));
+    Ok(())
+}
+
+/// Set a plan `Cancelled`, unless a worker started on it since it was read
+async fn mark_cancelled(config: &Config, vault: &str) -> Result<()> {
+    config.update_fund_plan(vault, |plan| match plan.status {
+        PlanStatus::Scheduled | PlanStatus::Failed => {
+            plan.status = PlanStatus::Cancelled;
+            Ok(())
+        }
+        status => anyhow::bail!("Plan {vault} is now {status:?} and cannot be cancelled"),
+    }).await
+}
+
+/// Mark a plan `Running` for this process, unless it was cancelled, finished
+/// or claimed by a live worker since it was last read
+///
+/// Returns the claimed plan, or the status that stopped the claim.
+async fn claim_plan(config: &Config, vault_id: &str) -> Result<Result<FundPlan, PlanStatus>> {
+    let pid = std::process::id();
+    config.update_fund_plan(vault_id, |plan| {
+        let taken = plan.status == PlanStatus::Running && plan.worker_alive() && plan.worker_pid != Some(pid);
+        if !plan.is_open() || taken {
+            return Ok(Err(plan.status));
+        }
+        plan.status = PlanStatus::Running;
+        plan.worker_pid = Some(pid);
+        plan.heartbeat = Some(chrono::Utc::now().timestamp());
+        Ok(Ok(plan.clone()))
+    }).await
+}
+
+/// Decompress a plan's vault to the deployer and record the result in the plan and the log
+async fn execute_plan(config: &Config, vault_id: &str) -> Result<()> {
+    let mut plan = match claim_plan(config, vault_id).await? {
+        Ok(plan) => plan,
+        Err(status) => {
+            println!("  ↳ Plan {vault_id} is {status:?}; nothing to do");
+            return Ok(());
+        }
+    };
+    
+    let outcome = decompress_plan(config, &plan).await;
+    match &outcome {
+        Ok(signature) => {
+            plan.status = PlanStatus::Completed;
+            plan.signature = signature.clone();
+            plan.error = None;
+        }
+        Err(e) => {
+            plan.status = PlanStatus::Failed;
+            plan.error = Some(format!("{e:#}"));
+        }
+    }
+    config.save_fund_plan(&plan)?;
+    
+    outcome.map(|_| ()).with_context(|| format!("Plan {vault_id} failed"))
+}
+
+async fn decompress_plan(config: &Config, plan: &FundPlan) -> Result<Option<String>> {
+    let deployer = config.load_deployer()?;
+    let vault = config.load_vault_keys()?
+        .into_iter()
+        .find(|k| k.pubkey().to_string() == plan.vault)
+        .with_context(|| format!("Vault key {} is missing from .shield/vault/", plan.vault))?;
+    
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(rpc_url.clone(), commitment());
+    let balance = rpc_client.get_balance(&vault.pubkey()).await
+        .context("Failed to get vault balance")?;
+    
+    // A worker that died mid-decompress may already have delivered
+    if balance == 0 {
+        config.remove_vault_key(&vault.pubkey())?;
+        println!("  ↳ Vault already empty; nothing left to decompress");
+        return Ok(None);
+    }
+    
+    let held_back = if plan.perturb { hold_back_amount(balance) } else { 0 };
+    let privacy = PrivacyLayer::new(&rpc_url);
+    let (signature, sent) = privacy
+        .decompress_sol(&vault, &deployer.pubkey(), plan.perturb.then(|| balance - held_back))
+        .await
+        .context("Failed to decompress funds. They remain shielded in the vault")?;
+    if held_back == 0 {
+        config.remove_vault_key(&vault.pubkey())?;
+    }
+    
+    let mut record = OperationRecord::new(OperationKind::Fund, &get_network_name());
+    record.amount_lamports = Some(sent);
+    record.signatures.push(signature.to_string());
+    record.funding_method = Some(FundingMethod::PrivacyCash);
+    record.note = Some(if held_back > 0 {
+        format!("detached; held back {} shielded", format_sol(held_back))
+    } else {
+        "detached".to_string()
+    });
+    let balance = rpc_client.get_balance(&deployer.pubkey()).await.ok();
+    
+    // Loaded last so the window for a foreground save to race is short;
+    // `save_state` keeps what either side recorded
+    let mut state = config.load_state()?;
+    state.operations.push(record);
+    state.last_balance = balance.unwrap_or(state.last_balance);
+    config.save_state(&state)?;
+    
+    print_success(&format!("Delivered {} to the deployer", format_sol(sent)));
+    Ok(Some(signature.to_string()))
+}
+
+/// Decompress balances earlier runs left shielded, without a new deposit
+async fn fund_from_compressed(config: &Config, deployer: &Keypair, perturb: Option<bool>) -> Result<()> {
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(rpc_url.clone(), commitment());
+    
+    // Vaults a detached plan will decompress are already spoken for
+    let planned: Vec<String> = config.load_fund_plans()?
+        .into_iter()
+        .filter(|p| p.is_open())
+        .map(|p| p.vault)
+        .collect();
+    let (scheduled, mut available): (Vec<_>, Vec<_>) = vault_balances(&rpc_client, config.load_vault_keys()?).await?
+        .into_iter()
+        .partition(|(vault, _)| planned.contains(&vault.pubkey().to_string()));
+    
+    if !scheduled.is_empty() {
+        println!(
+            "\n{} in {} vault(s) is already scheduled by `fund --detach` and is left to that plan.",
+            format_sol(scheduled.iter().map(|(_, balance)| balance).sum()),
+            scheduled.len()
+        );
+    }
+    if available.is_empty() {
+        anyhow::bail!(
+            "No shielded balance to decompress.\n\
+            Run `shield-deploy fund` to deposit new funds."
+        );
+    }
+    
+    // Largest first, so partial releases touch as few vaults as possible
+    available.sort_by(|a, b| b.1.cmp(&a.1));
+    let total: u64 = available.iter().map(|(_, balance)| balance).sum();
+    
+    println!("\nShielded balance:");
+    for (vault, balance) in &available {
+        println!("  • {}  {}", vault.pubkey(), format_sol(*balance));
+    }
+    println!("  Total: {}\n", format_sol(total));
+    
+    let requested = prompt_amount("Amount to decompress (SOL, or `all`)", true)?;
+    let perturb = perturb
+        .or(ProjectConfig::load()?.perturb_amounts)
+        .unwrap_or(get_network_name() == "mainnet-beta");
+    let amount = match requested {
+        // Same rule as a fresh funding: the deployer never receives a round, matchable total
+        Amount::Max if perturb => total - hold_back_amount(total).min(total),
+        other => other.resolve(total),
+    };
+    if amount > total {
+        anyhow::bail!("Only {} is shielded", format_sol(total));
+    }
+    
+    println!("\nDecompress {} to the deployer {}", format_sol(amount), deployer.pubkey());
+    if !prompt_confirmation("Proceed?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    
+    // The vaults may have been shielded long ago; only wait out what's left of the delay
+    let delay = ProjectConfig::load()?
+        .privacy_delay()
+        .unwrap_or(Duration::from_secs(PRIVACY_DELAY_SECS));
+    let youngest = available
+        .iter()
+        .filter_map(|(vault, _)| {
+            fs::metadata(config.vault_dir().join(vault.pubkey().to_string()).with_extension("json"))
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+        })
+        .min()
+        .unwrap_or_default();
+    if let Some(remaining) = delay.checked_sub(youngest).filter(|d| !d.is_zero()) {
+        println!("\n⏳ Waiting {} more for the privacy delay...", format_duration(remaining));
+        tokio::time::sleep(remaining).await;
+    }
+    
+    let privacy = PrivacyLayer::new(&rpc_url);
+    let mut state = config.load_state()?;
+    let mut remaining = amount;
+    let mut released = 0;
+    
+    for (vault, balance) in available {
+        if remaining == 0 {
+            break;
+        }
+        let drain = remaining + VAULT_DUST >= balance;
+        let (signature, sent) = privacy
+            .decompress_sol(&vault, &deployer.pubkey(), (!drain).then_some(remaining))
+            .await
+            .with_context(|| format!("Failed to decompress vault {}", vault.pubkey()))?;
+        if drain {
+            config.remove_vault_key(&vault.pubkey())?;
+        }
+        remaining = remaining.saturating_sub(sent);
+        released += sent;
+        
+        let mut record = OperationRecord::new(OperationKind::Fund, &get_network_name());
+        record.amount_lamports = Some(sent);
+        record.signatures.push(signature.to_string());
+        record.funding_method = Some(FundingMethod::PrivacyCash);
+        record.note = Some("decompressed from a shielded vault".to_string());
+        state.operations.push(record);
+        config.save_state(&state)?;
+    }
+    
+    print_success(&format!("Decompressed {} to the deployer", format_sol(released)));
+    let left = total.saturating_sub(released);
+    if left > 0 {
+        println!("\n{} stays shielded; see `shield-deploy status`.", format_sol(left));
+    }
     println!("\nNext step:");
     println!("→ Deploy using `shield-deploy deploy`");
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Send the whole balance of each vault to the deployer and forget the emptied keys
+async fn release_vaults(
+    config: &Config,
+    privacy: &PrivacyLayer,
+    deployer: &Keypair,
+    vaults: Vec<(Keypair, u64)>,
+) -> Result<()> {
+    let mut state = config.load_state()?;
+    
+    for (vault, _) in vaults {
+        let (signature, sent) = privacy.decompress_sol(&vault, &deployer.pubkey(), None).await?;
+        config.remove_vault_key(&vault.pubkey())?;
+        
+        let mut record = OperationRecord::new(OperationKind::Fund, &get_network_name());
+        record.amount_lamports = Some(sent);
+        record.signatures.push(signature.to_string());
+        record.funding_method = Some(FundingMethod::PrivacyCash);
+        record.note = Some("released shielded vault balance".to_string());
+        state.operations.push(record);
+    }
+    
+    config.save_state(&state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduled_plan(config: &Config) -> FundPlan {
+        let plan = FundPlan {
+            vault: Keypair::new().pubkey().to_string(),
+            amount_lamports: 100_000_000,
+            perturb: false,
+            created_at: 0,
+            execute_after: 0,
+            status: PlanStatus::Scheduled,
+            worker_pid: None,
+            heartbeat: None,
+            signature: None,
+            error: None,
+        };
+        config.save_fund_plan(&plan).unwrap();
+        plan
+    }
+
+    #[tokio::test]
+    async fn cancel_after_the_scheduled_check_stops_the_run() {
+        let root = tempfile::tempdir().unwrap();
+        let config = Config::at(root.path().join(".shield"));
+        let plan = scheduled_plan(&config);
+
+        // The worker saw it scheduled and due, then `fund cancel` landed
+        assert_eq!(config.load_fund_plan(&plan.vault).unwrap().status, PlanStatus::Scheduled);
+        mark_cancelled(&config, &plan.vault).await.unwrap();
+
+        assert_eq!(claim_plan(&config, &plan.vault).await.unwrap().err(), Some(PlanStatus::Cancelled));
+        let saved = config.load_fund_plan(&plan.vault).unwrap();
+        assert_eq!(saved.status, PlanStatus::Cancelled);
+        assert_eq!(saved.worker_pid, None);
+    }
+
+    #[tokio::test]
+    async fn cancel_refuses_a_claimed_plan() {
+        let root = tempfile::tempdir().unwrap();
+        let config = Config::at(root.path().join(".shield"));
+        let plan = scheduled_plan(&config);
+
+        let claimed = claim_plan(&config, &plan.vault).await.unwrap().unwrap();
+        assert_eq!(claimed.status, PlanStatus::Running);
+
+        assert!(mark_cancelled(&config, &plan.vault).await.is_err());
+        assert_eq!(config.load_fund_plan(&plan.vault).unwrap().status, PlanStatus::Running);
+    }
+
+    #[test]
+    fn a_stale_save_keeps_the_workers_record() {
+        let root = tempfile::tempdir().unwrap();
+        let config = Config::at(root.path().join(".shield"));
+        config.save_state(&crate::config::ProjectState::default()).unwrap();
+
+        // An upgrade loads the state, then the worker records its delivery
+        let mut foreground = config.load_state().unwrap();
+        let mut worker = config.load_state().unwrap();
+        worker.operations.push(OperationRecord::new(OperationKind::Fund, "devnet"));
+        worker.last_balance = 5;
+        config.save_state(&worker).unwrap();
+
+        foreground.operations.push(OperationRecord::new(OperationKind::Upgrade, "devnet"));
+        config.save_state(&foreground).unwrap();
+
+        let saved = config.load_state().unwrap();
+        let kinds: Vec<_> = saved.operations.iter().map(|op| op.kind).collect();
+        assert!(kinds.contains(&OperationKind::Fund) && kinds.contains(&OperationKind::Upgrade));
+        assert_eq!(saved.operations.len(), 2);
+        assert_eq!(saved.last_balance, 5);
+    }
+}
@@ -0,0 +1,214 @@
+use anyhow::{Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+use solana_sdk::signature::{write_keypair_file, Keypair, Signer};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::config::Config;
+use crate::utils::*;
+
+/// Characters that can appear in a base58 pubkey
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Longest prefix accepted; each extra character multiplies the work by up to 58
+const MAX_PREFIX_LEN: usize = 6;
+
+/// How often the progress line is refreshed while grinding
+const REPORT_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Keypairs found by [`grind`], with whether Ctrl-C cut the search short
+pub struct GrindResult {
+    pub keypairs: Vec<Keypair>,
+    pub interrupted: bool,
+}
+
+pub async fn execute(prefix: String, count: usize, case_sensitive: bool, out: Option<PathBuf>) -> Result<()> {
+    print_header("Grind Program Keypairs");
+
+    if out.is_some() && count != 1 {
+        anyhow::bail!("--out writes a single keypair; drop --count or --out");
+    }
+    if let Some(path) = out.as_ref().filter(|p| p.exists()) {
+        anyhow::bail!("{} already exists; refusing to overwrite a keypair", path.display());
+    }
+
+    let config = Config::new()?;
+    println!("\nPrefix:   {prefix}{}", if case_sensitive { "" } else { " (any case)" });
+    println!("Count:    {count}");
+    match &out {
+        Some(path) => println!("Saving to {}", path.display()),
+        None => println!("Saving to {}", config.pending_programs_dir().display()),
+    }
+    println!("Press Ctrl-C to stop; nothing is written for an unfinished match.\n");
+
+    let GrindResult { keypairs, interrupted } = grind(&prefix, case_sensitive, count).await?;
+    let found = keypairs.len();
+    for keypair in &keypairs {
+        match &out {
+            Some(path) => {
+                write_keypair_file(keypair, path)
+                    .map_err(|e| anyhow::anyhow!("Failed to write {}: {e}", path.display()))?;
+            }
+            None => {
+                config.save_pending_program_key(keypair)?;
+            }
+        }
+        println!("  ✓ {}", keypair.pubkey());
+    }
+
+    if interrupted {
+        print_warning(&format!("Interrupted; saved {found} of {count} keypairs"));
+    } else {
+        print_success(&format!("Saved {found} keypairs"));
+    }
+
+    match &out {
+        Some(path) => println!("\n→ Deploy with `shield-deploy deploy --program-keypair {}`", path.display()),
+        None => {
+            let pending = config.load_pending_program_keys()?.len();
+            println!("\nPending program keypairs: {pending}");
+            println!("→ `shield-deploy deploy` will offer to use them");
+        }
+    }
+
+    Ok(())
+}
+
+/// Search for `count` keypairs whose pubkey starts with `prefix`, on every core
+///
+/// Shows a live rate and ETA. Ctrl-C stops the search and returns whatever
+/// was found so far; nothing is written to disk here.
+pub async fn grind(prefix: &str, case_sensitive: bool, count: usize) -> Result<GrindResult> {
+    let expected_attempts = expected_attempts(prefix, case_sensitive)?;
+    if count == 0 {
+        anyhow::bail!("Count must be at least 1");
+    }
+
+    let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    println!("  ↳ {threads} threads, ~{expected_attempts:.0} attempts per match");
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let attempts = Arc::new(AtomicU64::new(0));
+    let (sender, receiver) = mpsc::channel::<Keypair>();
+
+    let workers: Vec<_> = (0..threads)
+        .map(|_| {
+            let stop = stop.clone();
+            let attempts = attempts.clone();
+            let sender = sender.clone();
+            let prefix = prefix.to_string();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    // Count in batches to keep the shared counter off the hot path
+                    for _ in 0..1_000 {
+                        let keypair = Keypair::new();
+                        if matches_prefix(&keypair.pubkey().to_string(), &prefix, case_sensitive)
+                            && sender.send(keypair).is_err()
+                        {
+                            return;
+                        }
+                    }
+                    attempts.fetch_add(1_000, Ordering::Relaxed);
+                }
+            })
+        })
+        .collect();
+    drop(sender);
+
+    let progress = ProgressBar::new_spinner();
+    progress.set_style(
+        ProgressStyle::with_template("  {spinner} {msg}").unwrap_or_else(|_| ProgressStyle::default_spinner()),
+    );
+
+    let started = Instant::now();
+    let mut keypairs = Vec::new();
+    let mut ticker = tokio::time::interval(REPORT_INTERVAL);
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    let interrupted = loop {
+        tokio::select! {
+            _ = &mut ctrl_c => break true,
+            _ = ticker.tick() => {}
+        }
+
+        keypairs.extend(receiver.try_iter().take(count - keypairs.len()));
+        if keypairs.len() >= count {
+            break false;
+        }
+
+        let total = attempts.load(Ordering::Relaxed);
+        let rate = total as f64 / started.elapsed().as_secs_f64().max(0.001);
+        let eta = if rate > 0.0 {
+            let remaining = expected_attempts * (count - keypairs.len()) as f64;
+            format_duration(Duration::from_secs_f64(remaining / rate))
+        } else {
+            "unknown".to_string()
+        };
+        progress.set_message(format!(
+            "{}/{count} found · {total} attempts · {rate:.0} keys/s · ETA ~{eta}",
+            keypairs.len()
+        ));
+        progress.tick();
+    };
+    progress.finish_and_clear();
+
+    stop.store(true, Ordering::Relaxed);
+    for worker in workers {
+        let _ = worker.join();
+    }
+
+    // Keep matches that arrived between the last tick and the stop
+    if !interrupted {
+        keypairs.extend(receiver.try_iter().take(count - keypairs.len()));
+    }
+
+    let total = attempts.load(Ordering::Relaxed);
+    let elapsed = started.elapsed();
+    println!(
+        "  ↳ {total} attempts in {:.1}s ({:.0} keys/s)",
+        elapsed.as_secs_f64(),
+        total as f64 / elapsed.as_secs_f64().max(0.001)
+    );
+
+    Ok(GrindResult { keypairs, interrupted })
+}
+
+/// Check `prefix` can occur and return the expected attempts per match
+fn expected_attempts(prefix: &str, case_sensitive: bool) -> Result<f64> {
+    if prefix.is_empty() {
+        anyhow::bail!("Prefix must be non-empty");
+    }
+    if prefix.chars().count() > MAX_PREFIX_LEN {
+        anyhow::bail!(
+            "Prefixes are limited to {MAX_PREFIX_LEN} characters; \"{prefix}\" would take days to grind"
+        );
+    }
+    prefix
+        .chars()
+        .map(|c| {
+            let variants = if case_sensitive {
+                BASE58_ALPHABET.contains(c) as usize
+            } else {
+                BASE58_ALPHABET.chars().filter(|a| a.eq_ignore_ascii_case(&c)).count()
+            };
+            if variants == 0 {
+                anyhow::bail!(
+                    "'{c}' can never appear in a pubkey.\n\
+                    Base58 excludes 0, O, I and l."
+                );
+            }
+            Ok(58.0 / variants as f64)
+        })
+        .product::<Result<f64>>()
+        .context("Invalid prefix")
+}
+
+fn matches_prefix(pubkey: &str, prefix: &str, case_sensitive: bool) -> bool {
+    if case_sensitive {
+        return pubkey.starts_with(prefix);
+    }
+    pubkey.get(..prefix.len()).is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+}
@@ -0,0 +1,236 @@
+use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    signature::Signature,
+};
+use std::str::FromStr;
+use crate::config::{Config, OperationKind, OperationRecord};
+use crate::cost::format_fee;
+use crate::utils::*;
+
+/// Number of signatures checked against the chain by --verify
+const VERIFY_SAMPLE_SIZE: usize = 3;
+
+pub async fn execute(
+    program: Option<String>,
+    since: Option<String>,
+    json: bool,
+    verify: bool,
+) -> Result<()> {
+    let config = Config::new()?;
+    let state = config.load_state()?;
+
+    let since_ts = since
+        .as_deref()
+        .map(|date| {
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .context("Invalid --since date, expected YYYY-MM-DD")
+                .map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().timestamp())
+        })
+        .transpose()?;
+
+    let mut records: Vec<&OperationRecord> = state.operations
+        .iter()
+        .filter(|r| program.is_none() || r.program_id == program)
+        .filter(|r| since_ts.is_none_or(|ts| r.timestamp >= ts))
+        .collect();
+    records.sort_by_key(|r| r.timestamp);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&records)?);
+        return Ok(());
+    }
+
+    print_header("Operation History");
+
+    if state.operations.is_empty() {
+        println!("\nNo operations recorded yet.");
+        println!("State files created by older versions don't include history;");
+        println!("new operations will be recorded from now on.");
+        return Ok(());
+    }
+
+    if records.is_empty() {
+        println!("\nNo operations match the given filters.");
+        return Ok(());
+    }
+
+    println!();
+    for record in &records {
+        let datetime = chrono::DateTime::from_timestamp(record.timestamp, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        print!("{datetime}  {:<18}", kind_label(record.kind));
+        if let Some(program_id) = &record.program_id {
+            print!(" {program_id}");
+        }
+        println!();
+
+        if let Some(amount) = record.amount_lamports {
+            println!("    Amount: {}", format_sol(amount));
+        }
+        if let Some(cost) = record.cost_lamports {
+            println!("    Cost:   {}", format_sol(cost));
+        }
+        if let Some(spend) = &record.spend {
+            println!(
+                "    Fees:   {} over {} transactions (priority {})",
+                format_fee(spend.transaction_fees),
+                spend.transactions,
+                format_fee(spend.priority_fees)
+            );
+            if spend.program_data_rent > 0 {
+                println!("    Rent:   {} in ProgramData", format_sol(spend.program_data_rent));
+            }
+            if spend.buffer_refund > 0 {
+                println!("    Refund: {} buffer rent", format_sol(spend.buffer_refund));
+            }
+        }
+        if let Some(note) = &record.note {
+            println!("    Note:   {note}");
+        }
+        let network = record.cluster.as_deref().unwrap_or(&state.network);
+        if let Some(buffer) = &record.buffer {
+            println!("    Buffer: {buffer}");
+        }
+        if !record.buffer_signatures.is_empty() {
+            println!("    Buffer transactions: {} (creation and writes)", record.buffer_signatures.len());
+            if is_verbose() {
+                for signature in &record.buffer_signatures {
+                    println!("      {}", explorer_tx_url(signature, network));
+                }
+            }
+        }
+        for signature in &record.signatures {
+            println!("    {}", explorer_tx_url(signature, network));
+        }
+    }
+
+    print_cumulative_spend(&records);
+
+    if verify {
+        verify_signatures(&records).await?;
+    }
+
+    Ok(())
+}
+
+/// Running totals for one program across its measured deploys and upgrades
+#[derive(Default)]
+struct ProgramSpend {
+    operations: usize,
+    spent: u64,
+    fees: u64,
+    priority_fees: u64,
+    rent: u64,
+}
+
+/// Per-program totals of the listed operations that recorded their spend
+fn print_cumulative_spend(records: &[&OperationRecord]) {
+    let mut totals: Vec<(&str, ProgramSpend)> = Vec::new();
+    for record in records {
+        let (Some(program_id), Some(spend)) = (record.program_id.as_deref(), &record.spend) else {
+            continue;
+        };
+        let index = match totals.iter().position(|(id, _)| *id == program_id) {
+            Some(index) => index,
+            None => {
+                totals.push((program_id, ProgramSpend::default()));
+                totals.len() - 1
+            }
+        };
+        let total = &mut totals[index].1;
+        total.operations += 1;
+        total.spent += spend.balance_before.saturating_sub(spend.balance_after);
+        total.fees += spend.transaction_fees;
+        total.priority_fees += spend.priority_fees;
+        // Records are in time order, so the last one holds the current rent
+        if spend.program_data_rent > 0 {
+            total.rent = spend.program_data_rent;
+        }
+    }
+
+    if totals.is_empty() {
+        return;
+    }
+
+    println!("\nSpend by program:");
+    for (program_id, total) in totals {
+        println!("{program_id}");
+        println!("    Deploys/upgrades: {}", total.operations);
+        println!("    Spent:            {} (deployer balance)", format_sol(total.spent));
+        println!("    Fees:             {} (priority {})", format_fee(total.fees), format_fee(total.priority_fees));
+        println!("    Rent locked:      {}", format_sol(total.rent));
+    }
+}
+
+pub fn kind_label(kind: OperationKind) -> &'static str {
+    match kind {
+        OperationKind::Init => "init",
+        OperationKind::Fund => "fund",
+        OperationKind::Withdraw => "withdraw",
+        OperationKind::Deploy => "deploy",
+        OperationKind::Upgrade => "upgrade",
+        OperationKind::Rotate => "rotate",
+        OperationKind::TransferAuthority => "transfer-authority",
+        OperationKind::Finalize => "finalize",
+        OperationKind::WriteBuffer => "write-buffer",
+        OperationKind::PrepareUpgrade => "prepare-upgrade",
+        OperationKind::PublishMetadata => "metadata",
+        OperationKind::MultisigPropose => "multisig-propose",
+        OperationKind::SpillCompress => "spill-compress",
+        OperationKind::ImportProgram => "import",
+        OperationKind::RemoveProgram => "remove",
+        OperationKind::CloseBuffer => "close-buffer",
+        OperationKind::CreateNonce => "nonce-create",
+        OperationKind::CloseNonce => "nonce-close",
+    }
+}
+
+/// Spot-check the most recent signatures to confirm the records aren't stale
+async fn verify_signatures(records: &[&OperationRecord]) -> Result<()> {
+    let signatures: Vec<Signature> = records
+        .iter()
+        .rev()
+        .flat_map(|r| r.signatures.iter())
+        .filter_map(|s| Signature::from_str(s).ok())
+        .take(VERIFY_SAMPLE_SIZE)
+        .collect();
+
+    if signatures.is_empty() {
+        println!("\nNo signatures to verify.");
+        return Ok(());
+    }
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    println!("\n Verifying {} recent signatures...", signatures.len());
+
+    let statuses = rpc_client
+        .get_signature_statuses_with_history(&signatures).await
+        .context("Failed to fetch signature statuses")?
+        .value;
+
+    let mut missing = 0;
+    for (signature, status) in signatures.iter().zip(statuses) {
+        match status {
+            Some(status) if status.err.is_none() => println!("  ✓ {signature}"),
+            Some(_) => println!("  ✗ {signature} (failed on chain)"),
+            None => {
+                missing += 1;
+                println!("  ✗ {signature} (not found)");
+            }
+        }
+    }
+
+    if missing > 0 {
+        print_warning("Some recorded signatures were not found - check that the RPC matches the recorded network");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,575 @@
+use anyhow::{Context, Result};
+use clap::{Subcommand, ValueHint};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk_ids::system_program;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+    instruction::Instruction as SdkInstruction,
+    instruction::AccountMeta,
+};
+use solana_system_interface::instruction as system_instruction;
+use solana_address::Address;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use crate::compute::{with_compute_budget, TxClass};
+use crate::config::Config;
+use crate::utils::*;
+
+/// Anchor's IDL instruction namespace tag (first 8 bytes of instruction data)
+const IDL_IX_TAG: u64 = 0x0a69e9a778bcf440;
+const IDL_SEED: &str = "anchor:idl";
+/// Discriminator (8) + authority (32) + data length (4)
+const IDL_HEADER_LEN: usize = 44;
+/// Largest allocation a single create/resize CPI can make
+const IDL_MAX_GROWTH: usize = 10_000;
+const IDL_WRITE_CHUNK: usize = 600;
+
+#[derive(Subcommand)]
+pub enum IdlAction {
+    /// Create the IDL account and publish the IDL
+    Init {
+        /// Program ID the IDL belongs to
+        program_id: String,
+        /// Path to the IDL JSON (defaults to target/idl/<name>.json)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        filepath: Option<PathBuf>,
+    },
+    /// Replace the IDL of an existing IDL account
+    Upgrade {
+        /// Program ID the IDL belongs to
+        program_id: String,
+        /// Path to the IDL JSON (defaults to target/idl/<name>.json)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        filepath: Option<PathBuf>,
+    },
+}
+
+/// Anchor `IdlInstruction` variants, borsh-encoded by hand
+enum IdlInstruction {
+    Create { data_len: u64 },
+    CreateBuffer,
+    Write { data: Vec<u8> },
+    SetBuffer,
+    Close,
+    Resize { data_len: u64 },
+}
+
+impl IdlInstruction {
+    fn data(&self) -> Vec<u8> {
+        let mut data = IDL_IX_TAG.to_le_bytes().to_vec();
+        match self {
+            IdlInstruction::Create { data_len } => {
+                data.push(0);
+                data.extend_from_slice(&data_len.to_le_bytes());
+            }
+            IdlInstruction::CreateBuffer => data.push(1),
+            IdlInstruction::Write { data: chunk } => {
+                data.push(2);
+                data.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+                data.extend_from_slice(chunk);
+            }
+            IdlInstruction::SetBuffer => data.push(3),
+            IdlInstruction::Close => data.push(5),
+            IdlInstruction::Resize { data_len } => {
+                data.push(6);
+                data.extend_from_slice(&data_len.to_le_bytes());
+            }
+        }
+        data
+    }
+}
+
+pub async fn execute(action: IdlAction) -> Result<()> {
+    let (program_id_str, filepath, upgrade) = match action {
+        IdlAction::Init { program_id, filepath } => (program_id, filepath, false),
+        IdlAction::Upgrade { program_id, filepath } => (program_id, filepath, true),
+    };
+
+    print_header(if upgrade { "Upgrade IDL" } else { "Publish IDL" });
+
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    let deployer = config.load_deployer()?;
+
+    let program_id = Pubkey::from_str(&program_id_str)
+        .context("Invalid program ID")?;
+
+    let idl_path = resolve_idl_file(&config, &program_id, filepath, "--filepath")?;
+    let idl_json = read_idl(&idl_path)?;
+
+    let compressed = compress_idl(&idl_json)?;
+    let idl_address = idl_address(&program_id)?;
+
+    println!("\nIDL file:     {}", idl_path.display());
+    println!("IDL account:  {idl_address}");
+    println!("Size:         {} bytes ({} compressed)\n", idl_json.len(), compressed.len());
+
+    if !prompt_confirmation("Proceed?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    if upgrade {
+        upgrade_idl(&rpc_client, &deployer, &program_id, &idl_address, &compressed).await?;
+    } else {
+        init_idl(&rpc_client, &deployer, &program_id, &idl_address, &compressed).await?;
+    }
+
+    verify_idl(&rpc_client, &idl_address, &idl_json).await?;
+    print_success("IDL published");
+    record_idl(&config, &program_id, &idl_address, &idl_json)?;
+
+    println!("\nIDL account: {idl_address}");
+    println!("Authority:   private deployer");
+
+    Ok(())
+}
+
+/// Publish the program's IDL right after a deploy or upgrade (`--with-idl`)
+///
+/// Creates the IDL account the first time and upgrades it on later runs,
+/// signed by the private deployer. The deploy has already been confirmed,
+/// so this does not ask again.
+pub async fn publish_with_deploy(
+    config: &Config,
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    program_id: &Pubkey,
+    filepath: Option<PathBuf>,
+) -> Result<()> {
+    let idl_path = resolve_idl_file(config, program_id, filepath, "--idl")?;
+    let idl_json = read_idl(&idl_path)?;
+    let compressed = compress_idl(&idl_json)?;
+    let idl_address = idl_address(program_id)?;
+
+    println!("\n Publishing IDL {}...", idl_path.display());
+    println!("  ↳ IDL account: {idl_address}");
+    println!("  ↳ Size: {} bytes ({} compressed)", idl_json.len(), compressed.len());
+
+    if with_retry(|| rpc_client.get_account_with_commitment(&idl_address, rpc_client.commitment()))
+        .await?
+        .value
+        .is_some()
+    {
+        upgrade_idl(rpc_client, deployer, program_id, &idl_address, &compressed).await?;
+    } else {
+        init_idl(rpc_client, deployer, program_id, &idl_address, &compressed).await?;
+    }
+
+    verify_idl(rpc_client, &idl_address, &idl_json).await?;
+    record_idl(config, program_id, &idl_address, &idl_json)?;
+    println!("  ✓ IDL published at {idl_address}");
+
+    Ok(())
+}
+
+/// `filepath`, else the IDL in target/idl matching the program's recorded name
+fn resolve_idl_file(config: &Config, program_id: &Pubkey, filepath: Option<PathBuf>, flag: &str) -> Result<PathBuf> {
+    if let Some(path) = filepath {
+        return Ok(path);
+    }
+
+    let managed_name = config.load_state()?
+        .program(&program_id.to_string())
+        .and_then(|p| p.name.clone());
+
+    detect_idl_file(managed_name.as_deref()).ok_or_else(|| anyhow::anyhow!(
+        "No IDL found in target/idl.\n\
+        Run `anchor build` or pass {flag}"
+    ))
+}
+
+fn read_idl(path: &Path) -> Result<Vec<u8>> {
+    let idl_json = std::fs::read(path)
+        .context(format!("Failed to read IDL {}", path.display()))?;
+    serde_json::from_slice::<serde_json::Value>(&idl_json)
+        .context("IDL file is not valid JSON")?;
+    Ok(idl_json)
+}
+
+/// Fetch the IDL account back and check its decompressed JSON matches `idl_json`
+async fn verify_idl(rpc_client: &RpcClient, idl_address: &Pubkey, idl_json: &[u8]) -> Result<()> {
+    let account = with_retry(|| rpc_client.get_account(idl_address)).await
+        .context("Failed to fetch the IDL account for verification")?;
+    let data_len = account.data
+        .get(40..IDL_HEADER_LEN)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .ok_or_else(|| anyhow::anyhow!("Invalid IDL account"))?;
+    let compressed = account.data
+        .get(IDL_HEADER_LEN..IDL_HEADER_LEN + data_len)
+        .ok_or_else(|| anyhow::anyhow!("IDL account is shorter than its stored length"))?;
+
+    let mut on_chain = Vec::new();
+    ZlibDecoder::new(compressed)
+        .read_to_end(&mut on_chain)
+        .context("IDL account data is not valid zlib")?;
+
+    // Compare parsed JSON so formatting differences don't count
+    let on_chain: serde_json::Value = serde_json::from_slice(&on_chain)
+        .context("On-chain IDL is not valid JSON")?;
+    let local: serde_json::Value = serde_json::from_slice(idl_json)?;
+    if on_chain != local {
+        anyhow::bail!(
+            "On-chain IDL at {idl_address} does not match the local file.\n\
+            Re-run `shield-deploy idl upgrade` to rewrite it."
+        );
+    }
+
+    println!("  ✓ On-chain IDL matches the local file");
+    Ok(())
+}
+
+fn record_idl(config: &Config, program_id: &Pubkey, idl_address: &Pubkey, idl_json: &[u8]) -> Result<()> {
+    let mut state = config.load_state()?;
+    if let Some(program) = state.program_mut(&program_id.to_string()) {
+        program.idl_address = Some(idl_address.to_string());
+        program.idl_hash = Some(sha256_hex(idl_json));
+        config.save_state(&state)?;
+    }
+    Ok(())
+}
+
+/// Address of a program's canonical Anchor IDL account
+pub fn idl_address(program_id: &Pubkey) -> Result<Pubkey> {
+    let (base, _) = Pubkey::find_program_address(&[], program_id);
+    Pubkey::create_with_seed(&base, IDL_SEED, program_id)
+        .map_err(|e| anyhow::anyhow!("Failed to derive IDL address: {e}"))
+}
+
+pub fn compress_idl(idl_json: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(idl_json)?;
+    encoder.finish().context("Failed to compress IDL")
+}
+
+fn detect_idl_file(name: Option<&str>) -> Option<PathBuf> {
+    let idl_dir = Path::new("target/idl");
+
+    let candidates = [name.map(String::from), get_program_lib_name().ok()];
+    for candidate in candidates.into_iter().flatten() {
+        let path = idl_dir.join(candidate).with_extension("json");
+        if path.exists() {
+            return Some(path);
+        }
+    }
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(idl_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("json"))
+        .collect();
+
+    if files.len() == 1 { files.pop() } else { None }
+}
+
+async fn init_idl(
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    program_id: &Pubkey,
+    idl_address: &Pubkey,
+    compressed: &[u8],
+) -> Result<()> {
+    if with_retry(|| rpc_client.get_account_with_commitment(idl_address, rpc_client.commitment()))
+        .await
+        .context("Failed to look up the IDL account")?
+        .value
+        .is_some()
+    {
+        anyhow::bail!(
+            "IDL account {idl_address} already exists.\n\
+            Run `shield-deploy idl upgrade` instead."
+        );
+    }
+
+    println!("\n Creating IDL account...");
+
+    let (base, _) = Pubkey::find_program_address(&[], program_id);
+    let data_len = (compressed.len() * 2).min(IDL_MAX_GROWTH);
+
+    let create_ix = SdkInstruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(deployer.pubkey(), true),
+            AccountMeta::new(*idl_address, false),
+            AccountMeta::new_readonly(base, false),
+            AccountMeta::new_readonly(Pubkey::new_from_array(system_program::ID.to_bytes()), false),
+            AccountMeta::new_readonly(*program_id, false),
+        ],
+        data: IdlInstruction::Create { data_len: data_len as u64 }.data(),
+    };
+
+    let signature = send_idl_instructions(rpc_client, deployer, TxClass::CreateBuffer, &[create_ix]).await
+        .context("Failed to create IDL account")?;
+    println!("  ✓ IDL account created: {signature}");
+
+    resize_if_needed(rpc_client, deployer, program_id, idl_address, compressed.len()).await?;
+
+    println!("\n Writing IDL...");
+    write_idl_chunks(rpc_client, deployer, program_id, idl_address, compressed).await?;
+
+    Ok(())
+}
+
+async fn upgrade_idl(
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    program_id: &Pubkey,
+    idl_address: &Pubkey,
+    compressed: &[u8],
+) -> Result<()> {
+    let idl_account = with_retry(|| rpc_client.get_account(idl_address)).await
+        .context("IDL account not found - run `shield-deploy idl init` first")?;
+
+    let authority = idl_account.data
+        .get(8..40)
+        .and_then(|bytes| Pubkey::try_from(bytes).ok())
+        .ok_or_else(|| anyhow::anyhow!("Invalid IDL account"))?;
+
+    if authority != deployer.pubkey() {
+        anyhow::bail!(
+            "IDL authority mismatch.\n\
+            Expected: {}\n\
+            Found: {authority}",
+            deployer.pubkey()
+        );
+    }
+
+    println!("\n Creating IDL buffer...");
+
+    let buffer_keypair = Keypair::new();
+    let buffer_pubkey = buffer_keypair.pubkey();
+    let buffer_size = IDL_HEADER_LEN + compressed.len();
+    let buffer_lamports = with_retry(|| rpc_client.get_minimum_balance_for_rent_exemption(buffer_size)).await
+        .context("Failed to get rent exemption for IDL buffer")?;
+
+    let create_account_ix = system_instruction::create_account(
+        &Address::from(deployer.pubkey().to_bytes()),
+        &Address::from(buffer_pubkey.to_bytes()),
+        buffer_lamports,
+        buffer_size as u64,
+        &Address::from(program_id.to_bytes()),
+    );
+
+    let create_account_ix = SdkInstruction {
+        program_id: Pubkey::from(create_account_ix.program_id.to_bytes()),
+        accounts: create_account_ix
+            .accounts
+            .iter()
+            .map(|acc| AccountMeta {
+                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            })
+            .collect(),
+        data: create_account_ix.data,
+    };
+
+    let create_buffer_ix = SdkInstruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(buffer_pubkey, false),
+            AccountMeta::new_readonly(deployer.pubkey(), true),
+        ],
+        data: IdlInstruction::CreateBuffer.data(),
+    };
+
+    let transaction = idl_transaction(
+        rpc_client,
+        deployer,
+        &[&buffer_keypair],
+        TxClass::CreateBuffer,
+        &[create_account_ix, create_buffer_ix],
+    )
+    .await?;
+    let signature = send_and_confirm(rpc_client, &transaction).await
+        .context("Failed to create IDL buffer")?;
+    println!("  ✓ Buffer created: {signature}");
+    println!("  ↳ Buffer address: {buffer_pubkey}");
+
+    println!("\n Writing IDL to buffer...");
+    write_idl_chunks(rpc_client, deployer, program_id, &buffer_pubkey, compressed).await?;
+
+    resize_if_needed(rpc_client, deployer, program_id, idl_address, compressed.len()).await?;
+
+    println!("\n Swapping IDL buffer in...");
+
+    let set_buffer_ix = SdkInstruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(buffer_pubkey, false),
+            AccountMeta::new(*idl_address, false),
+            AccountMeta::new(deployer.pubkey(), true),
+        ],
+        data: IdlInstruction::SetBuffer.data(),
+    };
+
+    // Close the buffer in the same transaction so its rent comes straight back
+    let close_buffer_ix = SdkInstruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(buffer_pubkey, false),
+            AccountMeta::new_readonly(deployer.pubkey(), true),
+            AccountMeta::new(deployer.pubkey(), false),
+        ],
+        data: IdlInstruction::Close.data(),
+    };
+
+    let signature = send_idl_instructions(rpc_client, deployer, TxClass::Upgrade, &[set_buffer_ix, close_buffer_ix]).await
+        .context("Failed to set IDL buffer")?;
+    println!("  ✓ IDL upgraded: {signature}");
+
+    Ok(())
+}
+
+/// Grow the IDL account in CPI-sized steps until it can hold `data_len` bytes
+async fn resize_if_needed(
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    program_id: &Pubkey,
+    idl_address: &Pubkey,
+    data_len: usize,
+) -> Result<()> {
+    let account = with_retry(|| rpc_client.get_account(idl_address)).await
+        .context("IDL account not found")?;
+    let mut capacity = account.data.len().saturating_sub(IDL_HEADER_LEN);
+
+    while capacity < data_len {
+        capacity = (capacity + IDL_MAX_GROWTH).min(data_len);
+
+        let resize_ix = SdkInstruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*idl_address, false),
+                AccountMeta::new(deployer.pubkey(), true),
+                AccountMeta::new_readonly(Pubkey::new_from_array(system_program::ID.to_bytes()), false),
+            ],
+            data: IdlInstruction::Resize { data_len: capacity as u64 }.data(),
+        };
+
+        send_idl_instructions(rpc_client, deployer, TxClass::Extend, &[resize_ix]).await
+            .context("Failed to resize IDL account")?;
+        println!("  ✓ IDL account resized to {capacity} bytes");
+    }
+
+    Ok(())
+}
+
+/// Append compressed IDL data to an IDL account or buffer, retrying failed chunks once
+///
+/// Writes append, so a chunk is only resent once its first transaction can
+/// no longer land, the way the loader reclaims buffers.
+async fn write_idl_chunks(
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    program_id: &Pubkey,
+    target: &Pubkey,
+    compressed: &[u8],
+) -> Result<()> {
+    let total_chunks = compressed.len().div_ceil(IDL_WRITE_CHUNK);
+    println!("  ↳ Writing {} bytes in {} chunks", compressed.len(), total_chunks);
+
+    for (chunk_index, chunk) in compressed.chunks(IDL_WRITE_CHUNK).enumerate() {
+        let write_ix = SdkInstruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*target, false),
+                AccountMeta::new_readonly(deployer.pubkey(), true),
+            ],
+            data: IdlInstruction::Write { data: chunk.to_vec() }.data(),
+        };
+
+        let transaction = idl_transaction(
+            rpc_client,
+            deployer,
+            &[],
+            TxClass::Write,
+            std::slice::from_ref(&write_ix),
+        )
+        .await?;
+        if let Err(error) = send_and_confirm(rpc_client, &transaction).await {
+            // A timed-out write may still land; resending before it can't
+            // would append the chunk twice
+            let expected_len = chunk_index * IDL_WRITE_CHUNK + chunk.len();
+            if never_lands(rpc_client, &transaction).await {
+                println!("  ⚠️  Retrying chunk {}...", chunk_index + 1);
+                send_idl_instructions(rpc_client, deployer, TxClass::Write, &[write_ix]).await
+                    .context(format!("Failed to write IDL chunk {} after retry", chunk_index + 1))?;
+            } else if stored_idl_len(rpc_client, target).await? != expected_len {
+                return Err(error).context(format!(
+                    "IDL chunk {} may still land, so it was not resent. Run the command again once it settles",
+                    chunk_index + 1
+                ));
+            }
+        }
+
+        if (chunk_index + 1) % 10 == 0 || chunk_index + 1 == total_chunks {
+            println!("  ↳ Progress: {}/{} chunks", chunk_index + 1, total_chunks);
+        }
+    }
+
+    println!("  ✓ IDL data written");
+    Ok(())
+}
+
+async fn stored_idl_len(rpc_client: &RpcClient, target: &Pubkey) -> Result<usize> {
+    let account = with_retry(|| rpc_client.get_account(target)).await
+        .context("IDL account not found")?;
+
+    account.data
+        .get(40..IDL_HEADER_LEN)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .ok_or_else(|| anyhow::anyhow!("Invalid IDL account"))
+}
+
+async fn send_idl_instructions(
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    class: TxClass,
+    instructions: &[SdkInstruction],
+) -> Result<Signature> {
+    let transaction = idl_transaction(rpc_client, deployer, &[], class, instructions).await?;
+    send_and_confirm(rpc_client, &transaction).await
+}
+
+/// Sign an IDL transaction with the same compute budget, nonce and fee payer
+/// handling as program buffer transactions
+async fn idl_transaction(
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    extra_signers: &[&Keypair],
+    class: TxClass,
+    instructions: &[SdkInstruction],
+) -> Result<Transaction> {
+    let payer = fee_payer(deployer);
+    let mut signers = vec![&*payer, deployer];
+    signers.dedup_by_key(|k| k.pubkey());
+    signers.extend_from_slice(extra_signers);
+
+    let (recent_blockhash, instructions) = durable_blockhash(
+        rpc_client,
+        &signers,
+        with_compute_budget(rpc_client, class, &payer.pubkey(), instructions).await,
+    )
+    .await?;
+    let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+    transaction.sign(&signers, recent_blockhash);
+    Ok(transaction)
+}
@@ -0,0 +1,69 @@
+use anyhow::{Context, Result};
+use solana_sdk::signature::{Keypair, Signer};
+use std::fs;
+use std::path::PathBuf;
+use crate::bundle::decode_bundle;
+use crate::config::{write_secret_file, Config};
+use crate::utils::*;
+
+pub async fn execute(path: PathBuf, force: bool) -> Result<()> {
+    print_header("Import Deployer");
+
+    let config = Config::new()?;
+    config.ensure_deployer_on_disk("import-deployer")?;
+
+    if config.deployer_exists() && !force {
+        anyhow::bail!(
+            "A private deployer already exists in this project.\n\
+            Importing would overwrite it. Back it up with `shield-deploy export-deployer`\n\
+            and re-run with --force if you really want to replace it."
+        );
+    }
+
+    let data = fs::read(&path)
+        .context(format!("Failed to read bundle {}", path.display()))?;
+
+    let passphrase = prompt_passphrase("Passphrase", false)?;
+    let bundle = decode_bundle(&data, &passphrase)?;
+
+    let deployer = Keypair::from_bytes(&bundle.deployer.keypair)
+        .map_err(|e| anyhow::anyhow!("Bundle contains an invalid keypair: {e}"))?;
+
+    let created = chrono::DateTime::from_timestamp(bundle.created_at, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("\nBundle contents:");
+    println!("• Deployer: {}", deployer.pubkey());
+    println!("• Created:  {created}");
+    println!("• State:    {}", if bundle.state.is_some() { "included" } else { "not included" });
+    println!("• Config:   {}\n", if bundle.config.is_some() { "included" } else { "not included" });
+
+    if !prompt_confirmation("Restore this deployer?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    config.save_deployer(&deployer)
+        .context("Failed to save deployer")?;
+
+    if let Some(state) = &bundle.state {
+        write_secret_file(&config.state_path(), serde_json::to_string_pretty(state)?)
+            .context("Failed to write state")?;
+    }
+
+    if let Some(project_config) = &bundle.config {
+        write_secret_file(&config.config_path(), project_config)
+            .context("Failed to write config")?;
+    }
+
+    config.add_gitignore()
+        .context("Failed to update .gitignore")?;
+
+    print_success("Deployer imported");
+
+    println!("\nDeployer pubkey: {}", deployer.pubkey());
+    println!("Verify it matches the pubkey of the exported deployer.");
+
+    Ok(())
+}
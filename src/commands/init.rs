@@ -1,12 +1,17 @@
 use anyhow::{Context, Result};
-use solana_sdk::signature::Keypair;
-use crate::config::Config;
+use solana_sdk::signature::{Keypair, Signer};
+use crate::config::{Config, OperationKind, OperationRecord, DEPLOYER_KEY_ENV};
 use crate::utils::{print_header, print_success, prompt_confirmation};
 
-pub async fn execute() -> Result<()> {
+pub async fn execute(unsafe_ignore_git: bool) -> Result<()> {
     print_header("Shield-Deploy");
     
     let config = Config::new()?;
+    config.check_git_secrets(unsafe_ignore_git)?;
+    
+    if Config::deployer_from_env() {
+        return init_env_deployer(&config);
+    }
     
     // Check if already initialized
     if config.deployer_exists() {
@@ -39,10 +44,11 @@ pub async fn execute() -> Result<()> {
         .context("Failed to update .gitignore")?;
     
     // Initialize state
+    let network = crate::utils::get_network_name();
     let state = crate::config::ProjectState {
-        network: crate::utils::get_network_name(),
-        deployed_programs: vec![],
-        last_balance: 0,
+        network: network.clone(),
+        operations: vec![OperationRecord::new(OperationKind::Init, &network)],
+        ..Default::default()
     };
     config.save_state(&state)?;
     
@@ -60,5 +66,38 @@ pub async fn execute() -> Result<()> {
     println!("\nNext step:");
     println!("→ Fund the deployer with SOL using `shield-deploy fund`");
     
+    Ok(())
+}
+
+/// Start project state for a deployer supplied by `SHIELD_DEPLOYER_KEY`
+///
+/// No key is generated or written; the environment stays the only copy.
+fn init_env_deployer(config: &Config) -> Result<()> {
+    if config.state_path().exists() {
+        anyhow::bail!(
+            "This project is already initialized.\n\
+            Run `shield-deploy status` to view current state."
+        );
+    }
+    
+    let deployer = config.load_deployer()?;
+    
+    config.add_gitignore()
+        .context("Failed to update .gitignore")?;
+    let network = crate::utils::get_network_name();
+    let state = crate::config::ProjectState {
+        network: network.clone(),
+        operations: vec![OperationRecord::new(OperationKind::Init, &network)],
+        ..Default::default()
+    };
+    config.save_state(&state)?;
+    
+    print_success("Project initialized");
+    println!("\nDeployer:       {}", deployer.pubkey());
+    println!("Location:       ${DEPLOYER_KEY_ENV} (not written to disk)");
+    
+    println!("\nNext step:");
+    println!("→ Fund the deployer with SOL using `shield-deploy fund`");
+    
     Ok(())
 }
\ No newline at end of file
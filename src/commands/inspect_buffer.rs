@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+};
+use std::str::FromStr;
+use crate::commands::verify::{fetch_buffer, find_local_artifacts};
+use crate::config::{BufferRecord, BufferStatus, Config};
+use crate::utils::*;
+
+#[derive(Serialize)]
+struct BufferReport {
+    buffer: String,
+    authority: Option<String>,
+    lamports: u64,
+    data_len: usize,
+    payload_size: usize,
+    payload_hash: String,
+    /// Local builds with the same bytecode
+    matching_artifacts: Vec<String>,
+    /// What this project recorded when it wrote the buffer
+    local: Option<BufferRecord>,
+}
+
+pub async fn execute(buffer_str: String, json: bool) -> Result<()> {
+    let config = Config::new()?;
+
+    let buffer = Pubkey::from_str(&buffer_str)
+        .context("Invalid buffer address")?;
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    let on_chain = fetch_buffer(&rpc_client, &buffer).await?;
+    let payload_hash = sha256_hex(&on_chain.payload);
+
+    let local = if config.state_path().exists() {
+        config.load_state()?
+            .buffers
+            .into_iter()
+            .find(|b| b.address == buffer_str)
+    } else {
+        None
+    };
+
+    let report = BufferReport {
+        buffer: buffer_str,
+        authority: on_chain.authority.map(|a| a.to_string()),
+        lamports: on_chain.lamports,
+        data_len: on_chain.data_len,
+        payload_size: on_chain.payload.len(),
+        matching_artifacts: find_local_artifacts(&config, &payload_hash)
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect(),
+        payload_hash,
+        local,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    print_header("Buffer Details");
+
+    println!();
+    println!("Buffer:            {}", report.buffer);
+    match &report.authority {
+        Some(authority) => println!("Authority:         {authority}"),
+        None => println!("Authority:         None (immutable)"),
+    }
+    println!("Lamports:          {}", format_sol(report.lamports));
+    println!("Account size:      {} bytes", report.data_len);
+    println!("Payload size:      {} bytes", report.payload_size);
+    println!("Payload hash:      {}", report.payload_hash);
+
+    println!();
+    if report.payload_size == 0 {
+        println!("Nothing has been written to this buffer yet.");
+    } else if report.matching_artifacts.is_empty() {
+        println!("No local build matches this payload");
+        println!("  ↳ Checked target/deploy/ and {}", config.artifacts_dir().display());
+    } else {
+        println!("Matching builds:");
+        for path in &report.matching_artifacts {
+            println!("  • {path}");
+        }
+    }
+
+    if let Some(record) = &report.local {
+        println!();
+        println!("Written by this project:");
+        if let Some(name) = &record.name {
+            println!("  • Artifact: {name}");
+        }
+        if let Some(program_id) = &record.program_id {
+            println!("  • Program:  {program_id}");
+        }
+        println!("  • Status:   {:?}", record.status);
+        if record.status == BufferStatus::Writing {
+            print_warning("The write into this buffer never finished; resume it with `shield-deploy write-buffer`");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,345 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{read_keypair_file, write_keypair_file, Keypair, Signer},
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use crate::commands::anchor::{resolve_cluster, set_program_id, ANCHOR_TOML};
+use crate::config::{Config, DeployedProgram};
+use crate::utils::*;
+
+const DEPLOY_DIR: &str = "target/deploy";
+
+/// Where Anchor and single-crate projects keep program crates
+const CRATE_ROOTS: &[&str] = &[".", "programs"];
+
+#[derive(Subcommand)]
+pub enum KeysAction {
+    /// Compare declare_id! and target/deploy keypairs with the deployed program IDs
+    Sync {
+        /// Patch declare_id! and copy the stored program keypair into target/deploy
+        #[arg(long, conflicts_with = "check")]
+        write: bool,
+        /// Exit non-zero if anything has drifted (for CI)
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+pub async fn execute(action: KeysAction) -> Result<()> {
+    match action {
+        KeysAction::Sync { write, check } => sync(write, check),
+    }
+}
+
+/// Where one managed program's ID is declared locally
+struct ProgramKeys {
+    name: String,
+    deployed: Pubkey,
+    /// `src/lib.rs` of the matching crate, or why none could be chosen
+    source: Result<PathBuf, String>,
+    declared: Option<Pubkey>,
+    keypair_path: PathBuf,
+    keypair: Option<Pubkey>,
+}
+
+impl ProgramKeys {
+    fn source_drifted(&self) -> bool {
+        self.source.is_err() || self.declared != Some(self.deployed)
+    }
+
+    fn keypair_drifted(&self) -> bool {
+        self.keypair != Some(self.deployed)
+    }
+}
+
+fn sync(write: bool, check: bool) -> Result<()> {
+    print_header("Keys Sync");
+
+    let config = Config::new()?;
+    let state = config.load_state()?;
+    let network = get_network_name();
+
+    // Latest deployment of each named program on this cluster
+    let mut programs: Vec<&DeployedProgram> = Vec::new();
+    for program in &state.deployed_programs {
+        if program.name.is_none() || program.cluster.as_deref().is_some_and(|c| c != network) {
+            continue;
+        }
+        match programs.iter_mut().find(|p| p.name == program.name) {
+            Some(existing) if existing.deployed_at <= program.deployed_at => *existing = program,
+            Some(_) => {}
+            None => programs.push(program),
+        }
+    }
+
+    if programs.is_empty() {
+        println!("\nNo named programs deployed on {network}.");
+        return Ok(());
+    }
+
+    let crates = find_program_crates();
+    let mut rows = Vec::new();
+    for program in programs {
+        let name = program.name.clone().unwrap_or_default();
+        let deployed = Pubkey::from_str(&program.program_id)
+            .with_context(|| format!("Invalid program ID in state: {}", program.program_id))?;
+        let source = match_crate(&crates, &name);
+        let declared = source
+            .as_ref()
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|source| declared_id(&source).ok());
+        let keypair_path = Path::new(DEPLOY_DIR).join(format!("{name}-keypair.json"));
+        let keypair = read_keypair_file(&keypair_path).ok().map(|k| k.pubkey());
+        rows.push(ProgramKeys { name, deployed, source, declared, keypair_path, keypair });
+    }
+
+    print_table(&rows);
+
+    let drifted: Vec<&ProgramKeys> = rows
+        .iter()
+        .filter(|r| r.source_drifted() || r.keypair_drifted())
+        .collect();
+    if drifted.is_empty() {
+        print_success("declare_id! and keypairs match the deployed programs");
+        return Ok(());
+    }
+
+    if check {
+        anyhow::bail!("{} program(s) out of sync. Run `shield-deploy keys sync --write`", drifted.len());
+    }
+    if !write {
+        println!("\n→ Run `shield-deploy keys sync --write` to fix");
+        return Ok(());
+    }
+
+    println!();
+    for row in drifted {
+        if row.source_drifted() {
+            match &row.source {
+                Ok(path) => match patch_declare_id(path, &row.deployed) {
+                    Ok(()) => println!("  ✓ {}: declare_id! in {} set to {}", row.name, path.display(), row.deployed),
+                    Err(e) => print_warning(&format!("{}: {e}", row.name)),
+                },
+                Err(reason) => print_warning(&format!("{}: not editing source, {reason}", row.name)),
+            }
+        }
+        if row.keypair_drifted() {
+            match config.load_program_key(&row.deployed)? {
+                Some(keypair) => {
+                    install_keypair(&keypair, &row.keypair_path)?;
+                    println!("  ✓ {}: {} now holds {}", row.name, row.keypair_path.display(), row.deployed);
+                }
+                None => print_warning(&format!(
+                    "{}: no stored keypair for {} in .shield/programs/, leaving {}",
+                    row.name,
+                    row.deployed,
+                    row.keypair_path.display()
+                )),
+            }
+        }
+    }
+
+    println!("\n→ Rebuild so the binary embeds the new ID");
+
+    Ok(())
+}
+
+fn print_table(rows: &[ProgramKeys]) {
+    let mark = |ok: bool| if ok { "✓" } else { "✗" };
+    println!();
+    for row in rows {
+        println!("{}", row.name);
+        println!("  Deployed:     {}", row.deployed);
+        match &row.source {
+            Ok(path) => println!(
+                "  declare_id!:  {} {} ({})",
+                mark(!row.source_drifted()),
+                row.declared.map(|id| id.to_string()).unwrap_or_else(|| "not found".to_string()),
+                path.display()
+            ),
+            Err(reason) => println!("  declare_id!:  ✗ {reason}"),
+        }
+        println!(
+            "  Keypair file: {} {} ({})",
+            mark(!row.keypair_drifted()),
+            row.keypair.map(|id| id.to_string()).unwrap_or_else(|| "missing".to_string()),
+            row.keypair_path.display()
+        );
+    }
+}
+
+/// Point declare_id! and Anchor.toml at a just-deployed program (`deploy --sync-keys`)
+///
+/// Shows both edits and asks before writing. Outside an Anchor workspace, or
+/// when everything already names `program_id`, nothing is touched.
+pub(crate) fn sync_deployed(name: Option<&str>, program_id: &Pubkey) -> Result<()> {
+    let Ok(anchor_toml) = fs::read_to_string(ANCHOR_TOML) else {
+        println!("\nNo {ANCHOR_TOML} here; skipping --sync-keys");
+        return Ok(());
+    };
+    let Some(name) = name else {
+        print_warning("The artifact has no program name; skipping --sync-keys");
+        return Ok(());
+    };
+
+    println!("\nSyncing keys for {name}...");
+
+    let source_edit = match match_crate(&find_program_crates(), name) {
+        Ok(path) => {
+            let source = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            match declared_id(&source) {
+                Ok(declared) if declared == *program_id => None,
+                Ok(declared) => Some((path, declared.to_string())),
+                Err(e) => {
+                    print_warning(&format!("{}: {e}; edit declare_id! by hand", path.display()));
+                    None
+                }
+            }
+        }
+        Err(reason) => {
+            print_warning(&format!("Not editing source, {reason}"));
+            None
+        }
+    };
+
+    let (cluster, _) = resolve_cluster(&get_network_name());
+    let updated_toml = set_program_id(&anchor_toml, &cluster, name, program_id);
+    let toml_changed = updated_toml.lines().ne(anchor_toml.lines());
+
+    if source_edit.is_none() && !toml_changed {
+        println!("  ✓ declare_id! and {ANCHOR_TOML} already hold {program_id}");
+        return Ok(());
+    }
+
+    println!();
+    if let Some((path, declared)) = &source_edit {
+        println!("{}", path.display());
+        println!("  - declare_id!(\"{declared}\");");
+        println!("  + declare_id!(\"{program_id}\");");
+    }
+    if toml_changed {
+        // set_program_id only replaces or adds lines, so a set difference is the diff
+        println!("{ANCHOR_TOML}");
+        for line in anchor_toml.lines().filter(|l| !updated_toml.lines().any(|u| u == *l)) {
+            println!("  - {line}");
+        }
+        for line in updated_toml.lines().filter(|l| !l.is_empty() && !anchor_toml.lines().any(|a| a == *l)) {
+            println!("  + {line}");
+        }
+    }
+    println!();
+
+    if !prompt_confirmation("Write these changes?")? {
+        println!("Left as is. Run `shield-deploy keys sync --write` later.");
+        return Ok(());
+    }
+
+    if let Some((path, _)) = &source_edit {
+        patch_declare_id(path, program_id)?;
+        println!("  ✓ declare_id! in {} set to {program_id}", path.display());
+    }
+    if toml_changed {
+        fs::write(ANCHOR_TOML, updated_toml).with_context(|| format!("Failed to update {ANCHOR_TOML}"))?;
+        println!("  ✓ {ANCHOR_TOML} [programs.{cluster}] updated");
+    }
+    println!("→ Rebuild so the binary embeds the new ID");
+
+    Ok(())
+}
+
+/// (lib name, src/lib.rs) of every crate under the usual roots
+fn find_program_crates() -> Vec<(String, PathBuf)> {
+    let mut manifests = Vec::new();
+    for &root in CRATE_ROOTS {
+        if root == "." {
+            manifests.push(Path::new(root).join("Cargo.toml"));
+        } else if let Ok(entries) = fs::read_dir(root) {
+            manifests.extend(entries.filter_map(|e| e.ok()).map(|e| e.path().join("Cargo.toml")));
+        }
+    }
+
+    manifests
+        .into_iter()
+        .filter(|manifest| manifest.exists())
+        .filter_map(|manifest| {
+            let lib_name = extract_lib_name(&manifest).ok()?;
+            let lib_rs = manifest.parent()?.join("src/lib.rs");
+            lib_rs.exists().then_some((lib_name, lib_rs))
+        })
+        .collect()
+}
+
+/// The one crate whose lib name is `name`, else why not
+fn match_crate(crates: &[(String, PathBuf)], name: &str) -> Result<PathBuf, String> {
+    let matches: Vec<&PathBuf> = crates
+        .iter()
+        .filter(|(lib_name, _)| lib_name == name)
+        .map(|(_, path)| path)
+        .collect();
+    match matches.as_slice() {
+        [] => Err(format!("no crate with lib name {name}")),
+        [path] => Ok((*path).clone()),
+        _ => Err(format!("{} crates are named {name}", matches.len())),
+    }
+}
+
+/// The single `declare_id!("...")` in a source file
+fn declared_id(source: &str) -> Result<Pubkey> {
+    let spans = declare_id_spans(source);
+    let [(start, end)] = spans.as_slice() else {
+        anyhow::bail!("expected one declare_id!, found {}", spans.len());
+    };
+    Pubkey::from_str(&source[*start..*end]).context("declare_id! does not hold a valid pubkey")
+}
+
+/// Byte ranges of the quoted IDs in `declare_id!("...")` invocations
+fn declare_id_spans(source: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut from = 0;
+    while let Some(found) = source[from..].find("declare_id!") {
+        let after = from + found + "declare_id!".len();
+        from = after;
+        let Some(open) = source[after..].find('"') else { break };
+        let start = after + open + 1;
+        // Only accept `declare_id!(` followed by whitespace and the string
+        if !source[after..after + open].trim().trim_start_matches('(').trim().is_empty() {
+            continue;
+        }
+        let Some(len) = source[start..].find('"') else { break };
+        spans.push((start, start + len));
+        from = start + len;
+    }
+    spans
+}
+
+fn patch_declare_id(path: &Path, program_id: &Pubkey) -> Result<()> {
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let spans = declare_id_spans(&source);
+    let [(start, end)] = spans.as_slice() else {
+        anyhow::bail!("{} has {} declare_id! invocations; edit it by hand", path.display(), spans.len());
+    };
+    let patched = format!("{}{program_id}{}", &source[..*start], &source[*end..]);
+    fs::write(path, patched).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Write `keypair` where Anchor expects it, keeping a copy of what was there
+fn install_keypair(keypair: &Keypair, path: &Path) -> Result<()> {
+    if path.exists() {
+        let backup = path.with_extension("json.bak");
+        fs::copy(path, &backup)
+            .with_context(|| format!("Failed to back up {}", path.display()))?;
+        println!("  ↳ Previous keypair saved to {}", backup.display());
+    } else if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    write_keypair_file(keypair, path)
+        .map_err(|e| anyhow::anyhow!("Failed to write {}: {e}", path.display()))?;
+    Ok(())
+}
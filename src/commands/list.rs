@@ -0,0 +1,133 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signer::Signer,
+};
+use std::str::FromStr;
+use crate::config::{Config, DeployedProgram};
+use crate::commands::verify::fetch_program_bytecode;
+use crate::utils::*;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ListSort {
+    Name,
+    Deployed,
+    Upgraded,
+}
+
+pub async fn execute(
+    refresh: bool,
+    json: bool,
+    sort: Option<ListSort>,
+    reverse: bool,
+) -> Result<()> {
+    let config = Config::new()?;
+    let mut state = config.load_state()?;
+
+    if refresh && !state.deployed_programs.is_empty() {
+        let rpc_url = get_rpc_url()?;
+        let rpc_client = RpcClient::new_with_commitment(
+            rpc_url,
+            commitment(),
+        );
+
+        for program in state.deployed_programs.iter_mut() {
+            let program_id = Pubkey::from_str(&program.program_id)?;
+            match fetch_program_bytecode(&rpc_client, &program_id).await {
+                Ok(on_chain) => {
+                    program.authority = on_chain.upgrade_authority.map(|a| a.to_string());
+                    program.immutable = on_chain.upgrade_authority.is_none();
+                }
+                Err(e) => {
+                    eprintln!("Warning: could not refresh {}: {e}", program.program_id);
+                }
+            }
+        }
+
+        config.save_state(&state)?;
+    }
+
+    let deployer = if config.deployer_exists() {
+        config.load_deployer().ok().map(|k| k.pubkey().to_string())
+    } else {
+        None
+    };
+
+    let mut programs = state.deployed_programs.clone();
+    match sort {
+        Some(ListSort::Name) => programs.sort_by(|a, b| a.name.cmp(&b.name)),
+        Some(ListSort::Deployed) => programs.sort_by_key(|p| p.deployed_at),
+        Some(ListSort::Upgraded) => programs.sort_by_key(|p| p.last_upgraded.unwrap_or(p.deployed_at)),
+        None => {}
+    }
+    if reverse {
+        programs.reverse();
+    }
+
+    if json {
+        let rows: Vec<serde_json::Value> = programs
+            .iter()
+            .map(|p| serde_json::json!({
+                "name": p.name,
+                "program_id": p.program_id,
+                "cluster": p.cluster.as_deref().unwrap_or(&state.network),
+                "deployed_at": p.deployed_at,
+                "last_upgraded": p.last_upgraded,
+                "immutable": p.immutable,
+                "authority": p.authority,
+                "authority_status": authority_status(p, deployer.as_deref()),
+            }))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+
+    if programs.is_empty() {
+        println!("No managed programs.");
+        println!("\nRun `shield-deploy deploy` to deploy one.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<20} {:<44} {:<12} {:<10} {:<10} {:<9} AUTHORITY",
+        "NAME", "PROGRAM ID", "CLUSTER", "DEPLOYED", "UPGRADED", "IMMUTABLE"
+    );
+    for program in &programs {
+        println!(
+            "{:<20} {:<44} {:<12} {:<10} {:<10} {:<9} {}",
+            program.name.as_deref().unwrap_or("-"),
+            program.program_id,
+            program.cluster.as_deref().unwrap_or(&state.network),
+            format_date(Some(program.deployed_at)),
+            format_date(program.last_upgraded),
+            if program.immutable { "yes" } else { "no" },
+            authority_status(program, deployer.as_deref()),
+        );
+    }
+
+    if !refresh {
+        println!("\nAuthority status is cached; use --refresh to re-check on chain.");
+    }
+
+    Ok(())
+}
+
+fn authority_status(program: &DeployedProgram, deployer: Option<&str>) -> &'static str {
+    if program.immutable {
+        return "none";
+    }
+    match (program.authority.as_deref(), deployer) {
+        (Some(authority), Some(deployer)) if authority == deployer => "deployer",
+        (Some(_), _) => "external",
+        (None, _) => "unknown",
+    }
+}
+
+fn format_date(timestamp: Option<i64>) -> String {
+    timestamp
+        .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
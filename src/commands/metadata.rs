@@ -0,0 +1,415 @@
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_loader_v3_interface::{
+    state::UpgradeableLoaderState,
+    instruction::{self as bpf_loader_upgradeable, UpgradeableLoaderInstruction},
+};
+use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+    instruction::Instruction as SdkInstruction,
+    instruction::AccountMeta,
+};
+use solana_system_interface::instruction as system_instruction;
+use solana_address::Address;
+use solana_pubkey::Pubkey as SolanaPubkeyV2;
+use std::str::FromStr;
+use crate::commands::verify::fetch_program_bytecode;
+use crate::commands::CliProgress;
+use crate::compute::FeeSettings;
+use crate::config::{Config, OperationKind, OperationRecord, ProjectConfig};
+use crate::security_txt::SecurityTxt;
+use crate::utils::*;
+
+/// Smallest metadata account, leaving room for later edits without a resize
+const METADATA_MIN_CAPACITY: usize = 1024;
+
+#[derive(Subcommand)]
+pub enum MetadataAction {
+    /// Publish or update a program's security.txt
+    Publish {
+        /// Program ID the security.txt describes
+        program_id: String,
+        #[command(flatten)]
+        fields: MetadataFields,
+    },
+    /// Print the security.txt published for a program
+    Show {
+        /// Program ID to look up
+        program_id: String,
+        /// Print structured JSON output
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Overrides for values otherwise taken from `shield-deploy config`
+#[derive(Args)]
+pub struct MetadataFields {
+    /// Project name (config: project_name)
+    #[arg(long)]
+    name: Option<String>,
+    /// Project website (config: project_url)
+    #[arg(long)]
+    project_url: Option<String>,
+    /// Comma-separated kind:value contacts (config: security_contacts)
+    #[arg(long)]
+    contacts: Option<String>,
+    /// Disclosure policy URL or text (config: security_policy)
+    #[arg(long)]
+    policy: Option<String>,
+    /// Source repository URL (config: source_code_url)
+    #[arg(long)]
+    source_code: Option<String>,
+    /// Preferred report languages, e.g. "en,de"
+    #[arg(long)]
+    preferred_languages: Option<String>,
+    /// Who audited the program
+    #[arg(long)]
+    auditors: Option<String>,
+    /// Date after which the info should be treated as stale (YYYY-MM-DD)
+    #[arg(long)]
+    expiry: Option<String>,
+}
+
+pub async fn execute(action: MetadataAction) -> Result<()> {
+    match action {
+        MetadataAction::Publish { program_id, fields } => publish(&program_id, fields).await,
+        MetadataAction::Show { program_id, json } => show(&program_id, json).await,
+    }
+}
+
+async fn publish(program_id_str: &str, fields: MetadataFields) -> Result<()> {
+    print_header("Publish security.txt");
+
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    let deployer = config.load_deployer()?;
+    let mut state = config.load_state()?;
+
+    let program_id = Pubkey::from_str(program_id_str).context("Invalid program ID")?;
+    if !state.deployed_programs.iter().any(|p| p.program_id == program_id_str) {
+        anyhow::bail!(
+            "{program_id} is not managed by this project.\n\
+            Run `shield-deploy recover --program-id {program_id}` first."
+        );
+    }
+
+    let security_txt = resolve_fields(fields, &ProjectConfig::load()?)?;
+    security_txt.validate().context("Invalid security.txt")?;
+    let encoded = security_txt.encode();
+
+    let metadata_address = metadata_address(&deployer.pubkey(), &program_id)?;
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    let header_len = UpgradeableLoaderState::size_of_buffer_metadata();
+    let existing = rpc_client
+        .get_account_with_commitment(&metadata_address, rpc_client.commitment()).await?
+        .value;
+
+    if let Some(account) = &existing {
+        let authority = match bincode::deserialize::<UpgradeableLoaderState>(&account.data) {
+            Ok(UpgradeableLoaderState::Buffer { authority_address }) => authority_address,
+            _ => anyhow::bail!("{metadata_address} exists but is not a loader buffer"),
+        };
+        if authority.map(|a| a.to_bytes()) != Some(deployer.pubkey().to_bytes()) {
+            anyhow::bail!("{metadata_address} is not controlled by the private deployer");
+        }
+    }
+
+    let current = existing
+        .as_ref()
+        .and_then(|account| SecurityTxt::find(&account.data[header_len..]));
+
+    if current.as_ref() == Some(&security_txt) {
+        record_address(&config, &mut state, program_id_str, &metadata_address)?;
+        print_success("security.txt is already up to date");
+        println!("\nMetadata account: {metadata_address}");
+        return Ok(());
+    }
+
+    println!("\nProgram:          {program_id}");
+    println!("Metadata account: {metadata_address}");
+    println!();
+    for (key, value) in security_txt.fields() {
+        println!("  {key:<20} {value}");
+    }
+    println!();
+
+    if !prompt_confirmation("Proceed?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let mut record = OperationRecord::new(OperationKind::PublishMetadata, &get_network_name());
+    record.program_id = Some(program_id_str.to_string());
+    record.note = Some(format!("security.txt at {metadata_address}"));
+
+    let balance_before = rpc_client.get_balance(&deployer.pubkey()).await?;
+
+    let capacity = match &existing {
+        Some(account) if account.data.len() - header_len >= encoded.len() => {
+            account.data.len() - header_len
+        }
+        _ => {
+            if existing.is_some() {
+                println!("\n Closing undersized metadata account...");
+                let signature = close_metadata_account(&rpc_client, &deployer, &metadata_address).await?;
+                println!("  ✓ Closed: {signature}");
+                record.signatures.push(signature.to_string());
+            }
+
+            let capacity = encoded.len().next_power_of_two().max(METADATA_MIN_CAPACITY);
+            println!("\n Creating metadata account...");
+            let signature = create_metadata_account(&rpc_client, &deployer, &program_id, capacity).await?;
+            println!("  ✓ Created: {signature}");
+            record.signatures.push(signature.to_string());
+            capacity
+        }
+    };
+
+    // Zero the tail so a shorter update doesn't leave stale fields behind
+    let mut payload = encoded;
+    payload.resize(capacity, 0);
+
+    println!("\n Writing security.txt...");
+    write_missing_chunks(&rpc_client, &FeeSettings::current(), &deployer, &metadata_address, &payload, &CliProgress).await?;
+
+    let balance_after = rpc_client.get_balance(&deployer.pubkey()).await?;
+    record.cost_lamports = Some(balance_before.saturating_sub(balance_after));
+
+    record_address(&config, &mut state, program_id_str, &metadata_address)?;
+    state.operations.push(record);
+    config.save_state(&state)?;
+
+    print_success("security.txt published");
+    println!("\nMetadata account: {metadata_address}");
+    println!("Authority:        private deployer");
+    println!("Read it back with `shield-deploy metadata show {program_id}`");
+
+    Ok(())
+}
+
+async fn show(program_id_str: &str, json: bool) -> Result<()> {
+    let program_id = Pubkey::from_str(program_id_str).context("Invalid program ID")?;
+    let config = Config::new()?;
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    let on_chain = fetch_program_bytecode(&rpc_client, &program_id).await?;
+
+    // A security.txt compiled into the program takes precedence
+    let (security_txt, source) = match SecurityTxt::find(&on_chain.bytecode) {
+        Some(txt) => (txt, "embedded in program".to_string()),
+        None => {
+            let recorded = if config.state_path().exists() {
+                config.load_state()?
+                    .program(&program_id_str)
+                    .and_then(|p| p.metadata_address.clone())
+            } else {
+                None
+            };
+
+            let candidates = recorded
+                .map(|address| Pubkey::from_str(&address))
+                .transpose()?
+                .into_iter()
+                .chain(
+                    on_chain.upgrade_authority
+                        .map(|authority| metadata_address(&authority, &program_id))
+                        .transpose()?,
+                );
+
+            let header_len = UpgradeableLoaderState::size_of_buffer_metadata();
+            let mut found = None;
+            for address in candidates {
+                let Ok(account) = rpc_client.get_account(&address).await else { continue };
+                if let Some(txt) = account.data.get(header_len..).and_then(SecurityTxt::find) {
+                    found = Some((txt, address.to_string()));
+                    break;
+                }
+            }
+            found.ok_or_else(|| anyhow::anyhow!("No security.txt found for {program_id}"))?
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "program_id": program_id_str,
+            "source": source,
+            "security_txt": security_txt,
+        }))?);
+        return Ok(());
+    }
+
+    print_header("security.txt");
+    println!("\nProgram: {program_id}");
+    println!("Source:  {source}\n");
+    for (key, value) in security_txt.fields() {
+        println!("  {key:<20} {value}");
+    }
+
+    if let Err(e) = security_txt.validate() {
+        println!();
+        print_warning(&format!("Published security.txt is not valid: {e}"));
+    }
+
+    Ok(())
+}
+
+/// Flags first, then config; the four required fields must come from somewhere
+fn resolve_fields(fields: MetadataFields, project_config: &ProjectConfig) -> Result<SecurityTxt> {
+    let required = |flag: Option<String>, configured: &Option<String>, key: &str, option: &str| {
+        flag.or_else(|| configured.clone()).ok_or_else(|| anyhow::anyhow!(
+            "Missing {key}.\n\
+            Pass --{option} or run `shield-deploy config set {key} <value>`"
+        ))
+    };
+
+    Ok(SecurityTxt {
+        name: required(fields.name, &project_config.project_name, "project_name", "name")?,
+        project_url: required(fields.project_url, &project_config.project_url, "project_url", "project-url")?,
+        contacts: required(fields.contacts, &project_config.security_contacts, "security_contacts", "contacts")?,
+        policy: required(fields.policy, &project_config.security_policy, "security_policy", "policy")?,
+        preferred_languages: fields.preferred_languages,
+        source_code: fields.source_code.or_else(|| project_config.source_code_url.clone()),
+        auditors: fields.auditors,
+        expiry: fields.expiry,
+    })
+}
+
+/// Deterministic metadata account, so readers can find it from the program's authority
+pub fn metadata_address(authority: &Pubkey, program_id: &Pubkey) -> Result<Pubkey> {
+    let loader_id = Pubkey::new_from_array(LOADER_ID.to_bytes());
+    Pubkey::create_with_seed(authority, &metadata_seed(program_id), &loader_id)
+        .map_err(|e| anyhow::anyhow!("Failed to derive metadata address: {e}"))
+}
+
+/// Seeds are capped at 32 bytes, so use a prefix of the program ID
+fn metadata_seed(program_id: &Pubkey) -> String {
+    let id = program_id.to_string();
+    format!("sec:{}", &id[..28])
+}
+
+fn record_address(
+    config: &Config,
+    state: &mut crate::config::ProjectState,
+    program_id: &str,
+    metadata_address: &Pubkey,
+) -> Result<()> {
+    if let Some(program) = state.program_mut(program_id) {
+        if program.metadata_address.as_deref() != Some(&metadata_address.to_string()) {
+            program.metadata_address = Some(metadata_address.to_string());
+            config.save_state(state)?;
+        }
+    }
+    Ok(())
+}
+
+/// Create the seeded buffer account and initialize it with the deployer as authority
+async fn create_metadata_account(
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    program_id: &Pubkey,
+    capacity: usize,
+) -> Result<Signature> {
+    let loader_id = Pubkey::new_from_array(LOADER_ID.to_bytes());
+    let address = metadata_address(&deployer.pubkey(), program_id)?;
+    let size = UpgradeableLoaderState::size_of_buffer(capacity);
+    let lamports = rpc_client
+        .get_minimum_balance_for_rent_exemption(size).await
+        .context("Failed to get rent exemption for metadata account")?;
+
+    let create_account_ix = system_instruction::create_account_with_seed(
+        &Address::from(deployer.pubkey().to_bytes()),
+        &Address::from(address.to_bytes()),
+        &Address::from(deployer.pubkey().to_bytes()),
+        &metadata_seed(program_id),
+        lamports,
+        size as u64,
+        &Address::from(loader_id.to_bytes()),
+    );
+
+    let create_account_ix = SdkInstruction {
+        program_id: Pubkey::from(create_account_ix.program_id.to_bytes()),
+        accounts: create_account_ix
+            .accounts
+            .iter()
+            .map(|acc| AccountMeta {
+                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            })
+            .collect(),
+        data: create_account_ix.data,
+    };
+
+    let initialize_ix = SdkInstruction {
+        program_id: loader_id,
+        accounts: vec![
+            AccountMeta::new(address, false),
+            AccountMeta::new_readonly(deployer.pubkey(), false),
+        ],
+        data: bincode::serialize(&UpgradeableLoaderInstruction::InitializeBuffer)?,
+    };
+
+    let recent_blockhash = rpc_client.get_latest_blockhash().await?;
+    let mut transaction = Transaction::new_with_payer(
+        &[create_account_ix, initialize_ix],
+        Some(&deployer.pubkey()),
+    );
+    transaction.sign(&[deployer], recent_blockhash);
+
+    send_and_confirm(rpc_client, &transaction).await
+        .context("Failed to create metadata account")
+}
+
+async fn close_metadata_account(
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    address: &Pubkey,
+) -> Result<Signature> {
+    let deployer_v2 = SolanaPubkeyV2::new_from_array(deployer.pubkey().to_bytes());
+    let address_v2 = SolanaPubkeyV2::new_from_array(address.to_bytes());
+
+    let close_ix = bpf_loader_upgradeable::close(&address_v2, &deployer_v2, &deployer_v2);
+    let close_ix = SdkInstruction {
+        program_id: Pubkey::from(close_ix.program_id.to_bytes()),
+        accounts: close_ix
+            .accounts
+            .iter()
+            .map(|acc| AccountMeta {
+                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            })
+            .collect(),
+        data: close_ix.data,
+    };
+
+    let recent_blockhash = rpc_client.get_latest_blockhash().await?;
+    let mut transaction = Transaction::new_with_payer(&[close_ix], Some(&deployer.pubkey()));
+    transaction.sign(&[deployer], recent_blockhash);
+
+    send_and_confirm(rpc_client, &transaction).await
+        .context("Failed to close metadata account")
+}
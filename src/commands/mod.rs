@@ -6,3 +6,54 @@ pub mod status;
 pub mod rotate;
 pub mod transfer_authority;
 pub mod finalize;
+pub mod verify;
+pub mod withdraw;
+pub mod balance;
+pub mod list;
+pub mod doctor;
+pub mod export_deployer;
+pub mod import_deployer;
+pub mod history;
+pub mod show;
+pub mod idl;
+pub mod write_buffer;
+pub mod prepare_upgrade;
+pub mod estimate;
+pub mod config;
+pub mod clean;
+pub mod completions;
+pub mod audit;
+pub mod recover;
+pub mod monitor;
+pub mod metadata;
+pub mod grind;
+pub mod simulate;
+pub mod update;
+pub mod anchor;
+pub mod backup;
+pub mod restore;
+pub mod multisig;
+pub mod pool_stats;
+pub mod programs;
+pub mod tx;
+pub mod state;
+pub mod quickstart;
+pub mod keys;
+pub mod inspect_buffer;
+pub mod buffers;
+pub mod nonce;
+
+use crate::project::Progress;
+
+/// Prints library progress the way the rest of the CLI does
+pub struct CliProgress;
+
+impl Progress for CliProgress {
+    fn step(&self, message: &str) {
+        println!("  ↳ {message}");
+    }
+
+    fn interactive(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
+use solana_sdk::{
+    pubkey::Pubkey,
+};
+use std::str::FromStr;
+use crate::config::{Config, ProjectConfig};
+use crate::utils::*;
+
+#[derive(Serialize, Clone, PartialEq, Eq)]
+struct Drift {
+    program_id: String,
+    name: Option<String>,
+    kind: &'static str,
+    expected: Option<String>,
+    found: Option<String>,
+}
+
+/// What the chain currently says about a program
+struct Observed {
+    authority: Option<String>,
+    bytecode_hash: String,
+}
+
+pub async fn execute(once: bool, interval: String, acknowledge: bool) -> Result<()> {
+    let interval = parse_duration(&interval)?;
+    let config = Config::new()?;
+    let webhook = ProjectConfig::load()?.monitor_webhook;
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    if config.load_state()?.deployed_programs.is_empty() {
+        println!("No managed programs to monitor.");
+        return Ok(());
+    }
+
+    if acknowledge {
+        return acknowledge_drift(&config, &rpc_client).await;
+    }
+
+    let mut last_alerted: Vec<Drift> = Vec::new();
+    loop {
+        let drift = check_programs(&config, &rpc_client).await?;
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S");
+
+        if drift.is_empty() {
+            println!("[{now}] ✓ No drift");
+        } else {
+            for d in &drift {
+                println!(
+                    "[{now}] ✗ {} {} changed: expected {}, found {}",
+                    d.name.as_deref().unwrap_or(&d.program_id),
+                    d.kind,
+                    d.expected.as_deref().unwrap_or("none"),
+                    d.found.as_deref().unwrap_or("none"),
+                );
+            }
+
+            // Only notify when the set of findings changes, not on every cycle
+            if drift != last_alerted {
+                if let Some(url) = &webhook {
+                    if let Err(e) = post_webhook(url, &drift).await {
+                        print_warning(&format!("Webhook delivery failed: {e}"));
+                    }
+                }
+            }
+        }
+        last_alerted = drift;
+
+        if once {
+            break;
+        }
+        tokio::time::sleep(interval).await;
+    }
+
+    if !last_alerted.is_empty() {
+        anyhow::bail!(
+            "Drift detected on {} program(s).\n\
+            Run `shield-deploy monitor --acknowledge` once you have confirmed the change.",
+            last_alerted.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Compare each managed program against state, one getAccount per program
+async fn check_programs(config: &Config, rpc_client: &RpcClient) -> Result<Vec<Drift>> {
+    let state = config.load_state()?;
+    let mut drift = Vec::new();
+
+    for program in &state.deployed_programs {
+        let drift_of = |kind, expected: Option<String>, found: Option<String>| Drift {
+            program_id: program.program_id.clone(),
+            name: program.name.clone(),
+            kind,
+            expected,
+            found,
+        };
+
+        let observed = match observe(rpc_client, &program.program_id).await {
+            Ok(Some(observed)) => observed,
+            Ok(None) => {
+                drift.push(drift_of("existence", Some("ProgramData".into()), None));
+                continue;
+            }
+            Err(e) => {
+                print_warning(&format!("Could not check {}: {e}", program.program_id));
+                continue;
+            }
+        };
+
+        let expected_authority = if program.immutable { None } else { program.authority.clone() };
+        if (program.immutable || program.authority.is_some()) && observed.authority != expected_authority {
+            drift.push(drift_of("authority", expected_authority, observed.authority.clone()));
+        }
+
+        if let Some(expected_hash) = &program.bytecode_hash {
+            if *expected_hash != observed.bytecode_hash {
+                drift.push(drift_of("bytecode", Some(expected_hash.clone()), Some(observed.bytecode_hash)));
+            }
+        }
+    }
+
+    Ok(drift)
+}
+
+async fn observe(rpc_client: &RpcClient, program_id: &str) -> Result<Option<Observed>> {
+    let program_id = Pubkey::from_str(program_id).context("Invalid program ID in state")?;
+    let loader_id = Pubkey::new_from_array(LOADER_ID.to_bytes());
+    let (programdata_address, _) = Pubkey::find_program_address(&[program_id.as_ref()], &loader_id);
+
+    let Some(account) = rpc_client
+        .get_account_with_commitment(&programdata_address, rpc_client.commitment()).await?
+        .value
+    else {
+        return Ok(None);
+    };
+
+    let authority = match bincode::deserialize::<UpgradeableLoaderState>(&account.data)? {
+        UpgradeableLoaderState::ProgramData { upgrade_authority_address, .. } => {
+            upgrade_authority_address.map(|a| a.to_string())
+        }
+        _ => anyhow::bail!("Invalid ProgramData account"),
+    };
+
+    let header_len = UpgradeableLoaderState::size_of_programdata_metadata();
+    let bytecode = strip_trailing_zeros(account.data.get(header_len..).unwrap_or_default());
+
+    Ok(Some(Observed {
+        authority,
+        bytecode_hash: sha256_hex(bytecode),
+    }))
+}
+
+/// Accept the current on-chain authority and bytecode as the new baseline
+async fn acknowledge_drift(config: &Config, rpc_client: &RpcClient) -> Result<()> {
+    let mut state = config.load_state()?;
+    let mut updated = 0;
+
+    for program in state.deployed_programs.iter_mut() {
+        let Ok(Some(observed)) = observe(rpc_client, &program.program_id).await else { continue };
+
+        let changed = program.authority != observed.authority
+            || program.immutable != observed.authority.is_none()
+            || program.bytecode_hash.as_deref() != Some(observed.bytecode_hash.as_str());
+
+        if changed {
+            println!("• {} acknowledged", program.name.as_deref().unwrap_or(&program.program_id));
+            program.immutable = observed.authority.is_none();
+            program.authority = observed.authority;
+            program.bytecode_hash = Some(observed.bytecode_hash);
+            updated += 1;
+        }
+    }
+
+    config.save_state(&state)?;
+    print_success(&format!("Baseline updated for {updated} program(s)"));
+
+    Ok(())
+}
+
+async fn post_webhook(url: &str, drift: &[Drift]) -> Result<()> {
+    let payload = serde_json::json!({
+        "source": "shield-deploy",
+        "event": "drift",
+        "drift": drift,
+    });
+
+    reqwest::Client::new()
+        .post(url)
+        .json(&payload)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
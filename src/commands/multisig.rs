@@ -0,0 +1,523 @@
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use clap::Subcommand;
+use sha2::{Digest, Sha256};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_sdk_ids::system_program;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+    instruction::Instruction as SdkInstruction,
+    instruction::AccountMeta,
+};
+use std::fs;
+use std::str::FromStr;
+use crate::commands::prepare_upgrade::upgrade_instruction;
+use crate::commands::verify::fetch_program_bytecode;
+use crate::compute::FeeSettings;
+use crate::config::{BufferStatus, Config, OperationKind, OperationRecord};
+use crate::loader::set_buffer_authority;
+use crate::utils::*;
+
+/// Squads v4 program
+const SQUADS_PROGRAM_ID: &str = "SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf";
+const SEED_PREFIX: &[u8] = b"multisig";
+
+/// Member permission bits
+const PERMISSION_INITIATE: u8 = 1;
+const PERMISSION_VOTE: u8 = 2;
+
+#[derive(Subcommand)]
+pub enum MultisigAction {
+    /// Propose upgrading a program from a prewritten buffer
+    Propose {
+        /// Squads multisig account
+        multisig: String,
+        /// Program to upgrade
+        program_id: String,
+        /// Buffer holding the new bytecode (from `write-buffer` or `prepare-upgrade`)
+        #[arg(long)]
+        buffer: String,
+        /// Vault index of the upgrade authority
+        #[arg(long, default_value_t = 0)]
+        vault_index: u8,
+        /// Recipient of the buffer's rent (defaults to the vault)
+        #[arg(long)]
+        spill: Option<String>,
+    },
+    /// Show a proposal's status and approvals
+    Status {
+        /// Proposal account address
+        proposal: String,
+    },
+}
+
+/// Fields of a Squads multisig account this command needs
+struct Multisig {
+    threshold: u16,
+    transaction_index: u64,
+    members: Vec<(Pubkey, u8)>,
+}
+
+struct Proposal {
+    multisig: Pubkey,
+    transaction_index: u64,
+    status: &'static str,
+    approved: Vec<Pubkey>,
+    rejected: Vec<Pubkey>,
+}
+
+pub async fn execute(action: MultisigAction) -> Result<()> {
+    match action {
+        MultisigAction::Propose { multisig, program_id, buffer, vault_index, spill } => {
+            propose(&multisig, &program_id, &buffer, vault_index, spill.as_deref()).await
+        }
+        MultisigAction::Status { proposal } => status(&proposal).await,
+    }
+}
+
+async fn propose(
+    multisig_str: &str,
+    program_id_str: &str,
+    buffer_str: &str,
+    vault_index: u8,
+    spill: Option<&str>,
+) -> Result<()> {
+    print_header("Propose Multisig Upgrade");
+
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    let deployer = config.load_deployer()?;
+    let squads = squads_program_id();
+
+    let multisig_address = Pubkey::from_str(multisig_str).context("Invalid multisig address")?;
+    let program_id = Pubkey::from_str(program_id_str).context("Invalid program ID")?;
+    let buffer = Pubkey::from_str(buffer_str).context("Invalid buffer address")?;
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    let multisig_account = rpc_client
+        .get_account(&multisig_address).await
+        .context("Multisig account not found")?;
+    if multisig_account.owner != squads {
+        anyhow::bail!("{multisig_address} is not a Squads v4 multisig");
+    }
+    let multisig = parse_multisig(&multisig_account.data)?;
+
+    let vault = vault_address(&multisig_address, vault_index);
+
+    // The vault must already be able to upgrade the program
+    let on_chain = fetch_program_bytecode(&rpc_client, &program_id).await?;
+    if on_chain.upgrade_authority != Some(vault) {
+        anyhow::bail!(
+            "Vault {vault} is not the upgrade authority of {program_id} (authority: {}).\n\
+            Transfer authority to the vault first or pick another --vault-index.",
+            on_chain.upgrade_authority.map(|a| a.to_string()).unwrap_or_else(|| "none".into())
+        );
+    }
+
+    let buffer_account = rpc_client.get_account(&buffer).await.context("Buffer account not found")?;
+    let buffer_authority = match bincode::deserialize::<UpgradeableLoaderState>(&buffer_account.data)? {
+        UpgradeableLoaderState::Buffer { authority_address } => {
+            authority_address.map(|a| Pubkey::from(a.to_bytes()))
+        }
+        _ => anyhow::bail!("{buffer} is not a loader buffer"),
+    };
+    let needs_handoff = match buffer_authority {
+        Some(authority) if authority == vault => false,
+        Some(authority) if authority == deployer.pubkey() => true,
+        _ => anyhow::bail!("Buffer {buffer} is controlled by neither the vault nor the private deployer"),
+    };
+
+    let spill = spill
+        .map(Pubkey::from_str)
+        .transpose()
+        .context("Invalid spill address")?
+        .unwrap_or(vault);
+
+    let transaction_index = multisig.transaction_index + 1;
+    let transaction_address = transaction_address(&multisig_address, transaction_index);
+    let proposal_address = proposal_address(&multisig_address, transaction_index);
+
+    let upgrade_ix = upgrade_instruction(&program_id, &buffer, &vault, &spill).await;
+    let vault_message = compile_vault_message(&vault, &upgrade_ix);
+
+    let permissions = multisig
+        .members
+        .iter()
+        .find(|(key, _)| *key == deployer.pubkey())
+        .map(|(_, permissions)| *permissions);
+
+    println!("\nMultisig:     {multisig_address}");
+    println!("Vault:        {vault} (index {vault_index})");
+    println!("Program:      {program_id}");
+    println!("Buffer:       {buffer}");
+    println!("Threshold:    {} of {}", multisig.threshold, multisig.members.len());
+    println!("Transaction:  #{transaction_index}");
+    println!("Proposal:     {proposal_address}\n");
+
+    let Some(permissions) = permissions.filter(|p| p & PERMISSION_INITIATE != 0) else {
+        print_warning("The private deployer is not a member with Initiate permission");
+        let out = config.proposals_dir().join(format!("squads-{proposal_address}.json"));
+        fs::create_dir_all(config.proposals_dir())
+            .context("Failed to create proposals directory")?;
+        fs::write(&out, serde_json::to_string_pretty(&serde_json::json!({
+            "squads_program": squads.to_string(),
+            "multisig": multisig_address.to_string(),
+            "vault": vault.to_string(),
+            "vault_index": vault_index,
+            "transaction_index": transaction_index,
+            "transaction": transaction_address.to_string(),
+            "proposal": proposal_address.to_string(),
+            "buffer_handoff_required": needs_handoff,
+            "transaction_message_base64": BASE64.encode(&vault_message),
+        }))?)
+        .context("Failed to write proposal")?;
+
+        println!("\nUnsigned proposal written to {}", out.display());
+        println!("→ A member can submit it with vault_transaction_create + proposal_create");
+        return Ok(());
+    };
+
+    if needs_handoff {
+        println!("The buffer will be handed from the private deployer to the vault.");
+    }
+    println!("The private deployer will create the proposal{}.\n",
+        if permissions & PERMISSION_VOTE != 0 { " and approve it" } else { "" });
+
+    if !prompt_confirmation("Proceed?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let mut record = OperationRecord::new(OperationKind::MultisigPropose, &get_network_name());
+    record.program_id = Some(program_id.to_string());
+    record.note = Some(format!("proposal {proposal_address} on {multisig_address}"));
+
+    if needs_handoff {
+        println!("\n Handing buffer to the vault...");
+        let signature = set_buffer_authority(&rpc_client, &FeeSettings::current(), &deployer, &buffer, &vault).await?;
+        println!("  ✓ Authority set: {signature}");
+        record.signatures.push(signature.to_string());
+    }
+
+    println!("\n Creating proposal...");
+    let mut instructions = vec![
+        vault_transaction_create_ix(&multisig_address, &transaction_address, &deployer.pubkey(), vault_index, &vault_message),
+        proposal_create_ix(&multisig_address, &proposal_address, &deployer.pubkey(), transaction_index),
+    ];
+    if permissions & PERMISSION_VOTE != 0 {
+        instructions.push(proposal_approve_ix(&multisig_address, &proposal_address, &deployer.pubkey()));
+    }
+    let signature = send_instructions(&rpc_client, &deployer, &instructions).await?;
+    println!("  ✓ Proposal created: {signature}");
+    record.signatures.push(signature.to_string());
+
+    let mut state = config.load_state()?;
+    if let Some(buffer_record) = state.buffers.iter_mut().find(|b| b.address == buffer.to_string()) {
+        buffer_record.status = BufferStatus::HandedOff;
+        buffer_record.authority = vault.to_string();
+        buffer_record.program_id = Some(program_id.to_string());
+    }
+    state.operations.push(record);
+    config.save_state(&state)?;
+
+    let approvals = usize::from(permissions & PERMISSION_VOTE != 0);
+    print_success("Upgrade proposed");
+    println!("\nProposal:  {proposal_address}");
+    println!("Approvals: {approvals} of {} required", multisig.threshold);
+    println!("→ Track it with `shield-deploy multisig status {proposal_address}`");
+
+    Ok(())
+}
+
+async fn status(proposal_str: &str) -> Result<()> {
+    let proposal_address = Pubkey::from_str(proposal_str).context("Invalid proposal address")?;
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    let account = rpc_client.get_account(&proposal_address).await.context("Proposal account not found")?;
+    if account.owner != squads_program_id() {
+        anyhow::bail!("{proposal_address} is not a Squads v4 account");
+    }
+    let proposal = parse_proposal(&account.data)?;
+    let multisig = parse_multisig(&rpc_client.get_account(&proposal.multisig).await?.data)?;
+
+    print_header("Multisig Proposal");
+    println!("\nProposal:    {proposal_address}");
+    println!("Multisig:    {}", proposal.multisig);
+    println!("Transaction: #{}", proposal.transaction_index);
+    println!("Status:      {}", proposal.status);
+    println!("Approvals:   {} of {} required", proposal.approved.len(), multisig.threshold);
+    for member in &proposal.approved {
+        println!("  ✓ {member}");
+    }
+    for member in &proposal.rejected {
+        println!("  ✗ {member}");
+    }
+
+    Ok(())
+}
+
+fn squads_program_id() -> Pubkey {
+    Pubkey::from_str(SQUADS_PROGRAM_ID).expect("valid Squads program ID")
+}
+
+fn vault_address(multisig: &Pubkey, vault_index: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[SEED_PREFIX, multisig.as_ref(), b"vault", &[vault_index]],
+        &squads_program_id(),
+    ).0
+}
+
+fn transaction_address(multisig: &Pubkey, index: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[SEED_PREFIX, multisig.as_ref(), b"transaction", &index.to_le_bytes()],
+        &squads_program_id(),
+    ).0
+}
+
+fn proposal_address(multisig: &Pubkey, index: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[SEED_PREFIX, multisig.as_ref(), b"transaction", &index.to_le_bytes(), b"proposal"],
+        &squads_program_id(),
+    ).0
+}
+
+/// Anchor instruction discriminator
+fn discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("global:{name}").as_bytes());
+    let mut out = [0u8; 8];
+    out.copy_from_slice(&hash[..8]);
+    out
+}
+
+/// Squads `TransactionMessage` for one instruction signed by the vault
+///
+/// Keys are ordered signer, writable non-signers, then readonly non-signers,
+/// with the vault as the only signer.
+fn compile_vault_message(vault: &Pubkey, ix: &SdkInstruction) -> Vec<u8> {
+    let mut writable = Vec::new();
+    let mut readonly = Vec::new();
+    for meta in &ix.accounts {
+        if meta.pubkey == *vault {
+            continue;
+        }
+        let list = if meta.is_writable { &mut writable } else { &mut readonly };
+        if !list.contains(&meta.pubkey) {
+            list.push(meta.pubkey);
+        }
+    }
+    readonly.retain(|key| !writable.contains(key));
+    if !readonly.contains(&ix.program_id) {
+        readonly.push(ix.program_id);
+    }
+
+    let keys: Vec<Pubkey> = std::iter::once(*vault)
+        .chain(writable.iter().copied())
+        .chain(readonly.iter().copied())
+        .collect();
+    let index_of = |key: &Pubkey| keys.iter().position(|k| k == key).unwrap_or(0) as u8;
+
+    let mut data = vec![1, 1, writable.len() as u8];
+    data.push(keys.len() as u8);
+    for key in &keys {
+        data.extend_from_slice(key.as_ref());
+    }
+
+    // One compiled instruction
+    data.push(1);
+    data.push(index_of(&ix.program_id));
+    data.push(ix.accounts.len() as u8);
+    data.extend(ix.accounts.iter().map(|meta| index_of(&meta.pubkey)));
+    data.extend_from_slice(&(ix.data.len() as u16).to_le_bytes());
+    data.extend_from_slice(&ix.data);
+
+    // No address table lookups
+    data.push(0);
+    data
+}
+
+fn vault_transaction_create_ix(
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    creator: &Pubkey,
+    vault_index: u8,
+    message: &[u8],
+) -> SdkInstruction {
+    let mut data = discriminator("vault_transaction_create").to_vec();
+    data.push(vault_index);
+    data.push(0); // ephemeral signers
+    data.extend_from_slice(&(message.len() as u32).to_le_bytes());
+    data.extend_from_slice(message);
+    data.push(0); // memo: None
+
+    SdkInstruction {
+        program_id: squads_program_id(),
+        accounts: vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*transaction, false),
+            AccountMeta::new_readonly(*creator, true),
+            AccountMeta::new(*creator, true),
+            AccountMeta::new_readonly(Pubkey::from(system_program::ID.to_bytes()), false),
+        ],
+        data,
+    }
+}
+
+fn proposal_create_ix(multisig: &Pubkey, proposal: &Pubkey, creator: &Pubkey, index: u64) -> SdkInstruction {
+    let mut data = discriminator("proposal_create").to_vec();
+    data.extend_from_slice(&index.to_le_bytes());
+    data.push(0); // draft: false
+
+    SdkInstruction {
+        program_id: squads_program_id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(*proposal, false),
+            AccountMeta::new_readonly(*creator, true),
+            AccountMeta::new(*creator, true),
+            AccountMeta::new_readonly(Pubkey::from(system_program::ID.to_bytes()), false),
+        ],
+        data,
+    }
+}
+
+fn proposal_approve_ix(multisig: &Pubkey, proposal: &Pubkey, member: &Pubkey) -> SdkInstruction {
+    let mut data = discriminator("proposal_approve").to_vec();
+    data.push(0); // memo: None
+
+    SdkInstruction {
+        program_id: squads_program_id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(*member, true),
+            AccountMeta::new(*proposal, false),
+        ],
+        data,
+    }
+}
+
+async fn send_instructions(
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    instructions: &[SdkInstruction],
+) -> Result<Signature> {
+    let recent_blockhash = rpc_client.get_latest_blockhash().await?;
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&deployer.pubkey()));
+    transaction.sign(&[deployer], recent_blockhash);
+
+    send_and_confirm(rpc_client, &transaction).await
+        .context("Failed to create proposal")
+}
+
+/// Minimal borsh reader for Squads accounts
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        // Skip the Anchor account discriminator
+        Self { data, offset: 8 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self.data
+            .get(self.offset..self.offset + len)
+            .ok_or_else(|| anyhow::anyhow!("Squads account data is truncated"))?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey> {
+        Ok(Pubkey::try_from(self.take(32)?)?)
+    }
+
+    fn pubkeys(&mut self) -> Result<Vec<Pubkey>> {
+        let len = self.u32()?;
+        (0..len).map(|_| self.pubkey()).collect()
+    }
+}
+
+fn parse_multisig(data: &[u8]) -> Result<Multisig> {
+    let mut reader = Reader::new(data);
+    reader.pubkey()?; // create_key
+    reader.pubkey()?; // config_authority
+    let threshold = reader.u16()?;
+    reader.u32()?; // time_lock
+    let transaction_index = reader.u64()?;
+    reader.u64()?; // stale_transaction_index
+    if reader.u8()? == 1 {
+        reader.pubkey()?; // rent_collector
+    }
+    reader.u8()?; // bump
+
+    let member_count = reader.u32()?;
+    let members = (0..member_count)
+        .map(|_| Ok((reader.pubkey()?, reader.u8()?)))
+        .collect::<Result<_>>()?;
+
+    Ok(Multisig { threshold, transaction_index, members })
+}
+
+fn parse_proposal(data: &[u8]) -> Result<Proposal> {
+    let mut reader = Reader::new(data);
+    let multisig = reader.pubkey()?;
+    let transaction_index = reader.u64()?;
+
+    let status = match reader.u8()? {
+        0 => "draft",
+        1 => "active",
+        2 => "rejected",
+        3 => "approved",
+        4 => "executing",
+        5 => "executed",
+        6 => "cancelled",
+        other => anyhow::bail!("Unknown proposal status {other}"),
+    };
+    // Every status except the deprecated Executing carries a timestamp
+    if status != "executing" {
+        reader.take(8)?;
+    }
+    reader.u8()?; // bump
+
+    let approved = reader.pubkeys()?;
+    let rejected = reader.pubkeys()?;
+
+    Ok(Proposal { multisig, transaction_index, status, approved, rejected })
+}
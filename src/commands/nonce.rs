@@ -0,0 +1,239 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use solana_address::Address;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction as SdkInstruction},
+    nonce::state::State as NonceAccountState,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use solana_system_interface::instruction as system_instruction;
+use std::str::FromStr;
+use crate::compute::{with_compute_budget, TxClass};
+use crate::config::{Config, NonceRecord, OperationKind, OperationRecord};
+use crate::network::confirm_mainnet;
+use crate::utils::*;
+
+#[derive(Subcommand)]
+pub enum NonceAction {
+    /// Create a durable nonce account funded and controlled by the deployer
+    Create,
+    /// List the nonce accounts this project created
+    List,
+    /// Close a nonce account and return its rent to the deployer
+    Close {
+        /// Nonce account address (defaults to the only one on this network)
+        address: Option<String>,
+    },
+}
+
+pub async fn execute(action: NonceAction) -> Result<()> {
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    let rpc_client = RpcClient::new_with_commitment(get_rpc_url()?, commitment());
+    match action {
+        NonceAction::Create => create(&config, &rpc_client).await,
+        NonceAction::List => list(&config, &rpc_client).await,
+        NonceAction::Close { address } => close(&config, &rpc_client, address).await,
+    }
+}
+
+async fn create(config: &Config, rpc_client: &RpcClient) -> Result<()> {
+    print_header("Create Nonce Account");
+
+    let deployer = config.load_deployer()?;
+    let nonce_keypair = Keypair::new();
+    let nonce = nonce_keypair.pubkey();
+    let rent = with_retry(|| rpc_client.get_minimum_balance_for_rent_exemption(NonceAccountState::size())).await?;
+
+    println!("\nAddress:   {nonce}");
+    println!("Authority: {} (deployer)", deployer.pubkey());
+    println!("Rent:      {} (returned by `nonce close`)\n", format_sol(rent));
+
+    confirm_mainnet("Creating a nonce account", Some(rent)).await?;
+    if !prompt_confirmation("Create this nonce account?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let instructions: Vec<SdkInstruction> = system_instruction::create_nonce_account(
+        &Address::from(deployer.pubkey().to_bytes()),
+        &Address::from(nonce.to_bytes()),
+        &Address::from(deployer.pubkey().to_bytes()),
+        rent,
+    )
+    .into_iter()
+    .map(|ix| SdkInstruction {
+        program_id: Pubkey::from(ix.program_id.to_bytes()),
+        accounts: ix
+            .accounts
+            .iter()
+            .map(|acc| AccountMeta {
+                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            })
+            .collect(),
+        data: ix.data,
+    })
+    .collect();
+
+    let balance_before = balance_of(rpc_client, &deployer.pubkey()).await?;
+    let recent_blockhash = with_retry(|| rpc_client.get_latest_blockhash()).await?;
+    let mut transaction = Transaction::new_with_payer(
+        &with_compute_budget(rpc_client, TxClass::Transfer, &deployer.pubkey(), &instructions).await,
+        Some(&deployer.pubkey()),
+    );
+    transaction.sign(&[&deployer, &nonce_keypair], recent_blockhash);
+
+    let signature = send_and_confirm(rpc_client, &transaction).await
+        .context("Failed to create nonce account")?;
+    let balance = balance_of(rpc_client, &deployer.pubkey()).await?;
+
+    let mut state = config.load_state()?;
+    state.nonce_accounts.push(NonceRecord {
+        address: nonce.to_string(),
+        network: get_network_name(),
+        created_at: chrono::Utc::now().timestamp(),
+        lamports: rent,
+    });
+    let mut record = OperationRecord::new(OperationKind::CreateNonce, &get_network_name());
+    record.signatures = vec![signature.to_string()];
+    record.cost_lamports = Some(balance_before.saturating_sub(balance));
+    record.note = Some(nonce.to_string());
+    state.operations.push(record);
+    state.last_balance = balance;
+    config.save_state(&state)?;
+
+    print_success(&format!("Nonce account created: {nonce}"));
+    println!("\n→ Sign final transactions with it: `shield-deploy deploy --nonce {nonce}`");
+    println!("  Also accepted by upgrade, transfer-authority and finalize");
+
+    Ok(())
+}
+
+async fn list(config: &Config, rpc_client: &RpcClient) -> Result<()> {
+    print_header("Nonce Accounts");
+
+    let state = config.load_state()?;
+    let network = get_network_name();
+    let records: Vec<&NonceRecord> = state.nonce_accounts.iter().filter(|n| n.network == network).collect();
+
+    println!();
+    if records.is_empty() {
+        println!("No nonce accounts on {network}.");
+        println!("→ Create one with `shield-deploy nonce create`");
+        return Ok(());
+    }
+
+    for record in records {
+        println!("{}", record.address);
+        let Ok(nonce) = Pubkey::from_str(&record.address) else { continue };
+        match nonce_account(rpc_client, &nonce).await {
+            Ok(account) => {
+                println!("  ↳ Nonce:     {}", account.blockhash);
+                println!("  ↳ Authority: {}", account.authority);
+                println!("  ↳ Balance:   {}", format_sol(account.lamports));
+            }
+            Err(e) => println!("  ↳ {e:#}"),
+        }
+    }
+
+    Ok(())
+}
+
+async fn close(config: &Config, rpc_client: &RpcClient, address: Option<String>) -> Result<()> {
+    print_header("Close Nonce Account");
+
+    let deployer = config.load_deployer()?;
+    let mut state = config.load_state()?;
+    let network = get_network_name();
+
+    let address = match address {
+        Some(address) => address,
+        None => {
+            let on_network: Vec<&NonceRecord> = state.nonce_accounts.iter().filter(|n| n.network == network).collect();
+            match on_network.as_slice() {
+                [only] => only.address.clone(),
+                [] => anyhow::bail!("No nonce accounts recorded on {network}"),
+                _ => anyhow::bail!(
+                    "{} nonce accounts on {network}; pass the address to close.\n\
+                    See `shield-deploy nonce list`.",
+                    on_network.len()
+                ),
+            }
+        }
+    };
+    let nonce = Pubkey::from_str(&address).with_context(|| format!("Invalid nonce account: {address}"))?;
+
+    let account = nonce_account(rpc_client, &nonce).await?;
+    if account.authority != deployer.pubkey() {
+        anyhow::bail!(
+            "The deployer is not the authority of {nonce} (authority: {}).",
+            account.authority
+        );
+    }
+
+    println!("\nAddress: {nonce}");
+    println!("Refund:  {} to the deployer\n", format_sol(account.lamports));
+    println!("Transactions signed against this nonce can no longer land once it is closed.\n");
+
+    confirm_mainnet("Closing a nonce account", None).await?;
+    if !prompt_confirmation("Close this nonce account?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    // Withdrawing the whole balance deletes the account
+    let withdraw_ix = system_instruction::withdraw_nonce_account(
+        &Address::from(nonce.to_bytes()),
+        &Address::from(deployer.pubkey().to_bytes()),
+        &Address::from(deployer.pubkey().to_bytes()),
+        account.lamports,
+    );
+    let withdraw_ix = SdkInstruction {
+        program_id: Pubkey::from(withdraw_ix.program_id.to_bytes()),
+        accounts: withdraw_ix
+            .accounts
+            .iter()
+            .map(|acc| AccountMeta {
+                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            })
+            .collect(),
+        data: withdraw_ix.data,
+    };
+
+    let recent_blockhash = with_retry(|| rpc_client.get_latest_blockhash()).await?;
+    let mut transaction = Transaction::new_with_payer(
+        &with_compute_budget(rpc_client, TxClass::Close, &deployer.pubkey(), &[withdraw_ix]).await,
+        Some(&deployer.pubkey()),
+    );
+    transaction.sign(&[&deployer], recent_blockhash);
+
+    let signature = send_and_confirm(rpc_client, &transaction).await
+        .context("Failed to close nonce account")?;
+
+    state.nonce_accounts.retain(|n| n.address != address);
+    let mut record = OperationRecord::new(OperationKind::CloseNonce, &get_network_name());
+    record.signatures = vec![signature.to_string()];
+    record.note = Some(format!("closed {nonce}, reclaimed {}", format_sol(account.lamports)));
+    state.operations.push(record);
+    if let Ok(balance) = balance_of(rpc_client, &deployer.pubkey()).await {
+        state.last_balance = balance;
+    }
+    config.save_state(&state)?;
+
+    print_success(&format!("Reclaimed {} to the deployer", format_sol(account.lamports)));
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use std::time::Duration;
+use crate::config::ProjectConfig;
+use crate::pool::{pool_stats, AnonymityReport, PoolHealth};
+use crate::utils::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StatsWindow {
+    #[value(name = "1h")]
+    Hour,
+    #[value(name = "24h")]
+    Day,
+    #[value(name = "7d")]
+    Week,
+}
+
+impl StatsWindow {
+    fn span(self) -> (&'static str, u64) {
+        match self {
+            StatsWindow::Hour => ("1h", 3_600),
+            StatsWindow::Day => ("24h", 86_400),
+            StatsWindow::Week => ("7d", 604_800),
+        }
+    }
+}
+
+pub async fn execute(windows: Vec<StatsWindow>, json: bool, watch: Option<u64>) -> Result<()> {
+    let rpc_url = get_rpc_url()?;
+    let endpoint = ProjectConfig::load()?.indexer_url.unwrap_or_else(|| rpc_url.clone());
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, commitment());
+
+    let mut windows = if windows.is_empty() { vec![StatsWindow::Day] } else { windows };
+    windows.dedup();
+    let spans: Vec<(&str, u64)> = windows.iter().map(|w| w.span()).collect();
+
+    loop {
+        let report = pool_stats(&rpc_client, &endpoint, &spans).await?;
+
+        if json {
+            // One document per refresh so --watch output can be streamed line by line
+            println!("{}", serde_json::to_string(&report)?);
+        } else {
+            if watch.is_some() {
+                print!("\x1B[2J\x1B[H");
+            }
+            render(&report);
+        }
+
+        let Some(interval) = watch else { break };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = tokio::time::sleep(Duration::from_secs(interval.max(1))) => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn render(report: &AnonymityReport) {
+    print_header("Privacy Pool Stats");
+
+    println!("\nIndexer:   {}", report.endpoint);
+    println!(
+        "TVL:       {}",
+        report.tvl_lamports.map(format_sol).unwrap_or_else(|| "unavailable".to_string())
+    );
+    println!(
+        "Accounts:  {}",
+        report.compressed_accounts.map(|n| n.to_string()).unwrap_or_else(|| "not reported by indexer".to_string())
+    );
+
+    println!();
+    println!("{:<8} {:>8} {:>10} {:>12} {:>10}", "WINDOW", "TXS", "COMPRESS", "DECOMPRESS", "PER HOUR");
+    for window in &report.windows {
+        let bound = if window.truncated { "≥" } else { "" };
+        println!(
+            "{:<8} {:>8} {:>10} {:>12} {:>10.1}",
+            window.label,
+            window.transactions,
+            format!("{bound}{}", window.compressions),
+            format!("{bound}{}", window.decompressions),
+            window.per_hour()
+        );
+    }
+
+    println!();
+    match report.health {
+        PoolHealth::Healthy => print_success("Pool activity is healthy for funding"),
+        PoolHealth::Thin => print_warning("Pool activity is thin; consider a longer privacy delay"),
+        PoolHealth::Quiet => print_warning("Pool is quiet; a funding now would stand out"),
+    }
+}
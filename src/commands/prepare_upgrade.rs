@@ -0,0 +1,316 @@
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_loader_v3_interface::{
+    state::UpgradeableLoaderState,
+    instruction as bpf_loader_upgradeable,
+};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    message::Message,
+    instruction::Instruction as SdkInstruction,
+    instruction::AccountMeta,
+};
+use solana_pubkey::Pubkey as SolanaPubkeyV2;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use crate::compute::FeeSettings;
+use crate::config::{BufferPurpose, BufferRecord, BufferStatus, Config, OperationKind, OperationRecord};
+use crate::commands::verify::fetch_program_bytecode;
+use crate::commands::CliProgress;
+use crate::loader::set_buffer_authority;
+use crate::utils::*;
+
+/// Account roles of the loader Upgrade instruction, in order
+const UPGRADE_ACCOUNT_ROLES: [&str; 7] = [
+    "programdata",
+    "program",
+    "buffer",
+    "spill",
+    "rent sysvar",
+    "clock sysvar",
+    "upgrade authority",
+];
+
+#[derive(Serialize)]
+struct UpgradeProposal {
+    program_id: String,
+    buffer: String,
+    spill: String,
+    upgrade_authority: String,
+    artifact_hash: String,
+    instruction: ProposalInstruction,
+    /// Legacy message with the upgrade authority as fee payer, bincode + base64
+    message_base64: String,
+}
+
+#[derive(Serialize)]
+struct ProposalInstruction {
+    program_id: String,
+    accounts: Vec<ProposalAccount>,
+    data_base64: String,
+}
+
+#[derive(Serialize)]
+struct ProposalAccount {
+    role: &'static str,
+    pubkey: String,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+pub async fn execute(
+    program_id_str: String,
+    program_path: Option<String>,
+    spill: Option<String>,
+    out: Option<PathBuf>,
+) -> Result<()> {
+    print_header("Prepare Governance Upgrade");
+
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    let deployer = config.load_deployer()?;
+    config.check_rotation_policy()?;
+
+    let program_id = Pubkey::from_str(&program_id_str)
+        .context("Invalid program ID")?;
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    let on_chain = fetch_program_bytecode(&rpc_client, &program_id).await
+        .context("Failed to fetch program")?;
+
+    let upgrade_authority = match on_chain.upgrade_authority {
+        None => anyhow::bail!("Program is immutable (upgrade authority is None)"),
+        Some(authority) if authority == deployer.pubkey() => anyhow::bail!(
+            "The private deployer is the upgrade authority.\n\
+            Use `shield-deploy upgrade` instead."
+        ),
+        Some(authority) => authority,
+    };
+
+    // Spilled buffer lamports go back to the governance vault by default
+    let spill = match spill {
+        Some(spill) => Pubkey::from_str(&spill).context("Invalid spill account")?,
+        None => upgrade_authority,
+    };
+
+    let program_file = if let Some(path) = program_path {
+        PathBuf::from(path)
+    } else {
+        detect_program_file()?
+    };
+
+    if !program_file.exists() {
+        anyhow::bail!("Program file not found: {}", program_file.display());
+    }
+
+    let program_data = fs::read(&program_file)
+        .context("Failed to read program file")?;
+    let artifact_hash = sha256_hex(&program_data);
+
+    let balance = rpc_client.get_balance(&deployer.pubkey()).await
+        .context("Failed to get deployer balance")?;
+    let rent = rpc_client
+        .get_minimum_balance_for_rent_exemption(
+            UpgradeableLoaderState::size_of_buffer(program_data.len()),
+        )
+        .await
+        .context("Failed to get rent exemption for buffer")?;
+
+    if balance < rent {
+        anyhow::bail!(
+            "Insufficient deployer balance.\n\
+            Current: {}\n\
+            Needed: {}\n\
+            Run `shield-deploy fund` to add more SOL.",
+            format_sol(balance),
+            format_sol(rent)
+        );
+    }
+
+    println!("\nProgram:            {program_id}");
+    println!("Upgrade authority:  {upgrade_authority}");
+    println!("Artifact:           {} ({} bytes)", program_file.display(), program_data.len());
+    println!();
+    println!("This will:");
+    println!("• Write the artifact to a buffer funded by the private deployer ({})", format_sol(rent));
+    println!("• Hand the buffer to the upgrade authority");
+    println!("• Emit the upgrade instruction for the multisig to execute\n");
+
+    if !prompt_confirmation("Proceed?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let mut state = config.load_state()?;
+
+    println!("\n Creating program buffer...");
+
+    let buffer_keypair = Keypair::new();
+    let buffer_pubkey = buffer_keypair.pubkey();
+    let fees = FeeSettings::current();
+    let create_signature = create_buffer_account(
+        &rpc_client,
+        &fees,
+        &deployer,
+        &buffer_keypair,
+        program_data.len(),
+    )
+    .await?;
+
+    println!("  ✓ Buffer created: {create_signature}");
+    println!("  ↳ Buffer address: {buffer_pubkey}");
+
+    state.buffers.push(BufferRecord {
+        address: buffer_pubkey.to_string(),
+        status: BufferStatus::Writing,
+        created_at: chrono::Utc::now().timestamp(),
+        name: program_file
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string()),
+        artifact_hash: artifact_hash.clone(),
+        size: program_data.len(),
+        authority: deployer.pubkey().to_string(),
+        program_id: Some(program_id.to_string()),
+        purpose: Some(BufferPurpose::Prepared),
+    });
+    let index = state.buffers.len() - 1;
+    config.save_state(&state)?;
+
+    println!("\n Writing program data to buffer...");
+    write_program_data_chunked(&rpc_client, &fees, &deployer, &buffer_pubkey, &program_data, &CliProgress)
+        .await
+        .context(format!(
+            "Failed to write program data. Re-run `shield-deploy write-buffer` to resume {buffer_pubkey}"
+        ))?;
+
+    println!("\n Handing buffer to the upgrade authority...");
+    let handoff_signature = set_buffer_authority(
+        &rpc_client,
+        &fees,
+        &deployer,
+        &buffer_pubkey,
+        &upgrade_authority,
+    )
+    .await?;
+    println!("  ✓ Authority set: {handoff_signature}");
+
+    state.buffers[index].status = BufferStatus::HandedOff;
+    state.buffers[index].authority = upgrade_authority.to_string();
+
+    let balance_after = rpc_client.get_balance(&deployer.pubkey()).await.ok();
+
+    let mut record = OperationRecord::new(OperationKind::PrepareUpgrade, &get_network_name());
+    record.program_id = Some(program_id.to_string());
+    record.signatures = vec![create_signature.to_string(), handoff_signature.to_string()];
+    record.cost_lamports = balance_after.map(|after| balance.saturating_sub(after));
+    record.note = Some(format!("buffer {buffer_pubkey} handed to {upgrade_authority}"));
+    state.operations.push(record);
+    state.last_balance = balance_after.unwrap_or(balance);
+    config.save_state(&state)?;
+
+    let upgrade_ix = upgrade_instruction(&program_id, &buffer_pubkey, &upgrade_authority, &spill).await;
+    let message = Message::new(&[upgrade_ix.clone()], Some(&upgrade_authority));
+
+    let proposal = UpgradeProposal {
+        program_id: program_id.to_string(),
+        buffer: buffer_pubkey.to_string(),
+        spill: spill.to_string(),
+        upgrade_authority: upgrade_authority.to_string(),
+        artifact_hash,
+        instruction: ProposalInstruction {
+            program_id: upgrade_ix.program_id.to_string(),
+            accounts: upgrade_ix
+                .accounts
+                .iter()
+                .zip(UPGRADE_ACCOUNT_ROLES)
+                .map(|(acc, role)| ProposalAccount {
+                    role,
+                    pubkey: acc.pubkey.to_string(),
+                    is_signer: acc.is_signer,
+                    is_writable: acc.is_writable,
+                })
+                .collect(),
+            data_base64: BASE64.encode(&upgrade_ix.data),
+        },
+        message_base64: BASE64.encode(bincode::serialize(&message)?),
+    };
+
+    let out_path = match out {
+        Some(path) => path,
+        None => {
+            fs::create_dir_all(config.proposals_dir())
+                .context("Failed to create proposals directory")?;
+            config.proposals_dir().join(format!("upgrade-{buffer_pubkey}.json"))
+        }
+    };
+    fs::write(&out_path, serde_json::to_string_pretty(&proposal)?)
+        .context("Failed to write upgrade proposal")?;
+
+    print_success("Upgrade prepared");
+
+    println!("\nBuffer:      {buffer_pubkey}");
+    println!("Spill:       {spill}");
+    println!("\nUpgrade instruction:");
+    println!("  Program:   {}", proposal.instruction.program_id);
+    for (i, account) in proposal.instruction.accounts.iter().enumerate() {
+        let flags = match (account.is_signer, account.is_writable) {
+            (true, true) => "signer, writable",
+            (true, false) => "signer",
+            (false, true) => "writable",
+            (false, false) => "readonly",
+        };
+        println!("  #{i} {:<18} {} ({flags})", account.role, account.pubkey);
+    }
+    println!("  Data:      {}", proposal.instruction.data_base64);
+    println!("\nMessage (base64):");
+    println!("{}", proposal.message_base64);
+    println!("\nSaved to {}", out_path.display());
+    println!("\nCreate a proposal with this instruction in your multisig.");
+    println!("If it never executes, the buffer stays listed in `shield-deploy status`.");
+
+    Ok(())
+}
+
+pub async fn upgrade_instruction(
+    program_id: &Pubkey,
+    buffer: &Pubkey,
+    authority: &Pubkey,
+    spill: &Pubkey,
+) -> SdkInstruction {
+    let upgrade_ix = bpf_loader_upgradeable::upgrade(
+        &SolanaPubkeyV2::new_from_array(program_id.to_bytes()),
+        &SolanaPubkeyV2::new_from_array(buffer.to_bytes()),
+        &SolanaPubkeyV2::new_from_array(authority.to_bytes()),
+        &SolanaPubkeyV2::new_from_array(spill.to_bytes()),
+    );
+
+    SdkInstruction {
+        program_id: Pubkey::from(upgrade_ix.program_id.to_bytes()),
+        accounts: upgrade_ix
+            .accounts
+            .iter()
+            .map(|acc| AccountMeta {
+                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            })
+            .collect(),
+        data: upgrade_ix.data,
+    }
+}
@@ -0,0 +1,263 @@
+use anyhow::{Context, Result};
+use clap::{Subcommand, ValueHint};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Signer},
+};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use crate::commands::verify::fetch_program_bytecode;
+use crate::config::{BufferStatus, Config, DeployedProgram, OperationKind, OperationRecord};
+use crate::utils::*;
+
+#[derive(Subcommand)]
+pub enum ProgramsAction {
+    /// Adopt a program deployed outside shield-deploy
+    Import {
+        program_id: String,
+        /// Keypair holding the program's upgrade authority (defaults to the deployer)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        authority: Option<PathBuf>,
+    },
+    /// Stop managing a program (nothing changes on chain)
+    Remove {
+        program_id: String,
+        /// Also delete the program's upgrade proposals and finished buffer records
+        #[arg(long)]
+        purge_artifacts: bool,
+    },
+}
+
+pub async fn execute(action: ProgramsAction) -> Result<()> {
+    match action {
+        ProgramsAction::Import { program_id, authority } => import(&program_id, authority).await,
+        ProgramsAction::Remove { program_id, purge_artifacts } => remove(&program_id, purge_artifacts).await,
+    }
+}
+
+async fn import(program_id_str: &str, authority_path: Option<PathBuf>) -> Result<()> {
+    print_header("Import Program");
+
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    let deployer = config.load_deployer()?;
+    let mut state = config.load_state()?;
+
+    let program_id = Pubkey::from_str(program_id_str).context("Invalid program ID")?;
+    if state.deployed_programs.iter().any(|p| p.program_id == program_id_str) {
+        anyhow::bail!("{program_id} is already managed by this project");
+    }
+
+    let authority = match &authority_path {
+        Some(path) => Some(
+            read_keypair_file(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", path.display()))?,
+        ),
+        None => None,
+    };
+    let signer = authority.as_ref().unwrap_or(&deployer);
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, commitment());
+
+    println!("\n Fetching program accounts...");
+    let on_chain = fetch_program_bytecode(&rpc_client, &program_id).await?;
+    let controlled = on_chain.upgrade_authority == Some(signer.pubkey());
+
+    println!("\nProgram:      {program_id}");
+    println!("ProgramData:  {}", on_chain.programdata_address);
+    println!("Size:         {} bytes", on_chain.bytecode.len());
+    println!("Slot:         {}", on_chain.slot);
+    println!(
+        "Authority:    {}",
+        on_chain.upgrade_authority.map(|a| a.to_string()).unwrap_or_else(|| "none (immutable)".to_string())
+    );
+    println!();
+
+    let external = authority.is_some() && controlled;
+    if controlled && !external {
+        println!("The private deployer controls this program.");
+    } else if external {
+        println!("{} controls this program.", signer.pubkey());
+        println!("Its keypair can be copied into .shield/authorities/ so upgrades sign with it.");
+    } else {
+        print_warning("Neither the deployer nor the given key controls this program");
+        println!("  It will be imported read-only: status and verify work, upgrades will not.");
+    }
+    println!();
+
+    if !prompt_confirmation("Import it?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let copy_authority = external
+        && prompt_confirmation("Copy the authority keypair into .shield/authorities/?")?;
+    if let (true, Some(authority)) = (copy_authority, &authority) {
+        config.stage_program_authority(&program_id, authority)?;
+        let path = config.commit_program_authority(&program_id)?;
+        println!("  ✓ Authority copied to {}", path.display());
+    }
+
+    let deployed_at = rpc_client
+        .get_block_time(on_chain.slot).await
+        .unwrap_or_else(|_| chrono::Utc::now().timestamp());
+
+    state.deployed_programs.push(DeployedProgram {
+        program_id: program_id.to_string(),
+        deployed_at,
+        cluster: Some(get_network_name()),
+        authority: on_chain.upgrade_authority.map(|a| a.to_string()),
+        immutable: on_chain.upgrade_authority.is_none(),
+        isolated_authority: copy_authority,
+        deployed_slot: Some(on_chain.slot),
+        size: Some(on_chain.bytecode.len()),
+        bytecode_hash: Some(sha256_hex(&on_chain.bytecode)),
+        ..Default::default()
+    });
+    let mut record = OperationRecord::new(OperationKind::ImportProgram, &get_network_name());
+    record.program_id = Some(program_id.to_string());
+    if !controlled {
+        record.note = Some("read-only".to_string());
+    }
+    state.operations.push(record);
+    config.save_state(&state)?;
+
+    print_success(&format!("Imported {program_id}"));
+
+    if controlled && (copy_authority || !external) {
+        println!("\n→ Upgrade it with `shield-deploy upgrade {program_id}`");
+    } else if external {
+        println!("\n→ Upgrades need the authority in .shield/authorities/ or a transfer to the deployer");
+    }
+
+    Ok(())
+}
+
+async fn remove(program_id_str: &str, purge_artifacts: bool) -> Result<()> {
+    print_header("Remove Program");
+
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    let deployer = config.load_deployer()?;
+    let mut state = config.load_state()?;
+
+    let program_id = Pubkey::from_str(program_id_str).context("Invalid program ID")?;
+    let Some(index) = state.deployed_programs.iter().position(|p| p.program_id == program_id_str) else {
+        anyhow::bail!("{program_id} is not managed by this project");
+    };
+
+    let program_authority = config.load_program_authority(&program_id)?;
+    let our_keys: Vec<Pubkey> = std::iter::once(deployer.pubkey())
+        .chain(program_authority.as_ref().map(|k| k.pubkey()))
+        .collect();
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, commitment());
+
+    println!("\nProgram: {program_id}");
+    let still_controlled = match fetch_program_bytecode(&rpc_client, &program_id).await {
+        Ok(on_chain) => {
+            let controlled = on_chain.upgrade_authority.is_some_and(|a| our_keys.contains(&a));
+            println!(
+                "On-chain authority: {}",
+                on_chain.upgrade_authority.map(|a| a.to_string()).unwrap_or_else(|| "none (immutable)".to_string())
+            );
+            controlled
+        }
+        Err(e) => {
+            print_warning(&format!("Could not read the program on chain: {e}"));
+            false
+        }
+    };
+
+    if still_controlled {
+        println!();
+        print_warning("THIS PROJECT STILL CONTROLS THE PROGRAM'S UPGRADE AUTHORITY");
+        println!("  Removing it is usually a mistake: upgrades, rotation and transfers");
+        println!("  will no longer touch it, and `rotate` would strand it on the old deployer.");
+        println!("  Transfer authority first if the program has changed hands.");
+        println!();
+        println!("Type the program ID to remove it anyway:");
+        ensure_interactive("Program ID")?;
+        let confirmation: String = dialoguer::Input::new()
+            .with_prompt("Program ID")
+            .interact_text()?;
+        if confirmation.trim() != program_id_str {
+            anyhow::bail!("Program ID mismatch. Nothing was removed.");
+        }
+    } else {
+        println!();
+        if !prompt_confirmation("Stop managing this program?")? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    // A dedicated key is the only way to upgrade an isolated program; keep it unless asked
+    if program_authority.is_some() {
+        if prompt_confirmation("Delete its dedicated authority key from .shield/authorities/?")? {
+            config.remove_program_authority(&program_id)?;
+            println!("  ✓ Authority key deleted");
+        } else {
+            println!("  ↳ Authority key kept in {}", config.authorities_dir().display());
+        }
+    }
+
+    let mut purged = 0;
+    if purge_artifacts {
+        // Buffers still holding rent stay tracked so `buffers` can reclaim them
+        let before = state.buffers.len();
+        state.buffers.retain(|b| {
+            b.program_id.as_deref() != Some(program_id_str)
+                || !matches!(b.status, BufferStatus::Consumed | BufferStatus::Closed)
+        });
+        purged += before - state.buffers.len();
+
+        if let Ok(entries) = fs::read_dir(config.proposals_dir()) {
+            for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+                let for_program = fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+                    .is_some_and(|v| v["program_id"].as_str() == Some(program_id_str));
+                if for_program {
+                    fs::remove_file(&path)
+                        .with_context(|| format!("Failed to delete {}", path.display()))?;
+                    purged += 1;
+                }
+            }
+        }
+    }
+
+    state.deployed_programs.remove(index);
+    let mut record = OperationRecord::new(OperationKind::RemoveProgram, &get_network_name());
+    record.program_id = Some(program_id.to_string());
+    if still_controlled {
+        record.note = Some("removed while still controlled".to_string());
+    }
+    state.operations.push(record);
+    config.save_state(&state)?;
+
+    print_success(&format!("{program_id} is no longer managed"));
+    if purge_artifacts {
+        println!("\nPurged {purged} proposal file(s) and buffer record(s).");
+    }
+
+    Ok(())
+}
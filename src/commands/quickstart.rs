@@ -0,0 +1,248 @@
+use anyhow::{Context, Result};
+use dialoguer::{theme::ColorfulTheme, Select};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::signer::Signer;
+use std::path::Path;
+use std::process::Command;
+use crate::commands::config::ConfigAction;
+use crate::commands::doctor::{check_circuits, CheckStatus};
+use crate::config::{Config, OperationKind};
+use crate::privacy::CIRCUIT_DIR;
+use crate::utils::*;
+
+/// Clusters offered when the detected one isn't wanted
+const CLUSTERS: &[(&str, &str)] = &[
+    ("devnet", "https://api.devnet.solana.com"),
+    ("mainnet-beta", "https://api.mainnet-beta.solana.com"),
+    ("localhost", "http://127.0.0.1:8899"),
+];
+
+/// Walk through cluster selection, init, funding, build and deploy
+///
+/// Progress is read back from the project itself, so re-running after an
+/// interruption skips whatever is already done.
+pub async fn execute() -> Result<()> {
+    print_header("Quickstart");
+
+    println!("\nThis walks through the whole first deployment:");
+    println!("  1. Pick a cluster");
+    println!("  2. Create a private deployer (init)");
+    println!("  3. Fund it through Privacy Cash");
+    println!("  4. Build your program");
+    println!("  5. Deploy it with the private deployer");
+    println!("\nYou can stop at any prompt and run `shield-deploy quickstart` again to resume.\n");
+
+    let config = Config::new()?;
+    let resuming = config.deployer_exists();
+
+    // Step 1: cluster
+    step(1, "Cluster");
+    if resuming {
+        println!("Using {} ({}), as configured for this project.", get_network_name(), get_rpc_url()?);
+    } else {
+        choose_cluster().await?;
+    }
+
+    // Step 2: init
+    step(2, "Private deployer");
+    if resuming {
+        println!("Already initialized, skipping.");
+    } else {
+        crate::commands::init::execute(false).await?;
+        if !config.deployer_exists() {
+            println!("\nQuickstart stopped before init. Run it again when ready.");
+            return Ok(());
+        }
+    }
+    let deployer = config.load_deployer()?.pubkey();
+
+    let rpc_client = RpcClient::new_with_commitment(get_rpc_url()?, commitment());
+
+    // Step 3: funding
+    step(3, "Funding");
+    let balance_before_fund = rpc_client.get_balance(&deployer).await
+        .context("Failed to get deployer balance")?;
+    if balance_before_fund > 0 {
+        println!("The deployer already holds {}.", format_sol(balance_before_fund));
+    }
+    if balance_before_fund == 0 || prompt_confirmation("Add more funds now?")? {
+        if get_network_name() != "localhost" {
+            explain_funding();
+            ensure_circuits()?;
+        }
+        crate::commands::fund::execute(false, None, false, false, false, false).await?;
+    }
+    let balance = rpc_client.get_balance(&deployer).await
+        .context("Failed to get deployer balance")?;
+    if balance == 0 {
+        println!("\nThe deployer is still empty. Fund it and run `shield-deploy quickstart` again to resume.");
+        return Ok(());
+    }
+
+    // Step 4: build artifact
+    step(4, "Build");
+    let Some(program_file) = find_or_build_artifact()? else {
+        println!("\nNo build artifact yet. Build your program and run `shield-deploy quickstart` again to resume.");
+        return Ok(());
+    };
+    println!("Using {}", program_file.display());
+
+    // Step 5: deploy
+    step(5, "Deploy");
+    let deployed_before = config.load_state()?.deployed_programs.len();
+    crate::commands::deploy::execute(
+        Some(program_file.display().to_string()),
+        None,
+        false,
+        false,
+        None,
+        false,
+        None,
+        false,
+        false,
+        None,
+        None,
+        None,
+        false,
+        None,
+        false,
+        false,
+        false,
+        None,
+        false,
+        false,
+        false,
+        None,
+    )
+    .await?;
+
+    let state = config.load_state()?;
+    if state.deployed_programs.len() == deployed_before {
+        println!("\nNothing was deployed. Run `shield-deploy quickstart` again to resume.");
+        return Ok(());
+    }
+    let balance_after = rpc_client.get_balance(&deployer).await.unwrap_or(balance);
+    summarize(&config, balance, balance_after)
+}
+
+fn step(number: usize, title: &str) {
+    println!("\n━━ Step {number}/5: {title} ━━\n");
+}
+
+async fn choose_cluster() -> Result<()> {
+    let network = get_network_name();
+    let rpc_url = get_rpc_url()?;
+    println!("Detected: {network} ({rpc_url})");
+
+    let mut items = vec![format!("Keep {network}")];
+    items.extend(CLUSTERS.iter().map(|(name, url)| format!("{name} ({url})")));
+    ensure_interactive("Which cluster should this project use?")?;
+    let choice = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Which cluster should this project use?")
+        .items(&items)
+        .default(0)
+        .interact()
+        .context("Failed to read selection")?;
+    if choice == 0 {
+        return Ok(());
+    }
+
+    let (name, url) = CLUSTERS[choice - 1];
+    if name == "mainnet-beta" {
+        print_warning("Mainnet deployments spend real SOL");
+    }
+    for (key, value) in [("network", name), ("rpc_url", url)] {
+        crate::commands::config::execute(ConfigAction::Set {
+            key: key.to_string(),
+            value: value.to_string(),
+            global: false,
+        })
+        .await?;
+    }
+    Ok(())
+}
+
+fn explain_funding() {
+    println!("Funding goes through the Privacy Cash pool so your wallet never pays the deployer directly:");
+    println!("  • Your wallet deposits into the pool; this deposit is public");
+    println!("  • A zero-knowledge proof is generated on this machine");
+    println!("  • The pool pays the deployer; the amount and its source stay hidden");
+    println!("  • Deploys wait out a privacy delay so timing doesn't link the two\n");
+}
+
+/// Privacy Cash needs its circuit files locally; give the user a chance to add them
+fn ensure_circuits() -> Result<()> {
+    loop {
+        let check = check_circuits(Path::new(CIRCUIT_DIR));
+        if check.status == CheckStatus::Pass {
+            return Ok(());
+        }
+        print_warning(&check.message);
+        if let Some(remediation) = &check.remediation {
+            println!("→ {remediation}\n");
+        }
+        if !prompt_confirmation("Check again?")? {
+            anyhow::bail!("Privacy Cash circuit files are missing from ./{CIRCUIT_DIR}/");
+        }
+    }
+}
+
+/// Detected `.so`, optionally after running the project's build
+fn find_or_build_artifact() -> Result<Option<std::path::PathBuf>> {
+    if !list_program_files().is_empty() {
+        return detect_program_file().map(Some);
+    }
+
+    let (program, args): (&str, &[&str]) = if Path::new("Anchor.toml").exists() {
+        ("anchor", &["build"])
+    } else {
+        ("cargo", &["build-sbf"])
+    };
+    let command_line = format!("{program} {}", args.join(" "));
+    println!("No build artifact found.");
+    if !prompt_confirmation(&format!("Run `{command_line}` now?"))? {
+        return Ok(None);
+    }
+
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to run `{command_line}`; is it installed?"))?;
+    if !status.success() {
+        anyhow::bail!("`{command_line}` failed ({status})");
+    }
+
+    if list_program_files().is_empty() {
+        return Ok(None);
+    }
+    detect_program_file().map(Some)
+}
+
+fn summarize(config: &Config, balance_before: u64, balance_after: u64) -> Result<()> {
+    let state = config.load_state()?;
+    let Some(program) = state.deployed_programs.last() else {
+        return Ok(());
+    };
+    let funded = state.operations
+        .iter()
+        .filter(|op| op.kind == OperationKind::Fund)
+        .count();
+
+    print_success("Quickstart complete");
+    println!("\nProgram ID:     {}", program.program_id);
+    if let Some(name) = &program.name {
+        println!("Program:        {name}");
+    }
+    println!("Network:        {}", state.network);
+    println!("Deploy cost:    {}", format_sol(balance_before.saturating_sub(balance_after)));
+    println!("Deployer left:  {}", format_sol(balance_after));
+    println!("Fundings:       {funded}");
+    println!("Explorer:       {}", explorer_address_url(&program.program_id, &state.network));
+
+    println!("\nNext steps:");
+    println!("→ Ship changes with `shield-deploy upgrade {}`", program.program_id);
+    println!("→ Check privacy hygiene with `shield-deploy status`");
+    println!("→ Back up the deployer with `shield-deploy backup`");
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use anyhow::{Context, Result};
+use solana_account_decoder_client_types::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signer::Signer,
+};
+use std::str::FromStr;
+use crate::config::{BufferPurpose, BufferRecord, BufferStatus, Config, DeployedProgram};
+use crate::commands::verify::fetch_program_bytecode;
+use crate::hygiene::{
+    scan_by_authority, BUFFER_AUTHORITY_OFFSET, PROGRAMDATA_AUTHORITY_OFFSET, TAG_BUFFER, TAG_PROGRAM, TAG_PROGRAMDATA,
+};
+use crate::utils::*;
+
+struct Discovered {
+    program_id: Pubkey,
+    slot: u64,
+    size: usize,
+}
+
+pub async fn execute(program_id_hints: Vec<String>) -> Result<()> {
+    print_header("Recover State From Chain");
+
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Restore deployer.json (or run `shield-deploy import-deployer`) first."
+        );
+    }
+
+    let deployer = config.load_deployer()?;
+    let mut state = config.load_state()?;
+
+    let hints = program_id_hints
+        .iter()
+        .map(|id| Pubkey::from_str(id).with_context(|| format!("Invalid program ID: {id}")))
+        .collect::<Result<Vec<_>>>()?;
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    println!("\nDeployer: {}", deployer.pubkey());
+    println!("\n Scanning the upgradeable loader...");
+
+    let mut program_ids: Vec<Pubkey> = Vec::new();
+    let mut buffers: Vec<Pubkey> = Vec::new();
+
+    match scan_loader(&rpc_client, &deployer.pubkey()).await {
+        Ok((found_programs, found_buffers)) => {
+            println!("  ✓ {} programs, {} buffers", found_programs.len(), found_buffers.len());
+            program_ids.extend(found_programs);
+            buffers = found_buffers;
+        }
+        Err(e) if !hints.is_empty() => {
+            print_warning(&format!("Scan failed, using --program-id hints only: {e}"));
+        }
+        Err(e) => {
+            return Err(e.context(
+                "The RPC rejected getProgramAccounts.\n\
+                Pass the programs you know with --program-id <id> (repeatable)"
+            ));
+        }
+    }
+    program_ids.extend(hints);
+    program_ids.sort();
+    program_ids.dedup();
+
+    let mut discovered = Vec::new();
+    for program_id in program_ids {
+        if state.deployed_programs.iter().any(|p| p.program_id == program_id.to_string()) {
+            continue;
+        }
+
+        match fetch_program_bytecode(&rpc_client, &program_id).await {
+            Ok(on_chain) if on_chain.upgrade_authority == Some(deployer.pubkey()) => {
+                discovered.push(Discovered {
+                    program_id,
+                    slot: on_chain.slot,
+                    size: on_chain.bytecode.len(),
+                });
+            }
+            Ok(_) => print_warning(&format!("{program_id} is not controlled by this deployer, skipping")),
+            Err(e) => print_warning(&format!("Could not read {program_id}: {e}")),
+        }
+    }
+
+    let new_buffers: Vec<Pubkey> = buffers
+        .into_iter()
+        .filter(|b| !state.buffers.iter().any(|r| r.address == b.to_string()))
+        .collect();
+
+    if discovered.is_empty() && new_buffers.is_empty() {
+        println!("\nState is already up to date.");
+        return Ok(());
+    }
+
+    println!();
+    for program in &discovered {
+        println!("• Program {} (slot {}, {} bytes)", program.program_id, program.slot, program.size);
+    }
+    for buffer in &new_buffers {
+        println!("• Buffer  {buffer}");
+    }
+    println!();
+
+    if !prompt_confirmation("Add these to state?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    if state.network.is_empty() {
+        state.network = get_network_name();
+    }
+
+    for program in &discovered {
+        let deployed_at = rpc_client
+            .get_block_time(program.slot).await
+            .unwrap_or_else(|_| chrono::Utc::now().timestamp());
+
+        state.deployed_programs.push(DeployedProgram {
+            program_id: program.program_id.to_string(),
+            deployed_at,
+            cluster: Some(state.network.clone()),
+            authority: Some(deployer.pubkey().to_string()),
+            deployed_slot: Some(program.slot),
+            size: Some(program.size),
+            ..Default::default()
+        });
+    }
+
+    let header_len = UpgradeableLoaderState::size_of_buffer_metadata();
+    for buffer in &new_buffers {
+        let Ok(account) = rpc_client.get_account(buffer).await else { continue };
+        let data = strip_trailing_zeros(account.data.get(header_len..).unwrap_or_default());
+
+        state.buffers.push(BufferRecord {
+            address: buffer.to_string(),
+            status: BufferStatus::Ready,
+            created_at: chrono::Utc::now().timestamp(),
+            name: None,
+            artifact_hash: sha256_hex(data),
+            size: data.len(),
+            authority: deployer.pubkey().to_string(),
+            program_id: None,
+            purpose: Some(BufferPurpose::Recovered),
+        });
+    }
+
+    config.save_state(&state)?;
+
+    print_success(&format!(
+        "Recovered {} programs and {} buffers",
+        discovered.len(),
+        new_buffers.len()
+    ));
+
+    Ok(())
+}
+
+/// Find ProgramData and Buffer accounts whose authority is the deployer
+async fn scan_loader(rpc_client: &RpcClient, deployer: &Pubkey) -> Result<(Vec<Pubkey>, Vec<Pubkey>)> {
+    let loader_id = Pubkey::new_from_array(LOADER_ID.to_bytes());
+
+    let programdata_accounts = scan_by_authority(rpc_client, TAG_PROGRAMDATA, PROGRAMDATA_AUTHORITY_OFFSET, deployer).await?;
+    let buffer_accounts = scan_by_authority(rpc_client, TAG_BUFFER, BUFFER_AUTHORITY_OFFSET, deployer).await?;
+
+    // ProgramData doesn't reference its program; find the Program account pointing at it
+    let mut programs = Vec::new();
+    for (programdata_address, _) in programdata_accounts {
+        let owners = rpc_client.get_program_accounts_with_config(
+            &loader_id,
+            RpcProgramAccountsConfig {
+                filters: Some(vec![
+                    RpcFilterType::DataSize(UpgradeableLoaderState::size_of_program() as u64),
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, vec![TAG_PROGRAM, 0, 0, 0])),
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(4, programdata_address.to_bytes().to_vec())),
+                ]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    data_slice: Some(UiDataSliceConfig { offset: 0, length: 0 }),
+                    commitment: Some(rpc_client.commitment()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .await?;
+        programs.extend(owners.into_iter().map(|(program_id, _)| program_id));
+    }
+
+    Ok((programs, buffer_accounts.into_iter().map(|(address, _)| address).collect()))
+}
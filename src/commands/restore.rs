@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use solana_sdk::signature::{Keypair, Signer};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+use crate::bundle::decode_archive;
+use crate::config::{write_secret_file, Config, DeployerKeypair, ProjectState};
+use crate::utils::*;
+
+pub async fn execute(path: PathBuf, force: bool) -> Result<()> {
+    print_header("Restore .shield");
+
+    let config = Config::new()?;
+
+    let data = fs::read(&path)
+        .with_context(|| format!("Failed to read backup {}", path.display()))?;
+
+    let passphrase = prompt_passphrase("Passphrase", false)?;
+    let archive = decode_archive(&data, &passphrase)?;
+
+    // Validate everything before touching the disk
+    let mut entries = Vec::new();
+    for file in &archive.files {
+        let relative = Path::new(&file.path);
+        if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+            anyhow::bail!("Backup contains an unsafe path: {}", file.path);
+        }
+        entries.push((file, config.shield_dir().join(relative), file.contents()?));
+    }
+
+    let deployer = entries
+        .iter()
+        .find(|(file, _, _)| file.path == "deployer.json")
+        .map(|(_, _, contents)| -> Result<Keypair> {
+            let data: DeployerKeypair = serde_json::from_slice(contents)?;
+            Keypair::from_bytes(&data.keypair).map_err(|e| anyhow::anyhow!("Invalid deployer keypair: {e}"))
+        })
+        .transpose()
+        .context("Backup contains an invalid deployer.json")?;
+
+    let state = entries
+        .iter()
+        .find(|(file, _, _)| file.path == "state.json")
+        .map(|(_, _, contents)| serde_json::from_slice::<ProjectState>(contents))
+        .transpose()
+        .context("Backup contains an invalid state.json")?;
+
+    let format_time = |ts: i64| {
+        chrono::DateTime::from_timestamp(ts, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "unknown".to_string())
+    };
+
+    println!("\nBackup contents:");
+    println!("• Created:  {}", format_time(archive.created_at));
+    println!(
+        "• Deployer: {}",
+        deployer.as_ref().map(|d| d.pubkey().to_string()).unwrap_or_else(|| "not included".into())
+    );
+    match &state {
+        Some(state) => {
+            println!("• Programs: {}", state.deployed_programs.len());
+            let last_op = state.operations.iter().map(|op| op.timestamp).max();
+            println!("• State:    last operation {}", last_op.map(format_time).unwrap_or_else(|| "none".into()));
+        }
+        None => println!("• State:    not included"),
+    }
+    println!("• Files:    {}", entries.len());
+
+    // Skip files that are newer on disk than in the backup unless forced
+    let mut to_write = Vec::new();
+    let mut skipped = Vec::new();
+    for (file, target, contents) in &entries {
+        if let Ok(existing) = fs::read(target) {
+            if existing == *contents {
+                continue;
+            }
+            let on_disk = fs::metadata(target)?
+                .modified()
+                .ok()
+                .and_then(|m| m.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(i64::MAX);
+            if on_disk > file.modified && !force {
+                skipped.push(file.path.as_str());
+                continue;
+            }
+        }
+        to_write.push((file.path.as_str(), target, contents));
+    }
+
+    if !skipped.is_empty() {
+        println!();
+        print_warning("These files are newer on disk and will be kept (use --force to overwrite):");
+        for path in &skipped {
+            println!("  • {path}");
+        }
+    }
+
+    if to_write.is_empty() {
+        println!("\nNothing to restore; .shield already matches the backup.");
+        return Ok(());
+    }
+
+    println!("\nWill restore:");
+    for (path, _, _) in &to_write {
+        println!("  • {path}");
+    }
+    println!();
+
+    if !prompt_confirmation("Restore these files?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    for (path, target, contents) in &to_write {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_secret_file(target, contents).with_context(|| format!("Failed to restore {path}"))?;
+    }
+
+    config.add_gitignore()
+        .context("Failed to update .gitignore")?;
+
+    print_success(&format!("Restored {} files", to_write.len()));
+
+    Ok(())
+}
@@ -1,21 +1,25 @@
 use anyhow::{Context, Result};
-use solana_client::rpc_client::RpcClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_loader_v3_interface::instruction as bpf_loader_upgradeable;
 use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
-use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::{
     pubkey::Pubkey, 
-    signature::{Keypair, Signer}, 
+    signature::{Keypair, Signature, Signer}, 
     transaction::Transaction,
     instruction::Instruction as SdkInstruction,
     instruction::AccountMeta,
 };
 use solana_pubkey::Pubkey as SolanaPubkeyV2;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use crate::config::Config;
+use crate::commands::backup::offer_backup;
+use crate::compute::{with_compute_budget, TxClass};
+use crate::config::{Config, OperationKind, OperationRecord};
+use crate::network::confirm_mainnet;
 use crate::utils::*;
 
-pub async fn execute() -> Result<()> {
+pub async fn execute(outfile: Option<PathBuf>) -> Result<()> {
     print_header("Rotate Deployer");
     
     let config = Config::new()?;
@@ -26,12 +30,21 @@ pub async fn execute() -> Result<()> {
             Run `shield-deploy init` first."
         );
     }
+    config.ensure_deployer_on_disk("rotate")?;
+    
+    // A bad path must fail now, not after authority has moved to a key we can't save
+    let outfile = outfile.map(|path| check_outfile(&path)).transpose()?;
     
     let old_deployer = config.load_deployer()?;
-    let state = config.load_state()?;
+    let mut state = config.load_state()?;
+    
+    offer_backup(&config)?;
     
     println!("\nThis will:");
     println!("• Create a new private deployer");
+    if let Some(path) = &outfile {
+        println!("  saved to {}", path.display());
+    }
     println!("• Transfer upgrade authority");
     println!("• Retire the old deployer");
     
@@ -43,12 +56,16 @@ pub async fn execute() -> Result<()> {
         println!("Cancelled.");
         return Ok(());
     }
+    confirm_mainnet("Rotating the deployer", None).await?;
     
     let new_deployer = Keypair::new();
     
     println!("\n✓ New deployer generated");
     println!("  ↳ New deployer pubkey: {}", new_deployer.pubkey());
     
+    let mut record = OperationRecord::new(OperationKind::Rotate, &get_network_name());
+    record.note = Some(format!("{} -> {}", old_deployer.pubkey(), new_deployer.pubkey()));
+    
     // Check if any programs need authority transfer
     if !state.deployed_programs.is_empty() {
         println!("\n⏳ Transferring upgrade authority...");
@@ -56,14 +73,35 @@ pub async fn execute() -> Result<()> {
         let rpc_url = get_rpc_url()?;
         let rpc_client = RpcClient::new_with_commitment(
             rpc_url.clone(),
-            CommitmentConfig::confirmed(),
+            commitment(),
         );
         
-        for program in &state.deployed_programs {
+        for program in state.deployed_programs.iter_mut() {
             let program_id = Pubkey::from_str(&program.program_id)
                 .context("Invalid program ID")?;
             
-            transfer_upgrade_authority(
+            // Isolated programs get a fresh dedicated key, not the new deployer
+            if let Some(current) = config.load_program_authority(&program_id)? {
+                let next = Keypair::new();
+                config.stage_program_authority(&program_id, &next)?;
+                let signature = set_upgrade_authority_checked(
+                    &rpc_client,
+                    &old_deployer,
+                    &current,
+                    &next,
+                    &program_id,
+                )
+                .await
+                .context(format!("Failed to rotate the dedicated authority for {program_id}"))?;
+                config.commit_program_authority(&program_id)?;
+                
+                record.signatures.push(signature.to_string());
+                program.authority = Some(next.pubkey().to_string());
+                println!("  ✓ Dedicated authority rotated for {program_id}");
+                continue;
+            }
+            
+            let signature = transfer_upgrade_authority(
                 &rpc_client,
                 &old_deployer,
                 &program_id,
@@ -72,16 +110,42 @@ pub async fn execute() -> Result<()> {
             .await
             .context(format!("Failed to transfer authority for {program_id}"))?;
             
+            record.signatures.push(signature.to_string());
+            program.authority = Some(new_deployer.pubkey().to_string());
             println!("  ✓ Authority transferred for {program_id}");
         }
     } else {
         println!("\n  ↳ No deployed programs, skipping authority transfer");
     }
     
-    config.save_deployer(&new_deployer)
-        .context("Failed to save new deployer")?;
+    let previous_path = config.deployer_path();
+    match &outfile {
+        Some(path) => {
+            config.save_deployer_to(&new_deployer, path)
+                .context("Failed to save new deployer")?;
+            state.deployer_file = Some(path.clone());
+        }
+        None => config.save_deployer(&new_deployer)
+            .context("Failed to save new deployer")?,
+    }
+    state.operations.push(record);
+    config.save_state(&state)?;
+    
+    // The old key would otherwise still sit at .shield/deployer.json
+    if outfile.is_some() && previous_path == config.default_deployer_path() {
+        fs::create_dir_all(config.retired_dir())
+            .context("Failed to create retired keys directory")?;
+        let retired = config.retired_dir().join(format!("{}.json", old_deployer.pubkey()));
+        fs::rename(&previous_path, &retired)
+            .context("Failed to move the old deployer aside")?;
+        println!("\n  ↳ Old deployer moved to {}", retired.display());
+    }
     
     print_success("Deployer rotated");
+    if let Some(path) = &outfile {
+        println!("\nNew deployer saved to {}", path.display());
+        println!("Commands load it from there; keep that location available.");
+    }
     
     println!("\nNew deployer is now active.");
     println!("Old deployer can be safely discarded.");
@@ -94,13 +158,35 @@ pub async fn execute() -> Result<()> {
     Ok(())
 }
 
+/// Resolve `--outfile` to an absolute path and prove its directory is writable
+fn check_outfile(path: &Path) -> Result<PathBuf> {
+    if path.exists() {
+        anyhow::bail!("{} already exists; refusing to overwrite it", path.display());
+    }
+    let file_name = path.file_name()
+        .with_context(|| format!("{} is not a file path", path.display()))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent = parent.canonicalize()
+        .with_context(|| format!("Directory {} does not exist", parent.display()))?;
+    
+    let probe = parent.join(format!(".shield-deploy-probe-{}", std::process::id()));
+    fs::write(&probe, b"")
+        .with_context(|| format!("Cannot write to {}", parent.display()))?;
+    fs::remove_file(&probe)?;
+    
+    Ok(parent.join(file_name))
+}
+
 /// Transfer upgrade authority from old deployer to new deployer
 async fn transfer_upgrade_authority(
     rpc_client: &RpcClient,
     current_authority: &Keypair,
     program_id: &Pubkey,
     new_authority: &Pubkey,
-) -> Result<()> {
+) -> Result<Signature> {
     
     // Derive ProgramData address
     let loader_id_sdk = Pubkey::new_from_array(LOADER_ID.to_bytes());
@@ -136,18 +222,17 @@ async fn transfer_upgrade_authority(
         data: set_authority_ix.data,
     };
     
-    let recent_blockhash = rpc_client.get_latest_blockhash()?;
+    let recent_blockhash = with_retry(|| rpc_client.get_latest_blockhash()).await?;
     let mut transaction = Transaction::new_with_payer(
-        &[sdk_instruction],
+        &with_compute_budget(rpc_client, TxClass::SetAuthority, &current_authority.pubkey(), &[sdk_instruction]).await,
         Some(&current_authority.pubkey()),
     );
     transaction.sign(&[current_authority], recent_blockhash);
     
-    let signature = rpc_client
-        .send_and_confirm_transaction(&transaction)
+    let signature = send_and_confirm(rpc_client, &transaction).await
         .context("Failed to transfer authority")?;
     
     println!("    ↳ Transaction: {signature}");
     
-    Ok(())
+    Ok(signature)
 }
\ No newline at end of file
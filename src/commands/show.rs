@@ -0,0 +1,183 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk_ids::{
+    bpf_loader, bpf_loader_deprecated, loader_v4,
+    bpf_loader_upgradeable::ID as LOADER_ID,
+};
+use solana_sdk::{
+    pubkey::Pubkey,
+};
+use std::str::FromStr;
+use crate::config::{Config, DeployedProgram, OperationRecord};
+use crate::commands::history::kind_label;
+use crate::commands::verify::fetch_program_bytecode;
+use crate::utils::*;
+
+/// Loader-v4 program accounts start with slot (u64), authority (32 bytes), status (u64)
+const LOADER_V4_HEADER_LEN: usize = 48;
+
+#[derive(Serialize)]
+struct ProgramReport {
+    program_id: String,
+    loader: &'static str,
+    owner: String,
+    executable: bool,
+    lamports: u64,
+    programdata_address: Option<String>,
+    programdata_lamports: Option<u64>,
+    upgrade_authority: Option<String>,
+    immutable: bool,
+    deployed_slot: Option<u64>,
+    bytecode_size: usize,
+    bytecode_hash: String,
+    managed: bool,
+    local: Option<DeployedProgram>,
+    history: Vec<OperationRecord>,
+}
+
+pub async fn execute(program_id_str: String, json: bool) -> Result<()> {
+    let config = Config::new()?;
+    let state = config.load_state()?;
+
+    let program_id = Pubkey::from_str(&program_id_str)
+        .context("Invalid program ID")?;
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    let account = rpc_client
+        .get_account(&program_id).await
+        .context("Program account not found on this network")?;
+
+    let local = state.program(&program_id_str).cloned();
+    let history: Vec<OperationRecord> = state.operations
+        .iter()
+        .filter(|r| r.program_id.as_deref() == Some(program_id_str.as_str()))
+        .cloned()
+        .collect();
+
+    let mut report = ProgramReport {
+        program_id: program_id_str.clone(),
+        loader: "unknown",
+        owner: account.owner.to_string(),
+        executable: account.executable,
+        lamports: account.lamports,
+        programdata_address: None,
+        programdata_lamports: None,
+        upgrade_authority: None,
+        immutable: true,
+        deployed_slot: None,
+        bytecode_size: 0,
+        bytecode_hash: String::new(),
+        managed: local.is_some(),
+        local,
+        history,
+    };
+
+    let owner = account.owner.to_bytes();
+    if owner == LOADER_ID.to_bytes() {
+        let on_chain = fetch_program_bytecode(&rpc_client, &program_id).await?;
+        let programdata_lamports = rpc_client
+            .get_balance(&on_chain.programdata_address).await
+            .ok();
+
+        report.loader = "bpf-loader-upgradeable";
+        report.programdata_address = Some(on_chain.programdata_address.to_string());
+        report.programdata_lamports = programdata_lamports;
+        report.upgrade_authority = on_chain.upgrade_authority.map(|a| a.to_string());
+        report.immutable = on_chain.upgrade_authority.is_none();
+        report.deployed_slot = Some(on_chain.slot);
+        report.bytecode_size = on_chain.bytecode.len();
+        report.bytecode_hash = sha256_hex(&on_chain.bytecode);
+    } else if owner == loader_v4::ID.to_bytes() && account.data.len() >= LOADER_V4_HEADER_LEN {
+        let slot = u64::from_le_bytes(account.data[0..8].try_into()?);
+        let authority = Pubkey::try_from(&account.data[8..40])
+            .map_err(|e| anyhow::anyhow!("Invalid loader-v4 header: {e}"))?;
+        let status = u64::from_le_bytes(account.data[40..48].try_into()?);
+        let bytecode = strip_trailing_zeros(&account.data[LOADER_V4_HEADER_LEN..]);
+
+        // Status 2 is Finalized; the authority field then points at the next version
+        report.loader = "loader-v4";
+        report.immutable = status == 2;
+        report.upgrade_authority = (status != 2).then(|| authority.to_string());
+        report.deployed_slot = Some(slot);
+        report.bytecode_size = bytecode.len();
+        report.bytecode_hash = sha256_hex(bytecode);
+    } else if owner == bpf_loader::ID.to_bytes() || owner == bpf_loader_deprecated::ID.to_bytes() {
+        let bytecode = strip_trailing_zeros(&account.data);
+
+        report.loader = if owner == bpf_loader::ID.to_bytes() {
+            "bpf-loader-2"
+        } else {
+            "bpf-loader-deprecated"
+        };
+        report.bytecode_size = bytecode.len();
+        report.bytecode_hash = sha256_hex(bytecode);
+    } else {
+        report.immutable = false;
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    print_header("Program Details");
+
+    println!();
+    println!("Program ID:        {}", report.program_id);
+    println!("Loader:            {} ({})", report.loader, report.owner);
+    println!("Executable:        {}", report.executable);
+    println!("Lamports:          {}", format_sol(report.lamports));
+
+    if report.loader == "unknown" {
+        print_warning("Account is not owned by a known BPF loader - it may not be a program");
+        return Ok(());
+    }
+
+    if let Some(address) = &report.programdata_address {
+        println!("ProgramData:       {address}");
+    }
+    if let Some(lamports) = report.programdata_lamports {
+        println!("ProgramData rent:  {}", format_sol(lamports));
+    }
+    match &report.upgrade_authority {
+        Some(authority) => println!("Upgrade authority: {authority}"),
+        None => println!("Upgrade authority: None (immutable)"),
+    }
+    if let Some(slot) = report.deployed_slot {
+        println!("Deployed slot:     {slot}");
+    }
+    println!("Bytecode size:     {} bytes", report.bytecode_size);
+    println!("Bytecode hash:     {}", report.bytecode_hash);
+    println!("Explorer:          {}", explorer_address_url(&report.program_id, &state.network));
+
+    match &report.local {
+        Some(program) => {
+            println!("\nManaged by this project");
+            if let Some(name) = &program.name {
+                println!("  Name:            {name}");
+            }
+            if let Some(hash) = &program.verified_hash {
+                let status = if *hash == report.bytecode_hash { "matches" } else { "DIFFERS from on-chain" };
+                println!("  Verified hash:   {hash} ({status})");
+            }
+            for record in &report.history {
+                let datetime = chrono::DateTime::from_timestamp(record.timestamp, 0)
+                    .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_else(|| "unknown".to_string());
+                println!("  {datetime}  {}", kind_label(record.kind));
+            }
+        }
+        None => {
+            println!("\nNot managed by this project.");
+            println!("→ Adopt it with `shield-deploy programs import {}`", report.program_id);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use crate::commands::deploy::deploy_program_bpf_upgradeable;
+use crate::commands::CliProgress;
+use crate::loader::finalize_program;
+use crate::commands::upgrade::upgrade_program_bpf_upgradeable;
+use crate::compute::FeeSettings;
+use crate::config::Config;
+use crate::cost::{default_max_data_len, estimate_deploy};
+use crate::utils::*;
+
+const LOCAL_RPC_URL: &str = "http://127.0.0.1:8899";
+const VALIDATOR_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// One step of the rehearsed flow
+struct Phase {
+    name: &'static str,
+    transactions: usize,
+    cost_lamports: u64,
+}
+
+/// Kills a validator started by `simulate` when the run ends, however it ends
+struct ValidatorGuard(Option<Child>);
+
+impl Drop for ValidatorGuard {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.0 {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+pub async fn execute(
+    program_path: Option<String>,
+    upgrade: bool,
+    finalize: bool,
+    rpc_url: Option<String>,
+) -> Result<()> {
+    print_header("Simulate Deployment");
+
+    let program_file = if let Some(path) = program_path {
+        PathBuf::from(path)
+    } else {
+        detect_program_file()?
+    };
+
+    let program_data = fs::read(&program_file)
+        .with_context(|| format!("Failed to read {}", program_file.display()))?;
+
+    // Everything the run writes lives here and is removed afterwards
+    let scratch = tempfile::tempdir().context("Failed to create a temporary directory")?;
+    let scratch_config = Config::at(scratch.path().join(".shield"));
+
+    let attach = rpc_url.is_some();
+    let rpc_url = rpc_url.unwrap_or_else(|| LOCAL_RPC_URL.to_string());
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url.clone(),
+        commitment(),
+    );
+
+    let _validator = if rpc_client.get_health().await.is_ok() {
+        println!("\nUsing validator at {rpc_url}");
+        ValidatorGuard(None)
+    } else if attach {
+        anyhow::bail!("No validator is reachable at {rpc_url}");
+    } else {
+        println!("\n Starting solana-test-validator...");
+        let guard = ValidatorGuard(Some(start_validator(&scratch.path().join("ledger"))?));
+        wait_for_validator(&rpc_client).await?;
+        println!("  ✓ Validator ready at {rpc_url}");
+        guard
+    };
+
+    if network_from_genesis_hash(&rpc_client.get_genesis_hash().await?.to_string()).is_some() {
+        anyhow::bail!("{rpc_url} is a public cluster; simulate only runs against a local validator");
+    }
+
+    let deployer = Keypair::new();
+    scratch_config.save_deployer(&deployer)?;
+
+    let max_data_len = default_max_data_len(program_data.len());
+    let estimate = estimate_deploy(&rpc_client, program_data.len(), max_data_len, 0).await?;
+    // Headroom for the optional upgrade buffer
+    let funding = estimate.required_balance() * 2;
+
+    println!("\nScratch deployer: {}", deployer.pubkey());
+    println!("Program size:     {} bytes", program_data.len());
+
+    println!("\n Funding scratch deployer (privacy pool stubbed with an airdrop)...");
+    let signature = rpc_client.request_airdrop(&deployer.pubkey(), funding).await?;
+    rpc_client
+        .poll_for_signature_with_commitment(&signature, rpc_client.commitment()).await
+        .context("Airdrop did not confirm")?;
+    println!("  ✓ Airdropped {}", format_sol(funding));
+
+    let mut phases = Vec::new();
+
+    let program_keypair = Keypair::new();
+    let program_id = program_keypair.pubkey();
+    let (transactions, cost_lamports) = measure(&rpc_client, &deployer.pubkey(), async {
+        deploy_program_bpf_upgradeable(&scratch_config, &rpc_client, &deployer, &program_keypair, &program_data, max_data_len, false)
+            .await
+    })
+    .await
+    .context("Simulated deploy failed")?;
+    phases.push(Phase { name: "deploy", transactions, cost_lamports });
+
+    if upgrade {
+        let (transactions, cost_lamports) = measure(&rpc_client, &deployer.pubkey(), async {
+            upgrade_program_bpf_upgradeable(&scratch_config, &rpc_client, &deployer, &program_id, &program_data).await
+        })
+        .await
+        .context("Simulated upgrade failed")?;
+        phases.push(Phase { name: "upgrade", transactions, cost_lamports });
+    }
+
+    if finalize {
+        let (transactions, cost_lamports) = measure(&rpc_client, &deployer.pubkey(), async {
+            finalize_program(&rpc_client, &FeeSettings::current(), &deployer, &deployer, &program_id, &CliProgress).await
+        })
+        .await
+        .context("Simulated finalize failed")?;
+        phases.push(Phase { name: "finalize", transactions, cost_lamports });
+    }
+
+    print_header("Simulation Report");
+
+    println!("\nProgram size:      {} bytes", program_data.len());
+    println!("Buffer account:    {} bytes", UpgradeableLoaderState::size_of_buffer(program_data.len()));
+    println!(
+        "ProgramData:       {} bytes",
+        UpgradeableLoaderState::size_of_programdata(max_data_len)
+    );
+    println!("Write chunks:      {}", estimate.chunk_count);
+    println!();
+    println!("{:<10} {:>12} {:>16}", "PHASE", "TXS", "COST");
+    for phase in &phases {
+        println!("{:<10} {:>12} {:>16}", phase.name, phase.transactions, format_sol(phase.cost_lamports));
+    }
+    let total_transactions: usize = phases.iter().map(|p| p.transactions).sum();
+    let total_cost: u64 = phases.iter().map(|p| p.cost_lamports).sum();
+    println!("{:<10} {:>12} {:>16}", "total", total_transactions, format_sol(total_cost));
+
+    println!("\nEstimated deploy cost: {}", format_sol(estimate.net_cost()));
+    println!("Privacy Cash fees and delays are not included.");
+
+    print_success("Simulation complete; the project's .shield state was not touched");
+
+    Ok(())
+}
+
+/// Run one phase and report how many deployer transactions it sent and what it cost
+async fn measure<F>(rpc_client: &RpcClient, deployer: &Pubkey, phase: F) -> Result<(usize, u64)>
+where
+    F: std::future::Future<Output = Result<solana_sdk::signature::Signature>>,
+{
+    let signatures_before = rpc_client.get_signatures_for_address(deployer).await?.len();
+    let balance_before = rpc_client.get_balance(deployer).await?;
+
+    phase.await?;
+
+    let signatures_after = rpc_client.get_signatures_for_address(deployer).await?.len();
+    let balance_after = rpc_client.get_balance(deployer).await?;
+
+    Ok((
+        signatures_after.saturating_sub(signatures_before),
+        balance_before.saturating_sub(balance_after),
+    ))
+}
+
+fn start_validator(ledger: &std::path::Path) -> Result<Child> {
+    Command::new("solana-test-validator")
+        .arg("--ledger")
+        .arg(ledger)
+        .arg("--reset")
+        .arg("--quiet")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context(
+            "Failed to start solana-test-validator.\n\
+            Install the Solana CLI or pass --rpc-url to attach to a running validator"
+        )
+}
+
+async fn wait_for_validator(rpc_client: &RpcClient) -> Result<()> {
+    let started = Instant::now();
+    while rpc_client.get_health().await.is_err() {
+        if started.elapsed() > VALIDATOR_STARTUP_TIMEOUT {
+            anyhow::bail!("solana-test-validator did not become healthy within 60s");
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    Ok(())
+}
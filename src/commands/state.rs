@@ -0,0 +1,98 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use dialoguer::{theme::ColorfulTheme, Select};
+use crate::config::{Config, StateBackup};
+use crate::utils::*;
+
+#[derive(Subcommand)]
+pub enum StateAction {
+    /// List the automatic backups of state.json
+    List,
+    /// Replace state.json with one of its backups
+    Restore {
+        /// Backup number from `state list` (asks when omitted)
+        index: Option<usize>,
+    },
+}
+
+pub async fn execute(action: StateAction) -> Result<()> {
+    match action {
+        StateAction::List => list(),
+        StateAction::Restore { index } => restore(index),
+    }
+}
+
+fn list() -> Result<()> {
+    print_header("State Backups");
+
+    let config = Config::new()?;
+    let backups = config.list_state_backups()?;
+
+    if backups.is_empty() {
+        println!("\nNo state backups yet. One is kept each time state.json changes.");
+        return Ok(());
+    }
+
+    println!();
+    for (index, backup) in backups.iter().enumerate() {
+        println!("{index:>3}  {}", describe(backup));
+    }
+    println!("\n→ Restore one with `shield-deploy state restore <number>`");
+
+    Ok(())
+}
+
+fn restore(index: Option<usize>) -> Result<()> {
+    print_header("Restore State");
+
+    let config = Config::new()?;
+    let backups = config.list_state_backups()?;
+
+    if backups.is_empty() {
+        anyhow::bail!("No state backups found in {}", config.state_backups_dir().display());
+    }
+
+    let index = match index {
+        Some(index) => index,
+        None => {
+            ensure_interactive("Restore which backup?")?;
+            let items: Vec<String> = backups.iter().map(describe).collect();
+            Select::with_theme(&ColorfulTheme::default())
+                .with_prompt("Restore which backup?")
+                .items(&items)
+                .default(0)
+                .interact()
+                .context("Failed to read selection")?
+        }
+    };
+    let backup = backups
+        .get(index)
+        .with_context(|| format!("No state backup #{index}; run `shield-deploy state list`"))?;
+
+    let current = config.load_state()?;
+    println!("\nCurrent:  {} program(s), {} operation(s)", current.deployed_programs.len(), current.operations.len());
+    println!("Backup:   {}", describe(backup));
+    println!("\nThe current state.json is backed up before it is replaced.\n");
+
+    if !prompt_confirmation("Restore this backup?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let restored = config.restore_state_backup(index)?;
+    print_success(&format!("Restored state from {}", restored.path.display()));
+
+    Ok(())
+}
+
+fn describe(backup: &StateBackup) -> String {
+    let taken_at = chrono::DateTime::from_timestamp(backup.taken_at, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    match (backup.programs, backup.operations) {
+        (Some(programs), Some(operations)) => {
+            format!("{taken_at}  {programs} program(s), {operations} operation(s)")
+        }
+        _ => format!("{taken_at}  (unreadable)"),
+    }
+}
@@ -1,35 +1,108 @@
 use anyhow::{Context, Result};
-use solana_client::rpc_client::RpcClient;
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
 use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Keypair,
     signer::Signer
 };
-use solana_sdk::commitment_config::CommitmentConfig;
-use crate::config::Config;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use crate::config::{
+    BufferStatus, Config, DeployedProgram, OperationKind, ProjectConfig, ProjectState, RotationSeverity, RotationStatus,
+    DEPLOYER_KEY_ENV,
+};
+use crate::commands::verify::fetch_program_bytecode;
+use crate::privacy::{audit_deployer, vault_balances, Linkability, PRIVACY_DELAY_SECS};
 use crate::utils::*;
 
-pub async fn execute() -> Result<()> {
-    print_header("Shield-Deploy Status");
-    
+/// Without a rotation policy, key age is judged against these
+const KEY_AGE_YELLOW_DAYS: i64 = 90;
+const KEY_AGE_RED_DAYS: i64 = 180;
+
+/// A backup older than this no longer reflects recent state
+const STALE_BACKUP_DAYS: i64 = 30;
+
+/// Pending operations older than this are flagged unless `pending_stale_after` is set
+const DEFAULT_PENDING_STALE_AFTER: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HygieneLevel {
+    Green,
+    Yellow,
+    Red,
+}
+
+/// One operational-hygiene signal shown in `status`
+#[derive(Serialize)]
+pub struct HygieneIndicator {
+    pub name: &'static str,
+    pub level: HygieneLevel,
+    pub value: String,
+    pub suggestion: Option<String>,
+}
+
+impl HygieneIndicator {
+    fn new(name: &'static str, level: HygieneLevel, value: impl Into<String>, suggestion: Option<&str>) -> Self {
+        Self { name, level, value: value.into(), suggestion: suggestion.map(String::from) }
+    }
+}
+
+/// Something persisted under `.shield/` that is waiting to be finished
+#[derive(Serialize)]
+pub struct PendingOperation {
+    pub kind: &'static str,
+    /// Key, buffer or program the operation is about
+    pub subject: String,
+    /// Amount or write progress
+    pub detail: String,
+    pub amount_lamports: Option<u64>,
+    pub created_at: Option<i64>,
+    pub stale: bool,
+    pub resume: String,
+    pub cancel: Option<String>,
+}
+
+pub async fn execute(json: bool, metrics: bool, textfile: Option<PathBuf>, verify: bool) -> Result<()> {
     let config = Config::new()?;
-    
+
+    if metrics || textfile.is_some() {
+        let text = render_metrics(&config).await?;
+        return match textfile {
+            Some(path) => write_textfile(&path, &text),
+            None => {
+                print!("{text}");
+                Ok(())
+            }
+        };
+    }
+
     if !config.deployer_exists() {
+        if json {
+            println!("{}", serde_json::json!({ "deployer": null }));
+            return Ok(());
+        }
+        print_header("Shield-Deploy Status");
         println!("\nNo private deployer found.");
         println!("\nRun `shield-deploy init` to get started.");
         return Ok(());
     }
-    
+
     let deployer = config.load_deployer()?;
     let state = config.load_state()?;
-    
+
     let rpc_url = get_rpc_url()?;
     let rpc_client = RpcClient::new_with_commitment(
         rpc_url.clone(),
-        CommitmentConfig::confirmed(),
+        commitment(),
     );
-    
-    let balance = rpc_client.get_balance(&deployer.pubkey())
+
+    let balance = rpc_client.get_balance(&deployer.pubkey()).await
         .context("Failed to get deployer balance")?;
-    
+
     let balance_status = if balance >= 5_000_000_000 {
         "sufficient"
     } else if balance >= 1_000_000_000 {
@@ -37,40 +110,583 @@ pub async fn execute() -> Result<()> {
     } else {
         "insufficient"
     };
-    
+
+    let shielded = vault_balances(&rpc_client, config.load_vault_keys()?).await?;
+    let rotation = config.rotation_status()?;
+    let hygiene = hygiene(&config, &state, &rpc_client, &deployer.pubkey()).await?;
+    let pending = pending_operations(&config, &state, &rpc_client, &shielded).await?;
+
+    if json {
+        let report = serde_json::json!({
+            "deployer": deployer.pubkey().to_string(),
+            "deployer_file": (!Config::deployer_from_env()).then(|| config.deployer_path()),
+            "deployer_from_env": Config::deployer_from_env(),
+            "network": state.network,
+            "balance_lamports": balance,
+            "shielded_lamports": shielded.iter().map(|(_, balance)| balance).sum::<u64>(),
+            "programs": state.deployed_programs.iter().map(|p| serde_json::json!({
+                "program_id": p.program_id,
+                "authority": p.authority,
+                "immutable": p.immutable,
+                "isolated_authority": p.isolated_authority,
+                "last_upgraded": p.last_upgraded,
+            })).collect::<Vec<_>>(),
+            "rotation": rotation.as_ref().map(|r| serde_json::json!({
+                "days_left": r.days_left(),
+                "operations_left": r.operations_left(),
+                "overdue": r.overdue(),
+            })),
+            "hygiene": hygiene,
+            "pending_operations": pending,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    print_header("Shield-Deploy Status");
+
     println!();
-    println!("Project:        {}", 
+    println!("Project:        {}",
         std::env::current_dir()
             .ok()
             .and_then(|p| p.file_name().map(|s| s.to_string_lossy().to_string()))
             .unwrap_or_else(|| "unknown".to_string())
     );
     println!("Deployer:       active");
+    if Config::deployer_from_env() {
+        println!("Deployer file:  ${DEPLOYER_KEY_ENV} (not on disk)");
+    } else if let Some(path) = &state.deployer_file {
+        println!("Deployer file:  {}", path.display());
+    }
     println!("Network:        {}", state.network);
     println!("Balance:        {} ({})", format_sol(balance), balance_status);
+    if !shielded.is_empty() {
+        let total: u64 = shielded.iter().map(|(_, balance)| balance).sum();
+        println!("Shielded:       {} in {} vault(s), released by the next fund", format_sol(total), shielded.len());
+    }
     println!("Programs:       {} deployed", state.deployed_programs.len());
-    
+    if let Some(rotation) = rotation {
+        let label = match rotation.severity() {
+            RotationSeverity::Ok => "",
+            RotationSeverity::DueSoon => " (due soon)",
+            RotationSeverity::Overdue | RotationSeverity::LongOverdue => " (overdue, run `shield-deploy rotate`)",
+        };
+        println!("Rotation:       {}{label}", rotation.summary());
+    }
+
     if !state.deployed_programs.is_empty() {
         println!("\nDeployed Programs:");
         for (i, program) in state.deployed_programs.iter().enumerate() {
             println!("  {}. {}", i + 1, program.program_id);
+            let deployer_pubkey = deployer.pubkey().to_string();
+            let authority = match program.authority.as_deref() {
+                _ if program.immutable => "none (immutable)".to_string(),
+                Some(key) if program.isolated_authority => format!("dedicated key {key}"),
+                None => "shared deployer".to_string(),
+                Some(key) if key == deployer_pubkey => "shared deployer".to_string(),
+                Some(key) => format!("external {key}"),
+            };
+            println!("     Authority: {authority}");
+            if let Some(build) = &program.verified_build {
+                println!("     ✓ Verified build (commit {})", &build.commit[..build.commit.len().min(8)]);
+            }
+            if verify {
+                println!("     Bytecode: {}", reverify_bytecode(&rpc_client, program).await);
+            } else if let Some(hash) = &program.verified_hash {
+                println!("     Bytecode: {} (verified)", &hash[..hash.len().min(16)]);
+            }
             if let Some(upgraded) = program.last_upgraded {
-                let datetime = chrono::DateTime::from_timestamp(upgraded, 0)
-                    .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
-                    .unwrap_or_else(|| "unknown".to_string());
-                println!("     Last upgraded: {datetime}");
+                println!("     Last upgraded: {}", format_timestamp(upgraded));
+            }
+        }
+    }
+
+    // Unfinished and handed-off buffers are listed with the pending operations
+    let ready_buffers: Vec<_> = state.buffers
+        .iter()
+        .filter(|b| b.status == BufferStatus::Ready)
+        .collect();
+    if !ready_buffers.is_empty() {
+        println!("\nBuffers:");
+        for buffer in ready_buffers {
+            println!("  • {} ({} bytes, ready)", buffer.address, buffer.size);
+        }
+    }
+
+    if !pending.is_empty() {
+        println!("\nPending Operations:");
+        for operation in &pending {
+            let age = operation
+                .created_at
+                .map(|at| format!(", {} old", format_duration(age_of(at))))
+                .unwrap_or_default();
+            let stale = if operation.stale { " ⚠️  stale" } else { "" };
+            println!("  • {} {} ({}{age}){stale}", operation.kind, operation.subject, operation.detail);
+            println!("    ↳ Resume: {}", operation.resume);
+            if let Some(cancel) = &operation.cancel {
+                println!("    ↳ Cancel: {cancel}");
             }
         }
     }
-    
-    println!("\nPrivacy:");
-    println!("• Main wallet not linked on-chain");
-    println!("• Deployer authority active");
-    
+
+    println!("\nHygiene:");
+    for indicator in &hygiene {
+        let icon = match indicator.level {
+            HygieneLevel::Green => "✓",
+            HygieneLevel::Yellow => "⚠️ ",
+            HygieneLevel::Red => "✗",
+        };
+        println!("{icon} {:<14} {}", indicator.name, indicator.value);
+        if let Some(suggestion) = &indicator.suggestion {
+            println!("  ↳ {suggestion}");
+        }
+    }
+
     if balance_status == "low" || balance_status == "insufficient" {
         println!("\n Low balance detected");
         println!("   Run `shield-deploy fund` to add more SOL");
     }
-    
+
+    Ok(())
+}
+
+/// Gauges for `status --metrics`, in the Prometheus/OpenMetrics text format
+///
+/// Names and labels are part of the CLI's interface; see the README before
+/// renaming any. RPC failures become `shield_deploy_up 0` rather than an
+/// error so a scrape never fails.
+async fn render_metrics(config: &Config) -> Result<String> {
+    let mut out = MetricsWriter::default();
+
+    let deployer = config.deployer_exists().then(|| config.load_deployer()).transpose()?;
+    out.gauge("shield_deploy_deployer_present", "Whether a private deployer exists (1) or not (0)", &[], deployer.is_some() as u64);
+    let Some(deployer) = deployer else {
+        out.gauge("shield_deploy_up", "Whether the RPC endpoint answered (1) or not (0)", &[], 1);
+        return Ok(out.finish());
+    };
+
+    let state = config.load_state()?;
+    let deployer_label = deployer.pubkey().to_string();
+    let labels = [("deployer", deployer_label.as_str()), ("network", state.network.as_str())];
+
+    let rpc_client = RpcClient::new_with_commitment(get_rpc_url()?, commitment());
+    let balance = rpc_client.get_balance(&deployer.pubkey()).await.ok();
+    out.gauge("shield_deploy_up", "Whether the RPC endpoint answered (1) or not (0)", &[], balance.is_some() as u64);
+
+    let shielded = match balance {
+        Some(balance) => {
+            out.gauge("shield_deploy_balance_lamports", "Deployer balance in lamports", &labels, balance);
+            let shielded = vault_balances(&rpc_client, config.load_vault_keys()?).await.unwrap_or_default();
+            out.gauge(
+                "shield_deploy_shielded_lamports",
+                "Lamports held in project vaults awaiting release",
+                &labels,
+                shielded.iter().map(|(_, lamports)| lamports).sum(),
+            );
+            shielded
+        }
+        None => Vec::new(),
+    };
+
+    if balance.is_some() {
+        let pending = pending_operations(config, &state, &rpc_client, &shielded).await?;
+        out.gauge("shield_deploy_pending_operations", "Unfinished operations under .shield", &labels, pending.len() as u64);
+        out.gauge(
+            "shield_deploy_pending_operations_stale",
+            "Unfinished operations older than pending_stale_after",
+            &labels,
+            pending.iter().filter(|p| p.stale).count() as u64,
+        );
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    if let Some(last) = state.operations.iter().map(|op| op.timestamp).max() {
+        out.gauge("shield_deploy_last_operation_timestamp_seconds", "Unix time of the newest recorded operation", &labels, last.max(0) as u64);
+        out.gauge("shield_deploy_last_operation_age_seconds", "Seconds since the newest recorded operation", &labels, now.saturating_sub(last).max(0) as u64);
+    }
+    let rotation = RotationStatus::from_state(&state, &ProjectConfig::load()?);
+    if let Some(age) = rotation.key_age_secs {
+        out.gauge("shield_deploy_deployer_key_age_seconds", "Seconds since the deployer was created or rotated", &labels, age.max(0) as u64);
+    }
+    out.gauge("shield_deploy_deployer_operations", "Operations recorded since the deployer was created or rotated", &labels, rotation.operations);
+
+    out.gauge("shield_deploy_programs", "Programs managed by this project", &labels, state.deployed_programs.len() as u64);
+    for program in &state.deployed_programs {
+        let name = program.name.as_deref().unwrap_or("");
+        let program_labels = [("program_id", program.program_id.as_str()), ("name", name)];
+        out.gauge("shield_deploy_program_immutable", "Whether the program's upgrade authority is None (1) or not (0)", &program_labels, program.immutable as u64);
+        out.gauge(
+            "shield_deploy_program_last_change_timestamp_seconds",
+            "Unix time the program was last deployed or upgraded",
+            &program_labels,
+            program.last_upgraded.unwrap_or(program.deployed_at).max(0) as u64,
+        );
+    }
+
+    Ok(out.finish())
+}
+
+/// Accumulates samples, emitting HELP/TYPE once per metric name
+#[derive(Default)]
+struct MetricsWriter {
+    text: String,
+    declared: Vec<&'static str>,
+}
+
+impl MetricsWriter {
+    fn gauge(&mut self, name: &'static str, help: &str, labels: &[(&str, &str)], value: u64) {
+        if !self.declared.contains(&name) {
+            self.declared.push(name);
+            self.text.push_str(&format!("# HELP {name} {help}\n# TYPE {name} gauge\n"));
+        }
+        let labels = labels
+            .iter()
+            .map(|(key, value)| format!("{key}=\"{}\"", escape_label(value)))
+            .collect::<Vec<_>>()
+            .join(",");
+        if labels.is_empty() {
+            self.text.push_str(&format!("{name} {value}\n"));
+        } else {
+            self.text.push_str(&format!("{name}{{{labels}}} {value}\n"));
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.text.push_str("# EOF\n");
+        self.text
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Replace the file atomically so the textfile collector never reads a partial write
+fn write_textfile(path: &Path, text: &str) -> Result<()> {
+    let tmp = path.with_extension("prom.tmp");
+    std::fs::write(&tmp, text)
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Key age, usage, backups and funding, judged from state history and the audit
+async fn hygiene(
+    config: &Config,
+    state: &ProjectState,
+    rpc_client: &RpcClient,
+    deployer: &Pubkey,
+) -> Result<Vec<HygieneIndicator>> {
+    let project_config = ProjectConfig::load()?;
+    let rotation = RotationStatus::from_state(state, &project_config);
+    let has_policy = project_config.rotate_after_days.is_some() || project_config.rotate_after_operations.is_some();
+    let policy_level = match rotation.severity() {
+        RotationSeverity::Ok => HygieneLevel::Green,
+        RotationSeverity::DueSoon => HygieneLevel::Yellow,
+        RotationSeverity::Overdue | RotationSeverity::LongOverdue => HygieneLevel::Red,
+    };
+    let now = chrono::Utc::now().timestamp();
+    let mut indicators = Vec::new();
+
+    let last_rotation = state
+        .operations
+        .iter()
+        .rev()
+        .find(|op| op.kind == OperationKind::Rotate)
+        .map(|op| format!("last rotated {}", format_timestamp(op.timestamp)))
+        .unwrap_or_else(|| "never rotated".to_string());
+    indicators.push(match rotation.key_age_secs {
+        Some(age) => {
+            let days = age / 86_400;
+            let level = if has_policy {
+                policy_level
+            } else if days >= KEY_AGE_RED_DAYS {
+                HygieneLevel::Red
+            } else if days >= KEY_AGE_YELLOW_DAYS {
+                HygieneLevel::Yellow
+            } else {
+                HygieneLevel::Green
+            };
+            HygieneIndicator::new(
+                "key age",
+                level,
+                format!("{days} days, {last_rotation}"),
+                (level != HygieneLevel::Green).then_some("Run `shield-deploy rotate`"),
+            )
+        }
+        None => HygieneIndicator::new("key age", HygieneLevel::Yellow, "unknown (no init record)", None),
+    });
+
+    let operations_level = if has_policy { policy_level } else { HygieneLevel::Green };
+    indicators.push(HygieneIndicator::new(
+        "operations",
+        operations_level,
+        format!("{} signed with this key", rotation.operations),
+        (operations_level != HygieneLevel::Green).then_some("Run `shield-deploy rotate`"),
+    ));
+
+    // Backups made before this was recorded are found by their file times
+    let last_backup = state.last_backup_at.or_else(|| newest_backup(config));
+    indicators.push(match last_backup {
+        Some(at) if (now - at) / 86_400 < STALE_BACKUP_DAYS => {
+            HygieneIndicator::new("backup", HygieneLevel::Green, format!("last {}", format_timestamp(at)), None)
+        }
+        Some(at) => HygieneIndicator::new(
+            "backup",
+            HygieneLevel::Yellow,
+            format!("last {}", format_timestamp(at)),
+            Some("Run `shield-deploy backup`"),
+        ),
+        None => HygieneIndicator::new(
+            "backup",
+            HygieneLevel::Red,
+            "never backed up or exported",
+            Some("Run `shield-deploy backup`"),
+        ),
+    });
+
+    let vault_keys: Vec<Pubkey> = config.load_vault_keys()?.iter().map(|k| k.pubkey()).collect();
+    let privacy_delay = project_config
+        .privacy_delay()
+        .unwrap_or(Duration::from_secs(PRIVACY_DELAY_SECS));
+    indicators.push(match audit_deployer(rpc_client, deployer, state, &vault_keys, privacy_delay).await {
+        Ok(report) => match report.verdict {
+            Linkability::Private => {
+                HygieneIndicator::new("funding", HygieneLevel::Green, "private only", None)
+            }
+            Linkability::Weak => HygieneIndicator::new(
+                "funding",
+                HygieneLevel::Yellow,
+                "weakly linkable activity",
+                Some("Run `shield-deploy audit` for details"),
+            ),
+            Linkability::Linked => HygieneIndicator::new(
+                "funding",
+                HygieneLevel::Red,
+                "direct transfers touch the deployer",
+                Some("Run `shield-deploy audit`, then consider `rotate`"),
+            ),
+        },
+        Err(_) => HygieneIndicator::new(
+            "funding",
+            HygieneLevel::Yellow,
+            "history unavailable",
+            Some("Run `shield-deploy audit` when the RPC is reachable"),
+        ),
+    });
+
+    Ok(indicators)
+}
+
+/// Every plan persisted under `.shield/` that a later command will pick up
+async fn pending_operations(
+    config: &Config,
+    state: &ProjectState,
+    rpc_client: &RpcClient,
+    shielded: &[(Keypair, u64)],
+) -> Result<Vec<PendingOperation>> {
+    let stale_after = ProjectConfig::load()?
+        .pending_stale_after()
+        .unwrap_or(DEFAULT_PENDING_STALE_AFTER);
+    let is_stale = |created_at: Option<i64>| created_at.is_some_and(|at| age_of(at) > stale_after);
+    let mut pending = Vec::new();
+
+    let plans: Vec<_> = config.load_fund_plans()?.into_iter().filter(|p| p.is_open()).collect();
+    for plan in &plans {
+        let due = format_timestamp(plan.execute_after);
+        let worker = match (plan.worker_pid, plan.heartbeat) {
+            (Some(pid), Some(beat)) => format!(
+                "worker {pid} {} (heartbeat {})",
+                if plan.worker_alive() { "alive" } else { "not responding" },
+                format_timestamp(beat)
+            ),
+            (Some(pid), None) => format!("worker {pid} starting"),
+            _ => "no worker".to_string(),
+        };
+        let detail = match &plan.error {
+            Some(error) => format!("{}, failed: {error}", format_sol(plan.amount_lamports)),
+            None => format!("{}, due {due}, {worker}", format_sol(plan.amount_lamports)),
+        };
+        let overdue = !plan.worker_alive() && chrono::Utc::now().timestamp() >= plan.execute_after;
+        pending.push(PendingOperation {
+            kind: "scheduled decompression",
+            subject: plan.vault.clone(),
+            detail,
+            amount_lamports: Some(plan.amount_lamports),
+            created_at: Some(plan.created_at),
+            stale: overdue || is_stale(Some(plan.created_at)),
+            resume: format!("`shield-deploy fund --resume` after {due}"),
+            cancel: Some(format!("`shield-deploy fund cancel {}`", plan.vault)),
+        });
+    }
+
+    for (vault, balance) in shielded {
+        if plans.iter().any(|p| p.vault == vault.pubkey().to_string()) {
+            continue;
+        }
+        let created_at = file_created(&config.vault_dir().join(format!("{}.json", vault.pubkey())));
+        pending.push(PendingOperation {
+            kind: "shielded remainder",
+            subject: vault.pubkey().to_string(),
+            detail: format_sol(*balance),
+            amount_lamports: Some(*balance),
+            created_at,
+            stale: is_stale(created_at),
+            resume: "`shield-deploy fund` offers to release it to the deployer".to_string(),
+            cancel: None,
+        });
+    }
+
+    for spill in config.load_spill_keys()? {
+        let balance = rpc_client.get_balance(&spill.pubkey()).await.unwrap_or(0);
+        if balance == 0 {
+            continue;
+        }
+        let created_at = file_created(&config.spill_dir().join(format!("{}.json", spill.pubkey())));
+        pending.push(PendingOperation {
+            kind: "spill compression",
+            subject: spill.pubkey().to_string(),
+            detail: format_sol(balance),
+            amount_lamports: Some(balance),
+            created_at,
+            stale: is_stale(created_at),
+            resume: "retried by the next `shield-deploy upgrade <program-id> --spill-compressed`".to_string(),
+            cancel: None,
+        });
+    }
+
+    for buffer in &state.buffers {
+        let created_at = Some(buffer.created_at);
+        match buffer.status {
+            BufferStatus::Writing => pending.push(PendingOperation {
+                kind: "buffer write",
+                subject: buffer.address.clone(),
+                detail: write_progress(rpc_client, &buffer.address, buffer.size).await,
+                amount_lamports: None,
+                created_at,
+                stale: is_stale(created_at),
+                resume: format!(
+                    "`shield-deploy write-buffer` with the same artifact{}",
+                    buffer.name.as_deref().map(|n| format!(" ({n}.so)")).unwrap_or_default()
+                ),
+                cancel: Some("`shield-deploy clean --pending`".to_string()),
+            }),
+            BufferStatus::HandedOff => pending.push(PendingOperation {
+                kind: "upgrade handoff",
+                subject: buffer.address.clone(),
+                detail: format!(
+                    "{} bytes for {}",
+                    buffer.size,
+                    buffer.program_id.as_deref().unwrap_or("unknown program")
+                ),
+                amount_lamports: None,
+                created_at,
+                stale: is_stale(created_at),
+                resume: format!(
+                    "{} executes .shield/proposals/upgrade-{}.json",
+                    buffer.authority, buffer.address
+                ),
+                cancel: None,
+            }),
+            _ => {}
+        }
+    }
+
+    // A staged key outlives `rotate` only when it stopped between sending and committing
+    if let Ok(entries) = std::fs::read_dir(config.authorities_dir()) {
+        for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+            let Some(program_id) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_suffix(".pending.json"))
+                .map(String::from)
+            else {
+                continue;
+            };
+            let created_at = file_created(&path);
+            pending.push(PendingOperation {
+                kind: "authority rotation",
+                subject: program_id.clone(),
+                detail: "interrupted".to_string(),
+                amount_lamports: None,
+                created_at,
+                stale: is_stale(created_at),
+                resume: format!(
+                    "compare {program_id}'s on-chain authority with {}, then re-run `shield-deploy rotate`",
+                    path.display()
+                ),
+                cancel: Some(format!("delete {} if the on-chain authority is unchanged", path.display())),
+            });
+        }
+    }
+
+    Ok(pending)
+}
+
+/// Chunks that already hold data; zeroed chunks are assumed unwritten
+async fn write_progress(rpc_client: &RpcClient, address: &str, size: usize) -> String {
+    let chunk_size = calculate_max_write_chunk_size();
+    let total = size.div_ceil(chunk_size);
+    let header_len = UpgradeableLoaderState::size_of_buffer_metadata();
+
+    let account = match Pubkey::from_str(address) {
+        Ok(address) => rpc_client.get_account(&address).await.ok(),
+        Err(_) => None,
+    };
+    match account {
+        Some(account) if account.data.len() >= header_len + size => {
+            let written = account.data[header_len..header_len + size]
+                .chunks(chunk_size)
+                .filter(|chunk| chunk.iter().any(|b| *b != 0))
+                .count();
+            format!("≈{written}/{total} chunks written")
+        }
+        Some(_) => format!("{total} chunks, buffer too small"),
+        None => format!("{total} chunks, buffer closed or missing"),
+    }
+}
+
+fn file_created(path: &std::path::Path) -> Option<i64> {
+    std::fs::metadata(path)
+        .ok()?
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|since| since.as_secs() as i64)
+}
+
+fn age_of(timestamp: i64) -> Duration {
+    Duration::from_secs(chrono::Utc::now().timestamp().saturating_sub(timestamp).max(0) as u64)
+}
+
+fn newest_backup(config: &Config) -> Option<i64> {
+    std::fs::read_dir(config.backups_dir())
+        .ok()?
+        .filter_map(|e| e.ok()?.metadata().ok()?.modified().ok())
+        .max()
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|since| since.as_secs() as i64)
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Compare the on-chain bytecode with the hash recorded at the last deploy or verify
+async fn reverify_bytecode(rpc_client: &RpcClient, program: &DeployedProgram) -> String {
+    let Some(expected) = program.verified_hash.as_ref().or(program.bytecode_hash.as_ref()) else {
+        return "no recorded hash".to_string();
+    };
+    let Ok(program_id) = Pubkey::from_str(&program.program_id) else {
+        return "invalid program ID".to_string();
+    };
+    match fetch_program_bytecode(rpc_client, &program_id).await {
+        Ok(on_chain) if sha256_hex(&on_chain.bytecode) == *expected => "✓ matches the recorded hash".to_string(),
+        Ok(_) => "⚠️  changed since it was recorded, run `shield-deploy verify`".to_string(),
+        Err(e) => format!("could not fetch ({e})"),
+    }
+}
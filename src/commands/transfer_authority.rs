@@ -1,23 +1,34 @@
 use anyhow::{Context, Result};
-use solana_client::rpc_client::RpcClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_loader_v3_interface::{
     instruction as bpf_loader_upgradeable,
+    state::UpgradeableLoaderState,
 };
 use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
-use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::{
     pubkey::Pubkey,
-    signature::Signer,
+    signature::{Signature, Signer},
     transaction::Transaction,
     instruction::Instruction as SdkInstruction,
     instruction::AccountMeta,
 };
 use solana_pubkey::Pubkey as SolanaPubkeyV2;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use crate::config::Config;
+use crate::compute::{with_compute_budget, TxClass};
+use crate::config::{Config, OperationKind, OperationRecord, ProjectState};
 use crate::utils::*;
 
-pub async fn execute(new_authority: String) -> Result<()> {
+pub async fn execute(new_authority: Option<String>, plan: Option<PathBuf>, dry_run: bool) -> Result<()> {
+    if let Some(plan) = plan {
+        return execute_plan(&plan, dry_run).await;
+    }
+    let new_authority = new_authority
+        .ok_or_else(|| anyhow::anyhow!("Pass a new authority or --plan <file>"))?;
+
     print_header("Transfer Upgrade Authority");
     
     let config = Config::new()?;
@@ -30,7 +41,7 @@ pub async fn execute(new_authority: String) -> Result<()> {
     }
     
     let deployer = config.load_deployer()?;
-    let state = config.load_state()?;
+    let mut state = config.load_state()?;
     
     let new_authority_pubkey = Pubkey::from_str(&new_authority)
         .context("Invalid public key for new authority")?;
@@ -64,24 +75,39 @@ pub async fn execute(new_authority: String) -> Result<()> {
     let rpc_url = get_rpc_url()?;
     let rpc_client = RpcClient::new_with_commitment(
         rpc_url.clone(),
-        CommitmentConfig::confirmed(),
+        authority_commitment(),
     );
     
     println!("\n Transferring authority...");
+    println!("  ↳ Confirming at {} commitment", commitment_label(rpc_client.commitment()));
     
-    for program in &state.deployed_programs {
-        let program_id = Pubkey::from_str(&program.program_id)
+    for i in 0..state.deployed_programs.len() {
+        let program_id = Pubkey::from_str(&state.deployed_programs[i].program_id)
             .context("Invalid program ID")?;
         
-        transfer_upgrade_authority(
+        let program_authority = config.load_program_authority(&program_id)?;
+        let signature = transfer_upgrade_authority(
             &rpc_client,
             &deployer,
+            program_authority.as_ref().unwrap_or(&deployer),
             &program_id,
             &new_authority_pubkey,
         )
         .await
         .context(format!("Failed to transfer authority for {program_id}"))?;
         
+        if program_authority.is_some() {
+            config.remove_program_authority(&program_id)?;
+            state.deployed_programs[i].isolated_authority = false;
+        }
+        state.deployed_programs[i].authority = Some(new_authority_pubkey.to_string());
+        let mut record = OperationRecord::new(OperationKind::TransferAuthority, &get_network_name());
+        record.program_id = Some(program_id.to_string());
+        record.signatures.push(signature.to_string());
+        record.note = Some(format!("new authority {new_authority_pubkey}"));
+        state.operations.push(record);
+        config.save_state(&state)?;
+        
         println!("  ✓ Authority transferred for {program_id}");
     }
     
@@ -97,16 +123,333 @@ pub async fn execute(new_authority: String) -> Result<()> {
     Ok(())
 }
 
+/// One row of a transfer plan, as written in the file
+#[derive(Serialize, Deserialize, Clone)]
+struct PlanRow {
+    program_id: String,
+    new_authority: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PlanFormat {
+    Json,
+    Csv,
+}
+
+/// Transfer each program in a JSON or CSV plan to its own new authority
+async fn execute_plan(plan_path: &Path, dry_run: bool) -> Result<()> {
+    print_header("Transfer Upgrade Authority (Plan)");
+
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    let deployer = config.load_deployer()?;
+    let mut state = config.load_state()?;
+
+    let raw = fs::read_to_string(plan_path)
+        .with_context(|| format!("Failed to read {}", plan_path.display()))?;
+    let format = plan_format(plan_path, &raw);
+    let rows = parse_plan(&raw, format)
+        .with_context(|| format!("{} is not a valid transfer plan", plan_path.display()))?;
+
+    if rows.is_empty() {
+        anyhow::bail!("{} contains no transfers", plan_path.display());
+    }
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, authority_commitment());
+
+    // Every row is checked before anything is sent, so a bad row can't leave a half-applied plan
+    println!("\n Validating {} row(s)...", rows.len());
+    let transfers = validate_plan(&rpc_client, &config, &state, &deployer.pubkey(), &rows).await?;
+
+    println!("\n{:<4} {:<44} NEW AUTHORITY", "#", "PROGRAM");
+    for (i, (program_id, new_authority)) in transfers.iter().enumerate() {
+        println!("{:<4} {:<44} {}", i + 1, program_id.to_string(), new_authority);
+    }
+
+    if dry_run {
+        print_success("Plan is valid (dry run, nothing sent)");
+        return Ok(());
+    }
+
+    println!("\n⚠️  Warning:");
+    println!("• After transfer, the private deployer cannot upgrade these programs");
+    println!("• This operation cannot be undone");
+    println!("• Make sure you control every new authority\n");
+
+    if !prompt_confirmation(&format!("Transfer {} program(s)?", transfers.len()))? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    println!("\n Transferring authority...");
+    println!("  ↳ Confirming at {} commitment", commitment_label(rpc_client.commitment()));
+
+    let mut failed: Vec<PlanRow> = Vec::new();
+    for (i, (program_id, new_authority)) in transfers.iter().enumerate() {
+        println!("\n[{}/{}] {program_id} → {new_authority}", i + 1, transfers.len());
+
+        let row = PlanRow {
+            program_id: program_id.to_string(),
+            new_authority: new_authority.to_string(),
+        };
+
+        let program_authority = match config.load_program_authority(program_id) {
+            Ok(program_authority) => program_authority,
+            Err(e) => {
+                println!("  ✗ {e:#}");
+                failed.push(row);
+                continue;
+            }
+        };
+        let result = transfer_upgrade_authority(
+            &rpc_client,
+            &deployer,
+            program_authority.as_ref().unwrap_or(&deployer),
+            program_id,
+            new_authority,
+        )
+        .await;
+
+        let signature = match result {
+            Ok(signature) => signature,
+            Err(e) => {
+                println!("  ✗ {e:#}");
+                failed.push(row);
+                continue;
+            }
+        };
+
+        if let Err(e) = record_transfer(
+            &config,
+            &mut state,
+            program_id,
+            new_authority,
+            &signature,
+            program_authority.is_some(),
+        ) {
+            println!("  ✗ Authority transferred ({signature}) but recording it failed: {e:#}");
+            failed.push(row);
+            continue;
+        }
+
+        println!("  ✓ Authority transferred");
+    }
+
+    let transferred = transfers.len() - failed.len();
+    println!("\nTransferred: {transferred}");
+    println!("Failed:      {}", failed.len());
+
+    if failed.is_empty() {
+        print_success("Authority transfer plan complete");
+        return Ok(());
+    }
+
+    let remainder = remainder_path(plan_path, format);
+    fs::write(&remainder, render_plan(&failed, format)?)
+        .with_context(|| format!("Failed to write {}", remainder.display()))?;
+    print_warning(&format!("{} transfer(s) failed", failed.len()));
+    println!("→ Re-run them with `shield-deploy transfer-authority --plan {}`", remainder.display());
+
+    anyhow::bail!("Authority transfer plan partially failed")
+}
+
+/// Forget the isolated key and record a landed transfer in state
+fn record_transfer(
+    config: &Config,
+    state: &mut ProjectState,
+    program_id: &Pubkey,
+    new_authority: &Pubkey,
+    signature: &Signature,
+    isolated: bool,
+) -> Result<()> {
+    if isolated {
+        config.remove_program_authority(program_id)?;
+    }
+    if let Some(program) = state.program_mut(&program_id.to_string()) {
+        program.isolated_authority = false;
+        program.authority = Some(new_authority.to_string());
+    }
+    let mut record = OperationRecord::new(OperationKind::TransferAuthority, &get_network_name());
+    record.program_id = Some(program_id.to_string());
+    record.signatures.push(signature.to_string());
+    record.note = Some(format!("new authority {new_authority}"));
+    state.operations.push(record);
+    config.save_state(state)
+}
+
+fn plan_format(path: &Path, raw: &str) -> PlanFormat {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("json") => PlanFormat::Json,
+        Some(ext) if ext.eq_ignore_ascii_case("csv") => PlanFormat::Csv,
+        _ if raw.trim_start().starts_with(['[', '{']) => PlanFormat::Json,
+        _ => PlanFormat::Csv,
+    }
+}
+
+/// JSON is either `[{"program_id", "new_authority"}]` or `{program_id: new_authority}`;
+/// CSV is `program_id,new_authority` per line with an optional header
+fn parse_plan(raw: &str, format: PlanFormat) -> Result<Vec<PlanRow>> {
+    match format {
+        PlanFormat::Json => {
+            if raw.trim_start().starts_with('{') {
+                let map: BTreeMap<String, String> = serde_json::from_str(raw)?;
+                Ok(map
+                    .into_iter()
+                    .map(|(program_id, new_authority)| PlanRow { program_id, new_authority })
+                    .collect())
+            } else {
+                Ok(serde_json::from_str(raw)?)
+            }
+        }
+        PlanFormat::Csv => {
+            let mut rows = Vec::new();
+            for (number, line) in raw.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+                if rows.is_empty() && fields.first().is_some_and(|f| f.eq_ignore_ascii_case("program_id")) {
+                    continue;
+                }
+                let [program_id, new_authority] = fields[..] else {
+                    anyhow::bail!("line {}: expected program_id,new_authority", number + 1);
+                };
+                rows.push(PlanRow {
+                    program_id: program_id.to_string(),
+                    new_authority: new_authority.to_string(),
+                });
+            }
+            Ok(rows)
+        }
+    }
+}
+
+fn render_plan(rows: &[PlanRow], format: PlanFormat) -> Result<String> {
+    Ok(match format {
+        PlanFormat::Json => serde_json::to_string_pretty(rows)?,
+        PlanFormat::Csv => {
+            let mut csv = String::from("program_id,new_authority\n");
+            for row in rows {
+                csv.push_str(&format!("{},{}\n", row.program_id, row.new_authority));
+            }
+            csv
+        }
+    })
+}
+
+/// `plan.csv` leaves its failures in `plan.remaining.csv` next to it
+fn remainder_path(plan_path: &Path, format: PlanFormat) -> PathBuf {
+    let stem = plan_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().trim_end_matches(".remaining").to_string())
+        .unwrap_or_else(|| "plan".to_string());
+    let extension = match format {
+        PlanFormat::Json => "json",
+        PlanFormat::Csv => "csv",
+    };
+    plan_path.with_file_name(format!("{stem}.remaining.{extension}"))
+}
+
+async fn validate_plan(
+    rpc_client: &RpcClient,
+    config: &Config,
+    state: &ProjectState,
+    deployer: &Pubkey,
+    rows: &[PlanRow],
+) -> Result<Vec<(Pubkey, Pubkey)>> {
+    let mut transfers = Vec::with_capacity(rows.len());
+    let mut errors = Vec::new();
+    let mut seen = HashSet::new();
+
+    for (i, row) in rows.iter().enumerate() {
+        let row_label = format!("row {} ({})", i + 1, row.program_id);
+
+        let program_id = match Pubkey::from_str(&row.program_id) {
+            Ok(program_id) => program_id,
+            Err(_) => {
+                errors.push(format!("{row_label}: invalid program ID"));
+                continue;
+            }
+        };
+        let new_authority = match Pubkey::from_str(&row.new_authority) {
+            Ok(new_authority) => new_authority,
+            Err(_) => {
+                errors.push(format!("{row_label}: invalid new authority '{}'", row.new_authority));
+                continue;
+            }
+        };
+        if !seen.insert(program_id) {
+            errors.push(format!("{row_label}: program listed more than once"));
+            continue;
+        }
+        if !state.deployed_programs.iter().any(|p| p.program_id == row.program_id) {
+            errors.push(format!("{row_label}: not managed by this project"));
+            continue;
+        }
+
+        let ours = config
+            .load_program_authority(&program_id)?
+            .map(|k| k.pubkey())
+            .unwrap_or(*deployer);
+        match current_upgrade_authority(rpc_client, &program_id).await {
+            Ok(Some(current)) if current == ours => {}
+            Ok(Some(current)) => errors.push(format!("{row_label}: authority is {current}, not ours")),
+            Ok(None) => errors.push(format!("{row_label}: program is immutable")),
+            Err(e) => errors.push(format!("{row_label}: {e}")),
+        }
+        if new_authority == ours {
+            errors.push(format!("{row_label}: new authority is the current one"));
+        }
+
+        transfers.push((program_id, new_authority));
+    }
+
+    if !errors.is_empty() {
+        for error in &errors {
+            println!("  ✗ {error}");
+        }
+        anyhow::bail!("{} of {} row(s) failed validation. Nothing was sent.", errors.len(), rows.len());
+    }
+
+    Ok(transfers)
+}
+
+async fn current_upgrade_authority(rpc_client: &RpcClient, program_id: &Pubkey) -> Result<Option<Pubkey>> {
+    let loader_id = Pubkey::new_from_array(LOADER_ID.to_bytes());
+    let (programdata_address, _) = Pubkey::find_program_address(&[program_id.as_ref()], &loader_id);
+    let account = with_retry(|| rpc_client.get_account(&programdata_address)).await
+        .with_context(|| format!("Failed to fetch ProgramData {programdata_address}"))?;
+
+    let programdata_state: UpgradeableLoaderState = bincode::deserialize(&account.data)
+        .context("invalid ProgramData account")?;
+    match programdata_state {
+        UpgradeableLoaderState::ProgramData { upgrade_authority_address, .. } => {
+            Ok(upgrade_authority_address.map(|a| Pubkey::new_from_array(a.to_bytes())))
+        }
+        _ => anyhow::bail!("invalid ProgramData account"),
+    }
+}
+
 /// Transfer upgrade authority to a new address
 /// 
 /// This uses bpf_loader_upgradeable::set_upgrade_authority to transfer control.
 /// After this, only the new authority can upgrade the program.
 async fn transfer_upgrade_authority(
     rpc_client: &RpcClient,
+    payer: &solana_sdk::signature::Keypair,
     current_authority: &solana_sdk::signature::Keypair,
     program_id: &Pubkey,
     new_authority: &Pubkey,
-) -> Result<()> {
+) -> Result<Signature> {
     // Derive ProgramData address
     let loader_id_sdk = Pubkey::new_from_array(LOADER_ID.to_bytes());
     let (programdata_address, _) = Pubkey::find_program_address(
@@ -143,18 +486,21 @@ async fn transfer_upgrade_authority(
         data: set_authority_ix.data,
     };
 
-    let recent_blockhash = rpc_client.get_latest_blockhash()?;
-    let mut transaction = Transaction::new_with_payer(
-        &[sdk_instruction],
-        Some(&current_authority.pubkey()),
-    );
-    transaction.sign(&[current_authority], recent_blockhash);
+    let mut signers = vec![payer, current_authority];
+    signers.dedup_by_key(|k| k.pubkey());
+    let (recent_blockhash, instructions) = durable_blockhash(
+        rpc_client,
+        &signers,
+        with_compute_budget(rpc_client, TxClass::SetAuthority, &payer.pubkey(), &[sdk_instruction]).await,
+    )
+    .await?;
+    let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+    transaction.sign(&signers, recent_blockhash);
     
-    let signature = rpc_client
-        .send_and_confirm_transaction(&transaction)
+    let signature = send_and_confirm(rpc_client, &transaction).await
         .context("Failed to transfer authority")?;
     
     println!("    ↳ Transaction: {signature}");
     
-    Ok(())
+    Ok(signature)
 }
\ No newline at end of file
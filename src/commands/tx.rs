@@ -0,0 +1,313 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_loader_v3_interface::instruction::UpgradeableLoaderInstruction;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::Signer,
+};
+use solana_sdk_ids::{
+    bpf_loader_upgradeable::ID as LOADER_ID,
+    compute_budget::ID as COMPUTE_BUDGET_ID,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+};
+use solana_system_interface::instruction::SystemInstruction;
+use solana_transaction_status_client_types::UiTransactionEncoding;
+use std::collections::HashMap;
+use std::str::FromStr;
+use crate::commands::history::kind_label;
+use crate::config::Config;
+use crate::utils::*;
+
+#[derive(Serialize)]
+struct TxReport {
+    signature: String,
+    slot: u64,
+    block_time: Option<i64>,
+    fee_lamports: Option<u64>,
+    success: bool,
+    error: Option<String>,
+    recorded_as: Option<String>,
+    instructions: Vec<ExplainedInstruction>,
+}
+
+#[derive(Serialize)]
+struct ExplainedInstruction {
+    program: String,
+    /// Decoded instruction name, or `raw` when the program is not understood
+    kind: String,
+    summary: String,
+    accounts: Vec<AccountRef>,
+}
+
+#[derive(Serialize)]
+struct AccountRef {
+    address: String,
+    label: Option<String>,
+}
+
+pub async fn execute(signature_str: String, json: bool) -> Result<()> {
+    let signature = Signature::from_str(&signature_str)
+        .context("Invalid transaction signature")?;
+
+    let config = Config::new()?;
+    let state = config.load_state()?;
+    let labels = known_accounts(&config)?;
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    let tx = rpc_client
+        .get_transaction_with_config(
+            &signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await
+        .with_context(|| format!("Transaction {signature} not found on this network"))?;
+
+    let decoded = tx.transaction.transaction
+        .decode()
+        .context("Could not decode transaction")?;
+    let meta = tx.transaction.meta;
+    let keys = decoded.message.static_account_keys();
+
+    let account = |index: u8| -> AccountRef {
+        match keys.get(index as usize) {
+            Some(key) => AccountRef {
+                address: key.to_string(),
+                label: labels.get(key).cloned(),
+            },
+            None => AccountRef {
+                address: format!("lookup-table account #{index}"),
+                label: None,
+            },
+        }
+    };
+
+    let instructions = decoded.message
+        .instructions()
+        .iter()
+        .map(|ix| {
+            let program_id = keys.get(ix.program_id_index as usize).copied().unwrap_or_default();
+            let accounts: Vec<AccountRef> = ix.accounts.iter().map(|&i| account(i)).collect();
+            let (program, kind, summary) = explain(&program_id, &ix.data, &accounts);
+            ExplainedInstruction { program, kind, summary, accounts }
+        })
+        .collect();
+
+    let recorded_as = state.operations
+        .iter()
+        .find(|op| op.signatures.iter().any(|s| *s == signature_str))
+        .map(|op| kind_label(op.kind).to_string());
+
+    let report = TxReport {
+        signature: signature_str,
+        slot: tx.slot,
+        block_time: tx.block_time,
+        fee_lamports: meta.as_ref().map(|m| m.fee),
+        success: meta.as_ref().is_none_or(|m| m.err.is_none()),
+        error: meta.as_ref().and_then(|m| m.err.as_ref()).map(|e| format!("{e:?}")),
+        recorded_as,
+        instructions,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    print_header("Transaction");
+
+    println!();
+    println!("Signature:   {}", report.signature);
+    println!("Slot:        {}", report.slot);
+    if let Some(time) = report.block_time {
+        let datetime = chrono::DateTime::from_timestamp(time, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        println!("Time:        {datetime}");
+    }
+    if let Some(fee) = report.fee_lamports {
+        println!("Fee:         {}", format_sol(fee));
+    }
+    match &report.error {
+        None => println!("Result:      success"),
+        Some(error) => println!("Result:      FAILED ({error})"),
+    }
+    match &report.recorded_as {
+        Some(kind) => println!("Recorded as: {kind} in this project's history"),
+        None => println!("Recorded as: not in this project's history"),
+    }
+    println!("Explorer:    {}", explorer_tx_url(&report.signature, &state.network));
+
+    println!("\nInstructions:");
+    for (i, ix) in report.instructions.iter().enumerate() {
+        println!("  {}. {}", i + 1, ix.summary);
+        if ix.kind == "raw" {
+            for account in &ix.accounts {
+                println!("       {}", describe(account));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Project keys worth naming when they show up in a transaction
+fn known_accounts(config: &Config) -> Result<HashMap<Pubkey, String>> {
+    let state = config.load_state()?;
+    let mut labels = HashMap::new();
+
+    if let Ok(deployer) = config.load_deployer() {
+        labels.insert(deployer.pubkey(), "deployer".to_string());
+    }
+    for vault in config.load_vault_keys()? {
+        labels.insert(vault.pubkey(), "vault".to_string());
+    }
+    for spill in config.load_spill_keys()? {
+        labels.insert(spill.pubkey(), "spill account".to_string());
+    }
+    for buffer in &state.buffers {
+        if let Ok(address) = Pubkey::from_str(&buffer.address) {
+            let name = buffer.name.as_deref().map(|n| format!(" for {n}")).unwrap_or_default();
+            labels.insert(address, format!("buffer{name}"));
+        }
+    }
+    for program in &state.deployed_programs {
+        let Ok(program_id) = Pubkey::from_str(&program.program_id) else { continue };
+        let name = program.name.clone().unwrap_or_else(|| "program".to_string());
+        let loader = Pubkey::new_from_array(LOADER_ID.to_bytes());
+        let (programdata, _) = Pubkey::find_program_address(&[program_id.as_ref()], &loader);
+
+        labels.insert(program_id, name.clone());
+        labels.insert(programdata, format!("{name} ProgramData"));
+        if let Ok(Some(authority)) = config.load_program_authority(&program_id) {
+            labels.insert(authority.pubkey(), format!("{name} authority"));
+        }
+    }
+
+    Ok(labels)
+}
+
+fn describe(account: &AccountRef) -> String {
+    match &account.label {
+        Some(label) => format!("{} ({label})", short(&account.address)),
+        None => short(&account.address),
+    }
+}
+
+fn short(address: &str) -> String {
+    if address.len() > 12 && !address.contains(' ') {
+        format!("{}...{}", &address[..4], &address[address.len() - 4..])
+    } else {
+        address.to_string()
+    }
+}
+
+/// Program name, instruction kind and a one-line narrative
+fn explain(program_id: &Pubkey, data: &[u8], accounts: &[AccountRef]) -> (String, String, String) {
+    let at = |i: usize| accounts.get(i).map(describe).unwrap_or_else(|| "?".to_string());
+
+    if program_id.to_bytes() == LOADER_ID.to_bytes() {
+        let program = "bpf-loader-upgradeable".to_string();
+        let Ok(ix) = bincode::deserialize::<UpgradeableLoaderInstruction>(data) else {
+            return (program, "raw".to_string(), format!("Unrecognized loader instruction ({} bytes)", data.len()));
+        };
+        let summary = match &ix {
+            UpgradeableLoaderInstruction::InitializeBuffer => {
+                format!("Initialize buffer {} with authority {}", at(0), at(1))
+            }
+            UpgradeableLoaderInstruction::Write { offset, bytes } => format!(
+                "Buffer write, chunk {}, {} bytes at offset {offset}, buffer {}",
+                *offset as usize / calculate_max_write_chunk_size(),
+                bytes.len(),
+                at(0)
+            ),
+            UpgradeableLoaderInstruction::DeployWithMaxDataLen { max_data_len } => format!(
+                "Deploy program {} from buffer {} (max {max_data_len} bytes), authority {}",
+                at(2), at(3), at(7)
+            ),
+            UpgradeableLoaderInstruction::Upgrade => format!(
+                "Upgrade program {} from buffer {}, refund to {}",
+                at(1), at(2), at(3)
+            ),
+            UpgradeableLoaderInstruction::SetAuthority if accounts.len() < 3 => format!(
+                "set_upgrade_authority to None on {} (finalize)",
+                at(0)
+            ),
+            UpgradeableLoaderInstruction::SetAuthority | UpgradeableLoaderInstruction::SetAuthorityChecked => format!(
+                "set_upgrade_authority on {} from {} to {}",
+                at(0), at(1), at(2)
+            ),
+            UpgradeableLoaderInstruction::Close => format!(
+                "Close {}, rent to {}",
+                at(0), at(1)
+            ),
+            UpgradeableLoaderInstruction::ExtendProgram { additional_bytes } => format!(
+                "Extend {} by {additional_bytes} bytes, paid by {}",
+                at(0), at(3)
+            ),
+            other => format!("Loader instruction {other:?}"),
+        };
+        let kind = format!("{ix:?}").split([' ', '{']).next().unwrap_or_default().to_string();
+        return (program, kind, summary);
+    }
+
+    if program_id.to_bytes() == SYSTEM_PROGRAM_ID.to_bytes() {
+        let program = "system".to_string();
+        let Ok(ix) = bincode::deserialize::<SystemInstruction>(data) else {
+            return (program, "raw".to_string(), format!("Unrecognized system instruction ({} bytes)", data.len()));
+        };
+        let summary = match &ix {
+            SystemInstruction::Transfer { lamports } => {
+                format!("Transfer {} from {} to {}", format_sol(*lamports), at(0), at(1))
+            }
+            SystemInstruction::CreateAccount { lamports, space, .. } => format!(
+                "Create account {} ({space} bytes, {}) funded by {}",
+                at(1), format_sol(*lamports), at(0)
+            ),
+            other => format!("System instruction {other:?}"),
+        };
+        let kind = format!("{ix:?}").split([' ', '{']).next().unwrap_or_default().to_string();
+        return (program, kind, summary);
+    }
+
+    if program_id.to_bytes() == COMPUTE_BUDGET_ID.to_bytes() {
+        let program = "compute-budget".to_string();
+        // Borsh: one tag byte, then a little-endian integer
+        let value = |len: usize| {
+            data.get(1..1 + len).map(|bytes| {
+                let mut buf = [0u8; 8];
+                buf[..len].copy_from_slice(bytes);
+                u64::from_le_bytes(buf)
+            })
+        };
+        return match (data.first(), value(4), value(8)) {
+            (Some(2), Some(units), _) => {
+                (program, "SetComputeUnitLimit".to_string(), format!("Set compute unit limit to {units}"))
+            }
+            (Some(3), _, Some(price)) => (
+                program,
+                "SetComputeUnitPrice".to_string(),
+                format!("Set priority fee to {price} micro-lamports per compute unit"),
+            ),
+            _ => (program, "raw".to_string(), "Compute budget instruction".to_string()),
+        };
+    }
+
+    (
+        program_id.to_string(),
+        "raw".to_string(),
+        format!("Unknown program {}: {} bytes of data", program_id, data.len()),
+    )
+}
@@ -0,0 +1,29 @@
+use anyhow::Result;
+use crate::update::{fetch_latest_release, is_newer, CURRENT_VERSION, REPOSITORY_URL};
+use crate::utils::*;
+
+pub async fn execute(check: bool) -> Result<()> {
+    let latest = fetch_latest_release().await.map_err(|e| anyhow::anyhow!(
+        "Could not check for updates: {e}\n\
+        See {REPOSITORY_URL}/releases"
+    ))?;
+
+    println!("Installed: {CURRENT_VERSION}");
+    println!("Latest:    {latest}");
+
+    if !is_newer(&latest, CURRENT_VERSION) {
+        print_success("shield-deploy is up to date");
+        return Ok(());
+    }
+
+    println!();
+    print_warning("A newer release is available");
+    println!("→ cargo install --git {REPOSITORY_URL} --tag {latest}");
+    println!("→ Release notes: {REPOSITORY_URL}/releases/tag/{latest}");
+
+    if check {
+        anyhow::bail!("shield-deploy {CURRENT_VERSION} is out of date");
+    }
+
+    Ok(())
+}
@@ -1,29 +1,65 @@
 use anyhow::{Context, Result};
-use solana_client::rpc_client::RpcClient;
+use dialoguer::{theme::ColorfulTheme, Select};
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_loader_v3_interface::{
     state::UpgradeableLoaderState,
     instruction as bpf_loader_upgradeable,
 };
 use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
-use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
 use solana_sdk::{
     pubkey::Pubkey, 
-    signature::{Keypair, Signer}, 
-    transaction::Transaction,
+    signature::{Keypair, Signature, Signer}, 
+    system_program,
+    transaction::{Transaction, TransactionError},
     instruction::Instruction as SdkInstruction,
-    instruction::AccountMeta,
+    instruction::{AccountMeta, InstructionError},
 };
-use solana_address::Address;
 use solana_pubkey::Pubkey as SolanaPubkeyV2;
-use solana_system_interface::instruction as system_instruction;
-use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
 use std::str::FromStr;
-use crate::config::Config;
+use std::time::Duration;
+use crate::artifact::load_program;
+use crate::commands::estimate::print_breakdown;
+use crate::commands::verify::{record_verified_hash, verify_deployed_bytecode, verify_deployed_hash};
+use crate::commands::CliProgress;
+use crate::compute::{estimate_priority_fee, with_compute_budget, FeeSettings, TxClass};
+use crate::config::{
+    BufferStatus, Config, DeployedProgram, OperationKind, OperationRecord, ProjectState,
+    ScheduledUpgrade, UpgradeTarget,
+};
+use crate::cost::{enforce_spend_limit, estimate_upgrade, measure_spend, print_spend};
+use crate::hygiene::auto_close_buffers;
+use crate::loader::{self, upgrade_program, upgrade_transaction, write_upgrade_buffer};
+use crate::network::confirm_mainnet;
+use crate::privacy::PrivacyLayer;
 use crate::utils::*;
 
-const MIN_UPGRADE_BALANCE: u64 = 1_000_000_000; // 1 SOL minimum
+/// Left on a spill key to pay the Privacy Cash fee (~0.006 SOL) and transaction fees
+const SPILL_FEE_RESERVE: u64 = 10_000_000;
+
+/// Smallest amount Privacy Cash accepts
+const PRIVACY_CASH_MINIMUM: u64 = 20_000_000;
 
-pub async fn execute(program_id_str: String) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub async fn execute(
+    program_id_str: Option<String>,
+    name: Option<String>,
+    program_path: Option<String>,
+    program_sha256: Option<String>,
+    spill_compressed: bool,
+    spill_to: Option<String>,
+    override_spend_limit: bool,
+    fee_payer_path: Option<PathBuf>,
+    no_auto_clean: bool,
+    at: Option<String>,
+    at_slot: Option<u64>,
+    resume: bool,
+    no_extend: bool,
+    with_idl: bool,
+    idl_path: Option<PathBuf>,
+) -> Result<()> {
     print_header("Upgrade Program");
     
     let config = Config::new()?;
@@ -36,98 +72,638 @@ pub async fn execute(program_id_str: String) -> Result<()> {
     }
     
     let deployer = config.load_deployer()?;
+    config.check_rotation_policy()?;
     let mut state = config.load_state()?;
 
+    let program_id_str = select_program(&state, program_id_str, name.as_deref())?;
     let program_id = Pubkey::from_str(&program_id_str)
         .context("Invalid program ID")?;
 
+    // Isolated programs sign with their own key; the deployer still pays
+    let program_authority = config.load_program_authority(&program_id)?;
+    
     // Verify program ownership BEFORE expensive operations
     println!("\n Verifying upgrade authority...");
     verify_upgrade_authority_early(
         &get_rpc_url()?,
         &program_id,
-        &deployer,
-    ).await?;
-    
-    if state.deployed_programs.is_empty() {
+        program_authority.as_ref().unwrap_or(&deployer),
+    )
+    .await?;
+    
+    if resume {
+        let plan = config.load_scheduled_upgrade(&program_id.to_string())?
+            .ok_or_else(|| anyhow::anyhow!("No scheduled upgrade of {program_id} to resume"))?;
+        let rpc_client = RpcClient::new_with_commitment(get_rpc_url()?, commitment());
+        // The buffer is already written; only the upgrade transaction is left to pay for
+        let priority_fee = estimate_priority_fee(&rpc_client, &[deployer.pubkey()]).await;
+        let estimate = estimate_upgrade(&rpc_client, &program_id, plan.size, priority_fee).await?;
+        let remaining = estimate.transaction_fees + estimate.priority_fees + estimate.extend_rent;
+        let network = confirm_mainnet("Resuming the scheduled upgrade", Some(remaining)).await?;
+        enforce_spend_limit(&network, remaining + estimate.margin, override_spend_limit)?;
+        if let Some(path) = &fee_payer_path {
+            use_fee_payer(&rpc_client, path, 0).await?;
+        }
+        println!("\nResuming the scheduled upgrade with buffer {}", plan.buffer);
+        return run_scheduled_upgrade(
+            &config,
+            &deployer,
+            program_authority.as_ref().unwrap_or(&deployer),
+            plan,
+            no_auto_clean,
+        )
+        .await;
+    }
+    let target = parse_target(at.as_deref(), at_slot)?;
+    if let Some(buffer) = config.load_scheduled_upgrade(&program_id.to_string())?.map(|plan| plan.buffer) {
         anyhow::bail!(
-            "No programs deployed yet.\n\
-            Run `shield-deploy deploy` first."
+            "An upgrade of {program_id} is already scheduled (buffer {buffer}).\n\
+            Finish it with `shield-deploy upgrade {program_id} --resume`."
         );
     }
     
-    let program_file = detect_program_file()
-        .ok_or_else(|| anyhow::anyhow!(
-            "No program file found.\n\
-            Build your program first or specify with --program"
-        ))?;
+    let artifact = load_program(program_path.as_deref(), program_sha256.as_deref()).await?;
+    artifact.print_summary();
+    
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url.clone(),
+        commitment(),
+    );
+    
+    let program_data = artifact.data;
+    
+    let last_program = state
+        .program_mut(&program_id_str)
+        .ok_or_else(|| anyhow::anyhow!("{program_id} is not a managed program"))?;
+    
+    let loader = Pubkey::new_from_array(LOADER_ID.to_bytes());
+    let (programdata, _) = Pubkey::find_program_address(&[program_id.as_ref()], &loader);
+    let priority_fee = estimate_priority_fee(&rpc_client, &[deployer.pubkey(), programdata]).await;
+    let estimate = estimate_upgrade(&rpc_client, &program_id, program_data.len(), priority_fee).await?;
     
-    if !program_file.exists() {
-        anyhow::bail!("Program file not found: {}", program_file.display());
+    // The loader rejects a binary larger than ProgramData; grow it before paying for the buffer
+    let extend_by = program_data.len().saturating_sub(estimate.max_data_len);
+    if extend_by > 0 && no_extend {
+        anyhow::bail!(
+            "The new build is {} bytes, but ProgramData holds {} bytes.\n\
+            Run without --no-extend to extend it by {extend_by} bytes ({} rent), \
+            or extend it yourself with `solana program extend {program_id} {extend_by}`.",
+            program_data.len(),
+            estimate.max_data_len,
+            format_sol(estimate.extend_rent)
+        );
     }
     
+    let spill_to = match spill_to {
+        Some(address) => Some(check_spill_account(&rpc_client, &address).await?),
+        None => None,
+    };
+    
     println!("\nThis will:");
     println!("• Rebuild your program");
     println!("• Use the same private deployer");
-    println!("• Preserve on-chain privacy\n");
+    println!("• Preserve on-chain privacy");
+    if spill_compressed {
+        println!("• Send the buffer refund to a fresh key and compress it into the privacy pool");
+    }
+    if let Some(spill) = &spill_to {
+        println!("• Send the buffer refund to {spill}");
+    }
+    if extend_by > 0 {
+        println!(
+            "• Extend ProgramData by {extend_by} bytes first ({} more rent, paid by the deployer)",
+            format_sol(estimate.extend_rent)
+        );
+    }
+    if let Some(target) = target {
+        println!("• Write the buffer now and send the upgrade at {}", describe_target(target));
+    }
+    println!();
+    
+    println!("Cost:");
+    print_breakdown(&estimate);
+    println!();
     
     if !prompt_confirmation("Proceed?")? {
         println!("Cancelled.");
         return Ok(());
     }
     
-    let rpc_url = get_rpc_url()?;
-    let rpc_client = RpcClient::new_with_commitment(
-        rpc_url.clone(),
-        CommitmentConfig::confirmed(),
-    );
-    
-    let balance = rpc_client.get_balance(&deployer.pubkey())
+    let balance = balance_of(&rpc_client, &deployer.pubkey()).await
         .context("Failed to get deployer balance")?;
     
-    if balance < MIN_UPGRADE_BALANCE {
+    let network = confirm_mainnet("Upgrading", Some(estimate.net_cost())).await?;
+    enforce_spend_limit(&network, estimate.required_balance(), override_spend_limit)?;
+    
+    // With a separate fee payer the deployer only needs the rent
+    let required = match &fee_payer_path {
+        Some(path) => {
+            let payer = use_fee_payer(&rpc_client, path, estimate.fee_budget()).await?;
+            println!("  ↳ Fee payer: {payer}");
+            estimate.required_balance() - estimate.fee_budget()
+        }
+        None => estimate.required_balance(),
+    };
+    
+    if balance < required {
         anyhow::bail!(
             "Insufficient deployer balance.\n\
             Current: {}\n\
-            Needed: ~1 SOL\n\
+            Needed: {} (short by {})\n\
             Run `shield-deploy fund` to add more SOL.",
-            format_sol(balance)
+            format_sol(balance),
+            format_sol(required),
+            format_sol(required - balance)
         );
     }
     
     println!("\n⬆ Upgrading program...");
     
-    let program_data = fs::read(&program_file)
-        .context("Failed to read program file")?;
-    
     println!("  ↳ New program size: {} bytes", program_data.len());
     
-    // Get the last deployed program
-    let last_program = state.deployed_programs.last_mut()
-        .ok_or_else(|| anyhow::anyhow!("No program found"))?;
+    println!("  ↳ Program ID: {program_id}");
     
-    let program_id = Pubkey::from_str(&last_program.program_id)
-        .context("Invalid program ID in state")?;
+    // The refund lands on a key that exists only to move it into the pool
+    let spill = if spill_compressed {
+        let spill = Keypair::new();
+        config.save_spill_key(&spill)?;
+        println!("  ↳ Spill: {} (ephemeral)", spill.pubkey());
+        spill.pubkey()
+    } else if let Some(spill) = spill_to {
+        println!("  ↳ Spill: {spill}");
+        spill
+    } else {
+        deployer.pubkey()
+    };
     
-    println!("  ↳ Program ID: {program_id}");
+    if extend_by > 0 {
+        println!("\n Extending ProgramData by {extend_by} bytes...");
+        let signature = extend_program_data(
+            &rpc_client,
+            &deployer,
+            program_authority.as_ref().unwrap_or(&deployer),
+            &program_id,
+            extend_by,
+        )
+        .await
+        .context("Failed to extend ProgramData; nothing was written")?;
+        println!("  ✓ ProgramData extended: {signature}");
+    
+        // Saved now as well: a scheduled upgrade returns before `state` is written
+        let capacity = estimate.max_data_len + extend_by;
+        last_program.max_data_len = Some(capacity);
+        let mut saved = config.load_state()?;
+        if let Some(program) = saved.program_mut(&program_id_str) {
+            program.max_data_len = Some(capacity);
+            config.save_state(&saved)?;
+        }
+    }
+    
+    if let Some(target) = target {
+        let authority = program_authority.as_ref().unwrap_or(&deployer);
+        let plan = prepare_scheduled_upgrade(
+            &config,
+            &rpc_client,
+            &deployer,
+            authority,
+            &program_id,
+            &program_data,
+            &spill,
+            spill_compressed,
+            target,
+        )
+        .await?;
+        return run_scheduled_upgrade(&config, &deployer, authority, plan, no_auto_clean).await;
+    }
+    
+    let spill_before = match spill_to {
+        Some(spill) => with_retry(|| rpc_client.get_balance(&spill)).await.ok(),
+        None => None,
+    };
     
-    upgrade_program_bpf_upgradeable(
+    let signature = upgrade_program_with_spill(
+        &config,
         &rpc_client,
         &deployer,
+        program_authority.as_ref().unwrap_or(&deployer),
         &program_id,
         &program_data,
+        &spill,
     )
     .await
     .context("Failed to upgrade program")?;
     
     print_success("Program upgraded successfully");
     
+    if let Some(before) = spill_before {
+        if let Ok(after) = with_retry(|| rpc_client.get_balance(&spill)).await {
+            println!("  ↳ Refunded {} to {spill}", format_sol(after.saturating_sub(before)));
+        }
+    }
+    
     println!("\nUpgrade authority unchanged.");
     
+    let balance_after = balance_of(&rpc_client, &deployer.pubkey()).await.ok();
+    
     last_program.last_upgraded = Some(chrono::Utc::now().timestamp());
-    state.last_balance = balance;
+    last_program.size = Some(program_data.len());
+    last_program.bytecode_hash = Some(sha256_hex(strip_trailing_zeros(&program_data)));
+    let mut record = OperationRecord::new(OperationKind::Upgrade, &get_network_name());
+    record.program_id = Some(program_id.to_string());
+    record.buffer = state.latest_buffer(&program_id.to_string());
+    record.buffer_signatures = take_buffer_signatures();
+    record.signatures.push(signature.to_string());
+    record.cost_lamports = balance_after.map(|after| balance.saturating_sub(after));
+    if let Some(after) = balance_after {
+        let spend = measure_spend(&rpc_client, &program_id, &record, balance, after).await;
+        print_spend(&spend);
+        record.spend = Some(spend);
+    }
+    state.operations.push(record);
+    state.last_balance = balance_after.unwrap_or(balance);
     config.save_state(&state)?;
     
+    let verified_hash = verify_deployed_bytecode(&rpc_client, &program_id, &program_data).await?;
+    record_verified_hash(&config, &program_id, verified_hash)?;
+    
+    if spill_compressed {
+        compress_spill_keys(&config, &rpc_url).await?;
+    }
+    
+    if with_idl {
+        crate::commands::idl::publish_with_deploy(&config, &rpc_client, &deployer, &program_id, idl_path)
+            .await
+            .with_context(|| format!(
+                "{program_id} is upgraded, but publishing its IDL failed.\n\
+                Retry with `shield-deploy idl upgrade {program_id}`"
+            ))?;
+    }
+    
+    if !no_auto_clean {
+        auto_close_buffers(&config, &rpc_client, &deployer, &CliProgress).await;
+    }
+    
+    Ok(())
+}
+
+/// The program to upgrade: the one named on the command line, else the only
+/// one deployed on this network, else a choice from the list
+fn select_program(state: &ProjectState, program_id: Option<String>, name: Option<&str>) -> Result<String> {
+    let network = get_network_name();
+    let managed = |id: &str| state.deployed_programs.iter().any(|p| p.program_id == id);
+
+    if let Some(program_id) = program_id {
+        if !managed(&program_id) {
+            anyhow::bail!(
+                "{program_id} is not a managed program.\n\
+                Adopt it with `shield-deploy programs import {program_id}` first."
+            );
+        }
+        return Ok(program_id);
+    }
+
+    // Newest entry per program on this network
+    let mut candidates: Vec<&DeployedProgram> = Vec::new();
+    for program in state
+        .deployed_programs
+        .iter()
+        .filter(|p| !p.immutable && p.cluster.as_deref().is_none_or(|c| c == network))
+    {
+        match candidates.iter_mut().find(|c| c.program_id == program.program_id) {
+            Some(existing) => *existing = program,
+            None => candidates.push(program),
+        }
+    }
+
+    if let Some(name) = name {
+        candidates.retain(|p| p.name.as_deref() == Some(name));
+        if candidates.is_empty() {
+            anyhow::bail!("No upgradeable program named {name} on {network}. See `shield-deploy list`.");
+        }
+    }
+
+    let labels: Vec<String> = candidates
+        .iter()
+        .map(|p| {
+            let deployed = chrono::DateTime::from_timestamp(p.deployed_at, 0)
+                .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            format!("{}  {}  (deployed {deployed})", p.program_id, p.name.as_deref().unwrap_or("-"))
+        })
+        .collect();
+
+    match candidates.as_slice() {
+        [] => anyhow::bail!(
+            "No upgradeable programs deployed on {network}.\n\
+            Run `shield-deploy deploy` first."
+        ),
+        [only] => return Ok(only.program_id.clone()),
+        _ => {}
+    }
+
+    if assume_yes() || !std::io::stdin().is_terminal() {
+        anyhow::bail!(
+            "{} programs could be upgraded and none was chosen:\n  {}\n\
+            Pass the program ID, --program-id <PUBKEY> or --name <NAME>",
+            candidates.len(),
+            labels.join("\n  ")
+        );
+    }
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Which program should be upgraded?")
+        .items(&labels)
+        .default(labels.len() - 1)
+        .interact()
+        .context("Failed to select a program")?;
+    Ok(candidates[selection].program_id.clone())
+}
+
+/// `--at` (RFC 3339 time) or `--at-slot`, whichever was given
+fn parse_target(at: Option<&str>, at_slot: Option<u64>) -> Result<Option<UpgradeTarget>> {
+    let target = match (at, at_slot) {
+        (Some(at), _) => {
+            let time = chrono::DateTime::parse_from_rfc3339(at)
+                .with_context(|| format!("Invalid --at time {at:?}; use RFC 3339, e.g. 2024-07-01T14:00:00Z"))?;
+            if time.timestamp() <= chrono::Utc::now().timestamp() {
+                anyhow::bail!("--at {at} is in the past");
+            }
+            UpgradeTarget::Time(time.timestamp())
+        }
+        (None, Some(slot)) => UpgradeTarget::Slot(slot),
+        (None, None) => return Ok(None),
+    };
+    Ok(Some(target))
+}
+
+fn describe_target(target: UpgradeTarget) -> String {
+    match target {
+        UpgradeTarget::Time(at) => chrono::DateTime::from_timestamp(at, 0)
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| at.to_string()),
+        UpgradeTarget::Slot(slot) => format!("slot {slot}"),
+    }
+}
+
+/// Write and hand off the buffer, simulate the upgrade, and persist the plan
+#[allow(clippy::too_many_arguments)]
+async fn prepare_scheduled_upgrade(
+    config: &Config,
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    upgrade_authority: &Keypair,
+    program_id: &Pubkey,
+    program_data: &[u8],
+    spill: &Pubkey,
+    spill_compressed: bool,
+    target: UpgradeTarget,
+) -> Result<ScheduledUpgrade> {
+    if let UpgradeTarget::Slot(slot) = target {
+        let current = rpc_client.get_slot().await.context("Failed to get the current slot")?;
+        if slot <= current {
+            anyhow::bail!("--at-slot {slot} has already passed (current slot {current})");
+        }
+    }
+    
+    let buffer = write_upgrade_buffer(
+        config,
+        rpc_client,
+        &FeeSettings::current(),
+        deployer,
+        upgrade_authority,
+        program_id,
+        program_data,
+        &CliProgress,
+    )
+    .await?;
+    
+    let plan = ScheduledUpgrade {
+        program_id: program_id.to_string(),
+        buffer: buffer.to_string(),
+        spill: spill.to_string(),
+        spill_compressed,
+        target,
+        bytecode_hash: sha256_hex(strip_trailing_zeros(program_data)),
+        size: program_data.len(),
+        created_at: chrono::Utc::now().timestamp(),
+    };
+    config.save_scheduled_upgrade(&plan)?;
+    
+    println!("\n Simulating the upgrade...");
+    let transaction = upgrade_transaction(rpc_client, &FeeSettings::current(), deployer, upgrade_authority, program_id, &buffer, spill).await?;
+    let simulation = rpc_client
+        .simulate_transaction(&transaction).await
+        .context("Failed to simulate the upgrade")?
+        .value;
+    if let Some(err) = simulation.err {
+        for line in simulation.logs.unwrap_or_default() {
+            println!("    {line}");
+        }
+        anyhow::bail!(
+            "The upgrade would fail: {err}\n\
+            Buffer {buffer} is kept; fix the cause and run `shield-deploy upgrade {program_id} --resume`."
+        );
+    }
+    println!("  ✓ Upgrade simulates cleanly");
+    
+    Ok(plan)
+}
+
+/// Wait for the plan's target, then send the upgrade and record it
+async fn run_scheduled_upgrade(
+    config: &Config,
+    deployer: &Keypair,
+    upgrade_authority: &Keypair,
+    plan: ScheduledUpgrade,
+    no_auto_clean: bool,
+) -> Result<()> {
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(rpc_url.clone(), commitment());
+    let program_id = Pubkey::from_str(&plan.program_id).context("Invalid program ID in plan")?;
+    let buffer = Pubkey::from_str(&plan.buffer).context("Invalid buffer in plan")?;
+    let spill = Pubkey::from_str(&plan.spill).context("Invalid spill in plan")?;
+    
+    println!("\n Waiting until {} (Ctrl-C keeps the buffer)...", describe_target(plan.target));
+    if !wait_for_target(&rpc_client, plan.target).await? {
+        print_warning("Interrupted before the upgrade was sent");
+        println!("Buffer {buffer} is kept and the plan saved.");
+        println!("→ Resume with `shield-deploy upgrade {program_id} --resume`");
+        return Ok(());
+    }
+    
+    let balance = balance_of(&rpc_client, &deployer.pubkey()).await
+        .context("Failed to get deployer balance")?;
+    let signature = send_upgrade(&rpc_client, deployer, upgrade_authority, &program_id, &buffer, &spill)
+        .await
+        .context(format!(
+            "Failed to upgrade program. Buffer {buffer} is kept; retry with `shield-deploy upgrade {program_id} --resume`"
+        ))?;
+    config.remove_scheduled_upgrade(&plan.program_id)?;
+    
+    print_success("Program upgraded successfully");
+    
+    let balance_after = balance_of(&rpc_client, &deployer.pubkey()).await.ok();
+    
+    let mut state = config.load_state()?;
+    if let Some(program) = state.program_mut(&plan.program_id) {
+        program.last_upgraded = Some(chrono::Utc::now().timestamp());
+        program.size = Some(plan.size);
+        program.bytecode_hash = Some(plan.bytecode_hash.clone());
+    }
+    if let Some(record) = state.buffers.iter_mut().find(|b| b.address == plan.buffer) {
+        record.status = BufferStatus::Consumed;
+    }
+    let mut record = OperationRecord::new(OperationKind::Upgrade, &get_network_name());
+    record.program_id = Some(plan.program_id.clone());
+    record.buffer = Some(plan.buffer.clone());
+    record.signatures.push(signature.to_string());
+    record.cost_lamports = balance_after.map(|after| balance.saturating_sub(after));
+    record.note = Some(format!("scheduled for {}", describe_target(plan.target)));
+    if let Some(after) = balance_after {
+        let spend = measure_spend(&rpc_client, &program_id, &record, balance, after).await;
+        print_spend(&spend);
+        record.spend = Some(spend);
+    }
+    state.operations.push(record);
+    state.last_balance = balance_after.unwrap_or(balance);
+    config.save_state(&state)?;
+    
+    let verified_hash = verify_deployed_hash(&rpc_client, &program_id, &plan.bytecode_hash).await?;
+    record_verified_hash(config, &program_id, verified_hash)?;
+    
+    if plan.spill_compressed {
+        compress_spill_keys(config, &rpc_url).await?;
+    }
+    
+    if !no_auto_clean {
+        auto_close_buffers(config, &rpc_client, deployer, &CliProgress).await;
+    }
+    
+    Ok(())
+}
+
+/// Count down to `target`; `false` if interrupted with Ctrl-C
+async fn wait_for_target(rpc_client: &RpcClient, target: UpgradeTarget) -> Result<bool> {
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    
+    loop {
+        tokio::select! {
+            _ = &mut ctrl_c => {
+                println!();
+                return Ok(false);
+            }
+            _ = ticker.tick() => {}
+        }
+        
+        let status = match target {
+            UpgradeTarget::Time(at) => {
+                let left = at - chrono::Utc::now().timestamp();
+                if left <= 0 {
+                    break;
+                }
+                format!("{} left", countdown(left as u64))
+            }
+            UpgradeTarget::Slot(slot) => {
+                // A failed poll just shows the last status for another tick
+                let Ok(current) = rpc_client.get_slot().await else { continue };
+                if current >= slot {
+                    break;
+                }
+                format!("slot {current}, {} to go", slot - current)
+            }
+        };
+        print!("\r  ↳ {status}      ");
+        std::io::stdout().flush()?;
+    }
+    
+    println!("\r  ✓ Target reached                    ");
+    Ok(true)
+}
+
+fn countdown(secs: u64) -> String {
+    if secs >= 86_400 {
+        return format_duration(Duration::from_secs(secs));
+    }
+    format!("{:02}:{:02}:{:02}", secs / 3_600, secs % 3_600 / 60, secs % 60)
+}
+
+/// Compress the balance of every persisted spill key into a fresh vault
+///
+/// Keys left over from earlier failures are retried too. A key is only
+/// deleted once its refund is shielded; otherwise it stays in
+/// `.shield/spill/` so the lamports can be swept later.
+async fn compress_spill_keys(config: &Config, rpc_url: &str) -> Result<()> {
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url.to_string(),
+        commitment(),
+    );
+    let privacy = PrivacyLayer::new(rpc_url);
+    
+    println!("\n Compressing buffer refunds...");
+    
+    for spill in config.load_spill_keys()? {
+        let spill_path = config.spill_dir().join(format!("{}.json", spill.pubkey()));
+        let refund = balance_of(&rpc_client, &spill.pubkey()).await
+            .context("Failed to get spill key balance")?;
+        
+        if refund == 0 {
+            config.remove_spill_key(&spill.pubkey())?;
+            continue;
+        }
+        
+        let amount = refund.saturating_sub(SPILL_FEE_RESERVE);
+        if amount < PRIVACY_CASH_MINIMUM {
+            print_warning(&format!(
+                "Refund of {} is below the Privacy Cash minimum once fees are reserved",
+                format_sol(refund)
+            ));
+            println!("  ↳ Left on {} ({})", spill.pubkey(), spill_path.display());
+            continue;
+        }
+        
+        // Persist the vault key first so a crash can never strand funds
+        let vault = Keypair::new();
+        let vault_path = config.save_vault_key(&vault)?;
+        
+        let result = match privacy
+            .compress_sol(&spill, &vault.pubkey(), amount as f64 / LAMPORTS_PER_SOL as f64)
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                print_warning(&format!("Failed to compress the refund: {e:#}"));
+                println!("  ↳ {} stays on {}", format_sol(refund), spill.pubkey());
+                println!("  ↳ Key kept at {}", spill_path.display());
+                println!("  ↳ Rerun `shield-deploy upgrade --spill-compressed` to retry");
+                continue;
+            }
+        };
+        
+        // Whatever remains is below the cost of moving it
+        let remaining = balance_of(&rpc_client, &spill.pubkey()).await.unwrap_or(0);
+        config.remove_spill_key(&spill.pubkey())?;
+        
+        let mut record = OperationRecord::new(OperationKind::SpillCompress, &get_network_name());
+        record.amount_lamports = Some(result.amount_received);
+        record.cost_lamports = Some(refund.saturating_sub(result.amount_received));
+        record.signatures.push(result.deposit_signature.to_string());
+        record.note = Some("upgrade buffer refund".to_string());
+        let mut state = config.load_state()?;
+        state.operations.push(record);
+        config.save_state(&state)?;
+        
+        println!("  ✓ Refund:    {}", format_sol(refund));
+        println!("  ✓ Shielded:  {}", format_sol(result.amount_received));
+        println!("  ↳ Fees:      {}", format_sol(refund.saturating_sub(result.amount_received + remaining)));
+        if remaining > 0 {
+            println!("  ↳ Abandoned: {} dust on the spill key", format_sol(remaining));
+        }
+        println!("  ↳ Vault key: {}", vault_path.display());
+    }
+    
     Ok(())
 }
 
@@ -139,11 +715,10 @@ async fn verify_upgrade_authority_early(
 ) -> Result<()> {
     let rpc_client = RpcClient::new_with_commitment(
         rpc_url.to_string(),
-        CommitmentConfig::confirmed(),
+        commitment(),
     );
     
-    let program_account = rpc_client
-        .get_account(program_id)
+    let program_account = with_retry(|| rpc_client.get_account(program_id)).await
         .context("Failed to fetch program account - it may not exist")?;
     
     let loader_id_sdk = Pubkey::new_from_array(LOADER_ID.to_bytes());
@@ -159,7 +734,7 @@ async fn verify_upgrade_authority_early(
     };
     
     let programdata_address_sdk = Pubkey::from(programdata_address.to_bytes());
-    let programdata = rpc_client.get_account(&programdata_address_sdk)
+    let programdata = with_retry(|| rpc_client.get_account(&programdata_address_sdk)).await
         .context("ProgramData account not found - program may be closed")?;
     
     match bincode::deserialize::<UpgradeableLoaderState>(&programdata.data)? {
@@ -199,135 +774,95 @@ async fn verify_upgrade_authority_early(
 /// 3. Upgrade program from buffer
 /// 4. Buffer is automatically closed
 pub async fn upgrade_program_bpf_upgradeable(
+    config: &Config,
     rpc_client: &RpcClient,
     upgrade_authority: &Keypair,
     program_id: &Pubkey,
     new_program_data: &[u8],
-) -> Result<()> {
-    let authority_pubkey = upgrade_authority.pubkey();
-    
-    // Derive ProgramData address
-    let loader_id_sdk = Pubkey::new_from_array(LOADER_ID.to_bytes());
-    let (programdata_address, _) = Pubkey::find_program_address(
-        &[program_id.as_ref()],
-        &loader_id_sdk,
-    );
-    
-    println!("  ↳ ProgramData address: {programdata_address}");
-    
-    // Verify upgrade authority
-    verify_upgrade_authority(
+) -> Result<Signature> {
+    upgrade_program_with_spill(
+        config,
         rpc_client,
-        &programdata_address,
-        &authority_pubkey,
+        upgrade_authority,
+        upgrade_authority,
+        program_id,
+        new_program_data,
+        &upgrade_authority.pubkey(),
     )
     .await
-    .context("Authority verification failed")?;
-    
-    println!("\n Creating upgrade buffer...");
-    
-    let buffer_keypair = Keypair::new();
-    let buffer_pubkey = buffer_keypair.pubkey();
-    
-    // Calculate required size for buffer
-    let buffer_size = UpgradeableLoaderState::size_of_buffer(new_program_data.len());
-    let buffer_lamports = rpc_client
-        .get_minimum_balance_for_rent_exemption(buffer_size)
-        .context("Failed to get rent exemption for buffer")?;
-
-    let authority_addr = Address::from(authority_pubkey.to_bytes());
-    let buffer_addr = Address::from(buffer_pubkey.to_bytes());
-    let loader_addr = Address::from(LOADER_ID.to_bytes());
-    
-    // Create buffer account
-    let create_buffer_ix = system_instruction::create_account(
-        &authority_addr,
-        &buffer_addr,
-        buffer_lamports,
-        buffer_size as u64,
-        &loader_addr,
-    );
+}
 
-    let sdk_instruction = SdkInstruction {
-        program_id: Pubkey::from(create_buffer_ix.program_id.to_bytes()),
-        accounts: create_buffer_ix
-            .accounts
-            .iter()
-            .map(|acc| AccountMeta {
-                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
-                is_signer: acc.is_signer,
-                is_writable: acc.is_writable,
-            })
-            .collect(),
-        data: create_buffer_ix.data,
-    };
-    
-    let recent_blockhash = rpc_client.get_latest_blockhash()?;
-    let mut transaction = Transaction::new_with_payer(
-        &[sdk_instruction],
-        Some(&authority_pubkey),
-    );
-    transaction.sign(&[upgrade_authority, &buffer_keypair], recent_blockhash);
-    
-    let signature = rpc_client
-        .send_and_confirm_transaction(&transaction)
-        .context("Failed to create buffer account")?;
-    
-    println!("  ✓ Buffer created: {signature}");
-    
-    println!("\n Writing new program data...");
-    
-    write_program_data_chunked(
+/// Same as [`upgrade_program_bpf_upgradeable`], refunding the buffer to `spill`
+pub async fn upgrade_program_with_spill(
+    config: &Config,
+    rpc_client: &RpcClient,
+    payer: &Keypair,
+    upgrade_authority: &Keypair,
+    program_id: &Pubkey,
+    new_program_data: &[u8],
+    spill: &Pubkey,
+) -> Result<Signature> {
+    let signature = upgrade_program(
+        config,
         rpc_client,
+        &FeeSettings::current(),
+        payer,
         upgrade_authority,
-        &buffer_pubkey,
+        program_id,
         new_program_data,
-        true,
+        spill,
+        &CliProgress,
     )
-    .await
-    .context("Failed to write program data")?;
+    .await?;
+
+    // Get program name
+    let lib_name = get_program_lib_name()?;
     
-    println!("\n Upgrading program...");
+    // Update IDL after successful upgrade
+    deploy_idl_if_available(program_id, &lib_name).await?;
     
-    // Convert to privacy_cash::Pubkey
-    let program_v2 = SolanaPubkeyV2::new_from_array(program_id.to_bytes());
-    let buffer_v2 = SolanaPubkeyV2::new_from_array(buffer_pubkey.to_bytes());
-    let authority_v2 = SolanaPubkeyV2::new_from_array(authority_pubkey.to_bytes());
-
+    Ok(signature)
+}
 
-    let upgrade_ix = bpf_loader_upgradeable::upgrade(
-        &program_v2,
-        &buffer_v2,
-        &authority_v2,
-        &authority_v2,
-    );
+/// Check that `address` can take a buffer refund: an existing, funded system account
+async fn check_spill_account(rpc_client: &RpcClient, address: &str) -> Result<Pubkey> {
+    let spill = Pubkey::from_str(address).context("Invalid --spill address")?;
+    let account = with_retry(|| rpc_client.get_account_with_commitment(&spill, rpc_client.commitment())).await?
+        .value
+        .ok_or_else(|| anyhow::anyhow!("Spill account {spill} does not exist. Fund it first."))?;
+    if account.owner != system_program::id() || account.executable {
+        anyhow::bail!(
+            "Spill account {spill} is owned by {}, not the system program.\n\
+            Refunds must go to a plain wallet address.",
+            account.owner
+        );
+    }
+    if account.lamports == 0 {
+        anyhow::bail!("Spill account {spill} has no balance. Fund it first.");
+    }
+    Ok(spill)
+}
 
-    let sdk_instruction = SdkInstruction {
-        program_id: Pubkey::from(upgrade_ix.program_id.to_bytes()),
-        accounts: upgrade_ix
-            .accounts
-            .iter()
-            .map(|acc| AccountMeta {
-                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
-                is_signer: acc.is_signer,
-                is_writable: acc.is_writable,
-            })
-            .collect(),
-        data: upgrade_ix.data,
-    };
-        
-    let recent_blockhash = rpc_client.get_latest_blockhash()?;
-    let mut transaction = Transaction::new_with_payer(
-        &[sdk_instruction],
-        Some(&authority_pubkey),
-    );
-    transaction.sign(&[upgrade_authority], recent_blockhash);
-    
-    let signature = rpc_client
-        .send_and_confirm_transaction_with_spinner(&transaction)
-        .context("Failed to upgrade program")?;
-    
-    println!("  ✓ Program upgraded: {signature}");
+/// Upgrade `program_id` from a ready buffer, with a fresh blockhash
+async fn send_upgrade(
+    rpc_client: &RpcClient,
+    payer: &Keypair,
+    upgrade_authority: &Keypair,
+    program_id: &Pubkey,
+    buffer_pubkey: &Pubkey,
+    spill: &Pubkey,
+) -> Result<Signature> {
+    let signature = loader::send_upgrade(
+        rpc_client,
+        &FeeSettings::current(),
+        payer,
+        upgrade_authority,
+        program_id,
+        buffer_pubkey,
+        spill,
+        &CliProgress,
+    )
+    .await?;
 
     // Get program name
     let lib_name = get_program_lib_name()?;
@@ -335,45 +870,80 @@ pub async fn upgrade_program_bpf_upgradeable(
     // Update IDL after successful upgrade
     deploy_idl_if_available(program_id, &lib_name).await?;
     
-    Ok(())
+    Ok(signature)
 }
 
-/// Verify that the current authority matches expected authority
-async fn verify_upgrade_authority(
+/// Grow ProgramData by `additional_bytes`, the deployer paying the extra rent
+///
+/// Sends ExtendProgramChecked, which the upgrade authority signs. Clusters
+/// that don't know it yet reject it as invalid instruction data; those get
+/// the older ExtendProgram instead. Fees go to the `--fee-payer` if one is set.
+async fn extend_program_data(
     rpc_client: &RpcClient,
-    programdata_address: &Pubkey,
-    expected_authority: &Pubkey,
-) -> Result<()> {
-    let account = rpc_client
-        .get_account(programdata_address)
-        .context("ProgramData account not found")?;
-    
-    // Parse ProgramData account
-    let programdata_state = bincode::deserialize::<UpgradeableLoaderState>(&account.data)
-        .context("Failed to deserialize ProgramData")?;
-    
-    match programdata_state {
-        UpgradeableLoaderState::ProgramData {
-            upgrade_authority_address,
-            slot: _,
-        } => {
-            if let Some(authority) = upgrade_authority_address {
-                if authority.to_bytes() == expected_authority.to_bytes() {
-                    println!("  ✓ Upgrade authority verified");
-                    Ok(())
-                } else {
-                    anyhow::bail!(
-                        "Upgrade authority mismatch.\n\
-                        Expected: {expected_authority}\n\
-                        Found: {authority}"
-                    )
-                }
-            } else {
-                anyhow::bail!("Program is not upgradeable (authority set to None)")
-            }
+    deployer: &Keypair,
+    upgrade_authority: &Keypair,
+    program_id: &Pubkey,
+    additional_bytes: usize,
+) -> Result<Signature> {
+    let additional_bytes = u32::try_from(additional_bytes).context("Extension too large")?;
+    let loader = Pubkey::new_from_array(LOADER_ID.to_bytes());
+    let (programdata, _) = Pubkey::find_program_address(&[program_id.as_ref()], &loader);
+
+    // Bincode: variant 9 (ExtendProgramChecked), then the u32 byte count
+    let mut data = 9u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&additional_bytes.to_le_bytes());
+    let checked_ix = SdkInstruction {
+        program_id: loader,
+        accounts: vec![
+            AccountMeta::new(programdata, false),
+            AccountMeta::new(*program_id, false),
+            AccountMeta::new_readonly(upgrade_authority.pubkey(), true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(deployer.pubkey(), true),
+        ],
+        data,
+    };
+
+    match send_extend(rpc_client, deployer, upgrade_authority, checked_ix).await {
+        Err(e) if matches!(
+            transaction_error(&e),
+            Some(TransactionError::InstructionError(_, InstructionError::InvalidInstructionData))
+        ) => {
+            let legacy_ix = bpf_loader_upgradeable::extend_program(
+                &SolanaPubkeyV2::new_from_array(program_id.to_bytes()),
+                Some(&SolanaPubkeyV2::new_from_array(deployer.pubkey().to_bytes())),
+                additional_bytes,
+            );
+            let legacy_ix = loader::to_sdk_instructions(vec![legacy_ix]).remove(0);
+            send_extend(rpc_client, deployer, upgrade_authority, legacy_ix).await
         }
-        _ => anyhow::bail!("Invalid ProgramData account state"),
+        result => result,
+    }
+}
+
+async fn send_extend(
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    upgrade_authority: &Keypair,
+    instruction: SdkInstruction,
+) -> Result<Signature> {
+    let needs_authority = instruction.accounts.iter().any(|a| a.is_signer && a.pubkey == upgrade_authority.pubkey());
+    let fee_payer = fee_payer(deployer);
+    let mut signers = vec![&*fee_payer, deployer];
+    if needs_authority {
+        signers.push(upgrade_authority);
     }
+    signers.dedup_by_key(|k| k.pubkey());
+
+    let (recent_blockhash, instructions) = durable_blockhash(
+        rpc_client,
+        &signers,
+        with_compute_budget(rpc_client, TxClass::Extend, &fee_payer.pubkey(), &[instruction]).await,
+    )
+    .await?;
+    let mut transaction = Transaction::new_with_payer(&instructions, Some(&fee_payer.pubkey()));
+    transaction.sign(&signers, recent_blockhash);
+    send_and_confirm(rpc_client, &transaction).await
 }
 
 /// Write program data to buffer account in chunks
@@ -421,15 +991,14 @@ async fn write_program_data_to_buffer(
             data: write_ix.data,
         };
         
-        let recent_blockhash = rpc_client.get_latest_blockhash()?;
+        let recent_blockhash = with_retry(|| rpc_client.get_latest_blockhash()).await?;
         let mut transaction = Transaction::new_with_payer(
             &[sdk_instruction],
             Some(&authority.pubkey()),
         );
         transaction.sign(&[authority], recent_blockhash);
         
-        rpc_client
-            .send_and_confirm_transaction(&transaction)
+        send_and_confirm(rpc_client, &transaction).await
             .context(format!("Failed to write chunk {} of {}", chunk_index + 1, total_chunks))?;
         
         if (chunk_index + 1) % 10 == 0 || chunk_index + 1 == total_chunks {
@@ -0,0 +1,524 @@
+use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
+use solana_sdk::{
+    pubkey::Pubkey,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use tempfile::TempDir;
+use crate::config::{Config, VerifiedBuild};
+use crate::utils::*;
+
+/// Bytecode and metadata read from a program's ProgramData account
+pub struct OnChainProgram {
+    pub programdata_address: Pubkey,
+    pub slot: u64,
+    pub upgrade_authority: Option<Pubkey>,
+    /// ELF payload with the metadata header and zero padding removed
+    pub bytecode: Vec<u8>,
+}
+
+pub async fn execute(
+    program_id_str: String,
+    program_path: Option<String>,
+    verifiable: bool,
+    repo_url: Option<String>,
+    commit: Option<String>,
+) -> Result<()> {
+    if verifiable {
+        return execute_verifiable(program_id_str, repo_url, commit).await;
+    }
+
+    print_header("Verify Program");
+
+    let config = Config::new()?;
+
+    let program_id = Pubkey::from_str(&program_id_str)
+        .context("Invalid program ID")?;
+
+    let program_file = if let Some(path) = program_path {
+        PathBuf::from(path)
+    } else {
+        detect_program_file()?
+    };
+
+    if !program_file.exists() {
+        anyhow::bail!("Program file not found: {}", program_file.display());
+    }
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    println!("\n Fetching on-chain bytecode...");
+
+    let on_chain = fetch_program_bytecode(&rpc_client, &program_id).await?;
+
+    let local_data = fs::read(&program_file)
+        .context("Failed to read program file")?;
+    let local_hash = sha256_hex(strip_trailing_zeros(&local_data));
+    let on_chain_hash = sha256_hex(&on_chain.bytecode);
+
+    println!();
+    println!("Program ID:        {program_id}");
+    println!("ProgramData:       {}", on_chain.programdata_address);
+    println!("Deployed slot:     {}", on_chain.slot);
+    match on_chain.upgrade_authority {
+        Some(authority) => println!("Upgrade authority: {authority}"),
+        None => println!("Upgrade authority: None (immutable)"),
+    }
+    println!();
+    println!("Local artifact:    {}", program_file.display());
+    println!("Local hash:        {local_hash}");
+    println!("On-chain hash:     {on_chain_hash}");
+
+    if local_hash != on_chain_hash {
+        anyhow::bail!(
+            "Bytecode mismatch.\n\
+            The on-chain program does not match {}.\n\
+            Rebuild from the deployed source or run `shield-deploy upgrade`.",
+            program_file.display()
+        );
+    }
+
+    print_success("Bytecode matches local artifact");
+
+    if record_verified_hash(&config, &program_id, on_chain_hash)? {
+        println!("  ↳ Verified hash recorded in state");
+    }
+
+    Ok(())
+}
+
+/// Check that `program_id` runs exactly `program_data` after a deploy or upgrade
+///
+/// The ProgramData payload is truncated to the artifact length, since the
+/// loader zero-fills the rest of the account, and hashed like the artifact.
+/// Returns the on-chain hash in the form `verify` records.
+pub async fn verify_deployed_bytecode(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    program_data: &[u8],
+) -> Result<String> {
+    println!("\n Verifying on-chain bytecode...");
+
+    let on_chain = fetch_program_bytecode(rpc_client, program_id).await?;
+    let mut deployed = on_chain.bytecode.clone();
+    let trailing_data = deployed.len() > program_data.len();
+    deployed.resize(program_data.len(), 0);
+
+    let local_hash = sha256_hex(program_data);
+    let on_chain_hash = sha256_hex(&deployed);
+    println!("  ↳ Local hash:    {local_hash}");
+    println!("  ↳ On-chain hash: {on_chain_hash}");
+
+    if trailing_data || local_hash != on_chain_hash {
+        anyhow::bail!(
+            "On-chain bytecode of {program_id} does not match the artifact that was sent.\n\
+            Another upgrade may have landed in between. Check the upgrade authority with\n\
+            `shield-deploy show {program_id}` and compare with `shield-deploy verify {program_id}`."
+        );
+    }
+
+    println!("  ✓ On-chain bytecode matches the artifact");
+    Ok(sha256_hex(&on_chain.bytecode))
+}
+
+/// Check that `program_id` runs the bytecode recorded as `expected_hash`
+///
+/// For upgrades sent from a buffer written earlier, when the artifact itself
+/// is no longer at hand.
+pub async fn verify_deployed_hash(rpc_client: &RpcClient, program_id: &Pubkey, expected_hash: &str) -> Result<String> {
+    println!("\n Verifying on-chain bytecode...");
+
+    let on_chain_hash = sha256_hex(&fetch_program_bytecode(rpc_client, program_id).await?.bytecode);
+    println!("  ↳ Expected hash: {expected_hash}");
+    println!("  ↳ On-chain hash: {on_chain_hash}");
+
+    if on_chain_hash != expected_hash {
+        anyhow::bail!(
+            "On-chain bytecode of {program_id} does not match the buffer that was sent.\n\
+            Another upgrade may have landed in between. Check the upgrade authority with\n\
+            `shield-deploy show {program_id}`."
+        );
+    }
+
+    println!("  ✓ On-chain bytecode matches");
+    Ok(on_chain_hash)
+}
+
+/// Store a matching on-chain hash on the program's record; `false` if it isn't tracked
+pub fn record_verified_hash(config: &Config, program_id: &Pubkey, hash: String) -> Result<bool> {
+    let mut state = config.load_state()?;
+    let Some(program) = state.program_mut(&program_id.to_string()) else {
+        return Ok(false);
+    };
+    program.verified_hash = Some(hash);
+    program.verified_at = Some(chrono::Utc::now().timestamp());
+    config.save_state(&state)?;
+    Ok(true)
+}
+
+/// Fetch a program's ProgramData account and extract its bytecode
+pub async fn fetch_program_bytecode(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+) -> Result<OnChainProgram> {
+    let program_account = with_retry(|| rpc_client.get_account(program_id)).await
+        .context("Failed to fetch program account - it may not exist")?;
+
+    let loader_id_sdk = Pubkey::new_from_array(LOADER_ID.to_bytes());
+    if program_account.owner != loader_id_sdk {
+        anyhow::bail!("Program is not an upgradeable program");
+    }
+
+    let programdata_address = match bincode::deserialize::<UpgradeableLoaderState>(
+        &program_account.data
+    )? {
+        UpgradeableLoaderState::Program { programdata_address } => programdata_address,
+        _ => anyhow::bail!("Invalid program account"),
+    };
+
+    let programdata_address = Pubkey::from(programdata_address.to_bytes());
+    let programdata = with_retry(|| rpc_client.get_account(&programdata_address)).await
+        .context("ProgramData account not found - program may be closed")?;
+
+    let (slot, upgrade_authority) = match bincode::deserialize::<UpgradeableLoaderState>(
+        &programdata.data
+    )? {
+        UpgradeableLoaderState::ProgramData {
+            slot,
+            upgrade_authority_address,
+        } => (
+            slot,
+            upgrade_authority_address.map(|authority| Pubkey::from(authority.to_bytes())),
+        ),
+        _ => anyhow::bail!("Invalid ProgramData account"),
+    };
+
+    let header_len = UpgradeableLoaderState::size_of_programdata_metadata();
+    let payload = programdata.data.get(header_len..).unwrap_or_default();
+
+    Ok(OnChainProgram {
+        programdata_address,
+        slot,
+        upgrade_authority,
+        bytecode: strip_trailing_zeros(payload).to_vec(),
+    })
+}
+
+/// Authority and contents of a loader buffer account
+pub struct OnChainBuffer {
+    pub authority: Option<Pubkey>,
+    pub lamports: u64,
+    /// Account data length, header included
+    pub data_len: usize,
+    /// Written payload with the header and zero padding removed
+    pub payload: Vec<u8>,
+}
+
+/// Fetch a buffer account and strip its `UpgradeableLoaderState::Buffer` header
+pub async fn fetch_buffer(rpc_client: &RpcClient, address: &Pubkey) -> Result<OnChainBuffer> {
+    let account = rpc_client
+        .get_account(address).await
+        .context("Buffer account not found on this network")?;
+
+    let loader_id_sdk = Pubkey::new_from_array(LOADER_ID.to_bytes());
+    if account.owner != loader_id_sdk {
+        anyhow::bail!("{address} is owned by {}, not the upgradeable loader", account.owner);
+    }
+
+    let authority = match bincode::deserialize::<UpgradeableLoaderState>(&account.data) {
+        Ok(UpgradeableLoaderState::Buffer { authority_address }) => {
+            authority_address.map(|authority| Pubkey::from(authority.to_bytes()))
+        }
+        _ => anyhow::bail!("{address} is a loader account but not a buffer"),
+    };
+
+    let header_len = UpgradeableLoaderState::size_of_buffer_metadata();
+    let payload = account.data.get(header_len..).unwrap_or_default();
+
+    Ok(OnChainBuffer {
+        authority,
+        lamports: account.lamports,
+        data_len: account.data.len(),
+        payload: strip_trailing_zeros(payload).to_vec(),
+    })
+}
+
+/// Local build artifacts whose bytecode hashes to `hash`
+///
+/// Looks at `.so` files in `target/deploy/` and `.shield/artifacts/`.
+pub fn find_local_artifacts(config: &Config, hash: &str) -> Vec<PathBuf> {
+    let mut matches = Vec::new();
+    for dir in [PathBuf::from("target/deploy"), config.artifacts_dir()] {
+        let Ok(entries) = fs::read_dir(&dir) else { continue };
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "so"))
+            .collect();
+        files.sort();
+        for file in files {
+            let Ok(data) = fs::read(&file) else { continue };
+            if sha256_hex(strip_trailing_zeros(&data)) == hash {
+                matches.push(file);
+            }
+        }
+    }
+    matches
+}
+
+/// Rebuild the program in the pinned solana-verify Docker image and compare
+/// the reproducible artifact against the on-chain bytecode
+async fn execute_verifiable(
+    program_id_str: String,
+    repo_url: Option<String>,
+    commit: Option<String>,
+) -> Result<()> {
+    print_header("Verifiable Build");
+
+    let config = Config::new()?;
+
+    let program_id = Pubkey::from_str(&program_id_str)
+        .context("Invalid program ID")?;
+
+    if !tool_available("docker") {
+        anyhow::bail!(
+            "Docker is required for verifiable builds but was not found.\n\
+            Install Docker (https://docs.docker.com/get-docker/) and make sure\n\
+            the daemon is running, then retry."
+        );
+    }
+
+    if !tool_available("solana-verify") {
+        anyhow::bail!(
+            "solana-verify was not found.\n\
+            Install it with `cargo install solana-verify` and retry."
+        );
+    }
+
+    // The clone is removed when this goes out of scope, after the build
+    let clone = match &repo_url {
+        Some(url) => {
+            let commit = commit.as_deref()
+                .ok_or_else(|| anyhow::anyhow!("--commit is required with --repo-url"))?;
+            Some(clone_at_commit(url, commit)?)
+        }
+        None => None,
+    };
+    let source_dir = match &clone {
+        Some(dir) => dir.path().to_path_buf(),
+        None => std::env::current_dir()?,
+    };
+
+    let commit = resolve_commit(&source_dir)?;
+
+    if repo_url.is_none() && worktree_dirty(&source_dir) {
+        print_warning("Working tree has uncommitted changes - the build will not match the recorded commit");
+    }
+
+    let lib_name = extract_lib_name(&source_dir.join("Cargo.toml")).ok();
+    let toolchain = detect_toolchain(&source_dir);
+
+    config.ensure_shield_dir()?;
+    fs::create_dir_all(config.logs_dir())
+        .context("Failed to create logs directory")?;
+    let log_path = config.logs_dir().join(format!(
+        "verifiable-build-{}.log",
+        chrono::Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+    let log_file = fs::File::create(&log_path)
+        .context("Failed to create build log")?;
+
+    println!("\n Building in Docker (this can take several minutes)...");
+    println!("  ↳ Source: {}", source_dir.display());
+    println!("  ↳ Commit: {commit}");
+    println!("  ↳ Log:    {}", log_path.display());
+
+    let mut build = Command::new("solana-verify");
+    build.arg("build").current_dir(&source_dir);
+    if let Some(name) = &lib_name {
+        build.args(["--library-name", name]);
+    }
+
+    let status = build
+        .stdout(Stdio::from(log_file.try_clone()?))
+        .stderr(Stdio::from(log_file))
+        .status()
+        .context("Failed to run solana-verify")?;
+
+    if !status.success() {
+        anyhow::bail!(
+            "Verifiable build failed.\n\
+            See the build log for details: {}",
+            log_path.display()
+        );
+    }
+
+    let artifact = find_build_artifact(&source_dir, lib_name.as_deref())
+        .ok_or_else(|| anyhow::anyhow!(
+            "Build succeeded but no artifact was found in {}/target/deploy.\n\
+            Build log: {}",
+            source_dir.display(),
+            log_path.display()
+        ))?;
+
+    let artifact_data = fs::read(&artifact)
+        .context("Failed to read build artifact")?;
+    let build_hash = sha256_hex(strip_trailing_zeros(&artifact_data));
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    let on_chain = fetch_program_bytecode(&rpc_client, &program_id).await?;
+    let on_chain_hash = sha256_hex(&on_chain.bytecode);
+
+    println!();
+    println!("Program ID:     {program_id}");
+    println!("Repository:     {}", repo_url.as_deref().unwrap_or("local working tree"));
+    println!("Commit:         {commit}");
+    println!("Toolchain:      {toolchain}");
+    println!("Artifact:       {}", artifact.display());
+    println!("Build hash:     {build_hash}");
+    println!("On-chain hash:  {on_chain_hash}");
+
+    if build_hash != on_chain_hash {
+        anyhow::bail!(
+            "Verifiable build does not match on-chain bytecode.\n\
+            The deployed program was not built from commit {commit}."
+        );
+    }
+
+    print_success("Verified build: on-chain bytecode is reproducible from source");
+
+    let mut state = config.load_state()?;
+    if let Some(program) = state.program_mut(&program_id_str) {
+        let now = chrono::Utc::now().timestamp();
+        program.verified_hash = Some(on_chain_hash.clone());
+        program.verified_at = Some(now);
+        program.verified_build = Some(VerifiedBuild {
+            commit,
+            hash: on_chain_hash,
+            toolchain,
+            repo_url,
+            verified_at: now,
+        });
+        config.save_state(&state)?;
+        println!("  ↳ Verified build recorded in state");
+    }
+
+    Ok(())
+}
+
+fn tool_available(tool: &str) -> bool {
+    Command::new(tool)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+fn clone_at_commit(repo_url: &str, commit: &str) -> Result<TempDir> {
+    let dir = tempfile::Builder::new()
+        .prefix("shield-verify-")
+        .tempdir()
+        .context("Failed to create a directory for the clone")?;
+
+    println!("\n Cloning {repo_url}...");
+
+    let status = Command::new("git")
+        .args(["clone", "--quiet", repo_url])
+        .arg(dir.path())
+        .status()
+        .context("Failed to run git clone")?;
+    if !status.success() {
+        anyhow::bail!("Failed to clone {repo_url}");
+    }
+
+    let status = Command::new("git")
+        .args(["checkout", "--quiet", commit])
+        .current_dir(dir.path())
+        .status()
+        .context("Failed to run git checkout")?;
+    if !status.success() {
+        anyhow::bail!("Commit {commit} not found in {repo_url}");
+    }
+
+    Ok(dir)
+}
+
+fn resolve_commit(source_dir: &Path) -> Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(source_dir)
+        .output()
+        .context("Failed to run git - verifiable builds require a git checkout")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} is not a git repository.\n\
+            Verifiable builds are tied to a commit; commit your source first.",
+            source_dir.display()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn worktree_dirty(source_dir: &Path) -> bool {
+    Command::new("git")
+        .args(["status", "--porcelain"])
+        .current_dir(source_dir)
+        .output()
+        .map(|output| !output.stdout.is_empty())
+        .unwrap_or(false)
+}
+
+/// Read the pinned toolchain from rust-toolchain(.toml), if the project has one
+fn detect_toolchain(source_dir: &Path) -> String {
+    let toml_path = source_dir.join("rust-toolchain.toml");
+    if let Ok(content) = fs::read_to_string(&toml_path) {
+        if let Some(channel) = toml::from_str::<toml::Value>(&content)
+            .ok()
+            .and_then(|v| v.get("toolchain")?.get("channel")?.as_str().map(String::from))
+        {
+            return channel;
+        }
+    }
+
+    if let Ok(content) = fs::read_to_string(source_dir.join("rust-toolchain")) {
+        return content.trim().to_string();
+    }
+
+    "solana-verify default image".to_string()
+}
+
+fn find_build_artifact(source_dir: &Path, lib_name: Option<&str>) -> Option<PathBuf> {
+    let deploy_dir = source_dir.join("target/deploy");
+
+    if let Some(name) = lib_name {
+        let path = deploy_dir.join(name).with_extension("so");
+        if path.exists() {
+            return Some(path);
+        }
+    }
+
+    fs::read_dir(&deploy_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .find(|entry| {
+            entry.path().extension().and_then(|s| s.to_str()) == Some("so")
+        })
+        .map(|entry| entry.path())
+}
@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use std::str::FromStr;
+use crate::config::{Config, OperationKind, OperationRecord, ProjectConfig};
+use crate::pool::{pool_stats, PoolHealth};
+use crate::privacy::PrivacyLayer;
+use crate::utils::*;
+
+/// Lamports kept back on the deployer to cover the Privacy Cash fee
+/// (~0.006 SOL) and transaction fees when withdrawing everything
+const WITHDRAW_FEE_RESERVE: u64 = 10_000_000;
+
+pub async fn execute(
+    amount: Option<Amount>,
+    all: bool,
+    to: Option<String>,
+    no_decompress: bool,
+) -> Result<()> {
+    print_header("Withdraw Deployer Funds");
+
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    let recipient = match (&to, no_decompress) {
+        (Some(to), _) => Some(Pubkey::from_str(to).context("Invalid recipient public key")?),
+        (None, true) => None,
+        (None, false) => anyhow::bail!(
+            "No recipient given.\n\
+            Pass --to <pubkey>, or --no-decompress to keep the funds shielded."
+        ),
+    };
+
+    let deployer = config.load_deployer()?;
+
+    if recipient == Some(deployer.pubkey()) {
+        anyhow::bail!("Recipient is the deployer itself");
+    }
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url.clone(),
+        commitment(),
+    );
+
+    let balance = rpc_client.get_balance(&deployer.pubkey()).await
+        .context("Failed to get deployer balance")?;
+
+    let available = balance.saturating_sub(WITHDRAW_FEE_RESERVE);
+
+    let amount = match (all, amount) {
+        (true, _) => Amount::Max,
+        (false, Some(amount)) => amount,
+        (false, None) => {
+            println!();
+            prompt_amount("Amount to withdraw (SOL, or `all`)", true)?
+        }
+    };
+    let amount_lamports = amount.resolve(available);
+
+    let rounded_lamports = PrivacyLayer::round_amount(amount_lamports);
+    let rounded_sol = rounded_lamports as f64 / LAMPORTS_PER_SOL as f64;
+
+    if rounded_lamports > available {
+        anyhow::bail!(
+            "Insufficient deployer balance.\n\
+            Current: {}\n\
+            Requested: {} (plus ~{} reserved for fees)",
+            format_sol(balance),
+            format_sol(rounded_lamports),
+            format_sol(WITHDRAW_FEE_RESERVE)
+        );
+    }
+
+    if rounded_lamports != amount_lamports {
+        println!("\n💡 Amount adjusted to {rounded_sol} SOL");
+        println!("   (Privacy Cash minimum: 0.02 SOL)");
+    }
+
+    // Same check as `fund`; an unreachable indexer just skips it
+    if get_network_name() == "mainnet-beta" {
+        let endpoint = ProjectConfig::load()?.indexer_url.unwrap_or_else(|| rpc_url.clone());
+        if let Ok(report) = pool_stats(&rpc_client, &endpoint, &[("1h", 3_600)]).await {
+            if report.health != PoolHealth::Healthy {
+                print_warning("Privacy pool activity is low right now; this withdrawal may stand out");
+                println!("→ Check `shield-deploy pool-stats --window 24h` or wait for more activity");
+            }
+        }
+    }
+
+    println!("\nThis withdrawal will:");
+    println!("• Compress {rounded_sol} SOL from the deployer into Privacy Cash");
+    println!("• Wait for the privacy delay");
+    match &recipient {
+        Some(recipient) => println!("• Decompress to {recipient}"),
+        None => println!("• Leave the funds shielded in a project vault"),
+    }
+    println!();
+    println!("The recipient will have no on-chain link to the deployer.\n");
+
+    if !prompt_confirmation("Proceed?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let privacy = PrivacyLayer::new(&rpc_url);
+
+    // Persist the vault key first so a crash can never strand funds
+    let vault = Keypair::new();
+    let vault_path = config.save_vault_key(&vault)?;
+
+    let result = privacy.compress_sol(&deployer, &vault.pubkey(), rounded_sol)
+        .await
+        .context("Failed to compress deployer funds")?;
+
+    let deposit_signature = result.deposit_signature.to_string();
+    let mut record = OperationRecord::new(OperationKind::Withdraw, &get_network_name());
+    record.amount_lamports = Some(result.amount_deposited);
+    record.signatures.push(deposit_signature.clone());
+    if recipient.is_none() {
+        record.note = Some("parked in vault".to_string());
+    }
+
+    let mut state = config.load_state()?;
+    state.operations.push(record);
+    if let Ok(balance) = rpc_client.get_balance(&deployer.pubkey()).await {
+        state.last_balance = balance;
+    }
+    config.save_state(&state)?;
+
+    if let Some(recipient) = recipient {
+        privacy.apply_privacy_delay().await;
+
+        let (signature, sent) = privacy.decompress_sol(&vault, &recipient, None).await
+            .context(format!(
+                "Failed to decompress funds. They remain shielded in {}",
+                vault_path.display()
+            ))?;
+
+        config.remove_vault_key(&vault.pubkey())?;
+
+        // The record now covers both legs, with what actually arrived
+        let mut state = config.load_state()?;
+        if let Some(record) = state
+            .operations
+            .iter_mut()
+            .rev()
+            .find(|op| op.signatures.contains(&deposit_signature))
+        {
+            record.signatures.push(signature.to_string());
+            record.amount_lamports = Some(sent);
+        }
+        config.save_state(&state)?;
+
+        print_success("Withdrawal complete");
+        println!("\nSent:       {}", format_sol(sent));
+        println!("Recipient:  {recipient}");
+    } else {
+        print_success("Funds shielded");
+        println!("\nShielded:   {}", format_sol(result.amount_received));
+        println!("Vault key:  {}", vault_path.display());
+        println!("\nThe funds stay shielded until a later fund or withdraw releases them.");
+    }
+
+    Ok(())
+}
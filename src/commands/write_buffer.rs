@@ -0,0 +1,203 @@
+use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use crate::commands::CliProgress;
+use crate::compute::FeeSettings;
+use crate::config::{BufferPurpose, BufferRecord, BufferStatus, Config, OperationKind, OperationRecord};
+use crate::loader::set_buffer_authority;
+use crate::utils::*;
+
+pub async fn execute(program_path: Option<String>, authority: Option<String>) -> Result<()> {
+    print_header("Write Program Buffer");
+
+    let config = Config::new()?;
+
+    if !config.deployer_exists() {
+        anyhow::bail!(
+            "No private deployer found.\n\
+            Run `shield-deploy init` first."
+        );
+    }
+
+    let deployer = config.load_deployer()?;
+    config.check_rotation_policy()?;
+
+    let new_authority = authority
+        .as_deref()
+        .map(Pubkey::from_str)
+        .transpose()
+        .context("Invalid authority public key")?;
+
+    let program_file = if let Some(path) = program_path {
+        PathBuf::from(path)
+    } else {
+        detect_program_file()?
+    };
+
+    if !program_file.exists() {
+        anyhow::bail!("Program file not found: {}", program_file.display());
+    }
+
+    let program_data = fs::read(&program_file)
+        .context("Failed to read program file")?;
+    let artifact_hash = sha256_hex(&program_data);
+
+    let rpc_url = get_rpc_url()?;
+    let rpc_client = RpcClient::new_with_commitment(
+        rpc_url,
+        commitment(),
+    );
+
+    let mut state = config.load_state()?;
+    let balance = rpc_client.get_balance(&deployer.pubkey()).await
+        .context("Failed to get deployer balance")?;
+
+    // An interrupted write of the same artifact is picked up where it stopped
+    let resumable = state.buffers
+        .iter()
+        .position(|b| {
+            b.status == BufferStatus::Writing
+                && b.artifact_hash == artifact_hash
+                && b.authority == deployer.pubkey().to_string()
+        });
+    let resumable = match resumable.map(|i| (i, Pubkey::from_str(&state.buffers[i].address))) {
+        Some((i, Ok(address))) if rpc_client.get_account(&address).await.is_ok() => Some(i),
+        _ => None,
+    };
+
+    println!("\nBuild artifact:");
+    println!("• {} ({} bytes)\n", program_file.display(), program_data.len());
+
+    match resumable {
+        Some(i) => println!("Resuming the unfinished buffer {}.", state.buffers[i].address),
+        None => {
+            let rent = rpc_client
+                .get_minimum_balance_for_rent_exemption(
+                    UpgradeableLoaderState::size_of_buffer(program_data.len()),
+                )
+                .await
+                .context("Failed to get rent exemption for buffer")?;
+            println!("This will create a buffer funded by the private deployer.");
+            println!("Buffer rent: {}", format_sol(rent));
+
+            if balance < rent {
+                anyhow::bail!(
+                    "Insufficient deployer balance.\n\
+                    Current: {}\n\
+                    Needed: {}\n\
+                    Run `shield-deploy fund` to add more SOL.",
+                    format_sol(balance),
+                    format_sol(rent)
+                );
+            }
+        }
+    }
+    if let Some(new_authority) = &new_authority {
+        println!("Buffer authority will be handed to {new_authority}.");
+    }
+    println!();
+
+    if !prompt_confirmation("Proceed?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let fees = FeeSettings::current();
+    let mut signatures = Vec::new();
+
+    let index = match resumable {
+        Some(i) => {
+            let buffer_pubkey = Pubkey::from_str(&state.buffers[i].address)?;
+            println!("\n Resuming buffer write...");
+            write_missing_chunks(&rpc_client, &fees, &deployer, &buffer_pubkey, &program_data, &CliProgress)
+                .await
+                .context(format!(
+                    "Failed to write program data. Re-run `shield-deploy write-buffer` to resume {buffer_pubkey}"
+                ))?;
+            i
+        }
+        None => {
+            println!("\n Creating program buffer...");
+
+            let buffer_keypair = Keypair::new();
+            let buffer_pubkey = buffer_keypair.pubkey();
+            let signature = create_buffer_account(
+                &rpc_client,
+                &fees,
+                &deployer,
+                &buffer_keypair,
+                program_data.len(),
+            )
+            .await?;
+            signatures.push(signature.to_string());
+
+            println!("  ✓ Buffer created: {signature}");
+            println!("  ↳ Buffer address: {buffer_pubkey}");
+
+            // Record the buffer before writing so an interruption can be resumed
+            state.buffers.push(BufferRecord {
+                address: buffer_pubkey.to_string(),
+                status: BufferStatus::Writing,
+                created_at: chrono::Utc::now().timestamp(),
+                name: program_file
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string()),
+                artifact_hash: artifact_hash.clone(),
+                size: program_data.len(),
+                authority: deployer.pubkey().to_string(),
+                program_id: None,
+                purpose: Some(BufferPurpose::Prepared),
+            });
+            config.save_state(&state)?;
+
+            println!("\n Writing program data to buffer...");
+            write_program_data_chunked(&rpc_client, &fees, &deployer, &buffer_pubkey, &program_data, &CliProgress)
+                .await
+                .context(format!(
+                    "Failed to write program data. Re-run `shield-deploy write-buffer` to resume {buffer_pubkey}"
+                ))?;
+
+            state.buffers.len() - 1
+        }
+    };
+
+    let buffer_pubkey = Pubkey::from_str(&state.buffers[index].address)?;
+    state.buffers[index].status = BufferStatus::Ready;
+
+    if let Some(new_authority) = new_authority {
+        println!("\n Setting buffer authority...");
+        let signature = set_buffer_authority(&rpc_client, &fees, &deployer, &buffer_pubkey, &new_authority).await?;
+        signatures.push(signature.to_string());
+        println!("  ✓ Authority set: {signature}");
+
+        state.buffers[index].authority = new_authority.to_string();
+        state.buffers[index].status = BufferStatus::HandedOff;
+    }
+
+    let balance_after = rpc_client.get_balance(&deployer.pubkey()).await.ok();
+
+    let mut record = OperationRecord::new(OperationKind::WriteBuffer, &get_network_name());
+    record.signatures = signatures;
+    record.cost_lamports = balance_after.map(|after| balance.saturating_sub(after));
+    record.note = Some(format!("buffer {buffer_pubkey}"));
+    state.operations.push(record);
+    state.last_balance = balance_after.unwrap_or(balance);
+    config.save_state(&state)?;
+
+    print_success("Buffer ready");
+
+    let buffer = &state.buffers[index];
+    println!("\nBuffer:     {}", buffer.address);
+    println!("Size:       {} bytes", buffer.size);
+    println!("Authority:  {}", buffer.authority);
+    println!("SHA-256:    {}", buffer.artifact_hash);
+
+    Ok(())
+}
@@ -0,0 +1,554 @@
+use serde::{Deserialize, Deserializer};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use crate::config::ProjectConfig;
+use crate::utils::{format_sol, get_network_name, is_verbose};
+
+/// Highest limit a transaction may request; used while simulating
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Base fee per signature
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// Compute units charged for when no limit instruction is present
+const DEFAULT_COMPUTE_UNIT_LIMIT: u64 = 200_000;
+
+/// Write limit when simulation is unavailable: the loader's 2,370 CU plus the budget instructions, with headroom
+const WRITE_COMPUTE_UNIT_LIMIT: u32 = 10_000;
+
+/// Deploy or upgrade limit when simulation is unavailable; twice the default, for large ELFs
+const DEPLOY_COMPUTE_UNIT_LIMIT: u32 = 400_000;
+
+/// Percentile of recent fees `auto` picks unless `priority_fee_percentile` is set
+const DEFAULT_PRIORITY_FEE_PERCENTILE: u64 = 75;
+
+/// Most accounts `getRecentPrioritizationFees` accepts
+const MAX_PRIORITY_FEE_ACCOUNTS: usize = 128;
+
+/// Compute unit price in micro-lamports, or `auto` to follow recent network fees
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PriorityFee {
+    Fixed(u64),
+    Auto,
+}
+
+impl FromStr for PriorityFee {
+    type Err = anyhow::Error;
+
+    fn from_str(raw: &str) -> anyhow::Result<Self> {
+        if raw.trim().eq_ignore_ascii_case("auto") {
+            return Ok(PriorityFee::Auto);
+        }
+        raw.trim()
+            .parse()
+            .map(PriorityFee::Fixed)
+            .map_err(|_| anyhow::anyhow!("'{raw}' is not `auto` or a micro-lamport amount"))
+    }
+}
+
+impl fmt::Display for PriorityFee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriorityFee::Fixed(price) => write!(f, "{price}"),
+            PriorityFee::Auto => f.write_str("auto"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PriorityFee {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Fixed(u64),
+            Text(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Fixed(price) => Ok(PriorityFee::Fixed(price)),
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// `--priority-fee` given on the command line, overriding config
+static PRIORITY_FEE_OVERRIDE: OnceLock<PriorityFee> = OnceLock::new();
+
+/// Use `fee` for every transaction in this run
+pub fn set_priority_fee(fee: PriorityFee) {
+    let _ = PRIORITY_FEE_OVERRIDE.set(fee);
+}
+
+/// Priority fee in effect: the flag, then `priority_fee`, then `auto` on mainnet
+pub fn priority_fee_setting() -> PriorityFee {
+    FeeSettings::current().priority_fee
+}
+
+/// How one project prices its transactions
+///
+/// Resolved from a project's config up front, so transactions built for a
+/// [`ShieldProject`](crate::ShieldProject) follow that project's settings
+/// rather than whatever config sits in the working directory.
+#[derive(Clone, Debug)]
+pub struct FeeSettings {
+    priority_fee: PriorityFee,
+    percentile: u64,
+    max_priority_fee: Option<u64>,
+    /// Price used under `auto` when the RPC can't report recent fees
+    static_price: u64,
+    /// Print a note the first time `static_price` stands in (CLI runs only)
+    note_fallback: bool,
+}
+
+impl FeeSettings {
+    /// Settings from `config` on `network`: `priority_fee`, else `auto` on mainnet
+    pub fn from_config(config: &ProjectConfig, network: &str) -> Self {
+        let priority_fee = match config.priority_fee {
+            Some(fee) => fee,
+            None if network == "mainnet-beta" => PriorityFee::Auto,
+            None => PriorityFee::Fixed(0),
+        };
+        Self {
+            priority_fee,
+            percentile: config
+                .priority_fee_percentile
+                .unwrap_or(DEFAULT_PRIORITY_FEE_PERCENTILE)
+                .clamp(1, 100),
+            max_priority_fee: config.max_priority_fee,
+            static_price: match config.priority_fee {
+                Some(PriorityFee::Fixed(price)) => price,
+                _ => 0,
+            },
+            note_fallback: false,
+        }
+    }
+
+    /// Settings for this run: `--priority-fee`, then the project in the working directory
+    pub fn current() -> Self {
+        let mut settings = Self::from_config(&ProjectConfig::load().unwrap_or_default(), &get_network_name());
+        if let Some(fee) = PRIORITY_FEE_OVERRIDE.get() {
+            settings.priority_fee = *fee;
+        }
+        settings.note_fallback = true;
+        settings
+    }
+}
+
+/// Compute unit price for transactions writing `accounts`, without caching
+///
+/// Used by cost estimates before the real transactions exist.
+pub async fn estimate_priority_fee(rpc_client: &RpcClient, accounts: &[Pubkey]) -> u64 {
+    let fees = FeeSettings::current();
+    match fees.priority_fee {
+        PriorityFee::Fixed(price) => price,
+        PriorityFee::Auto => sample_priority_fee(rpc_client, &fees, "estimate", accounts).await,
+    }
+}
+
+/// Prices chosen so far in this run, per class
+fn learned_prices() -> &'static Mutex<HashMap<TxClass, u64>> {
+    static PRICES: OnceLock<Mutex<HashMap<TxClass, u64>>> = OnceLock::new();
+    PRICES.get_or_init(Default::default)
+}
+
+/// Compute unit price for `class`, sampled once per run under `auto`
+pub async fn priority_fee_for(rpc_client: &RpcClient, fees: &FeeSettings, class: TxClass, accounts: &[Pubkey]) -> u64 {
+    match fees.priority_fee {
+        PriorityFee::Fixed(price) => price,
+        PriorityFee::Auto => {
+            if let Some(price) = learned_prices().lock().unwrap().get(&class).copied() {
+                return price;
+            }
+            let price = sample_priority_fee(rpc_client, fees, &class.to_string(), accounts).await;
+            learned_prices().lock().unwrap().insert(class, price);
+            price
+        }
+    }
+}
+
+/// Pick a percentile of `getRecentPrioritizationFees`, capped by `max_priority_fee`
+async fn sample_priority_fee(rpc_client: &RpcClient, settings: &FeeSettings, label: &str, accounts: &[Pubkey]) -> u64 {
+    let accounts = &accounts[..accounts.len().min(MAX_PRIORITY_FEE_ACCOUNTS)];
+
+    let mut fees: Vec<u64> = match rpc_client.get_recent_prioritization_fees(accounts).await {
+        Ok(samples) => samples.iter().map(|s| s.prioritization_fee).collect(),
+        Err(_) => return static_priority_fee(settings),
+    };
+    if fees.is_empty() {
+        return static_priority_fee(settings);
+    }
+    fees.sort_unstable();
+
+    let percentile = settings.percentile;
+    let at = |p: u64| fees[((fees.len() as u64 * p).div_ceil(100) as usize).saturating_sub(1)];
+    let chosen = at(percentile);
+    let capped = settings.max_priority_fee.map_or(chosen, |cap| chosen.min(cap));
+
+    if is_verbose() {
+        println!(
+            "  ↳ {label}: recent priority fees over {} slots: min {}, p50 {}, p75 {}, p90 {}, max {}",
+            fees.len(),
+            fees[0],
+            at(50),
+            at(75),
+            at(90),
+            fees[fees.len() - 1],
+        );
+        if capped < chosen {
+            println!("  ↳ {label}: p{percentile} is {chosen}, capped at max_priority_fee {capped} micro-lamports/CU");
+        } else {
+            println!("  ↳ {label}: using p{percentile}, {chosen} micro-lamports/CU");
+        }
+    }
+
+    capped
+}
+
+/// Fallback when the RPC can't report recent fees: a numeric `priority_fee`, else none
+fn static_priority_fee(settings: &FeeSettings) -> u64 {
+    static NOTED: AtomicBool = AtomicBool::new(false);
+
+    let price = settings.static_price;
+    if settings.note_fallback && !NOTED.swap(true, Ordering::Relaxed) {
+        println!(
+            "  ↳ Note: this RPC doesn't report recent priority fees; using the static priority fee ({price} micro-lamports/CU)"
+        );
+    }
+    price
+}
+
+/// Kinds of transaction, priced and budgeted together
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TxClass {
+    CreateBuffer,
+    Write,
+    Deploy,
+    Upgrade,
+    Extend,
+    SetAuthority,
+    Transfer,
+    Close,
+}
+
+impl fmt::Display for TxClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TxClass::CreateBuffer => "create-buffer",
+            TxClass::Write => "write",
+            TxClass::Deploy => "deploy",
+            TxClass::Upgrade => "upgrade",
+            TxClass::Extend => "extend",
+            TxClass::SetAuthority => "set-authority",
+            TxClass::Transfer => "transfer",
+            TxClass::Close => "close",
+        })
+    }
+}
+
+impl TxClass {
+    /// Limit to request when simulation can't measure one
+    ///
+    /// Writes and the final deploy or upgrade are sent in bulk or carry the
+    /// whole program, so they always get an explicit limit; other classes
+    /// keep the cluster default.
+    fn fallback_limit(self) -> Option<u32> {
+        match self {
+            TxClass::Write => Some(WRITE_COMPUTE_UNIT_LIMIT),
+            TxClass::Deploy | TxClass::Upgrade => Some(DEPLOY_COMPUTE_UNIT_LIMIT),
+            _ => None,
+        }
+    }
+
+    /// Whether one measured limit fits every transaction of this class
+    ///
+    /// Deploys, upgrades and extensions make the loader process the whole
+    /// program, so their cost grows with its size; a limit learned for one
+    /// program would starve a larger one later in the run.
+    fn reuses_limit(self) -> bool {
+        !matches!(self, TxClass::Deploy | TxClass::Upgrade | TxClass::Extend)
+    }
+}
+
+/// Limits learned so far in this run; `None` means simulation was unavailable
+fn learned_limits() -> &'static Mutex<HashMap<TxClass, Option<u32>>> {
+    static LIMITS: OnceLock<Mutex<HashMap<TxClass, Option<u32>>>> = OnceLock::new();
+    LIMITS.get_or_init(Default::default)
+}
+
+/// Consumption plus a safety margin for accounts that change between simulation and send
+fn with_margin(units_consumed: u64) -> u32 {
+    (units_consumed + units_consumed / 5 + 1_000).min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+}
+
+/// Compute budget chosen for one transaction
+pub struct ComputeBudget {
+    pub limit: Option<u32>,
+    /// Micro-lamports per compute unit
+    pub price: u64,
+}
+
+impl ComputeBudget {
+    pub fn instructions(&self) -> Vec<Instruction> {
+        let mut instructions = Vec::with_capacity(2);
+        if let Some(limit) = self.limit {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(limit));
+        }
+        if self.price > 0 {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(self.price));
+        }
+        instructions
+    }
+
+    /// Most the priority fee can add to the transaction, in lamports
+    pub fn max_priority_fee(&self) -> u64 {
+        let limit = self.limit.map_or(DEFAULT_COMPUTE_UNIT_LIMIT, u64::from);
+        (limit * self.price).div_ceil(1_000_000)
+    }
+}
+
+/// Priority fees of the transactions confirmed in this run, in lamports
+static PRIORITY_FEES_PAID: AtomicU64 = AtomicU64::new(0);
+
+/// Count the priority fee of a transaction that has been confirmed
+pub fn note_confirmed(transaction: &Transaction) {
+    note_priority_fee(priority_fee_of(&transaction.message));
+}
+
+/// Count `lamports` of priority fees confirmed outside `note_confirmed`
+pub fn note_priority_fee(lamports: u64) {
+    PRIORITY_FEES_PAID.fetch_add(lamports, Ordering::Relaxed);
+}
+
+/// Priority fees paid so far in this run, in lamports
+pub fn priority_fees_paid() -> u64 {
+    PRIORITY_FEES_PAID.load(Ordering::Relaxed)
+}
+
+/// Summary line for deploy and upgrade, when any priority fee was paid
+pub fn report_priority_fees() {
+    let paid = priority_fees_paid();
+    if paid > 0 {
+        println!(
+            "Priority fees paid: {paid} lamports ({:.6} SOL)",
+            paid as f64 / 1_000_000_000.0
+        );
+    }
+}
+
+/// What a message's compute budget instructions add to its fee
+///
+/// The price is charged on the requested limit, not on what was consumed.
+pub fn priority_fee_of(message: &Message) -> u64 {
+    let compute_budget_program = ComputeBudgetInstruction::set_compute_unit_price(0).program_id;
+    let mut budget = ComputeBudget { limit: None, price: 0 };
+    for ix in &message.instructions {
+        if message.account_keys.get(ix.program_id_index as usize) != Some(&compute_budget_program) {
+            continue;
+        }
+        // Borsh enum tags: 2 = SetComputeUnitLimit(u32), 3 = SetComputeUnitPrice(u64)
+        match (ix.data.first(), ix.data.get(1..)) {
+            (Some(2), Some(limit)) if limit.len() >= 4 => {
+                budget.limit = Some(u32::from_le_bytes([limit[0], limit[1], limit[2], limit[3]]));
+            }
+            (Some(3), Some(price)) if price.len() >= 8 => {
+                budget.price = u64::from_le_bytes(price[..8].try_into().unwrap_or_default());
+            }
+            _ => {}
+        }
+    }
+    budget.max_priority_fee()
+}
+
+/// Prefix `instructions` with a compute budget sized from a simulation
+///
+/// The first transaction of each class is simulated to learn its compute
+/// consumption; later transactions of the same class reuse that figure,
+/// except deploys, upgrades and extensions, which are simulated every time.
+/// The priority fee setting is applied as the unit price. When the RPC
+/// cannot simulate, writes, deploys and upgrades get a fixed limit sized
+/// for the loader instruction and other classes keep the cluster default.
+pub async fn with_compute_budget(
+    rpc_client: &RpcClient,
+    class: TxClass,
+    payer: &Pubkey,
+    instructions: &[Instruction],
+) -> Vec<Instruction> {
+    with_compute_budget_for(rpc_client, &FeeSettings::current(), class, payer, instructions).await
+}
+
+/// Same as [`with_compute_budget`], priced by `fees` instead of this run's settings
+pub async fn with_compute_budget_for(
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    class: TxClass,
+    payer: &Pubkey,
+    instructions: &[Instruction],
+) -> Vec<Instruction> {
+    let mut budgeted = compute_budget_for(rpc_client, fees, class, payer, instructions).await.instructions();
+    budgeted.extend_from_slice(instructions);
+    budgeted
+}
+
+/// Limit and unit price for a transaction of `class`, without building it
+pub async fn compute_budget(
+    rpc_client: &RpcClient,
+    class: TxClass,
+    payer: &Pubkey,
+    instructions: &[Instruction],
+) -> ComputeBudget {
+    compute_budget_for(rpc_client, &FeeSettings::current(), class, payer, instructions).await
+}
+
+/// Same as [`compute_budget`], priced by `fees` instead of this run's settings
+pub async fn compute_budget_for(
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    class: TxClass,
+    payer: &Pubkey,
+    instructions: &[Instruction],
+) -> ComputeBudget {
+    let price = priority_fee_for(rpc_client, fees, class, &writable_accounts(payer, instructions)).await;
+
+    let cached = class.reuses_limit().then(|| learned_limits().lock().unwrap().get(&class).copied()).flatten();
+    let limit = match cached {
+        Some(limit) => limit,
+        None => {
+            let simulated = simulate_limit(rpc_client, payer, instructions).await;
+            let limit = simulated.or_else(|| class.fallback_limit());
+            if class.reuses_limit() {
+                learned_limits().lock().unwrap().insert(class, limit);
+            }
+            report(class, limit, simulated.is_some(), price, payer, instructions);
+            limit
+        }
+    };
+
+    ComputeBudget { limit, price }
+}
+
+/// Accounts whose write locks set the local fee market for these instructions
+fn writable_accounts(payer: &Pubkey, instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut accounts = vec![*payer];
+    for meta in instructions.iter().flat_map(|ix| &ix.accounts) {
+        if meta.is_writable && !accounts.contains(&meta.pubkey) {
+            accounts.push(meta.pubkey);
+        }
+    }
+    accounts
+}
+
+async fn simulate_limit(rpc_client: &RpcClient, payer: &Pubkey, instructions: &[Instruction]) -> Option<u32> {
+    // Simulate with the limit instruction in place so its own cost is counted
+    let mut simulated = vec![ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT)];
+    simulated.extend_from_slice(instructions);
+    let transaction = Transaction::new_unsigned(Message::new(&simulated, Some(payer)));
+
+    let result = rpc_client
+        .simulate_transaction_with_config(
+            &transaction,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .ok()?
+        .value;
+
+    if result.err.is_some() {
+        return None;
+    }
+    result.units_consumed.map(with_margin)
+}
+
+fn report(class: TxClass, limit: Option<u32>, simulated: bool, price: u64, payer: &Pubkey, instructions: &[Instruction]) {
+    if !is_verbose() {
+        return;
+    }
+
+    let signatures = Message::new(instructions, Some(payer)).header.num_required_signatures as u64;
+    match limit {
+        Some(limit) => {
+            let max_fee = signatures * LAMPORTS_PER_SIGNATURE
+                + (limit as u64 * price).div_ceil(1_000_000);
+            let source = if simulated { "" } else { " (simulation unavailable, fixed limit)" };
+            println!("  ↳ {class}: compute limit {limit} CU{source}, max fee {}", format_sol(max_fee));
+        }
+        None => println!("  ↳ {class}: simulation unavailable, using the default compute limit"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Nothing listens here, so every simulation fails fast
+    fn unreachable_rpc() -> RpcClient {
+        RpcClient::new("http://127.0.0.1:1".to_string())
+    }
+
+    fn no_priority_fee() -> FeeSettings {
+        FeeSettings::from_config(&ProjectConfig::default(), "localhost")
+    }
+
+    #[tokio::test]
+    async fn deploy_limits_are_measured_every_time() {
+        let payer = Pubkey::new_unique();
+
+        compute_budget_for(&unreachable_rpc(), &no_priority_fee(), TxClass::Deploy, &payer, &[]).await;
+        compute_budget_for(&unreachable_rpc(), &no_priority_fee(), TxClass::Write, &payer, &[]).await;
+
+        let limits = learned_limits().lock().unwrap();
+        assert!(!limits.contains_key(&TxClass::Deploy));
+        assert_eq!(limits.get(&TxClass::Write), Some(&Some(WRITE_COMPUTE_UNIT_LIMIT)));
+    }
+
+    #[test]
+    fn only_bulk_and_loader_classes_have_a_fallback() {
+        assert_eq!(TxClass::Write.fallback_limit(), Some(WRITE_COMPUTE_UNIT_LIMIT));
+        assert_eq!(TxClass::Deploy.fallback_limit(), Some(DEPLOY_COMPUTE_UNIT_LIMIT));
+        assert_eq!(TxClass::Upgrade.fallback_limit(), Some(DEPLOY_COMPUTE_UNIT_LIMIT));
+        for class in [TxClass::CreateBuffer, TxClass::Extend, TxClass::SetAuthority, TxClass::Transfer, TxClass::Close] {
+            assert_eq!(class.fallback_limit(), None, "{class}");
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_simulation_falls_back_to_a_fixed_limit() {
+        let payer = Pubkey::new_unique();
+        let config = ProjectConfig { priority_fee: Some(PriorityFee::Fixed(5_000)), ..Default::default() };
+        let fees = FeeSettings::from_config(&config, "localhost");
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[2, 0, 0, 0],
+            vec![solana_sdk::instruction::AccountMeta::new(payer, true)],
+        );
+
+        for (class, limit) in [
+            (TxClass::Write, Some(WRITE_COMPUTE_UNIT_LIMIT)),
+            (TxClass::Upgrade, Some(DEPLOY_COMPUTE_UNIT_LIMIT)),
+            (TxClass::Transfer, None),
+        ] {
+            let instructions =
+                with_compute_budget_for(&unreachable_rpc(), &fees, class, &payer, std::slice::from_ref(&instruction)).await;
+
+            let mut expected = Vec::new();
+            expected.extend(limit.map(ComputeBudgetInstruction::set_compute_unit_limit));
+            expected.push(ComputeBudgetInstruction::set_compute_unit_price(5_000));
+            expected.push(instruction.clone());
+            assert_eq!(instructions, expected, "{class}");
+        }
+    }
+}
@@ -1,33 +1,514 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
-use solana_sdk::commitment_config::CommitmentConfig;
-use solana_sdk::{signature::Keypair, pubkey::Pubkey};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{signature::{Keypair, Signer}, pubkey::Pubkey};
+use std::collections::BTreeMap;
 use std::str::FromStr;
 use std::fs;
 use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+use crate::compute::PriorityFee;
 
 const SHIELD_DIR: &str = ".shield";
 const DEPLOYER_FILE: &str = "deployer.json";
 const STATE_FILE: &str = "state.json";
+const LOGS_DIR: &str = "logs";
+const VAULT_DIR: &str = "vault";
+const CONFIG_FILE: &str = "config.toml";
+const PROPOSALS_DIR: &str = "proposals";
+const PROGRAMS_DIR: &str = "programs";
+const PENDING_PROGRAMS_DIR: &str = "programs/pending";
+const BACKUPS_DIR: &str = "backups";
+const SPILL_DIR: &str = "spill";
+const AUTHORITIES_DIR: &str = "authorities";
+const PLANS_DIR: &str = "plans";
+const SCHEDULED_DIR: &str = "scheduled";
+const STATE_BACKUPS_DIR: &str = "state-backups";
+const RETIRED_DIR: &str = "retired";
+const ARTIFACTS_DIR: &str = "artifacts";
+
+/// Entries under `.shield/` holding private keys, which must never reach git
+const SECRET_ENTRIES: &[&str] = &[
+    DEPLOYER_FILE,
+    VAULT_DIR,
+    SPILL_DIR,
+    AUTHORITIES_DIR,
+    RETIRED_DIR,
+    PROGRAMS_DIR,
+];
+
+/// Deployer secret (base58 or JSON byte array) that overrides `deployer.json`
+pub const DEPLOYER_KEY_ENV: &str = "SHIELD_DEPLOYER_KEY";
+
+/// Previous versions of state.json kept when `state_backups` is unset
+const DEFAULT_STATE_BACKUPS: u64 = 10;
+
+/// A previous version of state.json under `.shield/state-backups/`
+pub struct StateBackup {
+    pub path: PathBuf,
+    pub taken_at: i64,
+    /// None when the file no longer parses
+    pub programs: Option<usize>,
+    pub operations: Option<usize>,
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct DeployerKeypair {
     pub keypair: Vec<u8>,
 }
 
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize, Default, Clone)]
 pub struct ProjectState {
     pub network: String,
     pub deployed_programs: Vec<DeployedProgram>,
     pub last_balance: u64,
+    #[serde(default)]
+    pub operations: Vec<OperationRecord>,
+    #[serde(default)]
+    pub buffers: Vec<BufferRecord>,
+    /// When `backup` or `export-deployer` last wrote a copy of the keys
+    #[serde(default)]
+    pub last_backup_at: Option<i64>,
+    /// Deployer keypair location outside `.shield/`, set by `rotate --outfile`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployer_file: Option<PathBuf>,
+    /// A fresh deploy that has created its buffer but not landed yet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_deploy: Option<PendingDeploy>,
+    /// Programs of other clusters, set aside by `--switch-network`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub parked_programs: BTreeMap<String, Vec<DeployedProgram>>,
+    /// Durable nonce accounts created by `nonce create`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nonce_accounts: Vec<NonceRecord>,
+    /// Set by `load_state`, so a save keeps what other processes wrote since
+    #[serde(skip)]
+    pub(crate) loaded: Option<LoadedState>,
+}
+
+/// What state.json held when a [`ProjectState`] was loaded
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct LoadedState {
+    operations: usize,
+    last_balance: u64,
 }
 
+impl ProjectState {
+    /// Latest record of `program_id`; earlier ones are superseded deploys
+    pub fn program(&self, program_id: &str) -> Option<&DeployedProgram> {
+        self.deployed_programs.iter().rev().find(|p| p.program_id == program_id)
+    }
+
+    /// Mutable [`ProjectState::program`]
+    pub fn program_mut(&mut self, program_id: &str) -> Option<&mut DeployedProgram> {
+        self.deployed_programs.iter_mut().rev().find(|p| p.program_id == program_id)
+    }
+
+    /// Most recently recorded buffer written for `program_id`
+    pub fn latest_buffer(&self, program_id: &str) -> Option<String> {
+        self.buffers
+            .iter()
+            .rev()
+            .find(|b| b.program_id.as_deref() == Some(program_id))
+            .map(|b| b.address.clone())
+    }
+
+    /// Make `network` current, parking programs recorded on any other cluster
+    ///
+    /// Returns how many programs were parked and how many parked on
+    /// `network` were brought back.
+    pub fn switch_network(&mut self, network: &str) -> (usize, usize) {
+        let previous = std::mem::replace(&mut self.network, network.to_string());
+        let mut hidden = 0;
+        for program in std::mem::take(&mut self.deployed_programs) {
+            let cluster = program.cluster.clone().unwrap_or_else(|| previous.clone());
+            if cluster == network {
+                self.deployed_programs.push(program);
+            } else {
+                hidden += 1;
+                self.parked_programs.entry(cluster).or_default().push(program);
+            }
+        }
+        let restored = self.parked_programs.remove(network).unwrap_or_default();
+        let restored_count = restored.len();
+        self.deployed_programs.extend(restored);
+        (hidden, restored_count)
+    }
+}
+
+/// Where an unfinished `deploy` stands, so the next run can pick it up
+///
+/// The program keypair stays in `.shield/programs/pending/`, never in
+/// state.json. How much of the buffer is written is read back from the
+/// buffer itself, which stays accurate however the writes were interrupted.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PendingDeploy {
+    pub program_id: String,
+    pub buffer: String,
+    /// SHA-256 of the artifact being deployed
+    pub artifact_hash: String,
+    pub size: usize,
+    pub started_at: i64,
+    /// ProgramData capacity chosen for the deploy
+    #[serde(default)]
+    pub max_data_len: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Init,
+    Fund,
+    Withdraw,
+    Deploy,
+    Upgrade,
+    Rotate,
+    TransferAuthority,
+    Finalize,
+    WriteBuffer,
+    PrepareUpgrade,
+    PublishMetadata,
+    MultisigPropose,
+    SpillCompress,
+    ImportProgram,
+    RemoveProgram,
+    CloseBuffer,
+    CreateNonce,
+    CloseNonce,
+}
+
+/// A single operation performed by the tool
+///
+/// Only deployer-side data is recorded. Funding wallets and withdraw
+/// recipients are never written here, so the state file cannot link them.
 #[derive(Serialize, Deserialize, Clone)]
+pub struct OperationRecord {
+    pub kind: OperationKind,
+    pub timestamp: i64,
+    #[serde(default)]
+    pub program_id: Option<String>,
+    #[serde(default)]
+    pub amount_lamports: Option<u64>,
+    #[serde(default)]
+    pub signatures: Vec<String>,
+    /// Lamports the deployer spent on the operation (fees and rent)
+    #[serde(default)]
+    pub cost_lamports: Option<u64>,
+    #[serde(default)]
+    pub note: Option<String>,
+    /// How a `fund` operation moved SOL to the deployer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub funding_method: Option<FundingMethod>,
+    /// Cluster the transactions were sent to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster: Option<String>,
+    /// Buffer a deploy or upgrade was written through
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer: Option<String>,
+    /// Buffer creation and write signatures, in the order they were sent
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub buffer_signatures: Vec<String>,
+    /// Measured spend of a deploy or upgrade
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spend: Option<SpendRecord>,
+}
+
+/// What a deploy or upgrade actually cost, from balances and confirmed transactions
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+pub struct SpendRecord {
+    pub balance_before: u64,
+    pub balance_after: u64,
+    /// Lamports held by the ProgramData account after the final transaction
+    pub program_data_rent: u64,
+    /// Buffer rent the final transaction returned (to the deployer or the spill account)
+    pub buffer_refund: u64,
+    /// Fees of every transaction sent, priority fees included
+    pub transaction_fees: u64,
+    pub priority_fees: u64,
+    pub transactions: usize,
+    /// Transactions the RPC had no metadata for, left out of the fees
+    #[serde(default)]
+    pub unaccounted_transactions: usize,
+}
+
+impl SpendRecord {
+    /// Net change of the deployer balance, negative when it went down
+    pub fn balance_change(&self) -> i128 {
+        self.balance_after as i128 - self.balance_before as i128
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum FundingMethod {
+    PrivacyCash,
+    /// Faucet airdrop on localnet or devnet; links nothing but is not private either
+    Airdrop,
+}
+
+impl OperationRecord {
+    /// A record of `kind` happening now on `network`
+    pub fn new(kind: OperationKind, network: &str) -> Self {
+        Self {
+            kind,
+            timestamp: chrono::Utc::now().timestamp(),
+            program_id: None,
+            amount_lamports: None,
+            signatures: Vec::new(),
+            cost_lamports: None,
+            note: None,
+            funding_method: None,
+            cluster: Some(network.to_string()),
+            buffer: None,
+            buffer_signatures: Vec::new(),
+            spend: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
 pub struct DeployedProgram {
     pub program_id: String,
     pub deployed_at: i64,
     pub last_upgraded: Option<i64>,
+    /// Artifact name (the .so file stem) the program was deployed from
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Cluster the program was deployed to
+    #[serde(default)]
+    pub cluster: Option<String>,
+    /// Last known upgrade authority, refreshed by commands that change it
+    #[serde(default)]
+    pub authority: Option<String>,
+    #[serde(default)]
+    pub immutable: bool,
+    /// Slot the current bytecode was deployed at, when recovered from chain
+    #[serde(default)]
+    pub deployed_slot: Option<u64>,
+    /// Bytecode size in bytes
+    #[serde(default)]
+    pub size: Option<usize>,
+    /// ProgramData capacity reserved at deploy; larger upgrades need an extend
+    #[serde(default)]
+    pub max_data_len: Option<usize>,
+    /// SHA-256 of the bytecode last deployed or acknowledged, watched by `monitor`
+    #[serde(default)]
+    pub bytecode_hash: Option<String>,
+    /// SHA-256 of the on-chain bytecode, recorded by `verify` on a match
+    #[serde(default)]
+    pub verified_hash: Option<String>,
+    #[serde(default)]
+    pub verified_at: Option<i64>,
+    /// Anchor IDL account published by `shield-deploy idl`
+    #[serde(default)]
+    pub idl_address: Option<String>,
+    /// SHA-256 of the last published IDL JSON
+    #[serde(default)]
+    pub idl_hash: Option<String>,
+    /// Upgrade authority is a dedicated key under `.shield/authorities/`
+    /// rather than the shared deployer
+    #[serde(default)]
+    pub isolated_authority: bool,
+    /// Loader buffer holding the program's published security.txt
+    #[serde(default)]
+    pub metadata_address: Option<String>,
+    /// Provenance of the last successful `verify --verifiable` run
+    #[serde(default)]
+    pub verified_build: Option<VerifiedBuild>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct VerifiedBuild {
+    pub commit: String,
+    pub hash: String,
+    pub toolchain: String,
+    pub repo_url: Option<String>,
+    pub verified_at: i64,
+}
+
+/// Lifecycle of a loader buffer written by this project
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum BufferStatus {
+    /// Created but not fully written, resumable by `write-buffer`
+    Writing,
+    /// Fully written and still owned by the deployer
+    Ready,
+    /// Buffer authority handed to another address (e.g. a multisig)
+    HandedOff,
+    /// Consumed by a deploy or upgrade
+    Consumed,
+    /// Closed and its rent reclaimed
+    Closed,
+}
+
+/// Why a buffer was created
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum BufferPurpose {
+    /// Fresh deploy
+    Deploy,
+    /// Upgrade of a deployed program, immediate or scheduled
+    Upgrade,
+    /// Written ahead of time by `write-buffer` or `prepare-upgrade`
+    Prepared,
+    /// Found on-chain by `recover`
+    Recovered,
+}
+
+/// A durable nonce account owned by the deployer
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NonceRecord {
+    pub address: String,
+    /// Cluster the account lives on
+    pub network: String,
+    pub created_at: i64,
+    /// Rent locked in the account, returned by `nonce close`
+    pub lamports: u64,
+}
+
+/// A loader buffer created by this project
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BufferRecord {
+    pub address: String,
+    pub status: BufferStatus,
+    pub created_at: i64,
+    /// Artifact name (the .so file stem) written into the buffer
+    #[serde(default)]
+    pub name: Option<String>,
+    /// SHA-256 of the artifact, used to match a resumed write
+    pub artifact_hash: String,
+    pub size: usize,
+    /// Current buffer authority
+    pub authority: String,
+    /// Program the buffer is intended for, when known
+    #[serde(default)]
+    pub program_id: Option<String>,
+    #[serde(default)]
+    pub purpose: Option<BufferPurpose>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanStatus {
+    Scheduled,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// When a scheduled upgrade may be sent
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum UpgradeTarget {
+    /// Unix time
+    Time(i64),
+    Slot(u64),
+}
+
+/// An upgrade whose buffer is written, waiting for its target
+///
+/// Written by `upgrade --at`/`--at-slot` once the buffer is ready, so
+/// `upgrade --resume` can send it after a crash or Ctrl-C.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScheduledUpgrade {
+    pub program_id: String,
+    pub buffer: String,
+    /// Receives the buffer refund
+    pub spill: String,
+    /// The spill key is ephemeral and gets compressed into the pool
+    #[serde(default)]
+    pub spill_compressed: bool,
+    pub target: UpgradeTarget,
+    /// SHA-256 of the program with trailing zeros stripped, as recorded on deploy
+    pub bytecode_hash: String,
+    pub size: usize,
+    pub created_at: i64,
+}
+
+/// A decompression from a vault to the deployer, waiting out the privacy delay
+///
+/// Written by `fund --detach` before the worker starts, and updated by the
+/// worker as it goes, so `status`, `fund --resume` and `fund cancel` can
+/// all see where it stands.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FundPlan {
+    /// The vault's public key, which also names the plan
+    pub vault: String,
+    /// Lamports shielded in the vault for this plan
+    pub amount_lamports: u64,
+    /// Hold back a random remainder when decompressing
+    #[serde(default)]
+    pub perturb: bool,
+    pub created_at: i64,
+    /// Earliest time the decompression may run
+    pub execute_after: i64,
+    pub status: PlanStatus,
+    #[serde(default)]
+    pub worker_pid: Option<u32>,
+    /// Last time the worker reported in
+    #[serde(default)]
+    pub heartbeat: Option<i64>,
+    #[serde(default)]
+    pub signature: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl FundPlan {
+    /// Workers report every 30s; two missed heartbeats means the worker is gone
+    pub fn worker_alive(&self) -> bool {
+        matches!(self.status, PlanStatus::Scheduled | PlanStatus::Running)
+            && self.heartbeat.is_some_and(|beat| chrono::Utc::now().timestamp() - beat < 90)
+    }
+
+    /// Not yet completed or cancelled
+    pub fn is_open(&self) -> bool {
+        matches!(self.status, PlanStatus::Scheduled | PlanStatus::Running | PlanStatus::Failed)
+    }
+}
+
+/// Held for a plan read-modify-write; removed on drop
+struct PlanLock(PathBuf);
+
+impl PlanLock {
+    /// Longest wait for another process to finish with the plan
+    const WAIT: std::time::Duration = std::time::Duration::from_secs(5);
+    /// A lock this old was left by a process that died holding it
+    const STALE: std::time::Duration = std::time::Duration::from_secs(30);
+
+    async fn acquire(path: PathBuf) -> Result<Self> {
+        let started = std::time::Instant::now();
+        loop {
+            match fs::File::options().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Self(path)),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .is_ok_and(|modified| modified.elapsed().unwrap_or_default() > Self::STALE);
+                    if stale {
+                        let _ = fs::remove_file(&path);
+                    } else if started.elapsed() > Self::WAIT {
+                        anyhow::bail!(
+                            "{} is held by another shield-deploy process.\n\
+                            If none is running, remove it and try again.",
+                            path.display()
+                        );
+                    } else {
+                        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                    }
+                }
+                Err(e) => return Err(e).with_context(|| format!("Failed to create {}", path.display())),
+            }
+        }
+    }
+}
+
+impl Drop for PlanLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
 }
 
 pub struct Config {
@@ -40,9 +521,14 @@ impl Config {
         Ok(Self { shield_dir })
     }
 
+    /// State rooted somewhere other than ./.shield, for scratch runs
+    pub fn at(shield_dir: PathBuf) -> Self {
+        Self { shield_dir }
+    }
+
     /// Validate that all deployed programs still exist and are accessible
     #[allow(dead_code)]
-    pub fn validate_deployed_programs(&self) -> Result<Vec<String>> {
+    pub async fn validate_deployed_programs(&self) -> Result<Vec<String>> {
         let state = self.load_state()?;
         let mut warnings = Vec::new();
         
@@ -50,13 +536,13 @@ impl Config {
             let rpc_url = crate::utils::get_rpc_url()?;
             let rpc_client = RpcClient::new_with_commitment(
                 rpc_url,
-                CommitmentConfig::confirmed(),
+                crate::utils::commitment(),
             );
             
             for program in &state.deployed_programs {
                 let program_id = Pubkey::from_str(&program.program_id)?;
                 
-                match rpc_client.get_account(&program_id) {
+                match rpc_client.get_account(&program_id).await {
                     Ok(_) => {
                         // Program exists - good
                     }
@@ -81,44 +567,553 @@ impl Config {
         Ok(())
     }
 
+    pub fn shield_dir(&self) -> &Path {
+        &self.shield_dir
+    }
+
+    /// Deployer keypair file: `.shield/deployer.json` unless `rotate --outfile` moved it
     pub fn deployer_path(&self) -> PathBuf {
+        self.load_state()
+            .ok()
+            .and_then(|state| state.deployer_file)
+            .unwrap_or_else(|| self.default_deployer_path())
+    }
+
+    pub fn default_deployer_path(&self) -> PathBuf {
         self.shield_dir.join(DEPLOYER_FILE)
     }
 
+    /// Old deployer keys moved aside when the active key leaves `.shield/`
+    pub fn retired_dir(&self) -> PathBuf {
+        self.shield_dir.join(RETIRED_DIR)
+    }
+
     pub fn state_path(&self) -> PathBuf {
         self.shield_dir.join(STATE_FILE)
     }
 
+    pub fn config_path(&self) -> PathBuf {
+        self.shield_dir.join(CONFIG_FILE)
+    }
+
+    pub fn logs_dir(&self) -> PathBuf {
+        self.shield_dir.join(LOGS_DIR)
+    }
+
+    pub fn proposals_dir(&self) -> PathBuf {
+        self.shield_dir.join(PROPOSALS_DIR)
+    }
+
+    /// Builds kept for provenance checks, e.g. the .so behind a prepared buffer
+    pub fn artifacts_dir(&self) -> PathBuf {
+        self.shield_dir.join(ARTIFACTS_DIR)
+    }
+
+    pub fn plans_dir(&self) -> PathBuf {
+        self.shield_dir.join(PLANS_DIR)
+    }
+
+    pub fn save_fund_plan(&self, plan: &FundPlan) -> Result<()> {
+        fs::create_dir_all(self.plans_dir())
+            .context("Failed to create plans directory")?;
+
+        let path = self.plans_dir().join(format!("{}.json", plan.vault));
+        fs::write(&path, serde_json::to_string_pretty(plan)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn load_fund_plan(&self, vault: &str) -> Result<FundPlan> {
+        let path = self.plans_dir().join(format!("{vault}.json"));
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("No funding plan {vault}"))?;
+        serde_json::from_str(&json).with_context(|| format!("{} is corrupt", path.display()))
+    }
+
+    /// Change a funding plan while holding its lock file
+    ///
+    /// Workers, `fund --resume` and `fund cancel` all rewrite plans; reading
+    /// and saving under one lock keeps a status one of them just set from
+    /// being overwritten. The plan is saved only if `change` modified it.
+    pub async fn update_fund_plan<T>(
+        &self,
+        vault: &str,
+        change: impl FnOnce(&mut FundPlan) -> Result<T>,
+    ) -> Result<T> {
+        fs::create_dir_all(self.plans_dir()).context("Failed to create plans directory")?;
+        let _lock = PlanLock::acquire(self.plans_dir().join(format!("{vault}.lock"))).await?;
+        let mut plan = self.load_fund_plan(vault)?;
+        let before = serde_json::to_string(&plan)?;
+        let result = change(&mut plan)?;
+        if serde_json::to_string(&plan)? != before {
+            self.save_fund_plan(&plan)?;
+        }
+        Ok(result)
+    }
+
+    /// Every funding plan, oldest first
+    pub fn load_fund_plans(&self) -> Result<Vec<FundPlan>> {
+        if !self.plans_dir().exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut plans = Vec::new();
+        for entry in fs::read_dir(self.plans_dir())? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            let json = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            plans.push(serde_json::from_str::<FundPlan>(&json)
+                .with_context(|| format!("{} is corrupt", path.display()))?);
+        }
+        plans.sort_by_key(|p| p.created_at);
+        Ok(plans)
+    }
+
+    pub fn scheduled_dir(&self) -> PathBuf {
+        self.shield_dir.join(SCHEDULED_DIR)
+    }
+
+    pub fn save_scheduled_upgrade(&self, plan: &ScheduledUpgrade) -> Result<()> {
+        fs::create_dir_all(self.scheduled_dir())
+            .context("Failed to create scheduled upgrades directory")?;
+
+        let path = self.scheduled_dir().join(format!("{}.json", plan.program_id));
+        fs::write(&path, serde_json::to_string_pretty(plan)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn load_scheduled_upgrade(&self, program_id: &str) -> Result<Option<ScheduledUpgrade>> {
+        let path = self.scheduled_dir().join(format!("{program_id}.json"));
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&json)
+            .map(Some)
+            .with_context(|| format!("{} is corrupt", path.display()))
+    }
+
+    /// Every scheduled upgrade still waiting to be sent
+    pub fn load_scheduled_upgrades(&self) -> Result<Vec<ScheduledUpgrade>> {
+        if !self.scheduled_dir().exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut plans = Vec::new();
+        for entry in fs::read_dir(self.scheduled_dir())? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            let json = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            plans.push(serde_json::from_str::<ScheduledUpgrade>(&json)
+                .with_context(|| format!("{} is corrupt", path.display()))?);
+        }
+        plans.sort_by_key(|p| p.created_at);
+        Ok(plans)
+    }
+
+    pub fn remove_scheduled_upgrade(&self, program_id: &str) -> Result<()> {
+        let path = self.scheduled_dir().join(format!("{program_id}.json"));
+        if path.exists() {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Default location for `backup` archives
+    pub fn backups_dir(&self) -> PathBuf {
+        self.shield_dir.join(BACKUPS_DIR)
+    }
+
     pub fn deployer_exists(&self) -> bool {
-        self.deployer_path().exists()
+        Self::deployer_from_env() || self.deployer_path().exists()
+    }
+
+    /// The deployer comes from `SHIELD_DEPLOYER_KEY` rather than a file
+    pub fn deployer_from_env() -> bool {
+        std::env::var_os(DEPLOYER_KEY_ENV).is_some_and(|v| !v.is_empty())
+    }
+
+    /// Refuse operations that would write a deployer over an env-provided one
+    pub fn ensure_deployer_on_disk(&self, action: &str) -> Result<()> {
+        if Self::deployer_from_env() {
+            anyhow::bail!(
+                "The deployer is provided by {DEPLOYER_KEY_ENV}, so `{action}` would leave it out of sync.\n\
+                Manage that key in your secret store, or unset {DEPLOYER_KEY_ENV} to use .shield/deployer.json."
+            );
+        }
+        Ok(())
     }
 
     pub fn save_deployer(&self, keypair: &Keypair) -> Result<()> {
         self.ensure_shield_dir()?;
-        
+        self.save_deployer_to(keypair, &self.deployer_path())
+    }
+
+    /// Write a deployer keypair to `path` (0600) without changing which file is active
+    pub fn save_deployer_to(&self, keypair: &Keypair, path: &Path) -> Result<()> {
         let deployer_data = DeployerKeypair {
             keypair: keypair.to_bytes().to_vec(),
         };
         
         let json = serde_json::to_string_pretty(&deployer_data)?;
-        fs::write(self.deployer_path(), json)
-            .context("Failed to write deployer keypair")?;
+        write_secret_file(path, &json)
+            .with_context(|| format!("Failed to write deployer keypair to {}", path.display()))?;
         
         Ok(())
     }
 
     pub fn load_deployer(&self) -> Result<Keypair> {
-        let json = fs::read_to_string(self.deployer_path())
-            .context("Failed to read deployer keypair")?;
+        if Self::deployer_from_env() {
+            return load_deployer_from_env();
+        }
+
+        let json = Zeroizing::new(
+            fs::read_to_string(self.deployer_path())
+                .context("Failed to read deployer keypair")?,
+        );
         
         let data: DeployerKeypair = serde_json::from_str(&json)?;
+        let bytes = Zeroizing::new(data.keypair);
         
-        let keypair = Keypair::from_bytes(&data.keypair)
+        let keypair = Keypair::from_bytes(&bytes)
             .map_err(|e| anyhow::anyhow!("Invalid keypair: {e}"))?;
         
+        self.warn_rotation_policy();
+        
         Ok(keypair)
     }
 
+    /// Note that the keys were just backed up or exported
+    pub fn record_backup(&self) -> Result<()> {
+        if !self.state_path().exists() {
+            return Ok(());
+        }
+        let mut state = self.load_state()?;
+        state.last_backup_at = Some(chrono::Utc::now().timestamp());
+        self.save_state(&state)
+    }
+
+    /// Where the deployer stands against `rotate_after_days` / `rotate_after_operations`
+    pub fn rotation_status(&self) -> Result<Option<RotationStatus>> {
+        let project_config = ProjectConfig::load_for(self)?;
+        if project_config.rotate_after_days.is_none() && project_config.rotate_after_operations.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(RotationStatus::from_state(&self.load_state()?, &project_config)))
+    }
+
+    /// Refuse a spend operation when `enforce_rotation` is set and the policy is exceeded
+    pub fn check_rotation_policy(&self) -> Result<()> {
+        if !ProjectConfig::load_for(self)?.enforce_rotation.unwrap_or(false) {
+            return Ok(());
+        }
+        match self.rotation_status()? {
+            Some(status) if status.overdue() => anyhow::bail!(
+                "The deployer is past its rotation policy ({}).\n\
+                enforce_rotation is set. Run `shield-deploy rotate` first.",
+                status.summary()
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Printed to stderr once per run so scripted output stays clean
+    fn warn_rotation_policy(&self) {
+        static WARNED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+        if WARNED.swap(true, std::sync::atomic::Ordering::Relaxed) {
+            return;
+        }
+        let Ok(Some(status)) = self.rotation_status() else { return };
+
+        match status.severity() {
+            RotationSeverity::Ok => {}
+            RotationSeverity::DueSoon => {
+                eprintln!("\n Deployer rotation due soon ({})", status.summary());
+            }
+            RotationSeverity::Overdue => {
+                eprintln!("\n⚠️  Deployer rotation is overdue ({})", status.summary());
+                eprintln!("   Run `shield-deploy rotate`");
+            }
+            RotationSeverity::LongOverdue => {
+                eprintln!("\n⚠️  ⚠️  DEPLOYER ROTATION IS LONG OVERDUE ({})", status.summary());
+                eprintln!("   The rotation policy was exceeded twice over. Run `shield-deploy rotate` now");
+            }
+        }
+    }
+
+    pub fn vault_dir(&self) -> PathBuf {
+        self.shield_dir.join(VAULT_DIR)
+    }
+
+    /// Persist a vault key holding shielded funds, before any funds move to it
+    pub fn save_vault_key(&self, keypair: &Keypair) -> Result<PathBuf> {
+        fs::create_dir_all(self.vault_dir())
+            .context("Failed to create vault directory")?;
+
+        let data = DeployerKeypair {
+            keypair: keypair.to_bytes().to_vec(),
+        };
+        let path = self.vault_dir()
+            .join(keypair.pubkey().to_string())
+            .with_extension("json");
+
+        write_secret_file(&path, &serde_json::to_string_pretty(&data)?)
+            .context("Failed to write vault key")?;
+
+        Ok(path)
+    }
+
+    pub fn load_vault_keys(&self) -> Result<Vec<Keypair>> {
+        if !self.vault_dir().exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut keys = Vec::new();
+        for entry in fs::read_dir(self.vault_dir())? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+
+            let json = fs::read_to_string(&path)
+                .context("Failed to read vault key")?;
+            let data: DeployerKeypair = serde_json::from_str(&json)?;
+            let keypair = Keypair::from_bytes(&data.keypair)
+                .map_err(|e| anyhow::anyhow!("Invalid vault key {}: {e}", path.display()))?;
+            keys.push(keypair);
+        }
+
+        Ok(keys)
+    }
+
+    pub fn remove_vault_key(&self, pubkey: &Pubkey) -> Result<()> {
+        let path = self.vault_dir()
+            .join(pubkey.to_string())
+            .with_extension("json");
+        if path.exists() {
+            fs::remove_file(path).context("Failed to remove vault key")?;
+        }
+        Ok(())
+    }
+
+    /// Per-program upgrade authorities from `deploy --isolate-authority`
+    pub fn authorities_dir(&self) -> PathBuf {
+        self.shield_dir.join(AUTHORITIES_DIR)
+    }
+
+    fn program_authority_path(&self, program_id: &Pubkey, pending: bool) -> PathBuf {
+        let name = if pending {
+            format!("{program_id}.pending.json")
+        } else {
+            format!("{program_id}.json")
+        };
+        self.authorities_dir().join(name)
+    }
+
+    /// Persist a program's next authority before it takes over on chain
+    ///
+    /// It only replaces the current key once `commit_program_authority`
+    /// runs, so a failed transfer never loses the key still in control.
+    pub fn stage_program_authority(&self, program_id: &Pubkey, keypair: &Keypair) -> Result<PathBuf> {
+        fs::create_dir_all(self.authorities_dir())
+            .context("Failed to create authorities directory")?;
+
+        let data = DeployerKeypair {
+            keypair: keypair.to_bytes().to_vec(),
+        };
+        let path = self.program_authority_path(program_id, true);
+        write_secret_file(&path, &serde_json::to_string_pretty(&data)?)
+            .context("Failed to write program authority")?;
+
+        Ok(path)
+    }
+
+    pub fn commit_program_authority(&self, program_id: &Pubkey) -> Result<PathBuf> {
+        let path = self.program_authority_path(program_id, false);
+        fs::rename(self.program_authority_path(program_id, true), &path)
+            .context("Failed to save program authority")?;
+        Ok(path)
+    }
+
+    /// The dedicated authority of an isolated program, if it has one
+    pub fn load_program_authority(&self, program_id: &Pubkey) -> Result<Option<Keypair>> {
+        let path = self.program_authority_path(program_id, false);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let json = fs::read_to_string(&path)
+            .context("Failed to read program authority")?;
+        let data: DeployerKeypair = serde_json::from_str(&json)?;
+        let keypair = Keypair::from_bytes(&data.keypair)
+            .map_err(|e| anyhow::anyhow!("Invalid program authority {}: {e}", path.display()))?;
+
+        Ok(Some(keypair))
+    }
+
+    pub fn remove_program_authority(&self, program_id: &Pubkey) -> Result<()> {
+        let path = self.program_authority_path(program_id, false);
+        if path.exists() {
+            fs::remove_file(path).context("Failed to remove program authority")?;
+        }
+        Ok(())
+    }
+
+    /// Ephemeral keys receiving upgrade buffer refunds until they are compressed
+    pub fn spill_dir(&self) -> PathBuf {
+        self.shield_dir.join(SPILL_DIR)
+    }
+
+    pub fn save_spill_key(&self, keypair: &Keypair) -> Result<PathBuf> {
+        fs::create_dir_all(self.spill_dir())
+            .context("Failed to create spill directory")?;
+
+        let data = DeployerKeypair {
+            keypair: keypair.to_bytes().to_vec(),
+        };
+        let path = self.spill_dir()
+            .join(keypair.pubkey().to_string())
+            .with_extension("json");
+
+        write_secret_file(&path, &serde_json::to_string_pretty(&data)?)
+            .context("Failed to write spill key")?;
+
+        Ok(path)
+    }
+
+    pub fn load_spill_keys(&self) -> Result<Vec<Keypair>> {
+        if !self.spill_dir().exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut keys = Vec::new();
+        for entry in fs::read_dir(self.spill_dir())? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+
+            let json = fs::read_to_string(&path)
+                .context("Failed to read spill key")?;
+            let data: DeployerKeypair = serde_json::from_str(&json)?;
+            let keypair = Keypair::from_bytes(&data.keypair)
+                .map_err(|e| anyhow::anyhow!("Invalid spill key {}: {e}", path.display()))?;
+            keys.push(keypair);
+        }
+
+        Ok(keys)
+    }
+
+    pub fn remove_spill_key(&self, pubkey: &Pubkey) -> Result<()> {
+        let path = self.spill_dir()
+            .join(pubkey.to_string())
+            .with_extension("json");
+        if path.exists() {
+            fs::remove_file(path).context("Failed to remove spill key")?;
+        }
+        Ok(())
+    }
+
+    /// Vanity program keypairs from `grind`, waiting to be used by `deploy`
+    pub fn pending_programs_dir(&self) -> PathBuf {
+        self.shield_dir.join(PENDING_PROGRAMS_DIR)
+    }
+
+    pub fn save_pending_program_key(&self, keypair: &Keypair) -> Result<PathBuf> {
+        fs::create_dir_all(self.pending_programs_dir())
+            .context("Failed to create pending programs directory")?;
+
+        let data = DeployerKeypair {
+            keypair: keypair.to_bytes().to_vec(),
+        };
+        let path = self.pending_programs_dir()
+            .join(keypair.pubkey().to_string())
+            .with_extension("json");
+
+        write_secret_file(&path, &serde_json::to_string_pretty(&data)?)
+            .context("Failed to write program keypair")?;
+
+        Ok(path)
+    }
+
+    pub fn load_pending_program_keys(&self) -> Result<Vec<Keypair>> {
+        if !self.pending_programs_dir().exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut paths: Vec<PathBuf> = fs::read_dir(self.pending_programs_dir())?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("json"))
+            .collect();
+        paths.sort();
+
+        let mut keys = Vec::new();
+        for path in paths {
+            let json = fs::read_to_string(&path)
+                .context("Failed to read program keypair")?;
+            let data: DeployerKeypair = serde_json::from_str(&json)?;
+            let keypair = Keypair::from_bytes(&data.keypair)
+                .map_err(|e| anyhow::anyhow!("Invalid program keypair {}: {e}", path.display()))?;
+            keys.push(keypair);
+        }
+
+        Ok(keys)
+    }
+
+    /// Keypairs of deployed programs, kept for `keys sync`
+    pub fn programs_dir(&self) -> PathBuf {
+        self.shield_dir.join(PROGRAMS_DIR)
+    }
+
+    /// Move a pending program keypair to `programs/` once its deploy has landed
+    pub fn keep_program_key(&self, pubkey: &Pubkey) -> Result<()> {
+        let pending = self.pending_programs_dir()
+            .join(pubkey.to_string())
+            .with_extension("json");
+        if !pending.exists() {
+            return Ok(());
+        }
+        let kept = self.programs_dir()
+            .join(pubkey.to_string())
+            .with_extension("json");
+        fs::rename(&pending, &kept).context("Failed to move program keypair")
+    }
+
+    /// A deployed (or still pending) program's keypair, if this project has it
+    pub fn load_program_key(&self, pubkey: &Pubkey) -> Result<Option<Keypair>> {
+        let file = format!("{pubkey}.json");
+        let Some(path) = [self.programs_dir().join(&file), self.pending_programs_dir().join(&file)]
+            .into_iter()
+            .find(|path| path.exists())
+        else {
+            return Ok(None);
+        };
+
+        let json = fs::read_to_string(&path)
+            .context("Failed to read program keypair")?;
+        let data: DeployerKeypair = serde_json::from_str(&json)?;
+        let keypair = Keypair::from_bytes(&data.keypair)
+            .map_err(|e| anyhow::anyhow!("Invalid program keypair {}: {e}", path.display()))?;
+        Ok(Some(keypair))
+    }
+
+    pub fn remove_pending_program_key(&self, pubkey: &Pubkey) -> Result<()> {
+        let path = self.pending_programs_dir()
+            .join(pubkey.to_string())
+            .with_extension("json");
+        if path.exists() {
+            fs::remove_file(path).context("Failed to remove program keypair")?;
+        }
+        Ok(())
+    }
+
     pub fn load_state(&self) -> Result<ProjectState> {
         if !self.state_path().exists() {
             return Ok(ProjectState::default());
@@ -127,33 +1122,810 @@ impl Config {
         let json = fs::read_to_string(self.state_path())
             .context("Failed to read state")?;
         
-        let state: ProjectState = serde_json::from_str(&json)?;
+        let mut state: ProjectState = serde_json::from_str(&json)?;
+        state.loaded = Some(LoadedState {
+            operations: state.operations.len(),
+            last_balance: state.last_balance,
+        });
         Ok(state)
     }
 
+    /// Write `state`, keeping operations and a balance other processes saved
+    /// since it was loaded
     pub fn save_state(&self, state: &ProjectState) -> Result<()> {
         self.ensure_shield_dir()?;
         
-        let json = serde_json::to_string_pretty(state)?;
-        fs::write(self.state_path(), json)
+        let merged = self.merge_saved_state(state)?;
+        let json = serde_json::to_string_pretty(merged.as_ref().unwrap_or(state))?;
+        self.backup_state(&json)?;
+        // Renamed into place so a concurrent load never reads half a file
+        let partial = self.state_path().with_extension(format!("json.{}.tmp", std::process::id()));
+        fs::write(&partial, json)
+            .context("Failed to write state")?;
+        fs::rename(&partial, self.state_path())
             .context("Failed to write state")?;
         
         Ok(())
     }
 
+    /// `state` plus what was saved over it since it was loaded, if anything
+    ///
+    /// Commands hold their state across minutes of RPC calls while a detached
+    /// funding worker records its delivery; saving must not drop that record.
+    fn merge_saved_state(&self, state: &ProjectState) -> Result<Option<ProjectState>> {
+        let Some(loaded) = state.loaded else {
+            return Ok(None);
+        };
+        let Some(saved) = fs::read_to_string(self.state_path())
+            .ok()
+            .and_then(|json| serde_json::from_str::<ProjectState>(&json).ok())
+        else {
+            return Ok(None);
+        };
+
+        let ours = state
+            .operations
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        let theirs: Vec<OperationRecord> = saved
+            .operations
+            .get(loaded.operations..)
+            .unwrap_or_default()
+            .iter()
+            .filter(|op| serde_json::to_value(op).is_ok_and(|op| !ours.contains(&op)))
+            .cloned()
+            .collect();
+        let balance_moved = saved.last_balance != loaded.last_balance
+            && state.last_balance == loaded.last_balance;
+        if theirs.is_empty() && !balance_moved {
+            return Ok(None);
+        }
+
+        let mut merged = state.clone();
+        merged.operations.extend(theirs);
+        merged.operations.sort_by_key(|op| op.timestamp);
+        if balance_moved {
+            merged.last_balance = saved.last_balance;
+        }
+        Ok(Some(merged))
+    }
+
+    pub fn state_backups_dir(&self) -> PathBuf {
+        self.shield_dir.join(STATE_BACKUPS_DIR)
+    }
+
+    /// Keep the current state.json before it is replaced by `next`
+    fn backup_state(&self, next: &str) -> Result<()> {
+        let keep = ProjectConfig::load_for(self)
+            .ok()
+            .and_then(|c| c.state_backups)
+            .unwrap_or(DEFAULT_STATE_BACKUPS) as usize;
+        if keep == 0 {
+            return Ok(());
+        }
+        let Ok(current) = fs::read_to_string(self.state_path()) else {
+            return Ok(());
+        };
+        if current == next {
+            return Ok(());
+        }
+
+        fs::create_dir_all(self.state_backups_dir())
+            .context("Failed to create state backups directory")?;
+        let path = self.state_backups_dir()
+            .join(format!("state-{}.json", chrono::Utc::now().timestamp_millis()));
+        fs::write(&path, current)
+            .context("Failed to back up state")?;
+
+        for stale in self.list_state_backups()?.into_iter().skip(keep) {
+            fs::remove_file(&stale.path)
+                .with_context(|| format!("Failed to prune {}", stale.path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Saved versions of state.json, newest first
+    pub fn list_state_backups(&self) -> Result<Vec<StateBackup>> {
+        if !self.state_backups_dir().exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups = Vec::new();
+        for entry in fs::read_dir(self.state_backups_dir())? {
+            let path = entry?.path();
+            let Some(taken_at_ms) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_prefix("state-")?.strip_suffix(".json"))
+                .and_then(|ms| ms.parse::<i64>().ok())
+            else {
+                continue;
+            };
+            let state = fs::read_to_string(&path)
+                .ok()
+                .and_then(|json| serde_json::from_str::<ProjectState>(&json).ok());
+            backups.push(StateBackup {
+                path,
+                taken_at: taken_at_ms / 1000,
+                programs: state.as_ref().map(|s| s.deployed_programs.len()),
+                operations: state.as_ref().map(|s| s.operations.len()),
+            });
+        }
+        backups.sort_by_key(|b| std::cmp::Reverse(b.path.clone()));
+        Ok(backups)
+    }
+
+    /// Replace state.json with backup `index` from `list_state_backups`
+    ///
+    /// The state being replaced is itself backed up first.
+    pub fn restore_state_backup(&self, index: usize) -> Result<StateBackup> {
+        let backup = self.list_state_backups()?
+            .into_iter()
+            .nth(index)
+            .with_context(|| format!("No state backup #{index}"))?;
+
+        let json = fs::read_to_string(&backup.path)
+            .with_context(|| format!("Failed to read {}", backup.path.display()))?;
+        let state: ProjectState = serde_json::from_str(&json)
+            .with_context(|| format!("{} is not a valid state file", backup.path.display()))?;
+        self.save_state(&state)?;
+
+        Ok(backup)
+    }
+
+    /// Ask git whether any key file is tracked or staged
+    pub fn tracked_secrets(&self) -> GitSecrets {
+        // Ask git about the project's own work tree, wherever the process runs
+        let root = match self.shield_dir.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let inside_repo = match std::process::Command::new("git")
+            .args(["rev-parse", "--is-inside-work-tree"])
+            .current_dir(root)
+            .output()
+        {
+            Ok(output) => output.status.success() && output.stdout.starts_with(b"true"),
+            Err(_) => return GitSecrets::GitUnavailable,
+        };
+        if !inside_repo {
+            return GitSecrets::NotARepo;
+        }
+
+        let mut paths: Vec<PathBuf> = SECRET_ENTRIES.iter().map(|e| self.shield_dir.join(e)).collect();
+        if let Some(file) = self.load_state().ok().and_then(|s| s.deployer_file) {
+            paths.push(file);
+        }
+
+        // One call per path: git rejects the whole list if any path is outside the repo
+        let tracked: Vec<String> = paths
+            .iter()
+            .filter_map(|path| {
+                std::process::Command::new("git")
+                    .args(["ls-files", "--cached", "-z", "--"])
+                    .arg(path.strip_prefix(root).unwrap_or(path))
+                    .current_dir(root)
+                    .output()
+                    .ok()
+                    .filter(|output| output.status.success())
+            })
+            .flat_map(|output| {
+                output.stdout
+                    .split(|&b| b == 0)
+                    .filter(|name| !name.is_empty())
+                    .map(|name| String::from_utf8_lossy(name).to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        if tracked.is_empty() {
+            GitSecrets::Clean
+        } else {
+            GitSecrets::Tracked(tracked)
+        }
+    }
+
+    /// Refuse to go on while key files are tracked by git
+    ///
+    /// A no-op outside a git repository; without git installed it only notes
+    /// that the check was skipped.
+    pub fn check_git_secrets(&self, unsafe_ignore_git: bool) -> Result<()> {
+        match self.tracked_secrets() {
+            GitSecrets::NotARepo | GitSecrets::Clean => Ok(()),
+            GitSecrets::GitUnavailable => {
+                eprintln!("Note: git not found; skipped the check for committed keys");
+                Ok(())
+            }
+            GitSecrets::Tracked(files) if unsafe_ignore_git => {
+                eprintln!("\n⚠️  Key files are tracked by git (continuing because of --unsafe-ignore-git):");
+                for file in &files {
+                    eprintln!("   {file}");
+                }
+                Ok(())
+            }
+            GitSecrets::Tracked(files) => anyhow::bail!(
+                "Private keys are tracked by git:\n  {}\n\n\
+                Anyone with the repository can use them. To fix:\n\
+                1. git rm -r --cached .shield && git commit -m \"Stop tracking .shield\"\n\
+                2. Run `shield-deploy rotate`; the old key stays in git history\n\
+                Pass --unsafe-ignore-git to proceed anyway.",
+                files.join("\n  ")
+            ),
+        }
+    }
+
     pub fn add_gitignore(&self) -> Result<()> {
-        let gitignore_path = Path::new(".gitignore");
+        let gitignore_path = self.shield_dir
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(".gitignore");
         let shield_entry = ".shield/\n";
         
         if gitignore_path.exists() {
-            let content = fs::read_to_string(gitignore_path)?;
+            let content = fs::read_to_string(&gitignore_path)?;
             if !content.contains(".shield") {
-                fs::write(gitignore_path, format!("{content}{shield_entry}"))?;
+                fs::write(&gitignore_path, format!("{content}{shield_entry}"))?;
             }
         } else {
-            fs::write(gitignore_path, shield_entry)?;
+            fs::write(&gitignore_path, shield_entry)?;
         }
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Whether git has any of the project's key files
+pub enum GitSecrets {
+    /// Not inside a git work tree; nothing to check
+    NotARepo,
+    /// git isn't installed, so the check couldn't run
+    GitUnavailable,
+    Clean,
+    /// Tracked or staged key files, as git lists them
+    Tracked(Vec<String>),
+}
+
+/// Write a file readable only by the current user
+pub fn write_secret_file(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    fs::write(path, contents)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(())
+}
+
+/// Parse `SHIELD_DEPLOYER_KEY` as a base58 secret or a JSON byte array
+///
+/// The key is never written to disk and the decoded copies are zeroized on drop.
+fn load_deployer_from_env() -> Result<Keypair> {
+    let raw = Zeroizing::new(
+        std::env::var(DEPLOYER_KEY_ENV)
+            .with_context(|| format!("{DEPLOYER_KEY_ENV} is not valid UTF-8"))?,
+    );
+    let raw = raw.trim();
+
+    let bytes: Zeroizing<Vec<u8>> = if raw.starts_with('[') {
+        Zeroizing::new(
+            serde_json::from_str(raw)
+                .with_context(|| format!("{DEPLOYER_KEY_ENV} is not a JSON byte array"))?,
+        )
+    } else {
+        Zeroizing::new(
+            bs58::decode(raw)
+                .into_vec()
+                .with_context(|| format!("{DEPLOYER_KEY_ENV} is not valid base58"))?,
+        )
+    };
+
+    Keypair::from_bytes(&bytes)
+        .map_err(|e| anyhow::anyhow!("Invalid keypair in {DEPLOYER_KEY_ENV}: {e}"))
+}
+
+/// Value types accepted by config keys, validated before anything is written
+#[derive(Clone, Copy)]
+pub enum ConfigValueKind {
+    Url,
+    Proxy,
+    PriorityFee,
+    Duration,
+    Integer,
+    Text,
+    Bool,
+    Contacts,
+    NetworkAmounts,
+    Enum(&'static [&'static str]),
+}
+
+pub struct ConfigKey {
+    pub name: &'static str,
+    pub kind: ConfigValueKind,
+    pub description: &'static str,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum RotationSeverity {
+    Ok,
+    DueSoon,
+    Overdue,
+    /// Twice past the policy
+    LongOverdue,
+}
+
+/// How far the deployer is into its rotation policy, from the operation history
+pub struct RotationStatus {
+    /// Time since the last init or rotate record; `None` when history has neither
+    pub key_age_secs: Option<i64>,
+    /// Operations recorded since then
+    pub operations: u64,
+    pub max_days: Option<u64>,
+    pub max_operations: Option<u64>,
+}
+
+impl RotationStatus {
+    pub fn from_state(state: &ProjectState, config: &ProjectConfig) -> Self {
+        let since = state
+            .operations
+            .iter()
+            .rposition(|op| matches!(op.kind, OperationKind::Init | OperationKind::Rotate));
+        let key_age_secs = since.map(|i| chrono::Utc::now().timestamp() - state.operations[i].timestamp);
+        let operations = state.operations.len() - since.map_or(0, |i| i + 1);
+
+        Self {
+            key_age_secs,
+            operations: operations as u64,
+            max_days: config.rotate_after_days,
+            max_operations: config.rotate_after_operations,
+        }
+    }
+
+    pub fn days_left(&self) -> Option<i64> {
+        let max_secs = self.max_days? as i64 * 86_400;
+        Some((max_secs - self.key_age_secs?).div_euclid(86_400))
+    }
+
+    pub fn operations_left(&self) -> Option<i64> {
+        Some(self.max_operations? as i64 - self.operations as i64)
+    }
+
+    pub fn overdue(&self) -> bool {
+        self.severity() >= RotationSeverity::Overdue
+    }
+
+    pub fn severity(&self) -> RotationSeverity {
+        let by_days = match (self.key_age_secs, self.max_days) {
+            (Some(age), Some(max)) => Self::grade(age.max(0) as u64, max * 86_400, 3 * 86_400),
+            _ => RotationSeverity::Ok,
+        };
+        let by_operations = match self.max_operations {
+            Some(max) => Self::grade(self.operations, max, (max / 10).max(1)),
+            None => RotationSeverity::Ok,
+        };
+        by_days.max(by_operations)
+    }
+
+    fn grade(used: u64, max: u64, warn_within: u64) -> RotationSeverity {
+        if used >= max.saturating_mul(2) {
+            RotationSeverity::LongOverdue
+        } else if used >= max {
+            RotationSeverity::Overdue
+        } else if max - used <= warn_within {
+            RotationSeverity::DueSoon
+        } else {
+            RotationSeverity::Ok
+        }
+    }
+
+    /// e.g. `12 days left, 3 operations over`
+    pub fn summary(&self) -> String {
+        let describe = |left: i64, unit: &str| {
+            if left >= 0 {
+                format!("{left} {unit} left")
+            } else {
+                format!("{} {unit} over", -left)
+            }
+        };
+
+        let mut parts = Vec::new();
+        match (self.days_left(), self.max_days) {
+            (Some(left), _) => parts.push(describe(left, "days")),
+            (None, Some(_)) => parts.push("key age unknown".to_string()),
+            _ => {}
+        }
+        if let Some(left) = self.operations_left() {
+            parts.push(describe(left, "operations"));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Cluster names accepted wherever a network is configured
+pub const NETWORKS: &[&str] = &["devnet", "testnet", "mainnet-beta", "localhost"];
+
+/// Commitment levels accepted by `--commitment` and the `commitment` key
+pub const COMMITMENTS: &[&str] = &["processed", "confirmed", "finalized"];
+
+/// Every key `shield-deploy config` knows how to validate
+pub const CONFIG_KEYS: &[ConfigKey] = &[
+    ConfigKey {
+        name: "rpc_url",
+        kind: ConfigValueKind::Url,
+        description: "RPC endpoint, overriding the Solana CLI config",
+    },
+    ConfigKey {
+        name: "proxy",
+        kind: ConfigValueKind::Proxy,
+        description: "Proxy for RPC and HTTP traffic (e.g. socks5h://127.0.0.1:9050 for Tor)",
+    },
+    ConfigKey {
+        name: "network",
+        kind: ConfigValueKind::Enum(NETWORKS),
+        description: "Cluster this project targets",
+    },
+    ConfigKey {
+        name: "privacy_delay",
+        kind: ConfigValueKind::Duration,
+        description: "Wait between Privacy Cash withdrawal and use of funds (e.g. 30s, 5m)",
+    },
+    ConfigKey {
+        name: "finalize_min_age",
+        kind: ConfigValueKind::Duration,
+        description: "Soak period after the last deploy or upgrade before finalize is allowed (e.g. 72h)",
+    },
+    ConfigKey {
+        name: "monitor_webhook",
+        kind: ConfigValueKind::Url,
+        description: "URL `monitor` POSTs drift alerts to",
+    },
+    ConfigKey {
+        name: "notify_webhook",
+        kind: ConfigValueKind::Url,
+        description: "URL deploy, upgrade, rotate, transfer-authority and finalize POST results to",
+    },
+    ConfigKey {
+        name: "priority_fee",
+        kind: ConfigValueKind::PriorityFee,
+        description: "Priority fee in micro-lamports per compute unit, or `auto` (default on mainnet-beta)",
+    },
+    ConfigKey {
+        name: "priority_fee_percentile",
+        kind: ConfigValueKind::Integer,
+        description: "Percentile of recent network fees `auto` picks (default 75)",
+    },
+    ConfigKey {
+        name: "max_priority_fee",
+        kind: ConfigValueKind::Integer,
+        description: "Cap on the `auto` priority fee in micro-lamports per compute unit",
+    },
+    ConfigKey {
+        name: "commitment",
+        kind: ConfigValueKind::Enum(COMMITMENTS),
+        description: "Commitment level for RPC reads and confirmations (default confirmed)",
+    },
+    ConfigKey {
+        name: "check_updates",
+        kind: ConfigValueKind::Bool,
+        description: "Check GitHub for a newer release at most once a day",
+    },
+    ConfigKey {
+        name: "indexer_url",
+        kind: ConfigValueKind::Url,
+        description: "Compression indexer queried by pool-stats (defaults to the RPC URL)",
+    },
+    ConfigKey {
+        name: "perturb_amounts",
+        kind: ConfigValueKind::Bool,
+        description: "Hold back a random remainder when funding (defaults to on for mainnet)",
+    },
+    ConfigKey {
+        name: "pending_stale_after",
+        kind: ConfigValueKind::Duration,
+        description: "Age at which status flags a pending operation as stale (default 7d)",
+    },
+    ConfigKey {
+        name: "auto_close_buffers_after",
+        kind: ConfigValueKind::Duration,
+        description: "Close the deployer's loader buffers older than this after each deploy or upgrade (e.g. 7d)",
+    },
+    ConfigKey {
+        name: "state_backups",
+        kind: ConfigValueKind::Integer,
+        description: "Previous versions of state.json kept in .shield/state-backups (default 10, 0 disables)",
+    },
+    ConfigKey {
+        name: "rotate_after_days",
+        kind: ConfigValueKind::Integer,
+        description: "Warn once the deployer is older than this many days",
+    },
+    ConfigKey {
+        name: "rotate_after_operations",
+        kind: ConfigValueKind::Integer,
+        description: "Warn once the deployer has recorded this many operations",
+    },
+    ConfigKey {
+        name: "enforce_rotation",
+        kind: ConfigValueKind::Bool,
+        description: "Refuse deploy, upgrade, fund and buffer writes past the rotation policy",
+    },
+    ConfigKey {
+        name: "allow_mainnet",
+        kind: ConfigValueKind::Bool,
+        description: "Send mainnet-beta transactions without --mainnet or a typed confirmation",
+    },
+    ConfigKey {
+        name: "spend_limit",
+        kind: ConfigValueKind::NetworkAmounts,
+        description: "Refuse single operations above this per network (e.g. mainnet-beta=10)",
+    },
+    ConfigKey {
+        name: "spend_warn",
+        kind: ConfigValueKind::NetworkAmounts,
+        description: "Warn on single operations above this per network (e.g. mainnet-beta=3)",
+    },
+    ConfigKey {
+        name: "project_name",
+        kind: ConfigValueKind::Text,
+        description: "Project name published in security.txt",
+    },
+    ConfigKey {
+        name: "project_url",
+        kind: ConfigValueKind::Url,
+        description: "Project website published in security.txt",
+    },
+    ConfigKey {
+        name: "security_contacts",
+        kind: ConfigValueKind::Contacts,
+        description: "security.txt contacts (e.g. email:sec@example.com,link:https://...)",
+    },
+    ConfigKey {
+        name: "security_policy",
+        kind: ConfigValueKind::Text,
+        description: "Disclosure policy URL or text published in security.txt",
+    },
+    ConfigKey {
+        name: "source_code_url",
+        kind: ConfigValueKind::Url,
+        description: "Source repository published in security.txt",
+    },
+];
+
+impl ConfigValueKind {
+    /// Parse and validate a raw command-line value
+    pub fn parse(&self, raw: &str) -> Result<toml::Value> {
+        match self {
+            ConfigValueKind::Url => {
+                let valid = raw
+                    .split_once("://")
+                    .map(|(scheme, rest)| {
+                        matches!(scheme, "http" | "https")
+                            && !rest.is_empty()
+                            && !rest.contains(char::is_whitespace)
+                    })
+                    .unwrap_or(false);
+                if !valid {
+                    anyhow::bail!("'{raw}' is not an http(s) URL");
+                }
+                Ok(toml::Value::String(raw.to_string()))
+            }
+            ConfigValueKind::Proxy => {
+                let valid = raw
+                    .split_once("://")
+                    .map(|(scheme, rest)| {
+                        matches!(scheme, "http" | "https" | "socks5" | "socks5h")
+                            && !rest.is_empty()
+                            && !rest.contains(char::is_whitespace)
+                    })
+                    .unwrap_or(false);
+                if !valid {
+                    anyhow::bail!("'{raw}' is not a proxy URL (http, https, socks5 or socks5h)");
+                }
+                Ok(toml::Value::String(raw.to_string()))
+            }
+            ConfigValueKind::PriorityFee => match raw.parse()? {
+                PriorityFee::Fixed(price) => Ok(toml::Value::Integer(price as i64)),
+                PriorityFee::Auto => Ok(toml::Value::String("auto".to_string())),
+            },
+            ConfigValueKind::Duration => {
+                crate::utils::parse_duration(raw)?;
+                Ok(toml::Value::String(raw.to_string()))
+            }
+            ConfigValueKind::Integer => {
+                let value: u64 = raw.parse()
+                    .map_err(|_| anyhow::anyhow!("'{raw}' is not a non-negative integer"))?;
+                Ok(toml::Value::Integer(value as i64))
+            }
+            ConfigValueKind::Text => {
+                if raw.trim().is_empty() || raw.contains('\0') {
+                    anyhow::bail!("value must be non-empty text");
+                }
+                Ok(toml::Value::String(raw.to_string()))
+            }
+            ConfigValueKind::Bool => {
+                let value: bool = raw.parse()
+                    .map_err(|_| anyhow::anyhow!("'{raw}' is not true or false"))?;
+                Ok(toml::Value::Boolean(value))
+            }
+            ConfigValueKind::Contacts => {
+                crate::security_txt::validate_contacts(raw)?;
+                Ok(toml::Value::String(raw.to_string()))
+            }
+            ConfigValueKind::NetworkAmounts => {
+                crate::cost::parse_network_amounts(raw)?;
+                Ok(toml::Value::String(raw.to_string()))
+            }
+            ConfigValueKind::Enum(allowed) => {
+                if !allowed.contains(&raw) {
+                    anyhow::bail!("'{raw}' is not one of: {}", allowed.join(", "));
+                }
+                Ok(toml::Value::String(raw.to_string()))
+            }
+        }
+    }
+}
+
+pub fn config_key(name: &str) -> Option<&'static ConfigKey> {
+    CONFIG_KEYS.iter().find(|k| k.name == name)
+}
+
+/// Which config file a value lives in
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ConfigScope {
+    Project,
+    Global,
+}
+
+impl ConfigScope {
+    pub fn label(self) -> &'static str {
+        match self {
+            ConfigScope::Project => "project",
+            ConfigScope::Global => "global",
+        }
+    }
+
+    pub fn path(self) -> Result<PathBuf> {
+        match self {
+            ConfigScope::Project => Ok(Config::new()?.config_path()),
+            ConfigScope::Global => {
+                let base = std::env::var_os("XDG_CONFIG_HOME")
+                    .map(PathBuf::from)
+                    .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+                    .context("Unable to determine the global config directory (HOME is not set)")?;
+                Ok(base.join("shield-deploy").join(CONFIG_FILE))
+            }
+        }
+    }
+
+    /// Raw table, including keys this version doesn't know about
+    pub fn load_table(self) -> Result<toml::Table> {
+        read_table(&self.path()?)
+    }
+
+    pub fn save_table(self, table: &toml::Table) -> Result<()> {
+        let path = self.path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        fs::write(&path, toml::to_string_pretty(table)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+}
+
+fn read_table(path: &Path) -> Result<toml::Table> {
+    if !path.exists() {
+        return Ok(toml::Table::new());
+    }
+
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content)
+        .with_context(|| format!("{} is not valid TOML", path.display()))
+}
+
+/// Effective settings: project config over global config
+#[derive(Deserialize, Default, Clone)]
+pub struct ProjectConfig {
+    pub rpc_url: Option<String>,
+    pub proxy: Option<String>,
+    pub network: Option<String>,
+    pub privacy_delay: Option<String>,
+    pub finalize_min_age: Option<String>,
+    pub priority_fee: Option<PriorityFee>,
+    pub commitment: Option<String>,
+    pub priority_fee_percentile: Option<u64>,
+    pub max_priority_fee: Option<u64>,
+    pub monitor_webhook: Option<String>,
+    pub notify_webhook: Option<String>,
+    pub check_updates: Option<bool>,
+    pub indexer_url: Option<String>,
+    pub perturb_amounts: Option<bool>,
+    pub pending_stale_after: Option<String>,
+    pub auto_close_buffers_after: Option<String>,
+    pub state_backups: Option<u64>,
+    pub rotate_after_days: Option<u64>,
+    pub rotate_after_operations: Option<u64>,
+    pub enforce_rotation: Option<bool>,
+    pub allow_mainnet: Option<bool>,
+    pub spend_limit: Option<String>,
+    pub spend_warn: Option<String>,
+    pub project_name: Option<String>,
+    pub project_url: Option<String>,
+    pub security_contacts: Option<String>,
+    pub security_policy: Option<String>,
+    pub source_code_url: Option<String>,
+}
+
+impl ProjectConfig {
+    pub fn load() -> Result<Self> {
+        Self::load_for(&Config::new()?)
+    }
+
+    /// Settings of the project whose state lives in `config`, over global config
+    pub fn load_for(config: &Config) -> Result<Self> {
+        let mut merged = ConfigScope::Global.load_table()?;
+        merged.extend(read_table(&config.config_path())?);
+
+        toml::Value::Table(merged)
+            .try_into()
+            .context("Config does not match the expected schema. Run `shield-deploy config list`")
+    }
+
+    pub fn privacy_delay(&self) -> Option<std::time::Duration> {
+        self.privacy_delay
+            .as_deref()
+            .and_then(|raw| crate::utils::parse_duration(raw).ok())
+    }
+
+    pub fn pending_stale_after(&self) -> Option<std::time::Duration> {
+        self.pending_stale_after
+            .as_deref()
+            .and_then(|raw| crate::utils::parse_duration(raw).ok())
+    }
+
+    pub fn finalize_min_age(&self) -> Option<std::time::Duration> {
+        self.finalize_min_age
+            .as_deref()
+            .and_then(|raw| crate::utils::parse_duration(raw).ok())
+    }
+
+    /// Soak time left before a program last changed at `last_change` may be finalized
+    ///
+    /// `None` when `finalize_min_age` isn't set or has passed by `now`.
+    pub fn finalize_soak_remaining(&self, last_change: i64, now: i64) -> Option<std::time::Duration> {
+        let min_age = self.finalize_min_age()?;
+        let age = std::time::Duration::from_secs(now.saturating_sub(last_change).max(0) as u64);
+        min_age.checked_sub(age).filter(|remaining| !remaining.is_zero())
+    }
+
+    pub fn auto_close_buffers_after(&self) -> Option<std::time::Duration> {
+        self.auto_close_buffers_after
+            .as_deref()
+            .and_then(|raw| crate::utils::parse_duration(raw).ok())
+    }
+
+    /// Hard cap on a single operation's spend on `network`, in lamports
+    pub fn spend_limit(&self, network: &str) -> Result<Option<u64>> {
+        network_amount(self.spend_limit.as_deref(), network)
+            .context("Invalid spend_limit in config")
+    }
+
+    /// Spend above which a single operation on `network` warns, in lamports
+    pub fn spend_warn(&self, network: &str) -> Result<Option<u64>> {
+        network_amount(self.spend_warn.as_deref(), network)
+            .context("Invalid spend_warn in config")
+    }
+}
+
+fn network_amount(raw: Option<&str>, network: &str) -> Result<Option<u64>> {
+    let Some(raw) = raw else { return Ok(None) };
+    Ok(crate::cost::parse_network_amounts(raw)?
+        .into_iter()
+        .find(|(name, _)| name == network)
+        .map(|(_, lamports)| lamports))
+}
@@ -0,0 +1,108 @@
+//! Transaction confirmation over websocket signature subscriptions
+//!
+//! The validator pushes a notification once a signature reaches the RPC
+//! client's commitment, so a confirmation lands without a status request
+//! every few hundred milliseconds. Endpoints without a websocket, runs with
+//! `--no-websocket` and runs through a proxy get a disconnected
+//! [`SignatureWatcher`], and callers fall back to polling over HTTP. The
+//! websocket client can't use the proxy, so it would reach the RPC directly.
+
+use futures::StreamExt;
+use solana_cli_config::Config as SolanaConfig;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSignatureSubscribeConfig;
+use solana_client::rpc_response::RpcSignatureResult;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature, transaction::TransactionError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use crate::proxy::proxy_url;
+use crate::utils::report_detail;
+
+/// Longest wait for the websocket handshake before polling instead
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+static NO_WEBSOCKET: AtomicBool = AtomicBool::new(false);
+
+/// Poll signature statuses over HTTP only (`--no-websocket`)
+pub fn set_no_websocket(disabled: bool) {
+    NO_WEBSOCKET.store(disabled, Ordering::Relaxed);
+}
+
+/// Websocket URL of the RPC at `rpc_url`: ws(s) scheme, port 8899 → 8900
+pub fn websocket_url(rpc_url: &str) -> String {
+    SolanaConfig::compute_websocket_url(rpc_url)
+}
+
+/// Signature subscriptions on one websocket connection to the RPC
+pub struct SignatureWatcher {
+    pubsub: Option<PubsubClient>,
+    commitment: CommitmentConfig,
+}
+
+impl SignatureWatcher {
+    /// Connect to the websocket next to `rpc_client`'s URL
+    ///
+    /// Never fails: without a connection every [`wait`](Self::wait) returns
+    /// `None` straight away and the caller polls.
+    pub async fn connect(rpc_client: &RpcClient) -> Self {
+        let commitment = rpc_client.commitment();
+        if NO_WEBSOCKET.load(Ordering::Relaxed) {
+            return Self { pubsub: None, commitment };
+        }
+        if proxy_url().is_some() {
+            report_detail("Proxy configured; polling for confirmations over it instead of a websocket");
+            return Self { pubsub: None, commitment };
+        }
+
+        let url = websocket_url(&rpc_client.url());
+        let pubsub = match tokio::time::timeout(CONNECT_TIMEOUT, PubsubClient::new(&url)).await {
+            Ok(Ok(pubsub)) => Some(pubsub),
+            Ok(Err(e)) => {
+                report_detail(&format!("Websocket {url} unavailable ({e}); polling for confirmations"));
+                None
+            }
+            Err(_) => {
+                report_detail(&format!("Websocket {url} timed out; polling for confirmations"));
+                None
+            }
+        };
+        Self { pubsub, commitment }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.pubsub.is_some()
+    }
+
+    /// Wait up to `timeout` for `signature` to reach the client's commitment
+    ///
+    /// Returns the transaction's result once notified. `None` means nothing
+    /// arrived in time or the subscription failed; only a status request
+    /// can tell whether the transaction landed or expired.
+    pub async fn wait(
+        &self,
+        signature: Signature,
+        timeout: Duration,
+    ) -> (Signature, Option<Result<(), TransactionError>>) {
+        let Some(pubsub) = &self.pubsub else {
+            return (signature, None);
+        };
+        let config = RpcSignatureSubscribeConfig {
+            commitment: Some(self.commitment),
+            enable_received_notification: Some(false),
+        };
+        let Ok((mut notifications, unsubscribe)) = pubsub.signature_subscribe(&signature, Some(config)).await else {
+            return (signature, None);
+        };
+
+        let notification = tokio::time::timeout(timeout, notifications.next()).await.ok().flatten();
+        drop(notifications);
+        unsubscribe().await;
+
+        let result = notification.and_then(|notification| match notification.value {
+            RpcSignatureResult::ProcessedSignature(processed) => Some(processed.err.map_or(Ok(()), Err)),
+            RpcSignatureResult::ReceivedSignature(_) => None,
+        });
+        (signature, result)
+    }
+}
@@ -0,0 +1,415 @@
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status_client_types::UiTransactionEncoding;
+use std::str::FromStr;
+use crate::artifact::MAX_PROGRAM_BYTES;
+use crate::config::{OperationRecord, ProjectConfig, SpendRecord, NETWORKS};
+use crate::utils::{
+    calculate_max_write_chunk_size, ensure_interactive, format_sol, parse_amount, print_warning,
+    with_retry, Amount,
+};
+
+/// Base fee per signature
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// Headroom on top of the estimated fees for resent writes and fee drift
+const FEE_MARGIN_PERCENT: u64 = 20;
+
+/// Smallest margin, enough for a handful of resent transactions
+const MIN_FEE_MARGIN: u64 = 5 * LAMPORTS_PER_SIGNATURE;
+
+/// Compute units a transaction is charged priority fees for without a
+/// compute budget instruction
+const DEFAULT_COMPUTE_UNITS: u64 = 200_000;
+
+/// Transaction lookups in flight while measuring a deploy's fees
+const FEE_LOOKUP_CONCURRENCY: usize = 8;
+
+/// ProgramData capacity reserved on a fresh deploy, leaving room to grow
+pub fn default_max_data_len(program_len: usize) -> usize {
+    program_len * 2
+}
+
+/// ProgramData capacity from `--max-len` or `--max-len-multiplier`
+///
+/// Falls back to the 2x default. The capacity can't go below the program
+/// itself or above what the loader accepts.
+pub fn resolve_max_data_len(
+    program_len: usize,
+    max_len: Option<usize>,
+    multiplier: Option<f64>,
+) -> anyhow::Result<usize> {
+    let max_data_len = match (max_len, multiplier) {
+        (Some(len), _) => len,
+        (None, Some(m)) if m.is_finite() && m >= 1.0 => (program_len as f64 * m).ceil() as usize,
+        (None, Some(m)) => anyhow::bail!("--max-len-multiplier must be at least 1.0, got {m}"),
+        (None, None) => default_max_data_len(program_len),
+    };
+    if max_data_len < program_len {
+        anyhow::bail!(
+            "--max-len {max_data_len} is smaller than the program ({program_len} bytes)"
+        );
+    }
+    if max_data_len > MAX_PROGRAM_BYTES {
+        anyhow::bail!(
+            "ProgramData capacity {max_data_len} is above the loader's {MAX_PROGRAM_BYTES} byte limit"
+        );
+    }
+    Ok(max_data_len)
+}
+
+/// Lamports needed for a deploy or upgrade, broken down by component
+///
+/// This is the single cost model behind `estimate` and the balance checks
+/// in deploy and upgrade.
+#[derive(Serialize, Default)]
+pub struct CostEstimate {
+    pub program_len: usize,
+    pub chunk_count: usize,
+    /// ProgramData capacity (fresh deploy) or current capacity (upgrade)
+    pub max_data_len: usize,
+    /// Held by the buffer during the write and returned to the deployer after
+    pub buffer_rent: u64,
+    pub program_rent: u64,
+    pub programdata_rent: u64,
+    /// Extra ProgramData rent when an upgrade needs a larger account
+    pub extend_rent: u64,
+    pub write_fees: u64,
+    /// Signature fees for the create-buffer and deploy/upgrade transactions
+    pub transaction_fees: u64,
+    pub priority_fees: u64,
+    /// Required on top of the fees, but not expected to be spent
+    pub margin: u64,
+}
+
+impl CostEstimate {
+    pub fn fees(&self) -> u64 {
+        self.write_fees + self.transaction_fees + self.priority_fees
+    }
+
+    /// Fees plus the margin, what a separate fee payer has to cover
+    pub fn fee_budget(&self) -> u64 {
+        self.fees() + self.margin
+    }
+
+    fn with_margin(mut self) -> Self {
+        self.margin = (self.fees() * FEE_MARGIN_PERCENT / 100).max(MIN_FEE_MARGIN);
+        self
+    }
+
+    /// Lamports spent for good once the operation completes
+    pub fn net_cost(&self) -> u64 {
+        self.program_rent + self.programdata_rent + self.extend_rent + self.fees()
+    }
+
+    /// Peak balance the deployer needs, including the refundable buffer rent
+    pub fn required_balance(&self) -> u64 {
+        // On deploy the buffer is drained into the new ProgramData account,
+        // so only the larger of the two is held at once
+        self.buffer_rent.max(self.programdata_rent)
+            + self.program_rent
+            + self.extend_rent
+            + self.fees()
+            + self.margin
+    }
+}
+
+/// Cost of a fresh deploy of `program_len` bytes
+pub async fn estimate_deploy(
+    rpc_client: &RpcClient,
+    program_len: usize,
+    max_data_len: usize,
+    priority_fee_microlamports: u64,
+) -> Result<CostEstimate> {
+    let chunk_count = program_len.div_ceil(calculate_max_write_chunk_size());
+
+    Ok(CostEstimate {
+        program_len,
+        chunk_count,
+        max_data_len,
+        buffer_rent: buffer_rent(rpc_client, program_len).await?,
+        program_rent: rpc_client
+            .get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_program()).await
+            .context("Failed to get rent exemption for program account")?,
+        programdata_rent: rpc_client
+            .get_minimum_balance_for_rent_exemption(
+                UpgradeableLoaderState::size_of_programdata(max_data_len),
+            )
+            .await
+            .context("Failed to get rent exemption for program data")?,
+        extend_rent: 0,
+        write_fees: chunk_count as u64 * LAMPORTS_PER_SIGNATURE,
+        // Create buffer and deploy are each signed by the deployer and a new keypair
+        transaction_fees: 4 * LAMPORTS_PER_SIGNATURE,
+        priority_fees: priority_fee(chunk_count + 2, priority_fee_microlamports),
+        margin: 0,
+    }
+    .with_margin())
+}
+
+/// Cost of upgrading the on-chain program to `program_len` bytes
+pub async fn estimate_upgrade(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    program_len: usize,
+    priority_fee_microlamports: u64,
+) -> Result<CostEstimate> {
+    let loader_id = Pubkey::new_from_array(LOADER_ID.to_bytes());
+    let (programdata_address, _) = Pubkey::find_program_address(
+        &[program_id.as_ref()],
+        &loader_id,
+    );
+    let programdata = rpc_client
+        .get_account(&programdata_address).await
+        .context("ProgramData account not found - program may not exist")?;
+
+    let capacity = programdata.data.len()
+        .saturating_sub(UpgradeableLoaderState::size_of_programdata_metadata());
+
+    let extend_rent = if program_len > capacity {
+        rpc_client
+            .get_minimum_balance_for_rent_exemption(
+                UpgradeableLoaderState::size_of_programdata(program_len),
+            )
+            .await
+            .context("Failed to get rent exemption for program data")?
+            .saturating_sub(programdata.lamports)
+    } else {
+        0
+    };
+
+    let chunk_count = program_len.div_ceil(calculate_max_write_chunk_size());
+
+    Ok(CostEstimate {
+        program_len,
+        chunk_count,
+        max_data_len: capacity,
+        buffer_rent: buffer_rent(rpc_client, program_len).await?,
+        extend_rent,
+        write_fees: chunk_count as u64 * LAMPORTS_PER_SIGNATURE,
+        // Create buffer takes two signatures, the upgrade one
+        transaction_fees: 3 * LAMPORTS_PER_SIGNATURE,
+        priority_fees: priority_fee(chunk_count + 2, priority_fee_microlamports),
+        ..Default::default()
+    }
+    .with_margin())
+}
+
+async fn buffer_rent(rpc_client: &RpcClient, program_len: usize) -> Result<u64> {
+    rpc_client
+        .get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_buffer(program_len)).await
+        .context("Failed to get rent exemption for buffer")
+}
+
+fn priority_fee(transactions: usize, microlamports_per_cu: u64) -> u64 {
+    (transactions as u64 * DEFAULT_COMPUTE_UNITS * microlamports_per_cu).div_ceil(1_000_000)
+}
+
+/// `network`'s `spend_limit` in `config` when `estimated` goes over it
+pub fn spend_limit_exceeded(config: &ProjectConfig, network: &str, estimated: u64) -> Result<Option<u64>> {
+    Ok(config.spend_limit(network)?.filter(|&limit| estimated > limit))
+}
+
+/// Check a single operation's estimated spend against the network's caps
+///
+/// Above `spend_warn` this only warns. Above `spend_limit` it refuses
+/// unless `override_limit` is set and the network name is typed back,
+/// even under `--yes`.
+pub fn enforce_spend_limit(network: &str, estimated: u64, override_limit: bool) -> Result<()> {
+    let config = ProjectConfig::load()?;
+
+    if let Some(limit) = spend_limit_exceeded(&config, network, estimated)? {
+        if !override_limit {
+            anyhow::bail!(
+                "Estimated spend {} exceeds the {network} spend limit of {}.\n\
+                Raise `spend_limit` or pass --override-spend-limit.",
+                format_sol(estimated),
+                format_sol(limit)
+            );
+        }
+
+        print_warning(&format!(
+            "Estimated spend {} exceeds the {network} spend limit of {}",
+            format_sol(estimated),
+            format_sol(limit)
+        ));
+        println!("Type the network name to exceed the limit:");
+        ensure_interactive("Network")?;
+        let confirmation: String = dialoguer::Input::new()
+            .with_prompt("Network")
+            .interact_text()?;
+        if confirmation.trim() != network {
+            anyhow::bail!("Network mismatch. Spend limit not overridden.");
+        }
+        return Ok(());
+    }
+
+    if let Some(warn) = config.spend_warn(network)? {
+        if estimated > warn {
+            print_warning(&format!(
+                "Estimated spend {} is above the {network} warning threshold of {}",
+                format_sol(estimated),
+                format_sol(warn)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse `network=amount` pairs, e.g. `mainnet-beta=10,devnet=100 sol`
+pub fn parse_network_amounts(raw: &str) -> Result<Vec<(String, u64)>> {
+    raw.split(',')
+        .map(|pair| {
+            let (network, amount) = pair
+                .split_once('=')
+                .with_context(|| format!("'{pair}' is not network=amount"))?;
+            let network = network.trim();
+            if !NETWORKS.contains(&network) {
+                anyhow::bail!("'{network}' is not one of: {}", NETWORKS.join(", "));
+            }
+            match parse_amount(amount, false).map_err(anyhow::Error::msg)? {
+                Amount::Lamports(lamports) => Ok((network.to_string(), lamports)),
+                Amount::Max => unreachable!("max is rejected when not allowed"),
+            }
+        })
+        .collect()
+}
+
+/// Fee and balance changes of one confirmed transaction
+struct ChargedTransaction {
+    fee: u64,
+    signatures: usize,
+    /// (account, balance before, balance after)
+    balances: Vec<(Pubkey, u64, u64)>,
+}
+
+/// Measure what a finished deploy or upgrade cost
+///
+/// Fees come from the confirmed metadata of every transaction in `record`
+/// (buffer creation, writes and the final one) and the rent figures from the
+/// final transaction's balance changes, so these are the amounts the cluster
+/// charged rather than estimates.
+pub async fn measure_spend(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    record: &OperationRecord,
+    balance_before: u64,
+    balance_after: u64,
+) -> SpendRecord {
+    let loader_id = Pubkey::new_from_array(LOADER_ID.to_bytes());
+    let (programdata_address, _) = Pubkey::find_program_address(&[program_id.as_ref()], &loader_id);
+    let buffer = record.buffer.as_deref().and_then(|b| Pubkey::from_str(b).ok());
+
+    let signatures: Vec<(Signature, bool)> = record
+        .buffer_signatures
+        .iter()
+        .map(|s| (s, false))
+        .chain(record.signatures.iter().map(|s| (s, true)))
+        .filter_map(|(s, is_final)| Signature::from_str(s).ok().map(|s| (s, is_final)))
+        .collect();
+
+    let charged: Vec<(Option<ChargedTransaction>, bool)> = futures::stream::iter(signatures)
+        .map(|(signature, is_final)| async move { (charged_transaction(rpc_client, &signature).await, is_final) })
+        .buffered(FEE_LOOKUP_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut spend = SpendRecord { balance_before, balance_after, ..Default::default() };
+    for (transaction, is_final) in charged {
+        let Some(transaction) = transaction else {
+            spend.unaccounted_transactions += 1;
+            continue;
+        };
+        spend.transactions += 1;
+        spend.transaction_fees += transaction.fee;
+        spend.priority_fees += transaction
+            .fee
+            .saturating_sub(transaction.signatures as u64 * LAMPORTS_PER_SIGNATURE);
+
+        if !is_final {
+            continue;
+        }
+        for (account, before, after) in transaction.balances {
+            if account == programdata_address {
+                spend.program_data_rent = after;
+            } else if Some(account) == buffer {
+                spend.buffer_refund = before.saturating_sub(after);
+            }
+        }
+    }
+    spend
+}
+
+async fn charged_transaction(rpc_client: &RpcClient, signature: &Signature) -> Option<ChargedTransaction> {
+    // getTransaction doesn't serve processed, so read at confirmed
+    let confirmed = with_retry(|| {
+        rpc_client.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+    })
+    .await
+    .ok()?;
+    let meta = confirmed.transaction.meta?;
+    let decoded = confirmed.transaction.transaction.decode()?;
+    let balances = decoded
+        .message
+        .static_account_keys()
+        .iter()
+        .zip(meta.pre_balances.iter().zip(&meta.post_balances))
+        .map(|(key, (before, after))| (*key, *before, *after))
+        .collect();
+
+    Some(ChargedTransaction {
+        fee: meta.fee,
+        signatures: decoded.signatures.len(),
+        balances,
+    })
+}
+
+/// End-of-run summary for deploy and upgrade
+pub fn print_spend(spend: &SpendRecord) {
+    let change = spend.balance_change();
+    println!("\nSpend summary:");
+    println!(
+        "  Deployer balance:     {} → {} ({}{:.6} SOL)",
+        format_sol(spend.balance_before),
+        format_sol(spend.balance_after),
+        if change < 0 { "-" } else { "+" },
+        change.unsigned_abs() as f64 / 1_000_000_000.0
+    );
+    if spend.program_data_rent > 0 {
+        println!("  Rent in ProgramData:  {} (locked while the program exists)", format_sol(spend.program_data_rent));
+    }
+    if spend.buffer_refund > 0 {
+        println!("  Buffer rent refunded: {}", format_sol(spend.buffer_refund));
+    }
+    println!(
+        "  Transaction fees:     {} over {} transactions",
+        format_fee(spend.transaction_fees),
+        spend.transactions
+    );
+    println!("  Priority fees:        {} (included above)", format_fee(spend.priority_fees));
+    if spend.unaccounted_transactions > 0 {
+        println!(
+            "  ↳ {} transactions not found on the RPC; their fees are not counted",
+            spend.unaccounted_transactions
+        );
+    }
+}
+
+/// Fees are far below 0.01 SOL, so show lamports as well
+pub fn format_fee(lamports: u64) -> String {
+    format!("{lamports} lamports ({:.6} SOL)", lamports as f64 / 1_000_000_000.0)
+}
@@ -0,0 +1,308 @@
+//! Automatic cleanup of stale loader buffers
+//!
+//! Buffers left by failed or abandoned writes keep their rent locked until
+//! someone closes them. With `auto_close_buffers_after` set, successful
+//! deploys and upgrades (and `doctor --fix`) close the deployer's buffers
+//! once they are older than that.
+
+use anyhow::{Context, Result};
+use solana_account_decoder_client_types::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_loader_v3_interface::instruction as bpf_loader_upgradeable;
+use solana_pubkey::Pubkey as SolanaPubkeyV2;
+use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction as SdkInstruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+use std::time::Duration;
+use crate::compute::{with_compute_budget_for, FeeSettings, TxClass};
+use crate::config::{BufferStatus, Config, OperationKind, OperationRecord, ProjectConfig, ProjectState};
+use crate::project::Progress;
+use crate::utils::*;
+
+/// Threshold `doctor --fix` uses when `auto_close_buffers_after` is unset
+pub const DEFAULT_BUFFER_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Loader account tags (bincode u32 enum discriminants)
+pub const TAG_BUFFER: u8 = 1;
+pub const TAG_PROGRAM: u8 = 2;
+pub const TAG_PROGRAMDATA: u8 = 3;
+
+/// Offset of the Option<Pubkey> authority in Buffer and ProgramData accounts
+pub const BUFFER_AUTHORITY_OFFSET: usize = 4;
+pub const PROGRAMDATA_AUTHORITY_OFFSET: usize = 12;
+
+/// Close stale buffers after a deploy or upgrade, if the project opted in
+///
+/// Cleanup never fails the command that triggered it; problems are reported
+/// through `progress`.
+pub async fn auto_close_buffers(config: &Config, rpc_client: &RpcClient, deployer: &Keypair, progress: &dyn Progress) {
+    let max_age = match ProjectConfig::load_for(config).map(|c| c.auto_close_buffers_after()) {
+        Ok(Some(max_age)) => max_age,
+        Ok(None) => return,
+        Err(e) => {
+            progress.step(&format!("Skipping buffer cleanup: {e}"));
+            return;
+        }
+    };
+    if let Err(e) = close_stale_buffers(config, rpc_client, deployer, max_age, progress).await {
+        progress.step(&format!("Buffer cleanup failed: {e:#}"));
+    }
+}
+
+/// Close the deployer's buffers older than `max_age`, returning the lamports reclaimed
+///
+/// Buffers an interrupted deploy can still finish with `deploy --recover`,
+/// and those waiting on a scheduled upgrade, are kept. A buffer whose age
+/// can't be determined is kept as well.
+pub async fn close_stale_buffers(
+    config: &Config,
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    max_age: Duration,
+    progress: &dyn Progress,
+) -> Result<u64> {
+    progress.step(&format!("Checking for stale buffers (older than {})", format_duration(max_age)));
+
+    let mut state = config.load_state()?;
+    let recoverable: Vec<Pubkey> = config
+        .load_pending_program_keys()?
+        .iter()
+        .map(|k| k.pubkey())
+        .collect();
+    let scheduled: Vec<String> = config
+        .load_scheduled_upgrades()?
+        .into_iter()
+        .map(|plan| plan.buffer)
+        .collect();
+    let in_use = |address: &Pubkey| {
+        scheduled.contains(&address.to_string()) || state.buffers.iter().any(|b| {
+            b.address == address.to_string()
+                && matches!(b.status, BufferStatus::Writing | BufferStatus::Ready)
+                && b.program_id.as_ref().is_some_and(|id| recoverable.iter().any(|k| k.to_string() == *id))
+        })
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let mut stale: Vec<(Pubkey, u64)> = Vec::new();
+    for (address, lamports) in scan_buffers(rpc_client, &deployer.pubkey()).await? {
+        if in_use(&address) {
+            continue;
+        }
+        let created = buffer_created_at(rpc_client, &state, &address).await;
+        if created.is_some_and(|created| now.saturating_sub(created) as u64 > max_age.as_secs()) {
+            stale.push((address, lamports));
+        }
+    }
+
+    if stale.is_empty() {
+        progress.step("No stale buffers");
+        return Ok(0);
+    }
+
+    let mut reclaimed = 0;
+    let mut signatures = Vec::new();
+    for (address, lamports) in &stale {
+        match close_buffer(rpc_client, deployer, address).await {
+            Ok(signature) => {
+                progress.step(&format!("Closed {address} ({})", format_sol(*lamports)));
+                reclaimed += lamports;
+                signatures.push(signature.to_string());
+                if let Some(record) = state.buffers.iter_mut().find(|b| b.address == address.to_string()) {
+                    record.status = BufferStatus::Closed;
+                }
+            }
+            Err(e) => progress.step(&format!("Could not close {address}: {e}")),
+        }
+    }
+
+    if !signatures.is_empty() {
+        let mut record = OperationRecord::new(OperationKind::CloseBuffer, &get_network_name());
+        record.note = Some(format!("closed {} stale buffers, reclaimed {}", signatures.len(), format_sol(reclaimed)));
+        record.signatures = signatures;
+        state.operations.push(record);
+        if let Ok(balance) = balance_of(rpc_client, &deployer.pubkey()).await {
+            state.last_balance = balance;
+        }
+        config.save_state(&state)?;
+        progress.step(&format!("Reclaimed {}", format_sol(reclaimed)));
+    }
+
+    Ok(reclaimed)
+}
+
+/// Loader buffers whose authority is `authority`, with their lamports
+pub async fn scan_buffers(rpc_client: &RpcClient, authority: &Pubkey) -> Result<Vec<(Pubkey, u64)>> {
+    Ok(scan_by_authority(rpc_client, TAG_BUFFER, BUFFER_AUTHORITY_OFFSET, authority).await?
+        .into_iter()
+        .map(|(address, account)| (address, account.lamports))
+        .collect())
+}
+
+/// Loader accounts tagged `tag` whose authority at `authority_offset` is `authority`
+///
+/// Only addresses and lamports come back; account data is sliced away.
+pub async fn scan_by_authority(
+    rpc_client: &RpcClient,
+    tag: u8,
+    authority_offset: usize,
+    authority: &Pubkey,
+) -> Result<Vec<(Pubkey, Account)>> {
+    let loader_id = Pubkey::new_from_array(LOADER_ID.to_bytes());
+
+    // Option<Pubkey> is encoded as a Some tag followed by the key
+    let mut authority_bytes = vec![1u8];
+    authority_bytes.extend_from_slice(authority.as_ref());
+
+    rpc_client
+        .get_program_accounts_with_config(
+            &loader_id,
+            RpcProgramAccountsConfig {
+                filters: Some(vec![
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, vec![tag, 0, 0, 0])),
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(authority_offset, authority_bytes)),
+                ]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    data_slice: Some(UiDataSliceConfig { offset: 0, length: 0 }),
+                    commitment: Some(rpc_client.commitment()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .await
+        .context("getProgramAccounts failed")
+}
+
+/// Unix time a buffer was created
+///
+/// Taken from the block time of its oldest signature. If the RPC returns a
+/// full page the account may be older still, which only errs toward keeping
+/// it. Falls back to the local record, then to unknown.
+async fn buffer_created_at(rpc_client: &RpcClient, state: &ProjectState, address: &Pubkey) -> Option<i64> {
+    let oldest = rpc_client
+        .get_signatures_for_address(address)
+        .await
+        .ok()
+        .and_then(|signatures| signatures.last().cloned());
+    let from_chain = match oldest {
+        Some(oldest) if oldest.block_time.is_some() => oldest.block_time,
+        Some(oldest) => rpc_client.get_block_time(oldest.slot).await.ok(),
+        None => None,
+    };
+
+    from_chain.or_else(|| {
+        state.buffers
+            .iter()
+            .find(|b| b.address == address.to_string())
+            .map(|b| b.created_at)
+    })
+}
+
+/// Update the local record of `buffer`, if there is one
+pub fn set_buffer_status(config: &Config, buffer: &Pubkey, status: BufferStatus) -> Result<()> {
+    let mut state = config.load_state()?;
+    if let Some(record) = state.buffers.iter_mut().find(|b| b.address == buffer.to_string()) {
+        record.status = status;
+        config.save_state(&state)?;
+    }
+    Ok(())
+}
+
+/// Close a buffer whose deploy or upgrade failed so its rent isn't stranded
+///
+/// `authority` is the buffer's authority; the rent goes to `payer`. Returns
+/// the lamports reclaimed. A failure here is only reported as progress,
+/// since the caller is already returning the original error.
+pub async fn reclaim_failed_buffer(
+    config: &Config,
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    payer: &Keypair,
+    authority: &Keypair,
+    address: &Pubkey,
+    progress: &dyn Progress,
+) -> Option<u64> {
+    // Gone already when the failure came after the buffer was consumed
+    let lamports = with_retry(|| rpc_client.get_balance(address)).await.ok().filter(|&l| l > 0)?;
+
+    progress.step(&format!("Closing buffer {address} to refund its rent"));
+    match close_buffer_to(rpc_client, fees, payer, authority, &payer.pubkey(), address).await {
+        Ok(signature) => {
+            progress.step(&format!("Reclaimed {} to {}", format_sol(lamports), payer.pubkey()));
+            if let Ok(mut state) = config.load_state() {
+                if let Some(record) = state.buffers.iter_mut().find(|b| b.address == address.to_string()) {
+                    record.status = BufferStatus::Closed;
+                }
+                let mut record = OperationRecord::new(OperationKind::CloseBuffer, &state.network);
+                record.note = Some(format!("refunded {} after a failed deploy", format_sol(lamports)));
+                record.signatures.push(signature.to_string());
+                state.operations.push(record);
+                if let Err(e) = config.save_state(&state) {
+                    progress.step(&format!("Could not record the closed buffer: {e}"));
+                }
+            }
+            Some(lamports)
+        }
+        Err(e) => {
+            progress.step(&format!(
+                "Could not close buffer {address}: {e:#}. Reclaim it later with `shield-deploy buffers --close {address}`"
+            ));
+            None
+        }
+    }
+}
+
+/// Close a buffer owned by the deployer, refunding its rent to the deployer
+pub async fn close_buffer(rpc_client: &RpcClient, deployer: &Keypair, address: &Pubkey) -> Result<Signature> {
+    close_buffer_to(rpc_client, &FeeSettings::current(), deployer, deployer, &deployer.pubkey(), address).await
+}
+
+/// Close a buffer signed by `authority`, sending its rent to `recipient`
+async fn close_buffer_to(
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    payer: &Keypair,
+    authority: &Keypair,
+    recipient: &Pubkey,
+    address: &Pubkey,
+) -> Result<Signature> {
+    let authority_v2 = SolanaPubkeyV2::new_from_array(authority.pubkey().to_bytes());
+    let recipient_v2 = SolanaPubkeyV2::new_from_array(recipient.to_bytes());
+    let address_v2 = SolanaPubkeyV2::new_from_array(address.to_bytes());
+
+    let close_ix = bpf_loader_upgradeable::close(&address_v2, &recipient_v2, &authority_v2);
+    let close_ix = SdkInstruction {
+        program_id: Pubkey::from(close_ix.program_id.to_bytes()),
+        accounts: close_ix
+            .accounts
+            .iter()
+            .map(|acc| AccountMeta {
+                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            })
+            .collect(),
+        data: close_ix.data,
+    };
+
+    let fee_payer = fee_payer(payer);
+    let recent_blockhash = with_retry(|| rpc_client.get_latest_blockhash()).await?;
+    let mut transaction = Transaction::new_with_payer(
+        &with_compute_budget_for(rpc_client, fees, TxClass::Close, &fee_payer.pubkey(), &[close_ix]).await,
+        Some(&fee_payer.pubkey()),
+    );
+    let mut signers = vec![&*fee_payer, payer, authority];
+    signers.dedup_by_key(|k| k.pubkey());
+    transaction.sign(&signers, recent_blockhash);
+
+    send_and_confirm(rpc_client, &transaction).await
+        .context("Failed to close buffer")
+}
@@ -0,0 +1,50 @@
+//! Privacy-preserving Solana program deployment
+//!
+//! The `shield-deploy` CLI is built on this crate: its commands add prompts
+//! and reporting around the same loader transactions ([`loader`]) that
+//! [`ShieldProject`] exposes. Release tooling can drive a project directly
+//! through [`ShieldProject`] instead of shelling out and parsing stdout:
+//!
+//! ```no_run
+//! use shield_deploy::{Progress, ShieldProject};
+//!
+//! struct Log;
+//! impl Progress for Log {
+//!     fn step(&self, message: &str) {
+//!         eprintln!("[shield] {message}");
+//!     }
+//! }
+//!
+//! # async fn run() -> Result<(), shield_deploy::ShieldError> {
+//! let project = ShieldProject::open(".")?;
+//! let program_id = "Prog1111111111111111111111111111111111111111".parse().unwrap();
+//! let program = std::fs::read("target/deploy/my_program.so").unwrap();
+//!
+//! project.upgrade(&program_id, &program, &Log).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod artifact;
+pub mod bundle;
+pub mod compute;
+pub mod config;
+pub mod confirm;
+pub mod cost;
+pub mod hygiene;
+pub mod loader;
+pub mod network;
+pub mod notify;
+pub mod pool;
+pub mod privacy;
+pub mod project;
+pub mod proxy;
+pub mod security_txt;
+pub mod update;
+pub mod utils;
+
+pub use config::{DeployedProgram, OperationKind, OperationRecord, ProjectState};
+pub use project::{
+    DeployOutcome, FundOutcome, NoProgress, Progress, ProjectStatus, ShieldError, ShieldProject,
+    ShieldResult,
+};
@@ -0,0 +1,876 @@
+//! Deploy, upgrade and finalize transactions for the upgradeable loader
+//!
+//! Shared by the CLI and [`ShieldProject`](crate::ShieldProject). Nothing
+//! here prompts or prints: each step is reported through a [`Progress`], and
+//! priority fees follow the [`FeeSettings`] passed in rather than the config
+//! of the working directory.
+
+use anyhow::{anyhow, Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_instruction::Instruction as LoaderInstruction;
+use solana_loader_v3_interface::{
+    instruction as bpf_loader_upgradeable,
+    state::UpgradeableLoaderState,
+};
+use solana_address::Address;
+use solana_pubkey::Pubkey as SolanaPubkeyV2;
+use solana_system_interface::instruction as system_instruction;
+use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction as SdkInstruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use crate::compute::{with_compute_budget_for, FeeSettings, TxClass};
+use crate::config::{BufferPurpose, BufferRecord, BufferStatus, Config, PendingDeploy};
+use crate::cost::estimate_deploy;
+use crate::hygiene::{reclaim_failed_buffer, set_buffer_status};
+use crate::project::Progress;
+use crate::utils::*;
+
+/// Bytes of the first chunk included in the pre-write simulation, leaving
+/// room for the buffer creation instructions in the same transaction
+const PREFLIGHT_WRITE_BYTES: usize = 512;
+
+/// Deploy `program_keypair`'s program from `program_data`
+///
+/// Creates a buffer, writes the program into it in chunks, then deploys
+/// from the buffer with the deployer as upgrade authority (none with
+/// `immutable`). A program that already exists at the address is upgraded
+/// instead, provided the deployer holds its authority.
+#[allow(clippy::too_many_arguments)]
+pub async fn deploy_program(
+    config: &Config,
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    deployer: &Keypair,
+    program_keypair: &Keypair,
+    program_data: &[u8],
+    max_data_len: usize,
+    immutable: bool,
+    progress: &dyn Progress,
+) -> Result<Signature> {
+    let program_id = program_keypair.pubkey();
+
+    progress.step("Verifying deployment prerequisites");
+    if verify_can_deploy(rpc_client, &program_id, deployer).await? {
+        progress.step("Program already exists; this will be an upgrade");
+        return upgrade_program(
+            config,
+            rpc_client,
+            fees,
+            deployer,
+            deployer,
+            &program_id,
+            program_data,
+            &deployer.pubkey(),
+            progress,
+        )
+        .await;
+    }
+    progress.step("Program does not exist; proceeding with a fresh deployment");
+
+    preflight_deploy(rpc_client, deployer, &program_id, program_data, max_data_len, progress).await?;
+
+    progress.step("Creating program buffer");
+    let buffer_pubkey =
+        create_deploy_buffer(config, rpc_client, fees, deployer, &program_id, program_data, max_data_len, progress)
+            .await?;
+
+    progress.step("Writing program data to buffer");
+    write_program_data_chunked(rpc_client, fees, deployer, &buffer_pubkey, program_data, progress)
+        .await
+        .context(format!(
+            "Failed to write program data. Finish with `shield-deploy deploy --recover {program_id}`"
+        ))?;
+    set_buffer_status(config, &buffer_pubkey, BufferStatus::Ready)?;
+
+    progress.step("Deploying program from buffer");
+    finish_deploy(
+        config,
+        rpc_client,
+        fees,
+        deployer,
+        &program_id,
+        Some(program_keypair),
+        &buffer_pubkey,
+        program_data.len(),
+        max_data_len,
+        immutable,
+        progress,
+    )
+    .await
+}
+
+/// Verify that a program can be deployed (doesn't exist or is upgradeable)
+///
+/// Returns whether the program already exists.
+async fn verify_can_deploy(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    upgrade_authority: &Keypair,
+) -> Result<bool> {
+    match with_retry(|| rpc_client.get_account(program_id)).await {
+        Ok(account) => {
+            let loader_id_sdk = Pubkey::new_from_array(LOADER_ID.to_bytes());
+            // Program exists - verify it's upgradeable
+            if account.owner != loader_id_sdk {
+                return Err(anyhow!(
+                    "Program {program_id} exists but is not an upgradeable program"
+                ));
+            }
+
+            // Check if this is a valid program
+            match bincode::deserialize::<UpgradeableLoaderState>(&account.data) {
+                Ok(UpgradeableLoaderState::Program {
+                    programdata_address,
+                }) => {
+                    // Get ProgramData to check authority
+                    let programdata_address_pk = Pubkey::new_from_array(programdata_address.to_bytes());
+                    let programdata = with_retry(|| rpc_client.get_account(&programdata_address_pk)).await?;
+                    match bincode::deserialize::<UpgradeableLoaderState>(&programdata.data)? {
+                        UpgradeableLoaderState::ProgramData {
+                            upgrade_authority_address,
+                            ..
+                        } => {
+                            if upgrade_authority_address.is_none() {
+                                return Err(anyhow!(
+                                    "Program {program_id} is immutable and cannot be upgraded"
+                                ));
+                            }
+                            let expected_authority_v2 = SolanaPubkeyV2::new_from_array(upgrade_authority.pubkey().to_bytes());
+                            if upgrade_authority_address != Some(expected_authority_v2) {
+                                return Err(anyhow!(
+                                    "Authority mismatch. Expected {}, found {:?}",
+                                    upgrade_authority.pubkey(),
+                                    upgrade_authority_address
+                                ));
+                            }
+                            Ok(true) // Exists and can upgrade
+                        }
+                        _ => Err(anyhow!("Invalid ProgramData state")),
+                    }
+                }
+                _ => Err(anyhow!("Invalid program account")),
+            }
+        }
+        Err(_) => Ok(false), // Program doesn't exist - can deploy
+    }
+}
+
+/// Catch failures of the final deploy step before paying for the buffer write
+///
+/// The buffer doesn't exist yet, so the deploy can't be simulated to the end.
+/// Instead the ProgramData rent is checked against the balance, and two
+/// simulations against a placeholder buffer surface program logs up front:
+/// creating the buffer with the first write, and the deploy transaction up
+/// to the point where it reads the (still empty) buffer.
+async fn preflight_deploy(
+    rpc_client: &RpcClient,
+    deployer: &Keypair,
+    program_id: &Pubkey,
+    program_data: &[u8],
+    max_data_len: usize,
+    progress: &dyn Progress,
+) -> Result<()> {
+    progress.step("Simulating the deploy before writing");
+
+    let payer = fee_payer(deployer).pubkey();
+    let estimate = estimate_deploy(rpc_client, program_data.len(), max_data_len, 0).await?;
+    let required = if payer == deployer.pubkey() {
+        estimate.required_balance()
+    } else {
+        estimate.required_balance() - estimate.fee_budget()
+    };
+    let balance = balance_of(rpc_client, &deployer.pubkey()).await
+        .context("Failed to get deployer balance")?;
+    if balance < required {
+        anyhow::bail!(
+            "The deploy would run out of funds at the last step.\n\
+            ProgramData rent alone is {} for {max_data_len} bytes; the deployer has {} and needs {} (short by {}).\n\
+            Run `shield-deploy fund` before any buffer rent is spent.",
+            format_sol(estimate.programdata_rent),
+            format_sol(balance),
+            format_sol(required),
+            format_sol(required - balance)
+        );
+    }
+    progress.step("Balance covers buffer, ProgramData and program rent");
+
+    let placeholder = SolanaPubkeyV2::new_unique();
+    let deployer_v2 = SolanaPubkeyV2::new_from_array(deployer.pubkey().to_bytes());
+    let program_v2 = SolanaPubkeyV2::new_from_array(program_id.to_bytes());
+
+    // Buffer creation plus a slice of the first chunk: authority and rent problems show here
+    let mut write_instructions = bpf_loader_upgradeable::create_buffer(
+        &deployer_v2,
+        &placeholder,
+        &deployer_v2,
+        estimate.buffer_rent,
+        program_data.len(),
+    )?;
+    let first = &program_data[..program_data.len().min(PREFLIGHT_WRITE_BYTES)];
+    write_instructions.push(bpf_loader_upgradeable::write(&placeholder, &deployer_v2, 0, first.to_vec()));
+    let simulation = simulate_instructions(rpc_client, &payer, &to_sdk_instructions(write_instructions)).await?;
+    if simulation.err.is_some() {
+        anyhow::bail!(
+            "Simulating the buffer creation and first write failed; nothing was sent.{}",
+            simulation_report(&simulation)
+        );
+    }
+    progress.step("Buffer creation and first write simulate cleanly");
+
+    // Program account creation runs for real; the loader then stops at the empty buffer
+    let deploy_instructions = bpf_loader_upgradeable::deploy_with_max_program_len(
+        &deployer_v2,
+        &program_v2,
+        &placeholder,
+        &deployer_v2,
+        estimate.program_rent,
+        max_data_len,
+    )?;
+    let deploy_index = deploy_instructions.len() as u8 - 1;
+    let simulation = simulate_instructions(rpc_client, &payer, &to_sdk_instructions(deploy_instructions)).await?;
+    match &simulation.err {
+        None => {}
+        Some(TransactionError::InstructionError(index, _)) if *index == deploy_index => {}
+        Some(_) => anyhow::bail!(
+            "Simulating the deploy transaction failed; nothing was sent.{}",
+            simulation_report(&simulation)
+        ),
+    }
+    progress.step("Deploy transaction accepted up to the buffer read");
+
+    Ok(())
+}
+
+/// Convert loader instructions to the SDK's instruction type
+pub fn to_sdk_instructions(instructions: Vec<LoaderInstruction>) -> Vec<SdkInstruction> {
+    instructions
+        .into_iter()
+        .map(|ix| SdkInstruction {
+            program_id: Pubkey::from(ix.program_id.to_bytes()),
+            accounts: ix
+                .accounts
+                .iter()
+                .map(|acc| AccountMeta {
+                    pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                    is_signer: acc.is_signer,
+                    is_writable: acc.is_writable,
+                })
+                .collect(),
+            data: ix.data,
+        })
+        .collect()
+}
+
+/// Create the buffer for a fresh deploy and record it before any data is written
+///
+/// The record carries the program ID so `deploy --recover` can find the
+/// buffer again if the deploy is interrupted.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_deploy_buffer(
+    config: &Config,
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    deployer: &Keypair,
+    program_id: &Pubkey,
+    program_data: &[u8],
+    max_data_len: usize,
+    progress: &dyn Progress,
+) -> Result<Pubkey> {
+    let buffer_keypair = Keypair::new();
+    let buffer_pubkey = buffer_keypair.pubkey();
+    let signature = create_buffer_account(rpc_client, fees, deployer, &buffer_keypair, program_data.len()).await
+        .context("Failed to create buffer account")?;
+
+    progress.step(&format!("Buffer created: {signature}"));
+    progress.step(&format!("Buffer address: {buffer_pubkey}"));
+
+    let now = chrono::Utc::now().timestamp();
+    let mut state = config.load_state()?;
+    state.buffers.push(BufferRecord {
+        address: buffer_pubkey.to_string(),
+        status: BufferStatus::Writing,
+        created_at: now,
+        name: None,
+        artifact_hash: sha256_hex(program_data),
+        size: program_data.len(),
+        authority: deployer.pubkey().to_string(),
+        program_id: Some(program_id.to_string()),
+        purpose: Some(BufferPurpose::Deploy),
+    });
+    state.pending_deploy = Some(PendingDeploy {
+        program_id: program_id.to_string(),
+        buffer: buffer_pubkey.to_string(),
+        artifact_hash: sha256_hex(program_data),
+        size: program_data.len(),
+        started_at: now,
+        max_data_len: Some(max_data_len),
+    });
+    config.save_state(&state)?;
+
+    Ok(buffer_pubkey)
+}
+
+/// Forget the pending deploy once `program_id` has landed
+pub fn clear_pending_deploy(config: &Config, program_id: &Pubkey) -> Result<()> {
+    let mut state = config.load_state()?;
+    if state.pending_deploy.as_ref().is_some_and(|p| p.program_id == program_id.to_string()) {
+        state.pending_deploy = None;
+        config.save_state(&state)?;
+    }
+    Ok(())
+}
+
+/// Deploy a fully written buffer to `program_id`
+///
+/// The program account is created when its keypair is given. Without one the
+/// account must already exist (left by an interrupted deploy), so only
+/// `DeployWithMaxDataLen` is sent. With `immutable` the upgrade authority is
+/// dropped in the same transaction, so it never exists on-chain.
+#[allow(clippy::too_many_arguments)]
+pub async fn finish_deploy(
+    config: &Config,
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    deployer: &Keypair,
+    program_id: &Pubkey,
+    program_keypair: Option<&Keypair>,
+    buffer_pubkey: &Pubkey,
+    program_len: usize,
+    max_data_len: usize,
+    immutable: bool,
+    progress: &dyn Progress,
+) -> Result<Signature> {
+    let deployer_pubkey = deployer.pubkey();
+
+    // The loader funds ProgramData from the payer; we only fund the program account
+    let program_lamports = rpc_client
+        .get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_program()).await
+        .context("Failed to get rent exemption for program account")?;
+
+    let programdata_address = programdata_address(program_id);
+    let sdk_instructions = deploy_instructions(
+        &deployer_pubkey,
+        program_id,
+        buffer_pubkey,
+        program_lamports,
+        max_data_len,
+        program_keypair.is_some(),
+        immutable,
+    )?;
+
+    let payer = fee_payer(deployer);
+    let mut signers = vec![&*payer, deployer];
+    signers.extend(program_keypair);
+    signers.dedup_by_key(|k| k.pubkey());
+    let (recent_blockhash, instructions) = durable_blockhash(
+        rpc_client,
+        &signers,
+        with_compute_budget_for(rpc_client, fees, TxClass::Deploy, &payer.pubkey(), &sdk_instructions).await,
+    )
+    .await?;
+    let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+    transaction.sign(&signers, recent_blockhash);
+
+    let signature = match send(rpc_client, &transaction, progress).await {
+        Ok(signature) => signature,
+        Err(e) => {
+            reclaim_if_dropped(config, rpc_client, fees, deployer, deployer, buffer_pubkey, &transaction, progress).await;
+            return Err(e).context(format!(
+                "Failed to deploy program. Retry with `shield-deploy deploy --recover {program_id}`"
+            ));
+        }
+    };
+    set_buffer_status(config, buffer_pubkey, BufferStatus::Consumed)?;
+    clear_pending_deploy(config, program_id)?;
+
+    progress.step(&format!("Program deployed: {signature}"));
+    progress.step(&format!("ProgramData address: {programdata_address}"));
+
+    Ok(signature)
+}
+
+/// Instructions of the transaction [`finish_deploy`] sends
+///
+/// `create_program` keeps the system `create_account` for the program account;
+/// `immutable` appends a `SetAuthority` to none after the deploy.
+pub fn deploy_instructions(
+    deployer: &Pubkey,
+    program_id: &Pubkey,
+    buffer_pubkey: &Pubkey,
+    program_lamports: u64,
+    max_data_len: usize,
+    create_program: bool,
+    immutable: bool,
+) -> Result<Vec<SdkInstruction>> {
+    let deployer_v2 = SolanaPubkeyV2::new_from_array(deployer.to_bytes());
+    let buffer_v2 = SolanaPubkeyV2::new_from_array(buffer_pubkey.to_bytes());
+    let program_v2 = SolanaPubkeyV2::new_from_array(program_id.to_bytes());
+
+    // Deploy with upgradeable loader
+    let mut instructions = bpf_loader_upgradeable::deploy_with_max_program_len(
+        &deployer_v2,
+        &program_v2,
+        &buffer_v2,
+        &deployer_v2,
+        program_lamports,
+        max_data_len,
+    )?;
+    if !create_program {
+        // Drop the system create_account; DeployWithMaxDataLen is last
+        instructions.drain(..instructions.len() - 1);
+    }
+    if immutable {
+        let programdata_v2 = SolanaPubkeyV2::new_from_array(programdata_address(program_id).to_bytes());
+        instructions.push(bpf_loader_upgradeable::set_upgrade_authority(
+            &programdata_v2,
+            &deployer_v2,
+            None,
+        ));
+    }
+    Ok(to_sdk_instructions(instructions))
+}
+
+/// ProgramData account of an upgradeable program
+pub fn programdata_address(program_id: &Pubkey) -> Pubkey {
+    let loader_id_sdk = Pubkey::new_from_array(LOADER_ID.to_bytes());
+    Pubkey::find_program_address(&[program_id.as_ref()], &loader_id_sdk).0
+}
+
+/// Block time of the slot `program_id` was last deployed or upgraded in
+///
+/// `None` when the ProgramData account or the block time can't be read.
+pub async fn last_deployed_at(rpc_client: &RpcClient, program_id: &Pubkey) -> Option<i64> {
+    let programdata = with_retry(|| rpc_client.get_account(&programdata_address(program_id))).await.ok()?;
+    let UpgradeableLoaderState::ProgramData { slot, .. } = bincode::deserialize(&programdata.data).ok()? else {
+        return None;
+    };
+    with_retry(|| rpc_client.get_block_time(slot)).await.ok()
+}
+
+/// Upgrade `program_id` with `new_program_data`, refunding the buffer to `spill`
+///
+/// `payer` funds and writes the buffer; `upgrade_authority` only signs the
+/// upgrade. A buffer whose upgrade is confirmed to have failed is closed
+/// again so its rent isn't stranded.
+#[allow(clippy::too_many_arguments)]
+pub async fn upgrade_program(
+    config: &Config,
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    payer: &Keypair,
+    upgrade_authority: &Keypair,
+    program_id: &Pubkey,
+    new_program_data: &[u8],
+    spill: &Pubkey,
+    progress: &dyn Progress,
+) -> Result<Signature> {
+    let buffer_pubkey = write_upgrade_buffer(
+        config,
+        rpc_client,
+        fees,
+        payer,
+        upgrade_authority,
+        program_id,
+        new_program_data,
+        progress,
+    )
+    .await?;
+
+    progress.step("Upgrading program");
+    let transaction =
+        upgrade_transaction(rpc_client, fees, payer, upgrade_authority, program_id, &buffer_pubkey, spill).await?;
+
+    // The buffer is fully written here; a failed upgrade shouldn't strand its rent
+    let signature = match send(rpc_client, &transaction, progress).await {
+        Ok(signature) => signature,
+        Err(e) => {
+            reclaim_if_dropped(config, rpc_client, fees, payer, upgrade_authority, &buffer_pubkey, &transaction, progress)
+                .await;
+            return Err(e).context("Failed to upgrade program");
+        }
+    };
+    set_buffer_status(config, &buffer_pubkey, BufferStatus::Consumed)?;
+
+    progress.step(&format!("Program upgraded: {signature}"));
+    Ok(signature)
+}
+
+/// Close `buffer_pubkey` once `transaction`, which would have consumed it, can't land
+///
+/// A send error alone doesn't mean the transaction failed: it may have timed
+/// out while still landing, and closing the buffer under it would fail the
+/// deploy. The buffer is kept unless the failure is confirmed.
+#[allow(clippy::too_many_arguments)]
+async fn reclaim_if_dropped(
+    config: &Config,
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    payer: &Keypair,
+    authority: &Keypair,
+    buffer_pubkey: &Pubkey,
+    transaction: &Transaction,
+    progress: &dyn Progress,
+) {
+    progress.step("Checking whether the transaction can still land");
+    if never_lands(rpc_client, transaction).await {
+        reclaim_failed_buffer(config, rpc_client, fees, payer, authority, buffer_pubkey, progress).await;
+    } else {
+        progress.step(&format!("Buffer {buffer_pubkey} kept, since the transaction may still land"));
+    }
+}
+
+/// Create and fill an upgrade buffer, handing it to `upgrade_authority`
+///
+/// `payer` funds and writes the buffer; the authority only signs the upgrade.
+#[allow(clippy::too_many_arguments)]
+pub async fn write_upgrade_buffer(
+    config: &Config,
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    payer: &Keypair,
+    upgrade_authority: &Keypair,
+    program_id: &Pubkey,
+    new_program_data: &[u8],
+    progress: &dyn Progress,
+) -> Result<Pubkey> {
+    let authority_pubkey = upgrade_authority.pubkey();
+    let programdata_address = programdata_address(program_id);
+
+    progress.step(&format!("ProgramData address: {programdata_address}"));
+
+    verify_upgrade_authority(rpc_client, &programdata_address, &authority_pubkey)
+        .await
+        .context("Authority verification failed")?;
+    progress.step("Upgrade authority verified");
+
+    progress.step("Creating upgrade buffer");
+
+    let buffer_keypair = Keypair::new();
+    let buffer_pubkey = buffer_keypair.pubkey();
+
+    // Calculate required size for buffer
+    let buffer_size = UpgradeableLoaderState::size_of_buffer(new_program_data.len());
+    let buffer_lamports = rpc_client
+        .get_minimum_balance_for_rent_exemption(buffer_size).await
+        .context("Failed to get rent exemption for buffer")?;
+
+    // The payer funds and writes the buffer; the authority only signs the upgrade
+    let create_buffer_ix = system_instruction::create_account(
+        &Address::from(payer.pubkey().to_bytes()),
+        &Address::from(buffer_pubkey.to_bytes()),
+        buffer_lamports,
+        buffer_size as u64,
+        &Address::from(LOADER_ID.to_bytes()),
+    );
+    let sdk_instruction = SdkInstruction {
+        program_id: Pubkey::from(create_buffer_ix.program_id.to_bytes()),
+        accounts: create_buffer_ix
+            .accounts
+            .iter()
+            .map(|acc| AccountMeta {
+                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            })
+            .collect(),
+        data: create_buffer_ix.data,
+    };
+
+    // `payer` funds the buffer rent; a --fee-payer only covers the fee
+    let fee_payer = fee_payer(payer);
+    let recent_blockhash = with_retry(|| rpc_client.get_latest_blockhash()).await?;
+    let mut transaction = Transaction::new_with_payer(
+        &with_compute_budget_for(rpc_client, fees, TxClass::CreateBuffer, &fee_payer.pubkey(), &[sdk_instruction]).await,
+        Some(&fee_payer.pubkey()),
+    );
+    let mut signers = vec![&*fee_payer, payer, &buffer_keypair];
+    signers.dedup_by_key(|k| k.pubkey());
+    transaction.sign(&signers, recent_blockhash);
+
+    let signature = send_and_confirm(rpc_client, &transaction).await
+        .context("Failed to create buffer account")?;
+    note_buffer_signature(&signature);
+
+    progress.step(&format!("Buffer created: {signature}"));
+
+    // Recorded before the write so a failed upgrade leaves a trace in `shield-deploy buffers`
+    let mut state = config.load_state()?;
+    state.buffers.push(BufferRecord {
+        address: buffer_pubkey.to_string(),
+        status: BufferStatus::Writing,
+        created_at: chrono::Utc::now().timestamp(),
+        name: None,
+        artifact_hash: sha256_hex(new_program_data),
+        size: new_program_data.len(),
+        authority: payer.pubkey().to_string(),
+        program_id: Some(program_id.to_string()),
+        purpose: Some(BufferPurpose::Upgrade),
+    });
+    config.save_state(&state)?;
+
+    progress.step("Writing new program data");
+    write_program_data_chunked(rpc_client, fees, payer, &buffer_pubkey, new_program_data, progress)
+        .await
+        .context("Failed to write program data")?;
+
+    if payer.pubkey() != authority_pubkey {
+        set_buffer_authority(rpc_client, fees, payer, &buffer_pubkey, &authority_pubkey).await
+            .context("Failed to hand the buffer to the program authority")?;
+    }
+
+    let mut state = config.load_state()?;
+    if let Some(record) = state.buffers.iter_mut().find(|b| b.address == buffer_pubkey.to_string()) {
+        record.status = BufferStatus::Ready;
+        record.authority = authority_pubkey.to_string();
+        config.save_state(&state)?;
+    }
+
+    Ok(buffer_pubkey)
+}
+
+/// Upgrade `program_id` from a ready buffer, with a fresh blockhash
+#[allow(clippy::too_many_arguments)]
+pub async fn send_upgrade(
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    payer: &Keypair,
+    upgrade_authority: &Keypair,
+    program_id: &Pubkey,
+    buffer_pubkey: &Pubkey,
+    spill: &Pubkey,
+    progress: &dyn Progress,
+) -> Result<Signature> {
+    progress.step("Upgrading program");
+
+    let transaction = upgrade_transaction(rpc_client, fees, payer, upgrade_authority, program_id, buffer_pubkey, spill).await?;
+    let signature = send(rpc_client, &transaction, progress).await
+        .context("Failed to upgrade program")?;
+
+    progress.step(&format!("Program upgraded: {signature}"));
+    Ok(signature)
+}
+
+/// Signed `Upgrade` transaction, refunding the buffer to `spill`
+pub async fn upgrade_transaction(
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    payer: &Keypair,
+    upgrade_authority: &Keypair,
+    program_id: &Pubkey,
+    buffer_pubkey: &Pubkey,
+    spill: &Pubkey,
+) -> Result<Transaction> {
+    let upgrade_ix = bpf_loader_upgradeable::upgrade(
+        &SolanaPubkeyV2::new_from_array(program_id.to_bytes()),
+        &SolanaPubkeyV2::new_from_array(buffer_pubkey.to_bytes()),
+        &SolanaPubkeyV2::new_from_array(upgrade_authority.pubkey().to_bytes()),
+        &SolanaPubkeyV2::new_from_array(spill.to_bytes()),
+    );
+    let sdk_instructions = to_sdk_instructions(vec![upgrade_ix]);
+
+    let fee_payer = fee_payer(payer);
+    let mut signers = vec![&*fee_payer, payer, upgrade_authority];
+    signers.dedup_by_key(|k| k.pubkey());
+    let (recent_blockhash, instructions) = durable_blockhash(
+        rpc_client,
+        &signers,
+        with_compute_budget_for(rpc_client, fees, TxClass::Upgrade, &fee_payer.pubkey(), &sdk_instructions).await,
+    )
+    .await?;
+    let mut transaction = Transaction::new_with_payer(&instructions, Some(&fee_payer.pubkey()));
+    transaction.sign(&signers, recent_blockhash);
+
+    Ok(transaction)
+}
+
+/// Verify that the current authority matches expected authority
+async fn verify_upgrade_authority(
+    rpc_client: &RpcClient,
+    programdata_address: &Pubkey,
+    expected_authority: &Pubkey,
+) -> Result<()> {
+    let account = with_retry(|| rpc_client.get_account(programdata_address)).await
+        .context("ProgramData account not found")?;
+
+    // Parse ProgramData account
+    let programdata_state = bincode::deserialize::<UpgradeableLoaderState>(&account.data)
+        .context("Failed to deserialize ProgramData")?;
+
+    match programdata_state {
+        UpgradeableLoaderState::ProgramData {
+            upgrade_authority_address,
+            slot: _,
+        } => {
+            if let Some(authority) = upgrade_authority_address {
+                if authority.to_bytes() == expected_authority.to_bytes() {
+                    Ok(())
+                } else {
+                    anyhow::bail!(
+                        "Upgrade authority mismatch.\n\
+                        Expected: {expected_authority}\n\
+                        Found: {authority}"
+                    )
+                }
+            } else {
+                anyhow::bail!("Program is not upgradeable (authority set to None)")
+            }
+        }
+        _ => anyhow::bail!("Invalid ProgramData account state"),
+    }
+}
+
+/// Hand a buffer to a new authority (the current authority must sign)
+pub async fn set_buffer_authority(
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    current_authority: &Keypair,
+    buffer_pubkey: &Pubkey,
+    new_authority: &Pubkey,
+) -> Result<Signature> {
+    let set_authority_ix = bpf_loader_upgradeable::set_buffer_authority(
+        &SolanaPubkeyV2::new_from_array(buffer_pubkey.to_bytes()),
+        &SolanaPubkeyV2::new_from_array(current_authority.pubkey().to_bytes()),
+        &SolanaPubkeyV2::new_from_array(new_authority.to_bytes()),
+    );
+    let sdk_instructions = to_sdk_instructions(vec![set_authority_ix]);
+
+    let payer = fee_payer(current_authority);
+    let recent_blockhash = with_retry(|| rpc_client.get_latest_blockhash()).await?;
+    let mut transaction = Transaction::new_with_payer(
+        &with_compute_budget_for(rpc_client, fees, TxClass::SetAuthority, &payer.pubkey(), &sdk_instructions).await,
+        Some(&payer.pubkey()),
+    );
+    let mut signers = vec![&*payer, current_authority];
+    signers.dedup_by_key(|k| k.pubkey());
+    transaction.sign(&signers, recent_blockhash);
+
+    send_and_confirm(rpc_client, &transaction).await
+        .context("Failed to set buffer authority")
+}
+
+/// Remove `program_id`'s upgrade authority, with the fee paid by `payer`
+///
+/// This cannot be undone. The authority is checked before sending and the
+/// program is read back afterwards to confirm it is immutable.
+pub async fn finalize_program(
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    payer: &Keypair,
+    current_authority: &Keypair,
+    program_id: &Pubkey,
+    progress: &dyn Progress,
+) -> Result<Signature> {
+    let programdata_address = programdata_address(program_id);
+    progress.step(&format!("ProgramData: {programdata_address}"));
+
+    // Verify we currently control this program
+    verify_current_authority(rpc_client, &programdata_address, current_authority).await?;
+    progress.step("Authority verified: you control this program");
+
+    // None instead of Some(pubkey) is what makes the program immutable
+    let set_authority_ix = bpf_loader_upgradeable::set_upgrade_authority(
+        &SolanaPubkeyV2::new_from_array(programdata_address.to_bytes()),
+        &SolanaPubkeyV2::new_from_array(current_authority.pubkey().to_bytes()),
+        None,
+    );
+    let sdk_instructions = to_sdk_instructions(vec![set_authority_ix]);
+
+    let mut signers = vec![payer, current_authority];
+    signers.dedup_by_key(|k| k.pubkey());
+    let (recent_blockhash, instructions) = durable_blockhash(
+        rpc_client,
+        &signers,
+        with_compute_budget_for(rpc_client, fees, TxClass::SetAuthority, &payer.pubkey(), &sdk_instructions).await,
+    )
+    .await?;
+    let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+    transaction.sign(&signers, recent_blockhash);
+
+    let signature = send_and_confirm(rpc_client, &transaction).await
+        .context("Failed to finalize program")?;
+    progress.step(&format!("Transaction confirmed: {signature}"));
+
+    verify_immutable(rpc_client, &programdata_address).await?;
+    progress.step("Verified: program is now immutable");
+
+    Ok(signature)
+}
+
+/// Verify we control the program before finalizing
+async fn verify_current_authority(
+    rpc_client: &RpcClient,
+    programdata_address: &Pubkey,
+    expected_authority: &Keypair,
+) -> Result<()> {
+    let account = with_retry(|| rpc_client.get_account(programdata_address)).await
+        .context("ProgramData account not found")?;
+
+    let programdata_state: UpgradeableLoaderState = bincode::deserialize(&account.data)
+        .context("Failed to deserialize ProgramData")?;
+
+    match programdata_state {
+        UpgradeableLoaderState::ProgramData {
+            upgrade_authority_address,
+            slot: _,
+        } => {
+            if let Some(authority) = upgrade_authority_address {
+                let expected_pubkey = expected_authority.pubkey();
+
+                if authority.to_bytes() == expected_pubkey.to_bytes() {
+                    Ok(())
+                } else {
+                    anyhow::bail!(
+                        "Authority mismatch.\n\
+                        Expected: {expected_pubkey}\n\
+                        Found: {authority}\n\
+                        You do not control this program."
+                    )
+                }
+            } else {
+                anyhow::bail!("Program is already immutable")
+            }
+        }
+        _ => anyhow::bail!("Invalid ProgramData account state"),
+    }
+}
+
+/// Verify the program is now immutable
+async fn verify_immutable(
+    rpc_client: &RpcClient,
+    programdata_address: &Pubkey,
+) -> Result<()> {
+    let account = with_retry(|| rpc_client.get_account(programdata_address)).await
+        .context("ProgramData account not found")?;
+
+    let programdata_state: UpgradeableLoaderState = bincode::deserialize(&account.data)
+        .context("Failed to deserialize ProgramData")?;
+
+    match programdata_state {
+        UpgradeableLoaderState::ProgramData {
+            upgrade_authority_address,
+            slot: _,
+        } => {
+            if upgrade_authority_address.is_none() {
+                Ok(())
+            } else {
+                anyhow::bail!(
+                    "Finalization failed: authority is still set to {upgrade_authority_address:?}"
+                )
+            }
+        }
+        _ => anyhow::bail!("Invalid ProgramData account state"),
+    }
+}
+
+/// Send and confirm, with a spinner when `progress` draws on a terminal
+async fn send(rpc_client: &RpcClient, transaction: &Transaction, progress: &dyn Progress) -> Result<Signature> {
+    if progress.interactive() {
+        send_and_confirm_with_spinner(rpc_client, transaction).await
+    } else {
+        send_and_confirm(rpc_client, transaction).await
+    }
+}
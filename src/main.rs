@@ -1,68 +1,1029 @@
-use clap::{Parser, Subcommand, arg, command};
+use clap::{CommandFactory, Parser, Subcommand, ValueHint, arg, command};
+use std::io::IsTerminal;
+use std::path::PathBuf;
 use anyhow::Result;
 
+// The commands refer to the library's modules through `crate::`
+use shield_deploy::{
+    artifact, bundle, compute, config, cost, hygiene, loader, network, pool, privacy, project, proxy, security_txt,
+    update, utils,
+};
+
 mod commands;
-mod config;
-mod privacy;
-mod utils;
+
+use shield_deploy::compute::PriorityFee;
+use shield_deploy::notify::with_notification;
+use shield_deploy::update::BackgroundCheck;
 
 #[derive(Parser)]
 #[command(name = "shield-deploy")]
 #[command(version)]
 #[command(about = "Privacy-preserving Solana program deployment", long_about = None)]
+#[command(after_long_help = ENVIRONMENT_HELP)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Print transaction details such as compute limits and fees
+    #[arg(short, long, global = true)]
+    verbose: bool,
+    /// Accept every confirmation (for CI and scripts)
+    #[arg(short = 'y', long, global = true)]
+    yes: bool,
+    /// Retries of a transient RPC failure (timeout, rate limit, unhealthy node)
+    #[arg(long, global = true, default_value_t = shield_deploy::utils::DEFAULT_MAX_RETRIES)]
+    max_retries: usize,
+    /// Move the project to the RPC's cluster when it differs from the one in state
+    #[arg(long, global = true)]
+    switch_network: bool,
+    /// Accept mainnet-beta transactions without the typed confirmation
+    #[arg(long, global = true)]
+    mainnet: bool,
+    /// Look for built .so files here instead of target/deploy
+    #[arg(long, global = true, value_name = "DIR", value_hint = ValueHint::DirPath)]
+    artifact_dir: Option<PathBuf>,
+    /// Commitment level for RPC reads and confirmations [default: `commitment` config, else confirmed]
+    #[arg(long, global = true, value_name = "LEVEL", value_parser = shield_deploy::config::COMMITMENTS.to_vec())]
+    commitment: Option<String>,
+    /// Poll for confirmations over HTTP instead of subscribing on the RPC's websocket (implied by a proxy)
+    #[arg(long, global = true)]
+    no_websocket: bool,
 }
 
+const ENVIRONMENT_HELP: &str = "\
+Environment:
+  SHIELD_DEPLOYER_KEY  Deployer secret key (base58 or JSON byte array) used
+                       instead of .shield/deployer.json. Meant for ephemeral
+                       CI deployers injected from a secret store: the key is
+                       never written to disk and decoded copies are zeroized.
+                       Environment variables can still leak through process
+                       listings, crash dumps and CI logs, so scope it to the
+                       deploy step. `init` records state without a key file;
+                       `rotate` and `import-deployer` refuse while it is set.";
+
 #[derive(Subcommand)]
 enum Commands {
     /// Initialize a private deployer for this project
-    Init,
+    ///
+    /// With SHIELD_DEPLOYER_KEY set, only project state is created and the
+    /// key stays in the environment.
+    Init {
+        /// Continue even though key files under .shield are tracked by git
+        #[arg(long)]
+        unsafe_ignore_git: bool,
+    },
+    /// Walk through cluster, init, funding, build and deploy interactively
+    ///
+    /// Resumes where it left off when run again after an interruption.
+    Quickstart,
     /// Fund the private deployer through Privacy Cash
-    Fund,
+    Fund {
+        /// Airdrop from the faucet instead (devnet or localnet only, NOT private)
+        #[arg(long)]
+        airdrop: bool,
+        /// Hold back a random remainder so deposit and receipt amounts differ
+        #[arg(long, conflicts_with = "no_perturb")]
+        perturb: bool,
+        /// Deliver the full amount even on mainnet
+        #[arg(long)]
+        no_perturb: bool,
+        /// Allow exceeding the network's spend_limit (asks for the network name)
+        #[arg(long)]
+        override_spend_limit: bool,
+        /// Shield now and decompress after the privacy delay in a background worker
+        #[arg(long, conflicts_with = "airdrop")]
+        detach: bool,
+        /// Decompress balances already shielded in project vaults instead of depositing
+        #[arg(long, conflicts_with_all = ["airdrop", "detach"])]
+        from_compressed: bool,
+        /// Run detached funding plans that are due and have no live worker
+        #[arg(long, conflicts_with_all = ["airdrop", "detach"])]
+        resume: bool,
+        /// Worker entry point for a detached plan
+        #[arg(long, hide = true)]
+        run_plan: Option<String>,
+        /// Continue even though key files under .shield are tracked by git
+        #[arg(long)]
+        unsafe_ignore_git: bool,
+        /// Priority fee in micro-lamports per compute unit, or `auto` from recent network fees
+        #[arg(long, value_name = "MICROLAMPORTS|auto")]
+        priority_fee: Option<PriorityFee>,
+        /// Send without the RPC's preflight simulation
+        #[arg(long)]
+        skip_preflight: bool,
+        #[command(subcommand)]
+        action: Option<commands::fund::FundAction>,
+    },
     /// Deploy a program using the private deployer
     Deploy {
-        /// Path to the program .so file
-        #[arg(short, long)]
+        /// Program .so: a path, `-` for stdin, or an https:// URL
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
         program: Option<String>,
+        /// Expected SHA-256 of the program (required for URLs)
+        #[arg(long, visible_alias = "sha256", value_name = "HEX")]
+        program_sha256: Option<String>,
+        /// Continue even though key files under .shield are tracked by git
+        #[arg(long)]
+        unsafe_ignore_git: bool,
+        /// Hand upgrade authority to a new key dedicated to this program
+        #[arg(long)]
+        isolate_authority: bool,
+        /// Allow exceeding the network's spend_limit (asks for the network name)
+        #[arg(long)]
+        override_spend_limit: bool,
+        /// Don't POST the result to notify_webhook
+        #[arg(long)]
+        no_notify: bool,
+        /// Pay transaction fees from this keypair instead of the deployer (links it on-chain)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        fee_payer: Option<PathBuf>,
+        /// Priority fee in micro-lamports per compute unit, or `auto` from recent network fees
+        #[arg(long, value_name = "MICROLAMPORTS|auto")]
+        priority_fee: Option<PriorityFee>,
+        /// Send without the RPC's preflight simulation
+        #[arg(long)]
+        skip_preflight: bool,
+        /// Finish an interrupted deploy of this program ID
+        #[arg(long, value_name = "PROGRAM_ID")]
+        recover: Option<String>,
+        /// Skip closing stale buffers (auto_close_buffers_after) after this run
+        #[arg(long)]
+        no_auto_clean: bool,
+        /// Buffer write transactions kept in flight at once (default 8)
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: Option<u16>,
+        /// Bytes per buffer write, below the computed maximum (for flaky RPCs)
+        #[arg(long, value_name = "BYTES")]
+        chunk_size: Option<usize>,
+        /// Send buffer writes straight to the upcoming leaders over QUIC instead of through the RPC (off with a proxy)
+        #[arg(long)]
+        use_tpu: bool,
+        /// Sign the final deploy/upgrade transaction against this durable nonce account (see `nonce create`)
+        #[arg(long, value_name = "PUBKEY")]
+        nonce: Option<String>,
+        /// Close the buffer of an interrupted deploy and start over
+        #[arg(long, conflicts_with = "recover")]
+        fresh: bool,
+        /// Program keypair (defaults to target/deploy/<name>-keypair.json next to the .so)
+        #[arg(long, value_hint = ValueHint::FilePath, conflicts_with = "recover")]
+        program_keypair: Option<PathBuf>,
+        /// ProgramData capacity in bytes (defaults to 2x the program; fixed until extended)
+        #[arg(long, value_name = "BYTES", conflicts_with_all = ["recover", "max_len_multiplier"])]
+        max_len: Option<usize>,
+        /// ProgramData capacity as a multiple of the program size, e.g. 1.5
+        #[arg(long, value_name = "FACTOR", conflicts_with = "recover")]
+        max_len_multiplier: Option<f64>,
+        /// Print the cost breakdown and balance check without sending anything
+        #[arg(long, conflicts_with_all = ["recover", "fresh"])]
+        dry_run: bool,
+        /// Deploy from this already written buffer instead of writing a new one
+        #[arg(long, value_name = "ADDRESS", conflicts_with_all = ["recover", "fresh"])]
+        buffer: Option<String>,
+        /// Deploy every program in target/deploy/ that isn't deployed on this network yet
+        #[arg(
+            long,
+            conflicts_with_all = ["program", "program_sha256", "program_keypair", "recover", "fresh", "max_len", "buffer", "name"]
+        )]
+        all: bool,
+        /// Deploy target/deploy/<NAME>.so from the workspace
+        #[arg(long, conflicts_with_all = ["program", "program_sha256"])]
+        name: Option<String>,
+        /// Make the program immutable in the deploy transaction (asks for the program ID)
+        #[arg(long = "final", conflicts_with_all = ["isolate_authority", "all"])]
+        finalize: bool,
+        /// Skip the typed program ID confirmation of --final; required with --yes
+        #[arg(long, requires = "finalize")]
+        force_finalize: bool,
+        /// Top up a short deployer balance through Privacy Cash and carry on
+        #[arg(long, conflicts_with = "dry_run")]
+        auto_fund: bool,
+        /// Grind a program ID starting with this base58 prefix (any case) before deploying
+        #[arg(long, value_name = "PREFIX", conflicts_with_all = ["program_keypair", "recover", "all"])]
+        vanity_prefix: Option<String>,
+        /// Deploy even though the same binary is already deployed on this network
+        #[arg(long, conflicts_with_all = ["recover", "all"])]
+        duplicate: bool,
+        /// Point declare_id! and Anchor.toml at the new program ID afterwards (asks first)
+        #[arg(long, conflicts_with = "dry_run")]
+        sync_keys: bool,
+        /// Publish the Anchor IDL on-chain after the deploy (init, or upgrade if it exists)
+        #[arg(long, conflicts_with = "dry_run")]
+        with_idl: bool,
+        /// IDL JSON for --with-idl (defaults to target/idl/<name>.json)
+        #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath, requires = "with_idl", conflicts_with = "all")]
+        idl: Option<PathBuf>,
     },
     /// Upgrade an existing program
     Upgrade {
-        program_id_str: String,
+        /// Program to upgrade (asks when several are deployed and none is given)
+        #[arg(value_name = "PROGRAM_ID")]
+        program_id_str: Option<String>,
+        /// Program to upgrade, as an alternative to the positional ID
+        #[arg(long = "program-id", value_name = "PUBKEY", conflicts_with = "program_id_str")]
+        program_id: Option<String>,
+        /// Upgrade the program deployed as NAME, from target/deploy/<NAME>.so unless --program is given
+        #[arg(long, conflicts_with_all = ["program_id_str", "program_id"])]
+        name: Option<String>,
+        /// Program .so: a path, `-` for stdin, or an https:// URL
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        program: Option<String>,
+        /// Expected SHA-256 of the program (required for URLs)
+        #[arg(long, visible_alias = "sha256", value_name = "HEX")]
+        program_sha256: Option<String>,
+        /// Refund the buffer to a fresh key and compress it into the privacy pool
+        #[arg(long)]
+        spill_compressed: bool,
+        /// Refund the buffer rent to this funded wallet instead of the deployer
+        #[arg(long, value_name = "PUBKEY", conflicts_with = "spill_compressed")]
+        spill: Option<String>,
+        /// Allow exceeding the network's spend_limit (asks for the network name)
+        #[arg(long)]
+        override_spend_limit: bool,
+        /// Don't POST the result to notify_webhook
+        #[arg(long)]
+        no_notify: bool,
+        /// Pay transaction fees from this keypair instead of the deployer (links it on-chain)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        fee_payer: Option<PathBuf>,
+        /// Priority fee in micro-lamports per compute unit, or `auto` from recent network fees
+        #[arg(long, value_name = "MICROLAMPORTS|auto")]
+        priority_fee: Option<PriorityFee>,
+        /// Send without the RPC's preflight simulation
+        #[arg(long)]
+        skip_preflight: bool,
+        /// Skip closing stale buffers (auto_close_buffers_after) after this run
+        #[arg(long)]
+        no_auto_clean: bool,
+        /// Buffer write transactions kept in flight at once (default 8)
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: Option<u16>,
+        /// Bytes per buffer write, below the computed maximum (for flaky RPCs)
+        #[arg(long, value_name = "BYTES")]
+        chunk_size: Option<usize>,
+        /// Send buffer writes straight to the upcoming leaders over QUIC instead of through the RPC (off with a proxy)
+        #[arg(long)]
+        use_tpu: bool,
+        /// Sign the final deploy/upgrade transaction against this durable nonce account (see `nonce create`)
+        #[arg(long, value_name = "PUBKEY")]
+        nonce: Option<String>,
+        /// Write the buffer now and send the upgrade at this time (RFC 3339, e.g. 2024-07-01T14:00:00Z)
+        #[arg(long, value_name = "TIME", conflicts_with = "at_slot")]
+        at: Option<String>,
+        /// Write the buffer now and send the upgrade once the cluster reaches this slot
+        #[arg(long, value_name = "SLOT")]
+        at_slot: Option<u64>,
+        /// Continue a scheduled upgrade interrupted by Ctrl-C or a crash
+        #[arg(long, conflicts_with_all = ["program", "program_sha256", "at", "at_slot", "spill_compressed", "spill"])]
+        resume: bool,
+        /// Abort instead of extending ProgramData when the new build doesn't fit
+        #[arg(long)]
+        no_extend: bool,
+        /// Publish the Anchor IDL on-chain after the upgrade (init, or upgrade if it exists)
+        #[arg(long, conflicts_with_all = ["at", "at_slot", "resume"])]
+        with_idl: bool,
+        /// IDL JSON for --with-idl (defaults to target/idl/<name>.json)
+        #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath, requires = "with_idl")]
+        idl: Option<PathBuf>,
     },
     /// Show deployer status and balance
-    Status,
+    Status {
+        /// Print structured JSON output, including the hygiene indicators
+        #[arg(long)]
+        json: bool,
+        /// Print Prometheus/OpenMetrics gauges instead
+        #[arg(long, conflicts_with = "json")]
+        metrics: bool,
+        /// Write the metrics to this file (for the node_exporter textfile collector)
+        #[arg(long, value_hint = ValueHint::FilePath, conflicts_with = "json")]
+        textfile: Option<PathBuf>,
+        /// Re-check each program's on-chain bytecode against the recorded hash
+        #[arg(long, conflicts_with_all = ["json", "metrics", "textfile"])]
+        verify: bool,
+    },
+    /// Print the deployer balance (single RPC call, script friendly)
+    Balance {
+        /// Print the raw balance in lamports
+        #[arg(long, conflicts_with = "json")]
+        lamports: bool,
+        /// Print structured JSON output
+        #[arg(long)]
+        json: bool,
+        /// Exit non-zero when the balance is below this amount: `2.5`, `2.5 sol` or `1500000000 lamports`
+        #[arg(long, value_name = "AMOUNT", value_parser = |s: &str| shield_deploy::utils::parse_amount(s, false))]
+        min: Option<shield_deploy::utils::Amount>,
+    },
+    /// List programs managed by this project
+    List {
+        /// Re-check authority and immutability on chain
+        #[arg(long)]
+        refresh: bool,
+        /// Print structured JSON output
+        #[arg(long)]
+        json: bool,
+        /// Sort order
+        #[arg(long, value_enum)]
+        sort: Option<commands::list::ListSort>,
+        /// Reverse the sort order
+        #[arg(long)]
+        reverse: bool,
+    },
+    /// Diagnose environment problems
+    Doctor {
+        /// Print structured JSON output
+        #[arg(long)]
+        json: bool,
+        /// Close the deployer's stale loader buffers (older than auto_close_buffers_after, default 7d)
+        #[arg(long, conflicts_with = "json")]
+        fix: bool,
+    },
+    /// Export the deployer, state, and config as an encrypted bundle
+    ExportDeployer {
+        /// Output path for the bundle
+        #[arg(value_hint = ValueHint::FilePath)]
+        out: PathBuf,
+    },
+    /// Import a deployer from an encrypted bundle
+    ImportDeployer {
+        /// Path to the bundle
+        #[arg(value_hint = ValueHint::FilePath)]
+        path: PathBuf,
+        /// Replace an existing deployer
+        #[arg(long)]
+        force: bool,
+    },
+    /// Show everything the tool has done in this project
+    History {
+        /// Only show operations for this program ID
+        #[arg(long)]
+        program: Option<String>,
+        /// Only show operations on or after this date (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+        /// Print structured JSON output
+        #[arg(long)]
+        json: bool,
+        /// Spot-check recent signatures against the chain
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Show everything known about a program
+    Show {
+        /// Program ID to inspect
+        program_id: String,
+        /// Print structured JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Explain what a transaction did in terms of this project's keys
+    Tx {
+        /// Transaction signature
+        signature: String,
+        /// Print structured JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Publish or upgrade an Anchor IDL with the private deployer
+    Idl {
+        #[command(subcommand)]
+        action: commands::idl::IdlAction,
+    },
+    /// Write a program artifact into a reusable buffer account
+    WriteBuffer {
+        /// Path to program .so file
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        program: Option<String>,
+        /// Hand the finished buffer to this authority (e.g. a multisig)
+        #[arg(long)]
+        authority: Option<String>,
+    },
+    /// Show a buffer's authority, payload hash and matching local build
+    InspectBuffer {
+        /// Buffer account address
+        buffer_pubkey: String,
+        /// Print structured JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// List buffers this project created, with their on-chain balance
+    Buffers {
+        /// Close this buffer and refund its rent to the deployer (repeatable)
+        #[arg(long, value_name = "ADDRESS")]
+        close: Vec<String>,
+        /// Include consumed and closed buffers
+        #[arg(long, conflicts_with = "close")]
+        all: bool,
+    },
+    /// Write an upgrade buffer and hand it to a governance-controlled authority
+    PrepareUpgrade {
+        /// Program ID to upgrade
+        program_id: String,
+        /// Path to program .so file
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        program: Option<String>,
+        /// Account receiving the buffer's lamports on upgrade (defaults to the upgrade authority)
+        #[arg(long)]
+        spill: Option<String>,
+        /// Where to write the proposal file (defaults to .shield/proposals/)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        out: Option<PathBuf>,
+    },
+    /// Estimate the SOL cost of a deploy or upgrade
+    Estimate {
+        /// Path to program .so file
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        program: Option<String>,
+        /// Estimate an upgrade of this existing program instead of a fresh deploy
+        #[arg(long)]
+        program_id: Option<String>,
+        /// ProgramData capacity in bytes for a fresh deploy (defaults to 2x the artifact)
+        #[arg(long, conflicts_with = "program_id")]
+        max_len: Option<usize>,
+        /// Priority fee in micro-lamports per compute unit, or `auto` (defaults to the `priority_fee` config)
+        #[arg(long, value_name = "MICROLAMPORTS|auto")]
+        priority_fee: Option<PriorityFee>,
+        /// Print structured JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Read and write project or global settings
+    Config {
+        #[command(subcommand)]
+        action: commands::config::ConfigAction,
+    },
+    /// List or restore automatic backups of state.json
+    State {
+        #[command(subcommand)]
+        action: commands::state::StateAction,
+    },
+    /// Remove caches and optionally reset project data
+    Clean {
+        /// Remove the Privacy Cash circuit files
+        #[arg(long)]
+        circuits: bool,
+        /// Remove command logs
+        #[arg(long)]
+        logs: bool,
+        /// Forget unfinished buffer writes
+        #[arg(long)]
+        pending: bool,
+        /// Remove generated upgrade proposals
+        #[arg(long)]
+        artifacts: bool,
+        /// Remove the automatic state.json backups
+        #[arg(long)]
+        state_backups: bool,
+        /// Remove everything under .shield except keys and backups
+        #[arg(long)]
+        all: bool,
+        /// With --all, also delete the deployer and vault keys
+        #[arg(long, requires = "all")]
+        include_keys: bool,
+        /// List what would be removed without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Generate a shell completion script
+    ///
+    /// Print a completion script for the given shell to stdout.
+    ///
+    /// Bash:
+    ///   shield-deploy completions bash > ~/.local/share/bash-completion/completions/shield-deploy
+    ///
+    /// Zsh (with ~/.zfunc in your fpath):
+    ///   shield-deploy completions zsh > ~/.zfunc/_shield-deploy
+    ///
+    /// Fish:
+    ///   shield-deploy completions fish > ~/.config/fish/completions/shield-deploy.fish
+    ///
+    /// PowerShell (add to your $PROFILE):
+    ///   shield-deploy completions powershell | Out-String | Invoke-Expression
+    #[command(verbatim_doc_comment)]
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Check the deployer's on-chain history for links to other wallets
+    Audit {
+        /// Print structured JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Rebuild state.json from programs and buffers the deployer controls on chain
+    Recover {
+        /// Program to check directly when the RPC disallows getProgramAccounts (repeatable)
+        #[arg(long = "program-id")]
+        program_ids: Vec<String>,
+    },
+    /// Watch managed programs for authority or bytecode changes
+    Monitor {
+        /// Check once and exit (non-zero on drift), for cron
+        #[arg(long)]
+        once: bool,
+        /// Time between checks when looping (e.g. 30s, 5m)
+        #[arg(long, default_value = "5m")]
+        interval: String,
+        /// Accept the current on-chain state as the new baseline
+        #[arg(long, conflicts_with = "once")]
+        acknowledge: bool,
+    },
+    /// Publish or read a program's security.txt
+    Metadata {
+        #[command(subcommand)]
+        action: commands::metadata::MetadataAction,
+    },
+    /// Create, list or close durable nonce accounts
+    ///
+    /// A transaction signed against a nonce stays valid until it lands,
+    /// instead of expiring with its blockhash after about a minute.
+    Nonce {
+        #[command(subcommand)]
+        action: commands::nonce::NonceAction,
+    },
+    /// Pre-generate vanity program keypairs for later deploys
+    Grind {
+        /// Base58 prefix the program ID must start with
+        #[arg(required_unless_present = "prefix_flag")]
+        prefix: Option<String>,
+        /// Same as the positional prefix
+        #[arg(long = "prefix", value_name = "PREFIX", conflicts_with = "prefix")]
+        prefix_flag: Option<String>,
+        /// Number of keypairs to find
+        #[arg(long, default_value_t = 1)]
+        count: usize,
+        /// Match the prefix's case exactly (slower)
+        #[arg(long)]
+        case_sensitive: bool,
+        /// Write the keypair here instead of the pending keypairs (requires --count 1)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        out: Option<PathBuf>,
+    },
+    /// Rehearse deploy, upgrade and finalize on a local test validator
+    Simulate {
+        /// Path to the program .so file
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        program: Option<String>,
+        /// Also rehearse an upgrade with the same artifact
+        #[arg(long)]
+        upgrade: bool,
+        /// Also rehearse making the program immutable
+        #[arg(long)]
+        finalize: bool,
+        /// Attach to a running local validator instead of starting one
+        #[arg(long, value_hint = ValueHint::Url)]
+        rpc_url: Option<String>,
+    },
+    /// Check for a newer shield-deploy release
+    Update {
+        /// Exit non-zero when a newer release exists
+        #[arg(long)]
+        check: bool,
+    },
+    /// Deploy Anchor workspace programs listed in Anchor.toml
+    Anchor {
+        #[command(subcommand)]
+        action: commands::anchor::AnchorAction,
+    },
+    /// Write an encrypted snapshot of the whole .shield directory
+    Backup {
+        /// Archive path (defaults to .shield/backups/shield-<timestamp>.bak)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        out: Option<PathBuf>,
+    },
+    /// Restore .shield from a backup archive
+    Restore {
+        /// Archive written by `shield-deploy backup`
+        #[arg(value_hint = ValueHint::FilePath)]
+        path: PathBuf,
+        /// Overwrite files that are newer on disk than in the backup
+        #[arg(long)]
+        force: bool,
+    },
+    /// Propose and track upgrades through a Squads multisig
+    Multisig {
+        #[command(subcommand)]
+        action: commands::multisig::MultisigAction,
+    },
+    /// Report privacy-pool activity from the compression indexer
+    PoolStats {
+        /// Look-back window; repeat for several (default 24h)
+        #[arg(long = "window", value_enum)]
+        windows: Vec<commands::pool_stats::StatsWindow>,
+        /// Print structured JSON output
+        #[arg(long)]
+        json: bool,
+        /// Refresh every SECONDS (default 60) until interrupted
+        #[arg(long, value_name = "SECONDS", num_args = 0..=1, default_missing_value = "60")]
+        watch: Option<u64>,
+    },
+    /// Manage which programs this project tracks
+    Programs {
+        #[command(subcommand)]
+        action: commands::programs::ProgramsAction,
+    },
+    /// Keep declare_id! and target/deploy keypairs in line with deployed program IDs
+    Keys {
+        #[command(subcommand)]
+        action: commands::keys::KeysAction,
+    },
     /// Rotate to a new private deployer
-    Rotate,
+    ///
+    /// Refused while SHIELD_DEPLOYER_KEY is set; rotate that key in your
+    /// secret store instead.
+    Rotate {
+        /// Save the new deployer here (0600) instead of .shield/deployer.json
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        outfile: Option<PathBuf>,
+        /// Don't POST the result to notify_webhook
+        #[arg(long)]
+        no_notify: bool,
+    },
     /// Transfer upgrade authority to another address
     TransferAuthority {
         /// New authority public key
-        new_authority: String,
+        #[arg(required_unless_present = "plan", conflicts_with = "plan")]
+        new_authority: Option<String>,
+        /// JSON or CSV file mapping program IDs to new authorities
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        plan: Option<PathBuf>,
+        /// Validate and print the plan without sending anything
+        #[arg(long, requires = "plan")]
+        dry_run: bool,
+        /// Don't POST the result to notify_webhook
+        #[arg(long)]
+        no_notify: bool,
+        /// Send without the RPC's preflight simulation
+        #[arg(long)]
+        skip_preflight: bool,
+        /// Sign each authority transfer against this durable nonce account (see `nonce create`)
+        #[arg(long, value_name = "PUBKEY")]
+        nonce: Option<String>,
     },
     /// Make a program immutable (cannot be upgraded by anyone)
     Finalize {
         /// Program ID to finalize
         program_id: String,
+        /// Finalize before finalize_min_age has passed (emergencies only)
+        #[arg(long)]
+        override_min_age: bool,
+        /// Skip the typed program ID confirmation; required with --yes
+        #[arg(long)]
+        force_finalize: bool,
+        /// Don't POST the result to notify_webhook
+        #[arg(long)]
+        no_notify: bool,
+        /// Send without the RPC's preflight simulation
+        #[arg(long)]
+        skip_preflight: bool,
+        /// Sign the finalize transaction against this durable nonce account (see `nonce create`)
+        #[arg(long, value_name = "PUBKEY")]
+        nonce: Option<String>,
+    },
+    /// Withdraw deployer funds privately through Privacy Cash
+    Withdraw {
+        /// Amount to withdraw: `2.5`, `2.5 sol`, `1500000000 lamports` or `all` (prompted when omitted)
+        amount: Option<shield_deploy::utils::Amount>,
+        /// Withdraw the full balance minus fees
+        #[arg(long, conflicts_with = "amount")]
+        all: bool,
+        /// Recipient public key
+        #[arg(long)]
+        to: Option<String>,
+        /// Leave the funds shielded in a project vault instead of sending them
+        #[arg(long, conflicts_with = "to")]
+        no_decompress: bool,
     },
+    /// Verify on-chain bytecode matches a local artifact
+    Verify {
+        /// Program ID to verify
+        program_id: String,
+        /// Path to the program .so file
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        program: Option<String>,
+        /// Rebuild from source with solana-verify (Docker) instead of using a local artifact
+        #[arg(long, conflicts_with = "program")]
+        verifiable: bool,
+        /// Repository to build from (defaults to the current source tree)
+        #[arg(long, requires_all = ["verifiable", "commit"])]
+        repo_url: Option<String>,
+        /// Commit to build when using --repo-url
+        #[arg(long, requires = "repo_url")]
+        commit: Option<String>,
+    },
+}
+
+fn main() -> Result<()> {
+    // Exported while this is still the only thread; `set_var` isn't safe once
+    // the runtime's workers are running
+    shield_deploy::proxy::apply();
+    tokio::runtime::Runtime::new()?.block_on(run())
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+async fn run() -> Result<()> {
     let cli = Cli::parse();
+    shield_deploy::utils::set_verbose(cli.verbose);
+    shield_deploy::utils::set_assume_yes(cli.yes);
+    shield_deploy::utils::set_max_retries(cli.max_retries);
+    shield_deploy::network::set_mainnet_opt_in(cli.mainnet);
+    shield_deploy::confirm::set_no_websocket(cli.no_websocket);
+    if let Some(level) = &cli.commitment {
+        shield_deploy::utils::set_commitment(level.parse()?);
+    }
+    if let Some(dir) = cli.artifact_dir.clone() {
+        shield_deploy::utils::set_artifact_dir(dir);
+    }
+    shield_deploy::utils::set_skip_preflight(skip_preflight(&cli.command));
+    if let Some(size) = chunk_size(&cli.command) {
+        shield_deploy::utils::set_chunk_size(size)?;
+    }
+    shield_deploy::utils::set_use_tpu(use_tpu(&cli.command));
+    if let Some(nonce) = nonce(&cli.command) {
+        shield_deploy::utils::set_nonce_account(nonce)?;
+    }
+    if let Some(concurrency) = write_concurrency(&cli.command) {
+        shield_deploy::utils::set_write_concurrency(concurrency as usize);
+    }
+    if let Some(fee) = priority_fee(&cli.command) {
+        shield_deploy::compute::set_priority_fee(fee);
+    }
 
-    match cli.command {
-        Commands::Init => commands::init::execute().await,
-        Commands::Fund => commands::fund::execute().await,
-        Commands::Deploy { program } => commands::deploy::execute(program).await,
-        Commands::Upgrade { program_id_str } => commands::upgrade::execute(program_id_str).await,
-        Commands::Status => commands::status::execute().await,
-        Commands::Rotate => commands::rotate::execute().await,
-        Commands::TransferAuthority { new_authority } => {
-            commands::transfer_authority::execute(new_authority).await
+    if touches_chain(&cli.command) {
+        shield_deploy::network::ensure_state_network(cli.switch_network)?;
+    }
+
+    let update_check = if machine_output(&cli.command) {
+        None
+    } else {
+        BackgroundCheck::start()
+    };
+
+    let result = match cli.command {
+        Commands::Init { unsafe_ignore_git } => commands::init::execute(unsafe_ignore_git).await,
+        Commands::Quickstart => commands::quickstart::execute().await,
+        Commands::Fund { action: Some(commands::fund::FundAction::Cancel { plan }), .. } => {
+            commands::fund::cancel(&plan).await
+        }
+        Commands::Fund { run_plan: Some(vault), .. } => commands::fund::run_plan(&vault).await,
+        Commands::Fund { resume: true, .. } => commands::fund::resume().await,
+        Commands::Fund { airdrop, perturb, no_perturb, override_spend_limit, detach, from_compressed, unsafe_ignore_git, .. } => {
+            let perturb = match (perturb, no_perturb) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            };
+            commands::fund::execute(airdrop, perturb, override_spend_limit, detach, from_compressed, unsafe_ignore_git).await
+        }
+        Commands::Deploy { isolate_authority, override_spend_limit, fee_payer, no_notify, unsafe_ignore_git, no_auto_clean, max_len_multiplier, dry_run, auto_fund, sync_keys, with_idl, all: true, .. } => {
+            with_notification(
+                "deploy",
+                no_notify,
+                commands::deploy::execute_all(isolate_authority, override_spend_limit, fee_payer, unsafe_ignore_git, no_auto_clean, max_len_multiplier, dry_run, auto_fund, sync_keys, with_idl),
+            )
+            .await
+        }
+        Commands::Deploy { program, program_sha256, isolate_authority, override_spend_limit, fee_payer, no_notify, unsafe_ignore_git, recover, no_auto_clean, fresh, program_keypair, max_len, max_len_multiplier, dry_run, buffer, name, finalize, force_finalize, auto_fund, vanity_prefix, duplicate, sync_keys, with_idl, idl, .. } => {
+            let program = match name {
+                Some(name) => Some(shield_deploy::utils::program_file_by_name(&name)?.display().to_string()),
+                None => program,
+            };
+            with_notification(
+                "deploy",
+                no_notify,
+                commands::deploy::execute(program, program_sha256, isolate_authority, override_spend_limit, fee_payer, unsafe_ignore_git, recover, no_auto_clean, fresh, program_keypair, max_len, max_len_multiplier, dry_run, buffer, finalize, force_finalize, auto_fund, vanity_prefix, duplicate, sync_keys, with_idl, idl),
+            )
+            .await
+        }
+        Commands::Upgrade {
+            program_id_str,
+            program_id,
+            name,
+            program,
+            program_sha256,
+            spill_compressed,
+            spill,
+            override_spend_limit,
+            fee_payer,
+            no_notify,
+            no_auto_clean,
+            at,
+            at_slot,
+            resume,
+            no_extend,
+            with_idl,
+            idl,
+            ..
+        } => {
+            let program = match (&name, program) {
+                (Some(name), None) if !resume => {
+                    Some(shield_deploy::utils::program_file_by_name(name)?.display().to_string())
+                }
+                (_, program) => program,
+            };
+            with_notification(
+                "upgrade",
+                no_notify,
+                commands::upgrade::execute(
+                    program_id_str.or(program_id),
+                    name,
+                    program,
+                    program_sha256,
+                    spill_compressed,
+                    spill,
+                    override_spend_limit,
+                    fee_payer,
+                    no_auto_clean,
+                    at,
+                    at_slot,
+                    resume,
+                    no_extend,
+                    with_idl,
+                    idl,
+                ),
+            )
+            .await
+        }
+        Commands::Status { json, metrics, textfile, verify } => {
+            commands::status::execute(json, metrics, textfile, verify).await
+        }
+        Commands::Balance { lamports, json, min } => {
+            commands::balance::execute(lamports, json, min).await
+        }
+        Commands::List { refresh, json, sort, reverse } => {
+            commands::list::execute(refresh, json, sort, reverse).await
+        }
+        Commands::Doctor { json, fix } => commands::doctor::execute(json, fix).await,
+        Commands::ExportDeployer { out } => commands::export_deployer::execute(out).await,
+        Commands::ImportDeployer { path, force } => {
+            commands::import_deployer::execute(path, force).await
+        }
+        Commands::History { program, since, json, verify } => {
+            commands::history::execute(program, since, json, verify).await
+        }
+        Commands::Show { program_id, json } => commands::show::execute(program_id, json).await,
+        Commands::Tx { signature, json } => commands::tx::execute(signature, json).await,
+        Commands::Idl { action } => commands::idl::execute(action).await,
+        Commands::WriteBuffer { program, authority } => {
+            commands::write_buffer::execute(program, authority).await
+        }
+        Commands::InspectBuffer { buffer_pubkey, json } => {
+            commands::inspect_buffer::execute(buffer_pubkey, json).await
+        }
+        Commands::Buffers { close, all } => {
+            commands::buffers::execute(close, all).await
+        }
+        Commands::PrepareUpgrade { program_id, program, spill, out } => {
+            commands::prepare_upgrade::execute(program_id, program, spill, out).await
+        }
+        Commands::Estimate { program, program_id, max_len, priority_fee, json } => {
+            commands::estimate::execute(program, program_id, max_len, priority_fee, json).await
+        }
+        Commands::Config { action } => commands::config::execute(action).await,
+        Commands::State { action } => commands::state::execute(action).await,
+        Commands::Clean { circuits, logs, pending, artifacts, state_backups, all, include_keys, dry_run } => {
+            commands::clean::execute(circuits, logs, pending, artifacts, state_backups, all, include_keys, dry_run)
+                .await
+        }
+        Commands::Completions { shell } => commands::completions::execute(shell, Cli::command()),
+        Commands::Audit { json } => commands::audit::execute(json).await,
+        Commands::Recover { program_ids } => commands::recover::execute(program_ids).await,
+        Commands::Monitor { once, interval, acknowledge } => {
+            commands::monitor::execute(once, interval, acknowledge).await
+        }
+        Commands::Metadata { action } => commands::metadata::execute(action).await,
+        Commands::Nonce { action } => commands::nonce::execute(action).await,
+        Commands::Grind { prefix, prefix_flag, count, case_sensitive, out } => {
+            let prefix = prefix.or(prefix_flag).unwrap_or_default();
+            commands::grind::execute(prefix, count, case_sensitive, out).await
+        }
+        Commands::Simulate { program, upgrade, finalize, rpc_url } => {
+            commands::simulate::execute(program, upgrade, finalize, rpc_url).await
+        }
+        Commands::Update { check } => commands::update::execute(check).await,
+        Commands::Anchor { action } => commands::anchor::execute(action).await,
+        Commands::Backup { out } => commands::backup::execute(out).await,
+        Commands::Restore { path, force } => commands::restore::execute(path, force).await,
+        Commands::Multisig { action } => commands::multisig::execute(action).await,
+        Commands::PoolStats { windows, json, watch } => {
+            commands::pool_stats::execute(windows, json, watch).await
+        }
+        Commands::Programs { action } => commands::programs::execute(action).await,
+        Commands::Keys { action } => commands::keys::execute(action).await,
+        Commands::Rotate { outfile, no_notify } => {
+            with_notification("rotate", no_notify, commands::rotate::execute(outfile)).await
+        }
+        Commands::TransferAuthority { new_authority, plan, dry_run, no_notify, .. } => {
+            with_notification(
+                "transfer-authority",
+                no_notify,
+                commands::transfer_authority::execute(new_authority, plan, dry_run),
+            )
+            .await
         },
-        Commands::Finalize { program_id } => {
-            commands::finalize::execute(program_id).await
+        Commands::Finalize { program_id, override_min_age, force_finalize, no_notify, .. } => {
+            with_notification(
+                "finalize",
+                no_notify,
+                commands::finalize::execute(program_id, override_min_age, force_finalize),
+            )
+            .await
+        }
+        Commands::Withdraw { amount, all, to, no_decompress } => {
+            commands::withdraw::execute(amount, all, to, no_decompress).await
         }
+        Commands::Verify { program_id, program, verifiable, repo_url, commit } => {
+            commands::verify::execute(program_id, program, verifiable, repo_url, commit).await
+        }
+    };
+
+    if let Some(check) = update_check {
+        check.finish().await;
+    }
+
+    result.map_err(shield_deploy::proxy::explain_error)
+}
+
+fn skip_preflight(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::Fund { skip_preflight: true, .. }
+            | Commands::Deploy { skip_preflight: true, .. }
+            | Commands::Upgrade { skip_preflight: true, .. }
+            | Commands::TransferAuthority { skip_preflight: true, .. }
+            | Commands::Finalize { skip_preflight: true, .. }
+    )
+}
+
+fn chunk_size(command: &Commands) -> Option<usize> {
+    match command {
+        Commands::Deploy { chunk_size, .. } | Commands::Upgrade { chunk_size, .. } => *chunk_size,
+        _ => None,
+    }
+}
+
+fn use_tpu(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::Deploy { use_tpu: true, .. } | Commands::Upgrade { use_tpu: true, .. }
+    )
+}
+
+fn nonce(command: &Commands) -> Option<&str> {
+    match command {
+        Commands::Deploy { nonce, .. }
+        | Commands::Upgrade { nonce, .. }
+        | Commands::TransferAuthority { nonce, .. }
+        | Commands::Finalize { nonce, .. } => nonce.as_deref(),
+        _ => None,
+    }
+}
+
+fn write_concurrency(command: &Commands) -> Option<u16> {
+    match command {
+        Commands::Deploy { concurrency, .. } | Commands::Upgrade { concurrency, .. } => *concurrency,
+        _ => None,
+    }
+}
+
+fn priority_fee(command: &Commands) -> Option<PriorityFee> {
+    match command {
+        Commands::Fund { priority_fee, .. }
+        | Commands::Deploy { priority_fee, .. }
+        | Commands::Upgrade { priority_fee, .. } => *priority_fee,
+        _ => None,
     }
-}
\ No newline at end of file
+}
+
+/// Commands that read or write chain state for the project's programs
+fn touches_chain(command: &Commands) -> bool {
+    !matches!(
+        command,
+        Commands::Init { .. }
+            | Commands::Doctor { .. }
+            | Commands::ExportDeployer { .. }
+            | Commands::ImportDeployer { .. }
+            | Commands::History { verify: false, .. }
+            | Commands::List { refresh: false, .. }
+            | Commands::Config { .. }
+            | Commands::State { .. }
+            | Commands::Completions { .. }
+            | Commands::Update { .. }
+            | Commands::Backup { .. }
+            | Commands::Restore { .. }
+            | Commands::Keys { .. }
+            | Commands::Grind { .. }
+            | Commands::Estimate { .. }
+            | Commands::PoolStats { .. }
+    )
+}
+
+/// Output meant for other programs gets no update notice
+fn machine_output(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::Completions { .. }
+            | Commands::Update { .. }
+            | Commands::Status { metrics: true, .. }
+            | Commands::Status { textfile: Some(_), .. }
+    )
+        || std::env::args().any(|arg| arg == "--json" || arg == "--quiet")
+        || !std::io::stdout().is_terminal()
+}
@@ -0,0 +1,106 @@
+//! Keeping commands on the cluster a project was set up for
+//!
+//! `state.json` records the cluster at `init`. The RPC can change underneath
+//! it (a Solana CLI config switched to mainnet for another project), so
+//! commands that touch the chain check the two agree before doing anything.
+//! Spending commands also need an explicit opt-in before touching mainnet-beta.
+
+use anyhow::Result;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::config::{Config, ProjectConfig};
+use crate::utils::*;
+
+const MAINNET: &str = "mainnet-beta";
+
+/// Refuse to run against a cluster other than the one in state.json
+///
+/// With `switch` the project moves to the current cluster instead. Programs
+/// recorded on the previous cluster are parked, not dropped, and come back
+/// when the project switches back.
+pub fn ensure_state_network(switch: bool) -> Result<()> {
+    let config = Config::new()?;
+    if !config.state_path().exists() {
+        return Ok(());
+    }
+    let mut state = config.load_state()?;
+    let network = get_network_name();
+    if state.network.is_empty() || state.network == network {
+        return Ok(());
+    }
+
+    if !switch {
+        anyhow::bail!(
+            "This project was initialized for {} but the RPC now points at {network} ({}).\n\
+            Point the Solana CLI back at {}, or pass --switch-network to use the project on {network}.",
+            state.network,
+            get_rpc_url().unwrap_or_default(),
+            state.network
+        );
+    }
+
+    let previous = state.network.clone();
+    let (hidden, restored) = state.switch_network(&network);
+    config.save_state(&state)?;
+
+    print_warning(&format!("Switched this project from {previous} to {network}"));
+    if hidden > 0 {
+        println!("  ↳ {hidden} program record(s) from {previous} are hidden until you switch back");
+    }
+    if restored > 0 {
+        println!("  ↳ Restored {restored} program record(s) from {network}");
+    }
+    println!();
+    Ok(())
+}
+
+static MAINNET_OPT_IN: AtomicBool = AtomicBool::new(false);
+
+/// Accept mainnet-beta transactions without the typed confirmation (`--mainnet`)
+pub fn set_mainnet_opt_in(opt_in: bool) {
+    MAINNET_OPT_IN.store(opt_in, Ordering::Relaxed);
+}
+
+/// Whether `network` is mainnet-beta without `--mainnet` or `allow_mainnet` in `config`
+///
+/// The check behind [`confirm_mainnet`], for callers that can't prompt.
+pub fn needs_mainnet_opt_in(config: &ProjectConfig, network: &str) -> bool {
+    network == MAINNET && !MAINNET_OPT_IN.load(Ordering::Relaxed) && !config.allow_mainnet.unwrap_or(false)
+}
+
+/// Stop before a spending command sends anything to mainnet-beta without opt-in
+///
+/// Opt-in is `--mainnet`, `allow_mainnet = true` in config, or typing the
+/// cluster name back after seeing the estimated spend. `--yes` alone is not
+/// enough. Other clusters pass straight through. The cluster comes from
+/// [`resolve_network_name`], so an RPC URL that doesn't name mainnet is still
+/// caught, and one that can't be identified stops here. Returns the cluster,
+/// for the spend limit that follows.
+pub async fn confirm_mainnet(action: &str, estimated_spend: Option<u64>) -> Result<String> {
+    let config = ProjectConfig::load()?;
+    let network = resolve_network_name(&config, &rpc_url_from(&config)).await?;
+    if network != MAINNET {
+        return Ok(network);
+    }
+    let spend = estimated_spend.map_or("transaction fees only".to_string(), format_sol);
+    if !needs_mainnet_opt_in(&config, &network) {
+        println!("  ↳ {action} on {MAINNET}, estimated spend {spend}");
+        return Ok(network);
+    }
+
+    print_warning(&format!("{action} sends real transactions on {MAINNET}"));
+    println!("  Estimated spend: {spend}");
+    if assume_yes() || !std::io::stdin().is_terminal() {
+        anyhow::bail!(
+            "Mainnet transactions need explicit opt-in.\n\
+            Pass --mainnet, or set `allow_mainnet = true` with `shield-deploy config set`."
+        );
+    }
+    let confirmation: String = dialoguer::Input::new()
+        .with_prompt(format!("Type {MAINNET} to continue"))
+        .interact_text()?;
+    if confirmation.trim() != MAINNET {
+        anyhow::bail!("Cluster name not confirmed. Nothing was sent.");
+    }
+    Ok(network)
+}
@@ -0,0 +1,89 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::future::Future;
+use std::time::Duration;
+use crate::config::{Config, ProjectConfig};
+use crate::utils::{get_network_name, print_warning};
+
+/// Notifications must never hold up the command that triggered them
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Payload POSTed to `notify_webhook`
+///
+/// Built only from operation records, which never hold keys or funding
+/// wallets, so nothing secret can end up in a chat channel.
+#[derive(Serialize)]
+pub struct Notification {
+    pub command: String,
+    pub cluster: String,
+    pub success: bool,
+    pub program_id: Option<String>,
+    pub signatures: Vec<String>,
+    pub cost_lamports: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Run a command and report its outcome to the configured webhook
+///
+/// Operations recorded during the run supply the program ID, signatures and
+/// cost. A run that records nothing and succeeds (e.g. cancelled at the
+/// prompt) sends nothing.
+pub async fn with_notification<F>(command: &str, no_notify: bool, run: F) -> Result<()>
+where
+    F: Future<Output = Result<()>>,
+{
+    let webhook = if no_notify {
+        None
+    } else {
+        ProjectConfig::load().ok().and_then(|c| c.notify_webhook)
+    };
+    let Some(webhook) = webhook else {
+        return run.await;
+    };
+
+    let config = Config::new()?;
+    let operations_before = config.load_state().map(|s| s.operations.len()).unwrap_or(0);
+
+    let result = run.await;
+
+    let new_operations = config
+        .load_state()
+        .map(|s| s.operations.into_iter().skip(operations_before).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    if result.is_ok() && new_operations.is_empty() {
+        return result;
+    }
+
+    let notification = Notification {
+        command: command.to_string(),
+        cluster: get_network_name(),
+        success: result.is_ok(),
+        program_id: new_operations.iter().rev().find_map(|op| op.program_id.clone()),
+        signatures: new_operations.iter().flat_map(|op| op.signatures.clone()).collect(),
+        cost_lamports: new_operations
+            .iter()
+            .filter_map(|op| op.cost_lamports)
+            .reduce(|a, b| a + b),
+        error: result.as_ref().err().map(|e| e.to_string()),
+    };
+
+    if let Err(e) = send(&webhook, &notification).await {
+        print_warning(&format!("Webhook notification failed: {e}"));
+    }
+
+    result
+}
+
+async fn send(url: &str, notification: &Notification) -> Result<()> {
+    reqwest::Client::builder()
+        .timeout(NOTIFY_TIMEOUT)
+        .build()?
+        .post(url)
+        .json(notification)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Light system program; compressed SOL is held by its pool PDA
+const LIGHT_SYSTEM_PROGRAM_ID: &str = "SySTEM1eSU2p4BGQfQpimFEWWSC1XDFeun3Nqzz3rT7";
+const SOL_POOL_SEED: &[u8] = b"sol_pool_pda";
+
+const INDEXER_TIMEOUT: Duration = Duration::from_secs(15);
+const PAGE_SIZE: usize = 1_000;
+
+/// Transactions classified per window; counts past this are reported as a lower bound
+const MAX_CLASSIFIED: usize = 500;
+
+/// Below these a freshly funded deployer stands out in the pool
+const HEALTHY_OPERATIONS_PER_HOUR: f64 = 10.0;
+const HEALTHY_TVL_LAMPORTS: u64 = 1_000 * 1_000_000_000;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum PoolHealth {
+    Healthy,
+    Thin,
+    Quiet,
+}
+
+/// Pool activity over one look-back window
+#[derive(Serialize, Debug)]
+pub struct PoolWindow {
+    pub label: String,
+    pub seconds: u64,
+    /// Compression transactions seen by the indexer in the window
+    pub transactions: usize,
+    pub compressions: usize,
+    pub decompressions: usize,
+    /// True when only the most recent `MAX_CLASSIFIED` were classified
+    pub truncated: bool,
+}
+
+impl PoolWindow {
+    pub fn per_hour(&self) -> f64 {
+        self.transactions as f64 / (self.seconds as f64 / 3_600.0)
+    }
+}
+
+/// How large the crowd is that a privacy-pool withdrawal hides in
+#[derive(Serialize, Debug)]
+pub struct AnonymityReport {
+    pub endpoint: String,
+    pub tvl_lamports: Option<u64>,
+    /// The indexer exposes no global count, so this is `None` unless it does
+    pub compressed_accounts: Option<u64>,
+    pub windows: Vec<PoolWindow>,
+    pub health: PoolHealth,
+}
+
+impl AnonymityReport {
+    fn assess(&mut self) {
+        let busiest = self.windows.iter().map(PoolWindow::per_hour).fold(0.0, f64::max);
+        let deep = self.tvl_lamports.is_none_or(|tvl| tvl >= HEALTHY_TVL_LAMPORTS);
+
+        self.health = if busiest >= HEALTHY_OPERATIONS_PER_HOUR && deep {
+            PoolHealth::Healthy
+        } else if busiest >= HEALTHY_OPERATIONS_PER_HOUR / 10.0 {
+            PoolHealth::Thin
+        } else {
+            PoolHealth::Quiet
+        };
+    }
+}
+
+/// Query the compression indexer at `endpoint` for pool activity
+///
+/// `windows` are (label, seconds) pairs. TVL is read from the Light system
+/// program's SOL pool through the regular RPC.
+pub async fn pool_stats(
+    rpc_client: &RpcClient,
+    endpoint: &str,
+    windows: &[(&str, u64)],
+) -> Result<AnonymityReport> {
+    let indexer = Indexer::new(endpoint)?;
+
+    indexer
+        .call("getIndexerHealth", json!({}))
+        .await
+        .with_context(|| format!("Compression indexer at {endpoint} is unavailable"))?;
+
+    let longest = windows.iter().map(|(_, seconds)| *seconds).max().unwrap_or(0);
+    let now = chrono::Utc::now().timestamp();
+    let recent = indexer.signatures_since(now - longest as i64).await?;
+
+    let mut report = AnonymityReport {
+        endpoint: endpoint.to_string(),
+        tvl_lamports: sol_pool_balance(rpc_client).await,
+        compressed_accounts: None,
+        windows: Vec::new(),
+        health: PoolHealth::Quiet,
+    };
+
+    for (label, seconds) in windows {
+        let in_window: Vec<&(String, i64)> = recent
+            .iter()
+            .filter(|(_, time)| *time >= now - *seconds as i64)
+            .collect();
+
+        let mut window = PoolWindow {
+            label: label.to_string(),
+            seconds: *seconds,
+            transactions: in_window.len(),
+            compressions: 0,
+            decompressions: 0,
+            truncated: in_window.len() > MAX_CLASSIFIED,
+        };
+        for (signature, _) in in_window.into_iter().take(MAX_CLASSIFIED) {
+            match indexer.lamport_flow(signature).await? {
+                flow if flow > 0 => window.compressions += 1,
+                flow if flow < 0 => window.decompressions += 1,
+                _ => {}
+            }
+        }
+        report.windows.push(window);
+    }
+
+    report.assess();
+    Ok(report)
+}
+
+/// Check that `endpoint` answers the compression methods [`pool_stats`] uses
+pub async fn serves_compression(endpoint: &str) -> Result<()> {
+    let indexer = Indexer::new(endpoint)?;
+    indexer.call("getIndexerHealth", json!({})).await?;
+    indexer
+        .call("getLatestCompressionSignatures", json!({ "limit": 1 }))
+        .await?;
+    Ok(())
+}
+
+async fn sol_pool_balance(rpc_client: &RpcClient) -> Option<u64> {
+    let program_id = Pubkey::from_str(LIGHT_SYSTEM_PROGRAM_ID).ok()?;
+    let (pool, _) = Pubkey::find_program_address(&[SOL_POOL_SEED], &program_id);
+    rpc_client.get_balance(&pool).await.ok()
+}
+
+/// Minimal JSON-RPC client for the Photon compression indexer
+struct Indexer {
+    client: reqwest::Client,
+    endpoint: String,
+}
+
+impl Indexer {
+    fn new(endpoint: &str) -> Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder().timeout(INDEXER_TIMEOUT).build()?,
+            endpoint: endpoint.to_string(),
+        })
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let response: Value = self.client
+            .post(&self.endpoint)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach the compression indexer at {}", self.endpoint))?
+            .error_for_status()
+            .with_context(|| format!("Compression indexer at {} returned an error", self.endpoint))?
+            .json()
+            .await
+            .with_context(|| format!("Compression indexer at {} sent invalid JSON", self.endpoint))?;
+
+        if let Some(error) = response.get("error") {
+            anyhow::bail!("Compression indexer at {} rejected {method}: {error}", self.endpoint);
+        }
+        Ok(response["result"].clone())
+    }
+
+    /// Signatures of compression transactions at or after `since`, newest first
+    async fn signatures_since(&self, since: i64) -> Result<Vec<(String, i64)>> {
+        let mut signatures = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let page = self
+                .call("getLatestCompressionSignatures", json!({ "cursor": cursor, "limit": PAGE_SIZE }))
+                .await?;
+            let items = page["value"]["items"].as_array().cloned().unwrap_or_default();
+
+            let mut reached_start = items.is_empty();
+            for item in &items {
+                let time = item["blockTime"].as_i64().unwrap_or(0);
+                if time < since {
+                    reached_start = true;
+                    break;
+                }
+                if let Some(signature) = item["signature"].as_str() {
+                    signatures.push((signature.to_string(), time));
+                }
+            }
+
+            cursor = page["value"]["cursor"].as_str().map(String::from);
+            if reached_start || cursor.is_none() {
+                break;
+            }
+        }
+
+        Ok(signatures)
+    }
+
+    /// Net lamports moved into compressed accounts: positive for a compress
+    async fn lamport_flow(&self, signature: &str) -> Result<i128> {
+        let result = self.call("getTransactionWithCompressionInfo", json!({ "signature": signature })).await?;
+        let info = &result["compressionInfo"];
+
+        let total = |key: &str| -> i128 {
+            info[key]
+                .as_array()
+                .map(|accounts| {
+                    accounts
+                        .iter()
+                        .filter_map(|a| a["account"]["lamports"].as_u64())
+                        .map(i128::from)
+                        .sum()
+                })
+                .unwrap_or(0)
+        };
+
+        Ok(total("openedAccounts") - total("closedAccounts"))
+    }
+}
@@ -1,14 +1,44 @@
 use anyhow::{Context, Result};
+use rand::Rng;
 use privacy_cash::{send_privately, SendPrivatelyResult};
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::AccountMeta,
+    instruction::Instruction as SdkInstruction,
     native_token::LAMPORTS_PER_SOL,
     pubkey::Pubkey,
-    signature::{Keypair},
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
 };
-use std::thread;
+use solana_system_interface::instruction as system_instruction;
+use solana_address::Address;
+use serde::Serialize;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
+use solana_transaction_status_client_types::UiTransactionEncoding;
+use std::collections::HashMap;
+use std::str::FromStr;
 use std::time::Duration;
+use crate::compute::{compute_budget, estimate_priority_fee, ComputeBudget, TxClass};
+use crate::config::{OperationKind, ProjectConfig, ProjectState};
+use crate::utils::{commitment, format_sol, is_verbose, send_and_confirm};
 
-const PRIVACY_DELAY_SECS: u64 = 30;
+pub const PRIVACY_DELAY_SECS: u64 = 30;
+const TRANSFER_FEE_LAMPORTS: u64 = 5_000;
+
+/// Maximum number of signatures getSignaturesForAddress returns per call
+const SIGNATURE_PAGE_SIZE: usize = 1_000;
+
+/// Directory Privacy Cash loads its Groth16 circuit files from
+pub const CIRCUIT_DIR: &str = "circuit";
+
+/// Circuit files and their expected SHA-256 digests
+pub const CIRCUIT_FILES: &[(&str, &str)] = &[
+    ("transaction2.wasm", "a277631b7616c2c0bfd78a1648b069972ac6020e5509ae8f9bfc8772bdc70ec1"),
+    ("transaction2.zkey", "4aa7aa5c1c28ed1f00fee84f49c1686f53210fd999ef7c8db6cfcd298af4e693"),
+];
 
 /// Privacy layer using Privacy Cash for ZK-proof private transfers
 /// 
@@ -95,6 +125,7 @@ impl PrivacyLayer {
         // Convert keypair to base58 private key
         let private_key_bytes = funding_keypair.to_bytes();
         let private_key_base58 = bs58::encode(&private_key_bytes).into_string();
+        self.report_sdk_leg("fund", &[funding_keypair.pubkey(), *burner_pubkey]).await;
         
         println!("\n📝 Generating ZK proof (Groth16)...");
         println!("  ↳ This may take a few seconds");
@@ -130,6 +161,156 @@ impl PrivacyLayer {
         Ok(result)
     }
 
+    /// Move SOL from `source` into shielded custody
+    ///
+    /// The funds travel through the Privacy Cash pool to `vault`, a fresh key
+    /// held under `.shield/vault/`. The vault has no on-chain link to `source`,
+    /// so whatever it holds is the project's compressed (shielded) balance
+    /// until `decompress_sol` releases it.
+    ///
+    /// The vault key must be persisted before calling this, otherwise a crash
+    /// between the deposit and the withdraw would strand the funds.
+    pub async fn compress_sol(
+        &self,
+        source: &Keypair,
+        vault: &Pubkey,
+        amount_sol: f64,
+    ) -> Result<SendPrivatelyResult> {
+        println!("\n🔒 Compressing {amount_sol} SOL via Privacy Cash...");
+
+        if amount_sol < 0.02 {
+            anyhow::bail!(
+                "Privacy Cash requires minimum 0.02 SOL\n\
+                You specified: {amount_sol} SOL"
+            );
+        }
+
+        let private_key_base58 = bs58::encode(source.to_bytes()).into_string();
+        self.report_sdk_leg("compress", &[source.pubkey(), *vault]).await;
+
+        let result = send_privately(
+            &private_key_base58,
+            &vault.to_string(),
+            amount_sol,
+            "sol",
+            self.rpc_url.as_deref(),
+        )
+        .await
+        .context("Privacy Cash transfer failed")?;
+
+        println!("  ✓ Deposit TX: {}", result.deposit_signature);
+        println!("  ✓ Shielded: {} SOL",
+            result.amount_received as f64 / LAMPORTS_PER_SOL as f64);
+
+        Ok(result)
+    }
+
+    /// Release shielded funds held by a vault key to `recipient`
+    ///
+    /// Sends `amount_lamports`, or the vault's whole balance minus the
+    /// transfer and priority fees when `None`. Returns the signature and
+    /// lamports sent.
+    pub async fn decompress_sol(
+        &self,
+        vault: &Keypair,
+        recipient: &Pubkey,
+        amount_lamports: Option<u64>,
+    ) -> Result<(Signature, u64)> {
+        let rpc_url = self.rpc_url.clone()
+            .context("No RPC URL configured for privacy layer")?;
+        let rpc_client = RpcClient::new_with_commitment(
+            rpc_url,
+            commitment(),
+        );
+
+        let balance = rpc_client.get_balance(&vault.pubkey()).await
+            .context("Failed to get vault balance")?;
+
+        let transfer = |lamports: u64| {
+            let transfer_ix = system_instruction::transfer(
+                &Address::from(vault.pubkey().to_bytes()),
+                &Address::from(recipient.to_bytes()),
+                lamports,
+            );
+            SdkInstruction {
+                program_id: Pubkey::from(transfer_ix.program_id.to_bytes()),
+                accounts: transfer_ix
+                    .accounts
+                    .iter()
+                    .map(|acc| AccountMeta {
+                        pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                        is_signer: acc.is_signer,
+                        is_writable: acc.is_writable,
+                    })
+                    .collect(),
+                data: transfer_ix.data,
+            }
+        };
+
+        // Size the budget on the full-balance transfer, then leave room for its fee
+        let provisional = amount_lamports.unwrap_or_else(|| balance.saturating_sub(TRANSFER_FEE_LAMPORTS));
+        let budget = compute_budget(&rpc_client, TxClass::Transfer, &vault.pubkey(), &[transfer(provisional)]).await;
+        let reserve = TRANSFER_FEE_LAMPORTS + budget.max_priority_fee();
+        if is_verbose() {
+            println!(
+                "  ↳ decompress leg: compute limit {}, price {} micro-lamports/CU, max fee {}",
+                budget.limit.map_or("default".to_string(), |limit| format!("{limit} CU")),
+                budget.price,
+                format_sol(reserve)
+            );
+        }
+
+        let lamports = match amount_lamports {
+            Some(lamports) => lamports,
+            None => balance.saturating_sub(reserve),
+        };
+
+        if lamports == 0 || lamports + reserve > balance {
+            anyhow::bail!(
+                "Vault {} holds {} lamports, not enough to release {lamports}",
+                vault.pubkey(),
+                balance
+            );
+        }
+
+        println!("\n🔓 Decompressing {} SOL to {recipient}...",
+            lamports as f64 / LAMPORTS_PER_SOL as f64);
+
+        let mut instructions = budget.instructions();
+        instructions.push(transfer(lamports));
+
+        let recent_blockhash = rpc_client.get_latest_blockhash().await?;
+        let mut transaction = Transaction::new_with_payer(
+            &instructions,
+            Some(&vault.pubkey()),
+        );
+        transaction.sign(&[vault], recent_blockhash);
+
+        let signature = send_and_confirm(&rpc_client, &transaction).await
+            .context("Failed to decompress funds")?;
+
+        println!("  ✓ Decompress TX: {signature}");
+
+        Ok((signature, lamports))
+    }
+
+    /// Verbose note on a leg whose transactions the Privacy Cash SDK builds
+    ///
+    /// `send_privately` composes, signs and sends the deposit and withdraw
+    /// itself, so no compute budget instructions can be attached to them.
+    /// The price this run would have chosen is shown for comparison.
+    async fn report_sdk_leg(&self, leg: &str, accounts: &[Pubkey]) {
+        if !is_verbose() {
+            return;
+        }
+        let Some(rpc_url) = self.rpc_url.clone() else { return };
+        let rpc_client = RpcClient::new_with_commitment(rpc_url, commitment());
+        println!(
+            "  ↳ {leg} leg: compute budget set by the Privacy Cash SDK (this run would pay {} micro-lamports/CU)",
+            estimate_priority_fee(&rpc_client, accounts).await
+        );
+    }
+
     /// Apply privacy delay before burner's first deployment
     /// 
     /// This breaks timing correlation between:
@@ -139,11 +320,16 @@ impl PrivacyLayer {
     /// Without delay: "Privacy Cash withdraw at T, deploy at T+5s" = linkable
     /// With delay: "Privacy Cash withdraw at T, deploy at T+30s" = harder to link
     pub async fn apply_privacy_delay(&self) {
-        println!("\n⏳ Applying privacy delay ({PRIVACY_DELAY_SECS} seconds)...");
+        let delay = ProjectConfig::load()
+            .ok()
+            .and_then(|config| config.privacy_delay())
+            .unwrap_or(Duration::from_secs(PRIVACY_DELAY_SECS));
+        
+        println!("\n⏳ Applying privacy delay ({} seconds)...", delay.as_secs());
         println!("  ↳ This breaks timing correlation");
         println!("  ↳ Makes linking withdraw → deploy harder");
         
-        thread::sleep(Duration::from_secs(PRIVACY_DELAY_SECS));
+        tokio::time::sleep(delay).await;
         
         println!("  ✓ Privacy delay complete");
     }
@@ -169,4 +355,234 @@ impl PrivacyLayer {
         
         (final_sol * LAMPORTS_PER_SOL as f64) as u64
     }
-}
\ No newline at end of file
+}
+
+/// Smallest remainder held back; keeps the vault above the rent-exempt minimum
+const HOLD_BACK_MINIMUM: u64 = 2_000_000;
+
+/// Most one decompress transaction to `recipient` can cost, for previews
+///
+/// Uses the priority fee this run would pick and the default compute limit,
+/// since the vault it would spend from may not exist yet.
+pub async fn decompress_fee_estimate(rpc_client: &RpcClient, recipient: &Pubkey) -> u64 {
+    let budget = ComputeBudget {
+        limit: None,
+        price: estimate_priority_fee(rpc_client, &[*recipient]).await,
+    };
+    TRANSFER_FEE_LAMPORTS + budget.max_priority_fee()
+}
+
+/// Random remainder to keep shielded when decompressing `amount_lamports`
+///
+/// Between 1% and 5% of the amount, so what reaches the deployer never
+/// equals what left the funding wallet.
+pub fn hold_back_amount(amount_lamports: u64) -> u64 {
+    let basis_points = rand::thread_rng().gen_range(100..=500);
+    (amount_lamports * basis_points / 10_000).max(HOLD_BACK_MINIMUM)
+}
+
+/// Vault keys that still hold lamports: the project's compressed balance
+pub async fn vault_balances(rpc_client: &RpcClient, vaults: Vec<Keypair>) -> Result<Vec<(Keypair, u64)>> {
+    let mut balances = Vec::new();
+    for vault in vaults {
+        let balance = rpc_client.get_balance(&vault.pubkey()).await
+            .context("Failed to get vault balance")?;
+        if balance > TRANSFER_FEE_LAMPORTS {
+            balances.push((vault, balance));
+        }
+    }
+    Ok(balances)
+}
+
+/// Where lamports moving in or out of the deployer came from or went to
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransferClass {
+    PrivacyPool,
+    DirectTransfer,
+    Airdrop,
+    ProgramFees,
+    Other,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Linkability {
+    Private,
+    Weak,
+    Linked,
+}
+
+#[derive(Serialize)]
+pub struct AuditEntry {
+    pub signature: String,
+    pub block_time: Option<i64>,
+    /// Net lamport change of the deployer (negative for outflows)
+    pub lamports: i64,
+    pub class: TransferClass,
+    pub counterparty: Option<String>,
+    pub linkability: Linkability,
+    pub note: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AuditReport {
+    pub deployer: String,
+    pub network: String,
+    pub entries: Vec<AuditEntry>,
+    pub verdict: Linkability,
+}
+
+/// Classify every transaction in the deployer's history by how it could
+/// link the deployer to another wallet
+///
+/// Signatures recorded in state identify our own fund, withdraw and program
+/// operations; anything else moving lamports is treated as a direct transfer.
+pub async fn audit_deployer(
+    rpc_client: &RpcClient,
+    deployer: &Pubkey,
+    state: &ProjectState,
+    vault_keys: &[Pubkey],
+    privacy_delay: Duration,
+) -> Result<AuditReport> {
+    let mut statuses = Vec::new();
+    let mut before = None;
+    loop {
+        let page = rpc_client
+            .get_signatures_for_address_with_config(
+                deployer,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until: None,
+                    limit: None,
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )
+            .await
+            .context("Failed to fetch deployer signature history")?;
+
+        let Some(last) = page.last() else { break };
+        before = Some(Signature::from_str(&last.signature)?);
+        let done = page.len() < SIGNATURE_PAGE_SIZE;
+        statuses.extend(page);
+        if done {
+            break;
+        }
+    }
+    statuses.reverse();
+
+    let recorded: HashMap<&str, OperationKind> = state.operations
+        .iter()
+        .flat_map(|op| op.signatures.iter().map(move |sig| (sig.as_str(), op.kind)))
+        .collect();
+
+    let loader_id = Pubkey::new_from_array(LOADER_ID.to_bytes());
+    let test_cluster = state.network != "mainnet-beta";
+    let mut entries: Vec<AuditEntry> = Vec::new();
+
+    for status in statuses.iter().filter(|s| s.err.is_none()) {
+        let signature = Signature::from_str(&status.signature)?;
+        let tx = rpc_client
+            .get_transaction_with_config(
+                &signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await
+            .with_context(|| format!("Failed to fetch transaction {signature}"))?;
+
+        let Some(meta) = tx.transaction.meta else { continue };
+        let Some(decoded) = tx.transaction.transaction.decode() else { continue };
+        let keys = decoded.message.static_account_keys();
+        let Some(index) = keys.iter().position(|k| k == deployer) else { continue };
+
+        let delta_at = |i: usize| meta.post_balances[i] as i64 - meta.pre_balances[i] as i64;
+        let lamports = delta_at(index);
+
+        // The account on the other side of the largest opposite balance change
+        let counterparty = keys
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != index && *i < meta.pre_balances.len())
+            .map(|(i, key)| (key, delta_at(i)))
+            .filter(|(_, delta)| delta.signum() == -lamports.signum() && *delta != 0)
+            .max_by_key(|(_, delta)| delta.abs())
+            .map(|(key, _)| *key);
+
+        let (class, linkability, note) = match recorded.get(status.signature.as_str()) {
+            Some(OperationKind::Fund) => (
+                TransferClass::PrivacyPool,
+                Linkability::Weak,
+                Some("Privacy Cash deposit and withdrawal ran back-to-back; their timing and amounts can be correlated".to_string()),
+            ),
+            Some(OperationKind::Withdraw) => (TransferClass::PrivacyPool, Linkability::Private, None),
+            Some(_) => (TransferClass::ProgramFees, Linkability::Private, None),
+            None if lamports > 0 && counterparty.is_some_and(|c| vault_keys.contains(&c)) => {
+                (TransferClass::PrivacyPool, Linkability::Private, Some("from a project vault".to_string()))
+            }
+            None if lamports > 0 && test_cluster => (
+                TransferClass::Airdrop,
+                Linkability::Weak,
+                Some(format!("direct inbound transfer on {}, assumed to be a faucet airdrop", state.network)),
+            ),
+            None if lamports > 0 => (
+                TransferClass::DirectTransfer,
+                Linkability::Linked,
+                Some("deployer funded directly by another wallet".to_string()),
+            ),
+            None if keys.contains(&loader_id) => (TransferClass::ProgramFees, Linkability::Private, None),
+            None if lamports < 0 && lamports.unsigned_abs() > meta.fee && counterparty.is_some() => (
+                TransferClass::DirectTransfer,
+                Linkability::Linked,
+                Some("deployer sent funds directly to another wallet".to_string()),
+            ),
+            None => (TransferClass::Other, Linkability::Private, None),
+        };
+
+        entries.push(AuditEntry {
+            signature: status.signature.clone(),
+            block_time: tx.block_time.or(status.block_time),
+            lamports,
+            class,
+            counterparty: counterparty
+                .filter(|_| class == TransferClass::DirectTransfer || class == TransferClass::Airdrop)
+                .map(|c| c.to_string()),
+            linkability,
+            note,
+        });
+    }
+
+    // Spending right after a privacy withdrawal lets an observer pair the two
+    let mut last_withdrawal: Option<i64> = None;
+    for entry in entries.iter_mut() {
+        match (entry.class, entry.block_time) {
+            (TransferClass::PrivacyPool, Some(time)) if entry.lamports > 0 => last_withdrawal = Some(time),
+            (TransferClass::ProgramFees, Some(time)) => {
+                if let Some(withdrawn) = last_withdrawal.take() {
+                    let gap = time - withdrawn;
+                    if gap >= 0 && (gap as u64) < privacy_delay.as_secs() {
+                        entry.linkability = entry.linkability.max(Linkability::Weak);
+                        entry.note = Some(format!("spent {gap}s after a privacy withdrawal"));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let verdict = entries
+        .iter()
+        .map(|e| e.linkability)
+        .max()
+        .unwrap_or(Linkability::Private);
+
+    Ok(AuditReport {
+        deployer: deployer.to_string(),
+        network: state.network.clone(),
+        entries,
+        verdict,
+    })
+}
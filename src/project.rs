@@ -0,0 +1,671 @@
+//! Programmatic API over a shield-deploy project
+//!
+//! Nothing here prompts. Choices the CLI asks the user about are plain
+//! parameters, and progress is reported through a [`Progress`] callback.
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::compute::FeeSettings;
+use crate::config::{
+    Config, DeployedProgram, FundingMethod, GitSecrets, OperationKind, OperationRecord, ProjectConfig, ProjectState,
+};
+use crate::cost::{default_max_data_len, estimate_deploy, estimate_upgrade, spend_limit_exceeded};
+use crate::loader::{deploy_program, finalize_program, last_deployed_at, upgrade_program};
+use crate::network::needs_mainnet_opt_in;
+use crate::privacy::{PrivacyLayer, PRIVACY_DELAY_SECS};
+use crate::utils::{
+    commitment_from, format_duration, network_name_from, resolve_network_name, rpc_url_from, run_scoped, sha256_hex,
+    strip_trailing_zeros, RunSettings,
+};
+
+/// Errors callers may want to handle individually
+#[derive(Debug)]
+pub enum ShieldError {
+    /// `init` has not been run for this project
+    NoDeployer,
+    /// `init` would overwrite an existing deployer
+    DeployerExists,
+    /// The deployer can't cover the operation
+    InsufficientBalance { balance: u64, required: u64 },
+    /// The program ID isn't in this project's state
+    UnknownProgram(String),
+    /// mainnet-beta without `allow_mainnet = true` in config
+    MainnetNotAllowed,
+    /// The estimate exceeds the network's `spend_limit`
+    SpendLimitExceeded { estimated: u64, limit: u64 },
+    /// Key files under `.shield/` are tracked by git
+    KeysTrackedByGit(Vec<String>),
+    /// `finalize_min_age` hasn't passed since the last deploy or upgrade
+    FinalizeTooSoon { remaining: Duration },
+    Other(anyhow::Error),
+}
+
+impl fmt::Display for ShieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShieldError::NoDeployer => write!(f, "no private deployer found; run init first"),
+            ShieldError::DeployerExists => write!(f, "a private deployer already exists"),
+            ShieldError::InsufficientBalance { balance, required } => write!(
+                f,
+                "insufficient deployer balance: {balance} lamports, {required} required"
+            ),
+            ShieldError::UnknownProgram(id) => write!(f, "program {id} is not managed by this project"),
+            ShieldError::MainnetNotAllowed => {
+                write!(f, "mainnet-beta transactions need `allow_mainnet = true` in the project config")
+            }
+            ShieldError::SpendLimitExceeded { estimated, limit } => write!(
+                f,
+                "estimated spend of {estimated} lamports exceeds the spend limit of {limit} lamports"
+            ),
+            ShieldError::KeysTrackedByGit(files) => write!(f, "private keys are tracked by git: {}", files.join(", ")),
+            ShieldError::FinalizeTooSoon { remaining } => write!(
+                f,
+                "finalize_min_age has not passed; finalization is allowed in {}",
+                format_duration(*remaining)
+            ),
+            ShieldError::Other(e) => write!(f, "{e:#}"),
+        }
+    }
+}
+
+impl std::error::Error for ShieldError {}
+
+impl From<anyhow::Error> for ShieldError {
+    fn from(e: anyhow::Error) -> Self {
+        ShieldError::Other(e)
+    }
+}
+
+impl From<solana_client::client_error::ClientError> for ShieldError {
+    fn from(e: solana_client::client_error::ClientError) -> Self {
+        ShieldError::Other(e.into())
+    }
+}
+
+pub type ShieldResult<T> = std::result::Result<T, ShieldError>;
+
+/// Receives progress messages in place of the CLI's stdout output
+pub trait Progress {
+    fn step(&self, message: &str);
+
+    /// Whether spinners may be drawn on the terminal while waiting for confirmations
+    fn interactive(&self) -> bool {
+        false
+    }
+}
+
+/// Discards all progress messages
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    fn step(&self, _message: &str) {}
+}
+
+/// Snapshot returned by [`ShieldProject::status`]
+#[derive(Debug, Clone)]
+pub struct ProjectStatus {
+    pub deployer: Pubkey,
+    pub balance_lamports: u64,
+    pub network: String,
+    pub programs: Vec<DeployedProgram>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FundOutcome {
+    pub amount_received: u64,
+    pub fees: u64,
+    /// Only the deployer-side withdraw; the deposit would reveal the funding wallet
+    pub withdraw_signature: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeployOutcome {
+    pub program_id: Pubkey,
+    pub signature: Signature,
+    pub cost_lamports: u64,
+}
+
+/// A project directory containing (or about to contain) `.shield/`
+///
+/// Settings come from the project's own `.shield/config.toml` over the
+/// global config, never from the working directory. The CLI's guards apply
+/// without their prompts: mainnet-beta needs `allow_mainnet`, `spend_limit`
+/// can't be overridden, key files tracked by git stop `init`, `fund` and
+/// `deploy`, and `finalize` waits out `finalize_min_age`. The CLI's
+/// process-wide flags (`--fee-payer`, `--nonce`, `--use-tpu`, `--max-retries`,
+/// `--commitment`) don't apply either; set them with the `with_*` methods.
+///
+/// ```no_run
+/// use shield_deploy::{NoProgress, ShieldProject};
+///
+/// # async fn run() -> Result<(), shield_deploy::ShieldError> {
+/// let project = ShieldProject::open("path/to/project")?;
+/// let program = std::fs::read("target/deploy/my_program.so").unwrap();
+///
+/// let outcome = project.deploy(&program, None, &NoProgress).await?;
+/// println!("deployed {}", outcome.program_id);
+/// # Ok(())
+/// # }
+/// ```
+pub struct ShieldProject {
+    config: Config,
+    settings: ProjectConfig,
+    rpc_url: String,
+    network: String,
+    run: RunSettings,
+}
+
+impl ShieldProject {
+    /// Open a project, using the RPC endpoint from its config
+    pub fn open(root: impl Into<PathBuf>) -> ShieldResult<Self> {
+        let config = Config::at(root.into().join(".shield"));
+        let settings = ProjectConfig::load_for(&config)?;
+        let rpc_url = rpc_url_from(&settings);
+        let network = network_name_from(&settings, &rpc_url);
+        Ok(Self { config, settings, rpc_url, network, run: RunSettings::default() })
+    }
+
+    /// Use a specific RPC endpoint instead of the configured one
+    ///
+    /// ```no_run
+    /// # use shield_deploy::ShieldProject;
+    /// # async fn run() -> Result<(), shield_deploy::ShieldError> {
+    /// let project = ShieldProject::open(".")?.with_rpc_url("http://127.0.0.1:8899");
+    /// let status = project.status().await?;
+    /// println!("{} holds {} lamports", status.deployer, status.balance_lamports);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_rpc_url(mut self, rpc_url: impl Into<String>) -> Self {
+        self.rpc_url = rpc_url.into();
+        self.network = network_name_from(&self.settings, &self.rpc_url);
+        self
+    }
+
+    /// Pay transaction fees from `fee_payer` instead of the deployer
+    ///
+    /// Rent still comes from the deployer. The fee payer is publicly linked
+    /// to every transaction it signs.
+    pub fn with_fee_payer(mut self, fee_payer: Keypair) -> Self {
+        self.run.fee_payer = Some(Arc::new(fee_payer));
+        self
+    }
+
+    /// Sign the final deploy, upgrade and finalize transactions against a durable nonce
+    pub fn with_nonce_account(mut self, nonce_account: Pubkey) -> Self {
+        self.run.nonce_account = Some(nonce_account);
+        self
+    }
+
+    /// Send buffer writes to the leaders over QUIC instead of through the RPC
+    pub fn with_tpu(mut self, enabled: bool) -> Self {
+        self.run.use_tpu = enabled;
+        self
+    }
+
+    /// Retry transient RPC failures up to `retries` times
+    pub fn with_max_retries(mut self, retries: usize) -> Self {
+        self.run.max_retries = Some(retries);
+        self
+    }
+
+    /// Confirm at `level` instead of the configured `commitment`, authority changes included
+    pub fn with_commitment(mut self, level: CommitmentLevel) -> Self {
+        self.run.commitment = Some(level);
+        self
+    }
+
+    pub fn rpc_url(&self) -> &str {
+        &self.rpc_url
+    }
+
+    /// `network` from config, else the cluster the RPC URL points at
+    pub fn network(&self) -> &str {
+        &self.network
+    }
+
+    pub fn state(&self) -> ShieldResult<ProjectState> {
+        Ok(self.config.load_state()?)
+    }
+
+    /// Create the private deployer and return its public key
+    pub fn init(&self) -> ShieldResult<Pubkey> {
+        if self.config.deployer_exists() {
+            return Err(ShieldError::DeployerExists);
+        }
+        self.check_git_secrets()?;
+
+        let deployer = Keypair::new();
+        self.config.save_deployer(&deployer)?;
+        self.config.add_gitignore()?;
+        self.config.save_state(&ProjectState {
+            network: self.network.clone(),
+            operations: vec![self.operation(OperationKind::Init)],
+            ..Default::default()
+        })?;
+
+        Ok(deployer.pubkey())
+    }
+
+    pub async fn status(&self) -> ShieldResult<ProjectStatus> {
+        let deployer = self.deployer()?;
+        let state = self.config.load_state()?;
+
+        Ok(ProjectStatus {
+            deployer: deployer.pubkey(),
+            balance_lamports: self.rpc_client().get_balance(&deployer.pubkey()).await?,
+            network: state.network,
+            programs: state.deployed_programs,
+        })
+    }
+
+    /// Fund the deployer from `funding` through Privacy Cash
+    ///
+    /// Applies the configured privacy delay before returning.
+    pub async fn fund(
+        &self,
+        funding: &Keypair,
+        amount_lamports: u64,
+        progress: &dyn Progress,
+    ) -> ShieldResult<FundOutcome> {
+        self.scoped(progress, self.run_fund(funding, amount_lamports, progress)).await
+    }
+
+    async fn run_fund(
+        &self,
+        funding: &Keypair,
+        amount_lamports: u64,
+        progress: &dyn Progress,
+    ) -> ShieldResult<FundOutcome> {
+        let deployer = self.deployer()?;
+        let privacy = PrivacyLayer::new(&self.rpc_url);
+        let amount_lamports = PrivacyLayer::round_amount(amount_lamports);
+        self.check_git_secrets()?;
+        let network = self.check_mainnet().await?;
+        self.check_spend_limit(&network, amount_lamports)?;
+
+        progress.step("Sending through Privacy Cash");
+        let result = privacy
+            .fund_burner_private(funding, &deployer.pubkey(), amount_lamports as f64 / 1e9)
+            .await?;
+
+        let mut state = self.config.load_state()?;
+        let mut record = self.operation(OperationKind::Fund);
+        record.amount_lamports = Some(result.amount_received);
+        record.signatures.push(result.withdraw_signature.to_string());
+        record.funding_method = Some(FundingMethod::PrivacyCash);
+        state.operations.push(record);
+        self.config.save_state(&state)?;
+
+        let delay = self.settings.privacy_delay().unwrap_or(Duration::from_secs(PRIVACY_DELAY_SECS));
+        progress.step(&format!("Applying privacy delay ({} seconds)", delay.as_secs()));
+        tokio::time::sleep(delay).await;
+
+        Ok(FundOutcome {
+            amount_received: result.amount_received,
+            fees: result.total_fees,
+            withdraw_signature: result.withdraw_signature.to_string(),
+        })
+    }
+
+    /// Deploy a new program, with a fresh program keypair unless one is given
+    pub async fn deploy(
+        &self,
+        program_data: &[u8],
+        program_keypair: Option<Keypair>,
+        progress: &dyn Progress,
+    ) -> ShieldResult<DeployOutcome> {
+        self.scoped(progress, self.run_deploy(program_data, program_keypair, progress)).await
+    }
+
+    async fn run_deploy(
+        &self,
+        program_data: &[u8],
+        program_keypair: Option<Keypair>,
+        progress: &dyn Progress,
+    ) -> ShieldResult<DeployOutcome> {
+        let deployer = self.deployer()?;
+        self.check_git_secrets()?;
+        let network = self.check_mainnet().await?;
+        let rpc_client = self.rpc_client();
+
+        let max_data_len = default_max_data_len(program_data.len());
+        let estimate = estimate_deploy(&rpc_client, program_data.len(), max_data_len, 0).await?;
+        self.check_spend_limit(&network, estimate.required_balance())?;
+        let balance = self.ensure_balance(&rpc_client, &deployer, estimate.required_balance()).await?;
+
+        let program_keypair = program_keypair.unwrap_or_else(Keypair::new);
+        let program_id = program_keypair.pubkey();
+
+        progress.step(&format!("Deploying {program_id}"));
+        let signature = deploy_program(
+            &self.config,
+            &rpc_client,
+            &self.fees(),
+            &deployer,
+            &program_keypair,
+            program_data,
+            max_data_len,
+            false,
+            progress,
+        )
+        .await?;
+        let balance_after = rpc_client.get_balance(&deployer.pubkey()).await?;
+
+        let mut state = self.config.load_state()?;
+        state.deployed_programs.push(DeployedProgram {
+            program_id: program_id.to_string(),
+            deployed_at: chrono::Utc::now().timestamp(),
+            cluster: Some(self.network.clone()),
+            authority: Some(deployer.pubkey().to_string()),
+            size: Some(program_data.len()),
+            max_data_len: Some(max_data_len),
+            bytecode_hash: Some(sha256_hex(strip_trailing_zeros(program_data))),
+            ..Default::default()
+        });
+        let cost_lamports = balance.saturating_sub(balance_after);
+        self.record(&mut state, OperationKind::Deploy, &program_id, signature, Some(cost_lamports));
+        state.last_balance = balance_after;
+        self.config.save_state(&state)?;
+
+        Ok(DeployOutcome { program_id, signature, cost_lamports })
+    }
+
+    /// Upgrade a managed program with new bytecode
+    pub async fn upgrade(
+        &self,
+        program_id: &Pubkey,
+        program_data: &[u8],
+        progress: &dyn Progress,
+    ) -> ShieldResult<DeployOutcome> {
+        self.scoped(progress, self.run_upgrade(program_id, program_data, progress)).await
+    }
+
+    async fn run_upgrade(
+        &self,
+        program_id: &Pubkey,
+        program_data: &[u8],
+        progress: &dyn Progress,
+    ) -> ShieldResult<DeployOutcome> {
+        let deployer = self.deployer()?;
+        let rpc_client = self.rpc_client();
+        self.managed(program_id)?;
+        let network = self.check_mainnet().await?;
+
+        let estimate = estimate_upgrade(&rpc_client, program_id, program_data.len(), 0).await?;
+        self.check_spend_limit(&network, estimate.required_balance())?;
+        let balance = self.ensure_balance(&rpc_client, &deployer, estimate.required_balance()).await?;
+
+        progress.step(&format!("Upgrading {program_id}"));
+        let program_authority = self.config.load_program_authority(program_id)?;
+        let authority = program_authority.as_ref().unwrap_or(&deployer);
+        let signature = upgrade_program(
+            &self.config,
+            &rpc_client,
+            &self.fees(),
+            &deployer,
+            authority,
+            program_id,
+            program_data,
+            &deployer.pubkey(),
+            progress,
+        )
+        .await?;
+        let balance_after = rpc_client.get_balance(&deployer.pubkey()).await?;
+
+        let mut state = self.config.load_state()?;
+        if let Some(program) = state.program_mut(&program_id.to_string()) {
+            program.last_upgraded = Some(chrono::Utc::now().timestamp());
+            program.size = Some(program_data.len());
+            program.bytecode_hash = Some(sha256_hex(strip_trailing_zeros(program_data)));
+        }
+        let cost_lamports = balance.saturating_sub(balance_after);
+        self.record(&mut state, OperationKind::Upgrade, program_id, signature, Some(cost_lamports));
+        state.last_balance = balance_after;
+        self.config.save_state(&state)?;
+
+        Ok(DeployOutcome { program_id: *program_id, signature, cost_lamports })
+    }
+
+    /// Make a managed program immutable. This cannot be undone.
+    ///
+    /// Refused until `finalize_min_age` has passed since the program was last
+    /// deployed or upgraded, by the local record or on-chain, whichever is later.
+    pub async fn finalize(&self, program_id: &Pubkey, progress: &dyn Progress) -> ShieldResult<Signature> {
+        self.scoped(progress, self.run_finalize(program_id, progress)).await
+    }
+
+    async fn run_finalize(&self, program_id: &Pubkey, progress: &dyn Progress) -> ShieldResult<Signature> {
+        let deployer = self.deployer()?;
+        self.managed(program_id)?;
+        self.check_mainnet().await?;
+        self.check_finalize_age(program_id).await?;
+
+        progress.step(&format!("Finalizing {program_id}"));
+        let program_authority = self.config.load_program_authority(program_id)?;
+        let authority = program_authority.as_ref().unwrap_or(&deployer);
+        let signature =
+            finalize_program(&self.rpc_client(), &self.fees(), &deployer, authority, program_id, progress).await?;
+        if program_authority.is_some() {
+            self.config.remove_program_authority(program_id)?;
+        }
+
+        let mut state = self.config.load_state()?;
+        if let Some(program) = state.program_mut(&program_id.to_string()) {
+            program.authority = None;
+            program.immutable = true;
+        }
+        self.record(&mut state, OperationKind::Finalize, program_id, signature, None);
+        self.config.save_state(&state)?;
+
+        Ok(signature)
+    }
+
+    fn deployer(&self) -> ShieldResult<Keypair> {
+        if !self.config.deployer_exists() {
+            return Err(ShieldError::NoDeployer);
+        }
+        Ok(self.config.load_deployer()?)
+    }
+
+    fn managed(&self, program_id: &Pubkey) -> ShieldResult<()> {
+        let id = program_id.to_string();
+        if !self.config.load_state()?.deployed_programs.iter().any(|p| p.program_id == id) {
+            return Err(ShieldError::UnknownProgram(id));
+        }
+        Ok(())
+    }
+
+    /// The check behind `--mainnet` and `allow_mainnet`, without the prompt
+    ///
+    /// Returns the cluster, resolved by genesis hash when neither `network`
+    /// nor the RPC URL names it; an RPC that can't be identified refuses.
+    async fn check_mainnet(&self) -> ShieldResult<String> {
+        let network = resolve_network_name(&self.settings, &self.rpc_url).await?;
+        if needs_mainnet_opt_in(&self.settings, &network) {
+            return Err(ShieldError::MainnetNotAllowed);
+        }
+        Ok(network)
+    }
+
+    /// `spend_limit` of `network`, with no override
+    fn check_spend_limit(&self, network: &str, estimated: u64) -> ShieldResult<()> {
+        match spend_limit_exceeded(&self.settings, network, estimated)? {
+            Some(limit) => Err(ShieldError::SpendLimitExceeded { estimated, limit }),
+            None => Ok(()),
+        }
+    }
+
+    fn check_git_secrets(&self) -> ShieldResult<()> {
+        match self.config.tracked_secrets() {
+            GitSecrets::Tracked(files) => Err(ShieldError::KeysTrackedByGit(files)),
+            _ => Ok(()),
+        }
+    }
+
+    /// `finalize_min_age` against the later of the local and on-chain last change
+    async fn check_finalize_age(&self, program_id: &Pubkey) -> ShieldResult<()> {
+        if self.settings.finalize_min_age().is_none() {
+            return Ok(());
+        }
+        let now = chrono::Utc::now().timestamp();
+        let local = self
+            .config
+            .load_state()?
+            .program(&program_id.to_string())
+            .map(|p| p.last_upgraded.unwrap_or(p.deployed_at));
+        // The later timestamp wins, so a local one still in the soak settles it
+        if let Some(remaining) = local.and_then(|at| self.settings.finalize_soak_remaining(at, now)) {
+            return Err(ShieldError::FinalizeTooSoon { remaining });
+        }
+        let on_chain = last_deployed_at(&self.rpc_client(), program_id).await;
+        let Some(last_change) = on_chain.max(local) else {
+            return Err(anyhow::anyhow!("cannot determine when {program_id} was last deployed or upgraded").into());
+        };
+        match self.settings.finalize_soak_remaining(last_change, now) {
+            Some(remaining) => Err(ShieldError::FinalizeTooSoon { remaining }),
+            None => Ok(()),
+        }
+    }
+
+    /// Run `work` with this project's send settings, its output going to `progress`
+    async fn scoped<T>(&self, progress: &dyn Progress, work: impl Future<Output = T>) -> T {
+        run_scoped(self.run.clone(), progress, work).await
+    }
+
+    fn rpc_client(&self) -> RpcClient {
+        let commitment = self
+            .run
+            .commitment
+            .map_or_else(|| commitment_from(&self.settings), |commitment| CommitmentConfig { commitment });
+        RpcClient::new_with_commitment(self.rpc_url.clone(), commitment)
+    }
+
+    fn fees(&self) -> FeeSettings {
+        FeeSettings::from_config(&self.settings, &self.network)
+    }
+
+    /// A new operation record, tagged with this project's network
+    fn operation(&self, kind: OperationKind) -> OperationRecord {
+        OperationRecord::new(kind, &self.network)
+    }
+
+    async fn ensure_balance(&self, rpc_client: &RpcClient, deployer: &Keypair, required: u64) -> ShieldResult<u64> {
+        let balance = rpc_client.get_balance(&deployer.pubkey()).await?;
+        if balance < required {
+            return Err(ShieldError::InsufficientBalance { balance, required });
+        }
+        Ok(balance)
+    }
+
+    fn record(
+        &self,
+        state: &mut ProjectState,
+        kind: OperationKind,
+        program_id: &Pubkey,
+        signature: Signature,
+        cost_lamports: Option<u64>,
+    ) {
+        let mut record = self.operation(kind);
+        record.program_id = Some(program_id.to_string());
+        record.signatures.push(signature.to_string());
+        record.cost_lamports = cost_lamports;
+        state.operations.push(record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn project_with_config(config: &str) -> (tempfile::TempDir, ShieldProject) {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join(".shield")).unwrap();
+        fs::write(root.path().join(".shield/config.toml"), config).unwrap();
+        let project = ShieldProject::open(root.path()).unwrap();
+        (root, project)
+    }
+
+    #[test]
+    fn open_reads_the_project_config() {
+        let (_root, project) = project_with_config(
+            "rpc_url = \"http://127.0.0.1:8899\"\nnetwork = \"testnet\"\n",
+        );
+
+        assert_eq!(project.rpc_url(), "http://127.0.0.1:8899");
+        assert_eq!(project.network(), "testnet");
+    }
+
+    #[test]
+    fn init_creates_the_deployer_once() {
+        let (root, project) = project_with_config(
+            "rpc_url = \"http://127.0.0.1:8899\"\nnetwork = \"testnet\"\n",
+        );
+
+        let deployer = project.init().unwrap();
+        assert!(root.path().join(".shield/deployer.json").exists());
+
+        let state = project.state().unwrap();
+        assert_eq!(state.network, "testnet");
+        assert_eq!(state.operations.len(), 1);
+        assert_eq!(state.operations[0].cluster.as_deref(), Some("testnet"));
+
+        assert!(matches!(project.init(), Err(ShieldError::DeployerExists)));
+        assert_eq!(project.config.load_deployer().unwrap().pubkey(), deployer);
+    }
+
+    #[test]
+    fn operations_need_a_deployer() {
+        let (_root, project) = project_with_config("rpc_url = \"http://127.0.0.1:8899\"\n");
+
+        assert!(matches!(project.deployer(), Err(ShieldError::NoDeployer)));
+    }
+
+    #[tokio::test]
+    async fn mainnet_needs_opt_in() {
+        // Nothing listens on port 1, so any RPC call would fail differently
+        let (_root, project) = project_with_config(
+            "rpc_url = \"http://127.0.0.1:1\"\nnetwork = \"mainnet-beta\"\n",
+        );
+        project.init().unwrap();
+
+        let deploy = project.deploy(&[0u8; 64], None, &NoProgress).await;
+        assert!(matches!(deploy, Err(ShieldError::MainnetNotAllowed)), "{deploy:?}");
+        let fund = project.fund(&Keypair::new(), 100_000_000, &NoProgress).await;
+        assert!(matches!(fund, Err(ShieldError::MainnetNotAllowed)), "{fund:?}");
+    }
+
+    #[tokio::test]
+    async fn finalize_waits_for_min_age() {
+        let (_root, project) = project_with_config(
+            "rpc_url = \"http://127.0.0.1:1\"\nnetwork = \"devnet\"\nfinalize_min_age = \"7d\"\n",
+        );
+        project.init().unwrap();
+        let program_id = Pubkey::new_unique();
+        let mut state = project.state().unwrap();
+        state.deployed_programs.push(DeployedProgram {
+            program_id: program_id.to_string(),
+            deployed_at: chrono::Utc::now().timestamp() - 24 * 60 * 60,
+            ..Default::default()
+        });
+        project.config.save_state(&state).unwrap();
+
+        match project.finalize(&program_id, &NoProgress).await {
+            Err(ShieldError::FinalizeTooSoon { remaining }) => {
+                assert!(remaining > Duration::from_secs(5 * 24 * 60 * 60), "{remaining:?}");
+                assert!(remaining <= Duration::from_secs(6 * 24 * 60 * 60), "{remaining:?}");
+            }
+            other => panic!("expected FinalizeTooSoon, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Routing RPC and HTTP traffic through a proxy
+//!
+//! Every HTTP client in the process (the `RpcClient` transport, the Privacy
+//! Cash SDK, webhooks, the indexer and update checks) is built on reqwest,
+//! which honors the standard proxy environment variables. A `proxy` config
+//! value is exported into those variables at startup, before the runtime
+//! spawns any threads, so it reaches all of them, including clients we don't
+//! construct ourselves.
+
+use anyhow::Result;
+use std::time::Duration;
+use crate::config::ProjectConfig;
+
+/// Variables reqwest reads, most specific first
+const PROXY_ENV_VARS: &[&str] = &["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"];
+
+/// Echoes the caller's public IP as plain text
+const EXIT_IP_URL: &str = "https://api.ipify.org";
+const EXIT_IP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Proxy in effect: the `proxy` setting, else the environment
+pub fn proxy_url() -> Option<String> {
+    if let Some(proxy) = ProjectConfig::load().ok().and_then(|c| c.proxy) {
+        return Some(proxy);
+    }
+    PROXY_ENV_VARS
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.trim().is_empty()))
+}
+
+/// Export the `proxy` setting so every HTTP client in this process uses it
+///
+/// Must run before the async runtime starts, while the process has a single
+/// thread, and before any client is built.
+pub fn apply() {
+    let Some(proxy) = ProjectConfig::load().ok().and_then(|c| c.proxy) else {
+        return;
+    };
+    for var in ["HTTPS_PROXY", "HTTP_PROXY", "ALL_PROXY"] {
+        std::env::set_var(var, &proxy);
+    }
+}
+
+/// Proxy URL with any credentials hidden, for display
+pub fn redact(url: &str) -> String {
+    match url.split_once("://") {
+        Some((scheme, rest)) => match rest.split_once('@') {
+            Some((_, host)) => format!("{scheme}://***@{host}"),
+            None => url.to_string(),
+        },
+        None => url.to_string(),
+    }
+}
+
+/// Say so when a connection failure happened while going through a proxy
+///
+/// Without this, a stopped Tor daemon looks exactly like a dead RPC.
+pub fn explain_error(error: anyhow::Error) -> anyhow::Error {
+    let Some(proxy) = proxy_url() else {
+        return error;
+    };
+
+    let chain = format!("{error:#}").to_lowercase();
+    let connection_failure = ["connect", "proxy", "socks", "timed out", "dns error", "tunnel"]
+        .iter()
+        .any(|needle| chain.contains(needle));
+    if !connection_failure {
+        return error;
+    }
+
+    error.context(format!(
+        "Connection failed while routing through proxy {}.\n\
+        Check the proxy is running and reachable (Tor listens on socks5h://127.0.0.1:9050 by default),\n\
+        or unset it with `shield-deploy config unset proxy` to test the direct path.",
+        redact(&proxy)
+    ))
+}
+
+/// Public IP seen by remote servers, through the proxy or bypassing it
+pub async fn exit_ip(direct: bool) -> Result<String> {
+    let mut builder = reqwest::Client::builder().timeout(EXIT_IP_TIMEOUT);
+    if direct {
+        builder = builder.no_proxy();
+    }
+    let ip = builder
+        .build()?
+        .get(EXIT_IP_URL)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    Ok(ip.trim().to_string())
+}
@@ -0,0 +1,157 @@
+use anyhow::Result;
+use serde::Serialize;
+
+/// Markers from the solana-security-txt format
+pub const SECURITY_TXT_BEGIN: &str = "=======BEGIN SECURITY.TXT V1=======\0";
+pub const SECURITY_TXT_END: &str = "=======END SECURITY.TXT V1=======\0";
+
+/// Contact kinds accepted by solana-security-txt
+const CONTACT_KINDS: &[&str] = &["email", "discord", "telegram", "twitter", "link", "other"];
+
+/// Fields of a solana security.txt, in the order they are written
+#[derive(Serialize, Default, Clone, PartialEq, Eq)]
+pub struct SecurityTxt {
+    pub name: String,
+    pub project_url: String,
+    pub contacts: String,
+    pub policy: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_languages: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auditors: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiry: Option<String>,
+}
+
+impl SecurityTxt {
+    /// Check every field before anything is sent on chain
+    pub fn validate(&self) -> Result<()> {
+        for (field, value) in self.fields() {
+            if value.trim().is_empty() {
+                anyhow::bail!("{field} must not be empty");
+            }
+            if value.contains('\0') {
+                anyhow::bail!("{field} must not contain NUL bytes");
+            }
+        }
+
+        validate_url(&self.project_url).map_err(|e| anyhow::anyhow!("project_url: {e}"))?;
+        validate_contacts(&self.contacts)?;
+        if looks_like_url(&self.policy) {
+            validate_url(&self.policy).map_err(|e| anyhow::anyhow!("policy: {e}"))?;
+        }
+        if let Some(source_code) = &self.source_code {
+            validate_url(source_code).map_err(|e| anyhow::anyhow!("source_code: {e}"))?;
+        }
+        if let Some(expiry) = &self.expiry {
+            chrono::NaiveDate::parse_from_str(expiry, "%Y-%m-%d")
+                .map_err(|_| anyhow::anyhow!("expiry: '{expiry}' is not a YYYY-MM-DD date"))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn fields(&self) -> Vec<(&'static str, &str)> {
+        let mut fields = vec![
+            ("name", self.name.as_str()),
+            ("project_url", self.project_url.as_str()),
+            ("contacts", self.contacts.as_str()),
+            ("policy", self.policy.as_str()),
+        ];
+        let optional = [
+            ("preferred_languages", &self.preferred_languages),
+            ("source_code", &self.source_code),
+            ("auditors", &self.auditors),
+            ("expiry", &self.expiry),
+        ];
+        fields.extend(optional.into_iter().filter_map(|(k, v)| v.as_deref().map(|v| (k, v))));
+        fields
+    }
+
+    /// Encode as `BEGIN\0key\0value\0...END\0`, the layout explorers scan for
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = SECURITY_TXT_BEGIN.as_bytes().to_vec();
+        for (key, value) in self.fields() {
+            data.extend_from_slice(key.as_bytes());
+            data.push(0);
+            data.extend_from_slice(value.as_bytes());
+            data.push(0);
+        }
+        data.extend_from_slice(SECURITY_TXT_END.as_bytes());
+        data
+    }
+
+    /// Find and parse a security.txt anywhere in `data` (bytecode or an account)
+    pub fn find(data: &[u8]) -> Option<Self> {
+        let begin = find_bytes(data, SECURITY_TXT_BEGIN.as_bytes())? + SECURITY_TXT_BEGIN.len();
+        let end = begin + find_bytes(&data[begin..], SECURITY_TXT_END.as_bytes())?;
+
+        let mut parts = data[begin..end]
+            .split(|&b| b == 0)
+            .map(|part| String::from_utf8_lossy(part).to_string());
+
+        let mut txt = SecurityTxt::default();
+        while let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+            match key.as_str() {
+                "name" => txt.name = value,
+                "project_url" => txt.project_url = value,
+                "contacts" => txt.contacts = value,
+                "policy" => txt.policy = value,
+                "preferred_languages" => txt.preferred_languages = Some(value),
+                "source_code" => txt.source_code = Some(value),
+                "auditors" => txt.auditors = Some(value),
+                "expiry" => txt.expiry = Some(value),
+                _ => {}
+            }
+        }
+
+        Some(txt)
+    }
+}
+
+/// Contacts are a comma-separated list of `kind:value`, e.g. `email:sec@example.com,link:https://…`
+pub fn validate_contacts(contacts: &str) -> Result<()> {
+    for contact in contacts.split(',').map(str::trim) {
+        let (kind, value) = contact
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("contact '{contact}' must look like kind:value"))?;
+
+        if !CONTACT_KINDS.contains(&kind) {
+            anyhow::bail!("contact kind '{kind}' is not one of: {}", CONTACT_KINDS.join(", "));
+        }
+        if value.trim().is_empty() {
+            anyhow::bail!("contact '{contact}' has no value");
+        }
+        match kind {
+            "email" if !value.contains('@') => anyhow::bail!("'{value}' is not an email address"),
+            "link" => validate_url(value).map_err(|e| anyhow::anyhow!("contact {contact}: {e}"))?,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn looks_like_url(value: &str) -> bool {
+    value.contains("://")
+}
+
+fn validate_url(value: &str) -> Result<()> {
+    let valid = value
+        .split_once("://")
+        .map(|(scheme, rest)| {
+            matches!(scheme, "http" | "https") && !rest.is_empty() && !rest.contains(char::is_whitespace)
+        })
+        .unwrap_or(false);
+
+    if !valid {
+        anyhow::bail!("'{value}' is not an http(s) URL");
+    }
+    Ok(())
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
@@ -0,0 +1,138 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use crate::config::ProjectConfig;
+
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const REPOSITORY_URL: &str = "https://github.com/Emengkeng/deploy-shield";
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/Emengkeng/deploy-shield/releases/latest";
+
+/// The background check runs at most once per day
+const CHECK_INTERVAL_SECS: i64 = 24 * 60 * 60;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+/// How long a finished command waits for an in-flight check before exiting
+const EXIT_GRACE: Duration = Duration::from_millis(500);
+
+#[derive(Serialize, Deserialize)]
+struct UpdateCache {
+    checked_at: i64,
+    latest: String,
+}
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+}
+
+/// Tag of the latest GitHub release, e.g. `v0.3.0`
+pub async fn fetch_latest_release() -> Result<String> {
+    let release: Release = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("shield-deploy/", env!("CARGO_PKG_VERSION")))
+        .build()?
+        .get(LATEST_RELEASE_URL)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .context("Unexpected response from GitHub")?;
+
+    save_cache(&release.tag_name);
+    Ok(release.tag_name)
+}
+
+/// Whether release tag `latest` is newer than version `current`
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim().trim_start_matches('v');
+    // Ignore pre-release and build suffixes
+    let core = version.split(['-', '+']).next()?;
+    core.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Opt-in check started before a command runs
+pub struct BackgroundCheck {
+    cached: Option<String>,
+    refresh: Option<JoinHandle<Option<String>>>,
+}
+
+impl BackgroundCheck {
+    /// Returns `None` unless `check_updates = true` is configured
+    pub fn start() -> Option<Self> {
+        let enabled = ProjectConfig::load().ok()?.check_updates.unwrap_or(false);
+        if !enabled {
+            return None;
+        }
+
+        let cache = load_cache();
+        let fresh = cache
+            .as_ref()
+            .map(|c| chrono::Utc::now().timestamp() - c.checked_at < CHECK_INTERVAL_SECS)
+            .unwrap_or(false);
+
+        let refresh = (!fresh).then(|| tokio::spawn(async { fetch_latest_release().await.ok() }));
+
+        Some(Self {
+            cached: cache.map(|c| c.latest),
+            refresh,
+        })
+    }
+
+    /// Print a one-line notice if a newer release is known; never fails
+    pub async fn finish(self) {
+        let refreshed = match self.refresh {
+            Some(handle) => tokio::time::timeout(EXIT_GRACE, handle)
+                .await
+                .ok()
+                .and_then(|joined| joined.ok())
+                .flatten(),
+            None => None,
+        };
+
+        if let Some(latest) = refreshed.or(self.cached) {
+            if is_newer(&latest, CURRENT_VERSION) {
+                eprintln!(
+                    "\nshield-deploy {latest} is available (you have {CURRENT_VERSION}). \
+                    Run `shield-deploy update --check` for details."
+                );
+            }
+        }
+    }
+}
+
+fn cache_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("shield-deploy").join("update-check.json"))
+}
+
+fn load_cache() -> Option<UpdateCache> {
+    let content = fs::read_to_string(cache_path()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Best effort; a read-only cache dir just means checking again next time
+fn save_cache(latest: &str) {
+    let Some(path) = cache_path() else { return };
+    let cache = UpdateCache {
+        checked_at: chrono::Utc::now().timestamp(),
+        latest: latest.to_string(),
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string(&cache) {
+        let _ = fs::write(path, json);
+    }
+}
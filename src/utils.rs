@@ -1,21 +1,97 @@
 use anyhow::{Context, Result};
-use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Password, Select};
+use futures::stream::{FuturesUnordered, StreamExt};
+use futures::FutureExt;
+use indicatif::ProgressBar;
 use solana_cli_config::{Config as SolanaConfig, CONFIG_FILE};
-use solana_client::rpc_client::RpcClient;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig};
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_client::nonblocking::tpu_client::TpuClient;
+use solana_client::tpu_client::TpuClientConfig;
 use solana_loader_v3_interface::{
+    state::UpgradeableLoaderState,
     instruction as bpf_loader_upgradeable,
 };
 use solana_sdk::{
+    clock::DEFAULT_MS_PER_SLOT,
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
     instruction::Instruction as SdkInstruction,
     instruction::AccountMeta,
+    message::Message,
+    nonce::state::{State as NonceAccountState, Versions as NonceVersions},
+    packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
-    signature::{Keypair, read_keypair_file, Signer},
-    transaction::Transaction
+    signature::{Keypair, read_keypair_file, Signature, Signer},
+    system_program,
+    transaction::{Transaction, TransactionError},
 };
+use solana_address::Address;
+use solana_system_interface::instruction as system_instruction;
 use solana_pubkey::Pubkey as SolanaPubkeyV2;
+use solana_sdk_ids::bpf_loader_upgradeable::ID as LOADER_ID;
+use rand::Rng;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::confirm::{websocket_url, SignatureWatcher};
+use crate::compute::{note_confirmed, note_priority_fee, priority_fee_of, with_compute_budget, with_compute_budget_for, FeeSettings, TxClass};
+use crate::config::ProjectConfig;
+use crate::project::Progress;
+use crate::proxy::proxy_url;
+
+/// How often `send_and_confirm` checks a sent transaction's status
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Wait for a websocket notification this long before asking over HTTP
+const WEBSOCKET_QUIET_PERIOD: Duration = Duration::from_secs(5);
+
+/// Longest a write's signature subscription stays open; past blockhash expiry
+const SIGNATURE_SUBSCRIPTION_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Write transactions kept in flight unless `--concurrency` says otherwise
+pub const DEFAULT_WRITE_CONCURRENCY: usize = 8;
+
+/// Most signatures the RPC accepts in one `getSignatureStatuses` call
+const MAX_SIGNATURE_STATUSES: usize = 256;
+
+/// Sends of one chunk before a buffer write gives up
+const MAX_CHUNK_ATTEMPTS: usize = 5;
+
+/// Slots after which chunk writes are signed with a fresh blockhash
+const BLOCKHASH_REFRESH_SLOTS: u64 = 60;
+
+/// Age of a blockhash after `BLOCKHASH_REFRESH_SLOTS` at the nominal slot time
+const BLOCKHASH_REFRESH: Duration = Duration::from_millis(BLOCKHASH_REFRESH_SLOTS * DEFAULT_MS_PER_SLOT);
+
+/// Fresh blockhashes an RPC may reject in a row before a write gives up
+const MAX_STALE_BLOCKHASHES: usize = 10;
+
+/// Retries of a transient RPC failure unless `--max-retries` says otherwise
+pub const DEFAULT_MAX_RETRIES: usize = 5;
+
+/// Wait before the first retry; doubled for each one after
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest wait between two retries
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(15);
 
 pub fn prompt_confirmation(message: &str) -> Result<bool> {
+    if assume_yes() {
+        println!("{message} yes (--yes)");
+        return Ok(true);
+    }
+    ensure_interactive(message)?;
     Confirm::with_theme(&ColorfulTheme::default())
         .with_prompt(message)
         .default(true)
@@ -23,20 +99,121 @@ pub fn prompt_confirmation(message: &str) -> Result<bool> {
         .context("Failed to get user confirmation")
 }
 
-pub fn prompt_amount(message: &str) -> Result<f64> {
+/// Formats accepted wherever an amount is read
+const AMOUNT_FORMATS: &str = "Enter SOL (`2.5` or `2.5 sol`) or lamports (`1500000000 lamports`)";
+
+/// An amount given on the command line or at a prompt
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Amount {
+    Lamports(u64),
+    /// `all` / `max`: everything available, resolved by the caller
+    Max,
+}
+
+impl Amount {
+    /// Lamports this amount stands for, given what is available
+    pub fn resolve(self, available: u64) -> u64 {
+        match self {
+            Amount::Lamports(lamports) => lamports,
+            Amount::Max => available,
+        }
+    }
+}
+
+impl std::str::FromStr for Amount {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        parse_amount(input, true)
+    }
+}
+
+/// Parse an amount without going through floating point
+///
+/// A bare number or one suffixed `sol` is SOL with up to 9 decimals;
+/// a `lamports` suffix takes a whole number. `all` and `max` are only
+/// accepted when `allow_max` is set.
+pub fn parse_amount(input: &str, allow_max: bool) -> Result<Amount, String> {
+    let input = input.trim().to_ascii_lowercase();
+
+    if input == "all" || input == "max" {
+        return if allow_max {
+            Ok(Amount::Max)
+        } else {
+            Err(format!("`{input}` is not supported here. {AMOUNT_FORMATS}"))
+        };
+    }
+
+    let lamports = if let Some(number) = input
+        .strip_suffix("lamports")
+        .or_else(|| input.strip_suffix("lamport"))
+    {
+        let number = number.trim_end();
+        if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("Lamports must be a whole number. {AMOUNT_FORMATS}"));
+        }
+        number
+            .parse::<u64>()
+            .map_err(|_| format!("{number} lamports is more than a u64 can hold"))?
+    } else {
+        let number = input.strip_suffix("sol").unwrap_or(&input).trim_end();
+        sol_to_lamports(number)?
+    };
+
+    if lamports == 0 {
+        return Err("Amount must be greater than zero".to_string());
+    }
+    Ok(Amount::Lamports(lamports))
+}
+
+fn sol_to_lamports(number: &str) -> Result<u64, String> {
+    let invalid = || format!("`{number}` is not a valid amount. {AMOUNT_FORMATS}");
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(invalid());
+    }
+    if !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    if fraction.len() > 9 {
+        return Err(format!("`{number}` has more than 9 decimal places (1 lamport = 0.000000001 SOL)"));
+    }
+
+    let overflow = || format!("{number} SOL is more than a u64 of lamports can hold");
+    let whole: u64 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| overflow())? };
+    let fraction: u64 = format!("{fraction:0<9}").parse().map_err(|_| invalid())?;
+
+    whole
+        .checked_mul(1_000_000_000)
+        .and_then(|lamports| lamports.checked_add(fraction))
+        .ok_or_else(overflow)
+}
+
+/// Prompt for an amount; `all`/`max` are offered when `allow_max` is set
+pub fn prompt_amount(message: &str, allow_max: bool) -> Result<Amount> {
+    ensure_interactive(message)?;
     let input: String = Input::with_theme(&ColorfulTheme::default())
         .with_prompt(message)
-        .validate_with(|input: &String| -> Result<(), &str> {
-            match input.parse::<f64>() {
-                Ok(val) if val > 0.0 => Ok(()),
-                _ => Err("Please enter a positive number"),
-            }
+        .validate_with(|input: &String| -> Result<(), String> {
+            parse_amount(input, allow_max).map(|_| ())
         })
         .interact_text()
         .context("Failed to get amount")?;
+
+    parse_amount(&input, allow_max).map_err(anyhow::Error::msg)
+}
+
+pub fn prompt_passphrase(message: &str, confirm: bool) -> Result<String> {
+    ensure_interactive(message)?;
+    let theme = ColorfulTheme::default();
+    let mut prompt = Password::with_theme(&theme).with_prompt(message);
+    
+    if confirm {
+        prompt = prompt.with_confirmation("Confirm passphrase", "Passphrases do not match");
+    }
     
-    input.parse::<f64>()
-        .context("Failed to parse amount")
+    prompt.interact().context("Failed to read passphrase")
 }
 
 pub enum FundingWalletChoice {
@@ -47,6 +224,12 @@ pub enum FundingWalletChoice {
 pub fn prompt_funding_wallet() -> Result<FundingWalletChoice> {
     let choices = vec!["Use current Solana CLI wallet", "Use a keypair file", "Cancel"];
     
+    if assume_yes() {
+        println!("Funding wallet: {} (--yes)", choices[0]);
+        return Ok(FundingWalletChoice::SolanaCli);
+    }
+    ensure_interactive("Choose a funding wallet")?;
+    
     let selection = Select::with_theme(&ColorfulTheme::default())
         .with_prompt("Choose a funding wallet")
         .items(&choices)
@@ -87,160 +270,658 @@ pub fn load_funding_keypair(choice: FundingWalletChoice) -> Result<Keypair> {
     }
 }
 
+/// Create an empty buffer account sized for `program_len` bytes of program data
+///
+/// The payer becomes the buffer authority once the loader initializes it.
+pub async fn create_buffer_account(
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    payer: &Keypair,
+    buffer_keypair: &Keypair,
+    program_len: usize,
+) -> Result<Signature> {
+    let buffer_size = UpgradeableLoaderState::size_of_buffer(program_len);
+    let buffer_lamports = rpc_client
+        .get_minimum_balance_for_rent_exemption(buffer_size).await
+        .context("Failed to get rent exemption for buffer")?;
+
+    let payer_v2 = SolanaPubkeyV2::new_from_array(payer.pubkey().to_bytes());
+    let buffer_v2 = SolanaPubkeyV2::new_from_array(buffer_keypair.pubkey().to_bytes());
+
+    // Creates the account and runs InitializeBuffer in one step
+    let instructions = bpf_loader_upgradeable::create_buffer(
+        &payer_v2,
+        &buffer_v2,
+        &payer_v2,
+        buffer_lamports,
+        program_len,
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to build buffer instructions: {e}"))?;
+
+    let sdk_instructions: Vec<SdkInstruction> = instructions
+        .into_iter()
+        .map(|ix| SdkInstruction {
+            program_id: Pubkey::from(ix.program_id.to_bytes()),
+            accounts: ix
+                .accounts
+                .iter()
+                .map(|acc| AccountMeta {
+                    pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                    is_signer: acc.is_signer,
+                    is_writable: acc.is_writable,
+                })
+                .collect(),
+            data: ix.data,
+        })
+        .collect();
+
+    let recent_blockhash = with_retry(|| rpc_client.get_latest_blockhash()).await?;
+    let mut transaction = Transaction::new_with_payer(
+        &with_compute_budget_for(rpc_client, fees, TxClass::CreateBuffer, &payer.pubkey(), &sdk_instructions).await,
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, buffer_keypair], recent_blockhash);
+
+    let signature = send_and_confirm(rpc_client, &transaction).await
+        .context("Failed to create buffer account")?;
+    note_buffer_signature(&signature);
+    Ok(signature)
+}
+
+static BUFFER_SIGNATURES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Remember a confirmed buffer creation or write for the operation record
+pub fn note_buffer_signature(signature: &Signature) {
+    if let Ok(mut signatures) = BUFFER_SIGNATURES.lock() {
+        signatures.push(signature.to_string());
+    }
+}
+
+/// Buffer signatures confirmed since the last call, oldest first
+pub fn take_buffer_signatures() -> Vec<String> {
+    BUFFER_SIGNATURES.lock().map(|mut s| std::mem::take(&mut *s)).unwrap_or_default()
+}
+
+/// Hand a program's upgrade authority to `new_authority` with SetAuthorityChecked
+///
+/// Both authorities sign, proving the new key is usable before it takes
+/// over. `payer` covers the fee so neither authority needs a balance.
+pub async fn set_upgrade_authority_checked(
+    rpc_client: &RpcClient,
+    payer: &Keypair,
+    current_authority: &Keypair,
+    new_authority: &Keypair,
+    program_id: &Pubkey,
+) -> Result<Signature> {
+    let loader_id = Pubkey::new_from_array(LOADER_ID.to_bytes());
+    let (programdata_address, _) = Pubkey::find_program_address(&[program_id.as_ref()], &loader_id);
+
+    let set_authority_ix = bpf_loader_upgradeable::set_upgrade_authority_checked(
+        &SolanaPubkeyV2::new_from_array(programdata_address.to_bytes()),
+        &SolanaPubkeyV2::new_from_array(current_authority.pubkey().to_bytes()),
+        &SolanaPubkeyV2::new_from_array(new_authority.pubkey().to_bytes()),
+    );
+
+    let sdk_instruction = SdkInstruction {
+        program_id: Pubkey::from(set_authority_ix.program_id.to_bytes()),
+        accounts: set_authority_ix
+            .accounts
+            .iter()
+            .map(|acc| AccountMeta {
+                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            })
+            .collect(),
+        data: set_authority_ix.data,
+    };
+
+    let recent_blockhash = with_retry(|| rpc_client.get_latest_blockhash()).await?;
+    let mut transaction = Transaction::new_with_payer(
+        &with_compute_budget(rpc_client, TxClass::SetAuthority, &payer.pubkey(), &[sdk_instruction]).await,
+        Some(&payer.pubkey()),
+    );
+    // The payer is often also the current authority
+    let mut signers = vec![payer, current_authority, new_authority];
+    signers.dedup_by_key(|k| k.pubkey());
+    transaction.sign(&signers, recent_blockhash);
+
+    send_and_confirm(rpc_client, &transaction).await
+        .context("Failed to set upgrade authority")
+}
+
+/// Signed loader Write transaction for one chunk
+async fn write_transaction(
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    authority: &Keypair,
+    buffer_pubkey: &Pubkey,
+    offset: usize,
+    chunk: &[u8],
+    recent_blockhash: Hash,
+) -> Transaction {
+    let buffer_pubkey_v2 = SolanaPubkeyV2::new_from_array(buffer_pubkey.to_bytes());
+    let authority_pubkey_v2 = SolanaPubkeyV2::new_from_array(authority.pubkey().to_bytes());
+
+    let write_ix = bpf_loader_upgradeable::write(
+        &buffer_pubkey_v2,
+        &authority_pubkey_v2,
+        offset as u32,
+        chunk.to_vec(),
+    );
+
+    let sdk_instruction = SdkInstruction {
+        program_id: Pubkey::from(write_ix.program_id.to_bytes()),
+        accounts: write_ix
+            .accounts
+            .iter()
+            .map(|acc| AccountMeta {
+                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            })
+            .collect(),
+        data: write_ix.data,
+    };
+
+    let payer = fee_payer(authority);
+    let mut transaction = Transaction::new_with_payer(
+        &with_compute_budget_for(rpc_client, fees, TxClass::Write, &payer.pubkey(), &[sdk_instruction]).await,
+        Some(&payer.pubkey()),
+    );
+    let mut signers = vec![&*payer, authority];
+    signers.dedup_by_key(|k| k.pubkey());
+    transaction.sign(&signers, recent_blockhash);
+    transaction
+}
+
 /// Write program data with automatic chunking and progress
 pub async fn write_program_data_chunked(
     rpc_client: &RpcClient,
+    fees: &FeeSettings,
     authority: &Keypair,
     buffer_pubkey: &Pubkey,
     program_data: &[u8],
-    show_progress: bool,
+    progress: &dyn Progress,
 ) -> Result<()> {
     let chunk_size = calculate_max_write_chunk_size();
-    let total_chunks = program_data.len().div_ceil(chunk_size);
-    
-    if show_progress {
-        println!("  ↳ Writing {} bytes in {} chunks", program_data.len(), total_chunks);
+    let chunk_indices: Vec<usize> = (0..program_data.len().div_ceil(chunk_size)).collect();
+
+    progress.step(&format!("Writing {} bytes in {} chunks", program_data.len(), chunk_indices.len()));
+
+    write_chunks(rpc_client, fees, authority, buffer_pubkey, program_data, &chunk_indices, progress).await?;
+    verify_written(rpc_client, fees, authority, buffer_pubkey, program_data, progress).await
+}
+
+/// Finish writing a partially written buffer
+///
+/// Reads the buffer back and only sends the chunks whose on-chain bytes
+/// differ from the artifact, so an interrupted write can be picked up again.
+pub async fn write_missing_chunks(
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    authority: &Keypair,
+    buffer_pubkey: &Pubkey,
+    program_data: &[u8],
+    progress: &dyn Progress,
+) -> Result<()> {
+    let chunk_indices = missing_chunks(rpc_client, buffer_pubkey, program_data).await?;
+
+    let total_chunks = program_data.len().div_ceil(calculate_max_write_chunk_size());
+    progress.step(&format!(
+        "{}/{} chunks already written, {} remaining",
+        total_chunks - chunk_indices.len(),
+        total_chunks,
+        chunk_indices.len()
+    ));
+
+    if chunk_indices.is_empty() {
+        return Ok(());
     }
-    
-    let mut failed_chunks = Vec::new();
-    
-    for (chunk_index, chunk) in program_data.chunks(chunk_size).enumerate() {
-        let offset = chunk_index * chunk_size;
-        
-        let buffer_pubkey_v2 = SolanaPubkeyV2::new_from_array(buffer_pubkey.to_bytes());
-        let authority_pubkey_v2 = SolanaPubkeyV2::new_from_array(authority.pubkey().to_bytes());
 
-        let write_ix = bpf_loader_upgradeable::write(
-            &buffer_pubkey_v2,
-            &authority_pubkey_v2,
-            offset as u32,
-            chunk.to_vec(),
+    write_chunks(rpc_client, fees, authority, buffer_pubkey, program_data, &chunk_indices, progress).await?;
+    verify_written(rpc_client, fees, authority, buffer_pubkey, program_data, progress).await
+}
+
+/// Indices of the chunks whose on-chain bytes differ from `program_data`
+async fn missing_chunks(rpc_client: &RpcClient, buffer_pubkey: &Pubkey, program_data: &[u8]) -> Result<Vec<usize>> {
+    let account = with_retry(|| rpc_client.get_account(buffer_pubkey)).await
+        .context("Buffer account not found")?;
+
+    let header_len = UpgradeableLoaderState::size_of_buffer_metadata();
+    if account.data.len() < header_len + program_data.len() {
+        anyhow::bail!(
+            "Buffer {buffer_pubkey} is too small for this artifact ({} < {} bytes)",
+            account.data.len().saturating_sub(header_len),
+            program_data.len()
         );
-        
-        let sdk_instruction = SdkInstruction {
-            program_id: Pubkey::from(write_ix.program_id.to_bytes()),
-            accounts: write_ix
-                .accounts
-                .iter()
-                .map(|acc| AccountMeta {
-                    pubkey: Pubkey::from(acc.pubkey.to_bytes()),
-                    is_signer: acc.is_signer,
-                    is_writable: acc.is_writable,
-                })
-                .collect(),
-            data: write_ix.data,
-        };
-        
-        let recent_blockhash = rpc_client.get_latest_blockhash()?;
-        let mut transaction = Transaction::new_with_payer(
-            &[sdk_instruction],
-            Some(&authority.pubkey()),
+    }
+    let written = &account.data[header_len..];
+
+    let chunk_size = calculate_max_write_chunk_size();
+    Ok(program_data
+        .chunks(chunk_size)
+        .enumerate()
+        .filter(|(i, chunk)| {
+            let offset = i * chunk_size;
+            &written[offset..offset + chunk.len()] != *chunk
+        })
+        .map(|(i, _)| i)
+        .collect())
+}
+
+/// Read the buffer back, rewrite any chunk that didn't land, and check again
+async fn verify_written(
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    authority: &Keypair,
+    buffer_pubkey: &Pubkey,
+    program_data: &[u8],
+    progress: &dyn Progress,
+) -> Result<()> {
+    let missing = missing_chunks(rpc_client, buffer_pubkey, program_data).await?;
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    progress.step(&format!("{} chunks missing on read-back, rewriting...", missing.len()));
+    write_chunks(rpc_client, fees, authority, buffer_pubkey, program_data, &missing, progress).await?;
+
+    let missing = missing_chunks(rpc_client, buffer_pubkey, program_data).await?;
+    if let Some(&first) = missing.first() {
+        anyhow::bail!(
+            "{} chunks are still missing from buffer {buffer_pubkey} (first at offset {})",
+            missing.len(),
+            first * calculate_max_write_chunk_size()
         );
-        transaction.sign(&[authority], recent_blockhash);
-        
-        // Try to send transaction
-        let result = rpc_client.send_and_confirm_transaction(&transaction);
-        
-        if result.is_err() {
-            failed_chunks.push(chunk_index);
+    }
+    Ok(())
+}
+
+/// Send write transactions with up to `write_concurrency()` in flight
+///
+/// Confirmations arrive as websocket notifications; without a websocket, or
+/// after `WEBSOCKET_QUIET_PERIOD` with none, statuses are polled for the
+/// whole window at once. A chunk whose transaction fails or expires goes
+/// back in the queue on its own, up to `MAX_CHUNK_ATTEMPTS` sends. One blockhash signs every write until it is
+/// `BLOCKHASH_REFRESH_SLOTS` old or the RPC reports it unknown; a send
+/// rejected with `BlockhashNotFound` is re-signed without using an attempt.
+///
+/// With `--use-tpu`, writes go straight to the current and upcoming leaders
+/// over QUIC and only confirmation uses the RPC. A write the TPU client
+/// can't send, or a TPU connection that can't be set up, falls back to the
+/// RPC. A configured proxy turns the TPU path off, since QUIC to the leaders
+/// would bypass it.
+async fn write_chunks(
+    rpc_client: &RpcClient,
+    fees: &FeeSettings,
+    authority: &Keypair,
+    buffer_pubkey: &Pubkey,
+    program_data: &[u8],
+    chunk_indices: &[usize],
+    progress: &dyn Progress,
+) -> Result<()> {
+    let chunk_size = calculate_max_write_chunk_size();
+    let total_chunks = chunk_indices.len();
+    let chunk_at = |chunk_index: usize| {
+        let offset = chunk_index * chunk_size;
+        (offset, &program_data[offset..std::cmp::min(offset + chunk_size, program_data.len())])
+    };
+    let concurrency = write_concurrency();
+    let watcher = SignatureWatcher::connect(rpc_client).await;
+    let mut notifications = FuturesUnordered::new();
+    let tpu = if use_tpu() && proxy_url().is_some() {
+        progress.step("Proxy configured; sending writes through the RPC instead of the TPU");
+        None
+    } else if use_tpu() {
+        // The TPU client tracks leaders in the background and needs its own handle
+        let tpu_rpc = Arc::new(RpcClient::new_with_commitment(rpc_client.url(), rpc_client.commitment()));
+        let ws_url = websocket_url(&rpc_client.url());
+        match TpuClient::new("shield-deploy", tpu_rpc, &ws_url, TpuClientConfig::default()).await {
+            Ok(tpu) => {
+                progress.step("Sending writes to the leaders over QUIC");
+                Some(tpu)
+            }
+            Err(e) => {
+                progress.step(&format!("TPU connection failed ({e}); sending writes through the RPC"));
+                None
+            }
         }
-        
-        if show_progress && ((chunk_index + 1) % 10 == 0 || chunk_index + 1 == total_chunks) {
-            println!("  ↳ Progress: {}/{} chunks", chunk_index + 1, total_chunks);
+    } else {
+        None
+    };
+    let started = Instant::now();
+
+    let mut queue: VecDeque<usize> = chunk_indices.iter().copied().collect();
+    let mut attempts: HashMap<usize, usize> = HashMap::new();
+    let mut last_error: HashMap<usize, String> = HashMap::new();
+    let mut in_flight: Vec<(usize, Signature, Hash)> = Vec::new();
+    // Every write carries the same compute budget, so one fee figure covers them
+    let mut fee_per_write = 0;
+    let mut blockhash = with_retry(|| rpc_client.get_latest_blockhash()).await?;
+    let mut blockhash_fetched = Instant::now();
+    // A resent chunk needs a new blockhash, or it would repeat the same signature
+    let mut resending = false;
+    let mut stale_blockhashes = 0;
+    let mut confirmed = 0;
+    let mut retried = 0;
+    let mut tpu_sends = 0;
+    let mut rpc_sends = 0;
+
+    while !queue.is_empty() || !in_flight.is_empty() {
+        if resending || blockhash_fetched.elapsed() > BLOCKHASH_REFRESH {
+            blockhash = with_retry(|| rpc_client.get_latest_blockhash()).await?;
+            blockhash_fetched = Instant::now();
+            resending = false;
         }
-    }
-    
-    // Retry failed chunks once
-    if !failed_chunks.is_empty() {
-        println!("  ⚠️  Retrying {} failed chunks...", failed_chunks.len());
-        
-        for &chunk_index in &failed_chunks {
-            let offset = chunk_index * chunk_size;
-            let chunk = &program_data[offset..std::cmp::min(offset + chunk_size, program_data.len())];
-            
-            let buffer_pubkey_v2 = SolanaPubkeyV2::new_from_array(buffer_pubkey.to_bytes());
-            let authority_pubkey_v2 = SolanaPubkeyV2::new_from_array(authority.pubkey().to_bytes());
-
-            let write_ix = bpf_loader_upgradeable::write(
-                &buffer_pubkey_v2,
-                &authority_pubkey_v2,
-                offset as u32,
-                chunk.to_vec(),
-            );
-            
-            let sdk_instruction = SdkInstruction {
-                program_id: Pubkey::from(write_ix.program_id.to_bytes()),
-                accounts: write_ix
-                    .accounts
-                    .iter()
-                    .map(|acc| AccountMeta {
-                        pubkey: Pubkey::from(acc.pubkey.to_bytes()),
-                        is_signer: acc.is_signer,
-                        is_writable: acc.is_writable,
-                    })
-                    .collect(),
-                data: write_ix.data,
+
+        while in_flight.len() < concurrency {
+            let Some(chunk_index) = queue.pop_front() else { break };
+            let sends = attempts.entry(chunk_index).or_default();
+            if *sends == MAX_CHUNK_ATTEMPTS {
+                anyhow::bail!(
+                    "Failed to write chunk {} after {MAX_CHUNK_ATTEMPTS} attempts: {}",
+                    chunk_index + 1,
+                    last_error.get(&chunk_index).map_or("unknown error", String::as_str)
+                );
+            }
+            *sends += 1;
+            if *sends > 1 {
+                retried += 1;
+            }
+
+            let (offset, chunk) = chunk_at(chunk_index);
+            let transaction = write_transaction(rpc_client, fees, authority, buffer_pubkey, offset, chunk, blockhash).await;
+            fee_per_write = priority_fee_of(&transaction.message);
+            let sent = match &tpu {
+                Some(tpu) if tpu.try_send_transaction(&transaction).await.is_ok() => {
+                    tpu_sends += 1;
+                    Ok(transaction.signatures[0])
+                }
+                _ => {
+                    rpc_sends += 1;
+                    with_retry(|| rpc_client.send_transaction_with_config(&transaction, send_config(rpc_client))).await
+                }
             };
-            
-            let recent_blockhash = rpc_client.get_latest_blockhash()?;
-            let mut transaction = Transaction::new_with_payer(
-                &[sdk_instruction],
-                Some(&authority.pubkey()),
-            );
-            transaction.sign(&[authority], recent_blockhash);
-            
-            rpc_client
-                .send_and_confirm_transaction(&transaction)
-                .context(format!("Failed to write chunk {} after retry", chunk_index + 1))?;
+            match sent {
+                Ok(signature) => {
+                    stale_blockhashes = 0;
+                    in_flight.push((chunk_index, signature, blockhash));
+                    if watcher.is_connected() {
+                        notifications.push(watcher.wait(signature, SIGNATURE_SUBSCRIPTION_TIMEOUT));
+                    }
+                }
+                // The RPC is behind or the hash aged out; re-sign the same chunk
+                Err(e) if is_blockhash_not_found(&e) => {
+                    stale_blockhashes += 1;
+                    if stale_blockhashes > MAX_STALE_BLOCKHASHES {
+                        anyhow::bail!(
+                            "The RPC rejected {MAX_STALE_BLOCKHASHES} fresh blockhashes in a row while writing chunk {}",
+                            chunk_index + 1
+                        );
+                    }
+                    if let Some(sends) = attempts.get_mut(&chunk_index) {
+                        *sends -= 1;
+                    }
+                    queue.push_front(chunk_index);
+                    resending = true;
+                    break;
+                }
+                Err(e) => {
+                    last_error.insert(chunk_index, format!("{:#}", explain_send_error(e)));
+                    queue.push_back(chunk_index);
+                    resending = true;
+                    break;
+                }
+            }
         }
-        
-        println!("  ✓ All failed chunks retried successfully");
+
+        let mut notified: HashMap<Signature, Result<(), TransactionError>> = HashMap::new();
+        if notifications.is_empty() {
+            tokio::time::sleep(CONFIRM_POLL_INTERVAL).await;
+        } else if let Ok(Some(first)) = tokio::time::timeout(WEBSOCKET_QUIET_PERIOD, notifications.next()).await {
+            // Take everything already delivered, not just the first
+            let ready = std::iter::from_fn(|| notifications.next().now_or_never().flatten());
+            for (signature, result) in std::iter::once(first).chain(ready) {
+                if let Some(result) = result {
+                    notified.insert(signature, result);
+                }
+            }
+        }
+        if in_flight.is_empty() {
+            continue;
+        }
+
+        // Without notifications, poll so failures and expired blockhashes still surface
+        let polled = notified.is_empty();
+        let statuses: Vec<Option<Result<(), TransactionError>>> = if polled {
+            let signatures: Vec<Signature> = in_flight.iter().map(|(_, signature, _)| *signature).collect();
+            let mut statuses = Vec::with_capacity(signatures.len());
+            for batch in signatures.chunks(MAX_SIGNATURE_STATUSES) {
+                statuses.extend(
+                    with_retry(|| rpc_client.get_signature_statuses(batch))
+                        .await?
+                        .value
+                        .into_iter()
+                        .map(|status| {
+                            status.and_then(|status| {
+                                let settled = status.err.is_some() || status.satisfies_commitment(rpc_client.commitment());
+                                settled.then(|| status.err.map_or(Ok(()), Err))
+                            })
+                        }),
+                );
+            }
+            statuses
+        } else {
+            in_flight.iter().map(|(_, signature, _)| notified.remove(signature)).collect()
+        };
+        let mut expired: HashMap<Hash, bool> = HashMap::new();
+        let mut pending = Vec::with_capacity(in_flight.len());
+        for ((chunk_index, signature, hash), status) in in_flight.drain(..).zip(statuses) {
+            match status {
+                Some(Err(err)) => {
+                    last_error.insert(chunk_index, format!("transaction {signature} failed: {err}"));
+                    queue.push_back(chunk_index);
+                    resending = true;
+                }
+                Some(Ok(())) => {
+                    note_priority_fee(fee_per_write);
+                    note_buffer_signature(&signature);
+                    confirmed += 1;
+                    if confirmed % 10 == 0 || confirmed == total_chunks {
+                        progress.step(&format!("Progress: {confirmed}/{total_chunks} chunks"));
+                    }
+                }
+                None if !polled => pending.push((chunk_index, signature, hash)),
+                None => {
+                    let is_expired = match expired.get(&hash) {
+                        Some(&is_expired) => is_expired,
+                        None => {
+                            let is_expired =
+                                !with_retry(|| rpc_client.is_blockhash_valid(&hash, CommitmentConfig::processed())).await?;
+                            expired.insert(hash, is_expired);
+                            is_expired
+                        }
+                    };
+                    if is_expired {
+                        last_error.insert(chunk_index, format!("transaction {signature} expired"));
+                        queue.push_back(chunk_index);
+                        resending = true;
+                    } else {
+                        pending.push((chunk_index, signature, hash));
+                    }
+                }
+            }
+        }
+        in_flight = pending;
     }
-    
-    if show_progress {
-        println!("  ✓ All data written successfully");
+
+    let route = send_route(tpu_sends, rpc_sends);
+    if let Some(mut tpu) = tpu {
+        tpu.shutdown().await;
     }
-    
+    if retried > 0 {
+        progress.step(&format!("{retried} chunk writes were retried"));
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+    progress.step(&format!(
+        "{total_chunks} chunks in {elapsed:.1}s ({:.1} chunks/s via {route})",
+        total_chunks as f64 / elapsed.max(0.001)
+    ));
+    progress.step("All data written successfully");
+
     Ok(())
 }
 
+/// Where buffer writes went, for the throughput summary
+fn send_route(tpu_sends: usize, rpc_sends: usize) -> String {
+    match (tpu_sends, rpc_sends) {
+        (0, _) => "RPC".to_string(),
+        (_, 0) => "TPU".to_string(),
+        (tpu, rpc) => format!("TPU, {rpc} of {} sends fell back to RPC", tpu + rpc),
+    }
+}
+
 pub fn get_rpc_url() -> Result<String> {
+    Ok(rpc_url_from(&ProjectConfig::load()?))
+}
+
+/// RPC endpoint for `config`: its `rpc_url`, then the Solana CLI config, then devnet
+pub fn rpc_url_from(config: &ProjectConfig) -> String {
+    // Project or global shield-deploy config takes precedence
+    if let Some(rpc_url) = &config.rpc_url {
+        return rpc_url.clone();
+    }
+    
     // Try to get from Solana CLI config
     if let Some(config_file) = CONFIG_FILE.as_ref() {
         if let Ok(config) = SolanaConfig::load(config_file) {
-            return Ok(config.json_rpc_url);
+            return config.json_rpc_url;
         }
     }
     
     // Default to devnet for hackathon
-    Ok("https://api.devnet.solana.com".to_string())
+    "https://api.devnet.solana.com".to_string()
+}
+
+/// Bytes per buffer write: `--chunk-size`, else the most a packet can carry
+pub fn calculate_max_write_chunk_size() -> usize {
+    if let Some(size) = CHUNK_SIZE_OVERRIDE.get() {
+        return *size;
+    }
+    max_write_chunk_size(fee_payer_set())
+}
+
+/// Largest Write payload that fits in a packet, measured on a probe transaction
+///
+/// The probe carries both compute budget instructions and, with a separate
+/// fee payer, the second signature, so the result holds whatever budget a
+/// write ends up with.
+pub fn max_write_chunk_size(separate_fee_payer: bool) -> usize {
+    let authority = Pubkey::new_unique();
+    let payer = if separate_fee_payer { Pubkey::new_unique() } else { authority };
+    let buffer_v2 = SolanaPubkeyV2::new_from_array(Pubkey::new_unique().to_bytes());
+    let authority_v2 = SolanaPubkeyV2::new_from_array(authority.to_bytes());
+
+    let write_ix = bpf_loader_upgradeable::write(&buffer_v2, &authority_v2, 0, Vec::new());
+    let write_ix = SdkInstruction {
+        program_id: Pubkey::from(write_ix.program_id.to_bytes()),
+        accounts: write_ix
+            .accounts
+            .iter()
+            .map(|acc| AccountMeta {
+                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            })
+            .collect(),
+        data: write_ix.data,
+    };
+    let instructions = [
+        ComputeBudgetInstruction::set_compute_unit_limit(u32::MAX),
+        ComputeBudgetInstruction::set_compute_unit_price(u64::MAX),
+        write_ix,
+    ];
+
+    let message = Message::new(&instructions, Some(&payer));
+    let probe = Transaction {
+        signatures: vec![Signature::default(); message.header.num_required_signatures as usize],
+        message,
+    };
+    let probe_len = bincode::serialized_size(&probe).map_or(PACKET_DATA_SIZE, |len| len as usize);
+
+    // The instruction data length prefix grows from one byte to two past 127 bytes
+    PACKET_DATA_SIZE.saturating_sub(probe_len + 1)
 }
 
-pub const fn calculate_max_write_chunk_size() -> usize {
-    // From Anchor: PACKET_DATA_SIZE - transaction overhead - buffer for shortvec
-    // Conservative estimate: 1232 bytes max packet size
-    // Transaction overhead: ~200 bytes (signatures, header, accounts)
-    // Safe chunk size: ~900 bytes
-    900
+static CHUNK_SIZE_OVERRIDE: OnceLock<usize> = OnceLock::new();
+
+/// Write buffers in chunks of at most `size` bytes (`--chunk-size`)
+pub fn set_chunk_size(size: usize) -> Result<()> {
+    let max = max_write_chunk_size(true);
+    if size == 0 || size > max {
+        anyhow::bail!("--chunk-size must be between 1 and {max} bytes");
+    }
+    let _ = CHUNK_SIZE_OVERRIDE.set(size);
+    Ok(())
 }
 
 
 pub fn get_network_name() -> String {
-    get_rpc_url()
-        .ok()
-        .and_then(|url| {
-            if url.contains("devnet") {
-                Some("devnet")
-            } else if url.contains("mainnet") {
-                Some("mainnet-beta")
-            } else if url.contains("testnet") {
-                Some("testnet")
-            } else {
-                Some("localhost")
-            }
-            .map(String::from)
-        })
-        .unwrap_or_else(|| "unknown".to_string())
+    match ProjectConfig::load() {
+        Ok(config) => network_name_from(&config, &rpc_url_from(&config)),
+        Err(_) => "unknown".to_string(),
+    }
+}
+
+/// Network for `config`: its `network`, else guessed from `rpc_url`
+pub fn network_name_from(config: &ProjectConfig, rpc_url: &str) -> String {
+    if let Some(network) = &config.network {
+        return network.clone();
+    }
+    network_from_url(rpc_url).unwrap_or("localhost").to_string()
+}
+
+/// Cluster an RPC URL names, if it names one
+fn network_from_url(rpc_url: &str) -> Option<&'static str> {
+    if rpc_url.contains("devnet") {
+        Some("devnet")
+    } else if rpc_url.contains("mainnet") {
+        Some("mainnet-beta")
+    } else if rpc_url.contains("testnet") {
+        Some("testnet")
+    } else if ["localhost", "127.0.0.1", "0.0.0.0", "[::1]"].iter().any(|host| rpc_url.contains(host)) {
+        Some("localhost")
+    } else {
+        None
+    }
+}
+
+/// Network for `config` on `rpc_url`, asking the RPC when neither names it
+///
+/// An unrecognized endpoint (a private RPC provider, a self-hosted node) is
+/// identified by its genesis hash. If that request fails the error is
+/// returned, so guards that depend on the cluster fail closed.
+pub async fn resolve_network_name(config: &ProjectConfig, rpc_url: &str) -> Result<String> {
+    if config.network.is_some() || network_from_url(rpc_url).is_some() {
+        return Ok(network_name_from(config, rpc_url));
+    }
+    let rpc_client = RpcClient::new_with_commitment(rpc_url.to_string(), commitment_from(config));
+    let genesis_hash = with_retry(|| rpc_client.get_genesis_hash()).await.with_context(|| format!(
+        "Could not identify the cluster behind {rpc_url}.\n\
+        Set it with `shield-deploy config set network <cluster>`."
+    ))?;
+    Ok(network_from_genesis_hash(&genesis_hash.to_string()).unwrap_or("localhost").to_string())
+}
+
+/// Identify a public cluster from its genesis hash
+pub fn network_from_genesis_hash(genesis_hash: &str) -> Option<&'static str> {
+    match genesis_hash {
+        "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d" => Some("mainnet-beta"),
+        "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG" => Some("devnet"),
+        "4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY" => Some("testnet"),
+        _ => None,
+    }
 }
 
 /// Get the program library name from Cargo.toml in current directory
@@ -290,20 +971,124 @@ pub fn extract_lib_name(cargo_toml_path: &Path) -> Result<String> {
     anyhow::bail!("Could not determine library name from Cargo.toml");
 }
 
-pub fn detect_program_file() -> Option<PathBuf> {
-    // Common Anchor project structure
-    let anchor_path = PathBuf::from("target/deploy");
-    if !anchor_path.exists() {
-        return None;
+static ARTIFACT_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Look for build artifacts only in `dir` (`--artifact-dir`)
+pub fn set_artifact_dir(dir: PathBuf) {
+    let _ = ARTIFACT_DIR.set(dir);
+}
+
+/// Directories searched for `.so` build artifacts, in order
+///
+/// `--artifact-dir` replaces the list. Otherwise `target/deploy/` (Anchor and
+/// `cargo build-sbf` in a single crate), `$CARGO_TARGET_DIR/deploy`, and the
+/// workspace root's `target/deploy/` when run from a member crate.
+pub fn artifact_dirs() -> Vec<PathBuf> {
+    if let Some(dir) = ARTIFACT_DIR.get() {
+        return vec![dir.clone()];
     }
-    
-    std::fs::read_dir(&anchor_path)
-        .ok()?
-        .filter_map(|entry| entry.ok())
-        .find(|entry| {
-            entry.path().extension().and_then(|s| s.to_str()) == Some("so")
+
+    let mut dirs = vec![PathBuf::from("target/deploy")];
+    if let Some(target) = std::env::var_os("CARGO_TARGET_DIR") {
+        dirs.push(PathBuf::from(target).join("deploy"));
+    }
+    if let Ok(cwd) = std::env::current_dir() {
+        dirs.extend(cwd.ancestors().skip(1).map(|dir| dir.join("target/deploy")).filter(|dir| dir.is_dir()));
+    }
+
+    let mut seen = Vec::new();
+    dirs.retain(|dir| {
+        let key = dir.canonicalize().unwrap_or_else(|_| dir.clone());
+        let new = !seen.contains(&key);
+        seen.push(key);
+        new
+    });
+    dirs
+}
+
+/// The program artifact to use when `--program` isn't given
+///
+/// A single candidate is used as is. With several, an interactive run picks
+/// from a list (newest build first); otherwise it fails listing them rather
+/// than guessing.
+pub fn detect_program_file() -> Result<PathBuf> {
+    let mut files = list_program_files();
+    if files.is_empty() {
+        let searched: Vec<String> = artifact_dirs().iter().map(|dir| dir.display().to_string()).collect();
+        anyhow::bail!(
+            "No program file found in {}.\n\
+            Build your program first or specify with --program",
+            searched.join(", ")
+        );
+    }
+    if files.len() == 1 {
+        return Ok(files.remove(0));
+    }
+
+    let modified = |path: &PathBuf| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    files.sort_by_key(|path| std::cmp::Reverse(modified(path)));
+    let labels: Vec<String> = files
+        .iter()
+        .map(|path| {
+            let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            let built = modified(path)
+                .map(|time| chrono::DateTime::<chrono::Local>::from(time).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            format!("{}  ({:.1} KB, built {built})", path.display(), size as f64 / 1024.0)
         })
+        .collect();
+
+    if assume_yes() || !std::io::stdin().is_terminal() {
+        anyhow::bail!(
+            "Found {} program files and won't guess which to use:\n  {}\n\
+            Pass --program <PATH> or --name <NAME>",
+            files.len(),
+            labels.join("\n  ")
+        );
+    }
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Several programs are built; which one?")
+        .items(&labels)
+        .default(0)
+        .interact()
+        .context("Failed to select a program file")?;
+    Ok(files.swap_remove(selection))
+}
+
+/// Every `.so` in the [`artifact_dirs`], sorted by name
+pub fn list_program_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = artifact_dirs()
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
         .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("so"))
+        .collect();
+    files.sort_by(|a, b| a.file_name().cmp(&b.file_name()).then_with(|| a.cmp(b)));
+    files
+}
+
+/// `<name>.so` for a program in the workspace
+pub fn program_file_by_name(name: &str) -> Result<PathBuf> {
+    let files = list_program_files();
+    files
+        .iter()
+        .find(|path| path.file_stem().is_some_and(|stem| stem == name))
+        .cloned()
+        .ok_or_else(|| {
+            let names: Vec<String> = files
+                .iter()
+                .filter_map(|path| path.file_stem().map(|s| s.to_string_lossy().to_string()))
+                .collect();
+            anyhow::anyhow!(
+                "No program named {name} in {}.\n\
+                Available: {}",
+                artifact_dirs().iter().map(|dir| dir.display().to_string()).collect::<Vec<_>>().join(", "),
+                if names.is_empty() { "none, build first".to_string() } else { names.join(", ") }
+            )
+        })
 }
 
 /// Deploy IDL after successful program deployment
@@ -338,6 +1123,691 @@ pub async fn deploy_idl_if_available(
     Ok(())
 }
 
+/// Hex-encoded SHA-256 digest of the given bytes
+pub fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Strip the zero padding the loader leaves after the ELF in
+/// ProgramData and buffer accounts, so on-chain and local bytes compare equal
+pub fn strip_trailing_zeros(data: &[u8]) -> &[u8] {
+    let end = data.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    &data[..end]
+}
+
+/// Solana Explorer link for a transaction on the given network
+pub fn explorer_tx_url(signature: &str, network: &str) -> String {
+    format!("https://explorer.solana.com/tx/{signature}{}", explorer_cluster_query(network))
+}
+
+/// Solana Explorer link for an account on the given network
+pub fn explorer_address_url(address: &str, network: &str) -> String {
+    format!("https://explorer.solana.com/address/{address}{}", explorer_cluster_query(network))
+}
+
+fn explorer_cluster_query(network: &str) -> String {
+    match network {
+        "mainnet-beta" => String::new(),
+        "localhost" => "?cluster=custom&customUrl=http%3A%2F%2Flocalhost%3A8899".to_string(),
+        cluster => format!("?cluster={cluster}"),
+    }
+}
+
+/// Parse a duration like `30s`, `5m`, `2h`, `1d` or `500ms` (bare numbers are seconds)
+pub fn parse_duration(raw: &str) -> Result<Duration> {
+    let raw = raw.trim();
+    let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+    let (number, unit) = raw.split_at(split);
+
+    let value: u64 = number.parse()
+        .map_err(|_| anyhow::anyhow!("'{raw}' is not a duration (e.g. 30s, 5m, 2h)"))?;
+
+    let duration = match unit {
+        "ms" => Duration::from_millis(value),
+        "" | "s" => Duration::from_secs(value),
+        "m" => Duration::from_secs(value * 60),
+        "h" => Duration::from_secs(value * 60 * 60),
+        "d" => Duration::from_secs(value * 24 * 60 * 60),
+        _ => anyhow::bail!("Unknown duration unit '{unit}' in '{raw}' (use ms, s, m, h or d)"),
+    };
+
+    Ok(duration)
+}
+
+/// Coarse human-readable duration, e.g. `2d 3h` or `45m`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes) = (secs / 86_400, secs % 86_400 / 3_600, secs % 3_600 / 60);
+
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{secs}s"),
+        (0, 0, m) => format!("{m}m"),
+        (0, h, m) => format!("{h}h {m}m"),
+        (d, h, _) => format!("{d}d {h}h"),
+    }
+}
+
+/// Send settings a [`ShieldProject`](crate::ShieldProject) carries in place of the CLI's flags
+///
+/// Inside [`run_scoped`] they replace the process-wide values the CLI sets,
+/// so a library caller never inherits another caller's fee payer or nonce.
+#[derive(Clone, Default)]
+pub struct RunSettings {
+    /// Pays transaction fees in place of the deployer
+    pub fee_payer: Option<Arc<Keypair>>,
+    /// Durable nonce the final transactions are signed against
+    pub nonce_account: Option<Pubkey>,
+    /// Send buffer writes to the leaders over QUIC
+    pub use_tpu: bool,
+    /// Retries of a transient RPC failure, [`DEFAULT_MAX_RETRIES`] when unset
+    pub max_retries: Option<usize>,
+    /// Commitment for confirmations and authority changes
+    pub commitment: Option<CommitmentLevel>,
+}
+
+struct RunScope {
+    settings: RunSettings,
+    log: tokio::sync::mpsc::UnboundedSender<String>,
+}
+
+tokio::task_local! {
+    static RUN_SCOPE: RunScope;
+}
+
+/// Run `work` with `settings` in place of the process-wide flags
+///
+/// Warnings and details it would print go to `progress` instead.
+pub async fn run_scoped<T>(settings: RunSettings, progress: &dyn Progress, work: impl Future<Output = T>) -> T {
+    let (log, mut messages) = tokio::sync::mpsc::unbounded_channel::<String>();
+    let work = RUN_SCOPE.scope(RunScope { settings, log }, work);
+    tokio::pin!(work);
+    loop {
+        tokio::select! {
+            output = &mut work => {
+                while let Ok(message) = messages.try_recv() {
+                    progress.step(&message);
+                }
+                return output;
+            }
+            Some(message) = messages.recv() => progress.step(&message),
+        }
+    }
+}
+
+/// A setting of the enclosing [`run_scoped`], `None` outside one
+fn scoped<T>(read: impl FnOnce(&RunSettings) -> T) -> Option<T> {
+    RUN_SCOPE.try_with(|scope| read(&scope.settings)).ok()
+}
+
+/// Hand `message` to the enclosing [`run_scoped`]; false outside one
+fn report_scoped(message: &str) -> bool {
+    RUN_SCOPE
+        .try_with(|scope| {
+            let _ = scope.log.send(message.to_string());
+        })
+        .is_ok()
+}
+
+/// Print a warning, or report it through a running project's `Progress`
+pub fn report_warning(message: &str) {
+    if !report_scoped(message) {
+        print_warning(message);
+    }
+}
+
+/// Print a `--verbose` detail, or report it through a running project's `Progress`
+pub fn report_detail(message: &str) {
+    if !report_scoped(message) && is_verbose() {
+        println!("  ↳ {message}");
+    }
+}
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Enable the extra detail printed with `--verbose`
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+pub fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+/// Answer every confirmation with yes (`--yes`)
+pub fn set_assume_yes(yes: bool) {
+    ASSUME_YES.store(yes, Ordering::Relaxed);
+}
+
+pub fn assume_yes() -> bool {
+    ASSUME_YES.load(Ordering::Relaxed)
+}
+
+/// Fail instead of blocking on a prompt nobody can answer
+///
+/// Prompts that `--yes` can't answer (amounts, passphrases, typed
+/// confirmations) call this too.
+pub fn ensure_interactive(prompt: &str) -> Result<()> {
+    if std::io::stdin().is_terminal() {
+        return Ok(());
+    }
+    let prompt = prompt.trim().trim_end_matches('?');
+    if assume_yes() {
+        anyhow::bail!("`{prompt}` needs an answer that --yes can't give, and stdin is not a terminal");
+    }
+    anyhow::bail!("`{prompt}` needs an answer, but stdin is not a terminal. Pass --yes to accept confirmations");
+}
+
+static WRITE_CONCURRENCY: OnceLock<usize> = OnceLock::new();
+
+/// Keep up to `concurrency` buffer writes in flight (`--concurrency`)
+pub fn set_write_concurrency(concurrency: usize) {
+    let _ = WRITE_CONCURRENCY.set(concurrency.max(1));
+}
+
+fn write_concurrency() -> usize {
+    WRITE_CONCURRENCY.get().copied().unwrap_or(DEFAULT_WRITE_CONCURRENCY)
+}
+
+static USE_TPU: AtomicBool = AtomicBool::new(false);
+
+/// Send buffer writes to the leaders' TPU ports over QUIC (`--use-tpu`)
+pub fn set_use_tpu(enabled: bool) {
+    USE_TPU.store(enabled, Ordering::Relaxed);
+}
+
+fn use_tpu() -> bool {
+    scoped(|run| run.use_tpu).unwrap_or_else(|| USE_TPU.load(Ordering::Relaxed))
+}
+
+static MAX_RETRIES: OnceLock<usize> = OnceLock::new();
+
+/// Retry transient RPC failures up to `retries` times (`--max-retries`)
+pub fn set_max_retries(retries: usize) {
+    let _ = MAX_RETRIES.set(retries);
+}
+
+fn max_retries() -> usize {
+    scoped(|run| run.max_retries)
+        .unwrap_or_else(|| MAX_RETRIES.get().copied())
+        .unwrap_or(DEFAULT_MAX_RETRIES)
+}
+
+/// Run an RPC call, retrying transient failures with jittered exponential backoff
+///
+/// Only network errors, rate limiting and an unhealthy node are retried.
+/// Anything the cluster decided (a failed signature check, a program error,
+/// a preflight rejection) is returned on the first attempt.
+pub async fn with_retry<T, F, Fut>(mut call: F) -> Result<T, ClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ClientError>>,
+{
+    let retries = max_retries();
+    let mut attempt = 0;
+    loop {
+        match call().await {
+            Err(e) if attempt < retries && is_transient(&e) => {
+                attempt += 1;
+                let delay = retry_delay(attempt);
+                report_warning(&format!(
+                    "RPC request failed: {e}. Retry {attempt}/{retries} in {:.1}s",
+                    delay.as_secs_f64()
+                ));
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Lamport balance of `pubkey`, retried like [`with_retry`]
+pub async fn balance_of(rpc_client: &RpcClient, pubkey: &Pubkey) -> Result<u64, ClientError> {
+    with_retry(|| rpc_client.get_balance(pubkey)).await
+}
+
+/// Whether a failed RPC call is worth repeating unchanged
+fn is_transient(error: &ClientError) -> bool {
+    match error.kind() {
+        ClientErrorKind::Io(_) => true,
+        ClientErrorKind::Reqwest(e) => {
+            e.is_timeout()
+                || e.is_connect()
+                || e.status().is_some_and(|s| s.as_u16() == 429 || s.is_server_error())
+        }
+        // Node unhealthy, block not available, min context slot not reached
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            matches!(code, -32005 | -32004 | -32016)
+        }
+        _ => false,
+    }
+}
+
+/// Backoff for the `attempt`th retry, scaled by 50-100% so parallel clients spread out
+fn retry_delay(attempt: usize) -> Duration {
+    let backoff = RETRY_BASE_DELAY
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(RETRY_MAX_DELAY);
+    backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+}
+
+static COMMITMENT_OVERRIDE: OnceLock<CommitmentLevel> = OnceLock::new();
+
+/// Use `level` for every RPC client and confirmation in this run (`--commitment`)
+pub fn set_commitment(level: CommitmentLevel) {
+    let _ = COMMITMENT_OVERRIDE.set(level);
+}
+
+/// Commitment in effect: the flag, then `commitment` in config, then confirmed
+pub fn commitment() -> CommitmentConfig {
+    if let Some(commitment) = commitment_override() {
+        return CommitmentConfig { commitment };
+    }
+    commitment_from(&ProjectConfig::load().unwrap_or_default())
+}
+
+/// `commitment` from `config`, else confirmed
+pub fn commitment_from(config: &ProjectConfig) -> CommitmentConfig {
+    config
+        .commitment
+        .as_deref()
+        .and_then(|raw| raw.parse().ok())
+        .map_or(CommitmentConfig::confirmed(), |commitment| CommitmentConfig { commitment })
+}
+
+/// Commitment for authority changes: finalized unless `--commitment` is given
+///
+/// A transfer or finalize that is rolled back with its fork would leave
+/// state recording an authority the chain never applied.
+pub fn authority_commitment() -> CommitmentConfig {
+    commitment_override().map_or(CommitmentConfig::finalized(), |commitment| CommitmentConfig { commitment })
+}
+
+fn commitment_override() -> Option<CommitmentLevel> {
+    scoped(|run| run.commitment).unwrap_or_else(|| COMMITMENT_OVERRIDE.get().copied())
+}
+
+/// `processed`, `confirmed` or `finalized`
+pub fn commitment_label(commitment: CommitmentConfig) -> &'static str {
+    match commitment.commitment {
+        CommitmentLevel::Processed => "processed",
+        CommitmentLevel::Confirmed => "confirmed",
+        CommitmentLevel::Finalized => "finalized",
+    }
+}
+
+static SKIP_PREFLIGHT: AtomicBool = AtomicBool::new(false);
+
+/// Send transactions without the RPC's preflight simulation (`--skip-preflight`)
+pub fn set_skip_preflight(skip: bool) {
+    SKIP_PREFLIGHT.store(skip, Ordering::Relaxed);
+}
+
+static FEE_PAYER: OnceLock<Keypair> = OnceLock::new();
+
+/// Key that pays transaction fees, from [`fee_payer`]
+pub enum FeePayer<'a> {
+    Key(&'a Keypair),
+    /// A running [`ShieldProject`](crate::ShieldProject)'s fee payer
+    Scoped(Arc<Keypair>),
+}
+
+impl std::ops::Deref for FeePayer<'_> {
+    type Target = Keypair;
+
+    fn deref(&self) -> &Keypair {
+        match self {
+            FeePayer::Key(keypair) => keypair,
+            FeePayer::Scoped(keypair) => keypair,
+        }
+    }
+}
+
+/// Key that pays transaction fees in place of `default` (`--fee-payer`)
+///
+/// Only fees move: `default` still funds rent and signs as authority.
+pub fn fee_payer(default: &Keypair) -> FeePayer<'_> {
+    match scoped(|run| run.fee_payer.clone()) {
+        Some(Some(keypair)) => FeePayer::Scoped(keypair),
+        Some(None) => FeePayer::Key(default),
+        None => FeePayer::Key(FEE_PAYER.get().unwrap_or(default)),
+    }
+}
+
+fn fee_payer_set() -> bool {
+    scoped(|run| run.fee_payer.is_some()).unwrap_or_else(|| FEE_PAYER.get().is_some())
+}
+
+/// Load a `--fee-payer` keypair, check it can cover `fees` and make it the payer
+pub async fn use_fee_payer(rpc_client: &RpcClient, path: &Path, fees: u64) -> Result<Pubkey> {
+    let keypair = read_keypair_file(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read fee payer {}: {e}", path.display()))?;
+    let pubkey = keypair.pubkey();
+
+    if get_network_name() == "mainnet-beta" {
+        print_warning("A separate fee payer signs every transaction of this deployment.");
+        println!("  {pubkey} will be publicly linked to the deployer and the program.");
+        println!("  Use it only with a wallet that is already unrelated to your identity.\n");
+    }
+
+    let balance = with_retry(|| rpc_client.get_balance(&pubkey)).await
+        .context("Failed to get fee payer balance")?;
+    if balance < fees {
+        anyhow::bail!(
+            "Insufficient fee payer balance.\n\
+            Fee payer: {pubkey}\n\
+            Current: {}\n\
+            Needed: {} (estimated fees)",
+            format_sol(balance),
+            format_sol(fees)
+        );
+    }
+
+    // `deploy --all` loads the same fee payer once per program
+    match FEE_PAYER.get() {
+        Some(existing) if existing.pubkey() == pubkey => {}
+        _ => FEE_PAYER.set(keypair).map_err(|_| anyhow::anyhow!("Fee payer already set"))?,
+    }
+    Ok(pubkey)
+}
+
+static NONCE_ACCOUNT: OnceLock<Pubkey> = OnceLock::new();
+
+/// Sign final transactions against the durable nonce at `address` (`--nonce`)
+pub fn set_nonce_account(address: &str) -> Result<()> {
+    let nonce = Pubkey::from_str(address).with_context(|| format!("Invalid nonce account: {address}"))?;
+    let _ = NONCE_ACCOUNT.set(nonce);
+    Ok(())
+}
+
+/// A durable nonce account as stored on chain
+pub struct NonceAccount {
+    /// Hash a transaction must be signed with to use the nonce
+    pub blockhash: Hash,
+    pub authority: Pubkey,
+    pub lamports: u64,
+}
+
+/// Read an initialized nonce account
+pub async fn nonce_account(rpc_client: &RpcClient, nonce: &Pubkey) -> Result<NonceAccount> {
+    let account = with_retry(|| rpc_client.get_account(nonce)).await
+        .with_context(|| format!("Nonce account {nonce} not found"))?;
+    if account.owner != system_program::id() {
+        anyhow::bail!("{nonce} is not a nonce account");
+    }
+    let versions: NonceVersions = bincode::deserialize(&account.data)
+        .with_context(|| format!("{nonce} is not a nonce account"))?;
+    match versions.state() {
+        NonceAccountState::Initialized(data) => Ok(NonceAccount {
+            blockhash: data.blockhash(),
+            authority: data.authority,
+            lamports: account.lamports,
+        }),
+        NonceAccountState::Uninitialized => anyhow::bail!("Nonce account {nonce} is not initialized"),
+    }
+}
+
+/// Blockhash and instructions for a transaction signed by `signers`
+///
+/// Normally the latest blockhash. With `--nonce`, the nonce's stored hash,
+/// with `advance_nonce_account` put first so the transaction stays valid
+/// however long it takes to land. The nonce authority must be a signer.
+pub async fn durable_blockhash(
+    rpc_client: &RpcClient,
+    signers: &[&Keypair],
+    instructions: Vec<SdkInstruction>,
+) -> Result<(Hash, Vec<SdkInstruction>)> {
+    let nonce = scoped(|run| run.nonce_account).unwrap_or_else(|| NONCE_ACCOUNT.get().copied());
+    let Some(nonce) = nonce else {
+        let blockhash = with_retry(|| rpc_client.get_latest_blockhash()).await?;
+        return Ok((blockhash, instructions));
+    };
+
+    let account = nonce_account(rpc_client, &nonce).await?;
+    if !signers.iter().any(|signer| signer.pubkey() == account.authority) {
+        anyhow::bail!(
+            "Nonce account {nonce} is controlled by {}, which does not sign this transaction",
+            account.authority
+        );
+    }
+    let advance_ix = system_instruction::advance_nonce_account(
+        &Address::from(nonce.to_bytes()),
+        &Address::from(account.authority.to_bytes()),
+    );
+    let mut durable = vec![SdkInstruction {
+        program_id: Pubkey::from(advance_ix.program_id.to_bytes()),
+        accounts: advance_ix
+            .accounts
+            .iter()
+            .map(|acc| AccountMeta {
+                pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            })
+            .collect(),
+        data: advance_ix.data,
+    }];
+    durable.extend(instructions);
+    Ok((account.blockhash, durable))
+}
+
+/// Nonce account a transaction advances, if it was built on one
+fn durable_nonce_of(transaction: &Transaction) -> Option<Pubkey> {
+    let message = &transaction.message;
+    let first = message.instructions.first()?;
+    // bincode tag of SystemInstruction::AdvanceNonceAccount
+    if message.account_keys.get(first.program_id_index as usize)? != &system_program::id() || first.data != [4, 0, 0, 0] {
+        return None;
+    }
+    message.account_keys.get(*first.accounts.first()? as usize).copied()
+}
+
+fn send_config(rpc_client: &RpcClient) -> RpcSendTransactionConfig {
+    RpcSendTransactionConfig {
+        skip_preflight: SKIP_PREFLIGHT.load(Ordering::Relaxed),
+        preflight_commitment: Some(rpc_client.commitment().commitment),
+        ..RpcSendTransactionConfig::default()
+    }
+}
+
+/// Send a transaction and wait until it is confirmed or its blockhash (or nonce) expires
+///
+/// Every command that submits transactions goes through this (or the spinner
+/// variant) so `--skip-preflight` and preflight error reporting apply everywhere.
+/// The confirmation comes from a websocket subscription when the RPC has
+/// one; a status request every `WEBSOCKET_QUIET_PERIOD` still catches expiry.
+pub async fn send_and_confirm(rpc_client: &RpcClient, transaction: &Transaction) -> Result<Signature> {
+    let watcher = SignatureWatcher::connect(rpc_client).await;
+    let signature = with_retry(|| rpc_client.send_transaction_with_config(transaction, send_config(rpc_client))).await
+        .map_err(explain_send_error)?;
+
+    loop {
+        let mut status = match watcher.wait(signature, WEBSOCKET_QUIET_PERIOD).await {
+            (_, Some(result)) => Some(result),
+            (_, None) => {
+                with_retry(|| rpc_client.get_signature_status_with_commitment(&signature, rpc_client.commitment()))
+                    .await?
+            }
+        };
+        if status.is_none() && is_expired(rpc_client, transaction).await? {
+            // A nonce also moves when this transaction itself lands, so look once more
+            status = with_retry(|| rpc_client.get_signature_status_with_commitment(&signature, rpc_client.commitment()))
+                .await?;
+            if status.is_none() {
+                anyhow::bail!("Transaction {signature} expired before it was confirmed");
+            }
+        }
+        if let Some(result) = status {
+            result.map_err(|error| TransactionFailed {
+                message: format!("Transaction {signature} failed: {error}"),
+                error,
+            })?;
+            note_confirmed(transaction);
+            return Ok(signature);
+        }
+        if !watcher.is_connected() {
+            tokio::time::sleep(CONFIRM_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Whether `transaction` can no longer land
+///
+/// A durable transaction stays valid until its nonce moves on; any other
+/// expires with its blockhash.
+async fn is_expired(rpc_client: &RpcClient, transaction: &Transaction) -> Result<bool> {
+    let recent_blockhash = transaction.message.recent_blockhash;
+    match durable_nonce_of(transaction) {
+        Some(nonce) => Ok(nonce_account(rpc_client, &nonce).await?.blockhash != recent_blockhash),
+        None => Ok(!with_retry(|| rpc_client.is_blockhash_valid(&recent_blockhash, CommitmentConfig::processed())).await?),
+    }
+}
+
+/// Whether `transaction` is confirmed never to take effect
+///
+/// True when it failed on-chain, or when its blockhash expired without it
+/// landing; a plain transaction is waited on until one of those is known.
+/// A durable transaction stays valid until its nonce moves, so without a
+/// status it only counts once the nonce has moved on. RPC errors count as
+/// unknown, since writing off a transaction that still lands is the costly
+/// mistake.
+pub async fn never_lands(rpc_client: &RpcClient, transaction: &Transaction) -> bool {
+    let Some(signature) = transaction.signatures.first().copied() else {
+        return false;
+    };
+    let status = || async move {
+        with_retry(|| rpc_client.get_signature_status_with_commitment(&signature, rpc_client.commitment())).await
+    };
+    let durable = durable_nonce_of(transaction).is_some();
+
+    loop {
+        match status().await {
+            Ok(Some(result)) => return result.is_err(),
+            Ok(None) => {}
+            Err(_) => return false,
+        }
+        match is_expired(rpc_client, transaction).await {
+            // It may have landed just before expiring, so look once more
+            Ok(true) => return matches!(status().await, Ok(None) | Ok(Some(Err(_)))),
+            Ok(false) if !durable => tokio::time::sleep(CONFIRM_POLL_INTERVAL).await,
+            Ok(false) | Err(_) => return false,
+        }
+    }
+}
+
+/// `send_and_confirm` with a progress spinner, for the long final deploy/upgrade step
+pub async fn send_and_confirm_with_spinner(rpc_client: &RpcClient, transaction: &Transaction) -> Result<Signature> {
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_message(format!("Waiting for {} confirmation...", commitment_label(rpc_client.commitment())));
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    let result = send_and_confirm(rpc_client, transaction).await;
+    spinner.finish_and_clear();
+    result
+}
+
+/// Whether a send failed only because the RPC didn't know its blockhash
+fn is_blockhash_not_found(error: &ClientError) -> bool {
+    matches!(error.get_transaction_error(), Some(TransactionError::BlockhashNotFound))
+}
+
+/// Turn a preflight rejection into the simulation logs and instruction error
+fn explain_send_error(error: ClientError) -> anyhow::Error {
+    let ClientErrorKind::RpcError(RpcError::RpcResponseError {
+        message,
+        data: RpcResponseErrorData::SendTransactionPreflightFailure(simulation),
+        ..
+    }) = error.kind()
+    else {
+        return error.into();
+    };
+
+    let mut report = format!("Preflight simulation rejected the transaction: {message}");
+    report.push_str(&simulation_report(simulation));
+    report.push_str("\nIf this RPC's preflight is unreliable, retry with --skip-preflight.");
+
+    match simulation.err.clone() {
+        Some(error) => TransactionFailed { message: report, error }.into(),
+        None => anyhow::anyhow!(report),
+    }
+}
+
+/// A sent transaction that preflight or the cluster rejected
+#[derive(Debug)]
+pub struct TransactionFailed {
+    message: String,
+    pub error: TransactionError,
+}
+
+impl fmt::Display for TransactionFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for TransactionFailed {}
+
+/// Runtime error behind a failed [`send_and_confirm`], if the transaction was rejected
+pub fn transaction_error(error: &anyhow::Error) -> Option<TransactionError> {
+    error.chain().find_map(|cause| {
+        cause
+            .downcast_ref::<TransactionFailed>()
+            .map(|failed| failed.error.clone())
+            .or_else(|| cause.downcast_ref::<ClientError>().and_then(|e| e.get_transaction_error()))
+    })
+}
+
+/// Simulate `instructions` paid by `payer` without signing or sending them
+///
+/// Signatures aren't verified and the blockhash is replaced, so accounts
+/// that don't exist yet (a buffer about to be created) can stand in.
+pub async fn simulate_instructions(
+    rpc_client: &RpcClient,
+    payer: &Pubkey,
+    instructions: &[SdkInstruction],
+) -> Result<RpcSimulateTransactionResult> {
+    let transaction = Transaction::new_unsigned(Message::new(instructions, Some(payer)));
+    Ok(rpc_client
+        .simulate_transaction_with_config(
+            &transaction,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(rpc_client.commitment()),
+                ..Default::default()
+            },
+        )
+        .await
+        .context("Failed to simulate transaction")?
+        .value)
+}
+
+/// Instruction error, compute units and program logs of a simulation, one per line
+pub fn simulation_report(simulation: &RpcSimulateTransactionResult) -> String {
+    let mut report = String::new();
+    if let Some(err) = &simulation.err {
+        report.push_str(&format!("\nInstruction error: {err:?}"));
+    }
+    if let Some(units) = simulation.units_consumed {
+        report.push_str(&format!("\nCompute units consumed: {units}"));
+    }
+    match &simulation.logs {
+        Some(logs) if !logs.is_empty() => {
+            report.push_str("\nProgram logs:");
+            for line in logs {
+                report.push_str(&format!("\n  {line}"));
+            }
+        }
+        _ => report.push_str("\nThe RPC returned no program logs."),
+    }
+    report
+}
+
 pub fn format_sol(lamports: u64) -> String {
     format!("{:.2} SOL", lamports as f64 / 1_000_000_000.0)
 }
@@ -358,4 +1828,172 @@ pub fn print_warning(message: &str) {
 #[allow(dead_code)]
 pub fn print_error(message: &str) {
     eprintln!("\n {message}");
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lamports(input: &str) -> Result<u64, String> {
+        parse_amount(input, false).map(|amount| amount.resolve(0))
+    }
+
+    #[test]
+    fn parses_sol_exactly() {
+        assert_eq!(lamports("1.5"), Ok(1_500_000_000));
+        assert_eq!(lamports("0.000000001"), Ok(1));
+        assert_eq!(lamports(".25"), Ok(250_000_000));
+        assert_eq!(lamports("3"), Ok(3_000_000_000));
+        assert_eq!(lamports("0.1"), Ok(100_000_000));
+    }
+
+    #[test]
+    fn parses_unit_suffixes() {
+        assert_eq!(lamports("2.5 sol"), Ok(2_500_000_000));
+        assert_eq!(lamports("2.5SOL"), Ok(2_500_000_000));
+        assert_eq!(lamports("1500000000 lamports"), Ok(1_500_000_000));
+        assert_eq!(lamports("1 lamport"), Ok(1));
+        assert!(lamports("1.5 lamports").is_err());
+    }
+
+    #[test]
+    fn rejects_more_than_nine_decimals() {
+        assert!(lamports("0.0000000001").is_err());
+        assert!(lamports("1.1234567891 sol").is_err());
+    }
+
+    #[test]
+    fn rejects_amounts_past_u64() {
+        assert_eq!(lamports("18446744073.709551615"), Ok(u64::MAX));
+        assert!(lamports("18446744073.709551616").is_err());
+        assert!(lamports("18446744074").is_err());
+        assert!(lamports("18446744073709551616 lamports").is_err());
+    }
+
+    #[test]
+    fn rejects_negative_empty_and_zero() {
+        for input in ["-1", "-0.5 sol", "-1 lamports", "", " ", ".", "sol", "lamports", "0", "0.0", "1e9", "1,5"] {
+            assert!(lamports(input).is_err(), "{input:?} should be rejected");
+        }
+    }
+
+    /// A signed Write of `chunk_len` bytes, as `write_transaction` builds it
+    fn signed_write(chunk_len: usize, separate_fee_payer: bool, budget: bool) -> Transaction {
+        let authority = Keypair::new();
+        let fee_payer = Keypair::new();
+        let payer = if separate_fee_payer { &fee_payer } else { &authority };
+
+        let write_ix = bpf_loader_upgradeable::write(
+            &SolanaPubkeyV2::new_unique(),
+            &SolanaPubkeyV2::new_from_array(authority.pubkey().to_bytes()),
+            u32::MAX,
+            vec![0xff; chunk_len],
+        );
+        let mut instructions = Vec::new();
+        if budget {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(u32::MAX));
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(u64::MAX));
+        }
+        instructions.push(SdkInstruction {
+            program_id: Pubkey::from(write_ix.program_id.to_bytes()),
+            accounts: write_ix
+                .accounts
+                .iter()
+                .map(|acc| AccountMeta {
+                    pubkey: Pubkey::from(acc.pubkey.to_bytes()),
+                    is_signer: acc.is_signer,
+                    is_writable: acc.is_writable,
+                })
+                .collect(),
+            data: write_ix.data,
+        });
+
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        let mut signers = vec![payer, &authority];
+        signers.dedup_by_key(|k| k.pubkey());
+        transaction.sign(&signers, Hash::new_unique());
+        transaction
+    }
+
+    fn packet_len(transaction: &Transaction) -> usize {
+        bincode::serialized_size(transaction).unwrap() as usize
+    }
+
+    #[test]
+    fn max_write_chunk_fits_a_packet() {
+        for separate_fee_payer in [false, true] {
+            let chunk = max_write_chunk_size(separate_fee_payer);
+            for budget in [false, true] {
+                let len = packet_len(&signed_write(chunk, separate_fee_payer, budget));
+                assert!(
+                    len <= PACKET_DATA_SIZE,
+                    "{len} bytes (fee payer: {separate_fee_payer}, budget: {budget})"
+                );
+            }
+            // Nothing is left on the table once both budget instructions are in
+            assert!(packet_len(&signed_write(chunk + 1, separate_fee_payer, true)) > PACKET_DATA_SIZE);
+        }
+    }
+
+    #[test]
+    fn separate_fee_payer_shrinks_the_chunk() {
+        assert!(max_write_chunk_size(true) < max_write_chunk_size(false));
+    }
+
+    #[test]
+    fn max_only_where_allowed() {
+        assert_eq!(parse_amount("all", true), Ok(Amount::Max));
+        assert_eq!(parse_amount(" MAX ", true), Ok(Amount::Max));
+        assert!(parse_amount("max", false).is_err());
+    }
+
+    #[test]
+    fn transaction_error_survives_context() {
+        use solana_sdk::instruction::InstructionError;
+
+        let error = TransactionError::InstructionError(2, InstructionError::InvalidInstructionData);
+        let failed = anyhow::Error::from(TransactionFailed { message: "Transaction failed".to_string(), error: error.clone() })
+            .context("Failed to extend program data");
+
+        assert_eq!(transaction_error(&failed), Some(error));
+        assert_eq!(transaction_error(&anyhow::anyhow!("invalid instruction data")), None);
+    }
+
+    #[test]
+    fn reports_the_route_writes_took() {
+        assert_eq!(send_route(0, 12), "RPC");
+        assert_eq!(send_route(12, 0), "TPU");
+        assert_eq!(send_route(9, 3), "TPU, 3 of 12 sends fell back to RPC");
+        assert_eq!(send_route(0, 0), "RPC");
+    }
+
+    #[test]
+    fn names_only_clusters_the_url_gives_away() {
+        assert_eq!(network_from_url("https://api.mainnet-beta.solana.com"), Some("mainnet-beta"));
+        assert_eq!(network_from_url("https://api.devnet.solana.com"), Some("devnet"));
+        assert_eq!(network_from_url("http://127.0.0.1:8899"), Some("localhost"));
+        // Left to the genesis hash, not assumed to be a local validator
+        assert_eq!(network_from_url("https://xyz.rpcpool.com"), None);
+        assert_eq!(network_from_url("https://rpc.example.org:8899"), None);
+    }
+
+    #[tokio::test]
+    async fn a_running_project_gets_its_own_settings_and_output() {
+        struct Collect(Mutex<Vec<String>>);
+        impl Progress for Collect {
+            fn step(&self, message: &str) {
+                self.0.lock().unwrap().push(message.to_string());
+            }
+        }
+
+        let progress = Collect(Mutex::new(Vec::new()));
+        let settings = RunSettings { max_retries: Some(2), ..Default::default() };
+        let (retries, tpu) = run_scoped(settings, &progress, async {
+            report_warning("RPC request failed");
+            (max_retries(), use_tpu())
+        })
+        .await;
+
+        assert_eq!((retries, tpu), (2, false));
+        assert_eq!(*progress.0.lock().unwrap(), ["RPC request failed"]);
+    }
+}
@@ -0,0 +1,89 @@
+//! Buffer reclaim after a failed deploy, against a local validator
+//!
+//! Needs `solana-test-validator` listening on `SHIELD_TEST_RPC_URL`
+//! (default `http://127.0.0.1:8899`):
+//!
+//! ```text
+//! solana-test-validator --reset &
+//! cargo test --test reclaim_buffer -- --ignored
+//! ```
+
+use shield_deploy::compute::FeeSettings;
+use shield_deploy::config::{BufferStatus, Config, ProjectConfig};
+use shield_deploy::loader::{create_deploy_buffer, finish_deploy};
+use shield_deploy::utils::write_program_data_chunked;
+use shield_deploy::NoProgress;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signer};
+use std::time::Duration;
+
+/// Upper bound on what the failed deploy may cost: signatures, priority fees, the close
+const MAX_FEES: u64 = LAMPORTS_PER_SOL / 100;
+
+#[tokio::test]
+#[ignore = "needs a local solana-test-validator"]
+async fn failed_deploy_refunds_the_buffer() {
+    let rpc_url = std::env::var("SHIELD_TEST_RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:8899".to_string());
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let root = tempfile::tempdir().unwrap();
+    let config = Config::at(root.path().join(".shield"));
+    let fees = FeeSettings::from_config(&ProjectConfig::default(), "localhost");
+
+    let deployer = Keypair::new();
+    let airdrop = rpc_client.request_airdrop(&deployer.pubkey(), 5 * LAMPORTS_PER_SOL).await.unwrap();
+    while !rpc_client.confirm_transaction(&airdrop).await.unwrap() {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    let balance_before = rpc_client.get_balance(&deployer.pubkey()).await.unwrap();
+
+    // Not an ELF, so the loader rejects the final deploy after the buffer is written
+    let program_data = vec![7u8; 4096];
+    let program_keypair = Keypair::new();
+    let buffer = create_deploy_buffer(
+        &config,
+        &rpc_client,
+        &fees,
+        &deployer,
+        &program_keypair.pubkey(),
+        &program_data,
+        program_data.len(),
+        &NoProgress,
+    )
+    .await
+    .unwrap();
+    write_program_data_chunked(&rpc_client, &fees, &deployer, &buffer, &program_data, &NoProgress)
+        .await
+        .unwrap();
+    assert!(rpc_client.get_balance(&buffer).await.unwrap() > 0);
+
+    let result = finish_deploy(
+        &config,
+        &rpc_client,
+        &fees,
+        &deployer,
+        &program_keypair.pubkey(),
+        Some(&program_keypair),
+        &buffer,
+        program_data.len(),
+        program_data.len(),
+        false,
+        &NoProgress,
+    )
+    .await;
+    assert!(result.is_err());
+
+    assert_eq!(rpc_client.get_balance(&buffer).await.unwrap(), 0);
+    let balance_after = rpc_client.get_balance(&deployer.pubkey()).await.unwrap();
+    assert!(balance_after <= balance_before);
+    assert!(
+        balance_before - balance_after < MAX_FEES,
+        "lost {} lamports, more than fees",
+        balance_before - balance_after
+    );
+
+    let state = config.load_state().unwrap();
+    let record = state.buffers.iter().find(|b| b.address == buffer.to_string()).unwrap();
+    assert_eq!(record.status, BufferStatus::Closed);
+}